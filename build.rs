@@ -3,6 +3,9 @@ use std::error::Error;
 use vergen::EmitBuilder;
 
 fn main() -> Result<(), Box<dyn Error>> {
+    // protoc is vendored so that building does not need it installed.
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::compile_protos("proto/indexify.proto")?;
     EmitBuilder::builder()
         .all_build()
         .all_cargo()
@@ -0,0 +1,26 @@
+use reqwest::StatusCode;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("invalid url: `{0}`")]
+    Url(#[from] url::ParseError),
+
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+
+    /// The server answered the request with an error.
+    #[error("indexify responded with {status}: {message}")]
+    Api { status: StatusCode, message: String },
+}
+
+impl ClientError {
+    /// The status the server answered with, for errors of the API.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            ClientError::Api { status, .. } => Some(*status),
+            ClientError::Http(err) => err.status(),
+            ClientError::Url(_) => None,
+        }
+    }
+}
@@ -0,0 +1,527 @@
+//! A typed async client of the Indexify API, for Rust services which create
+//! repositories, add content and search it.
+//!
+//! Requests which are safe to send again are retried with backoff, see
+//! [`RetryPolicy`]. Texts and events are added with an `Idempotency-Key`, so
+//! a request which is retried after a timeout adds them once.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), indexify_client::ClientError> {
+//! use indexify_client::{IndexifyClient, SearchRequest, Text, TextAddRequest};
+//!
+//! let client = IndexifyClient::new("http://localhost:8900")?.with_api_key("idx_...");
+//! client
+//!     .add_texts("docs", &TextAddRequest::new(vec![Text::new("Indexify")]))
+//!     .await?;
+//! let results = client
+//!     .search("docs", &SearchRequest::new("docs.embedding", "what is indexify").with_k(3))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use bytes::Bytes;
+use reqwest::{header, multipart, Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use url::Url;
+
+mod error;
+mod retry;
+mod types;
+
+pub use error::ClientError;
+pub use retry::RetryPolicy;
+pub use types::*;
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+const NAMESPACE_HEADER: &str = "x-indexify-namespace";
+
+const API_KEY_HEADER: &str = "x-api-key";
+
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Whether a request is sent again when it fails in a way that may pass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Retry {
+    Safe,
+    Never,
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexifyClient {
+    http: reqwest::Client,
+    base_url: Url,
+    namespace: Option<String>,
+    api_key: Option<String>,
+    retry_policy: RetryPolicy,
+}
+
+impl IndexifyClient {
+    /// A client of the server at `base_url`, such as
+    /// `http://localhost:8900`, in the default namespace.
+    pub fn new(base_url: &str) -> Result<Self> {
+        Self::with_http_client(base_url, reqwest::Client::new())
+    }
+
+    /// A client which sends its requests with `http`, for services which
+    /// configure timeouts, proxies or TLS themselves.
+    pub fn with_http_client(base_url: &str, http: reqwest::Client) -> Result<Self> {
+        Ok(Self {
+            http,
+            base_url: Url::parse(base_url)?,
+            namespace: None,
+            api_key: None,
+            retry_policy: RetryPolicy::default(),
+        })
+    }
+
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub async fn list_repositories(&self, params: &ListParams) -> Result<Page<DataRepository>> {
+        let request = self.request(Method::GET, &["repositories"])?.query(params);
+        self.send_json(request, Retry::Safe).await
+    }
+
+    pub async fn get_repository(&self, repository: &str) -> Result<DataRepository> {
+        let request = self.request(Method::GET, &["repositories", repository])?;
+        let response: GetRepositoryResponse = self.send_json(request, Retry::Safe).await?;
+        Ok(response.repository)
+    }
+
+    /// Creates the repository, or replaces its configuration when it exists.
+    /// Returns the version of the repository.
+    pub async fn create_repository(&self, repository: &CreateRepository) -> Result<i64> {
+        let request = self
+            .request(Method::POST, &["repositories"])?
+            .json(repository);
+        let response: CreateRepositoryResponse = self.send_json(request, Retry::Safe).await?;
+        Ok(response.version)
+    }
+
+    /// Deletes the repository with its content and indexes. A repository
+    /// with content is only deleted with `force`.
+    pub async fn delete_repository(
+        &self,
+        repository: &str,
+        force: bool,
+    ) -> Result<DeleteRepositoryResponse> {
+        let request = self
+            .request(Method::DELETE, &["repositories", repository])?
+            .query(&[("force", force)]);
+        self.send_json(request, Retry::Safe).await
+    }
+
+    pub async fn list_extractors(&self, params: &ListParams) -> Result<Page<ExtractorDescription>> {
+        let request = self.request(Method::GET, &["extractors"])?.query(params);
+        self.send_json(request, Retry::Safe).await
+    }
+
+    /// Binds an extractor to the repository. Binding is not retried, as a
+    /// binding which was added by a request that timed out can not be added
+    /// again.
+    pub async fn bind_extractor(
+        &self,
+        repository: &str,
+        binding: &ExtractorBinding,
+    ) -> Result<ExtractorBindResponse> {
+        let request = self
+            .request(
+                Method::POST,
+                &["repositories", repository, "extractor_bindings"],
+            )?
+            .json(binding);
+        self.send_json(request, Retry::Never).await
+    }
+
+    /// Pauses the binding, which creates no work until it is resumed.
+    pub async fn pause_extractor_binding(
+        &self,
+        repository: &str,
+        binding: &str,
+    ) -> Result<ExtractorBinding> {
+        self.update_extractor_binding(repository, binding, "pause")
+            .await
+    }
+
+    pub async fn resume_extractor_binding(
+        &self,
+        repository: &str,
+        binding: &str,
+    ) -> Result<ExtractorBinding> {
+        self.update_extractor_binding(repository, binding, "resume")
+            .await
+    }
+
+    async fn update_extractor_binding(
+        &self,
+        repository: &str,
+        binding: &str,
+        action: &str,
+    ) -> Result<ExtractorBinding> {
+        let request = self.request(
+            Method::POST,
+            &[
+                "repositories",
+                repository,
+                "extractor_bindings",
+                binding,
+                action,
+            ],
+        )?;
+        let response: UpdateExtractorBindingResponse = self.send_json(request, Retry::Safe).await?;
+        Ok(response.extractor_binding)
+    }
+
+    pub async fn list_indexes(&self, repository: &str, params: &ListParams) -> Result<Page<Index>> {
+        let request = self
+            .request(Method::GET, &["repositories", repository, "indexes"])?
+            .query(params);
+        self.send_json(request, Retry::Safe).await
+    }
+
+    /// Adds texts to the repository, with a new idempotency key.
+    pub async fn add_texts(
+        &self,
+        repository: &str,
+        texts: &TextAddRequest,
+    ) -> Result<TextAdditionResponse> {
+        self.add_texts_with_key(repository, texts, &new_idempotency_key())
+            .await
+    }
+
+    /// Adds texts to the repository with the idempotency key, for services
+    /// which keep the key to retry the request after they restart.
+    pub async fn add_texts_with_key(
+        &self,
+        repository: &str,
+        texts: &TextAddRequest,
+        idempotency_key: &str,
+    ) -> Result<TextAdditionResponse> {
+        let request = self
+            .request(Method::POST, &["repositories", repository, "add_texts"])?
+            .header(IDEMPOTENCY_KEY_HEADER, idempotency_key)
+            .json(texts);
+        self.send_json(request, Retry::Safe).await
+    }
+
+    /// Uploads a file to the repository. Uploads are not retried.
+    pub async fn upload_file(&self, repository: &str, name: &str, data: Bytes) -> Result<()> {
+        let part = multipart::Part::stream(data).file_name(name.to_string());
+        let request = self
+            .request(Method::POST, &["repositories", repository, "upload_file"])?
+            .multipart(multipart::Form::new().part("file", part));
+        self.send(request, Retry::Never).await?;
+        Ok(())
+    }
+
+    pub async fn list_content(
+        &self,
+        repository: &str,
+        filter: &ContentFilter,
+        params: &ListParams,
+    ) -> Result<Page<ContentInfo>> {
+        let request = self
+            .request(Method::GET, &["repositories", repository, "content"])?
+            .query(filter)
+            .query(params);
+        self.send_json(request, Retry::Safe).await
+    }
+
+    /// The bytes of the content, wherever they are stored.
+    pub async fn read_content(&self, repository: &str, content_id: &str) -> Result<Bytes> {
+        let request = self.request(
+            Method::GET,
+            &["repositories", repository, "content", content_id, "data"],
+        )?;
+        Ok(self.send(request, Retry::Safe).await?.bytes().await?)
+    }
+
+    pub async fn delete_content(
+        &self,
+        repository: &str,
+        content_id: &str,
+    ) -> Result<DeleteContentResponse> {
+        let request = self.request(
+            Method::DELETE,
+            &["repositories", repository, "content", content_id],
+        )?;
+        self.send_json(request, Retry::Safe).await
+    }
+
+    /// Adds events to the repository, with a new idempotency key.
+    pub async fn add_events(&self, repository: &str, events: &[Event]) -> Result<EventAddResponse> {
+        self.add_events_with_key(repository, events, &new_idempotency_key())
+            .await
+    }
+
+    pub async fn add_events_with_key(
+        &self,
+        repository: &str,
+        events: &[Event],
+        idempotency_key: &str,
+    ) -> Result<EventAddResponse> {
+        let request = self
+            .request(Method::POST, &["repositories", repository, "events"])?
+            .header(IDEMPOTENCY_KEY_HEADER, idempotency_key)
+            .json(&EventAddRequest { events });
+        self.send_json(request, Retry::Safe).await
+    }
+
+    pub async fn search(
+        &self,
+        repository: &str,
+        search: &SearchRequest,
+    ) -> Result<Vec<DocumentFragment>> {
+        let request = self
+            .request(Method::POST, &["repositories", repository, "search"])?
+            .json(search);
+        let response: IndexSearchResponse = self.send_json(request, Retry::Safe).await?;
+        Ok(response.results)
+    }
+
+    pub async fn keyword_search(
+        &self,
+        repository: &str,
+        search: &KeywordSearchRequest,
+    ) -> Result<Vec<DocumentFragment>> {
+        let request = self
+            .request(
+                Method::POST,
+                &["repositories", repository, "keyword_search"],
+            )?
+            .json(search);
+        let response: IndexSearchResponse = self.send_json(request, Retry::Safe).await?;
+        Ok(response.results)
+    }
+
+    /// The attributes of the index, of one content or of all of it.
+    pub async fn get_attributes(
+        &self,
+        repository: &str,
+        index: &str,
+        content_id: Option<&str>,
+    ) -> Result<Vec<ExtractedAttributes>> {
+        let mut request = self
+            .request(Method::GET, &["repositories", repository, "attributes"])?
+            .query(&[("index", index)]);
+        if let Some(content_id) = content_id {
+            request = request.query(&[("content_id", content_id)]);
+        }
+        let response: AttributeLookupResponse = self.send_json(request, Retry::Safe).await?;
+        Ok(response.attributes)
+    }
+
+    /// The attributes of the index which match the filter expression, e.g.
+    /// `attributes.price < 10`.
+    pub async fn query_attributes(
+        &self,
+        repository: &str,
+        index: &str,
+        query: Option<&str>,
+        params: &ListParams,
+    ) -> Result<Page<ExtractedAttributes>> {
+        let mut request = self
+            .request(
+                Method::GET,
+                &["repositories", repository, "attributes", "query"],
+            )?
+            .query(&[("index", index)])
+            .query(params);
+        if let Some(query) = query {
+            request = request.query(&[("query", query)]);
+        }
+        self.send_json(request, Retry::Safe).await
+    }
+
+    pub async fn get_work(&self, repository: &str, work_id: &str) -> Result<GetWorkResponse> {
+        let request = self.request(Method::GET, &["repositories", repository, "work", work_id])?;
+        self.send_json(request, Retry::Safe).await
+    }
+
+    /// The url of the path segments, which are escaped.
+    fn url(&self, segments: &[&str]) -> Result<Url> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| url::ParseError::RelativeUrlWithCannotBeABaseBase)?
+            .pop_if_empty()
+            .extend(segments);
+        Ok(url)
+    }
+
+    fn request(&self, method: Method, segments: &[&str]) -> Result<RequestBuilder> {
+        let mut request = self.http.request(method, self.url(segments)?);
+        if let Some(namespace) = &self.namespace {
+            request = request.header(NAMESPACE_HEADER, namespace);
+        }
+        if let Some(api_key) = &self.api_key {
+            request = request.header(API_KEY_HEADER, api_key);
+        }
+        Ok(request.header(header::ACCEPT, "application/json"))
+    }
+
+    async fn send_json<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+        retry: Retry,
+    ) -> Result<T> {
+        Ok(self.send(request, retry).await?.json().await?)
+    }
+
+    /// Sends the request, and sends it again while it fails in a way that
+    /// may pass and the retry policy allows it. Requests with a streamed
+    /// body can not be sent again.
+    async fn send(&self, request: RequestBuilder, retry: Retry) -> Result<Response> {
+        let request = request.build()?;
+        let mut attempt = 1;
+        loop {
+            let Some(attempt_request) = request
+                .try_clone()
+                .filter(|_| retry == Retry::Safe && attempt < self.retry_policy.max_attempts)
+            else {
+                return error_for_status(self.http.execute(request).await?).await;
+            };
+            let delay = match self.http.execute(attempt_request).await {
+                Ok(response) if retry::is_retryable_status(response.status()) => {
+                    self.retry_policy.delay(attempt, Some(&response))
+                }
+                Ok(response) => return error_for_status(response).await,
+                Err(err) if retry::is_retryable_error(&err) => {
+                    self.retry_policy.delay(attempt, None)
+                }
+                Err(err) => return Err(err.into()),
+            };
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+fn new_idempotency_key() -> String {
+    uuid::Uuid::now_v7().to_string()
+}
+
+/// The response, or the error the server answered with.
+async fn error_for_status(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let message = response.text().await.unwrap_or_default();
+    Err(ClientError::Api { status, message })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Answers requests with the responses in turn, and records the
+    /// requests.
+    async fn serve(responses: Vec<&'static str>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buffer = vec![0; 64 * 1024];
+                let read = stream.read(&mut buffer).await.unwrap();
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&buffer[..read]).to_string());
+                stream.write_all(response.as_bytes()).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+        (format!("http://{}", addr), requests)
+    }
+
+    fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+        request.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    #[tokio::test]
+    async fn test_retries_with_the_same_idempotency_key() {
+        let (url, requests) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nretry-after: 0\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 45\r\nconnection: close\r\n\r\n{\"content_ids\":[\"a\"],\"inline_extractions\":[]}",
+        ])
+        .await;
+        let client = IndexifyClient::new(&url)
+            .unwrap()
+            .with_namespace("research")
+            .with_api_key("idx_key");
+        let response = client
+            .add_texts("my docs", &TextAddRequest::new(vec![Text::new("hello")]))
+            .await;
+        let requests = requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].starts_with("POST /repositories/my%20docs/add_texts "));
+        assert_eq!(header(&requests[0], NAMESPACE_HEADER), Some("research"));
+        assert_eq!(header(&requests[0], API_KEY_HEADER), Some("idx_key"));
+        let key = header(&requests[0], IDEMPOTENCY_KEY_HEADER).unwrap();
+        assert_eq!(header(&requests[1], IDEMPOTENCY_KEY_HEADER), Some(key));
+        assert_eq!(response.unwrap().content_ids, vec!["a"]);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_not_retried() {
+        let (url, requests) = serve(vec![
+            "HTTP/1.1 404 Not Found\r\ncontent-length: 20\r\nconnection: close\r\n\r\nrepository not found",
+        ])
+        .await;
+        let client = IndexifyClient::new(&url).unwrap();
+        let err = client.get_repository("missing").await.unwrap_err();
+        assert_eq!(err.status(), Some(reqwest::StatusCode::NOT_FOUND));
+        assert!(err.to_string().contains("repository not found"));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let unavailable =
+            "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+        let (url, requests) = serve(vec![unavailable, unavailable]).await;
+        let client = IndexifyClient::new(&url)
+            .unwrap()
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 2,
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(1),
+            });
+        let err = client
+            .search("docs", &SearchRequest::new("docs.embedding", "hello"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), Some(reqwest::StatusCode::SERVICE_UNAVAILABLE));
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use reqwest::{header, Response, StatusCode};
+
+/// How requests which failed in a way that may pass are retried: when the
+/// server could not be reached or timed out, was rate limited with a 429, or
+/// was unavailable with a 502, 503 or 504.
+///
+/// Only requests which are safe to send again are retried: reads, searches,
+/// updates which replace a whole object, and additions of texts and events,
+/// which are sent with an `Idempotency-Key` so that their retries are added
+/// once.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts of a request, including the first one. 1 disables retries.
+    pub max_attempts: u32,
+    /// The backoff before the first retry, which doubles with every retry.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy which sends every request once.
+    pub fn never() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// The backoff after the attempt, counted from 1, before jitter.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// How long to wait after the attempt: the `Retry-After` of the response
+    /// when it has one, or the backoff with jitter between half of it and
+    /// all of it, so that clients which failed together do not retry
+    /// together.
+    pub(crate) fn delay(&self, attempt: u32, response: Option<&Response>) -> Duration {
+        if let Some(retry_after) = response.and_then(retry_after) {
+            return retry_after.min(self.max_backoff);
+        }
+        let backoff = self.backoff(attempt);
+        backoff / 2 + backoff.mul_f64(rand::random::<f64>() / 2.0)
+    }
+}
+
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS |
+            StatusCode::BAD_GATEWAY |
+            StatusCode::SERVICE_UNAVAILABLE |
+            StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+pub(crate) fn is_retryable_error(err: &reqwest::Error) -> bool {
+    err.is_connect() || err.is_timeout()
+}
+
+/// The `Retry-After` of a response, in seconds.
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff(5), Duration::from_secs(1));
+        assert_eq!(policy.backoff(100), Duration::from_secs(1));
+        for attempt in 1..6 {
+            let delay = policy.delay(attempt, None);
+            assert!(delay >= policy.backoff(attempt) / 2);
+            assert!(delay <= policy.backoff(attempt));
+        }
+    }
+
+    #[test]
+    fn test_retryable_status() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable_status(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(!is_retryable_status(StatusCode::CONFLICT));
+    }
+}
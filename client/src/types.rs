@@ -0,0 +1,486 @@
+//! The requests and responses of the API. Settings which are rarely set by
+//! services, such as the chunkers and sinks of bindings or the policies of
+//! repositories, are kept as JSON, see the API docs for their fields.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ListParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+impl ListParams {
+    /// The page after `page`, or `None` after the last page.
+    pub fn next<T>(&self, page: &Page<T>) -> Option<Self> {
+        Some(Self {
+            cursor: Some(page.next_cursor.clone()?),
+            limit: self.limit,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    /// Items across all the pages. It can be off while items are added or
+    /// removed.
+    pub total_estimate: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExtractorBinding {
+    pub extractor: String,
+    pub name: String,
+    /// The content the binding extracts, by its metadata, e.g.
+    /// `metadata.lang = "en"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_params: Option<Value>,
+    /// The mime types of the content the binding extracts. Every content is
+    /// extracted when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub content_types: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<usize>,
+    #[serde(default)]
+    pub disabled: bool,
+    /// The other settings of the binding, such as `chunker`, `sinks` or
+    /// `quantization`.
+    #[serde(flatten)]
+    pub options: Map<String, Value>,
+}
+
+impl ExtractorBinding {
+    pub fn new(extractor: &str, name: &str) -> Self {
+        Self {
+            extractor: extractor.into(),
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_filter(mut self, filter: &str) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    pub fn with_input_params(mut self, input_params: Value) -> Self {
+        self.input_params = Some(input_params);
+        self
+    }
+
+    pub fn with_option(mut self, name: &str, value: Value) -> Self {
+        self.options.insert(name.into(), value);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CreateRepository {
+    pub name: String,
+    pub extractor_bindings: Vec<ExtractorBinding>,
+    pub metadata: HashMap<String, Value>,
+    /// The other settings of the repository, such as `data_connectors`,
+    /// `dedup_policy`, `retention` or `text_analysis`.
+    #[serde(flatten)]
+    pub options: Map<String, Value>,
+}
+
+impl CreateRepository {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_extractor_binding(mut self, binding: ExtractorBinding) -> Self {
+        self.extractor_bindings.push(binding);
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: HashMap<String, Value>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn with_option(mut self, name: &str, value: Value) -> Self {
+        self.options.insert(name.into(), value);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct CreateRepositoryResponse {
+    pub version: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DataRepository {
+    #[serde(default)]
+    pub namespace: String,
+    pub name: String,
+    pub extractor_bindings: Vec<ExtractorBinding>,
+    pub metadata: HashMap<String, Value>,
+    /// Incremented by every update of the repository.
+    #[serde(default)]
+    pub version: i64,
+    #[serde(flatten)]
+    pub options: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct GetRepositoryResponse {
+    pub repository: DataRepository,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeleteRepositoryResponse {
+    pub dry_run: bool,
+    pub content: u64,
+    pub chunks: u64,
+    pub attributes: u64,
+    pub events: u64,
+    pub work: u64,
+    pub indexes: Vec<String>,
+    pub vector_collections: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExtractorDescription {
+    pub name: String,
+    pub description: String,
+    pub input_params: Value,
+    pub schemas: Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExtractorBindResponse {
+    #[serde(default)]
+    pub index_names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct UpdateExtractorBindingResponse {
+    pub extractor_binding: ExtractorBinding,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Index {
+    pub name: String,
+    pub schema: Value,
+    pub schema_version: i32,
+    #[serde(default)]
+    pub extractor_binding: Option<String>,
+    #[serde(flatten)]
+    pub options: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Text {
+    pub text: String,
+    #[serde(default)]
+    pub metadata: HashMap<String, Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetched_at: Option<u64>,
+}
+
+impl Text {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_metadata(mut self, metadata: HashMap<String, Value>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TextAddRequest {
+    pub documents: Vec<Text>,
+    /// Waits until the texts are searchable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync: Option<bool>,
+    /// Extracts the texts in the request, with the results in the response.
+    pub inline: bool,
+}
+
+impl TextAddRequest {
+    pub fn new(documents: Vec<Text>) -> Self {
+        Self {
+            documents,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TextAdditionResponse {
+    pub content_ids: Vec<String>,
+    #[serde(default)]
+    pub inline_extractions: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Event {
+    pub text: String,
+    pub unix_timestamp: Option<u64>,
+    pub metadata: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct EventAddRequest<'a> {
+    pub events: &'a [Event],
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventAddResponse {
+    pub event_ids: Vec<String>,
+}
+
+/// Where the extraction of content by an extractor binding stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractionState {
+    Pending,
+    Completed,
+    /// The filters of the binding do not match the content.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Provenance {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_connector: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetched_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContentInfo {
+    pub id: String,
+    pub content_type: String,
+    pub source: String,
+    pub metadata: HashMap<String, Value>,
+    pub extraction_state: HashMap<String, ExtractionState>,
+    pub legal_hold: bool,
+    #[serde(default)]
+    pub parent_content_id: Option<String>,
+    #[serde(default)]
+    pub produced_by_binding: Option<String>,
+    #[serde(default)]
+    pub provenance: Provenance,
+}
+
+/// The content listed, by a filter expression on its metadata and by its
+/// provenance. Unset fields match all content.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ContentFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_connector: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fetched_after: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fetched_before: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeleteContentResponse {
+    pub deleted_at: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SearchRequest {
+    pub index: String,
+    pub query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub k: Option<u64>,
+    /// The content searched, by its metadata, e.g. `metadata.lang = "en"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f32>,
+    /// Attribute indexes whose attributes are returned with the results.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include_attributes: Vec<String>,
+    /// The other options of the search, such as `attribute_filter`, `mmr` or
+    /// `summary`.
+    #[serde(flatten)]
+    pub options: Map<String, Value>,
+}
+
+impl SearchRequest {
+    pub fn new(index: &str, query: &str) -> Self {
+        Self {
+            index: index.into(),
+            query: query.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_k(mut self, k: u64) -> Self {
+        self.k = Some(k);
+        self
+    }
+
+    pub fn with_filter(mut self, filter: &str) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    pub fn with_option(mut self, name: &str, value: Value) -> Self {
+        self.options.insert(name.into(), value);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KeywordSearchRequest {
+    pub index: String,
+    pub query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub k: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include_attributes: Vec<String>,
+}
+
+impl KeywordSearchRequest {
+    pub fn new(index: &str, query: &str) -> Self {
+        Self {
+            index: index.into(),
+            query: query.into(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DocumentFragment {
+    pub content_id: String,
+    pub text: String,
+    pub confidence_score: f32,
+    pub metadata: HashMap<String, Value>,
+    #[serde(default)]
+    pub attributes: Option<HashMap<String, Value>>,
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// The other fields of the result, such as `offsets`, `region` or
+    /// `explanation`.
+    #[serde(flatten)]
+    pub options: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct IndexSearchResponse {
+    pub results: Vec<DocumentFragment>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExtractedAttributes {
+    pub id: String,
+    pub content_id: String,
+    pub attributes: Value,
+    pub extractor_name: String,
+    #[serde(default)]
+    pub schema_version: i32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct AttributeLookupResponse {
+    pub attributes: Vec<ExtractedAttributes>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkInfo {
+    pub id: String,
+    pub content_id: String,
+    pub extractor: String,
+    pub extractor_binding: String,
+    pub state: String,
+    pub executor_id: Option<String>,
+    pub items_processed: u64,
+    pub percent: Option<f64>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GetWorkResponse {
+    pub work: WorkInfo,
+    pub log: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_extractor_binding_options() {
+        let binding = ExtractorBinding::new("diptanu/minilm-l6-extractor", "embeddings")
+            .with_filter("metadata.lang = \"en\"")
+            .with_option("chunker", json!({"strategy": "sentence", "size": 512}));
+        let value = serde_json::to_value(&binding).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "extractor": "diptanu/minilm-l6-extractor",
+                "name": "embeddings",
+                "filter": "metadata.lang = \"en\"",
+                "disabled": false,
+                "chunker": {"strategy": "sentence", "size": 512},
+            })
+        );
+        let binding: ExtractorBinding = serde_json::from_value(value).unwrap();
+        assert_eq!(binding.options["chunker"]["size"], json!(512));
+    }
+
+    #[test]
+    fn test_list_params_next() {
+        let params = ListParams {
+            cursor: None,
+            limit: Some(10),
+        };
+        let page = Page::<String> {
+            items: vec![],
+            next_cursor: Some("abc".into()),
+            total_estimate: 20,
+        };
+        let next = params.next(&page).unwrap();
+        assert_eq!(next.cursor.as_deref(), Some("abc"));
+        assert_eq!(next.limit, Some(10));
+        let last = Page::<String> {
+            next_cursor: None,
+            ..page
+        };
+        assert!(next.next(&last).is_none());
+    }
+}
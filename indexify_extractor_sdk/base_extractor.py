@@ -9,6 +9,13 @@ from pydantic import BaseModel, Json
 class EmbeddingSchema(BaseModel):
     distance_metric: str
     dim: int
+    # Set when every chunk is embedded as several vectors, such as the vectors
+    # of its tokens, which are searched by late interaction.
+    multi_vector: bool = False
+    # The content the extractor embeds, "text" and "image". Extractors which
+    # embed both into the same space, such as CLIP, can search images with a
+    # text and the other way around.
+    modalities: List[str] = ["text"]
 
 class ExtractorSchema(BaseModel):
     features: dict[str, Union[EmbeddingSchema, Json]]
@@ -26,6 +33,10 @@ class Feature(BaseModel):
     def embedding(cls, value: List[float], name: str="embedding"):
         return cls(feature_type="embedding", name=name, value=json.dumps(value))
     
+    @classmethod
+    def multi_vector(cls, value: List[List[float]], name: str="embedding"):
+        return cls(feature_type="embedding", name=name, value=json.dumps(value))
+
     @classmethod
     def ner(cls, entity: str, value: str, score: float, name: str="ner"):
         return cls(feature_type="ner", name=name, value=json.dumps({"entity": entity, "value": value, "score": score}))
@@ -1,12 +1,88 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20240101_000001_add_repository_text_analysis;
+mod m20240101_000002_create_connector_state;
+mod m20240101_000003_create_api_keys;
+mod m20240101_000004_add_content_source;
+mod m20240101_000005_add_namespaces;
+mod m20240101_000006_add_index_generations;
+mod m20240101_000007_create_repository_roles;
+mod m20240101_000008_add_work_trace_context;
+mod m20240101_000009_add_work_timestamps;
+mod m20240101_000010_create_audit_log;
+mod m20240101_000011_create_repository_dashboards;
+mod m20240101_000012_create_legal_holds;
+mod m20240101_000013_add_chunk_text_search;
+mod m20240101_000014_create_change_log;
+mod m20240101_000015_add_index_reranker;
+mod m20240101_000016_add_session_events;
+mod m20240101_000017_add_parent_chunks;
+mod m20240101_000018_add_chunk_offsets;
+mod m20240101_000019_add_repository_dedup_policy;
+mod m20240101_000020_add_repository_retention;
+mod m20240101_000021_add_content_deleted_at;
+mod m20240101_000022_create_ingestion_jobs;
+mod m20240101_000023_add_work_progress;
+mod m20240101_000024_create_idempotency_keys;
+mod m20240101_000025_add_repository_version;
+mod m20240101_000026_add_content_lineage;
+mod m20240101_000027_create_graph_index;
+mod m20240101_000028_create_embedding_cache;
+mod m20240101_000029_create_extraction_event_archive;
+mod m20240101_000030_create_event_consumers;
+mod m20240101_000031_add_chunk_regions;
+mod m20240101_000032_add_chunk_times;
+mod m20240101_000033_add_content_provenance;
+mod m20240101_000034_create_repository_templates;
+mod m20240101_000035_add_attribute_schema_versions;
+mod m20240101_000036_create_summaries;
+mod m20240101_000037_add_content_hash;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20240101_000001_add_repository_text_analysis::Migration),
+            Box::new(m20240101_000002_create_connector_state::Migration),
+            Box::new(m20240101_000003_create_api_keys::Migration),
+            Box::new(m20240101_000004_add_content_source::Migration),
+            Box::new(m20240101_000005_add_namespaces::Migration),
+            Box::new(m20240101_000006_add_index_generations::Migration),
+            Box::new(m20240101_000007_create_repository_roles::Migration),
+            Box::new(m20240101_000008_add_work_trace_context::Migration),
+            Box::new(m20240101_000009_add_work_timestamps::Migration),
+            Box::new(m20240101_000010_create_audit_log::Migration),
+            Box::new(m20240101_000011_create_repository_dashboards::Migration),
+            Box::new(m20240101_000012_create_legal_holds::Migration),
+            Box::new(m20240101_000013_add_chunk_text_search::Migration),
+            Box::new(m20240101_000014_create_change_log::Migration),
+            Box::new(m20240101_000015_add_index_reranker::Migration),
+            Box::new(m20240101_000016_add_session_events::Migration),
+            Box::new(m20240101_000017_add_parent_chunks::Migration),
+            Box::new(m20240101_000018_add_chunk_offsets::Migration),
+            Box::new(m20240101_000019_add_repository_dedup_policy::Migration),
+            Box::new(m20240101_000020_add_repository_retention::Migration),
+            Box::new(m20240101_000021_add_content_deleted_at::Migration),
+            Box::new(m20240101_000022_create_ingestion_jobs::Migration),
+            Box::new(m20240101_000023_add_work_progress::Migration),
+            Box::new(m20240101_000024_create_idempotency_keys::Migration),
+            Box::new(m20240101_000025_add_repository_version::Migration),
+            Box::new(m20240101_000026_add_content_lineage::Migration),
+            Box::new(m20240101_000027_create_graph_index::Migration),
+            Box::new(m20240101_000028_create_embedding_cache::Migration),
+            Box::new(m20240101_000029_create_extraction_event_archive::Migration),
+            Box::new(m20240101_000030_create_event_consumers::Migration),
+            Box::new(m20240101_000031_add_chunk_regions::Migration),
+            Box::new(m20240101_000032_add_chunk_times::Migration),
+            Box::new(m20240101_000033_add_content_provenance::Migration),
+            Box::new(m20240101_000034_create_repository_templates::Migration),
+            Box::new(m20240101_000035_add_attribute_schema_versions::Migration),
+            Box::new(m20240101_000036_create_summaries::Migration),
+            Box::new(m20240101_000037_add_content_hash::Migration),
+        ]
     }
 }
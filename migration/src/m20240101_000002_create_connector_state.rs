@@ -0,0 +1,106 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ConnectorState::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ConnectorState::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ConnectorState::RepositoryId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ConnectorState::Checkpoint).json_binary())
+                    .col(ColumnDef::new(ConnectorState::LastSyncedAt).big_integer())
+                    .col(ColumnDef::new(ConnectorState::SyncStartedAt).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ConnectorSyncRuns::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ConnectorSyncRuns::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ConnectorSyncRuns::ConnectorId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ConnectorSyncRuns::RepositoryId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ConnectorSyncRuns::Status)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ConnectorSyncRuns::StartedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ConnectorSyncRuns::FinishedAt).big_integer())
+                    .col(
+                        ColumnDef::new(ConnectorSyncRuns::ItemsSynced)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ConnectorSyncRuns::Error).string())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ConnectorSyncRuns::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ConnectorState::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ConnectorState {
+    Table,
+    Id,
+    RepositoryId,
+    Checkpoint,
+    LastSyncedAt,
+    SyncStartedAt,
+}
+
+#[derive(Iden)]
+enum ConnectorSyncRuns {
+    Table,
+    Id,
+    ConnectorId,
+    RepositoryId,
+    Status,
+    StartedAt,
+    FinishedAt,
+    ItemsSynced,
+    Error,
+}
@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Content::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Content::Source)
+                            .string()
+                            .not_null()
+                            .default("api"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Work::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Work::Source)
+                            .string()
+                            .not_null()
+                            .default("api"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Work::Table)
+                    .drop_column(Work::Source)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Content::Table)
+                    .drop_column(Content::Source)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Content {
+    Table,
+    Source,
+}
+
+#[derive(Iden)]
+enum Work {
+    Table,
+    Source,
+}
@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// The tables of namespaced objects. What existed before namespaces is in
+/// the default namespace.
+const NAMESPACED_TABLES: [&str; 10] = [
+    "index",
+    "content",
+    "chunked_content",
+    "events",
+    "work",
+    "attributes_index",
+    "connector_state",
+    "connector_sync_runs",
+    "api_keys",
+    "data_repository",
+];
+
+/// The primary keys which the namespace becomes a part of, with the columns
+/// they had before.
+const NAMESPACED_PRIMARY_KEYS: [(&str, &str, &str); 5] = [
+    ("index", "namespace, repository_id, name", "name"),
+    ("content", "namespace, id", "id"),
+    ("chunked_content", "namespace, chunk_id", "chunk_id"),
+    ("attributes_index", "namespace, id", "id"),
+    ("data_repository", "namespace, name", "name"),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in NAMESPACED_TABLES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Alias::new(table))
+                        .add_column_if_not_exists(
+                            ColumnDef::new(Alias::new("namespace"))
+                                .string()
+                                .not_null()
+                                .default("default"),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+        // The statements of sea-query can not change primary keys.
+        for (table, columns, _) in NAMESPACED_PRIMARY_KEYS {
+            manager
+                .get_connection()
+                .execute_unprepared(&format!(
+                    r#"ALTER TABLE "{table}" DROP CONSTRAINT IF EXISTS "{table}_pkey", ADD PRIMARY KEY ({columns})"#
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (table, _, columns) in NAMESPACED_PRIMARY_KEYS {
+            manager
+                .get_connection()
+                .execute_unprepared(&format!(
+                    r#"ALTER TABLE "{table}" DROP CONSTRAINT IF EXISTS "{table}_pkey", ADD PRIMARY KEY ({columns})"#
+                ))
+                .await?;
+        }
+        for table in NAMESPACED_TABLES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Alias::new(table))
+                        .drop_column(Alias::new("namespace"))
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Index::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Index::Generation)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ChunkedContent::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(ChunkedContent::Generation)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        // The chunks of the generations of an index are kept apart while it
+        // is reindexed.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE chunked_content DROP CONSTRAINT IF EXISTS chunked_content_pkey, \
+                 ADD PRIMARY KEY (namespace, index_name, generation, chunk_id)",
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE chunked_content DROP CONSTRAINT IF EXISTS chunked_content_pkey, \
+                 ADD PRIMARY KEY (namespace, chunk_id)",
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ChunkedContent::Table)
+                    .drop_column(ChunkedContent::Generation)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Index::Table)
+                    .drop_column(Index::Generation)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Index {
+    Table,
+    Generation,
+}
+
+#[derive(Iden)]
+enum ChunkedContent {
+    Table,
+    Generation,
+}
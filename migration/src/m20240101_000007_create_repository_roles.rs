@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RepositoryRoles::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RepositoryRoles::Namespace)
+                            .string()
+                            .not_null()
+                            .default("default"),
+                    )
+                    .col(
+                        ColumnDef::new(RepositoryRoles::RepositoryId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RepositoryRoles::Principal)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RepositoryRoles::Role).string().not_null())
+                    .col(
+                        ColumnDef::new(RepositoryRoles::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        sea_query::Index::create()
+                            .col(RepositoryRoles::Namespace)
+                            .col(RepositoryRoles::RepositoryId)
+                            .col(RepositoryRoles::Principal),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RepositoryRoles::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum RepositoryRoles {
+    Table,
+    Namespace,
+    RepositoryId,
+    Principal,
+    Role,
+    CreatedAt,
+}
@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Work::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Work::CreatedAt)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column_if_not_exists(ColumnDef::new(Work::AssignedAt).big_integer())
+                    .add_column_if_not_exists(ColumnDef::new(Work::CompletedAt).big_integer())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Work::Table)
+                    .drop_column(Work::CreatedAt)
+                    .drop_column(Work::AssignedAt)
+                    .drop_column(Work::CompletedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Work {
+    Table,
+    CreatedAt,
+    AssignedAt,
+    CompletedAt,
+}
@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditLog::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuditLog::Seq)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(AuditLog::Namespace)
+                            .string()
+                            .not_null()
+                            .default("default"),
+                    )
+                    .col(ColumnDef::new(AuditLog::Principal).string().not_null())
+                    .col(ColumnDef::new(AuditLog::Action).string().not_null())
+                    .col(ColumnDef::new(AuditLog::RepositoryId).string())
+                    .col(ColumnDef::new(AuditLog::Method).string().not_null())
+                    .col(ColumnDef::new(AuditLog::Path).string().not_null())
+                    .col(ColumnDef::new(AuditLog::Status).integer().not_null())
+                    .col(ColumnDef::new(AuditLog::CreatedAt).big_integer().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditLog::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum AuditLog {
+    Table,
+    Seq,
+    Namespace,
+    Principal,
+    Action,
+    RepositoryId,
+    Method,
+    Path,
+    Status,
+    CreatedAt,
+}
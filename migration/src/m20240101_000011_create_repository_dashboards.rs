@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Content added before its creation time was recorded counts as
+        // created at the epoch.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Content::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Content::CreatedAt)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RepositoryDashboards::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RepositoryDashboards::Namespace)
+                            .string()
+                            .not_null()
+                            .default("default"),
+                    )
+                    .col(
+                        ColumnDef::new(RepositoryDashboards::RepositoryId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RepositoryDashboards::Summary)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RepositoryDashboards::ComputedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        sea_query::Index::create()
+                            .col(RepositoryDashboards::Namespace)
+                            .col(RepositoryDashboards::RepositoryId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RepositoryDashboards::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Content::Table)
+                    .drop_column(Content::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum RepositoryDashboards {
+    Table,
+    Namespace,
+    RepositoryId,
+    Summary,
+    ComputedAt,
+}
+
+#[derive(Iden)]
+enum Content {
+    Table,
+    CreatedAt,
+}
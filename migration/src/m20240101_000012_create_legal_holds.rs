@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LegalHolds::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LegalHolds::Namespace)
+                            .string()
+                            .not_null()
+                            .default("default"),
+                    )
+                    .col(ColumnDef::new(LegalHolds::Id).string().not_null())
+                    .col(ColumnDef::new(LegalHolds::RepositoryId).string().not_null())
+                    .col(ColumnDef::new(LegalHolds::ContentId).string())
+                    .col(ColumnDef::new(LegalHolds::Reason).text().not_null())
+                    .col(
+                        ColumnDef::new(LegalHolds::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        sea_query::Index::create()
+                            .col(LegalHolds::Namespace)
+                            .col(LegalHolds::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LegalHolds::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum LegalHolds {
+    Table,
+    Namespace,
+    Id,
+    RepositoryId,
+    ContentId,
+    Reason,
+    CreatedAt,
+}
@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ChunkedContent::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(ChunkedContent::TextSearch).custom(Alias::new("tsvector")),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .if_not_exists()
+                    .name("chunked_content_text_search_idx")
+                    .table(ChunkedContent::Table)
+                    .col(ChunkedContent::TextSearch)
+                    .index_type(IndexType::FullText)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("chunked_content_text_search_idx")
+                    .table(ChunkedContent::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ChunkedContent::Table)
+                    .drop_column(ChunkedContent::TextSearch)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ChunkedContent {
+    Table,
+    TextSearch,
+}
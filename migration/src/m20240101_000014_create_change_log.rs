@@ -0,0 +1,121 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ChangeLog::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ChangeLog::Seq)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ChangeLog::Namespace)
+                            .string()
+                            .not_null()
+                            .default("default"),
+                    )
+                    .col(ColumnDef::new(ChangeLog::RepositoryId).string().not_null())
+                    .col(ColumnDef::new(ChangeLog::Kind).string().not_null())
+                    .col(ColumnDef::new(ChangeLog::ObjectId).string().not_null())
+                    .col(ColumnDef::new(ChangeLog::IndexName).string())
+                    .col(
+                        ColumnDef::new(ChangeLog::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .if_not_exists()
+                    .name("idx_change_log_repository_seq")
+                    .table(ChangeLog::Table)
+                    .col(ChangeLog::Namespace)
+                    .col(ChangeLog::RepositoryId)
+                    .col(ChangeLog::Seq)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(SyncCheckpoints::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SyncCheckpoints::Namespace)
+                            .string()
+                            .not_null()
+                            .default("default"),
+                    )
+                    .col(
+                        ColumnDef::new(SyncCheckpoints::RepositoryId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(SyncCheckpoints::Source).string().not_null())
+                    .col(
+                        ColumnDef::new(SyncCheckpoints::Cursor)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(SyncCheckpoints::SyncedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        sea_query::Index::create()
+                            .col(SyncCheckpoints::Namespace)
+                            .col(SyncCheckpoints::RepositoryId)
+                            .col(SyncCheckpoints::Source),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SyncCheckpoints::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ChangeLog::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ChangeLog {
+    Table,
+    Seq,
+    Namespace,
+    RepositoryId,
+    Kind,
+    ObjectId,
+    IndexName,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum SyncCheckpoints {
+    Table,
+    Namespace,
+    RepositoryId,
+    Source,
+    Cursor,
+    SyncedAt,
+}
@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Events::Table)
+                    .add_column_if_not_exists(ColumnDef::new(Events::SessionId).string())
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Events::Embedded)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Events::Summarized)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .if_not_exists()
+                    .name("idx_events_session")
+                    .table(Events::Table)
+                    .col(Events::Namespace)
+                    .col(Events::RepositoryId)
+                    .col(Events::SessionId)
+                    .col(Events::UnixTimeStamp)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx_events_session")
+                    .table(Events::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Events::Table)
+                    .drop_column(Events::SessionId)
+                    .drop_column(Events::Embedded)
+                    .drop_column(Events::Summarized)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Events {
+    Table,
+    Namespace,
+    RepositoryId,
+    UnixTimeStamp,
+    SessionId,
+    Embedded,
+    Summarized,
+}
@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ChunkedContent::Table)
+                    .add_column_if_not_exists(ColumnDef::new(ChunkedContent::ParentId).string())
+                    .add_column_if_not_exists(
+                        ColumnDef::new(ChunkedContent::IsParent)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ChunkedContent::Table)
+                    .drop_column(ChunkedContent::ParentId)
+                    .drop_column(ChunkedContent::IsParent)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ChunkedContent {
+    Table,
+    ParentId,
+    IsParent,
+}
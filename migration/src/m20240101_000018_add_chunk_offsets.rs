@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ChunkedContent::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(ChunkedContent::StartOffset).big_integer(),
+                    )
+                    .add_column_if_not_exists(
+                        ColumnDef::new(ChunkedContent::EndOffset).big_integer(),
+                    )
+                    .add_column_if_not_exists(ColumnDef::new(ChunkedContent::Page).integer())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ChunkedContent::Table)
+                    .drop_column(ChunkedContent::StartOffset)
+                    .drop_column(ChunkedContent::EndOffset)
+                    .drop_column(ChunkedContent::Page)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ChunkedContent {
+    Table,
+    StartOffset,
+    EndOffset,
+    Page,
+}
@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(DataRepository::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(DataRepository::DedupPolicy)
+                            .string()
+                            .not_null()
+                            .default("dedupe"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(DataRepository::Table)
+                    .drop_column(DataRepository::DedupPolicy)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum DataRepository {
+    Table,
+    DedupPolicy,
+}
@@ -0,0 +1,156 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(IngestionJobs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(IngestionJobs::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(IngestionJobs::Namespace)
+                            .string()
+                            .not_null()
+                            .default("default"),
+                    )
+                    .col(
+                        ColumnDef::new(IngestionJobs::RepositoryId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(IngestionJobs::ManifestName)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(IngestionJobs::ManifestUrl)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(IngestionJobs::Format).string().not_null())
+                    .col(ColumnDef::new(IngestionJobs::Status).string().not_null())
+                    .col(ColumnDef::new(IngestionJobs::TotalRows).big_integer())
+                    .col(
+                        ColumnDef::new(IngestionJobs::NextRow)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(IngestionJobs::RowsSucceeded)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(IngestionJobs::RowsFailed)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(IngestionJobs::Error).text())
+                    .col(
+                        ColumnDef::new(IngestionJobs::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(IngestionJobs::HeartbeatAt).big_integer())
+                    .col(ColumnDef::new(IngestionJobs::FinishedAt).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .if_not_exists()
+                    .name("ingestion_jobs_status_idx")
+                    .table(IngestionJobs::Table)
+                    .col(IngestionJobs::Status)
+                    .col(IngestionJobs::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(IngestionJobErrors::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(IngestionJobErrors::Namespace)
+                            .string()
+                            .not_null()
+                            .default("default"),
+                    )
+                    .col(
+                        ColumnDef::new(IngestionJobErrors::JobId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(IngestionJobErrors::Row)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(IngestionJobErrors::Error).text().not_null())
+                    .primary_key(
+                        sea_query::Index::create()
+                            .col(IngestionJobErrors::Namespace)
+                            .col(IngestionJobErrors::JobId)
+                            .col(IngestionJobErrors::Row),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(IngestionJobErrors::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(IngestionJobs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum IngestionJobs {
+    Table,
+    Id,
+    Namespace,
+    RepositoryId,
+    ManifestName,
+    ManifestUrl,
+    Format,
+    Status,
+    TotalRows,
+    NextRow,
+    RowsSucceeded,
+    RowsFailed,
+    Error,
+    CreatedAt,
+    HeartbeatAt,
+    FinishedAt,
+}
+
+#[derive(Iden)]
+enum IngestionJobErrors {
+    Table,
+    Namespace,
+    JobId,
+    Row,
+    Error,
+}
@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Work::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Work::ItemsProcessed)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column_if_not_exists(ColumnDef::new(Work::Percent).double())
+                    .add_column_if_not_exists(ColumnDef::new(Work::Log).json_binary())
+                    .add_column_if_not_exists(ColumnDef::new(Work::LastError).text())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Work::Table)
+                    .drop_column(Work::ItemsProcessed)
+                    .drop_column(Work::Percent)
+                    .drop_column(Work::Log)
+                    .drop_column(Work::LastError)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Work {
+    Table,
+    ItemsProcessed,
+    Percent,
+    Log,
+    LastError,
+}
@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(IdempotencyKeys::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(IdempotencyKeys::Namespace)
+                            .string()
+                            .not_null()
+                            .default("default"),
+                    )
+                    .col(
+                        ColumnDef::new(IdempotencyKeys::RepositoryId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(IdempotencyKeys::Key).string().not_null())
+                    .col(
+                        ColumnDef::new(IdempotencyKeys::RequestHash)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(IdempotencyKeys::Response).json_binary())
+                    .col(
+                        ColumnDef::new(IdempotencyKeys::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        sea_query::Index::create()
+                            .col(IdempotencyKeys::Namespace)
+                            .col(IdempotencyKeys::RepositoryId)
+                            .col(IdempotencyKeys::Key),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .if_not_exists()
+                    .name("idempotency_keys_created_at_idx")
+                    .table(IdempotencyKeys::Table)
+                    .col(IdempotencyKeys::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(IdempotencyKeys::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum IdempotencyKeys {
+    Table,
+    Namespace,
+    RepositoryId,
+    Key,
+    RequestHash,
+    Response,
+    CreatedAt,
+}
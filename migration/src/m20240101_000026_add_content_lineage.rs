@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Content::Table)
+                    .add_column_if_not_exists(ColumnDef::new(Content::ParentContentId).string())
+                    .add_column_if_not_exists(ColumnDef::new(Content::ProducedByBinding).string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .if_not_exists()
+                    .name("idx_content_parent")
+                    .table(Content::Table)
+                    .col(Content::Namespace)
+                    .col(Content::ParentContentId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx_content_parent")
+                    .table(Content::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Content::Table)
+                    .drop_column(Content::ParentContentId)
+                    .drop_column(Content::ProducedByBinding)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Content {
+    Table,
+    Namespace,
+    ParentContentId,
+    ProducedByBinding,
+}
@@ -0,0 +1,135 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GraphNodes::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(GraphNodes::Namespace).string().not_null())
+                    .col(ColumnDef::new(GraphNodes::RepositoryId).string().not_null())
+                    .col(ColumnDef::new(GraphNodes::IndexName).string().not_null())
+                    .col(ColumnDef::new(GraphNodes::Id).string().not_null())
+                    .col(ColumnDef::new(GraphNodes::ContentId).string().not_null())
+                    .col(ColumnDef::new(GraphNodes::Name).string().not_null())
+                    .col(ColumnDef::new(GraphNodes::EntityType).string())
+                    .col(ColumnDef::new(GraphNodes::Properties).json_binary())
+                    .col(
+                        ColumnDef::new(GraphNodes::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        sea_query::Index::create()
+                            .col(GraphNodes::Namespace)
+                            .col(GraphNodes::RepositoryId)
+                            .col(GraphNodes::IndexName)
+                            .col(GraphNodes::Id)
+                            .col(GraphNodes::ContentId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(GraphEdges::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(GraphEdges::Namespace).string().not_null())
+                    .col(ColumnDef::new(GraphEdges::RepositoryId).string().not_null())
+                    .col(ColumnDef::new(GraphEdges::IndexName).string().not_null())
+                    .col(ColumnDef::new(GraphEdges::Id).string().not_null())
+                    .col(ColumnDef::new(GraphEdges::SourceId).string().not_null())
+                    .col(ColumnDef::new(GraphEdges::TargetId).string().not_null())
+                    .col(ColumnDef::new(GraphEdges::Relation).string().not_null())
+                    .col(ColumnDef::new(GraphEdges::ContentId).string().not_null())
+                    .col(ColumnDef::new(GraphEdges::Properties).json_binary())
+                    .col(
+                        ColumnDef::new(GraphEdges::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        sea_query::Index::create()
+                            .col(GraphEdges::Namespace)
+                            .col(GraphEdges::RepositoryId)
+                            .col(GraphEdges::IndexName)
+                            .col(GraphEdges::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .if_not_exists()
+                    .name("idx_graph_edges_source")
+                    .table(GraphEdges::Table)
+                    .col(GraphEdges::Namespace)
+                    .col(GraphEdges::RepositoryId)
+                    .col(GraphEdges::IndexName)
+                    .col(GraphEdges::SourceId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .if_not_exists()
+                    .name("idx_graph_edges_target")
+                    .table(GraphEdges::Table)
+                    .col(GraphEdges::Namespace)
+                    .col(GraphEdges::RepositoryId)
+                    .col(GraphEdges::IndexName)
+                    .col(GraphEdges::TargetId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GraphEdges::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(GraphNodes::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum GraphNodes {
+    Table,
+    Namespace,
+    RepositoryId,
+    IndexName,
+    Id,
+    ContentId,
+    Name,
+    EntityType,
+    Properties,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum GraphEdges {
+    Table,
+    Namespace,
+    RepositoryId,
+    IndexName,
+    Id,
+    SourceId,
+    TargetId,
+    Relation,
+    ContentId,
+    Properties,
+    CreatedAt,
+}
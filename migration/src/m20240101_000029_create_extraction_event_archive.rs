@@ -0,0 +1,105 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The coordinator looks up the unprocessed events, which stay few
+        // while the processed ones accumulate until they are compacted. The
+        // index is partial, which the index statements of sea-query can not
+        // express.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "CREATE INDEX IF NOT EXISTS idx_extraction_event_unprocessed ON extraction_event \
+                 (id) WHERE processed_at IS NULL",
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .if_not_exists()
+                    .name("idx_extraction_event_processed_at")
+                    .table(ExtractionEvent::Table)
+                    .col(ExtractionEvent::ProcessedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ExtractionEventArchive::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ExtractionEventArchive::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ExtractionEventArchive::Payload)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ExtractionEventArchive::AllocationInfo)
+                            .json_binary()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(ExtractionEventArchive::ProcessedAt)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ExtractionEventArchive::ArchivedAt)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(ExtractionEventArchive::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx_extraction_event_processed_at")
+                    .table(ExtractionEvent::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared("DROP INDEX IF EXISTS idx_extraction_event_unprocessed")
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum ExtractionEventArchive {
+    Table,
+    Id,
+    Payload,
+    AllocationInfo,
+    ProcessedAt,
+    ArchivedAt,
+}
+
+#[derive(Iden)]
+enum ExtractionEvent {
+    Table,
+    ProcessedAt,
+}
@@ -0,0 +1,106 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ExtractionEvent::Table)
+                    // The order the events are consumed in.
+                    .add_column_if_not_exists(
+                        ColumnDef::new(ExtractionEvent::Seq)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment(),
+                    )
+                    // The transaction which added the event, so that events
+                    // are only consumed once every event before them is
+                    // committed.
+                    .add_column_if_not_exists(
+                        ColumnDef::new(ExtractionEvent::TxId)
+                            .big_integer()
+                            .not_null()
+                            .default(Expr::cust("txid_current()")),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .if_not_exists()
+                    .name("idx_extraction_event_seq")
+                    .table(ExtractionEvent::Table)
+                    .col(ExtractionEvent::Seq)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(EventConsumers::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(EventConsumers::Name)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(EventConsumers::AckedSeq)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(EventConsumers::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(EventConsumers::Table).to_owned())
+            .await?;
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx_extraction_event_seq")
+                    .table(ExtractionEvent::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ExtractionEvent::Table)
+                    .drop_column(ExtractionEvent::Seq)
+                    .drop_column(ExtractionEvent::TxId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum EventConsumers {
+    Table,
+    Name,
+    AckedSeq,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum ExtractionEvent {
+    Table,
+    Seq,
+    TxId,
+}
@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Content::Table)
+                    .add_column_if_not_exists(ColumnDef::new(Content::SourceConnector).string())
+                    .add_column_if_not_exists(ColumnDef::new(Content::ExternalId).string())
+                    .add_column_if_not_exists(ColumnDef::new(Content::SourceUrl).text())
+                    .add_column_if_not_exists(ColumnDef::new(Content::FetchedAt).big_integer())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Content::Table)
+                    .drop_column(Content::SourceConnector)
+                    .drop_column(Content::ExternalId)
+                    .drop_column(Content::SourceUrl)
+                    .drop_column(Content::FetchedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Content {
+    Table,
+    SourceConnector,
+    ExternalId,
+    SourceUrl,
+    FetchedAt,
+}
@@ -0,0 +1,96 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RepositoryTemplates::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RepositoryTemplates::Namespace)
+                            .string()
+                            .not_null()
+                            .default("default"),
+                    )
+                    .col(
+                        ColumnDef::new(RepositoryTemplates::Name)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RepositoryTemplates::Description)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RepositoryTemplates::ExtractorBindings)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RepositoryTemplates::DataConnectors)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RepositoryTemplates::Metadata)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RepositoryTemplates::TextAnalysis)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RepositoryTemplates::DedupPolicy)
+                            .string()
+                            .not_null()
+                            .default("dedupe"),
+                    )
+                    .col(
+                        ColumnDef::new(RepositoryTemplates::Retention)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RepositoryTemplates::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        sea_query::Index::create()
+                            .col(RepositoryTemplates::Namespace)
+                            .col(RepositoryTemplates::Name),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RepositoryTemplates::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum RepositoryTemplates {
+    Table,
+    Namespace,
+    Name,
+    Description,
+    ExtractorBindings,
+    DataConnectors,
+    Metadata,
+    TextAnalysis,
+    DedupPolicy,
+    Retention,
+    CreatedAt,
+}
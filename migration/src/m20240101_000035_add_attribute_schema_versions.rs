@@ -0,0 +1,152 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Indexes and attributes created before schemas were versioned have
+        // the first version.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Index::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Index::SchemaVersion)
+                            .integer()
+                            .not_null()
+                            .default(1),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AttributesIndex::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(AttributesIndex::SchemaVersion)
+                            .integer()
+                            .not_null()
+                            .default(1),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(QuarantinedAttributes::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(QuarantinedAttributes::Namespace)
+                            .string()
+                            .not_null()
+                            .default("default"),
+                    )
+                    .col(
+                        ColumnDef::new(QuarantinedAttributes::Id)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(QuarantinedAttributes::RepositoryId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(QuarantinedAttributes::IndexName)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(QuarantinedAttributes::ExtractorId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(QuarantinedAttributes::ContentId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(QuarantinedAttributes::Data)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(QuarantinedAttributes::Errors)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(QuarantinedAttributes::SchemaVersion)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(QuarantinedAttributes::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        sea_query::Index::create()
+                            .col(QuarantinedAttributes::Namespace)
+                            .col(QuarantinedAttributes::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(QuarantinedAttributes::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AttributesIndex::Table)
+                    .drop_column(AttributesIndex::SchemaVersion)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Index::Table)
+                    .drop_column(Index::SchemaVersion)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum QuarantinedAttributes {
+    Table,
+    Namespace,
+    Id,
+    RepositoryId,
+    IndexName,
+    ExtractorId,
+    ContentId,
+    Data,
+    Errors,
+    SchemaVersion,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Index {
+    Table,
+    SchemaVersion,
+}
+
+#[derive(Iden)]
+enum AttributesIndex {
+    Table,
+    SchemaVersion,
+}
@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Summaries::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Summaries::Namespace)
+                            .string()
+                            .not_null()
+                            .default("default"),
+                    )
+                    .col(ColumnDef::new(Summaries::RepositoryId).string().not_null())
+                    .col(ColumnDef::new(Summaries::IndexName).string().not_null())
+                    .col(ColumnDef::new(Summaries::ContentId).string().not_null())
+                    .col(ColumnDef::new(Summaries::ExtractorId).string().not_null())
+                    .col(ColumnDef::new(Summaries::Text).text().not_null())
+                    .col(
+                        ColumnDef::new(Summaries::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        sea_query::Index::create()
+                            .col(Summaries::Namespace)
+                            .col(Summaries::RepositoryId)
+                            .col(Summaries::IndexName)
+                            .col(Summaries::ContentId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Summaries::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Summaries {
+    Table,
+    Namespace,
+    RepositoryId,
+    IndexName,
+    ContentId,
+    ExtractorId,
+    Text,
+    UpdatedAt,
+}
@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Content::Table)
+                    .add_column_if_not_exists(ColumnDef::new(Content::Hash).string())
+                    .to_owned(),
+            )
+            .await?;
+        // Content added before has its hash computed here, so that it is
+        // recognized when it is added again.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE content SET content_hash = encode(sha256(convert_to(payload, 'UTF8')), \
+                 'hex') WHERE content_hash IS NULL",
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .if_not_exists()
+                    .name("idx_content_hash")
+                    .table(Content::Table)
+                    .col(Content::Namespace)
+                    .col(Content::RepositoryId)
+                    .col(Content::Hash)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx_content_hash")
+                    .table(Content::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Content::Table)
+                    .drop_column(Content::Hash)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Content {
+    Table,
+    Namespace,
+    RepositoryId,
+    #[iden = "content_hash"]
+    Hash,
+}
@@ -2,6 +2,7 @@ import httpx
 from .repository import Repository
 from .settings import DEFAULT_SERVICE_URL
 from .extractor import Extractor
+from .utils import list_items
 
 from typing import List
 
@@ -11,9 +12,7 @@ class IndexifyClient:
         self._service_url = service_url
 
     def repositories(self) -> list[Repository]:
-        response = httpx.get(f"{self._service_url}/repositories")
-        response.raise_for_status()
-        repositories_dict = response.json()["repositories"]
+        repositories_dict = list_items(f"{self._service_url}/repositories")
         repositories = []
         for rd in repositories_dict:
             repositories.append(Repository(rd["name"], self._service_url))
@@ -34,10 +33,18 @@ class IndexifyClient:
     def get_repository(self, name: str) -> Repository:
         return Repository(name, self._service_url)
 
-    def extractors(self) -> List[Extractor]:
-        response = httpx.get(f"{self._service_url}/extractors")
+    def delete_repository(
+        self, name: str, force: bool = False, dry_run: bool = False
+    ) -> dict:
+        response = httpx.delete(
+            f"{self._service_url}/repositories/{name}",
+            params={"force": force, "dry_run": dry_run},
+        )
         response.raise_for_status()
-        extractors_dict = response.json()["extractors"]
+        return response.json()
+
+    def extractors(self) -> List[Extractor]:
+        extractors_dict = list_items(f"{self._service_url}/extractors")
         extractors = []
         for ed in extractors_dict:
             extractors.append(Extractor.from_dict(ed))
@@ -1,14 +1,12 @@
-import requests
 from dataclasses import dataclass
 from typing import Union
 
 from .settings import DEFAULT_SERVICE_URL
+from .utils import list_items
 
 
 def list_extractors(base_url: str = DEFAULT_SERVICE_URL) -> list[dict]:
-    response = requests.get(f"{base_url}/extractors")
-    response.raise_for_status()
-    return response.json()["extractors"]
+    return list_items(f"{base_url}/extractors")
 
 
 @dataclass
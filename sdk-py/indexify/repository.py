@@ -7,7 +7,7 @@ from collections import namedtuple
 from .data_containers import TextChunk
 from .settings import DEFAULT_SERVICE_URL
 from typing import List
-from .utils import json_set_default
+from .utils import json_set_default, list_items
 from indexify.exceptions import ApiException
 from .index import Index
 
@@ -107,6 +107,8 @@ class Repository:
         name: str,
         input_params: dict = {},
         filter: Filter = None,
+        chunker: dict = None,
+        parent_chunker: dict = None,
     ) -> dict:
         """Bind an extractor to this repository
 
@@ -115,6 +117,8 @@ class Repository:
             - name (str): Name for this instance
             - input_params (dict): Dictionary containing extractor input params
             - filter (Filter): Optional filter for this extractor
+            - chunker (dict): Optional chunker splitting text content before it is extracted, e.g. {"strategy": "sentence", "size": 256, "overlap": 32}
+            - parent_chunker (dict): Optional chunker splitting text content into parent chunks, which the chunker splits again
 
         Returns:
             dict: response payload
@@ -131,6 +135,10 @@ class Repository:
             "input_params": input_params,
             "filters": filter.json() if filter else [],
         }
+        if chunker:
+            req["chunker"] = chunker
+        if parent_chunker:
+            req["parent_chunker"] = parent_chunker
 
         request_body = json.dumps(req, default=json_set_default)
         response = httpx.post(
@@ -145,9 +153,7 @@ class Repository:
         return
 
     def indexes(self) -> List[Index]:
-        response = httpx.get(f"{self._service_url}/repositories/{self.name}/indexes")
-        response.raise_for_status()
-        return response.json()["indexes"]
+        return list_items(f"{self._service_url}/repositories/{self.name}/indexes")
 
     @classmethod
     def get(cls, name: str, service_url: str = DEFAULT_SERVICE_URL) -> "Repository":
@@ -179,8 +185,36 @@ class Repository:
         response.raise_for_status()
         return response.json()["attributes"]
 
-    def search_index(self, name: str, query: str, top_k: int) -> list[TextChunk]:
-        req = {"index": name, "query": query, "k": top_k}
+    def delete_content(self, content_id: str) -> int:
+        response = httpx.delete(
+            f"{self._service_url}/repositories/{self.name}/content/{content_id}"
+        )
+        response.raise_for_status()
+        return response.json()["deleted_at"]
+
+    def restore_content(self, content_id: str) -> None:
+        response = httpx.post(
+            f"{self._service_url}/repositories/{self.name}/content/{content_id}/restore"
+        )
+        response.raise_for_status()
+
+    def search_index(
+        self,
+        name: str,
+        query: str,
+        top_k: int,
+        explain: bool = False,
+        min_score: float = None,
+        max_chunks_per_content: int = None,
+        return_parent_chunks: bool = False,
+    ) -> list[TextChunk]:
+        req = {"index": name, "query": query, "k": top_k, "explain": explain}
+        if min_score is not None:
+            req["min_score"] = min_score
+        if max_chunks_per_content is not None:
+            req["max_chunks_per_content"] = max_chunks_per_content
+        if return_parent_chunks:
+            req["return_parent_chunks"] = True
         response = httpx.post(
             f"{self._service_url}/repositories/{self.name}/search",
             json=req,
@@ -189,6 +223,42 @@ class Repository:
         response.raise_for_status()
         return response.json()["results"]
 
+    def add_session_events(self, session_id: str, messages: List[str]) -> List[str]:
+        req = {
+            "events": [
+                {"text": message, "unix_timestamp": None, "metadata": {}}
+                for message in messages
+            ]
+        }
+        response = httpx.post(
+            f"{self._service_url}/repositories/{self.name}/sessions/{session_id}/events",
+            json=req,
+            headers={"Content-Type": "application/json"},
+        )
+        response.raise_for_status()
+        return response.json()["event_ids"]
+
+    def query_memory(
+        self,
+        session_id: str,
+        query: str,
+        k: int = 5,
+        recency_half_life_secs: int = None,
+        recency_weight: float = None,
+    ) -> List[dict]:
+        req = {"query": query, "k": k}
+        if recency_half_life_secs is not None:
+            req["recency_half_life_secs"] = recency_half_life_secs
+        if recency_weight is not None:
+            req["recency_weight"] = recency_weight
+        response = httpx.post(
+            f"{self._service_url}/repositories/{self.name}/sessions/{session_id}/query",
+            json=req,
+            headers={"Content-Type": "application/json"},
+        )
+        response.raise_for_status()
+        return response.json()["results"]
+
     def __repr__(self) -> str:
         return f"Repository(name={self.name})"
 
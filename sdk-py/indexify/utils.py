@@ -1,5 +1,7 @@
 from enum import Enum
 
+import httpx
+
 
 def json_set_default(obj):
     if isinstance(obj, set):
@@ -14,3 +16,17 @@ class Metric(str, Enum):
 
     def __str__(self) -> str:
         return self.name.lower()
+
+
+def list_items(url: str, **kwargs) -> list:
+    """Reads every page of a list API."""
+    items = []
+    params = {}
+    while True:
+        response = httpx.get(url, params=params, **kwargs)
+        response.raise_for_status()
+        page = response.json()
+        items.extend(page["items"])
+        if page.get("next_cursor") is None:
+            return items
+        params["cursor"] = page["next_cursor"]
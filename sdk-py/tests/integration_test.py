@@ -70,7 +70,7 @@ class TestIntegrationTest(unittest.TestCase):
             ]
         )
         time.sleep(10)
-        results = repository.search_index(f"{name}-embedding", "LLM", 1)
+        results = repository.search_index(f"{name}.embedding", "LLM", 1)
         assert len(results) == 1
 
     def test_list_extractors(self):
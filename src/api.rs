@@ -1,16 +1,168 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use axum::{
-    http::StatusCode,
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
+use base64::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, BytesOrString};
 use smart_default::SmartDefault;
 use strum::{Display, EnumString};
 use utoipa::{IntoParams, ToSchema};
 
-use crate::{persistence, vectordbs};
+use crate::{
+    attribute_query::{AttributeAggregation, AttributeSort, Metric},
+    data_repository_manager,
+    embedding_cache,
+    export,
+    filter::{Filter, Scope},
+    health,
+    memory,
+    persistence,
+    progress,
+    query_throttle,
+    text_analysis,
+    vector_index,
+    vectordbs,
+};
+
+const DEFAULT_LIST_LIMIT: u64 = 100;
+const MAX_LIST_LIMIT: u64 = 1000;
+const DEFAULT_GRAPH_MAX_HOPS: usize = 3;
+const MAX_GRAPH_MAX_HOPS: usize = 6;
+const DEFAULT_GRAPH_PATHS: usize = 10;
+const DEFAULT_ATTRIBUTE_GROUPS: u64 = 10;
+const MAX_ATTRIBUTE_GROUPS: u64 = 1000;
+
+/// Query parameters of the list APIs. `cursor` is the `next_cursor` of the
+/// previous page, the first page is returned without it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+pub struct ListParams {
+    pub cursor: Option<String>,
+    /// Items per page, 100 by default and at most 1000.
+    pub limit: Option<u64>,
+}
+
+impl ListParams {
+    pub fn limit(&self) -> u64 {
+        self.limit
+            .unwrap_or(DEFAULT_LIST_LIMIT)
+            .clamp(1, MAX_LIST_LIMIT)
+    }
+
+    /// The offset of the first item of the page, for listings paged by
+    /// offset.
+    pub fn offset(&self) -> Result<u64, IndexifyAPIError> {
+        match &self.cursor {
+            Some(cursor) => cursor.parse::<u64>().map_err(|_| {
+                IndexifyAPIError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("invalid cursor: {}", cursor),
+                )
+            }),
+            None => Ok(0),
+        }
+    }
+}
+
+/// The envelope every list API responds with. Cursors are opaque, a page has
+/// a `next_cursor` only when there are more items after it.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[aliases(
+    ListRepositoriesResponse = Page<DataRepository>,
+    ListContentResponse = Page<ContentInfo>,
+    ListIndexesResponse = Page<Index>,
+    ListExtractorsResponse = Page<ExtractorDescription>,
+    ListExecutorsResponse = Page<Executor>,
+    ListWorkResponse = Page<WorkInfo>,
+    ListEventsResponse = Page<Event>,
+    ListConnectorSyncsResponse = Page<ConnectorSync>,
+    ListIngestionJobsResponse = Page<IngestionJob>,
+    ListIngestionErrorsResponse = Page<IngestionRowError>,
+    ListApiKeysResponse = Page<ApiKey>,
+    ListRoleBindingsResponse = Page<RoleBinding>,
+    ListLegalHoldsResponse = Page<LegalHold>,
+    ListRepositoryTemplatesResponse = Page<RepositoryTemplate>,
+    ListAuditEventsResponse = Page<AuditEvent>,
+    QueryAttributesResponse = Page<ExtractedAttributes>,
+    ListQuarantinedAttributesResponse = Page<QuarantinedAttributes>
+)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    /// Items across all the pages. It can be off while items are added or
+    /// removed.
+    pub total_estimate: u64,
+}
+
+impl<T> Page<T> {
+    /// Pages through a listing which is loaded whole, the cursor is the
+    /// offset of the first item of the page.
+    pub fn from_items(items: Vec<T>, params: &ListParams) -> Result<Self, IndexifyAPIError> {
+        let offset = params.offset()? as usize;
+        let total = items.len();
+        let items: Vec<T> = items
+            .into_iter()
+            .skip(offset)
+            .take(params.limit() as usize)
+            .collect();
+        let next = offset + items.len();
+        Ok(Self {
+            items,
+            next_cursor: (next < total).then(|| next.to_string()),
+            total_estimate: total as u64,
+        })
+    }
+
+    /// A page of a listing read from the database in the order of `key`,
+    /// after the key in the cursor. `items` holds up to one item more than
+    /// the limit, which tells whether there is a next page.
+    pub fn from_keyset(
+        mut items: Vec<T>,
+        params: &ListParams,
+        total_estimate: u64,
+        key: impl Fn(&T) -> String,
+    ) -> Self {
+        let limit = params.limit() as usize;
+        let next_cursor = if items.len() > limit {
+            items.truncate(limit);
+            items.last().map(key)
+        } else {
+            None
+        };
+        Self {
+            items,
+            next_cursor,
+            total_estimate,
+        }
+    }
+
+    /// A page of a listing read from the database at the offset of the
+    /// cursor, out of `total` items.
+    pub fn from_offset(
+        items: Vec<T>,
+        params: &ListParams,
+        total: u64,
+    ) -> Result<Self, IndexifyAPIError> {
+        let next = params.offset()? + items.len() as u64;
+        Ok(Self {
+            items,
+            next_cursor: (next < total).then(|| next.to_string()),
+            total_estimate: total,
+        })
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+            total_estimate: self.total_estimate,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, EnumString, Display)]
 #[serde(rename = "extractor_filter")]
@@ -32,7 +184,227 @@ pub struct ExtractorBinding {
     pub extractor: String,
     pub name: String,
     pub filters: Option<Vec<ExtractorFilter>>,
+    /// The content the binding extracts, by its metadata, e.g.
+    /// `metadata.lang = "en" and not metadata.tags contains "draft"`. Content
+    /// has to match both `filters` and `filter`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub filter: Option<Filter>,
     pub input_params: Option<serde_json::Value>,
+    #[serde(default)]
+    pub sinks: Vec<OutputSink>,
+    /// How the text of content is split into chunks before the extractor
+    /// embeds it. Without it the extractor is given the whole content.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunker: Option<Chunker>,
+    /// How the text of content is split into parent chunks, which the
+    /// chunker splits into the chunks the extractor embeds. Searches can
+    /// return the parent chunk of the chunks they match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_chunker: Option<Chunker>,
+    /// The most work of the binding which runs at once, across executors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+    /// The most work of the binding which is started in a minute, for
+    /// extractors which call rate limited APIs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<usize>,
+    /// A paused binding creates no work until it is resumed.
+    #[serde(default)]
+    pub disabled: bool,
+    /// Compresses the vectors of the embedding indexes of the binding, when
+    /// the vector store supports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quantization: Option<Quantization>,
+    /// Fields of the metadata of content indexed in the vector store along
+    /// with the embeddings of the binding, which searches can filter by.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub payload_indexes: Vec<PayloadIndex>,
+    /// The mime types of the content the binding extracts, such as
+    /// `application/pdf` or `text/*`. Every content is extracted when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub content_types: Vec<String>,
+    /// What is done with attributes which do not match the schema of their
+    /// index.
+    #[serde(default)]
+    pub invalid_attributes: InvalidAttributesPolicy,
+}
+
+/// What is done with extracted attributes which do not match the schema the
+/// extractor declared for their index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InvalidAttributesPolicy {
+    /// The work which extracted them fails.
+    #[default]
+    Reject,
+    /// They are set aside with their violations, and listed at
+    /// `/repositories/{name}/indexes/{index}/quarantine`.
+    Quarantine,
+}
+
+impl From<InvalidAttributesPolicy> for persistence::InvalidAttributesPolicy {
+    fn from(value: InvalidAttributesPolicy) -> Self {
+        match value {
+            InvalidAttributesPolicy::Reject => persistence::InvalidAttributesPolicy::Reject,
+            InvalidAttributesPolicy::Quarantine => persistence::InvalidAttributesPolicy::Quarantine,
+        }
+    }
+}
+
+impl From<persistence::InvalidAttributesPolicy> for InvalidAttributesPolicy {
+    fn from(value: persistence::InvalidAttributesPolicy) -> Self {
+        match value {
+            persistence::InvalidAttributesPolicy::Reject => InvalidAttributesPolicy::Reject,
+            persistence::InvalidAttributesPolicy::Quarantine => InvalidAttributesPolicy::Quarantine,
+        }
+    }
+}
+
+/// Sizes and overlaps are counted in tokens, the words of the text separated
+/// by whitespace.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum Chunker {
+    /// Windows of `size` tokens.
+    FixedTokens {
+        size: usize,
+        #[serde(default)]
+        overlap: usize,
+    },
+    /// Whole sentences packed into chunks of up to `size` tokens.
+    Sentence {
+        size: usize,
+        #[serde(default)]
+        overlap: usize,
+    },
+    /// Split at the first of the separators, and at the next ones where the
+    /// pieces are still too large. Paragraphs, lines, sentences and words by
+    /// default.
+    Recursive {
+        size: usize,
+        #[serde(default)]
+        overlap: usize,
+        #[serde(default)]
+        separators: Vec<String>,
+    },
+    /// Split at the headers of markdown, every chunk starting with the
+    /// headers of its section.
+    Markdown {
+        size: usize,
+        #[serde(default)]
+        overlap: usize,
+    },
+    /// Square tiles of images, `size` pixels wide, overlapping by `overlap`
+    /// pixels. Text is not split.
+    ImageTiles {
+        size: usize,
+        #[serde(default)]
+        overlap: usize,
+    },
+}
+
+impl From<persistence::Chunker> for Chunker {
+    fn from(value: persistence::Chunker) -> Self {
+        match value {
+            persistence::Chunker::FixedTokens { size, overlap } => {
+                Chunker::FixedTokens { size, overlap }
+            }
+            persistence::Chunker::Sentence { size, overlap } => Chunker::Sentence { size, overlap },
+            persistence::Chunker::Recursive {
+                size,
+                overlap,
+                separators,
+            } => Chunker::Recursive {
+                size,
+                overlap,
+                separators,
+            },
+            persistence::Chunker::Markdown { size, overlap } => Chunker::Markdown { size, overlap },
+            persistence::Chunker::ImageTiles { size, overlap } => {
+                Chunker::ImageTiles { size, overlap }
+            }
+        }
+    }
+}
+
+impl From<Chunker> for persistence::Chunker {
+    fn from(value: Chunker) -> Self {
+        match value {
+            Chunker::FixedTokens { size, overlap } => {
+                persistence::Chunker::FixedTokens { size, overlap }
+            }
+            Chunker::Sentence { size, overlap } => persistence::Chunker::Sentence { size, overlap },
+            Chunker::Recursive {
+                size,
+                overlap,
+                separators,
+            } => persistence::Chunker::Recursive {
+                size,
+                overlap,
+                separators,
+            },
+            Chunker::Markdown { size, overlap } => persistence::Chunker::Markdown { size, overlap },
+            Chunker::ImageTiles { size, overlap } => {
+                persistence::Chunker::ImageTiles { size, overlap }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename = "output_sink")]
+pub enum OutputSink {
+    #[serde(rename = "webhook")]
+    Webhook { url: String },
+    #[serde(rename = "kafka")]
+    Kafka { brokers: String, topic: String },
+    #[serde(rename = "s3")]
+    S3 {
+        bucket: String,
+        region: String,
+        prefix: String,
+    },
+}
+
+impl From<persistence::OutputSink> for OutputSink {
+    fn from(value: persistence::OutputSink) -> Self {
+        match value {
+            persistence::OutputSink::Webhook { url } => OutputSink::Webhook { url },
+            persistence::OutputSink::Kafka { brokers, topic } => {
+                OutputSink::Kafka { brokers, topic }
+            }
+            persistence::OutputSink::S3 {
+                bucket,
+                region,
+                prefix,
+            } => OutputSink::S3 {
+                bucket,
+                region,
+                prefix,
+            },
+        }
+    }
+}
+
+impl From<OutputSink> for persistence::OutputSink {
+    fn from(value: OutputSink) -> Self {
+        match value {
+            OutputSink::Webhook { url } => persistence::OutputSink::Webhook { url },
+            OutputSink::Kafka { brokers, topic } => {
+                persistence::OutputSink::Kafka { brokers, topic }
+            }
+            OutputSink::S3 {
+                bucket,
+                region,
+                prefix,
+            } => persistence::OutputSink::S3 {
+                bucket,
+                region,
+                prefix,
+            },
+        }
+    }
 }
 
 impl From<persistence::ExtractorBinding> for ExtractorBinding {
@@ -64,17 +436,31 @@ impl From<persistence::ExtractorBinding> for ExtractorBinding {
             name: value.name,
             extractor: value.extractor,
             filters: Some(filters),
+            filter: value.filter,
             input_params: Some(value.input_params),
+            sinks: value.sinks.into_iter().map(|s| s.into()).collect(),
+            chunker: value.chunker.map(|c| c.into()),
+            parent_chunker: value.parent_chunker.map(|c| c.into()),
+            max_concurrency: value.max_concurrency,
+            requests_per_minute: value.requests_per_minute,
+            disabled: value.disabled,
+            quantization: value.quantization.map(|q| q.into()),
+            payload_indexes: value
+                .payload_indexes
+                .into_iter()
+                .map(|p| p.into())
+                .collect(),
+            content_types: value.content_types,
+            invalid_attributes: value.invalid_attributes.into(),
         }
     }
 }
 
-pub fn into_persistence_extractor_binding(
-    repository: &str,
-    extractor_binding: ExtractorBinding,
-) -> persistence::ExtractorBinding {
+pub fn into_persistence_filters(
+    filters: Vec<ExtractorFilter>,
+) -> Vec<persistence::ExtractorFilter> {
     let mut extraction_filters = vec![];
-    for filter in extractor_binding.filters.unwrap_or_default() {
+    for filter in filters {
         match filter {
             ExtractorFilter::Eq { filters } => {
                 for (field, value) in filters {
@@ -88,6 +474,15 @@ pub fn into_persistence_extractor_binding(
             }
         }
     }
+    extraction_filters
+}
+
+pub fn into_persistence_extractor_binding(
+    repository: &str,
+    extractor_binding: ExtractorBinding,
+) -> persistence::ExtractorBinding {
+    let extraction_filters =
+        into_persistence_filters(extractor_binding.filters.unwrap_or_default());
     persistence::ExtractorBinding::new(
         &extractor_binding.name,
         repository,
@@ -97,13 +492,240 @@ pub fn into_persistence_extractor_binding(
             .input_params
             .unwrap_or(serde_json::json!({})),
     )
+    .with_sinks(
+        extractor_binding
+            .sinks
+            .into_iter()
+            .map(|s| s.into())
+            .collect(),
+    )
+    .with_chunker(extractor_binding.chunker.map(|c| c.into()))
+    .with_parent_chunker(extractor_binding.parent_chunker.map(|c| c.into()))
+    .with_limits(
+        extractor_binding.max_concurrency,
+        extractor_binding.requests_per_minute,
+    )
+    .with_disabled(extractor_binding.disabled)
+    .with_quantization(extractor_binding.quantization.map(|q| q.into()))
+    .with_payload_indexes(
+        extractor_binding
+            .payload_indexes
+            .into_iter()
+            .map(|p| p.into())
+            .collect(),
+    )
+    .with_content_types(extractor_binding.content_types)
+    .with_invalid_attributes(extractor_binding.invalid_attributes.into())
+    .with_filter(extractor_binding.filter)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebCrawlerConfig {
+    pub seed_urls: Vec<String>,
+    pub max_depth: Option<u32>,
+    pub max_pages: Option<usize>,
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+    pub respect_robots_txt: Option<bool>,
+    pub recrawl_interval_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct KafkaSourceConfig {
+    pub brokers: String,
+    pub topic: String,
+    pub group_id: Option<String>,
+    pub text_field: Option<String>,
+    pub metadata_field: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename = "source_type")]
+pub enum SourceType {
+    #[serde(rename = "google_contact")]
+    GoogleContact { metadata: Option<String> },
+    #[serde(rename = "gmail")]
+    Gmail { metadata: Option<String> },
+    #[serde(rename = "web_crawler")]
+    WebCrawler(WebCrawlerConfig),
+    #[serde(rename = "kafka")]
+    Kafka(KafkaSourceConfig),
+}
+
+/// When a data connector is synced. Cron expressions have a seconds field and
+/// are evaluated in UTC.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub enum SyncSchedule {
+    #[serde(rename = "interval")]
+    Interval { secs: u64 },
+    #[serde(rename = "cron")]
+    Cron { expression: String },
+}
+
+impl From<persistence::SyncSchedule> for SyncSchedule {
+    fn from(value: persistence::SyncSchedule) -> Self {
+        match value {
+            persistence::SyncSchedule::Interval { secs } => SyncSchedule::Interval { secs },
+            persistence::SyncSchedule::Cron { expression } => SyncSchedule::Cron { expression },
+        }
+    }
+}
+
+impl From<SyncSchedule> for persistence::SyncSchedule {
+    fn from(value: SyncSchedule) -> Self {
+        match value {
+            SyncSchedule::Interval { secs } => persistence::SyncSchedule::Interval { secs },
+            SyncSchedule::Cron { expression } => persistence::SyncSchedule::Cron { expression },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DataConnector {
+    pub source: SourceType,
+    #[serde(default)]
+    pub schedule: Option<SyncSchedule>,
+}
+
+impl From<persistence::DataConnector> for DataConnector {
+    fn from(value: persistence::DataConnector) -> Self {
+        let source = match value.source {
+            persistence::SourceType::GoogleContact { metadata } => {
+                SourceType::GoogleContact { metadata }
+            }
+            persistence::SourceType::Gmail { metadata } => SourceType::Gmail { metadata },
+            persistence::SourceType::WebCrawler(config) => {
+                SourceType::WebCrawler(WebCrawlerConfig {
+                    seed_urls: config.seed_urls,
+                    max_depth: Some(config.max_depth),
+                    max_pages: Some(config.max_pages),
+                    allowed_domains: config.allowed_domains,
+                    respect_robots_txt: Some(config.respect_robots_txt),
+                    recrawl_interval_secs: config.recrawl_interval_secs,
+                })
+            }
+            persistence::SourceType::Kafka(config) => SourceType::Kafka(KafkaSourceConfig {
+                brokers: config.brokers,
+                topic: config.topic,
+                group_id: Some(config.group_id),
+                text_field: Some(config.text_field),
+                metadata_field: Some(config.metadata_field),
+            }),
+        };
+        Self {
+            source,
+            schedule: value.schedule.map(|s| s.into()),
+        }
+    }
+}
+
+impl From<DataConnector> for persistence::DataConnector {
+    fn from(value: DataConnector) -> Self {
+        let source = match value.source {
+            SourceType::GoogleContact { metadata } => {
+                persistence::SourceType::GoogleContact { metadata }
+            }
+            SourceType::Gmail { metadata } => persistence::SourceType::Gmail { metadata },
+            SourceType::WebCrawler(config) => {
+                let mut persisted = persistence::WebCrawlerConfig::new(config.seed_urls);
+                if let Some(max_depth) = config.max_depth {
+                    persisted.max_depth = max_depth;
+                }
+                if let Some(max_pages) = config.max_pages {
+                    persisted.max_pages = max_pages;
+                }
+                if let Some(respect_robots_txt) = config.respect_robots_txt {
+                    persisted.respect_robots_txt = respect_robots_txt;
+                }
+                persisted.allowed_domains = config.allowed_domains;
+                persisted.recrawl_interval_secs = config.recrawl_interval_secs;
+                persistence::SourceType::WebCrawler(persisted)
+            }
+            SourceType::Kafka(config) => {
+                let mut persisted =
+                    persistence::KafkaSourceConfig::new(&config.brokers, &config.topic);
+                if let Some(group_id) = config.group_id {
+                    persisted.group_id = group_id;
+                }
+                if let Some(text_field) = config.text_field {
+                    persisted.text_field = text_field;
+                }
+                if let Some(metadata_field) = config.metadata_field {
+                    persisted.metadata_field = metadata_field;
+                }
+                persistence::SourceType::Kafka(persisted)
+            }
+        };
+        Self {
+            source,
+            schedule: value.schedule.map(|s| s.into()),
+        }
+    }
+}
+
+/// Stop words are removed and synonyms are expanded by the keyword index,
+/// both when content is indexed and when it is queried.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TextAnalysisConfig {
+    #[serde(default)]
+    pub stop_words: Vec<String>,
+    #[serde(default)]
+    pub synonyms: HashMap<String, Vec<String>>,
+    /// The language the terms are stemmed in, such as `english`. `simple`,
+    /// the default, only lowercases them.
+    #[serde(default = "default_text_search_language")]
+    pub language: String,
+}
+
+fn default_text_search_language() -> String {
+    text_analysis::DEFAULT_TEXT_SEARCH_LANGUAGE.into()
+}
+
+impl Default for TextAnalysisConfig {
+    fn default() -> Self {
+        persistence::TextAnalysisConfig::default().into()
+    }
+}
+
+impl From<persistence::TextAnalysisConfig> for TextAnalysisConfig {
+    fn from(value: persistence::TextAnalysisConfig) -> Self {
+        Self {
+            stop_words: value.stop_words,
+            synonyms: value.synonyms,
+            language: value.language,
+        }
+    }
+}
+
+impl From<TextAnalysisConfig> for persistence::TextAnalysisConfig {
+    fn from(value: TextAnalysisConfig) -> Self {
+        Self {
+            stop_words: value.stop_words,
+            synonyms: value.synonyms,
+            language: value.language,
+        }
+    }
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct DataRepository {
+    #[serde(default)]
+    pub namespace: String,
     pub name: String,
     pub extractor_bindings: Vec<ExtractorBinding>,
+    #[serde(default)]
+    pub data_connectors: Vec<DataConnector>,
     pub metadata: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub dedup_policy: DedupPolicy,
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+    #[serde(default)]
+    pub text_analysis: TextAnalysisConfig,
+    /// Incremented by every update of the repository. Updates sent with the
+    /// version in an `If-Match` header fail if the repository changed since.
+    #[serde(default)]
+    pub version: i64,
 }
 
 impl From<persistence::DataRepository> for DataRepository {
@@ -114,9 +736,19 @@ impl From<persistence::DataRepository> for DataRepository {
             .map(|e| e.into())
             .collect();
         DataRepository {
+            namespace: value.namespace,
             name: value.name,
             extractor_bindings: ap_extractors,
+            data_connectors: value
+                .data_connectors
+                .into_iter()
+                .map(|c| c.into())
+                .collect(),
             metadata: value.metadata,
+            dedup_policy: value.dedup_policy.into(),
+            retention: value.retention.into(),
+            text_analysis: value.text_analysis.into(),
+            version: value.version,
         }
     }
 }
@@ -125,275 +757,2810 @@ impl From<persistence::DataRepository> for DataRepository {
 pub struct CreateRepository {
     pub name: String,
     pub extractor_bindings: Vec<ExtractorBinding>,
+    #[serde(default)]
+    pub data_connectors: Vec<DataConnector>,
     pub metadata: HashMap<String, serde_json::Value>,
+    /// What happens to content added with the id of stored content.
+    #[serde(default)]
+    pub dedup_policy: DedupPolicy,
+    /// How long content is kept, forever by default.
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+    #[serde(default)]
+    pub text_analysis: TextAnalysisConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
-pub struct CreateRepositoryResponse {}
+pub struct CreateRepositoryResponse {
+    /// The version of the repository after the update.
+    pub version: i64,
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GetRepositoryResponse {
+/// The extractor bindings and settings which repositories created from the
+/// template start with.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RepositoryTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub extractor_bindings: Vec<ExtractorBinding>,
+    #[serde(default)]
+    pub data_connectors: Vec<DataConnector>,
+    #[serde(default)]
+    pub metadata: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub dedup_policy: DedupPolicy,
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+    #[serde(default)]
+    pub text_analysis: TextAnalysisConfig,
+    /// When the template was created or last replaced, set by the server.
+    #[serde(default)]
+    pub created_at: u64,
+}
+
+impl From<RepositoryTemplate> for persistence::RepositoryTemplate {
+    fn from(value: RepositoryTemplate) -> Self {
+        Self {
+            // The bindings are given their repository when a repository is
+            // created from the template.
+            extractor_bindings: value
+                .extractor_bindings
+                .into_iter()
+                .map(|e| into_persistence_extractor_binding("", e))
+                .collect(),
+            data_connectors: value
+                .data_connectors
+                .into_iter()
+                .map(|c| c.into())
+                .collect(),
+            metadata: value.metadata,
+            dedup_policy: value.dedup_policy.into(),
+            retention: value.retention.into(),
+            text_analysis: value.text_analysis.into(),
+            name: value.name,
+            description: value.description,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<persistence::RepositoryTemplate> for RepositoryTemplate {
+    fn from(value: persistence::RepositoryTemplate) -> Self {
+        Self {
+            name: value.name,
+            description: value.description,
+            extractor_bindings: value
+                .extractor_bindings
+                .into_iter()
+                .map(|e| e.into())
+                .collect(),
+            data_connectors: value
+                .data_connectors
+                .into_iter()
+                .map(|c| c.into())
+                .collect(),
+            metadata: value.metadata,
+            dedup_policy: value.dedup_policy.into(),
+            retention: value.retention.into(),
+            text_analysis: value.text_analysis.into(),
+            created_at: value.created_at,
+        }
+    }
+}
+
+/// A repository created with the bindings and settings of a template.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateRepositoryFromTemplate {
+    pub name: String,
+    /// Added to the metadata of the template.
+    #[serde(default)]
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+/// The body of the conflict returned when a repository was updated by
+/// another request since the version in the `If-Match` header.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RepositoryVersionConflict {
+    pub message: String,
     pub repository: DataRepository,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
-pub struct ListRepositoriesResponse {
-    pub repositories: Vec<DataRepository>,
+pub struct TextAnalysisResponse {
+    pub text_analysis: TextAnalysisConfig,
 }
 
-#[derive(Display, Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
-#[serde(rename = "distance")]
-pub enum IndexDistance {
-    #[serde(rename = "dot")]
-    #[strum(serialize = "dot")]
+/// Query parameters of the deletion of a repository.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+pub struct DeleteRepositoryParams {
+    /// Deletes a repository which holds content or events.
+    #[serde(default)]
+    pub force: bool,
+    /// Reports what would be removed without removing it.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// The file format of an index export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
     #[default]
-    Dot,
+    Parquet,
+    /// The Arrow IPC streaming format.
+    Arrow,
+}
 
-    #[serde(rename = "cosine")]
-    #[strum(serialize = "cosine")]
-    Cosine,
+impl From<ExportFormat> for export::ExportFormat {
+    fn from(value: ExportFormat) -> Self {
+        match value {
+            ExportFormat::Parquet => export::ExportFormat::Parquet,
+            ExportFormat::Arrow => export::ExportFormat::Arrow,
+        }
+    }
+}
 
-    #[serde(rename = "euclidean")]
-    #[strum(serialize = "euclidean")]
-    Euclidean,
+/// Query parameters of the export of an index.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+pub struct ExportIndexParams {
+    #[serde(default)]
+    pub format: ExportFormat,
 }
 
-impl From<IndexDistance> for vectordbs::IndexDistance {
-    fn from(value: IndexDistance) -> Self {
-        match value {
-            IndexDistance::Dot => vectordbs::IndexDistance::Dot,
-            IndexDistance::Cosine => vectordbs::IndexDistance::Cosine,
-            IndexDistance::Euclidean => vectordbs::IndexDistance::Euclidean,
+/// What was removed along with a repository, or would be on a dry run.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeleteRepositoryResponse {
+    pub dry_run: bool,
+    pub content: u64,
+    pub chunks: u64,
+    pub attributes: u64,
+    pub events: u64,
+    /// Work which was not done yet and was cancelled.
+    pub work: u64,
+    pub indexes: Vec<String>,
+    pub vector_collections: Vec<String>,
+}
+
+impl DeleteRepositoryResponse {
+    pub fn new(deletion: persistence::RepositoryDeletion, dry_run: bool) -> Self {
+        Self {
+            dry_run,
+            content: deletion.content,
+            chunks: deletion.chunks,
+            attributes: deletion.attributes,
+            events: deletion.events,
+            work: deletion.work,
+            indexes: deletion.indexes,
+            vector_collections: deletion.vector_collections,
+        }
+    }
+}
+
+/// When content was soft deleted. It can be restored until the grace period
+/// of the retention configuration is over.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeleteContentResponse {
+    pub deleted_at: u64,
+}
+
+/// Content fetched again from its source url.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RefetchContentResponse {
+    /// The id of the content, which is the id of the content which replaced
+    /// it when it changed.
+    pub content_id: String,
+    pub changed: bool,
+}
+
+impl From<data_repository_manager::RefetchedContent> for RefetchContentResponse {
+    fn from(value: data_repository_manager::RefetchedContent) -> Self {
+        Self {
+            content_id: value.content_id,
+            changed: value.changed,
+        }
+    }
+}
+
+/// A transition of the extraction of a content, sent as the data of a
+/// server-sent event named after `event`. The extractor binding, extractor
+/// and work are set on the transitions of the work of an extractor binding.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ContentProgressEvent {
+    /// One of `created`, `scheduled`, `started`, `completed`, `failed` and
+    /// `done`.
+    pub event: String,
+    pub content_id: String,
+    pub extractor_binding: Option<String>,
+    pub extractor: Option<String>,
+    pub work_id: Option<String>,
+}
+
+impl From<progress::ProgressEvent> for ContentProgressEvent {
+    fn from(event: progress::ProgressEvent) -> Self {
+        Self {
+            event: event.kind.to_string(),
+            content_id: event.content_id,
+            extractor_binding: event.extractor_binding,
+            extractor: event.extractor,
+            work_id: event.work_id,
+        }
+    }
+}
+
+/// How long the content of a repository is kept. Content older than
+/// `max_age_secs`, or beyond the `max_items_per_source` most recent content of
+/// its source, is deleted unless it is under a legal hold.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RetentionPolicy {
+    #[serde(default)]
+    pub max_age_secs: Option<u64>,
+    #[serde(default)]
+    pub max_items_per_source: Option<u64>,
+}
+
+impl From<RetentionPolicy> for persistence::RetentionPolicy {
+    fn from(value: RetentionPolicy) -> Self {
+        Self {
+            max_age_secs: value.max_age_secs,
+            max_items_per_source: value.max_items_per_source,
+        }
+    }
+}
+
+impl From<persistence::RetentionPolicy> for RetentionPolicy {
+    fn from(value: persistence::RetentionPolicy) -> Self {
+        Self {
+            max_age_secs: value.max_age_secs,
+            max_items_per_source: value.max_items_per_source,
+        }
+    }
+}
+
+/// What happens to content added to a repository with the id of content it
+/// already stores. Ids are hashes of the repository and the text or file name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupPolicy {
+    /// The content is dropped, along with its metadata.
+    #[default]
+    Dedupe,
+    /// Every content added gets a unique id.
+    AllowDuplicates,
+    /// The metadata of the content is merged into the stored metadata.
+    UpsertMetadata,
+}
+
+impl From<DedupPolicy> for persistence::DedupPolicy {
+    fn from(value: DedupPolicy) -> Self {
+        match value {
+            DedupPolicy::Dedupe => persistence::DedupPolicy::Dedupe,
+            DedupPolicy::AllowDuplicates => persistence::DedupPolicy::AllowDuplicates,
+            DedupPolicy::UpsertMetadata => persistence::DedupPolicy::UpsertMetadata,
+        }
+    }
+}
+
+impl From<persistence::DedupPolicy> for DedupPolicy {
+    fn from(value: persistence::DedupPolicy) -> Self {
+        match value {
+            persistence::DedupPolicy::Dedupe => DedupPolicy::Dedupe,
+            persistence::DedupPolicy::AllowDuplicates => DedupPolicy::AllowDuplicates,
+            persistence::DedupPolicy::UpsertMetadata => DedupPolicy::UpsertMetadata,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetRepositoryResponse {
+    pub repository: DataRepository,
+}
+
+#[derive(Display, Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
+#[serde(rename = "distance")]
+pub enum IndexDistance {
+    #[serde(rename = "dot")]
+    #[strum(serialize = "dot")]
+    #[default]
+    Dot,
+
+    #[serde(rename = "cosine")]
+    #[strum(serialize = "cosine")]
+    Cosine,
+
+    #[serde(rename = "euclidean")]
+    #[strum(serialize = "euclidean")]
+    Euclidean,
+}
+
+impl From<IndexDistance> for vectordbs::IndexDistance {
+    fn from(value: IndexDistance) -> Self {
+        match value {
+            IndexDistance::Dot => vectordbs::IndexDistance::Dot,
+            IndexDistance::Cosine => vectordbs::IndexDistance::Cosine,
+            IndexDistance::Euclidean => vectordbs::IndexDistance::Euclidean,
+        }
+    }
+}
+
+impl From<vectordbs::IndexDistance> for IndexDistance {
+    fn from(val: vectordbs::IndexDistance) -> Self {
+        match val {
+            vectordbs::IndexDistance::Dot => IndexDistance::Dot,
+            vectordbs::IndexDistance::Cosine => IndexDistance::Cosine,
+            vectordbs::IndexDistance::Euclidean => IndexDistance::Euclidean,
+        }
+    }
+}
+
+/// How the vectors of an index are compressed. Quantized indexes take less
+/// memory, so that large indexes stay in memory and are searched faster, at
+/// the cost of some recall.
+#[derive(Display, Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Quantization {
+    /// Every dimension as an 8 bit integer, a quarter of the memory, with
+    /// little loss of recall.
+    Scalar,
+    /// Groups of dimensions as the code of their nearest centroid, a
+    /// sixteenth of the memory, with a noticeable loss of recall.
+    Product,
+}
+
+impl From<Quantization> for vectordbs::Quantization {
+    fn from(value: Quantization) -> Self {
+        match value {
+            Quantization::Scalar => vectordbs::Quantization::Scalar,
+            Quantization::Product => vectordbs::Quantization::Product,
+        }
+    }
+}
+
+impl From<vectordbs::Quantization> for Quantization {
+    fn from(value: vectordbs::Quantization) -> Self {
+        match value {
+            vectordbs::Quantization::Scalar => Quantization::Scalar,
+            vectordbs::Quantization::Product => Quantization::Product,
+        }
+    }
+}
+
+/// What an embedding extractor embeds, text and images for extractors such as
+/// CLIP which embed both into the same space.
+#[derive(Display, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Modality {
+    Text,
+    Image,
+}
+
+fn default_modalities() -> Vec<Modality> {
+    vec![Modality::Text]
+}
+
+impl From<persistence::Modality> for Modality {
+    fn from(value: persistence::Modality) -> Self {
+        match value {
+            persistence::Modality::Text => Modality::Text,
+            persistence::Modality::Image => Modality::Image,
+        }
+    }
+}
+
+/// The type of a field of content metadata with a payload index.
+#[derive(Display, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum PayloadFieldType {
+    Keyword,
+    Integer,
+    Float,
+}
+
+/// A field of the metadata of content indexed in the vector store, so that
+/// searches filtered by it stay fast.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct PayloadIndex {
+    pub field: String,
+    #[serde(rename = "type")]
+    pub field_type: PayloadFieldType,
+}
+
+impl From<PayloadIndex> for vectordbs::PayloadIndex {
+    fn from(value: PayloadIndex) -> Self {
+        let field_type = match value.field_type {
+            PayloadFieldType::Keyword => vectordbs::PayloadFieldType::Keyword,
+            PayloadFieldType::Integer => vectordbs::PayloadFieldType::Integer,
+            PayloadFieldType::Float => vectordbs::PayloadFieldType::Float,
+        };
+        Self {
+            field: value.field,
+            field_type,
+        }
+    }
+}
+
+impl From<vectordbs::PayloadIndex> for PayloadIndex {
+    fn from(value: vectordbs::PayloadIndex) -> Self {
+        let field_type = match value.field_type {
+            vectordbs::PayloadFieldType::Keyword => PayloadFieldType::Keyword,
+            vectordbs::PayloadFieldType::Integer => PayloadFieldType::Integer,
+            vectordbs::PayloadFieldType::Float => PayloadFieldType::Float,
+        };
+        Self {
+            field: value.field,
+            field_type,
+        }
+    }
+}
+
+/// What the quantization of an index trades for its smaller size.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct QuantizationTradeoff {
+    pub quantization: Quantization,
+    /// How many times less memory the quantized vectors take.
+    pub compression: u32,
+    /// The effect on the recall and the latency of searches.
+    pub description: String,
+}
+
+impl From<Quantization> for QuantizationTradeoff {
+    fn from(quantization: Quantization) -> Self {
+        let (compression, description) = match quantization {
+            Quantization::Scalar => (
+                4,
+                "Recall drops by about 1%. Searches of indexes which no longer fit in memory \
+                 unquantized are several times faster.",
+            ),
+            Quantization::Product => (
+                16,
+                "Recall drops by 5-10% depending on the model, unless more candidates are \
+                 reranked. Searches are faster for the largest indexes, but slower to build.",
+            ),
+        };
+        Self {
+            quantization,
+            compression,
+            description: description.into(),
+        }
+    }
+}
+
+/// Request payload for creating a new vector index.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ExtractorBindRequest {
+    #[serde(flatten)]
+    pub extractor_binding: ExtractorBinding,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
+pub struct ExtractorBindResponse {
+    #[serde(default)]
+    pub index_names: Vec<String>,
+    /// The content the binding would extract, when it was posted as a dry
+    /// run and not added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<BindingPreview>,
+}
+
+/// Query parameters of adding an extractor binding.
+#[derive(Debug, Default, Serialize, Deserialize, IntoParams)]
+pub struct ExtractorBindParams {
+    /// Validate the binding and preview the content it would extract, without
+    /// adding it.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// How many of the content the binding would extract are returned in the
+    /// preview. Defaults to 10.
+    pub sample_size: Option<u64>,
+}
+
+/// Query parameters of searching the entities of a graph index.
+#[derive(Debug, Default, Serialize, Deserialize, IntoParams)]
+pub struct GraphEntitiesParams {
+    /// Part of the name of the entities, ignoring case.
+    #[serde(default)]
+    pub query: String,
+    pub entity_type: Option<String>,
+    /// Entities returned, 100 by default and at most 1000.
+    pub limit: Option<u64>,
+}
+
+impl GraphEntitiesParams {
+    pub fn limit(&self) -> u64 {
+        self.limit
+            .unwrap_or(DEFAULT_LIST_LIMIT)
+            .clamp(1, MAX_LIST_LIMIT)
+    }
+}
+
+/// Query parameters of the neighbors of an entity of a graph index.
+#[derive(Debug, Default, Serialize, Deserialize, IntoParams)]
+pub struct GraphNeighborsParams {
+    /// The name of the entity, ignoring case.
+    pub entity: String,
+    /// Only the relationships with this relation.
+    pub relation: Option<String>,
+    /// Relationships returned, 100 by default and at most 1000.
+    pub limit: Option<u64>,
+}
+
+impl GraphNeighborsParams {
+    pub fn limit(&self) -> u64 {
+        self.limit
+            .unwrap_or(DEFAULT_LIST_LIMIT)
+            .clamp(1, MAX_LIST_LIMIT)
+    }
+}
+
+/// Query parameters of the paths between two entities of a graph index.
+#[derive(Debug, Default, Serialize, Deserialize, IntoParams)]
+pub struct GraphPathsParams {
+    pub from: String,
+    pub to: String,
+    /// The most relationships on a path, 3 by default and at most 6.
+    pub max_hops: Option<usize>,
+    /// Paths returned, 10 by default and at most 1000.
+    pub limit: Option<usize>,
+}
+
+impl GraphPathsParams {
+    pub fn max_hops(&self) -> usize {
+        self.max_hops
+            .unwrap_or(DEFAULT_GRAPH_MAX_HOPS)
+            .clamp(1, MAX_GRAPH_MAX_HOPS)
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+            .unwrap_or(DEFAULT_GRAPH_PATHS)
+            .clamp(1, MAX_LIST_LIMIT as usize)
+    }
+}
+
+/// An entity of a graph index, merged from all the content it was
+/// extracted from.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GraphEntity {
+    pub id: String,
+    pub name: String,
+    pub entity_type: Option<String>,
+    pub properties: Option<serde_json::Value>,
+    /// How many content the entity was extracted from.
+    pub mentions: u64,
+}
+
+impl From<persistence::GraphNode> for GraphEntity {
+    fn from(value: persistence::GraphNode) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            entity_type: value.entity_type,
+            properties: value.properties,
+            mentions: value.mentions as u64,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GraphRelationship {
+    pub source: String,
+    pub relation: String,
+    pub target: String,
+    /// The content the relationship was extracted from.
+    pub content_ids: Vec<String>,
+}
+
+impl From<persistence::GraphEdge> for GraphRelationship {
+    fn from(value: persistence::GraphEdge) -> Self {
+        Self {
+            source: value.source_id,
+            relation: value.relation,
+            target: value.target_id,
+            content_ids: value.content_ids,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GraphEntitiesResponse {
+    pub entities: Vec<GraphEntity>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GraphNeighbor {
+    pub relationship: GraphRelationship,
+    pub entity: GraphEntity,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GraphNeighborsResponse {
+    pub entity: GraphEntity,
+    pub neighbors: Vec<GraphNeighbor>,
+}
+
+/// The shortest paths between two entities, as the relationships from the
+/// first entity, and the entities on them.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GraphPathsResponse {
+    pub entities: Vec<GraphEntity>,
+    pub paths: Vec<Vec<GraphRelationship>>,
+}
+
+/// The content an extractor binding would extract if it were added.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BindingPreview {
+    /// How much content matches the filters of the binding.
+    pub matching_content: u64,
+    /// The first of the matching content, in the order of their ids.
+    pub sample: Vec<MatchedContent>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MatchedContent {
+    pub id: String,
+    pub content_type: String,
+    pub source: String,
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+impl From<persistence::BindingPreview> for BindingPreview {
+    fn from(value: persistence::BindingPreview) -> Self {
+        Self {
+            matching_content: value.matching_content,
+            sample: value
+                .sample
+                .into_iter()
+                .map(|c| MatchedContent {
+                    id: c.id,
+                    content_type: c.content_type.to_string(),
+                    source: c.source,
+                    metadata: c.metadata,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Text {
+    pub text: String,
+    #[serde(default)]
+    pub metadata: HashMap<String, serde_json::Value>,
+    /// The url the text was fetched from.
+    #[serde(default)]
+    pub source_url: Option<String>,
+    /// The id of the text in the system it came from.
+    #[serde(default)]
+    pub external_id: Option<String>,
+    /// When the text was fetched, in seconds since the epoch.
+    #[serde(default)]
+    pub fetched_at: Option<u64>,
+}
+
+impl Text {
+    pub fn content_payload(&self, repository: &str) -> persistence::ContentPayload {
+        persistence::ContentPayload::from_text(repository, &self.text, self.metadata.clone())
+            .with_provenance(persistence::Provenance {
+                source_connector: None,
+                external_id: self.external_id.clone(),
+                source_url: self.source_url.clone(),
+                fetched_at: self.fetched_at,
+            })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TextAddRequest {
+    pub documents: Vec<Text>,
+    pub sync: Option<bool>,
+    /// Run the bindings whose extractor is allowed to run inline before
+    /// responding, so the texts are searchable once the response is received.
+    #[serde(default)]
+    pub inline: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkItemStatus {
+    Added,
+    /// The line is not a valid document.
+    Invalid,
+    /// The batch the document was written in failed.
+    Failed,
+}
+
+/// The outcome of one line of a bulk request.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BulkItemResult {
+    /// The line of the request, starting at 1.
+    pub line: usize,
+    pub status: BulkItemStatus,
+    pub content_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BulkAddTextsResponse {
+    pub added: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RunExtractorsResponse {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum ExtractorOutputSchema {
+    #[serde(rename = "embedding")]
+    Embedding {
+        dim: usize,
+        distance: IndexDistance,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        quantization: Option<Quantization>,
+        /// Every chunk is embedded as several vectors, searched by late
+        /// interaction.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        multi_vector: bool,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        payload_indexes: Vec<PayloadIndex>,
+        /// The content the extractor embeds, and the queries the index can be
+        /// searched with.
+        #[serde(default = "default_modalities")]
+        modalities: Vec<Modality>,
+    },
+    #[serde(rename = "attributes")]
+    Attributes { schema: serde_json::Value },
+    #[serde(rename = "graph")]
+    Graph {
+        entity_types: Vec<String>,
+        relation_types: Vec<String>,
+    },
+    /// A summary of every content. Summaries have no schema, so this is last
+    /// to be tried when the untagged outputs are read.
+    #[serde(rename = "summary")]
+    Summary {},
+}
+
+impl From<persistence::ExtractorOutputSchema> for ExtractorOutputSchema {
+    fn from(value: persistence::ExtractorOutputSchema) -> Self {
+        match value {
+            persistence::ExtractorOutputSchema::Embedding(schema) => {
+                ExtractorOutputSchema::Embedding {
+                    dim: schema.dim,
+                    distance: schema.distance.into(),
+                    quantization: schema.quantization.map(|q| q.into()),
+                    multi_vector: schema.multi_vector,
+                    payload_indexes: schema
+                        .payload_indexes
+                        .into_iter()
+                        .map(|p| p.into())
+                        .collect(),
+                    modalities: schema.modalities.into_iter().map(|m| m.into()).collect(),
+                }
+            }
+            persistence::ExtractorOutputSchema::Attributes(schema) => {
+                ExtractorOutputSchema::Attributes {
+                    schema: schema.schema,
+                }
+            }
+            persistence::ExtractorOutputSchema::Graph(schema) => ExtractorOutputSchema::Graph {
+                entity_types: schema.entity_types,
+                relation_types: schema.relation_types,
+            },
+            persistence::ExtractorOutputSchema::Summary(_) => ExtractorOutputSchema::Summary {},
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractorSchema {
+    pub outputs: HashMap<String, ExtractorOutputSchema>,
+}
+
+impl From<persistence::ExtractorSchema> for ExtractorSchema {
+    fn from(value: persistence::ExtractorSchema) -> Self {
+        Self {
+            outputs: value
+                .outputs
+                .into_iter()
+                .map(|(k, v)| (k, v.into()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExtractorDescription {
+    pub name: String,
+    pub description: String,
+    pub input_params: serde_json::Value,
+    pub schemas: ExtractorSchema,
+}
+
+impl From<persistence::Extractor> for ExtractorDescription {
+    fn from(value: persistence::Extractor) -> Self {
+        Self {
+            name: value.name,
+            description: value.description,
+            input_params: value.input_params,
+            schemas: value.schemas.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Executor {
+    pub id: String,
+    pub extractors: Vec<ExtractorDescription>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InlineExtractionStatus {
+    /// The output of the binding was indexed before the response.
+    Indexed,
+    /// The binding did not finish inline and is run by the executors.
+    Queued,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct InlineExtraction {
+    pub content_id: String,
+    pub extractor_binding: String,
+    pub status: InlineExtractionStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<data_repository_manager::InlineExtraction> for InlineExtraction {
+    fn from(value: data_repository_manager::InlineExtraction) -> Self {
+        let status = match value.error {
+            None => InlineExtractionStatus::Indexed,
+            Some(_) => InlineExtractionStatus::Queued,
+        };
+        Self {
+            content_id: value.content_id,
+            extractor_binding: value.extractor_binding,
+            status,
+            error: value.error,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
+pub struct TextAdditionResponse {
+    pub content_ids: Vec<String>,
+    /// The bindings run on the texts, when they were added inline.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inline_extractions: Vec<InlineExtraction>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Index {
+    pub name: String,
+    pub schema: ExtractorOutputSchema,
+    /// Incremented whenever the schema of an attribute index is extended.
+    pub schema_version: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reranker: Option<RerankerConfig>,
+    /// The binding whose extractor writes the index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extractor_binding: Option<String>,
+    /// The output of the extractor the index holds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// The trade-off of the quantization of an embedding index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantization: Option<QuantizationTradeoff>,
+}
+
+impl From<persistence::Index> for Index {
+    fn from(value: persistence::Index) -> Self {
+        let quantization = match &value.schema {
+            persistence::ExtractorOutputSchema::Embedding(schema) => schema
+                .quantization
+                .clone()
+                .map(|q| Quantization::from(q).into()),
+            _ => None,
+        };
+        Self {
+            name: value.name,
+            quantization,
+            schema: value.schema.into(),
+            schema_version: value.schema_version,
+            reranker: value.reranker.map(|r| r.into()),
+            extractor_binding: value.extractor_binding,
+            output: value.output,
+        }
+    }
+}
+
+/// What reranks the candidates of a search.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RerankerBackend {
+    /// A cross-encoder served by an extractor. It is given every candidate as
+    /// text with the query in the `query` input param, and returns a number
+    /// as the feature of the content it extracts.
+    Extractor { name: String },
+    /// A reranking service which is posted `{"query": .., "documents": [..]}`
+    /// and responds with `{"scores": [..]}` in the order of the documents.
+    Http { url: String },
+}
+
+impl From<persistence::RerankerBackend> for RerankerBackend {
+    fn from(value: persistence::RerankerBackend) -> Self {
+        match value {
+            persistence::RerankerBackend::Extractor { name } => Self::Extractor { name },
+            persistence::RerankerBackend::Http { url } => Self::Http { url },
+        }
+    }
+}
+
+impl From<RerankerBackend> for persistence::RerankerBackend {
+    fn from(value: RerankerBackend) -> Self {
+        match value {
+            RerankerBackend::Extractor { name } => Self::Extractor { name },
+            RerankerBackend::Http { url } => Self::Http { url },
+        }
+    }
+}
+
+fn default_rerank_candidates() -> u64 {
+    50
+}
+
+/// Reranks the candidates retrieved from the vector store of an index before
+/// the top k of them are returned by a search.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RerankerConfig {
+    /// Candidates retrieved from the vector store, 50 by default. Searches
+    /// for more results than this rerank as many candidates as results.
+    #[serde(default = "default_rerank_candidates")]
+    pub candidates: u64,
+    pub backend: RerankerBackend,
+}
+
+impl From<persistence::RerankerConfig> for RerankerConfig {
+    fn from(value: persistence::RerankerConfig) -> Self {
+        Self {
+            candidates: value.candidates,
+            backend: value.backend.into(),
+        }
+    }
+}
+
+impl From<RerankerConfig> for persistence::RerankerConfig {
+    fn from(value: RerankerConfig) -> Self {
+        Self {
+            candidates: value.candidates,
+            backend: value.backend.into(),
+        }
+    }
+}
+
+/// Sets the reranker of an index, or removes it when `reranker` is `null`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateRerankerRequest {
+    pub reranker: Option<RerankerConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateRerankerResponse {
+    pub index: String,
+    pub reranker: Option<RerankerConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReindexResponse {
+    pub index: String,
+    pub generation: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WarmIndexResponse {
+    pub index: String,
+    /// The generation of the index which was warmed up.
+    pub generation: i64,
+    pub vectors: u64,
+    /// Whether the extractor of the index embedded a query, which loads its
+    /// model on an executor.
+    pub extractor_warmed: bool,
+    pub elapsed_ms: u64,
+}
+
+impl From<vector_index::IndexWarmup> for WarmIndexResponse {
+    fn from(value: vector_index::IndexWarmup) -> Self {
+        Self {
+            index: value.snapshot.index_name,
+            generation: value.snapshot.generation,
+            vectors: value.vectors,
+            extractor_warmed: value.extractor_warmed,
+            elapsed_ms: value.elapsed.as_millis() as u64,
+        }
+    }
+}
+
+/// Restricts a search to content whose attributes in `index` match all the
+/// `filters` and the `query`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AttributeFilter {
+    pub index: String,
+    #[serde(default)]
+    pub filters: Vec<ExtractorFilter>,
+    /// Comparisons on the attributes, as in attribute queries, e.g.
+    /// `attributes.price > 100`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub query: Option<Filter>,
+}
+
+impl AttributeFilter {
+    pub fn prefilter(
+        &self,
+    ) -> Result<data_repository_manager::AttributePrefilter, IndexifyAPIError> {
+        if let Some(query) = &self.query {
+            query.check_scope(Scope::Attributes).map_err(|e| {
+                IndexifyAPIError::new(StatusCode::BAD_REQUEST, format!("invalid query: {}", e))
+            })?;
+        }
+        let filters = into_persistence_filters(self.filters.clone());
+        Ok(data_repository_manager::AttributePrefilter {
+            index: self.index.clone(),
+            filter: Filter::all(
+                filters
+                    .iter()
+                    .map(|filter| filter.to_filter(Scope::Attributes))
+                    .chain(self.query.clone()),
+            ),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams, ToSchema)]
+pub struct SearchRequest {
+    /// The embedding index to search, `{binding}.{output}`.
+    pub index: String,
+    /// The text searched for. Either a query or an image is given.
+    #[serde(default)]
+    pub query: String,
+    /// The image searched for, in indexes whose extractor embeds images.
+    #[serde(default)]
+    pub image: Option<ImageQuery>,
+    pub k: Option<u64>,
+    #[serde(default)]
+    pub attribute_filter: Option<AttributeFilter>,
+    /// Return with every result how it was scored and which index served it.
+    #[serde(default)]
+    pub explain: bool,
+    /// Attributes indexes whose attributes of the content of every result are
+    /// returned with it.
+    #[serde(default)]
+    pub include_attributes: Vec<String>,
+    /// Diversify the results across content instead of returning the nearest
+    /// chunks, which are often chunks of the same content.
+    #[serde(default)]
+    pub mmr: Option<MmrOptions>,
+    /// Drop results scored lower than this.
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    /// Return at most this many chunks of the same content, the best scored
+    /// ones, so that one long document does not fill the results.
+    #[serde(default)]
+    pub max_chunks_per_content: Option<usize>,
+    /// Return the parent chunks of the chunks which matched, for bindings
+    /// with a parent chunker, instead of the chunks themselves.
+    #[serde(default)]
+    pub return_parent_chunks: bool,
+    /// Only return chunks of content whose metadata has these values. The
+    /// fields need a payload index in the index searched.
+    #[serde(default)]
+    pub metadata_filter: HashMap<String, serde_json::Value>,
+    /// Only return chunks of content whose metadata matches, e.g.
+    /// `metadata.lang = "en" and metadata.source.kind = "web"`. The vector
+    /// store only matches fields with `=` joined by `and`, and the fields need
+    /// a payload index in the index searched.
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub filter: Option<Filter>,
+    /// Return the summaries of the content of the results from a summary
+    /// index, alongside the chunks or instead of them.
+    #[serde(default)]
+    pub summary: Option<SummaryOptions>,
+}
+
+impl SearchRequest {
+    /// The fields of the metadata and their values which chunks are matched
+    /// by, from `metadata_filter` and `filter`.
+    pub fn metadata_filter(&self) -> Result<HashMap<String, serde_json::Value>, IndexifyAPIError> {
+        let mut metadata_filter = self.metadata_filter.clone();
+        let Some(filter) = &self.filter else {
+            return Ok(metadata_filter);
+        };
+        let invalid_filter =
+            |e| IndexifyAPIError::new(StatusCode::BAD_REQUEST, format!("invalid filter: {}", e));
+        for (field, value) in filter.payload_matches().map_err(invalid_filter)? {
+            match metadata_filter.get(&field) {
+                Some(other) if *other != value => {
+                    return Err(invalid_filter(format!(
+                        "field {} can not be both {} and {}",
+                        field, other, value
+                    )))
+                }
+                _ => {
+                    metadata_filter.insert(field, value);
+                }
+            }
+        }
+        Ok(metadata_filter)
+    }
+
+    pub fn result_limits(&self) -> Result<data_repository_manager::ResultLimits, IndexifyAPIError> {
+        if self.max_chunks_per_content == Some(0) {
+            return Err(IndexifyAPIError::new(
+                StatusCode::BAD_REQUEST,
+                "max_chunks_per_content has to be at least 1".to_string(),
+            ));
+        }
+        Ok(data_repository_manager::ResultLimits {
+            min_score: self.min_score,
+            max_chunks_per_content: self.max_chunks_per_content,
+            parent_chunks: self.return_parent_chunks,
+        })
+    }
+
+    pub fn search_query(&self) -> Result<vector_index::SearchQuery, IndexifyAPIError> {
+        match (&self.image, self.query.is_empty()) {
+            (None, false) => Ok(vector_index::SearchQuery::Text(self.query.clone())),
+            (Some(image), true) => {
+                let data = BASE64_STANDARD.decode(&image.data).map_err(|e| {
+                    IndexifyAPIError::new(
+                        StatusCode::BAD_REQUEST,
+                        format!("the image is not valid base64: {}", e),
+                    )
+                })?;
+                Ok(vector_index::SearchQuery::Image {
+                    content_type: image.content_type.clone(),
+                    data,
+                })
+            }
+            _ => Err(IndexifyAPIError::new(
+                StatusCode::BAD_REQUEST,
+                "either a query or an image has to be searched for".to_string(),
+            )),
+        }
+    }
+}
+
+/// How the summaries of the content of search results are returned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryMode {
+    /// Every chunk is returned with the summary of its content.
+    #[default]
+    Alongside,
+    /// The chunks of summarized content are replaced by one result with the
+    /// summary, scored like its best chunk. Content which has no summary is
+    /// returned as chunks.
+    Instead,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SummaryOptions {
+    /// The summary index, `{binding}.{output}`.
+    pub index: String,
+    #[serde(default)]
+    pub mode: SummaryMode,
+}
+
+impl SummaryOptions {
+    /// Adds the summaries, by content id, to the results, which are ordered
+    /// best first.
+    pub fn apply(
+        &self,
+        document_fragments: Vec<DocumentFragment>,
+        summaries: &HashMap<String, String>,
+    ) -> Vec<DocumentFragment> {
+        let mut summarized = HashSet::new();
+        let mut results = Vec::with_capacity(document_fragments.len());
+        for mut fragment in document_fragments {
+            let Some(summary) = summaries.get(&fragment.content_id) else {
+                results.push(fragment);
+                continue;
+            };
+            fragment.summary = Some(summary.clone());
+            if self.mode == SummaryMode::Instead {
+                if !summarized.insert(fragment.content_id.clone()) {
+                    continue;
+                }
+                fragment.text = summary.clone();
+                fragment.offsets = None;
+                fragment.region = None;
+            }
+            results.push(fragment);
+        }
+        results
+    }
+}
+
+/// An image searched for.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImageQuery {
+    /// Such as `image/png`.
+    pub content_type: String,
+    /// The bytes of the image, encoded in base64.
+    pub data: String,
+}
+
+fn default_mmr_lambda() -> f32 {
+    0.5
+}
+
+/// Maximal marginal relevance: the results are picked one at a time out of
+/// the `fetch_k` nearest chunks, trading the relevance of a chunk off against
+/// picking another chunk of content which was already picked.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MmrOptions {
+    /// Between 0 and 1, 0.5 by default. 1 ranks by relevance only, lower
+    /// values spread the results over more content.
+    #[serde(default = "default_mmr_lambda")]
+    pub lambda: f32,
+    /// Candidates the results are picked out of, 4 times `k` by default.
+    pub fetch_k: Option<u64>,
+}
+
+impl MmrOptions {
+    pub fn diversification(
+        &self,
+        k: u64,
+    ) -> Result<data_repository_manager::Diversification, IndexifyAPIError> {
+        if !(0.0..=1.0).contains(&self.lambda) {
+            return Err(IndexifyAPIError::new(
+                StatusCode::BAD_REQUEST,
+                format!("lambda has to be between 0 and 1, got {}", self.lambda),
+            ));
+        }
+        Ok(data_repository_manager::Diversification {
+            lambda: self.lambda,
+            fetch_k: self.fetch_k.unwrap_or(k * 4),
+        })
+    }
+}
+
+/// Searches the chunks of an index for the terms of the query, for exact
+/// terms such as error codes or names where embeddings fall short.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct KeywordSearchRequest {
+    pub index: String,
+    pub query: String,
+    pub k: Option<u64>,
+    /// Return with every result its keyword score and which index served it.
+    #[serde(default)]
+    pub explain: bool,
+    /// Attributes indexes whose attributes of the content of every result are
+    /// returned with it.
+    #[serde(default)]
+    pub include_attributes: Vec<String>,
+    /// Return the summaries of the content of the results from a summary
+    /// index, alongside the chunks or instead of them.
+    #[serde(default)]
+    pub summary: Option<SummaryOptions>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExtractedAttributes {
+    pub id: String,
+    pub content_id: String,
+    pub attributes: serde_json::Value,
+    pub extractor_name: String,
+    /// The version of the schema of the index the attributes were validated
+    /// against.
+    #[serde(default = "default_schema_version")]
+    pub schema_version: i32,
+}
+
+fn default_schema_version() -> i32 {
+    1
+}
+
+impl From<persistence::ExtractedAttributes> for ExtractedAttributes {
+    fn from(value: persistence::ExtractedAttributes) -> Self {
+        Self {
+            id: value.id,
+            content_id: value.content_id,
+            attributes: value.attributes,
+            extractor_name: value.extractor_name,
+            schema_version: value.schema_version,
+        }
+    }
+}
+
+impl From<ExtractedAttributes> for persistence::ExtractedAttributes {
+    fn from(value: ExtractedAttributes) -> Self {
+        Self {
+            id: value.id,
+            content_id: value.content_id,
+            attributes: value.attributes,
+            extractor_name: value.extractor_name,
+            schema_version: value.schema_version,
+        }
+    }
+}
+
+/// Extracted attributes set aside because they do not match the schema of
+/// their index.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QuarantinedAttributes {
+    pub attributes: ExtractedAttributes,
+    /// How the attributes violate the schema.
+    pub errors: Vec<String>,
+    pub quarantined_at: u64,
+}
+
+impl From<persistence::QuarantinedAttributes> for QuarantinedAttributes {
+    fn from(value: persistence::QuarantinedAttributes) -> Self {
+        Self {
+            attributes: value.attributes.into(),
+            errors: value.errors,
+            quarantined_at: value.quarantined_at,
+        }
+    }
+}
+
+/// How the payload of replicated content is encoded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadEncoding {
+    Text,
+    Base64,
+}
+
+/// An object of a repository which was added or updated, as read from its
+/// change feed.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReplicatedChange {
+    Content {
+        id: String,
+        content_type: String,
+        payload: String,
+        encoding: PayloadEncoding,
+        metadata: HashMap<String, serde_json::Value>,
+        source: String,
+        #[serde(default)]
+        provenance: persistence::Provenance,
+    },
+    Chunk {
+        index: String,
+        chunk_id: String,
+        content_id: String,
+        text: String,
+        #[serde(default)]
+        parent_id: Option<String>,
+        #[serde(default)]
+        is_parent: bool,
+        #[serde(default)]
+        offsets: Option<persistence::ChunkOffsets>,
+        #[serde(default)]
+        region: Option<persistence::ImageRegion>,
+    },
+    Attributes {
+        index: String,
+        attributes: ExtractedAttributes,
+    },
+}
+
+impl From<data_repository_manager::RepositoryChange> for ReplicatedChange {
+    fn from(value: data_repository_manager::RepositoryChange) -> Self {
+        match value {
+            data_repository_manager::RepositoryChange::Content(payload) => Self::Content {
+                id: payload.id,
+                content_type: payload.content_type.to_string(),
+                encoding: match payload.payload_type {
+                    persistence::PayloadType::InlineBlob => PayloadEncoding::Base64,
+                    _ => PayloadEncoding::Text,
+                },
+                payload: payload.payload,
+                metadata: payload.metadata,
+                source: payload.source,
+                provenance: payload.provenance,
+            },
+            data_repository_manager::RepositoryChange::Chunk { index, chunk } => Self::Chunk {
+                index,
+                chunk_id: chunk.chunk_id,
+                content_id: chunk.content_id,
+                text: chunk.text,
+                parent_id: chunk.parent_id,
+                is_parent: chunk.is_parent,
+                offsets: chunk.offsets,
+                region: chunk.region,
+            },
+            data_repository_manager::RepositoryChange::Attributes { index, attributes } => {
+                Self::Attributes {
+                    index,
+                    attributes: attributes.into(),
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<ReplicatedChange> for data_repository_manager::RepositoryChange {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ReplicatedChange) -> Result<Self, Self::Error> {
+        let change = match value {
+            ReplicatedChange::Content {
+                id,
+                content_type,
+                payload,
+                encoding,
+                metadata,
+                source,
+                provenance,
+            } => Self::Content(persistence::ContentPayload {
+                id,
+                content_type: content_type.parse()?,
+                payload,
+                payload_type: match encoding {
+                    PayloadEncoding::Text => persistence::PayloadType::EmbeddedStorage,
+                    PayloadEncoding::Base64 => persistence::PayloadType::InlineBlob,
+                },
+                metadata,
+                source,
+                extractor_bindings_state: HashMap::new(),
+                parent_content_id: None,
+                produced_by_binding: None,
+                provenance,
+            }),
+            ReplicatedChange::Chunk {
+                index,
+                chunk_id,
+                content_id,
+                text,
+                parent_id,
+                is_parent,
+                offsets,
+                region,
+            } => Self::Chunk {
+                index,
+                chunk: persistence::Chunk {
+                    text,
+                    chunk_id,
+                    content_id,
+                    parent_id,
+                    is_parent,
+                    offsets,
+                    region,
+                },
+            },
+            ReplicatedChange::Attributes { index, attributes } => Self::Attributes {
+                index,
+                attributes: attributes.into(),
+            },
+        };
+        Ok(change)
+    }
+}
+
+/// Changes of a repository in the order they were made. `cursor` is passed
+/// back to read the changes made after these, even when there are none yet.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChangeFeedResponse {
+    pub changes: Vec<ReplicatedChange>,
+    pub cursor: String,
+    pub has_more: bool,
+}
+
+impl From<data_repository_manager::ChangeBatch> for ChangeFeedResponse {
+    fn from(value: data_repository_manager::ChangeBatch) -> Self {
+        Self {
+            changes: value.changes.into_iter().map(|c| c.into()).collect(),
+            cursor: value.cursor.to_string(),
+            has_more: value.has_more,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams, ToSchema)]
+pub struct AttributeLookupRequest {
+    pub content_id: Option<String>,
+    pub index: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AttributeLookupResponse {
+    pub attributes: Vec<ExtractedAttributes>,
+}
+
+/// Query parameters of the attribute query.
+#[derive(Debug, Default, Serialize, Deserialize, IntoParams)]
+pub struct AttributeQueryParams {
+    pub index: String,
+    /// Comparisons on the attributes, e.g. `attributes.price > 100 and
+    /// attributes.tags contains "legal"`. All the attributes of the index
+    /// match when it is missing.
+    pub query: Option<String>,
+    /// The attribute to order by, e.g. `attributes.price desc`. Ordered by
+    /// id when it is missing.
+    pub sort: Option<String>,
+}
+
+fn parse_attribute_query(query: Option<&str>) -> Result<Option<Filter>, IndexifyAPIError> {
+    query
+        .map(|query| Filter::parse_in(query, Scope::Attributes))
+        .transpose()
+        .map_err(|e| {
+            IndexifyAPIError::new(StatusCode::BAD_REQUEST, format!("invalid query: {}", e))
+        })
+}
+
+impl AttributeQueryParams {
+    pub fn query(&self) -> Result<Option<Filter>, IndexifyAPIError> {
+        parse_attribute_query(self.query.as_deref())
+    }
+
+    pub fn sort(&self) -> Result<Option<AttributeSort>, IndexifyAPIError> {
+        self.sort
+            .as_deref()
+            .map(AttributeSort::parse)
+            .transpose()
+            .map_err(|e| {
+                IndexifyAPIError::new(StatusCode::BAD_REQUEST, format!("invalid sort: {}", e))
+            })
+    }
+}
+
+/// Query parameters of the attribute aggregation.
+#[derive(Debug, Default, Serialize, Deserialize, IntoParams)]
+pub struct AttributeAggregationParams {
+    pub index: String,
+    /// Comparisons on the attributes, as in attribute queries. All the
+    /// attributes of the index are aggregated when it is missing.
+    pub query: Option<String>,
+    /// The attribute to group by, e.g. `attributes.language`. An attribute
+    /// which is an array is counted in the group of every element. All the
+    /// attributes are in one group when it is missing.
+    pub group_by: Option<String>,
+    /// `min`, `max`, `avg` or `sum` of numeric attributes, separated by
+    /// commas, e.g. `avg(attributes.price), max(attributes.price)`.
+    pub metrics: Option<String>,
+    /// The most groups returned, the largest first, 10 by default.
+    pub limit: Option<u64>,
+}
+
+impl AttributeAggregationParams {
+    pub fn query(&self) -> Result<Option<Filter>, IndexifyAPIError> {
+        parse_attribute_query(self.query.as_deref())
+    }
+
+    pub fn aggregation(&self) -> Result<AttributeAggregation, IndexifyAPIError> {
+        let group_by = self
+            .group_by
+            .as_deref()
+            .map(AttributeAggregation::parse_group_by)
+            .transpose()
+            .map_err(|e| {
+                IndexifyAPIError::new(StatusCode::BAD_REQUEST, format!("invalid group_by: {}", e))
+            })?;
+        let metrics =
+            Metric::parse_list(self.metrics.as_deref().unwrap_or_default()).map_err(|e| {
+                IndexifyAPIError::new(StatusCode::BAD_REQUEST, format!("invalid metrics: {}", e))
+            })?;
+        Ok(AttributeAggregation {
+            group_by,
+            metrics,
+            limit: self
+                .limit
+                .unwrap_or(DEFAULT_ATTRIBUTE_GROUPS)
+                .clamp(1, MAX_ATTRIBUTE_GROUPS),
+        })
+    }
+}
+
+/// A group of attributes, with the value of the attribute they are grouped
+/// by, and their metrics by name. A metric is null when no attribute of the
+/// group has a number for it.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AttributeGroup {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<serde_json::Value>,
+    pub count: u64,
+    pub metrics: HashMap<String, Option<f64>>,
+}
+
+impl From<persistence::AttributeGroup> for AttributeGroup {
+    fn from(value: persistence::AttributeGroup) -> Self {
+        Self {
+            key: value.key,
+            count: value.count,
+            metrics: value.metrics,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AggregateAttributesResponse {
+    pub index: String,
+    /// Attributes which match the query.
+    pub total: u64,
+    pub groups: Vec<AttributeGroup>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
+pub struct Event {
+    text: String,
+    unix_timestamp: Option<u64>,
+    metadata: HashMap<String, serde_json::Value>,
+}
+
+impl From<Event> for persistence::Event {
+    fn from(value: Event) -> Self {
+        persistence::Event::new(&value.text, value.unix_timestamp, value.metadata)
+    }
+}
+
+impl From<persistence::Event> for Event {
+    fn from(value: persistence::Event) -> Self {
+        Self {
+            text: value.message,
+            unix_timestamp: Some(value.unix_timestamp),
+            metadata: value.metadata,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EventAddRequest {
+    pub events: Vec<Event>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EventAddResponse {
+    pub event_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AddSessionEventsResponse {
+    pub event_ids: Vec<String>,
+}
+
+/// The events of a session which have not been summarized, oldest first.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ListSessionEventsResponse {
+    pub events: Vec<Event>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct QueryMemoryRequest {
+    pub query: String,
+    pub k: Option<u64>,
+    /// The age at which the recency of an event halves,
+    /// `memory.recency_half_life_secs` by default.
+    #[serde(default)]
+    pub recency_half_life_secs: Option<u64>,
+    /// Between 0 and 1, the weight of the recency of an event against its
+    /// similarity to the query, `memory.recency_weight` by default. 0 ranks
+    /// events by their similarity only.
+    #[serde(default)]
+    pub recency_weight: Option<f32>,
+}
+
+impl QueryMemoryRequest {
+    /// The ranking of the query, the fields which are not set are taken
+    /// from `default`.
+    pub fn ranking(
+        &self,
+        default: memory::RecencyRanking,
+    ) -> Result<memory::RecencyRanking, IndexifyAPIError> {
+        if self.recency_half_life_secs == Some(0) {
+            return Err(IndexifyAPIError::new(
+                StatusCode::BAD_REQUEST,
+                "recency_half_life_secs must be greater than 0".into(),
+            ));
+        }
+        if self
+            .recency_weight
+            .is_some_and(|w| !(0.0..=1.0).contains(&w))
+        {
+            return Err(IndexifyAPIError::new(
+                StatusCode::BAD_REQUEST,
+                "recency_weight must be between 0 and 1".into(),
+            ));
+        }
+        Ok(memory::RecencyRanking {
+            half_life_secs: self
+                .recency_half_life_secs
+                .unwrap_or(default.half_life_secs),
+            weight: self.recency_weight.unwrap_or(default.weight),
+        })
+    }
+}
+
+/// An event of a session, scored by its similarity to the query and its
+/// recency.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MemoryResult {
+    pub id: String,
+    pub text: String,
+    pub unix_timestamp: u64,
+    pub metadata: HashMap<String, serde_json::Value>,
+    pub score: f32,
+    pub similarity: f32,
+    pub recency: f32,
+}
+
+impl From<memory::MemoryHit> for MemoryResult {
+    fn from(value: memory::MemoryHit) -> Self {
+        Self {
+            id: value.event.id,
+            text: value.event.message,
+            unix_timestamp: value.event.unix_timestamp,
+            metadata: value.event.metadata,
+            score: value.score,
+            similarity: value.similarity,
+            recency: value.recency,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct QueryMemoryResponse {
+    pub results: Vec<MemoryResult>,
+}
+
+/// A file attached to the events of an ingestion request.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct IngestFile {
+    pub name: String,
+    /// The bytes of the file, encoded in base64.
+    pub data: String,
+    #[serde(default)]
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+/// Events and the content related to them, such as a chat turn and the file
+/// attached to it, which are added in one transaction.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct IngestRequest {
+    /// Added to the metadata of the events and the content as `session_id`.
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Added to the metadata of the events and the content, unless they set
+    /// the same keys.
+    #[serde(default)]
+    pub metadata: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub events: Vec<Event>,
+    #[serde(default)]
+    pub documents: Vec<Text>,
+    #[serde(default)]
+    pub files: Vec<IngestFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct IngestResponse {
+    pub event_ids: Vec<String>,
+    pub content_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyScope {
+    Read,
+    Ingest,
+    Admin,
+}
+
+impl From<persistence::ApiKeyScope> for ApiKeyScope {
+    fn from(value: persistence::ApiKeyScope) -> Self {
+        match value {
+            persistence::ApiKeyScope::Read => ApiKeyScope::Read,
+            persistence::ApiKeyScope::Ingest => ApiKeyScope::Ingest,
+            persistence::ApiKeyScope::Admin => ApiKeyScope::Admin,
+        }
+    }
+}
+
+impl From<ApiKeyScope> for persistence::ApiKeyScope {
+    fn from(value: ApiKeyScope) -> Self {
+        match value {
+            ApiKeyScope::Read => persistence::ApiKeyScope::Read,
+            ApiKeyScope::Ingest => persistence::ApiKeyScope::Ingest,
+            ApiKeyScope::Admin => persistence::ApiKeyScope::Admin,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scope: ApiKeyScope,
+    /// Repositories the key is limited to, every repository when empty.
+    #[serde(default)]
+    pub repositories: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateApiKeyResponse {
+    pub id: String,
+    /// The key to send in the `x-api-key` header. It is not stored by the
+    /// server and can not be retrieved again.
+    pub key: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    pub scope: ApiKeyScope,
+    pub repositories: Vec<String>,
+    pub created_at: u64,
+}
+
+impl From<persistence::ApiKey> for ApiKey {
+    fn from(value: persistence::ApiKey) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            scope: value.scope.into(),
+            repositories: value.repositories,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RepositoryRole {
+    Reader,
+    Writer,
+    Admin,
+}
+
+impl From<persistence::RepositoryRole> for RepositoryRole {
+    fn from(value: persistence::RepositoryRole) -> Self {
+        match value {
+            persistence::RepositoryRole::Reader => RepositoryRole::Reader,
+            persistence::RepositoryRole::Writer => RepositoryRole::Writer,
+            persistence::RepositoryRole::Admin => RepositoryRole::Admin,
+        }
+    }
+}
+
+impl From<RepositoryRole> for persistence::RepositoryRole {
+    fn from(value: RepositoryRole) -> Self {
+        match value {
+            RepositoryRole::Reader => persistence::RepositoryRole::Reader,
+            RepositoryRole::Writer => persistence::RepositoryRole::Writer,
+            RepositoryRole::Admin => persistence::RepositoryRole::Admin,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GrantRoleRequest {
+    pub role: RepositoryRole,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RoleBinding {
+    /// The id of the api key the role is granted to.
+    pub principal: String,
+    pub role: RepositoryRole,
+    pub created_at: u64,
+}
+
+impl From<persistence::RoleBinding> for RoleBinding {
+    fn from(value: persistence::RoleBinding) -> Self {
+        Self {
+            principal: value.principal,
+            role: value.role.into(),
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PlaceLegalHoldRequest {
+    /// The content to hold, all the content of the repository when not set.
+    #[serde(default)]
+    pub content_id: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LegalHold {
+    pub id: String,
+    pub content_id: Option<String>,
+    pub reason: String,
+    pub created_at: u64,
+}
+
+impl From<persistence::LegalHold> for LegalHold {
+    fn from(value: persistence::LegalHold) -> Self {
+        Self {
+            id: value.id,
+            content_id: value.content_id,
+            reason: value.reason,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    RepositoryUpsert,
+    RepositoryDelete,
+    ExtractorBindingChange,
+    ContentAdd,
+    ContentDelete,
+    ContentRestore,
+    IndexDrop,
+    IndexReindex,
+    ExtractorRun,
+    TextAnalysisUpdate,
+    RetentionUpdate,
+    IndexRerankerUpdate,
+    RoleGrant,
+    RoleRevoke,
+    ApiKeyCreate,
+    ApiKeyDelete,
+    LegalHoldPlace,
+    LegalHoldRelease,
+    ConfigChange,
+    Other,
+}
+
+impl From<persistence::AuditAction> for AuditAction {
+    fn from(value: persistence::AuditAction) -> Self {
+        match value {
+            persistence::AuditAction::RepositoryUpsert => AuditAction::RepositoryUpsert,
+            persistence::AuditAction::RepositoryDelete => AuditAction::RepositoryDelete,
+            persistence::AuditAction::ExtractorBindingChange => AuditAction::ExtractorBindingChange,
+            persistence::AuditAction::ContentAdd => AuditAction::ContentAdd,
+            persistence::AuditAction::ContentDelete => AuditAction::ContentDelete,
+            persistence::AuditAction::ContentRestore => AuditAction::ContentRestore,
+            persistence::AuditAction::IndexDrop => AuditAction::IndexDrop,
+            persistence::AuditAction::IndexReindex => AuditAction::IndexReindex,
+            persistence::AuditAction::ExtractorRun => AuditAction::ExtractorRun,
+            persistence::AuditAction::TextAnalysisUpdate => AuditAction::TextAnalysisUpdate,
+            persistence::AuditAction::RetentionUpdate => AuditAction::RetentionUpdate,
+            persistence::AuditAction::IndexRerankerUpdate => AuditAction::IndexRerankerUpdate,
+            persistence::AuditAction::RoleGrant => AuditAction::RoleGrant,
+            persistence::AuditAction::RoleRevoke => AuditAction::RoleRevoke,
+            persistence::AuditAction::ApiKeyCreate => AuditAction::ApiKeyCreate,
+            persistence::AuditAction::ApiKeyDelete => AuditAction::ApiKeyDelete,
+            persistence::AuditAction::LegalHoldPlace => AuditAction::LegalHoldPlace,
+            persistence::AuditAction::LegalHoldRelease => AuditAction::LegalHoldRelease,
+            persistence::AuditAction::ConfigChange => AuditAction::ConfigChange,
+            persistence::AuditAction::Other => AuditAction::Other,
+        }
+    }
+}
+
+impl From<AuditAction> for persistence::AuditAction {
+    fn from(value: AuditAction) -> Self {
+        match value {
+            AuditAction::RepositoryUpsert => persistence::AuditAction::RepositoryUpsert,
+            AuditAction::RepositoryDelete => persistence::AuditAction::RepositoryDelete,
+            AuditAction::ExtractorBindingChange => persistence::AuditAction::ExtractorBindingChange,
+            AuditAction::ContentAdd => persistence::AuditAction::ContentAdd,
+            AuditAction::ContentDelete => persistence::AuditAction::ContentDelete,
+            AuditAction::ContentRestore => persistence::AuditAction::ContentRestore,
+            AuditAction::IndexDrop => persistence::AuditAction::IndexDrop,
+            AuditAction::IndexReindex => persistence::AuditAction::IndexReindex,
+            AuditAction::ExtractorRun => persistence::AuditAction::ExtractorRun,
+            AuditAction::TextAnalysisUpdate => persistence::AuditAction::TextAnalysisUpdate,
+            AuditAction::RetentionUpdate => persistence::AuditAction::RetentionUpdate,
+            AuditAction::IndexRerankerUpdate => persistence::AuditAction::IndexRerankerUpdate,
+            AuditAction::RoleGrant => persistence::AuditAction::RoleGrant,
+            AuditAction::RoleRevoke => persistence::AuditAction::RoleRevoke,
+            AuditAction::ApiKeyCreate => persistence::AuditAction::ApiKeyCreate,
+            AuditAction::ApiKeyDelete => persistence::AuditAction::ApiKeyDelete,
+            AuditAction::LegalHoldPlace => persistence::AuditAction::LegalHoldPlace,
+            AuditAction::LegalHoldRelease => persistence::AuditAction::LegalHoldRelease,
+            AuditAction::ConfigChange => persistence::AuditAction::ConfigChange,
+            AuditAction::Other => persistence::AuditAction::Other,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AuditEvent {
+    /// Position of the event in the audit log.
+    pub seq: u64,
+    /// The id of the api key the request was made with, or `anonymous` when
+    /// authentication is disabled.
+    pub principal: String,
+    pub action: AuditAction,
+    pub repository: Option<String>,
+    pub method: String,
+    pub path: String,
+    /// The status the request was answered with.
+    pub status: u16,
+    pub created_at: u64,
+}
+
+impl From<persistence::AuditEvent> for AuditEvent {
+    fn from(value: persistence::AuditEvent) -> Self {
+        Self {
+            seq: value.seq,
+            principal: value.principal,
+            action: value.action.into(),
+            repository: value.repository,
+            method: value.method,
+            path: value.path,
+            status: value.status,
+            created_at: value.created_at,
+        }
+    }
+}
+
+/// Filters of the audit log, every filter which is set has to match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+pub struct AuditLogParams {
+    pub repository: Option<String>,
+    pub principal: Option<String>,
+    pub action: Option<AuditAction>,
+}
+
+impl From<AuditLogParams> for persistence::AuditLogFilter {
+    fn from(value: AuditLogParams) -> Self {
+        Self {
+            repository: value.repository,
+            principal: value.principal,
+            action: value.action.map(|a| a.into()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UploadArchiveResponse {
+    pub content_ids: Vec<String>,
+}
+
+/// Query parameters of the content listing.
+#[derive(Debug, Default, Serialize, Deserialize, IntoParams)]
+pub struct ContentFilterParams {
+    /// Filters on the metadata of the content, as a JSON list of extractor
+    /// filters, e.g. `[{"eq": {"author": "alice"}}]`.
+    pub filters: Option<String>,
+    /// A filter on the metadata of the content, e.g. `metadata.author =
+    /// "alice" or metadata.pages > 10`. Content has to match both `filters`
+    /// and `filter`.
+    pub filter: Option<String>,
+    /// The id of the connector which fetched the content.
+    pub source_connector: Option<String>,
+    pub external_id: Option<String>,
+    pub source_url: Option<String>,
+    /// Content fetched at or after this time, in seconds since the epoch.
+    pub fetched_after: Option<u64>,
+    /// Content fetched before this time, in seconds since the epoch.
+    pub fetched_before: Option<u64>,
+}
+
+impl ContentFilterParams {
+    /// The filter of the content matching both `filters` and `filter`.
+    pub fn filter(&self) -> Result<Option<Filter>, IndexifyAPIError> {
+        let filters: Vec<ExtractorFilter> = match &self.filters {
+            Some(filters) => serde_json::from_str(filters).map_err(|e| {
+                IndexifyAPIError::new(StatusCode::BAD_REQUEST, format!("invalid filters: {}", e))
+            })?,
+            None => vec![],
+        };
+        let filter = self
+            .filter
+            .as_deref()
+            .map(|filter| Filter::parse_in(filter, Scope::Metadata))
+            .transpose()
+            .map_err(|e| {
+                IndexifyAPIError::new(StatusCode::BAD_REQUEST, format!("invalid filter: {}", e))
+            })?;
+        Ok(Filter::all(
+            into_persistence_filters(filters)
+                .iter()
+                .map(|filter| filter.to_filter(Scope::Metadata))
+                .chain(filter),
+        ))
+    }
+
+    pub fn provenance(&self) -> persistence::ProvenanceFilter {
+        persistence::ProvenanceFilter {
+            source_connector: self.source_connector.clone(),
+            external_id: self.external_id.clone(),
+            source_url: self.source_url.clone(),
+            fetched_after: self.fetched_after,
+            fetched_before: self.fetched_before,
+        }
+    }
+}
+
+/// Where the extraction of content by an extractor binding stands.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractionState {
+    Pending,
+    Completed,
+    /// The filters of the binding do not match the content.
+    Skipped,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ContentInfo {
+    pub id: String,
+    pub content_type: String,
+    pub source: String,
+    pub metadata: HashMap<String, serde_json::Value>,
+    /// The state of the extraction of the content by each extractor binding
+    /// of the repository.
+    pub extraction_state: HashMap<String, ExtractionState>,
+    /// Whether the content is under a legal hold, which keeps it from being
+    /// deleted.
+    pub legal_hold: bool,
+    /// The content an extractor binding derived this content from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_content_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub produced_by_binding: Option<String>,
+    pub provenance: Provenance,
+}
+
+/// Where content was fetched from.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct Provenance {
+    /// The id of the connector which fetched the content.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_connector: Option<String>,
+    /// The id of the item in the system it came from, such as
+    /// `{topic}/{partition}/{offset}` for Kafka messages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    /// When the content was fetched, in seconds since the epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetched_at: Option<u64>,
+}
+
+impl From<persistence::Provenance> for Provenance {
+    fn from(value: persistence::Provenance) -> Self {
+        Self {
+            source_connector: value.source_connector,
+            external_id: value.external_id,
+            source_url: value.source_url,
+            fetched_at: value.fetched_at,
+        }
+    }
+}
+
+impl ContentInfo {
+    pub fn new(
+        value: persistence::ContentPayload,
+        bindings: &[persistence::ExtractorBinding],
+        legal_hold: bool,
+    ) -> Self {
+        let extraction_state = bindings
+            .iter()
+            .map(|binding| {
+                let state = if value
+                    .extractor_bindings_state
+                    .get(&binding.name)
+                    .is_some_and(|s| *s > 0)
+                {
+                    ExtractionState::Completed
+                } else if binding
+                    .content_filter()
+                    .is_none_or(|filter| filter.matches(&value.metadata))
+                {
+                    ExtractionState::Pending
+                } else {
+                    ExtractionState::Skipped
+                };
+                (binding.name.clone(), state)
+            })
+            .collect();
+        Self {
+            id: value.id,
+            content_type: value.content_type.to_string(),
+            source: value.source,
+            metadata: value.metadata,
+            extraction_state,
+            legal_hold,
+            parent_content_id: value.parent_content_id,
+            produced_by_binding: value.produced_by_binding,
+            provenance: value.provenance.into(),
+        }
+    }
+}
+
+/// A content in the lineage of another content.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LineageContent {
+    pub id: String,
+    pub content_type: String,
+    pub parent_content_id: Option<String>,
+    pub produced_by_binding: Option<String>,
+    /// How many derivations away from the content this content is.
+    pub distance: u32,
+}
+
+impl From<persistence::ContentLineageNode> for LineageContent {
+    fn from(value: persistence::ContentLineageNode) -> Self {
+        Self {
+            id: value.id,
+            content_type: value.content_type,
+            parent_content_id: value.parent_content_id,
+            produced_by_binding: value.produced_by_binding,
+            distance: value.depth.unsigned_abs(),
+        }
+    }
+}
+
+/// The content a content was derived from, from its parent up to the content
+/// which was added to the repository, and the content derived from it,
+/// nearest first.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ContentLineage {
+    pub content: LineageContent,
+    pub ancestors: Vec<LineageContent>,
+    pub derived: Vec<LineageContent>,
+}
+
+impl ContentLineage {
+    pub fn new(nodes: Vec<persistence::ContentLineageNode>) -> Option<Self> {
+        let mut ancestors = Vec::new();
+        let mut derived = Vec::new();
+        let mut content = None;
+        for node in nodes {
+            match node.depth {
+                d if d < 0 => ancestors.push(node),
+                0 => content = Some(node),
+                _ => derived.push(node),
+            }
         }
+        ancestors.sort_by_key(|n| -n.depth);
+        Some(Self {
+            content: content?.into(),
+            ancestors: ancestors.into_iter().map(Into::into).collect(),
+            derived: derived.into_iter().map(Into::into).collect(),
+        })
     }
 }
 
-impl From<vectordbs::IndexDistance> for IndexDistance {
-    fn from(val: vectordbs::IndexDistance) -> Self {
-        match val {
-            vectordbs::IndexDistance::Dot => IndexDistance::Dot,
-            vectordbs::IndexDistance::Cosine => IndexDistance::Cosine,
-            vectordbs::IndexDistance::Euclidean => IndexDistance::Euclidean,
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExtractorVolume {
+    pub extractor: String,
+    pub completed_work: u64,
+    pub failed_work: u64,
+}
+
+/// The health of an extractor binding, computed from the content and the
+/// work of its repository.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExtractorBindingStats {
+    pub extractor_binding: String,
+    pub extractor: String,
+    /// Content whose metadata matches the filters of the binding.
+    pub content_matched: u64,
+    /// Matched content which the binding has extracted.
+    pub content_processed: u64,
+    /// Work waiting for or running on an executor.
+    pub pending_work: u64,
+    pub completed_work: u64,
+    pub failed_work: u64,
+    /// Mean seconds from the creation to the completion of the completed
+    /// work.
+    pub average_latency_secs: Option<f64>,
+    /// The error of the work which failed last.
+    pub last_error: Option<String>,
+    pub last_failed_at: Option<u64>,
+}
+
+impl From<persistence::ExtractorBindingStats> for ExtractorBindingStats {
+    fn from(value: persistence::ExtractorBindingStats) -> Self {
+        Self {
+            extractor_binding: value.extractor_binding,
+            extractor: value.extractor,
+            content_matched: value.content_matched,
+            content_processed: value.content_processed,
+            pending_work: value.pending_work,
+            completed_work: value.completed_work,
+            failed_work: value.failed_work,
+            average_latency_secs: value.average_latency_secs,
+            last_error: value.last_error,
+            last_failed_at: value.last_failed_at,
         }
     }
 }
 
-/// Request payload for creating a new vector index.
-#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
-pub struct ExtractorBindRequest {
-    #[serde(flatten)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateExtractorBindingResponse {
     pub extractor_binding: ExtractorBinding,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
-pub struct ExtractorBindResponse {
-    #[serde(default)]
-    pub index_names: Vec<String>,
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExtractorBindingStatsResponse {
+    pub extractor_bindings: Vec<ExtractorBindingStats>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct Text {
-    pub text: String,
-    #[serde(default)]
-    pub metadata: HashMap<String, serde_json::Value>,
+pub struct WorkFailure {
+    pub work_id: String,
+    pub content_id: String,
+    pub extractor: String,
+    pub extractor_binding: String,
+    pub error: Option<String>,
+    pub failed_at: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct TextAddRequest {
-    pub documents: Vec<Text>,
-    pub sync: Option<bool>,
+pub struct IndexSize {
+    pub index: String,
+    /// Chunks in the current generation of the index, or attributes of an
+    /// attribute index.
+    pub entries: u64,
 }
 
+/// The query load of a repository since the server started.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct RunExtractorsResponse {}
-
-#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
-#[serde(untagged)]
-pub enum ExtractorOutputSchema {
-    #[serde(rename = "embedding")]
-    Embedding { dim: usize, distance: IndexDistance },
-    #[serde(rename = "attributes")]
-    Attributes { schema: serde_json::Value },
+pub struct RepositoryQueryLoad {
+    pub repository: String,
+    pub in_flight: usize,
+    /// The moving average of the latency of the queries.
+    pub latency_ms: f64,
+    pub queries: u64,
+    /// Queries waiting for a slot.
+    pub queued: usize,
+    /// Queries which had to wait for a slot.
+    pub throttled: u64,
+    /// Queries rejected with `429 Too Many Requests` after waiting for a slot.
+    pub shed: u64,
 }
 
-impl From<persistence::ExtractorOutputSchema> for ExtractorOutputSchema {
-    fn from(value: persistence::ExtractorOutputSchema) -> Self {
-        match value {
-            persistence::ExtractorOutputSchema::Embedding(schema) => {
-                ExtractorOutputSchema::Embedding {
-                    dim: schema.dim,
-                    distance: schema.distance.into(),
-                }
-            }
-            persistence::ExtractorOutputSchema::Attributes(schema) => {
-                ExtractorOutputSchema::Attributes {
-                    schema: schema.schema,
-                }
-            }
+impl From<query_throttle::RepositoryLoad> for RepositoryQueryLoad {
+    fn from(value: query_throttle::RepositoryLoad) -> Self {
+        Self {
+            repository: value.repository,
+            in_flight: value.in_flight,
+            latency_ms: value.latency_ms,
+            queries: value.queries,
+            queued: value.queued,
+            throttled: value.throttled,
+            shed: value.shed,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ExtractorSchema {
-    pub outputs: HashMap<String, ExtractorOutputSchema>,
+/// The queries running on the server and the query load of the repositories
+/// of the namespace. While `saturated`, the queries of a repository beyond
+/// its share of `limit` wait for a slot.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct QueryLoadResponse {
+    pub limit: usize,
+    pub in_flight: usize,
+    pub saturated: bool,
+    pub repositories: Vec<RepositoryQueryLoad>,
 }
 
-impl From<persistence::ExtractorSchema> for ExtractorSchema {
-    fn from(value: persistence::ExtractorSchema) -> Self {
+impl From<query_throttle::QueryLoad> for QueryLoadResponse {
+    fn from(value: query_throttle::QueryLoad) -> Self {
         Self {
-            outputs: value
-                .outputs
-                .into_iter()
-                .map(|(k, v)| (k, v.into()))
-                .collect(),
+            limit: value.limit,
+            in_flight: value.in_flight,
+            saturated: value.saturated,
+            repositories: value.repositories.into_iter().map(|r| r.into()).collect(),
         }
     }
 }
 
+/// The embeddings in the embedding cache, and the lookups of the cache by
+/// the server since it started.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct ExtractorDescription {
-    pub name: String,
-    pub description: String,
-    pub input_params: serde_json::Value,
-    pub schemas: ExtractorSchema,
+pub struct EmbeddingCacheResponse {
+    pub enabled: bool,
+    pub entries: u64,
+    pub max_entries: u64,
+    pub hits: u64,
+    pub misses: u64,
+    /// Embeddings evicted by the server since it started.
+    pub evictions: u64,
 }
 
-impl From<persistence::Extractor> for ExtractorDescription {
-    fn from(value: persistence::Extractor) -> Self {
+impl From<embedding_cache::EmbeddingCacheStats> for EmbeddingCacheResponse {
+    fn from(value: embedding_cache::EmbeddingCacheStats) -> Self {
         Self {
-            name: value.name,
-            description: value.description,
-            input_params: value.input_params,
-            schemas: value.schemas.into(),
+            enabled: value.enabled,
+            entries: value.entries,
+            max_entries: value.max_entries,
+            hits: value.hits,
+            misses: value.misses,
+            evictions: value.evictions,
         }
     }
 }
 
+/// Whether a dependency of the server answered its health check.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct Executor {
-    pub id: String,
-    pub extractors: Vec<ExtractorDescription>,
-}
-
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct ListExecutorsResponse {
-    pub executors: Vec<Executor>,
+pub struct DependencyStatus {
+    /// `database`, `vector_store` or `blob_store`.
+    pub name: String,
+    /// The service behind the dependency, such as `postgres` or `qdrant`.
+    pub backend: String,
+    pub healthy: bool,
+    pub latency_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
-pub struct ListExtractorsResponse {
-    pub extractors: Vec<ExtractorDescription>,
+impl From<health::DependencyHealth> for DependencyStatus {
+    fn from(value: health::DependencyHealth) -> Self {
+        Self {
+            name: value.name,
+            backend: value.backend,
+            healthy: value.healthy,
+            latency_ms: value.latency.as_millis() as u64,
+            error: value.error,
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
-pub struct TextAdditionResponse {}
-
+/// The health of the server, `ok` when every dependency is healthy and
+/// `unavailable` otherwise.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct Index {
-    pub name: String,
-    pub schema: ExtractorOutputSchema,
+pub struct HealthResponse {
+    pub status: String,
+    pub dependencies: Vec<DependencyStatus>,
 }
 
-impl From<persistence::Index> for Index {
-    fn from(value: persistence::Index) -> Self {
+impl From<health::HealthReport> for HealthResponse {
+    fn from(value: health::HealthReport) -> Self {
         Self {
-            name: value.name,
-            schema: value.schema.into(),
+            status: if value.is_healthy() {
+                "ok".into()
+            } else {
+                "unavailable".into()
+            },
+            dependencies: value.dependencies.into_iter().map(Into::into).collect(),
         }
     }
 }
 
+/// A summary of the activity of a repository as of `computed_at`. The
+/// summaries are computed periodically by the server, the rates and the
+/// counts of finished work cover the last `window_secs`.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct ListIndexesResponse {
-    pub indexes: Vec<Index>,
+pub struct RepositoryDashboard {
+    pub computed_at: u64,
+    pub window_secs: u64,
+    pub total_content: u64,
+    pub content_added: u64,
+    pub ingest_rate_per_hour: f64,
+    /// Content added which is waiting for the coordinator to create its work.
+    pub unprocessed_events: u64,
+    /// Work waiting for an executor.
+    pub pending_work: u64,
+    /// Work assigned to an executor and not finished yet.
+    pub in_flight_work: u64,
+    pub failed_work: u64,
+    /// The extractors which finished the most work.
+    pub top_extractors: Vec<ExtractorVolume>,
+    pub index_sizes: Vec<IndexSize>,
+    /// The work which failed last, most recent first, with why it failed.
+    pub recent_failures: Vec<WorkFailure>,
 }
 
-#[derive(Debug, Serialize, Deserialize, IntoParams, ToSchema)]
-pub struct SearchRequest {
-    pub index: String,
-    pub query: String,
-    pub k: Option<u64>,
+impl From<persistence::RepositoryDashboard> for RepositoryDashboard {
+    fn from(value: persistence::RepositoryDashboard) -> Self {
+        let ingest_rate_per_hour = if value.window_secs == 0 {
+            0.0
+        } else {
+            value.content_added as f64 * 3600.0 / value.window_secs as f64
+        };
+        Self {
+            computed_at: value.computed_at,
+            window_secs: value.window_secs,
+            total_content: value.total_content,
+            content_added: value.content_added,
+            ingest_rate_per_hour,
+            unprocessed_events: value.unprocessed_events,
+            pending_work: value.pending_work,
+            in_flight_work: value.in_flight_work,
+            failed_work: value.failed_work,
+            top_extractors: value
+                .top_extractors
+                .into_iter()
+                .map(|e| ExtractorVolume {
+                    extractor: e.extractor,
+                    completed_work: e.completed_work,
+                    failed_work: e.failed_work,
+                })
+                .collect(),
+            index_sizes: value
+                .index_sizes
+                .into_iter()
+                .map(|i| IndexSize {
+                    index: i.index,
+                    entries: i.entries,
+                })
+                .collect(),
+            recent_failures: value
+                .recent_failures
+                .into_iter()
+                .map(|f| WorkFailure {
+                    work_id: f.work_id,
+                    content_id: f.content_id,
+                    extractor: f.extractor,
+                    extractor_binding: f.extractor_binding,
+                    error: f.error,
+                    failed_at: f.failed_at,
+                })
+                .collect(),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
-pub struct ExtractedAttributes {
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WorkInfo {
     pub id: String,
     pub content_id: String,
-    pub attributes: serde_json::Value,
-    pub extractor_name: String,
+    pub extractor: String,
+    pub extractor_binding: String,
+    pub state: String,
+    pub executor_id: Option<String>,
+    /// The items, such as chunks, the executor has extracted so far.
+    pub items_processed: u64,
+    /// How much of the work is done, from 0 to 100, when the executor knows.
+    pub percent: Option<f64>,
+    /// Why the work failed, or the last error the executor recovered from.
+    pub last_error: Option<String>,
 }
 
-impl From<persistence::ExtractedAttributes> for ExtractedAttributes {
-    fn from(value: persistence::ExtractedAttributes) -> Self {
+impl From<persistence::Work> for WorkInfo {
+    fn from(value: persistence::Work) -> Self {
         Self {
             id: value.id,
             content_id: value.content_id,
-            attributes: value.attributes,
-            extractor_name: value.extractor_name,
+            extractor: value.extractor,
+            extractor_binding: value.extractor_binding,
+            state: value.work_state.to_string(),
+            executor_id: value.executor_id,
+            items_processed: value.items_processed,
+            percent: value.percent,
+            last_error: value.last_error,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, IntoParams, ToSchema)]
-pub struct AttributeLookupRequest {
-    pub content_id: Option<String>,
-    pub index: String,
-}
-
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct AttributeLookupResponse {
-    pub attributes: Vec<ExtractedAttributes>,
+pub struct GetWorkResponse {
+    pub work: WorkInfo,
+    /// The last lines the executor logged for the work, oldest first.
+    pub log: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
-pub struct Event {
-    text: String,
-    unix_timestamp: Option<u64>,
-    metadata: HashMap<String, serde_json::Value>,
+impl From<persistence::Work> for GetWorkResponse {
+    fn from(mut value: persistence::Work) -> Self {
+        let log = std::mem::take(&mut value.log);
+        Self {
+            work: value.into(),
+            log,
+        }
+    }
 }
 
-impl From<Event> for persistence::Event {
-    fn from(value: Event) -> Self {
-        persistence::Event::new(&value.text, value.unix_timestamp, value.metadata)
-    }
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConnectorSync {
+    pub connector_id: String,
+    pub status: String,
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    pub items_synced: u64,
+    pub error: Option<String>,
 }
 
-impl From<persistence::Event> for Event {
-    fn from(value: persistence::Event) -> Self {
+impl From<persistence::ConnectorSyncRun> for ConnectorSync {
+    fn from(value: persistence::ConnectorSyncRun) -> Self {
         Self {
-            text: value.message,
-            unix_timestamp: Some(value.unix_timestamp),
-            metadata: value.metadata,
+            connector_id: value.connector_id,
+            status: value.status.to_string(),
+            started_at: value.started_at,
+            finished_at: value.finished_at,
+            items_synced: value.items_synced,
+            error: value.error,
         }
     }
 }
 
+/// A background job which ingests the rows of a manifest into a repository.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct EventAddRequest {
-    pub events: Vec<Event>,
+pub struct IngestionJob {
+    pub id: String,
+    pub repository: String,
+    pub manifest_name: String,
+    pub format: String,
+    pub status: String,
+    /// Known once the job has read the manifest.
+    pub total_rows: Option<u64>,
+    /// Rows processed so far, a resumed job continues after them.
+    pub next_row: u64,
+    pub rows_succeeded: u64,
+    pub rows_failed: u64,
+    pub error: Option<String>,
+    pub created_at: u64,
+    pub finished_at: Option<u64>,
 }
 
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct EventAddResponse {}
+impl From<persistence::IngestionJob> for IngestionJob {
+    fn from(value: persistence::IngestionJob) -> Self {
+        Self {
+            id: value.id,
+            repository: value.repository,
+            manifest_name: value.manifest_name,
+            format: value.format.to_string(),
+            status: value.status.to_string(),
+            total_rows: value.total_rows,
+            next_row: value.next_row,
+            rows_succeeded: value.rows_succeeded,
+            rows_failed: value.rows_failed,
+            error: value.error,
+            created_at: value.created_at,
+            finished_at: value.finished_at,
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct ListEventsResponse {
-    pub messages: Vec<Event>,
+pub struct IngestionRowError {
+    /// The row of the manifest, starting at 1.
+    pub row: u64,
+    pub error: String,
+}
+
+impl From<persistence::IngestionRowError> for IngestionRowError {
+    fn from(value: persistence::IngestionRowError) -> Self {
+        Self {
+            row: value.row,
+            error: value.error,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
 pub struct DocumentFragment {
     pub content_id: String,
     pub text: String,
+    /// Where the text is in the content, for chunks split by the chunker of a
+    /// binding, so that it can be highlighted in the original document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offsets: Option<ChunkOffsets>,
+    /// Where the chunk is in its image, for tiles of images split by the
+    /// `image_tiles` chunker.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<ImageRegion>,
     pub confidence_score: f32,
     pub metadata: HashMap<String, serde_json::Value>,
+    /// Only returned when the search was made with `explain`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<SearchExplanation>,
+    /// The attributes extracted from the content, by attributes index. Only
+    /// returned when the search was made with `include_attributes`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<HashMap<String, serde_json::Value>>,
+    /// The summary of the content. Only returned when the search was made
+    /// with `summary` and the content was summarized.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+/// Where a chunk is in the text of its content, in characters.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct ChunkOffsets {
+    pub start: usize,
+    pub end: usize,
+    /// The page the chunk starts on, for text with page breaks like the text
+    /// extracted from PDFs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    /// The time of the recording the chunk starts at, in milliseconds, for
+    /// chunks of transcripts of audio.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_ms: Option<u64>,
+    /// The time of the recording the chunk ends at, in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_ms: Option<u64>,
+}
+
+impl From<persistence::ChunkOffsets> for ChunkOffsets {
+    fn from(value: persistence::ChunkOffsets) -> Self {
+        Self {
+            start: value.start,
+            end: value.end,
+            page: value.page,
+            start_ms: value.start_ms,
+            end_ms: value.end_ms,
+        }
+    }
+}
+
+/// Where a tile is in its image, in pixels from the top left corner.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct ImageRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl From<persistence::ImageRegion> for ImageRegion {
+    fn from(value: persistence::ImageRegion) -> Self {
+        Self {
+            x: value.x,
+            y: value.y,
+            width: value.width,
+            height: value.height,
+        }
+    }
+}
+
+/// Why a result of a search ranked where it did.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SearchExplanation {
+    /// Position of the chunk in the results of the vector store, starting at
+    /// 0.
+    pub rank: usize,
+    pub chunk_id: String,
+    /// The parent chunk the chunk was split out of, whose text is returned
+    /// when the search was made with `return_parent_chunks`.
+    pub parent_chunk_id: Option<String>,
+    /// Similarity of the embedding of the chunk to the embedding of the query.
+    pub similarity_score: f32,
+    /// Score of the query terms in the chunk, when the keyword index was
+    /// searched.
+    pub keyword_score: Option<f32>,
+    /// Score given by the reranker, when the results were reranked.
+    pub reranker_score: Option<f32>,
+    /// The attribute filter the candidates were restricted with.
+    pub attribute_filter: Option<AttributeFilter>,
+    pub index: String,
+    /// The collection of the vector store which served the result.
+    pub vector_index: String,
+    /// The generation of the index which served the result, see reindexing.
+    pub generation: i64,
+}
+
+impl SearchExplanation {
+    pub fn new(
+        explanation: vector_index::HitExplanation,
+        attribute_filter: Option<AttributeFilter>,
+    ) -> Self {
+        Self {
+            rank: explanation.rank,
+            chunk_id: explanation.chunk_id,
+            parent_chunk_id: explanation.parent_chunk_id,
+            similarity_score: explanation.similarity_score,
+            keyword_score: explanation.keyword_score,
+            reranker_score: explanation.reranker_score,
+            attribute_filter,
+            index: explanation.snapshot.index_name,
+            vector_index: explanation.snapshot.vector_index_name,
+            generation: explanation.snapshot.generation,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
 pub struct IndexSearchResponse {
     pub results: Vec<DocumentFragment>,
 }
+
+#[derive(Debug)]
 pub struct IndexifyAPIError {
     status_code: StatusCode,
     message: String,
@@ -406,6 +3573,14 @@ impl IndexifyAPIError {
             message,
         }
     }
+
+    pub fn status_code(&self) -> StatusCode {
+        self.status_code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
 }
 
 impl IntoResponse for IndexifyAPIError {
@@ -414,6 +3589,142 @@ impl IntoResponse for IndexifyAPIError {
     }
 }
 
+pub const NAMESPACE_HEADER: &str = "x-indexify-namespace";
+
+/// The namespace a request works in, read from the `x-indexify-namespace`
+/// header. Requests without the header work in the default namespace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Namespace(pub String);
+
+impl Namespace {
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, IndexifyAPIError> {
+        let Some(namespace) = headers.get(NAMESPACE_HEADER) else {
+            return Ok(Self(persistence::DEFAULT_NAMESPACE.into()));
+        };
+        let namespace = namespace
+            .to_str()
+            .map_err(|e| IndexifyAPIError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+        persistence::validate_namespace(namespace)
+            .map_err(|e| IndexifyAPIError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+        Ok(Self(namespace.into()))
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Namespace {
+    type Rejection = IndexifyAPIError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Self::from_headers(&parts.headers)
+    }
+}
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Idempotency keys are at most this long.
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// The `Idempotency-Key` header of a request, with which the retries of the
+/// request get its original response instead of adding its content again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdempotencyKey(pub Option<String>);
+
+impl IdempotencyKey {
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, IndexifyAPIError> {
+        let Some(key) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+            return Ok(Self(None));
+        };
+        let key = key
+            .to_str()
+            .map_err(|e| IndexifyAPIError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+        if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+            return Err(IndexifyAPIError::new(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "idempotency key must have 1 to {} characters",
+                    MAX_IDEMPOTENCY_KEY_LEN
+                ),
+            ));
+        }
+        Ok(Self(Some(key.into())))
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IdempotencyKey {
+    type Rejection = IndexifyAPIError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Self::from_headers(&parts.headers)
+    }
+}
+
+/// The entity tag of a repository at a version.
+pub fn repository_etag(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+/// The version of a repository in the `If-Match` header of a request, which
+/// is updated only if it is still at that version. Requests without the
+/// header, or with `*`, update the repository whatever its version.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IfMatch(pub Option<i64>);
+
+impl IfMatch {
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, IndexifyAPIError> {
+        let Some(etag) = headers.get(header::IF_MATCH) else {
+            return Ok(Self(None));
+        };
+        let etag = etag
+            .to_str()
+            .map_err(|e| IndexifyAPIError::new(StatusCode::BAD_REQUEST, e.to_string()))?
+            .trim();
+        if etag == "*" {
+            return Ok(Self(None));
+        }
+        etag.trim_start_matches("W/")
+            .trim_matches('"')
+            .parse()
+            .map(|version| Self(Some(version)))
+            .map_err(|_| {
+                IndexifyAPIError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("`{}` is not the entity tag of a repository version", etag),
+                )
+            })
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = IndexifyAPIError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Self::from_headers(&parts.headers)
+    }
+}
+
+/// The `Range` header of a download, with the bytes of the content to
+/// return. A header which is not text is ignored, like any range which is
+/// not understood, and the whole content is returned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeHeader(pub Option<String>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for RangeHeader {
+    type Rejection = IndexifyAPIError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self(
+            parts
+                .headers
+                .get(header::RANGE)
+                .and_then(|range| range.to_str().ok())
+                .map(|range| range.to_string()),
+        ))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, EnumString)]
 pub enum FeatureType {
     #[strum(serialize = "embedding")]
@@ -422,6 +3733,10 @@ pub enum FeatureType {
     NamedEntity,
     #[strum(serialize = "metadata")]
     Metadata,
+    #[strum(serialize = "graph")]
+    Graph,
+    #[strum(serialize = "summary")]
+    Summary,
     #[strum(serialize = "unknown")]
     Unknown,
 }
@@ -453,3 +3768,251 @@ pub struct ExtractRequest {
 pub struct ExtractResponse {
     pub content: Vec<Content>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_from_items() {
+        let params = ListParams {
+            cursor: None,
+            limit: Some(2),
+        };
+        let page = Page::from_items(vec![1, 2, 3], &params).unwrap();
+        assert_eq!(page.items, vec![1, 2]);
+        assert_eq!(page.next_cursor, Some("2".to_string()));
+        assert_eq!(page.total_estimate, 3);
+
+        let params = ListParams {
+            cursor: page.next_cursor,
+            limit: Some(2),
+        };
+        let page = Page::from_items(vec![1, 2, 3], &params).unwrap();
+        assert_eq!(page.items, vec![3]);
+        assert_eq!(page.next_cursor, None);
+
+        let params = ListParams {
+            cursor: Some("abc".into()),
+            limit: None,
+        };
+        assert!(Page::from_items(vec![1], &params).is_err());
+    }
+
+    #[test]
+    fn test_page_from_keyset() {
+        let params = ListParams {
+            cursor: None,
+            limit: Some(2),
+        };
+        let page = Page::from_keyset(vec!["a", "b", "c"], &params, 10, |s| s.to_string());
+        assert_eq!(page.items, vec!["a", "b"]);
+        assert_eq!(page.next_cursor, Some("b".to_string()));
+        assert_eq!(page.total_estimate, 10);
+    }
+
+    #[test]
+    fn test_if_match() {
+        let if_match = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::IF_MATCH, value.parse().unwrap());
+            IfMatch::from_headers(&headers)
+        };
+        assert_eq!(
+            IfMatch::from_headers(&HeaderMap::new()).unwrap(),
+            IfMatch(None)
+        );
+        assert_eq!(if_match("*").unwrap(), IfMatch(None));
+        assert_eq!(if_match(&repository_etag(3)).unwrap(), IfMatch(Some(3)));
+        assert_eq!(if_match("W/\"3\"").unwrap(), IfMatch(Some(3)));
+        assert!(if_match("\"abc\"").is_err());
+    }
+
+    #[test]
+    fn test_index_quantization() {
+        // Schemas stored before indexes could be quantized.
+        let schema: persistence::EmbeddingSchema =
+            serde_json::from_value(serde_json::json!({"dim": 384, "distance": "cosine"})).unwrap();
+        assert_eq!(schema.quantization, None);
+        let index = |quantization| persistence::Index {
+            name: "minilm.embedding".into(),
+            schema: persistence::ExtractorOutputSchema::Embedding(persistence::EmbeddingSchema {
+                quantization,
+                ..schema.clone()
+            }),
+            schema_version: 1,
+            reranker: None,
+            extractor_binding: Some("minilm".into()),
+            output: Some("embedding".into()),
+        };
+        assert!(Index::from(index(None)).quantization.is_none());
+        let tradeoff = Index::from(index(Some(vectordbs::Quantization::Scalar)))
+            .quantization
+            .unwrap();
+        assert_eq!(tradeoff.quantization, Quantization::Scalar);
+        assert_eq!(tradeoff.compression, 4);
+        let json = serde_json::to_value(Index::from(index(Some(vectordbs::Quantization::Product))))
+            .unwrap();
+        assert_eq!(json["schema"]["quantization"], "product");
+        assert_eq!(json["quantization"]["compression"], 16);
+    }
+
+    #[test]
+    fn test_search_query() {
+        let request =
+            |body: serde_json::Value| -> SearchRequest { serde_json::from_value(body).unwrap() };
+        assert_eq!(
+            request(serde_json::json!({"index": "docs.embedding", "query": "hello"}))
+                .search_query()
+                .unwrap(),
+            vector_index::SearchQuery::Text("hello".into())
+        );
+        assert_eq!(
+            request(serde_json::json!({
+                "index": "photos.embedding",
+                "image": {"content_type": "image/png", "data": "aGVsbG8="}
+            }))
+            .search_query()
+            .unwrap(),
+            vector_index::SearchQuery::Image {
+                content_type: "image/png".into(),
+                data: b"hello".to_vec(),
+            }
+        );
+        assert!(request(serde_json::json!({"index": "docs.embedding"}))
+            .search_query()
+            .is_err());
+        assert!(request(serde_json::json!({
+            "index": "photos.embedding",
+            "query": "hello",
+            "image": {"content_type": "image/png", "data": "aGVsbG8="}
+        }))
+        .search_query()
+        .is_err());
+        assert!(request(serde_json::json!({
+            "index": "photos.embedding",
+            "image": {"content_type": "image/png", "data": "not base64!"}
+        }))
+        .search_query()
+        .is_err());
+    }
+
+    #[test]
+    fn test_search_filters() {
+        let request =
+            |body: serde_json::Value| -> SearchRequest { serde_json::from_value(body).unwrap() };
+        let metadata_filter = request(serde_json::json!({
+            "index": "docs.embedding",
+            "query": "hello",
+            "metadata_filter": {"lang": "en"},
+            "filter": r#"metadata.lang = "en" and metadata.source.kind = "web""#,
+        }))
+        .metadata_filter()
+        .unwrap();
+        assert_eq!(
+            metadata_filter,
+            HashMap::from([
+                ("lang".to_string(), serde_json::json!("en")),
+                ("source.kind".to_string(), serde_json::json!("web")),
+            ])
+        );
+        assert!(request(serde_json::json!({
+            "index": "docs.embedding",
+            "query": "hello",
+            "metadata_filter": {"lang": "fr"},
+            "filter": r#"metadata.lang = "en""#,
+        }))
+        .metadata_filter()
+        .is_err());
+        assert!(request(serde_json::json!({
+            "index": "docs.embedding",
+            "query": "hello",
+            "filter": r#"metadata.lang = "en" or metadata.lang = "fr""#,
+        }))
+        .metadata_filter()
+        .is_err());
+        assert!(serde_json::from_value::<SearchRequest>(serde_json::json!({
+            "index": "docs.embedding",
+            "query": "hello",
+            "filter": "metadata.lang =",
+        }))
+        .is_err());
+
+        let attribute_filter: AttributeFilter = serde_json::from_value(serde_json::json!({
+            "index": "products.attributes",
+            "filters": [{"eq": {"brand": "acme"}}],
+            "query": "attributes.price > 100",
+        }))
+        .unwrap();
+        assert_eq!(
+            attribute_filter
+                .prefilter()
+                .unwrap()
+                .filter
+                .unwrap()
+                .to_string(),
+            r#"attributes.brand = "acme" and attributes.price > 100"#
+        );
+        let attribute_filter: AttributeFilter = serde_json::from_value(serde_json::json!({
+            "index": "products.attributes",
+            "query": r#"metadata.lang = "en""#,
+        }))
+        .unwrap();
+        assert!(attribute_filter.prefilter().is_err());
+    }
+
+    #[test]
+    fn test_summary_options() {
+        let fragment = |content_id: &str, text: &str, score: f32| DocumentFragment {
+            content_id: content_id.into(),
+            text: text.into(),
+            offsets: Some(ChunkOffsets {
+                start: 0,
+                end: text.len(),
+                page: None,
+                start_ms: None,
+                end_ms: None,
+            }),
+            confidence_score: score,
+            ..Default::default()
+        };
+        let fragments = || {
+            vec![
+                fragment("report", "revenue grew", 0.9),
+                fragment("notes", "action items", 0.8),
+                fragment("report", "costs fell", 0.7),
+            ]
+        };
+        let summaries = HashMap::from([("report".to_string(), "A quarterly report.".to_string())]);
+        let options = |mode| SummaryOptions {
+            index: "summarizer.summary".into(),
+            mode,
+        };
+
+        let results = options(SummaryMode::Alongside).apply(fragments(), &summaries);
+        let texts: Vec<(&str, Option<&str>)> = results
+            .iter()
+            .map(|f| (f.text.as_str(), f.summary.as_deref()))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("revenue grew", Some("A quarterly report.")),
+                ("action items", None),
+                ("costs fell", Some("A quarterly report.")),
+            ]
+        );
+
+        let results = options(SummaryMode::Instead).apply(fragments(), &summaries);
+        let texts: Vec<(&str, f32)> = results
+            .iter()
+            .map(|f| (f.text.as_str(), f.confidence_score))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![("A quarterly report.", 0.9), ("action items", 0.8)]
+        );
+        assert!(results[0].offsets.is_none());
+        assert!(results[1].offsets.is_some());
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    io::{Cursor, Read},
+    path::{Component, Path},
+};
+
+use anyhow::{anyhow, Result};
+use bytes::Bytes;
+use flate2::read::GzDecoder;
+
+use crate::server_config::ArchiveConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// A file unpacked from an archive, with its path relative to the root of the
+/// archive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveEntry {
+    pub path: String,
+    pub data: Bytes,
+}
+
+/// Keeps track of the limits while an archive is unpacked. Sizes are counted
+/// from the bytes actually read, the sizes recorded in the archive headers are
+/// not trusted.
+struct Unpacker<'a> {
+    config: &'a ArchiveConfig,
+    unpacked_size: u64,
+    entries: Vec<ArchiveEntry>,
+}
+
+impl<'a> Unpacker<'a> {
+    fn new(config: &'a ArchiveConfig) -> Self {
+        Self {
+            config,
+            unpacked_size: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, path: &Path, reader: impl Read) -> Result<()> {
+        let Some(path) = relative_path(path) else {
+            return Err(anyhow!(
+                "archive entry {} is outside of the archive",
+                path.display()
+            ));
+        };
+        if self.entries.len() >= self.config.max_entries {
+            return Err(anyhow!(
+                "archive has more than {} files",
+                self.config.max_entries
+            ));
+        }
+        let remaining = self.config.max_unpacked_size_bytes - self.unpacked_size;
+        let mut data = Vec::new();
+        reader.take(remaining + 1).read_to_end(&mut data)?;
+        if data.len() as u64 > remaining {
+            return Err(anyhow!(
+                "archive is larger than {} bytes once unpacked",
+                self.config.max_unpacked_size_bytes
+            ));
+        }
+        self.unpacked_size += data.len() as u64;
+        self.entries.push(ArchiveEntry {
+            path,
+            data: Bytes::from(data),
+        });
+        Ok(())
+    }
+}
+
+/// The path of an entry joined with `/`, or `None` when the path is absolute
+/// or escapes the root of the archive.
+fn relative_path(path: &Path) -> Option<String> {
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(c) => components.push(c.to_str()?.to_string()),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!components.is_empty()).then(|| components.join("/"))
+}
+
+/// Unpacks the files of an archive into memory. Directories, links and other
+/// special entries are skipped.
+pub fn unpack(
+    format: ArchiveFormat,
+    data: &[u8],
+    config: &ArchiveConfig,
+) -> Result<Vec<ArchiveEntry>> {
+    if data.len() as u64 > config.max_archive_size_bytes {
+        return Err(anyhow!(
+            "archive is larger than {} bytes",
+            config.max_archive_size_bytes
+        ));
+    }
+    let mut unpacker = Unpacker::new(config);
+    match format {
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(Cursor::new(data))?;
+            for i in 0..archive.len() {
+                let file = archive.by_index(i)?;
+                if !file.is_file() {
+                    continue;
+                }
+                let path = Path::new(file.name()).to_path_buf();
+                unpacker.add(&path, file)?;
+            }
+        }
+        ArchiveFormat::Tar => unpack_tar(tar::Archive::new(data), &mut unpacker)?,
+        ArchiveFormat::TarGz => unpack_tar(tar::Archive::new(GzDecoder::new(data)), &mut unpacker)?,
+    }
+    Ok(unpacker.entries)
+}
+
+fn unpack_tar<R: Read>(mut archive: tar::Archive<R>, unpacker: &mut Unpacker) -> Result<()> {
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.to_path_buf();
+        unpacker.add(&path, entry)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn tar_archive(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, text) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(text.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, text.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_unpack_tar_gz() {
+        let tar = tar_archive(&[("wiki/home.md", "welcome"), ("wiki/faq.md", "questions")]);
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&tar).unwrap();
+        let data = encoder.finish().unwrap();
+
+        let entries = unpack(ArchiveFormat::TarGz, &data, &ArchiveConfig::default()).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                ArchiveEntry {
+                    path: "wiki/home.md".into(),
+                    data: Bytes::from("welcome"),
+                },
+                ArchiveEntry {
+                    path: "wiki/faq.md".into(),
+                    data: Bytes::from("questions"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unpack_zip() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer.add_directory("mail", Default::default()).unwrap();
+        writer.start_file("mail/1.eml", Default::default()).unwrap();
+        writer.write_all(b"hello").unwrap();
+        let data = writer.finish().unwrap().into_inner();
+
+        let entries = unpack(ArchiveFormat::Zip, &data, &ArchiveConfig::default()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, "mail/1.eml");
+        assert_eq!(entries[0].data, Bytes::from("hello"));
+    }
+
+    #[test]
+    fn test_unpack_limits() {
+        let data = tar_archive(&[("a.txt", "aaaa"), ("b.txt", "bbbb")]);
+        let config = ArchiveConfig {
+            max_entries: 1,
+            ..Default::default()
+        };
+        assert!(unpack(ArchiveFormat::Tar, &data, &config).is_err());
+        let config = ArchiveConfig {
+            max_unpacked_size_bytes: 6,
+            ..Default::default()
+        };
+        assert!(unpack(ArchiveFormat::Tar, &data, &config).is_err());
+        let config = ArchiveConfig {
+            max_archive_size_bytes: 10,
+            ..Default::default()
+        };
+        assert!(unpack(ArchiveFormat::Tar, &data, &config).is_err());
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            relative_path(Path::new("./wiki/home.md")),
+            Some("wiki/home.md".into())
+        );
+        assert_eq!(relative_path(Path::new("../etc/passwd")), None);
+        assert_eq!(relative_path(Path::new("/etc/passwd")), None);
+    }
+}
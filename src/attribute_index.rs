@@ -1,8 +1,25 @@
-use std::{fmt, sync::Arc};
+use std::{
+    fmt,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use jsonschema::JSONSchema;
+use tracing::{info, warn};
 
-use crate::persistence::{ExtractedAttributes, Extractor, Repository};
+use crate::{
+    filter::Filter,
+    persistence::{
+        ExtractedAttributes,
+        Extractor,
+        ExtractorBinding,
+        InvalidAttributesPolicy,
+        MetadataSchema,
+        NewIndex,
+        Repository,
+    },
+};
 
 pub struct AttributeIndexManager {
     repository: Arc<Repository>,
@@ -21,46 +38,294 @@ impl AttributeIndexManager {
 
     pub async fn create_index(
         &self,
+        namespace: &str,
         repository: &str,
         index_name: &str,
         extractor_config: Extractor,
     ) -> Result<String> {
         // TODO: create a new table for the index from a postgres schema
+        let index_schema = serde_json::json!(extractor_config.schemas);
         self.repository
             .create_index_metadata(
+                namespace,
                 repository,
-                &extractor_config.name,
-                index_name,
-                "structured_store",
-                serde_json::json!(extractor_config.schemas),
-                "json",
+                NewIndex {
+                    extractor_name: &extractor_config.name,
+                    name: index_name,
+                    storage_name: "structured_store",
+                    schema: index_schema.clone(),
+                    index_type: "json",
+                },
             )
             .await?;
+        self.evolve_schema(namespace, repository, index_name, index_schema)
+            .await?;
         Ok(index_name.to_string())
     }
 
+    /// Moves an existing index to the schema its extractor declares now,
+    /// at the next version, when the extractor changed it. Only additive
+    /// changes are allowed, so that the attributes written before stay valid.
+    async fn evolve_schema(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        index_schema: serde_json::Value,
+    ) -> Result<()> {
+        let (current, version) = self
+            .repository
+            .attribute_index_schema(namespace, repository, index_name)
+            .await?;
+        let output = ExtractorBinding::parse_output_index_name(index_name).map(|(_, o)| o);
+        let evolved = MetadataSchema {
+            schema: index_schema,
+        };
+        let (from, to) = (current.output_schema(output), evolved.output_schema(output));
+        if from == to {
+            return Ok(());
+        }
+        check_additive(from, to).map_err(|reason| {
+            anyhow!(
+                "the schema of index {} can only be extended, {}",
+                index_name,
+                reason
+            )
+        })?;
+        info!(
+            "extending the schema of index {} to version {}",
+            index_name,
+            version + 1
+        );
+        self.repository
+            .set_index_schema(
+                namespace,
+                repository,
+                index_name,
+                evolved.schema,
+                version + 1,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Writes attributes which match the schema of the index. Attributes
+    /// which do not fail the write, or are quarantined, depending on the
+    /// policy of their binding.
+    #[tracing::instrument(skip(self, extracted_attributes))]
     pub async fn add_index(
         &self,
+        namespace: &str,
         repository: &str,
         index_name: &str,
-        extracted_attributes: ExtractedAttributes,
+        mut extracted_attributes: ExtractedAttributes,
+        invalid_attributes: InvalidAttributesPolicy,
     ) -> Result<()> {
+        let (schema, version) = self
+            .repository
+            .attribute_index_schema(namespace, repository, index_name)
+            .await?;
+        let output = ExtractorBinding::parse_output_index_name(index_name).map(|(_, o)| o);
+        extracted_attributes.schema_version = version;
+        if let Err(errors) = validate(
+            schema.output_schema(output),
+            &extracted_attributes.attributes,
+        ) {
+            match invalid_attributes {
+                InvalidAttributesPolicy::Reject => {
+                    return Err(anyhow!(
+                        "attributes of content {} do not match the schema of index {}: {}",
+                        extracted_attributes.content_id,
+                        index_name,
+                        errors.join(", ")
+                    ));
+                }
+                InvalidAttributesPolicy::Quarantine => {
+                    warn!(
+                        "quarantining attributes of content {} which do not match the schema of index {}: {}",
+                        extracted_attributes.content_id,
+                        index_name,
+                        errors.join(", ")
+                    );
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs();
+                    self.repository
+                        .quarantine_attributes(
+                            namespace,
+                            repository,
+                            index_name,
+                            extracted_attributes,
+                            &errors,
+                            now,
+                        )
+                        .await?;
+                    return Ok(());
+                }
+            }
+        }
         self.repository
-            .add_attributes(repository, index_name, extracted_attributes)
+            .add_attributes(namespace, repository, index_name, extracted_attributes)
             .await?;
         Ok(())
     }
 
     pub async fn get_attributes(
         &self,
+        namespace: &str,
         repository: &str,
         index_name: &str,
         content_id: Option<&String>,
     ) -> Result<Vec<ExtractedAttributes>> {
         let extracted_attributes = self
             .repository
-            .get_extracted_attributes(repository, index_name, content_id)
+            .get_extracted_attributes(namespace, repository, index_name, content_id)
             .await?;
         Ok(extracted_attributes)
     }
+
+    pub async fn content_ids_matching(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<String>> {
+        let content_ids = self
+            .repository
+            .content_ids_matching_attributes(namespace, repository, index_name, filter)
+            .await?;
+        Ok(content_ids)
+    }
+}
+
+/// Validates attributes against a JSON schema. A schema which is not valid
+/// itself accepts any attributes, the extractor which declared it is at
+/// fault rather than the attributes.
+fn validate(schema: &serde_json::Value, attributes: &serde_json::Value) -> Result<(), Vec<String>> {
+    let compiled = match JSONSchema::compile(schema) {
+        Ok(compiled) => compiled,
+        Err(err) => {
+            warn!(
+                "unable to compile the schema of an attribute index: {}",
+                err
+            );
+            return Ok(());
+        }
+    };
+    let result = compiled.validate(attributes);
+    result.map_err(|errors| errors.map(|e| e.to_string()).collect())
+}
+
+/// Checks that the attributes valid under the schema `from` are valid under
+/// `to` as well: properties and their definitions are kept, no property
+/// becomes required, and additional properties are not forbidden when they
+/// were allowed.
+fn check_additive(from: &serde_json::Value, to: &serde_json::Value) -> Result<(), String> {
+    if from.get("type") != to.get("type") {
+        return Err("its type changed".into());
+    }
+    let properties = |schema: &serde_json::Value| {
+        schema
+            .get("properties")
+            .and_then(|p| p.as_object())
+            .cloned()
+            .unwrap_or_default()
+    };
+    let (from_properties, to_properties) = (properties(from), properties(to));
+    for (name, definition) in &from_properties {
+        match to_properties.get(name) {
+            None => return Err(format!("property {} was removed", name)),
+            Some(to_definition) if to_definition != definition => {
+                return Err(format!("property {} changed", name))
+            }
+            Some(_) => {}
+        }
+    }
+    let required = |schema: &serde_json::Value| -> Vec<String> {
+        schema
+            .get("required")
+            .and_then(|r| serde_json::from_value(r.clone()).ok())
+            .unwrap_or_default()
+    };
+    let from_required = required(from);
+    if let Some(name) = required(to)
+        .into_iter()
+        .find(|name| !from_required.contains(name))
+    {
+        return Err(format!("property {} became required", name));
+    }
+    let closed = |schema: &serde_json::Value| {
+        schema.get("additionalProperties") == Some(&serde_json::Value::Bool(false))
+    };
+    if closed(to) && !closed(from) {
+        return Err("additional properties are no longer allowed".into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "sentiment": {"type": "string"},
+                "score": {"type": "number"},
+            },
+            "required": ["sentiment"],
+        })
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&schema(), &json!({"sentiment": "positive", "score": 0.9})).is_ok());
+        let errors = validate(&schema(), &json!({"score": "high"})).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(validate(&json!({}), &json!({"anything": 1})).is_ok());
+        // A schema which can not be compiled accepts any attributes.
+        assert!(validate(&json!({"type": 12}), &json!({"score": "high"})).is_ok());
+    }
+
+    #[test]
+    fn test_check_additive() {
+        let mut extended = schema();
+        extended["properties"]["language"] = json!({"type": "string"});
+        assert!(check_additive(&schema(), &extended).is_ok());
+
+        let mut relaxed = schema();
+        relaxed["required"] = json!([]);
+        assert!(check_additive(&schema(), &relaxed).is_ok());
+
+        let mut removed = schema();
+        removed["properties"]
+            .as_object_mut()
+            .unwrap()
+            .remove("score");
+        assert_eq!(
+            check_additive(&schema(), &removed).unwrap_err(),
+            "property score was removed"
+        );
+
+        let mut retyped = schema();
+        retyped["properties"]["score"] = json!({"type": "string"});
+        assert!(check_additive(&schema(), &retyped).is_err());
+
+        let mut required = extended.clone();
+        required["required"] = json!(["sentiment", "language"]);
+        assert_eq!(
+            check_additive(&schema(), &required).unwrap_err(),
+            "property language became required"
+        );
+
+        let mut closed = schema();
+        closed["additionalProperties"] = json!(false);
+        assert!(check_additive(&schema(), &closed).is_err());
+        assert!(check_additive(&closed, &closed).is_ok());
+    }
 }
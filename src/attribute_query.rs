@@ -0,0 +1,255 @@
+use std::fmt;
+
+use sea_orm::Value;
+
+use crate::filter::{fmt_path, path_sql, Parser, PathSegment, Scope, Token};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// The order of the results of an attribute query, such as
+/// `attributes.price desc`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeSort {
+    pub path: Vec<PathSegment>,
+    pub order: SortOrder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricFunction {
+    Min,
+    Max,
+    Avg,
+    Sum,
+}
+
+impl MetricFunction {
+    fn sql(&self) -> &'static str {
+        match self {
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Avg => "avg",
+            Self::Sum => "sum",
+        }
+    }
+}
+
+/// A statistic of a numeric attribute over a group of attributes, such as
+/// `avg(attributes.price)`. Values which are not numbers are left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+    pub function: MetricFunction,
+    pub path: Vec<PathSegment>,
+}
+
+/// Groups the attributes of an index by the value of an attribute, or all
+/// of them in one group without `group_by`, and counts them along with the
+/// metrics. Groups are ordered by their count, largest first, so the first
+/// `limit` groups are the most frequent values. An attribute which is an
+/// array is counted in the group of every one of its elements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeAggregation {
+    pub group_by: Option<Vec<PathSegment>>,
+    pub metrics: Vec<Metric>,
+    pub limit: u64,
+}
+
+/// A function applied to a path, such as `avg(attributes.price)`.
+fn metric(parser: &mut Parser) -> Result<Metric, String> {
+    let function = match parser.next() {
+        Some(Token::Ident(word)) => match word.to_lowercase().as_str() {
+            "min" => MetricFunction::Min,
+            "max" => MetricFunction::Max,
+            "avg" => MetricFunction::Avg,
+            "sum" => MetricFunction::Sum,
+            _ => return Err(format!("unknown metric {}", word)),
+        },
+        token => return Err(format!("expected a metric, found {:?}", token)),
+    };
+    parser.expect(Token::LeftParen)?;
+    let path = parser.path(Scope::Attributes)?;
+    parser.expect(Token::RightParen)?;
+    Ok(Metric { function, path })
+}
+
+impl AttributeSort {
+    pub fn parse(sort: &str) -> Result<Self, String> {
+        let mut parser = Parser::new(sort)?;
+        let path = parser.path(Scope::Attributes)?;
+        let order = if parser.is_keyword("desc") {
+            parser.next();
+            SortOrder::Desc
+        } else {
+            if parser.is_keyword("asc") {
+                parser.next();
+            }
+            SortOrder::Asc
+        };
+        parser.finish()?;
+        Ok(Self { path, order })
+    }
+
+    /// The sort as an `order by` expression. Attributes which are missing are
+    /// sorted last.
+    pub fn to_sql(&self, values: &mut Vec<Value>) -> String {
+        let order = match self.order {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        };
+        format!(
+            "{} {} nulls last",
+            path_sql(Scope::Attributes, &self.path, values),
+            order
+        )
+    }
+}
+
+impl Metric {
+    /// A list of metrics separated by commas, such as
+    /// `min(attributes.price), max(attributes.price)`.
+    pub fn parse_list(metrics: &str) -> Result<Vec<Self>, String> {
+        let mut parser = Parser::new(metrics)?;
+        let mut list = Vec::new();
+        while parser.peek().is_some() {
+            list.push(metric(&mut parser)?);
+            if parser.peek() == Some(&Token::Comma) {
+                parser.next();
+            } else {
+                parser.finish()?;
+            }
+        }
+        Ok(list)
+    }
+
+    fn to_sql(&self, values: &mut Vec<Value>) -> String {
+        let path = path_sql(Scope::Attributes, &self.path, values);
+        format!(
+            "{}(case when jsonb_typeof({}) = 'number' then ({})::float8 end)",
+            self.function.sql(),
+            path,
+            path
+        )
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}(", self.function.sql())?;
+        fmt_path(Scope::Attributes, &self.path, f)?;
+        write!(f, ")")
+    }
+}
+
+impl AttributeAggregation {
+    /// The path of an attribute to group by, such as `attributes.language`.
+    pub fn parse_group_by(group_by: &str) -> Result<Vec<PathSegment>, String> {
+        let mut parser = Parser::new(group_by)?;
+        let path = parser.path(Scope::Attributes)?;
+        parser.finish()?;
+        Ok(path)
+    }
+
+    /// The aggregation of the rows of `attributes_index` which match
+    /// `condition`, as rows of the group `key`, its `count` and its `metrics`
+    /// as a jsonb array in the order of the metrics. Its parameters are
+    /// appended to `values` after the ones of the condition.
+    pub fn to_sql(&self, condition: &str, values: &mut Vec<Value>) -> String {
+        let metrics: Vec<String> = self.metrics.iter().map(|m| m.to_sql(values)).collect();
+        let metrics = format!("jsonb_build_array({})", metrics.join(", "));
+        match &self.group_by {
+            None => format!(
+                "select null::jsonb as key, count(*) as count, {} as metrics from attributes_index where {}",
+                metrics, condition
+            ),
+            Some(path) => {
+                let path = path_sql(Scope::Attributes, path, values);
+                format!(
+                    "select g.key as key, count(*) as count, {} as metrics from attributes_index cross join lateral jsonb_array_elements(case when jsonb_typeof({}) = 'array' then {} else jsonb_build_array({}) end) as g(key) where {} group by g.key order by count desc, g.key limit {}",
+                    metrics, path, path, path, condition, self.limit
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sort() {
+        assert_eq!(
+            AttributeSort::parse("attributes.price desc").unwrap(),
+            AttributeSort {
+                path: vec![PathSegment::Field("price".into())],
+                order: SortOrder::Desc,
+            }
+        );
+        assert_eq!(
+            AttributeSort::parse("attributes.price").unwrap().order,
+            SortOrder::Asc
+        );
+        assert!(AttributeSort::parse("attributes.price sideways").is_err());
+    }
+
+    #[test]
+    fn test_parse_metrics() {
+        let metrics =
+            Metric::parse_list(r#"avg(attributes.price), MAX(attributes["unit price"][0])"#)
+                .unwrap();
+        assert_eq!(
+            metrics,
+            vec![
+                Metric {
+                    function: MetricFunction::Avg,
+                    path: vec![PathSegment::Field("price".into())],
+                },
+                Metric {
+                    function: MetricFunction::Max,
+                    path: vec![
+                        PathSegment::Field("unit price".into()),
+                        PathSegment::Element(0)
+                    ],
+                },
+            ]
+        );
+        assert_eq!(metrics[0].to_string(), "avg(attributes.price)");
+        assert_eq!(
+            metrics[1].to_string(),
+            r#"max(attributes["unit price"][0])"#
+        );
+        assert!(Metric::parse_list("").unwrap().is_empty());
+        assert!(Metric::parse_list("median(attributes.price)").is_err());
+        assert!(Metric::parse_list("avg(attributes.price) min(attributes.price)").is_err());
+        assert!(Metric::parse_list("avg(price)").is_err());
+    }
+
+    #[test]
+    fn test_aggregation_sql() {
+        let aggregation = AttributeAggregation {
+            group_by: Some(AttributeAggregation::parse_group_by("attributes.tags").unwrap()),
+            metrics: Metric::parse_list("sum(attributes.pages)").unwrap(),
+            limit: 5,
+        };
+        let mut values = vec!["default".into()];
+        assert_eq!(
+            aggregation.to_sql("namespace=$1", &mut values),
+            "select g.key as key, count(*) as count, jsonb_build_array(sum(case when jsonb_typeof(data -> $2) = 'number' then (data -> $2)::float8 end)) as metrics from attributes_index cross join lateral jsonb_array_elements(case when jsonb_typeof(data -> $3) = 'array' then data -> $3 else jsonb_build_array(data -> $3) end) as g(key) where namespace=$1 group by g.key order by count desc, g.key limit 5"
+        );
+        assert_eq!(values.len(), 3);
+
+        let aggregation = AttributeAggregation {
+            group_by: None,
+            metrics: vec![],
+            limit: 5,
+        };
+        assert_eq!(
+            aggregation.to_sql("namespace=$1", &mut vec!["default".into()]),
+            "select null::jsonb as key, count(*) as count, jsonb_build_array() as metrics from attributes_index where namespace=$1"
+        );
+        assert!(AttributeAggregation::parse_group_by("attributes.tags desc").is_err());
+    }
+}
@@ -0,0 +1,185 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{Method, Request},
+    middleware::Next,
+    response::Response,
+};
+use tracing::error;
+
+use crate::{
+    api::Namespace,
+    auth::Principal,
+    persistence::{AuditAction, AuditEvent, Repository},
+    read_only::is_write,
+};
+
+/// The principal of the requests made while authentication is disabled.
+const ANONYMOUS_PRINCIPAL: &str = "anonymous";
+
+/// The kind of mutation a request makes and the repository it targets, or
+/// `None` when the request does not change the state of the server.
+fn audit_action(method: &Method, path: &str) -> Option<(AuditAction, Option<String>)> {
+    if !is_write(method, path) {
+        return None;
+    }
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let action = match (method.as_str(), segments.as_slice()) {
+        ("POST", ["repositories"]) | ("POST", ["repository_templates", _, "repositories"]) => {
+            AuditAction::RepositoryUpsert
+        }
+        ("DELETE", ["repositories", _]) => AuditAction::RepositoryDelete,
+        ("POST", ["repositories", _, "extractor_bindings"]) |
+        ("POST", ["repositories", _, "extractor_bindings", _, "pause" | "resume"]) => {
+            AuditAction::ExtractorBindingChange
+        }
+        (
+            "POST",
+            ["repositories", _, "add_texts" | "bulk_add_texts" | "upload_file" | "upload_archive" | "ingest"],
+        ) => AuditAction::ContentAdd,
+        ("POST", ["repositories", _, "ingestion_jobs"]) => AuditAction::ContentAdd,
+        ("DELETE", ["repositories", _, "content", ..]) => AuditAction::ContentDelete,
+        ("POST", ["repositories", _, "content", _, "restore"]) => AuditAction::ContentRestore,
+        ("DELETE", ["repositories", _, "indexes", ..]) => AuditAction::IndexDrop,
+        ("POST", ["repositories", _, "indexes", _, "reindex"]) => AuditAction::IndexReindex,
+        ("POST", ["repositories", _, "run_extractors"]) => AuditAction::ExtractorRun,
+        ("PUT", ["repositories", _, "text_analysis"]) => AuditAction::TextAnalysisUpdate,
+        ("PUT", ["repositories", _, "retention"]) => AuditAction::RetentionUpdate,
+        ("PUT", ["repositories", _, "indexes", _, "reranker"]) => AuditAction::IndexRerankerUpdate,
+        ("PUT", ["repositories", _, "roles", _]) => AuditAction::RoleGrant,
+        ("DELETE", ["repositories", _, "roles", _]) => AuditAction::RoleRevoke,
+        ("POST", ["repositories", _, "legal_holds"]) => AuditAction::LegalHoldPlace,
+        ("DELETE", ["repositories", _, "legal_holds", _]) => AuditAction::LegalHoldRelease,
+        ("POST", ["api_keys"]) => AuditAction::ApiKeyCreate,
+        ("DELETE", ["api_keys", _]) => AuditAction::ApiKeyDelete,
+        _ => AuditAction::Other,
+    };
+    let repository = match segments.as_slice() {
+        ["repositories", repository, ..] => Some(repository.to_string()),
+        _ => None,
+    };
+    Some((action, repository))
+}
+
+/// Appends a request to the audit log when it changes the state of the
+/// server, along with the status it was answered with.
+pub async fn record(
+    repository: &Repository,
+    namespace: &str,
+    principal: Option<&Principal>,
+    method: &Method,
+    path: &str,
+    status: u16,
+) {
+    let Some((action, target)) = audit_action(method, path) else {
+        return;
+    };
+    let principal = principal.map_or(ANONYMOUS_PRINCIPAL, |p| p.0.as_str());
+    let event = AuditEvent::new(
+        principal,
+        action,
+        target.as_deref(),
+        method.as_str(),
+        path,
+        status,
+    );
+    if let Err(err) = repository.append_audit_event(namespace, &event).await {
+        error!("unable to append to the audit log: {}", err.to_string());
+    }
+}
+
+/// Appends every request which changes the state of the server to the audit
+/// log, along with the status it was answered with. Has to run after the
+/// authentication, which tells the principal of the request.
+pub async fn record_mutations<B>(
+    State(repository): State<Arc<Repository>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    if audit_action(&method, &path).is_none() {
+        return next.run(request).await;
+    }
+    // Requests in an invalid namespace are rejected by the handlers.
+    let Ok(Namespace(namespace)) = Namespace::from_headers(request.headers()) else {
+        return next.run(request).await;
+    };
+    let principal = request.extensions().get::<Principal>().cloned();
+    let response = next.run(request).await;
+    record(
+        &repository,
+        &namespace,
+        principal.as_ref(),
+        &method,
+        &path,
+        response.status().as_u16(),
+    )
+    .await;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_action() {
+        assert_eq!(
+            audit_action(&Method::GET, "/repositories/docs/content"),
+            None
+        );
+        assert_eq!(
+            audit_action(&Method::POST, "/repositories/docs/search"),
+            None
+        );
+        assert_eq!(
+            audit_action(&Method::POST, "/repositories"),
+            Some((AuditAction::RepositoryUpsert, None))
+        );
+        assert_eq!(
+            audit_action(&Method::POST, "/repository_templates/rag/repositories"),
+            Some((AuditAction::RepositoryUpsert, None))
+        );
+        assert_eq!(
+            audit_action(&Method::POST, "/repositories/docs/upload_file"),
+            Some((AuditAction::ContentAdd, Some("docs".into())))
+        );
+        assert_eq!(
+            audit_action(&Method::POST, "/repositories/docs/ingestion_jobs"),
+            Some((AuditAction::ContentAdd, Some("docs".into())))
+        );
+        assert_eq!(
+            audit_action(
+                &Method::POST,
+                "/repositories/docs/extractor_bindings/openai/pause"
+            ),
+            Some((AuditAction::ExtractorBindingChange, Some("docs".into())))
+        );
+        assert_eq!(
+            audit_action(&Method::DELETE, "/repositories/docs"),
+            Some((AuditAction::RepositoryDelete, Some("docs".into())))
+        );
+        assert_eq!(
+            audit_action(&Method::DELETE, "/repositories/docs/indexes/embeddings"),
+            Some((AuditAction::IndexDrop, Some("docs".into())))
+        );
+        assert_eq!(
+            audit_action(&Method::DELETE, "/repositories/docs/legal_holds/abc"),
+            Some((AuditAction::LegalHoldRelease, Some("docs".into())))
+        );
+        assert_eq!(
+            audit_action(&Method::POST, "/repositories/docs/content/abc/restore"),
+            Some((AuditAction::ContentRestore, Some("docs".into())))
+        );
+        assert_eq!(
+            audit_action(&Method::DELETE, "/api_keys/abc"),
+            Some((AuditAction::ApiKeyDelete, None))
+        );
+        assert_eq!(
+            audit_action(&Method::POST, "/repositories/docs/events"),
+            Some((AuditAction::Other, Some("docs".into())))
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    api::{IndexifyAPIError, Namespace},
+    persistence::{ApiKey, ApiKeyScope, Repository},
+    server_config::AuthConfig,
+};
+
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// Operations on a repository which are sent with `POST` but only read.
+const SEARCH_OPERATIONS: [&str; 2] = ["search", "keyword_search"];
+
+/// Operations on a repository which only add content to it.
+const INGEST_OPERATIONS: [&str; 7] = [
+    "add_texts",
+    "bulk_add_texts",
+    "upload_file",
+    "upload_archive",
+    "events",
+    "ingest",
+    "run_extractors",
+];
+
+/// The id of the api key a request was authenticated with, made available to
+/// the layers and handlers after the authentication.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Principal(pub String);
+
+/// Paths which are served without an api key.
+const PUBLIC_PATHS: [&str; 7] = [
+    "metrics",
+    "api-docs-ui",
+    "api-docs",
+    "redoc",
+    "rapidoc",
+    "healthz",
+    "readyz",
+];
+
+#[derive(Clone, Debug)]
+pub struct AuthState {
+    repository: Arc<Repository>,
+    admin_key_hash: Option<String>,
+}
+
+impl AuthState {
+    pub fn new(repository: Arc<Repository>, config: &AuthConfig) -> Self {
+        Self {
+            repository,
+            admin_key_hash: config.admin_key.as_deref().map(ApiKey::hash_secret),
+        }
+    }
+
+    async fn api_key(&self, secret: &str) -> Result<Option<ApiKey>> {
+        let key_hash = ApiKey::hash_secret(secret);
+        if self.admin_key_hash.as_ref() == Some(&key_hash) {
+            return Ok(Some(ApiKey {
+                id: "admin".into(),
+                namespace: None,
+                name: "admin".into(),
+                key_hash,
+                scope: ApiKeyScope::Admin,
+                repositories: vec![],
+                created_at: 0,
+            }));
+        }
+        self.repository.api_key_by_hash(&key_hash).await
+    }
+
+    /// Whether a role granted to the key on the repository grants the scope.
+    async fn role_allows(
+        &self,
+        api_key: &ApiKey,
+        scope: ApiKeyScope,
+        namespace: &str,
+        repository: Option<&str>,
+    ) -> Result<bool> {
+        let Some(repository) = repository else {
+            return Ok(false);
+        };
+        if api_key.namespace.as_deref() != Some(namespace) {
+            return Ok(false);
+        }
+        let role = self
+            .repository
+            .repository_role(namespace, repository, &api_key.id)
+            .await?;
+        Ok(role.is_some_and(|role| role.scope() >= scope))
+    }
+
+    /// Checks that a request carries an api key with the scope required by
+    /// the route, in the namespace of the request and on the repository the
+    /// route targets, and returns the principal of the key. A role granted
+    /// to the key on the repository grants the scope as well. Returns `None`
+    /// for the routes which are served without an api key.
+    pub async fn authorize(
+        &self,
+        method: &Method,
+        path: &str,
+        headers: &HeaderMap,
+    ) -> Result<Option<Principal>, IndexifyAPIError> {
+        let Some((scope, repository)) = required_access(method, path) else {
+            return Ok(None);
+        };
+        let Namespace(namespace) = Namespace::from_headers(headers)?;
+        let secret = api_key_secret(headers).ok_or(IndexifyAPIError::new(
+            StatusCode::UNAUTHORIZED,
+            "missing api key".to_string(),
+        ))?;
+        let api_key = self
+            .api_key(&secret)
+            .await
+            .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .ok_or(IndexifyAPIError::new(
+                StatusCode::UNAUTHORIZED,
+                "invalid api key".to_string(),
+            ))?;
+        let allowed = api_key.allows(scope, &namespace, repository.as_deref()) ||
+            self.role_allows(&api_key, scope, &namespace, repository.as_deref())
+                .await
+                .map_err(|e| {
+                    IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                })?;
+        if !allowed {
+            return Err(IndexifyAPIError::new(
+                StatusCode::FORBIDDEN,
+                format!(
+                    "api key {} does not have the {} scope on {} in namespace {}",
+                    api_key.name,
+                    scope,
+                    repository.as_deref().unwrap_or("this server"),
+                    namespace
+                ),
+            ));
+        }
+        Ok(Some(Principal(api_key.id)))
+    }
+}
+
+/// The scope a request requires and the repository it targets, or `None` when
+/// the request does not need an api key.
+fn required_access(method: &Method, path: &str) -> Option<(ApiKeyScope, Option<String>)> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    if segments == [""] || PUBLIC_PATHS.contains(&segments[0]) {
+        return None;
+    }
+    let access = match segments.as_slice() {
+        ["repositories"] if method == Method::GET => (ApiKeyScope::Read, None),
+        ["repositories", repository, "roles", ..] => {
+            (ApiKeyScope::Admin, Some(repository.to_string()))
+        }
+        ["repositories", repository, "sessions", _, operation] => {
+            let scope = if method == Method::GET || *operation == "query" {
+                ApiKeyScope::Read
+            } else {
+                ApiKeyScope::Ingest
+            };
+            (scope, Some(repository.to_string()))
+        }
+        ["repositories", repository, ..] => {
+            let operation = segments.get(2).copied();
+            let scope = if method == Method::GET ||
+                operation.is_some_and(|o| SEARCH_OPERATIONS.contains(&o))
+            {
+                ApiKeyScope::Read
+            } else if method == Method::POST &&
+                operation.is_some_and(|o| INGEST_OPERATIONS.contains(&o))
+            {
+                ApiKeyScope::Ingest
+            } else {
+                ApiKeyScope::Admin
+            };
+            (scope, Some(repository.to_string()))
+        }
+        ["extractors", "extract"] => (ApiKeyScope::Ingest, None),
+        _ if method == Method::GET &&
+            !["api_keys", "audit_log", "query_load", "embedding_cache"]
+                .contains(&segments[0]) =>
+        {
+            (ApiKeyScope::Read, None)
+        }
+        _ => (ApiKeyScope::Admin, None),
+    };
+    Some(access)
+}
+
+/// The key of a request, from either the `x-api-key` header or a bearer token.
+fn api_key_secret(headers: &HeaderMap) -> Option<String> {
+    if let Some(key) = headers.get(API_KEY_HEADER) {
+        return key.to_str().ok().map(|k| k.to_string());
+    }
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|k| k.trim().to_string())
+}
+
+/// Rejects requests which are not authorized, see [`AuthState::authorize`].
+pub async fn authenticate<B>(
+    State(state): State<AuthState>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, IndexifyAPIError> {
+    let principal = state
+        .authorize(request.method(), request.uri().path(), request.headers())
+        .await?;
+    if let Some(principal) = principal {
+        request.extensions_mut().insert(principal);
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_access() {
+        assert_eq!(required_access(&Method::GET, "/"), None);
+        assert_eq!(
+            required_access(&Method::GET, "/api-docs/openapi.json"),
+            None
+        );
+        assert_eq!(required_access(&Method::GET, "/readyz"), None);
+        assert_eq!(
+            required_access(&Method::POST, "/repositories/docs/search"),
+            Some((ApiKeyScope::Read, Some("docs".into())))
+        );
+        assert_eq!(
+            required_access(&Method::POST, "/repositories/docs/keyword_search"),
+            Some((ApiKeyScope::Read, Some("docs".into())))
+        );
+        assert_eq!(
+            required_access(&Method::POST, "/repositories/docs/add_texts"),
+            Some((ApiKeyScope::Ingest, Some("docs".into())))
+        );
+        assert_eq!(
+            required_access(&Method::POST, "/repositories/docs/sessions/chat-1/query"),
+            Some((ApiKeyScope::Read, Some("docs".into())))
+        );
+        assert_eq!(
+            required_access(&Method::POST, "/repositories/docs/sessions/chat-1/events"),
+            Some((ApiKeyScope::Ingest, Some("docs".into())))
+        );
+        assert_eq!(
+            required_access(&Method::PUT, "/repositories/docs/text_analysis"),
+            Some((ApiKeyScope::Admin, Some("docs".into())))
+        );
+        assert_eq!(
+            required_access(&Method::GET, "/repositories/docs/roles"),
+            Some((ApiKeyScope::Admin, Some("docs".into())))
+        );
+        assert_eq!(
+            required_access(&Method::POST, "/repositories"),
+            Some((ApiKeyScope::Admin, None))
+        );
+        assert_eq!(
+            required_access(&Method::GET, "/api_keys"),
+            Some((ApiKeyScope::Admin, None))
+        );
+        assert_eq!(
+            required_access(&Method::GET, "/audit_log"),
+            Some((ApiKeyScope::Admin, None))
+        );
+        assert_eq!(
+            required_access(&Method::GET, "/query_load"),
+            Some((ApiKeyScope::Admin, None))
+        );
+        assert_eq!(
+            required_access(&Method::GET, "/embedding_cache"),
+            Some((ApiKeyScope::Admin, None))
+        );
+    }
+
+    #[test]
+    fn test_api_key_scopes() {
+        let (key, secret) =
+            ApiKey::generate("ingest", "team_a", ApiKeyScope::Ingest, vec!["docs".into()]);
+        assert_eq!(key.key_hash, ApiKey::hash_secret(&secret));
+        assert!(key.allows(ApiKeyScope::Read, "team_a", Some("docs")));
+        assert!(key.allows(ApiKeyScope::Ingest, "team_a", Some("docs")));
+        assert!(!key.allows(ApiKeyScope::Admin, "team_a", Some("docs")));
+        assert!(!key.allows(ApiKeyScope::Read, "team_a", Some("mail")));
+        assert!(!key.allows(ApiKeyScope::Read, "team_b", Some("docs")));
+        assert!(key.allows(ApiKeyScope::Read, "team_a", None));
+
+        let (admin, _) =
+            ApiKey::generate("admin", "team_a", ApiKeyScope::Admin, vec!["docs".into()]);
+        assert!(admin.allows(ApiKeyScope::Admin, "team_a", Some("docs")));
+        assert!(!admin.allows(ApiKeyScope::Admin, "team_a", None));
+    }
+
+    #[test]
+    fn test_api_key_secret() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(api_key_secret(&headers), None);
+        headers.insert(header::AUTHORIZATION, "Bearer idx_abc".parse().unwrap());
+        assert_eq!(api_key_secret(&headers), Some("idx_abc".into()));
+        headers.insert(API_KEY_HEADER, "idx_def".parse().unwrap());
+        assert_eq!(api_key_secret(&headers), Some("idx_def".into()));
+    }
+}
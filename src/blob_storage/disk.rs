@@ -1,12 +1,26 @@
+use std::{
+    io::{self, SeekFrom},
+    ops::Range,
+};
+
 use async_trait::async_trait;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use tokio::{
     fs::File,
-    io::{AsyncReadExt, AsyncWriteExt},
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+    sync::mpsc,
 };
+use tokio_stream::wrappers::ReceiverStream;
 
 use super::{BlobStorage, BlobStorageReader};
 
+/// The prefix of the files written by health checks. Keys of blobs are ids,
+/// which don't start with a dot.
+const HEALTH_CHECK_FILE: &str = ".indexify-health-check";
+
+/// The most bytes of a file sent at once when it is streamed.
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
 #[derive(Debug)]
 pub struct DiskStorage {
     base_dir: String,
@@ -36,6 +50,21 @@ impl BlobStorage for DiskStorage {
         std::fs::remove_file(path)?;
         Ok(())
     }
+
+    /// Writes and removes a file in the base directory, which fails when the
+    /// disk is full or the directory is missing or read-only.
+    async fn health_check(&self) -> Result<(), anyhow::Error> {
+        // Concurrent checks write their own files.
+        let path = format!(
+            "{}/{}-{:x}",
+            self.base_dir,
+            HEALTH_CHECK_FILE,
+            rand::random::<u64>()
+        );
+        tokio::fs::write(&path, b"ok").await?;
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
 }
 
 pub struct DiskStorageReader {}
@@ -50,4 +79,42 @@ impl BlobStorageReader for DiskStorageReader {
         file.read_to_end(&mut buffer).await?;
         Ok(buffer)
     }
+
+    #[tracing::instrument(skip(self))]
+    async fn size(&self, path: &str) -> Result<u64, anyhow::Error> {
+        let path = path.strip_prefix("file://").unwrap_or(path);
+        Ok(tokio::fs::metadata(path).await?.len())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn stream(
+        &self,
+        path: &str,
+        range: Range<u64>,
+    ) -> Result<ReceiverStream<io::Result<Bytes>>, anyhow::Error> {
+        let path = path.strip_prefix("file://").unwrap_or(path);
+        let mut file = File::open(path).await?;
+        file.seek(SeekFrom::Start(range.start)).await?;
+        let mut file = file.take(range.end.saturating_sub(range.start));
+        let (tx, rx) = mpsc::channel(2);
+        tokio::spawn(async move {
+            loop {
+                let mut buffer = BytesMut::with_capacity(STREAM_CHUNK_SIZE);
+                match file.read_buf(&mut buffer).await {
+                    Ok(0) => break,
+                    Ok(_) => {
+                        // The client went away.
+                        if tx.send(Ok(buffer.freeze())).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(err) => {
+                        let _ = tx.send(Err(err)).await;
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(ReceiverStream::new(rx))
+    }
 }
@@ -1,8 +1,9 @@
-use std::sync::Arc;
+use std::{io, ops::Range, sync::Arc};
 
 use anyhow::anyhow;
 use async_trait::async_trait;
 use bytes::Bytes;
+use tokio_stream::wrappers::ReceiverStream;
 
 use crate::server_config::BlobStorageConfig;
 
@@ -16,11 +17,25 @@ pub type BlobStorageReaderTS = Arc<dyn BlobStorageReader + Sync + Send>;
 pub trait BlobStorage {
     async fn put(&self, key: &str, data: Bytes) -> Result<String, anyhow::Error>;
     fn delete(&self, key: &str) -> Result<(), anyhow::Error>;
+
+    /// Checks that blobs can be written, for the readiness of the server.
+    async fn health_check(&self) -> Result<(), anyhow::Error>;
 }
 
 #[async_trait]
 pub trait BlobStorageReader {
     async fn get(&self, key: &str) -> Result<Vec<u8>, anyhow::Error>;
+
+    /// The size of the blob, in bytes.
+    async fn size(&self, key: &str) -> Result<u64, anyhow::Error>;
+
+    /// The bytes of the blob in the range, read as the stream is consumed.
+    /// An error ends the stream with the error.
+    async fn stream(
+        &self,
+        key: &str,
+        range: Range<u64>,
+    ) -> Result<ReceiverStream<io::Result<Bytes>>, anyhow::Error>;
 }
 
 pub struct BlobStorageBuilder {
@@ -33,7 +48,8 @@ impl BlobStorageBuilder {
     }
 
     pub fn reader_from_link(link: &str) -> Result<BlobStorageReaderTS, anyhow::Error> {
-        if link.starts_with("file://") {
+        // Disk storage links to the files it stores by their path.
+        if link.starts_with("file://") || !link.contains("://") {
             return Ok(Arc::new(disk::DiskStorageReader {}));
         }
         Err(anyhow!("Unknown blob storage backend {}", link))
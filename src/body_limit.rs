@@ -0,0 +1,121 @@
+use anyhow::anyhow;
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, Method, Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use tokio_stream::StreamExt;
+
+use crate::{api::IndexifyAPIError, server_config::BodyLimitConfig};
+
+/// Whether a request uploads a file, an archive, a stream of content or the
+/// manifest of an ingestion job, whose body is limited separately from the
+/// other requests.
+pub fn is_upload(method: &Method, path: &str) -> bool {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    method == Method::POST &&
+        matches!(
+            segments.as_slice(),
+            [
+                "repositories",
+                _,
+                "upload_file" | "upload_archive" | "ingest" | "bulk_add_texts" | "ingestion_jobs"
+            ]
+        )
+}
+
+/// The largest body of a request, unlimited when `None`.
+pub fn max_body_bytes(config: &BodyLimitConfig, method: &Method, path: &str) -> Option<usize> {
+    if is_upload(method, path) {
+        config.max_upload_bytes
+    } else {
+        Some(config.max_request_bytes)
+    }
+}
+
+/// Rejects requests whose `Content-Length` is over the limit with `413 Payload
+/// Too Large`, and fails the reading of bodies without a length once they
+/// grow over it.
+pub async fn limit_bodies(
+    State(config): State<BodyLimitConfig>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Result<Response, IndexifyAPIError> {
+    let Some(limit) = max_body_bytes(&config, request.method(), request.uri().path()) else {
+        return Ok(next.run(request).await);
+    };
+    let content_length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if content_length.is_some_and(|length| length > limit) {
+        return Err(IndexifyAPIError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("the body of the request is larger than {} bytes", limit),
+        ));
+    }
+    let request = request.map(|body| {
+        let mut read = 0;
+        Body::wrap_stream(body.map(move |chunk| {
+            let chunk = chunk?;
+            read += chunk.len();
+            if read > limit {
+                return Err(anyhow!(
+                    "the body of the request is larger than {} bytes",
+                    limit
+                ));
+            }
+            Ok(chunk)
+        }))
+    });
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_body_bytes() {
+        let config = BodyLimitConfig {
+            max_request_bytes: 1024,
+            max_upload_bytes: None,
+        };
+        assert_eq!(
+            max_body_bytes(&config, &Method::POST, "/repositories/docs/add_texts"),
+            Some(1024)
+        );
+        assert_eq!(
+            max_body_bytes(&config, &Method::POST, "/repositories/docs/upload_file"),
+            None
+        );
+        let config = BodyLimitConfig {
+            max_upload_bytes: Some(4096),
+            ..config
+        };
+        assert_eq!(
+            max_body_bytes(&config, &Method::POST, "/repositories/docs/ingest"),
+            Some(4096)
+        );
+        assert_eq!(
+            max_body_bytes(&config, &Method::GET, "/repositories/docs/ingest"),
+            Some(1024)
+        );
+        // Bulk imports and the manifests of ingestion jobs are uploads.
+        assert_eq!(
+            max_body_bytes(&config, &Method::POST, "/repositories/docs/bulk_add_texts"),
+            Some(4096)
+        );
+        assert_eq!(
+            max_body_bytes(&config, &Method::POST, "/repositories/docs/ingestion_jobs"),
+            Some(4096)
+        );
+        assert_eq!(
+            max_body_bytes(&config, &Method::GET, "/repositories/docs/ingestion_jobs"),
+            Some(1024)
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use super::api_key;
+
+const API_KEY_ENV: &str = "COHERE_API_KEY";
+
+/// The most texts embedded in one request.
+const MAX_BATCH_SIZE: usize = 96;
+
+/// The texts are embedded as documents. Queries are embedded through the same
+/// extractor, so they are embedded as documents as well.
+const INPUT_TYPE: &str = "search_document";
+
+#[derive(Serialize)]
+struct EmbedRequest<'a> {
+    model: &'a str,
+    texts: &'a [String],
+    input_type: &'a str,
+}
+
+#[derive(Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+/// Embeds the texts with the `/embed` API of Cohere.
+pub async fn embed(
+    client: &reqwest::Client,
+    base_url: &str,
+    model: &str,
+    texts: &[String],
+) -> Result<Vec<Vec<f32>>> {
+    let api_key = api_key(API_KEY_ENV)?;
+    let mut embeddings = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(MAX_BATCH_SIZE) {
+        let resp = client
+            .post(format!("{}/embed", base_url.trim_end_matches('/')))
+            .bearer_auth(&api_key)
+            .json(&EmbedRequest {
+                model,
+                texts: batch,
+                input_type: INPUT_TYPE,
+            })
+            .send()
+            .await
+            .map_err(|e| anyhow!("unable to embed text with cohere: {}", e))?;
+        if !resp.status().is_success() {
+            return Err(anyhow!(
+                "unable to embed text with cohere: status: {}, error: {}",
+                resp.status(),
+                resp.text().await?
+            ));
+        }
+        let batch_embeddings = resp
+            .json::<EmbedResponse>()
+            .await
+            .map_err(|e| anyhow!("unable to decode cohere embeddings: {}", e))?
+            .embeddings;
+        if batch_embeddings.len() != batch.len() {
+            return Err(anyhow!(
+                "cohere returned {} embeddings for {} texts",
+                batch_embeddings.len(),
+                batch.len()
+            ));
+        }
+        embeddings.extend(batch_embeddings);
+    }
+    Ok(embeddings)
+}
@@ -0,0 +1,226 @@
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read},
+};
+
+use anyhow::{anyhow, Result};
+use quick_xml::events::Event;
+use serde_json::json;
+use tracing::warn;
+
+use crate::internal_api::{Content, ExtractorDescription, ExtractorSchema};
+
+/// Extracts the text of PDFs, Word documents and HTML pages as text content
+/// derived from them, page by page.
+pub const DOCUMENT_TEXT_EXTRACTOR: &str = "indexify/document-text";
+
+/// The metadata of the page of a document a text was extracted from,
+/// counted from 1.
+pub const PAGE_METADATA_KEY: &str = "page";
+
+const DOCX: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+/// The width html is wrapped at, wide enough that paragraphs are not split
+/// into lines.
+const HTML_WIDTH: usize = 10_000;
+
+pub fn description() -> ExtractorDescription {
+    ExtractorDescription {
+        name: DOCUMENT_TEXT_EXTRACTOR.into(),
+        description: "Extracts the text of PDFs, Word documents and HTML pages, page by page"
+            .into(),
+        input_params: json!({"type": "object", "properties": {}}),
+        // The text is added as content of the repository, not to an index.
+        schema: ExtractorSchema {
+            output: HashMap::new(),
+        },
+    }
+}
+
+/// The text of every page of the document, as text content with the page in
+/// its metadata. Pages without text are left out.
+pub fn extract(content: &Content) -> Result<Vec<Content>> {
+    let content_type: mime::Mime = content
+        .content_type
+        .parse()
+        .map_err(|_| anyhow!("invalid content type {}", content.content_type))?;
+    let pages = match content_type.essence_str() {
+        "application/pdf" => pdf_pages(&content.source)?,
+        DOCX => docx_pages(&content.source)?,
+        "text/html" | "application/xhtml+xml" => {
+            vec![html2text::from_read(content.source.as_slice(), HTML_WIDTH)]
+        }
+        other => {
+            return Err(anyhow!(
+                "{} can not extract the text of content of type {}",
+                DOCUMENT_TEXT_EXTRACTOR,
+                other
+            ))
+        }
+    };
+    Ok(pages
+        .into_iter()
+        .enumerate()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .map(|(i, text)| Content {
+            content_type: mime::TEXT_PLAIN.to_string(),
+            source: text.trim().as_bytes().to_vec(),
+            feature: None,
+            metadata: HashMap::from([(PAGE_METADATA_KEY.into(), json!(i + 1))]),
+        })
+        .collect())
+}
+
+fn pdf_pages(data: &[u8]) -> Result<Vec<String>> {
+    let document =
+        lopdf::Document::load_mem(data).map_err(|e| anyhow!("unable to read the pdf: {}", e))?;
+    Ok(document
+        .get_pages()
+        .into_keys()
+        .map(|page| {
+            // A page whose text can not be read, such as a page in a font
+            // without a text encoding, is left out instead of failing the
+            // other pages.
+            document.extract_text(&[page]).unwrap_or_else(|e| {
+                warn!(
+                    "unable to extract the text of page {} of a pdf: {}",
+                    page, e
+                );
+                String::new()
+            })
+        })
+        .collect())
+}
+
+/// The paragraphs of the body of a Word document. Pages start at the page
+/// breaks of the document, and at the breaks where Word last laid out a
+/// page.
+fn docx_pages(data: &[u8]) -> Result<Vec<String>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .map_err(|e| anyhow!("unable to read the word document: {}", e))?;
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .map_err(|_| anyhow!("the word document has no word/document.xml"))?
+        .read_to_string(&mut xml)?;
+    let mut reader = quick_xml::Reader::from_str(&xml);
+    let mut pages = vec![String::new()];
+    let mut in_text = false;
+    loop {
+        let page = pages.last_mut().unwrap();
+        match reader.read_event()? {
+            Event::Start(e) if e.name().as_ref() == b"w:t" => in_text = true,
+            Event::End(e) if e.name().as_ref() == b"w:t" => in_text = false,
+            Event::Text(text) if in_text => page.push_str(&text.unescape()?),
+            Event::End(e) if e.name().as_ref() == b"w:p" => page.push('\n'),
+            Event::Empty(e) => match e.name().as_ref() {
+                b"w:tab" => page.push('\t'),
+                b"w:br" | b"w:cr" => {
+                    let page_break = e
+                        .try_get_attribute("w:type")?
+                        .is_some_and(|a| a.value.as_ref() == b"page");
+                    if !page_break {
+                        page.push('\n');
+                    } else if !page.trim().is_empty() {
+                        pages.push(String::new());
+                    }
+                }
+                // Word marks the page it laid out after a page break as well.
+                b"w:lastRenderedPageBreak" if !page.trim().is_empty() => pages.push(String::new()),
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn docx(body: &str) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("word/document.xml", zip::write::FileOptions::default())
+            .unwrap();
+        write!(
+            writer,
+            r#"<?xml version="1.0" encoding="UTF-8"?><w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>{}</w:body></w:document>"#,
+            body
+        )
+        .unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn texts(content: &[Content]) -> Vec<(String, serde_json::Value)> {
+        content
+            .iter()
+            .map(|c| {
+                (
+                    String::from_utf8(c.source.clone()).unwrap(),
+                    c.metadata[PAGE_METADATA_KEY].clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_docx_pages() {
+        let content = Content {
+            content_type: DOCX.into(),
+            source: docx(
+                r#"<w:p><w:r><w:t>Terms &amp; conditions</w:t></w:r></w:p>
+                <w:p><w:r><w:t xml:space="preserve">Name:</w:t><w:tab/><w:t>value</w:t></w:r></w:p>
+                <w:p><w:r><w:br w:type="page"/></w:r></w:p>
+                <w:p><w:r><w:lastRenderedPageBreak/><w:t>Appendix</w:t></w:r></w:p>"#,
+            ),
+            feature: None,
+            metadata: HashMap::new(),
+        };
+        assert_eq!(
+            texts(&extract(&content).unwrap()),
+            vec![
+                ("Terms & conditions\nName:\tvalue".to_string(), json!(1)),
+                ("Appendix".to_string(), json!(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_html() {
+        let content = Content {
+            content_type: "text/html; charset=utf-8".into(),
+            source: b"<html><body><h1>Guide</h1><p>Install it.</p></body></html>".to_vec(),
+            feature: None,
+            metadata: HashMap::new(),
+        };
+        let pages = extract(&content).unwrap();
+        assert_eq!(pages.len(), 1);
+        let text = String::from_utf8(pages[0].source.clone()).unwrap();
+        assert!(text.contains("Guide"));
+        assert!(text.contains("Install it."));
+        assert_eq!(pages[0].content_type, mime::TEXT_PLAIN.to_string());
+    }
+
+    #[test]
+    fn test_unsupported_content() {
+        let content = Content {
+            content_type: "image/png".into(),
+            source: vec![],
+            feature: None,
+            metadata: HashMap::new(),
+        };
+        assert!(extract(&content).is_err());
+        let content = Content {
+            content_type: "application/pdf".into(),
+            source: b"not a pdf".to_vec(),
+            feature: None,
+            metadata: HashMap::new(),
+        };
+        assert!(extract(&content).is_err());
+    }
+}
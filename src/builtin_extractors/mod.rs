@@ -0,0 +1,520 @@
+//! Extractors which run in the coordinator, so that a deployment without any
+//! executors can still extract content. Their work is assigned to the
+//! coordinator like the work of other extractors is assigned to executors.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use strum::{Display, EnumString};
+
+pub use self::{
+    documents::DOCUMENT_TEXT_EXTRACTOR,
+    transcription::{TranscriptSegment, TRANSCRIPTION_EXTRACTOR},
+};
+use crate::{
+    chunking,
+    content_reader::ContentReader,
+    internal_api::{
+        Content,
+        ExtractorDescription,
+        ExtractorSchema,
+        Feature,
+        FeatureType,
+        OutputSchema,
+        Work,
+        WorkState,
+        WorkStatus,
+    },
+    persistence::{default_modalities, EmbeddingSchema, Extractor, ExtractorOutputSchema},
+    server_config::BuiltinExtractorsConfig,
+    vectordbs::IndexDistance,
+};
+
+mod cohere;
+mod documents;
+#[cfg(feature = "onnx")]
+mod onnx;
+mod openai;
+pub mod transcription;
+
+/// Embeds text with the embedding model of a provider.
+pub const EMBEDDING_EXTRACTOR: &str = "indexify/embedding";
+
+/// The id the coordinator runs the work of the embedding extractor as, in
+/// place of the id of an executor.
+pub const BUILTIN_EXECUTOR_ID: &str = "indexify-builtin";
+
+/// The output, and feature, of the embedding extractor.
+const EMBEDDING_OUTPUT: &str = "embedding";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display, EnumString)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum EmbeddingProvider {
+    #[serde(rename = "openai")]
+    #[strum(serialize = "openai")]
+    OpenAI,
+    Cohere,
+    /// A model exported to ONNX, run in the coordinator.
+    Onnx,
+}
+
+/// The `input_params` of a binding of the embedding extractor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmbeddingParams {
+    pub provider: EmbeddingProvider,
+    /// Defaults to the default model of the provider.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// The dimensions of the embeddings. Only needed for models whose
+    /// dimensions are not known, or to shorten the embeddings of the models
+    /// which support it.
+    #[serde(default)]
+    pub dim: Option<usize>,
+}
+
+impl EmbeddingParams {
+    pub fn from_input_params(input_params: Option<&serde_json::Value>) -> Result<Self> {
+        let input_params = input_params.cloned().unwrap_or(json!({}));
+        serde_json::from_value(input_params)
+            .map_err(|e| anyhow!("invalid input params for {}: {}", EMBEDDING_EXTRACTOR, e))
+    }
+
+    pub fn model(&self) -> &str {
+        self.model.as_deref().unwrap_or(match self.provider {
+            EmbeddingProvider::OpenAI => "text-embedding-3-small",
+            EmbeddingProvider::Cohere => "embed-english-v3.0",
+            EmbeddingProvider::Onnx => "all-MiniLM-L6-v2",
+        })
+    }
+
+    /// The dimensions of the embeddings of the model, and whether the model
+    /// can shorten its embeddings to fewer dimensions.
+    fn model_dim(&self) -> Option<(usize, bool)> {
+        let dim = match (self.provider, self.model()) {
+            (EmbeddingProvider::OpenAI, "text-embedding-3-small") => (1536, true),
+            (EmbeddingProvider::OpenAI, "text-embedding-3-large") => (3072, true),
+            (EmbeddingProvider::OpenAI, "text-embedding-ada-002") => (1536, false),
+            (EmbeddingProvider::Cohere, "embed-english-v3.0") => (1024, false),
+            (EmbeddingProvider::Cohere, "embed-multilingual-v3.0") => (1024, false),
+            (EmbeddingProvider::Cohere, "embed-english-light-v3.0") => (384, false),
+            (EmbeddingProvider::Cohere, "embed-multilingual-light-v3.0") => (384, false),
+            (EmbeddingProvider::Onnx, "all-MiniLM-L6-v2") => (384, false),
+            _ => return None,
+        };
+        Some(dim)
+    }
+
+    /// The dimensions of the embeddings the params produce.
+    pub fn dim(&self) -> Result<usize> {
+        match (self.model_dim(), self.dim) {
+            (None, Some(dim)) => Ok(dim),
+            (None, None) => Err(anyhow!(
+                "the dimensions of model {} of provider {} are not known, set `dim`",
+                self.model(),
+                self.provider
+            )),
+            (Some((dim, _)), None) => Ok(dim),
+            (Some((model_dim, shortens)), Some(dim)) => {
+                if dim == model_dim || (shortens && dim > 0 && dim < model_dim) {
+                    Ok(dim)
+                } else {
+                    Err(anyhow!(
+                        "model {} of provider {} does not produce embeddings of {} dimensions",
+                        self.model(),
+                        self.provider,
+                        dim
+                    ))
+                }
+            }
+        }
+    }
+
+    /// The dimensions to ask the provider for, when they are not the default
+    /// dimensions of the model.
+    fn shortened_dim(&self) -> Option<usize> {
+        match (self.model_dim(), self.dim) {
+            (Some((model_dim, true)), Some(dim)) if dim < model_dim => Some(dim),
+            _ => None,
+        }
+    }
+}
+
+/// The description the embedding extractor is recorded with. The dimensions
+/// of its output are those of the default model of OpenAI, the indexes of a
+/// binding are created with the dimensions of its params, see
+/// [`for_binding`].
+pub fn description() -> ExtractorDescription {
+    ExtractorDescription {
+        name: EMBEDDING_EXTRACTOR.into(),
+        description: "Embeds text with the models of OpenAI or Cohere, or with a local ONNX \
+                      model"
+            .into(),
+        input_params: json!({
+            "type": "object",
+            "properties": {
+                "provider": {"type": "string", "enum": ["openai", "cohere", "onnx"]},
+                "model": {"type": "string"},
+                "dim": {"type": "integer", "minimum": 1},
+            },
+            "required": ["provider"],
+        }),
+        schema: ExtractorSchema {
+            output: HashMap::from([(
+                EMBEDDING_OUTPUT.into(),
+                OutputSchema::Embedding {
+                    dim: 1536,
+                    distance_metric: IndexDistance::Cosine.to_string(),
+                    multi_vector: false,
+                    modalities: default_modalities(),
+                },
+            )]),
+        },
+    }
+}
+
+/// The descriptions of all the built-in extractors, which are run by the
+/// same executor.
+pub fn descriptions() -> Vec<ExtractorDescription> {
+    vec![
+        description(),
+        documents::description(),
+        transcription::description(),
+    ]
+}
+
+/// The extractor as it is bound with the input params, whose output schemas
+/// depend on the params for the built-in extractors.
+pub fn for_binding(
+    mut extractor: Extractor,
+    input_params: &serde_json::Value,
+) -> Result<Extractor> {
+    if extractor.name != EMBEDDING_EXTRACTOR {
+        return Ok(extractor);
+    }
+    let params = EmbeddingParams::from_input_params(Some(input_params))?;
+    extractor.schemas.outputs = HashMap::from([(
+        EMBEDDING_OUTPUT.into(),
+        ExtractorOutputSchema::Embedding(EmbeddingSchema {
+            dim: params.dim()?,
+            distance: IndexDistance::Cosine,
+            quantization: None,
+            multi_vector: false,
+            payload_indexes: vec![],
+            modalities: default_modalities(),
+        }),
+    )]);
+    Ok(extractor)
+}
+
+/// Runs the built-in extractors.
+pub struct BuiltinExtractors {
+    client: reqwest::Client,
+    config: BuiltinExtractorsConfig,
+    #[cfg(feature = "onnx")]
+    onnx_models: onnx::OnnxModels,
+}
+
+impl fmt::Debug for BuiltinExtractors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BuiltinExtractors").finish()
+    }
+}
+
+impl BuiltinExtractors {
+    pub fn new(config: BuiltinExtractorsConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()?;
+        Ok(Self {
+            client,
+            #[cfg(feature = "onnx")]
+            onnx_models: onnx::OnnxModels::new(&config.models_dir),
+            config,
+        })
+    }
+
+    /// Extracts each of the contents with the extractor, like the `extract`
+    /// of the extractors run by executors.
+    pub async fn extract(
+        &self,
+        extractor: &str,
+        content: Vec<Content>,
+        input_params: Option<&serde_json::Value>,
+    ) -> Result<Vec<Vec<Content>>> {
+        match extractor {
+            EMBEDDING_EXTRACTOR => self.embed_content(content, input_params).await,
+            DOCUMENT_TEXT_EXTRACTOR => content.iter().map(documents::extract).collect(),
+            TRANSCRIPTION_EXTRACTOR => {
+                let params = transcription::TranscriptionParams::from_input_params(input_params)?;
+                let mut transcripts = Vec::with_capacity(content.len());
+                for c in content {
+                    transcripts.push(vec![self.transcribe(&params, c).await?]);
+                }
+                Ok(transcripts)
+            }
+            _ => Err(anyhow!("{} is not a built-in extractor", extractor)),
+        }
+    }
+
+    /// The transcript of audio content, see [`transcription::transcript`].
+    async fn transcribe(
+        &self,
+        params: &transcription::TranscriptionParams,
+        content: Content,
+    ) -> Result<Content> {
+        if !transcription::is_audio(&content) {
+            return Err(anyhow!(
+                "{} can not transcribe content of type {}",
+                TRANSCRIPTION_EXTRACTOR,
+                content.content_type
+            ));
+        }
+        let segments = openai::transcribe(
+            &self.client,
+            &self.config.openai_base_url,
+            params.model(),
+            params.language.as_deref(),
+            &content.content_type,
+            content.source,
+        )
+        .await?;
+        Ok(transcription::transcript(&segments))
+    }
+
+    async fn embed_content(
+        &self,
+        content: Vec<Content>,
+        input_params: Option<&serde_json::Value>,
+    ) -> Result<Vec<Vec<Content>>> {
+        let params = EmbeddingParams::from_input_params(input_params)?;
+        let texts = content
+            .iter()
+            .map(|c| {
+                c.source_as_text()
+                    .ok_or(anyhow!("{} can only embed text", EMBEDDING_EXTRACTOR))
+            })
+            .collect::<Result<Vec<String>>>()?;
+        let embeddings = self.embed(&params, &texts).await?;
+        let dim = params.dim()?;
+        if let Some(embedding) = embeddings.iter().find(|e| e.len() != dim) {
+            return Err(anyhow!(
+                "model {} returned embeddings of {} dimensions instead of {}",
+                params.model(),
+                embedding.len(),
+                dim
+            ));
+        }
+        Ok(content
+            .into_iter()
+            .zip(embeddings)
+            .map(|(content, embedding)| {
+                vec![Content {
+                    feature: Some(Feature {
+                        feature_type: FeatureType::Embedding,
+                        name: EMBEDDING_OUTPUT.into(),
+                        data: json!(embedding),
+                    }),
+                    metadata: HashMap::new(),
+                    ..content
+                }]
+            })
+            .collect())
+    }
+
+    /// Does the work of a built-in extractor like an executor does. The
+    /// content is split by the chunker of the binding for the embedding
+    /// extractor, the content derived by the other extractors, such as the
+    /// pages of a document, are their items. Work which fails is reported as
+    /// failed with its error.
+    pub async fn extract_work(&self, work: Work) -> WorkStatus {
+        let started_at = Instant::now();
+        let work_id = work.id.clone();
+        let work_extractor = work.extractor.clone();
+        let result = async {
+            let source = ContentReader::new(work.content_payload.clone())
+                .read()
+                .await?;
+            let content = Content {
+                content_type: work.content_payload.content_type.clone(),
+                source,
+                feature: None,
+                metadata: work.content_payload.metadata.clone(),
+            };
+            if work.extractor != EMBEDDING_EXTRACTOR {
+                let derived = self
+                    .extract(&work.extractor, vec![content], Some(&work.params))
+                    .await?
+                    .concat();
+                let origins = vec![None; derived.len()];
+                return Ok((derived.into_iter().map(|c| vec![c]).collect(), origins));
+            }
+            let (chunks, origins): (Vec<_>, Vec<_>) = match &work.chunker {
+                Some(chunker) => {
+                    chunking::chunk_content(chunker, work.parent_chunker.as_ref(), content)
+                        .into_iter()
+                        .map(|(chunk, origin)| (chunk, Some(origin)))
+                        .unzip()
+                }
+                None => (vec![content], vec![None]),
+            };
+            let extracted = self
+                .extract(&work.extractor, chunks, Some(&work.params))
+                .await?;
+            Ok::<_, anyhow::Error>((extracted, origins))
+        }
+        .await;
+        match result {
+            Ok((extracted, origins)) => {
+                let items = extracted.len();
+                let mut extracted_content = Vec::new();
+                let mut chunk_origins = Vec::new();
+                for (extracted, origin) in extracted.into_iter().zip(origins) {
+                    if let Some(origin) = origin {
+                        chunk_origins.extend(std::iter::repeat_n(origin, extracted.len()));
+                    }
+                    extracted_content.extend(extracted);
+                }
+                WorkStatus {
+                    work_id,
+                    status: WorkState::Completed,
+                    items_processed: Some(items as u64),
+                    percent: Some(100.0),
+                    log: vec![format!(
+                        "{} {} items in {} ms",
+                        match work_extractor.as_str() {
+                            DOCUMENT_TEXT_EXTRACTOR => "extracted the text of",
+                            TRANSCRIPTION_EXTRACTOR => "transcribed",
+                            _ => "embedded",
+                        },
+                        items,
+                        started_at.elapsed().as_millis()
+                    )],
+                    extracted_content,
+                    chunk_origins,
+                    error: None,
+                }
+            }
+            Err(err) => WorkStatus {
+                work_id,
+                status: WorkState::Failed,
+                extracted_content: vec![],
+                chunk_origins: vec![],
+                items_processed: None,
+                percent: None,
+                log: vec![],
+                error: Some(err.to_string()),
+            },
+        }
+    }
+
+    async fn embed(&self, params: &EmbeddingParams, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(vec![]);
+        }
+        match params.provider {
+            EmbeddingProvider::OpenAI => {
+                openai::embed(
+                    &self.client,
+                    &self.config.openai_base_url,
+                    params.model(),
+                    params.shortened_dim(),
+                    texts,
+                )
+                .await
+            }
+            EmbeddingProvider::Cohere => {
+                cohere::embed(
+                    &self.client,
+                    &self.config.cohere_base_url,
+                    params.model(),
+                    texts,
+                )
+                .await
+            }
+            #[cfg(feature = "onnx")]
+            EmbeddingProvider::Onnx => self.onnx_models.embed(params.model(), texts).await,
+            #[cfg(not(feature = "onnx"))]
+            EmbeddingProvider::Onnx => Err(anyhow!(
+                "indexify was built without the `onnx` feature, which runs ONNX models"
+            )),
+        }
+    }
+}
+
+/// The api key of a provider, from an environment variable so that it is not
+/// stored with the bindings.
+fn api_key(env: &str) -> Result<String> {
+    std::env::var(env).map_err(|_| anyhow!("set {} to use the provider", env))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(value: serde_json::Value) -> EmbeddingParams {
+        EmbeddingParams::from_input_params(Some(&value)).unwrap()
+    }
+
+    #[test]
+    fn test_embedding_dim() {
+        assert_eq!(params(json!({"provider": "openai"})).dim().unwrap(), 1536);
+        assert_eq!(
+            params(json!({"provider": "openai", "model": "text-embedding-3-large", "dim": 256}))
+                .dim()
+                .unwrap(),
+            256
+        );
+        assert_eq!(
+            params(json!({"provider": "openai", "dim": 256})).shortened_dim(),
+            Some(256)
+        );
+        assert_eq!(params(json!({"provider": "openai"})).shortened_dim(), None);
+        // Models which can not shorten their embeddings.
+        assert!(params(
+            json!({"provider": "openai", "model": "text-embedding-ada-002", "dim": 256})
+        )
+        .dim()
+        .is_err());
+        assert!(params(json!({"provider": "cohere", "dim": 512}))
+            .dim()
+            .is_err());
+        assert_eq!(params(json!({"provider": "cohere"})).dim().unwrap(), 1024);
+        assert_eq!(params(json!({"provider": "onnx"})).dim().unwrap(), 384);
+        // Models whose dimensions are not known need them set.
+        assert!(params(json!({"provider": "onnx", "model": "bge-small-en"}))
+            .dim()
+            .is_err());
+        assert_eq!(
+            params(json!({"provider": "onnx", "model": "bge-small-en", "dim": 384}))
+                .dim()
+                .unwrap(),
+            384
+        );
+        assert!(EmbeddingParams::from_input_params(Some(&json!({"provider": "other"}))).is_err());
+        assert!(EmbeddingParams::from_input_params(None).is_err());
+    }
+
+    #[test]
+    fn test_for_binding() {
+        let extractor: Extractor = description().try_into().unwrap();
+        let bound = for_binding(
+            extractor,
+            &json!({"provider": "cohere", "model": "embed-english-light-v3.0"}),
+        )
+        .unwrap();
+        let Some(ExtractorOutputSchema::Embedding(schema)) =
+            bound.schemas.outputs.get(EMBEDDING_OUTPUT)
+        else {
+            panic!("the embedding extractor has no embedding output");
+        };
+        assert_eq!(schema.dim, 384);
+        assert!(matches!(schema.distance, IndexDistance::Cosine));
+    }
+}
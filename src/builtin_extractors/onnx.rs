@@ -0,0 +1,188 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Once},
+};
+
+use anyhow::{anyhow, Result};
+use ndarray::{Array2, ArrayView3, Axis, Ix3};
+use ort::{GraphOptimizationLevel, Session, Tensor};
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+
+/// Tokens past this many are dropped from the texts, the length of the inputs
+/// of the sentence transformer models.
+const MAX_TOKENS: usize = 512;
+
+/// A sentence transformer model exported to ONNX, with the tokenizer it was
+/// trained with. The embedding of a text is the mean of the embeddings of its
+/// tokens, normalized.
+struct OnnxModel {
+    session: Session,
+    tokenizer: Tokenizer,
+}
+
+impl OnnxModel {
+    /// Loads the model from `model.onnx` and `tokenizer.json` in the
+    /// directory.
+    fn load(dir: &Path) -> Result<Self> {
+        let session = Session::builder()?
+            .with_optimization_level(GraphOptimizationLevel::Level3)?
+            .commit_from_file(dir.join("model.onnx"))?;
+        let mut tokenizer = Tokenizer::from_file(dir.join("tokenizer.json"))
+            .map_err(|e| anyhow!("unable to load tokenizer of {}: {}", dir.display(), e))?;
+        tokenizer.with_padding(Some(PaddingParams::default()));
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: MAX_TOKENS,
+                ..Default::default()
+            }))
+            .map_err(|e| anyhow!("unable to configure tokenizer: {}", e))?;
+        Ok(Self { session, tokenizer })
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(|e| anyhow!("unable to tokenize texts: {}", e))?;
+        let shape = (encodings.len(), encodings[0].len());
+        let tensor = |values: Vec<i64>| -> Result<Tensor<i64>> {
+            Ok(Tensor::from_array(Array2::from_shape_vec(shape, values)?)?)
+        };
+        let ids = tensor(
+            encodings
+                .iter()
+                .flat_map(|e| e.get_ids().iter().map(|i| *i as i64))
+                .collect(),
+        )?;
+        let mask = tensor(
+            encodings
+                .iter()
+                .flat_map(|e| e.get_attention_mask().iter().map(|m| *m as i64))
+                .collect(),
+        )?;
+        let type_ids = tensor(
+            encodings
+                .iter()
+                .flat_map(|e| e.get_type_ids().iter().map(|t| *t as i64))
+                .collect(),
+        )?;
+        // The inputs are the ids, the attention mask and, only for the models
+        // of BERT, the token types, in the order the model declares them.
+        let inputs: Vec<_> = self
+            .session
+            .inputs
+            .iter()
+            .map(|input| input.name.clone())
+            .zip([ids, mask, type_ids])
+            .collect();
+        let outputs = self.session.run(inputs)?;
+        let hidden = outputs[0].try_extract_tensor::<f32>()?;
+        let hidden = hidden
+            .into_dimensionality::<Ix3>()
+            .map_err(|e| anyhow!("unexpected output of model: {}", e))?;
+        let mask: Vec<Vec<u32>> = encodings
+            .iter()
+            .map(|e| e.get_attention_mask().to_vec())
+            .collect();
+        Ok(mean_pool(hidden, &mask))
+    }
+}
+
+/// The normalized mean of the embeddings of the tokens of each text, apart
+/// from the padding.
+fn mean_pool(hidden: ArrayView3<f32>, mask: &[Vec<u32>]) -> Vec<Vec<f32>> {
+    hidden
+        .axis_iter(Axis(0))
+        .zip(mask)
+        .map(|(tokens, mask)| {
+            let mut embedding = vec![0.0; tokens.shape()[1]];
+            let mut count = 0.0;
+            for (token, _) in tokens.axis_iter(Axis(0)).zip(mask).filter(|(_, m)| **m > 0) {
+                for (e, t) in embedding.iter_mut().zip(token.iter()) {
+                    *e += t;
+                }
+                count += 1.0;
+            }
+            if count > 0.0 {
+                embedding.iter_mut().for_each(|e| *e /= count);
+            }
+            normalize(embedding)
+        })
+        .collect()
+}
+
+fn normalize(mut embedding: Vec<f32>) -> Vec<f32> {
+    let norm = embedding.iter().map(|e| e * e).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        embedding.iter_mut().for_each(|e| *e /= norm);
+    }
+    embedding
+}
+
+/// The environment of ONNX Runtime, shared by the sessions of the process.
+static ENVIRONMENT: Once = Once::new();
+
+/// The ONNX models in a directory, loaded the first time they embed text.
+pub struct OnnxModels {
+    dir: PathBuf,
+    models: Mutex<HashMap<String, Arc<OnnxModel>>>,
+}
+
+impl OnnxModels {
+    pub fn new(dir: &str) -> Self {
+        Self {
+            dir: dir.into(),
+            models: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Embeds the texts with the model in the directory of the same name. The
+    /// model runs on the blocking threads, so that it does not hold up the
+    /// coordinator.
+    pub async fn embed(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let model = self.model(model)?;
+        let texts = texts.to_vec();
+        tokio::task::spawn_blocking(move || model.embed(&texts)).await?
+    }
+
+    fn model(&self, name: &str) -> Result<Arc<OnnxModel>> {
+        if name.contains('/') || name.contains("..") {
+            return Err(anyhow!("invalid onnx model name: {}", name));
+        }
+        let mut models = self.models.lock().unwrap();
+        if let Some(model) = models.get(name) {
+            return Ok(model.clone());
+        }
+        // Sessions created before the environment is configured use a default
+        // one, so a failure to name it is not fatal.
+        ENVIRONMENT.call_once(|| {
+            if let Err(err) = ort::init().with_name("indexify").commit() {
+                tracing::warn!("unable to configure the onnx runtime: {}", err);
+            }
+        });
+        let model = Arc::new(OnnxModel::load(&self.dir.join(name))?);
+        models.insert(name.into(), model.clone());
+        Ok(model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::Array3;
+
+    use super::*;
+
+    #[test]
+    fn test_mean_pool() {
+        // Two texts of two tokens, the second of which is padding for the
+        // second text.
+        let hidden =
+            Array3::from_shape_vec((2, 2, 2), vec![3.0, 0.0, 0.0, 4.0, 1.0, 1.0, 100.0, 100.0])
+                .unwrap();
+        let embeddings = mean_pool(hidden.view(), &[vec![1, 1], vec![1, 0]]);
+        assert_eq!(embeddings[0], vec![0.6, 0.8]);
+        let component = 1.0 / 2.0_f32.sqrt();
+        assert_eq!(embeddings[1], vec![component, component]);
+    }
+}
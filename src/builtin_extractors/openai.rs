@@ -0,0 +1,141 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use super::api_key;
+
+const API_KEY_ENV: &str = "OPENAI_API_KEY";
+
+/// The most texts embedded in one request.
+const MAX_BATCH_SIZE: usize = 2048;
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimensions: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Embeds the texts with the `/embeddings` API of OpenAI, or of a server
+/// which implements it.
+pub async fn embed(
+    client: &reqwest::Client,
+    base_url: &str,
+    model: &str,
+    dimensions: Option<usize>,
+    texts: &[String],
+) -> Result<Vec<Vec<f32>>> {
+    let api_key = api_key(API_KEY_ENV)?;
+    let mut embeddings = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(MAX_BATCH_SIZE) {
+        let resp = client
+            .post(format!("{}/embeddings", base_url.trim_end_matches('/')))
+            .bearer_auth(&api_key)
+            .json(&EmbeddingRequest {
+                model,
+                input: batch,
+                dimensions,
+            })
+            .send()
+            .await
+            .map_err(|e| anyhow!("unable to embed text with openai: {}", e))?;
+        if !resp.status().is_success() {
+            return Err(anyhow!(
+                "unable to embed text with openai: status: {}, error: {}",
+                resp.status(),
+                resp.text().await?
+            ));
+        }
+        let mut data = resp
+            .json::<EmbeddingResponse>()
+            .await
+            .map_err(|e| anyhow!("unable to decode openai embeddings: {}", e))?
+            .data;
+        if data.len() != batch.len() {
+            return Err(anyhow!(
+                "openai returned {} embeddings for {} texts",
+                data.len(),
+                batch.len()
+            ));
+        }
+        data.sort_by_key(|d| d.index);
+        embeddings.extend(data.into_iter().map(|d| d.embedding));
+    }
+    Ok(embeddings)
+}
+
+/// A span of a recording and the text spoken in it, in seconds from the
+/// start of the recording.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TranscriptionSegment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+#[derive(Deserialize)]
+struct TranscriptionResponse {
+    #[serde(default)]
+    segments: Vec<TranscriptionSegment>,
+}
+
+/// Transcribes a recording with the `/audio/transcriptions` API of OpenAI,
+/// or of a server which implements it, into the segments of the transcript.
+pub async fn transcribe(
+    client: &reqwest::Client,
+    base_url: &str,
+    model: &str,
+    language: Option<&str>,
+    content_type: &str,
+    data: Vec<u8>,
+) -> Result<Vec<TranscriptionSegment>> {
+    let api_key = api_key(API_KEY_ENV)?;
+    // The format of the recording is told by the extension of its file name.
+    let extension = mime_guess::get_mime_extensions_str(content_type)
+        .and_then(|extensions| extensions.first())
+        .unwrap_or(&"bin");
+    let file = reqwest::multipart::Part::bytes(data)
+        .file_name(format!("recording.{}", extension))
+        .mime_str(content_type)?;
+    let mut form = reqwest::multipart::Form::new()
+        .part("file", file)
+        .text("model", model.to_string())
+        .text("response_format", "verbose_json")
+        .text("timestamp_granularities[]", "segment");
+    if let Some(language) = language {
+        form = form.text("language", language.to_string());
+    }
+    let resp = client
+        .post(format!(
+            "{}/audio/transcriptions",
+            base_url.trim_end_matches('/')
+        ))
+        .bearer_auth(&api_key)
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|e| anyhow!("unable to transcribe audio with openai: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(anyhow!(
+            "unable to transcribe audio with openai: status: {}, error: {}",
+            resp.status(),
+            resp.text().await?
+        ));
+    }
+    Ok(resp
+        .json::<TranscriptionResponse>()
+        .await
+        .map_err(|e| anyhow!("unable to decode openai transcription: {}", e))?
+        .segments)
+}
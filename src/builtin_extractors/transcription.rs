@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::openai::TranscriptionSegment;
+use crate::internal_api::{Content, ExtractorDescription, ExtractorSchema};
+
+/// Transcribes audio into text content derived from it, with the times of
+/// the recording every part of the text was spoken at.
+pub const TRANSCRIPTION_EXTRACTOR: &str = "indexify/transcription";
+
+/// The metadata of a transcript with its segments, see
+/// [`TranscriptSegment`].
+pub const SEGMENTS_METADATA_KEY: &str = "segments";
+
+/// A segment of a transcript: where its text is in the transcript, in
+/// characters, and when it was spoken, in milliseconds from the start of the
+/// recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub start: usize,
+    pub end: usize,
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+impl TranscriptSegment {
+    /// The segments of a transcript, from its metadata.
+    pub fn from_metadata(metadata: &HashMap<String, serde_json::Value>) -> Option<Vec<Self>> {
+        serde_json::from_value(metadata.get(SEGMENTS_METADATA_KEY)?.clone()).ok()
+    }
+}
+
+/// The `input_params` of a binding of the transcription extractor.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptionParams {
+    /// `whisper-1` by default.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// The language spoken, as an ISO-639-1 code, detected when it is not
+    /// set.
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+impl TranscriptionParams {
+    pub fn from_input_params(input_params: Option<&serde_json::Value>) -> Result<Self> {
+        let input_params = input_params.cloned().unwrap_or(json!({}));
+        serde_json::from_value(input_params).map_err(|e| {
+            anyhow!(
+                "invalid input params for {}: {}",
+                TRANSCRIPTION_EXTRACTOR,
+                e
+            )
+        })
+    }
+
+    pub fn model(&self) -> &str {
+        self.model.as_deref().unwrap_or("whisper-1")
+    }
+}
+
+pub fn description() -> ExtractorDescription {
+    ExtractorDescription {
+        name: TRANSCRIPTION_EXTRACTOR.into(),
+        description: "Transcribes audio with the speech to text models of OpenAI, with the \
+                      times every segment was spoken at"
+            .into(),
+        input_params: json!({
+            "type": "object",
+            "properties": {
+                "model": {"type": "string"},
+                "language": {"type": "string"},
+            },
+        }),
+        // The transcript is added as content of the repository, not to an
+        // index.
+        schema: ExtractorSchema {
+            output: HashMap::new(),
+        },
+    }
+}
+
+/// Whether the extractor can transcribe the content.
+pub fn is_audio(content: &Content) -> bool {
+    content
+        .content_type
+        .parse::<mime::Mime>()
+        .is_ok_and(|mime| mime.type_() == mime::AUDIO || mime.type_() == mime::VIDEO)
+}
+
+/// The transcript of the segments as text content, one segment per line,
+/// with the segments in its metadata. Segments without text are left out.
+pub fn transcript(segments: &[TranscriptionSegment]) -> Content {
+    let mut text = String::new();
+    let mut transcript_segments = Vec::new();
+    for segment in segments {
+        let segment_text = segment.text.trim();
+        if segment_text.is_empty() {
+            continue;
+        }
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        let start = text.chars().count();
+        text.push_str(segment_text);
+        transcript_segments.push(TranscriptSegment {
+            start,
+            end: start + segment_text.chars().count(),
+            start_ms: (segment.start.max(0.0) * 1000.0).round() as u64,
+            end_ms: (segment.end.max(0.0) * 1000.0).round() as u64,
+        });
+    }
+    Content {
+        content_type: mime::TEXT_PLAIN.to_string(),
+        source: text.into_bytes(),
+        feature: None,
+        metadata: HashMap::from([(SEGMENTS_METADATA_KEY.into(), json!(transcript_segments))]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: f64, end: f64, text: &str) -> TranscriptionSegment {
+        TranscriptionSegment {
+            start,
+            end,
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn test_transcript() {
+        let content = transcript(&[
+            segment(0.0, 2.5, " Bonjour à tous."),
+            segment(2.5, 3.0, "  "),
+            segment(3.0, 7.25, " Today we cover the roadmap."),
+        ]);
+        assert_eq!(
+            String::from_utf8(content.source.clone()).unwrap(),
+            "Bonjour à tous.\nToday we cover the roadmap."
+        );
+        assert_eq!(
+            TranscriptSegment::from_metadata(&content.metadata).unwrap(),
+            vec![
+                TranscriptSegment {
+                    start: 0,
+                    end: 15,
+                    start_ms: 0,
+                    end_ms: 2500,
+                },
+                TranscriptSegment {
+                    start: 16,
+                    end: 43,
+                    start_ms: 3000,
+                    end_ms: 7250,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_audio() {
+        let content = |content_type: &str| Content {
+            content_type: content_type.into(),
+            source: vec![],
+            feature: None,
+            metadata: HashMap::new(),
+        };
+        assert!(is_audio(&content("audio/mpeg")));
+        assert!(is_audio(&content("video/mp4")));
+        assert!(!is_audio(&content("text/plain")));
+        assert!(!is_audio(&content("not a type")));
+    }
+}
@@ -0,0 +1,794 @@
+use std::io::Cursor;
+
+use image::{GenericImageView, ImageOutputFormat};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    builtin_extractors::TranscriptSegment,
+    internal_api,
+    persistence::{ChunkOffsets, Chunker, ImageRegion},
+};
+
+/// The separators the recursive chunker splits at when a binding does not
+/// set its own, from paragraphs down to words.
+pub const DEFAULT_SEPARATORS: [&str; 4] = ["\n\n", "\n", ". ", " "];
+
+/// Checks that chunks of the size of a chunker can advance through the text.
+pub fn validate_chunker(chunker: &Chunker) -> Result<(), String> {
+    let (size, overlap) = chunker.size_and_overlap();
+    if size == 0 {
+        return Err("the size of chunks must be greater than 0".into());
+    }
+    if overlap >= size {
+        return Err(format!(
+            "the overlap of chunks, {}, must be smaller than their size, {}",
+            overlap, size
+        ));
+    }
+    Ok(())
+}
+
+/// Checks the parent chunker of a binding, which splits content into parent
+/// chunks that the chunker of the binding splits again, so it needs one with
+/// smaller chunks.
+pub fn validate_parent_chunker(
+    chunker: Option<&Chunker>,
+    parent_chunker: &Chunker,
+) -> Result<(), String> {
+    validate_chunker(parent_chunker)?;
+    if matches!(parent_chunker, Chunker::ImageTiles { .. }) ||
+        matches!(chunker, Some(Chunker::ImageTiles { .. }))
+    {
+        return Err("the tiles of images can not have parent chunks".into());
+    }
+    let Some(chunker) = chunker else {
+        return Err("a parent chunker needs a chunker to split its chunks".into());
+    };
+    let (size, _) = chunker.size_and_overlap();
+    let (parent_size, _) = parent_chunker.size_and_overlap();
+    if parent_size <= size {
+        return Err(format!(
+            "the size of parent chunks, {}, must be greater than the size of chunks, {}",
+            parent_size, size
+        ));
+    }
+    Ok(())
+}
+
+/// Where content given to an extractor was split out of the content of the
+/// work, absent for content which was not chunked.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChunkOrigin {
+    /// The offsets of the chunk in the text of the content.
+    #[serde(default)]
+    pub offsets: Option<ChunkOffsets>,
+    /// The parent chunk the chunk was split out of, when the binding has a
+    /// parent chunker.
+    #[serde(default)]
+    pub parent: Option<ParentChunk>,
+    /// The region of the image the chunk is a tile of.
+    #[serde(default)]
+    pub region: Option<ImageRegion>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParentChunk {
+    pub text: String,
+    pub offsets: ChunkOffsets,
+}
+
+/// A chunk and the byte range of the text it was split out of.
+#[derive(Debug, Clone, PartialEq)]
+struct Span {
+    text: String,
+    start: usize,
+    end: usize,
+}
+
+impl Span {
+    /// The span of a slice of the text, without its surrounding whitespace.
+    fn trimmed(slice: &str, start: usize) -> Self {
+        let text = slice.trim();
+        let start = start + (slice.len() - slice.trim_start().len());
+        Self {
+            text: text.to_string(),
+            start,
+            end: start + text.len(),
+        }
+    }
+
+    /// The offsets of the span in characters of the text, with the page it
+    /// starts on when the text has form feeds, which text extracted from
+    /// PDFs has between its pages.
+    fn offsets(&self, text: &str) -> ChunkOffsets {
+        let start = text[..self.start].chars().count();
+        let end = start + text[self.start..self.end].chars().count();
+        let page = text
+            .contains('\x0c')
+            .then(|| text[..self.start].matches('\x0c').count() as u32 + 1);
+        ChunkOffsets {
+            start,
+            end,
+            page,
+            start_ms: None,
+            end_ms: None,
+        }
+    }
+}
+
+/// Splits text into the spans of the chunks the extractor of a binding
+/// embeds. Sizes are counted in tokens, the words of the text separated by
+/// whitespace, and consecutive chunks share up to `overlap` tokens.
+fn spans(chunker: &Chunker, text: &str) -> Vec<Span> {
+    let (size, overlap) = chunker.size_and_overlap();
+    let size = size.max(1);
+    let overlap = overlap.min(size - 1);
+    match chunker {
+        Chunker::FixedTokens { .. } => fixed_tokens(text, 0, size, overlap),
+        Chunker::Sentence { .. } => sentence_chunks(text, size, overlap),
+        Chunker::Recursive { separators, .. } => {
+            let separators: Vec<&str> = if separators.is_empty() {
+                DEFAULT_SEPARATORS.to_vec()
+            } else {
+                separators.iter().map(|s| s.as_str()).collect()
+            };
+            recursive(text, 0, size, overlap, &separators)
+        }
+        Chunker::Markdown { .. } => markdown(text, size, overlap),
+        Chunker::ImageTiles { .. } => vec![Span::trimmed(text, 0)],
+    }
+}
+
+/// The content an extractor is given for text content, one for every chunk
+/// of its text, along with where the chunk is in the text. With a parent
+/// chunker the text is split into parent chunks first, and the chunker splits
+/// the text of every parent chunk. Content which is not text is given as it
+/// is, apart from images which are split into tiles by the image tiles
+/// chunker. The offsets of chunks of transcripts have the times of the
+/// recording they were spoken at.
+pub fn chunk_content(
+    chunker: &Chunker,
+    parent_chunker: Option<&Chunker>,
+    content: internal_api::Content,
+) -> Vec<(internal_api::Content, ChunkOrigin)> {
+    if let Chunker::ImageTiles { size, overlap } = chunker {
+        if is_image(&content) {
+            return image_tiles(content, *size, *overlap);
+        }
+    }
+    let Some(text) = content.source_as_text() else {
+        return vec![(content, ChunkOrigin::default())];
+    };
+    let segments = TranscriptSegment::from_metadata(&content.metadata);
+    let offsets = |span: &Span| with_times(span.offsets(&text), segments.as_deref());
+    let parents = match parent_chunker {
+        Some(parent_chunker) => spans(parent_chunker, &text)
+            .into_iter()
+            .map(|parent| {
+                let offsets = offsets(&parent);
+                (
+                    parent.start..parent.end,
+                    Some(ParentChunk {
+                        text: parent.text,
+                        offsets,
+                    }),
+                )
+            })
+            .collect(),
+        None => vec![(0..text.len(), None)],
+    };
+    let mut chunks = Vec::new();
+    for (range, parent) in parents {
+        for mut chunk in spans(chunker, &text[range.clone()]) {
+            chunk.start += range.start;
+            chunk.end += range.start;
+            let origin = ChunkOrigin {
+                offsets: Some(offsets(&chunk)),
+                parent: parent.clone(),
+                region: None,
+            };
+            let chunk = internal_api::Content {
+                content_type: content.content_type.clone(),
+                source: chunk.text.into_bytes(),
+                feature: None,
+                metadata: content.metadata.clone(),
+            };
+            chunks.push((chunk, origin));
+        }
+    }
+    if chunks.is_empty() {
+        return vec![(content, ChunkOrigin::default())];
+    }
+    chunks
+}
+
+/// The offsets with the times of the recording a chunk of a transcript was
+/// spoken at, from the start of the segment it starts in to the end of the
+/// segment it ends in.
+fn with_times(mut offsets: ChunkOffsets, segments: Option<&[TranscriptSegment]>) -> ChunkOffsets {
+    let Some(segments) = segments else {
+        return offsets;
+    };
+    offsets.start_ms = segments
+        .iter()
+        .find(|s| s.end > offsets.start)
+        .map(|s| s.start_ms);
+    offsets.end_ms = segments
+        .iter()
+        .rev()
+        .find(|s| s.start < offsets.end)
+        .map(|s| s.end_ms);
+    offsets
+}
+
+fn is_image(content: &internal_api::Content) -> bool {
+    content
+        .content_type
+        .parse::<mime::Mime>()
+        .is_ok_and(|content_type| content_type.type_() == mime::IMAGE)
+}
+
+/// The starts of tiles of `size` along a side of an image, `size - overlap`
+/// apart, with the last tile ending at the edge of the image.
+fn tile_starts(length: u32, size: u32, overlap: u32) -> Vec<u32> {
+    if length <= size {
+        return vec![0];
+    }
+    let step = size.saturating_sub(overlap).max(1);
+    let mut starts: Vec<u32> = (0..length - size).step_by(step as usize).collect();
+    starts.push(length - size);
+    starts
+}
+
+/// Square tiles of an image, encoded as PNG. An image which is not larger
+/// than a tile, or which can not be decoded, is given as it is.
+fn image_tiles(
+    content: internal_api::Content,
+    size: usize,
+    overlap: usize,
+) -> Vec<(internal_api::Content, ChunkOrigin)> {
+    let image = match image::load_from_memory(&content.source) {
+        Ok(image) => image,
+        Err(err) => {
+            warn!("unable to decode an image to split it into tiles: {}", err);
+            return vec![(content, ChunkOrigin::default())];
+        }
+    };
+    let (width, height) = image.dimensions();
+    let size = size.max(1) as u32;
+    if width <= size && height <= size {
+        return vec![(content, ChunkOrigin::default())];
+    }
+    let overlap = overlap as u32;
+    let mut tiles = Vec::new();
+    for y in tile_starts(height, size, overlap) {
+        for x in tile_starts(width, size, overlap) {
+            let region = ImageRegion {
+                x,
+                y,
+                width: size.min(width - x),
+                height: size.min(height - y),
+            };
+            let mut source = Vec::new();
+            let encoded = image
+                .crop_imm(region.x, region.y, region.width, region.height)
+                .write_to(&mut Cursor::new(&mut source), ImageOutputFormat::Png);
+            if let Err(err) = encoded {
+                warn!("unable to encode a tile of an image: {}", err);
+                return vec![(content, ChunkOrigin::default())];
+            }
+            let tile = internal_api::Content {
+                content_type: mime::IMAGE_PNG.to_string(),
+                source,
+                feature: None,
+                metadata: content.metadata.clone(),
+            };
+            let origin = ChunkOrigin {
+                region: Some(region),
+                ..Default::default()
+            };
+            tiles.push((tile, origin));
+        }
+    }
+    tiles
+}
+
+fn token_count(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+/// The words of a text with their byte offsets in it.
+fn tokens(text: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                tokens.push((s, &text[s..i]));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        tokens.push((s, &text[s..]));
+    }
+    tokens
+}
+
+fn fixed_tokens(text: &str, offset: usize, size: usize, overlap: usize) -> Vec<Span> {
+    let tokens = tokens(text);
+    let step = (size - overlap).max(1);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < tokens.len() {
+        let end = (start + size).min(tokens.len());
+        let (last_start, last) = tokens[end - 1];
+        chunks.push(Span {
+            text: tokens[start..end]
+                .iter()
+                .map(|(_, token)| *token)
+                .collect::<Vec<&str>>()
+                .join(" "),
+            start: offset + tokens[start].0,
+            end: offset + last_start + last.len(),
+        });
+        if end == tokens.len() {
+            break;
+        }
+        start += step;
+    }
+    chunks
+}
+
+/// Packs pieces of at most `size` tokens into chunks of at most `size`
+/// tokens, starting every chunk after the first with the last pieces of the
+/// chunk before it which fit in `overlap` tokens.
+fn merge(pieces: Vec<Span>, size: usize, overlap: usize, separator: &str) -> Vec<Span> {
+    let mut chunks = Vec::new();
+    let mut current: Vec<(Span, usize)> = Vec::new();
+    let mut current_tokens = 0;
+    for piece in pieces {
+        let tokens = token_count(&piece.text);
+        if tokens == 0 {
+            continue;
+        }
+        if !current.is_empty() && current_tokens + tokens > size {
+            chunks.push(join(&current, separator));
+            while !current.is_empty() &&
+                (current_tokens > overlap || current_tokens + tokens > size)
+            {
+                current_tokens -= current.remove(0).1;
+            }
+        }
+        current.push((piece, tokens));
+        current_tokens += tokens;
+    }
+    if !current.is_empty() {
+        chunks.push(join(&current, separator));
+    }
+    chunks
+}
+
+fn join(pieces: &[(Span, usize)], separator: &str) -> Span {
+    Span {
+        text: pieces
+            .iter()
+            .map(|(piece, _)| piece.text.as_str())
+            .collect::<Vec<&str>>()
+            .join(separator),
+        start: pieces[0].0.start,
+        end: pieces[pieces.len() - 1].0.end,
+    }
+}
+
+/// The sentences of a text, ending at `.`, `!` or `?` followed by whitespace
+/// and at blank lines.
+fn sentences(text: &str) -> Vec<Span> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|(_, c)| *c);
+        let ends_sentence = matches!(c, '.' | '!' | '?') &&
+            next.is_none_or(|next| next.is_whitespace()) ||
+            c == '\n' && next == Some('\n');
+        if ends_sentence {
+            let end = i + c.len_utf8();
+            sentences.push(Span::trimmed(&text[start..end], start));
+            start = end;
+        }
+    }
+    sentences.push(Span::trimmed(&text[start..], start));
+    sentences.retain(|s| !s.text.is_empty());
+    sentences
+}
+
+fn sentence_chunks(text: &str, size: usize, overlap: usize) -> Vec<Span> {
+    let mut pieces = Vec::new();
+    for sentence in sentences(text) {
+        if token_count(&sentence.text) > size {
+            pieces.extend(fixed_tokens(&sentence.text, sentence.start, size, overlap));
+        } else {
+            pieces.push(sentence);
+        }
+    }
+    merge(pieces, size, overlap, " ")
+}
+
+/// Splits text at the first separator, and the pieces which are still larger
+/// than `size` at the next ones, then packs the pieces back into chunks.
+/// Offsets are counted from `offset`, where the text starts.
+fn recursive(
+    text: &str,
+    offset: usize,
+    size: usize,
+    overlap: usize,
+    separators: &[&str],
+) -> Vec<Span> {
+    let Some((separator, rest)) = separators.split_first() else {
+        return fixed_tokens(text, offset, size, overlap);
+    };
+    let mut pieces = Vec::new();
+    let mut start = offset;
+    for piece in text.split(separator) {
+        if token_count(piece) > size {
+            pieces.extend(recursive(piece, start, size, overlap, rest));
+        } else {
+            pieces.push(Span::trimmed(piece, start));
+        }
+        start += piece.len() + separator.len();
+    }
+    merge(pieces, size, overlap, separator)
+}
+
+/// The level of a markdown header line, 1 for `# Title`.
+fn header_level(line: &str) -> Option<usize> {
+    let line = line.trim_start();
+    let level = line.chars().take_while(|c| *c == '#').count();
+    ((1..=6).contains(&level) && line[level..].starts_with(' ')).then_some(level)
+}
+
+/// Splits markdown into its sections at headers and the sections into
+/// chunks. Every chunk starts with the headers of its section and of the
+/// sections it is nested in, so that it keeps the context of the document,
+/// and its offsets are the ones of the text of its section.
+fn markdown(text: &str, size: usize, overlap: usize) -> Vec<Span> {
+    let mut chunks = Vec::new();
+    let mut headers: Vec<(usize, String)> = Vec::new();
+    let mut body_start = 0;
+    let mut in_code_block = false;
+    let mut flush = |headers: &[(usize, String)], start: usize, end: usize| {
+        let body = &text[start..end];
+        if token_count(body) == 0 {
+            return;
+        }
+        let prefix = headers
+            .iter()
+            .map(|(_, header)| header.as_str())
+            .collect::<Vec<&str>>()
+            .join("\n");
+        let body_size = size.saturating_sub(token_count(&prefix)).max(1);
+        for mut chunk in recursive(
+            body,
+            start,
+            body_size,
+            overlap.min(body_size - 1),
+            &DEFAULT_SEPARATORS,
+        ) {
+            if !prefix.is_empty() {
+                chunk.text = format!("{}\n\n{}", prefix, chunk.text);
+            }
+            chunks.push(chunk);
+        }
+    };
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let line_end = line_start + line.len();
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        }
+        if let Some(level) = header_level(line).filter(|_| !in_code_block) {
+            flush(&headers, body_start, line_start);
+            body_start = line_end;
+            headers.retain(|(l, _)| *l < level);
+            headers.push((level, line.trim().to_string()));
+        }
+        line_start = line_end;
+    }
+    flush(&headers, body_start, text.len());
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::builtin_extractors::transcription::SEGMENTS_METADATA_KEY;
+
+    fn split(chunker: &Chunker, text: &str) -> Vec<String> {
+        spans(chunker, text)
+            .into_iter()
+            .map(|span| span.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_fixed_tokens() {
+        let chunker = Chunker::FixedTokens {
+            size: 4,
+            overlap: 1,
+        };
+        assert_eq!(split(&chunker, "a b c d e f g"), vec!["a b c d", "d e f g"]);
+        assert_eq!(split(&chunker, "a b"), vec!["a b"]);
+        assert!(split(&chunker, "  ").is_empty());
+    }
+
+    #[test]
+    fn test_sentence() {
+        let chunker = Chunker::Sentence {
+            size: 6,
+            overlap: 0,
+        };
+        assert_eq!(
+            split(&chunker, "One two three. Four five! Six seven eight? Nine."),
+            vec!["One two three. Four five!", "Six seven eight? Nine."]
+        );
+        let chunker = Chunker::Sentence {
+            size: 6,
+            overlap: 3,
+        };
+        assert_eq!(
+            split(&chunker, "One two three. Four five six. Seven."),
+            vec!["One two three. Four five six.", "Four five six. Seven."]
+        );
+    }
+
+    #[test]
+    fn test_recursive() {
+        let chunker = Chunker::Recursive {
+            size: 5,
+            overlap: 0,
+            separators: vec![],
+        };
+        let text = "a b c\n\nd e f g h i j\n\nk";
+        assert_eq!(
+            split(&chunker, text),
+            vec!["a b c", "d e f g h", "i j\n\nk"]
+        );
+    }
+
+    #[test]
+    fn test_markdown() {
+        let chunker = Chunker::Markdown {
+            size: 20,
+            overlap: 0,
+        };
+        let text = "intro\n# Guide\nsetup steps\n## Install\nrun it\n```\n# not a header\n```\n# Faq\nnone";
+        assert_eq!(
+            split(&chunker, text),
+            vec![
+                "intro",
+                "# Guide\n\nsetup steps",
+                "# Guide\n## Install\n\nrun it\n```\n# not a header\n```",
+                "# Faq\n\nnone",
+            ]
+        );
+    }
+
+    fn text_content(text: &str) -> internal_api::Content {
+        internal_api::Content {
+            content_type: mime::TEXT_PLAIN.to_string(),
+            source: text.into(),
+            feature: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    fn offsets(start: usize, end: usize, page: Option<u32>) -> ChunkOffsets {
+        ChunkOffsets {
+            start,
+            end,
+            page,
+            start_ms: None,
+            end_ms: None,
+        }
+    }
+
+    #[test]
+    fn test_chunk_content_with_parents() {
+        let chunker = Chunker::FixedTokens {
+            size: 2,
+            overlap: 0,
+        };
+        let parent_chunker = Chunker::Sentence {
+            size: 4,
+            overlap: 0,
+        };
+        let chunks: Vec<(String, ChunkOrigin)> = chunk_content(
+            &chunker,
+            Some(&parent_chunker),
+            text_content("One two three. Four five."),
+        )
+        .into_iter()
+        .map(|(chunk, origin)| (chunk.source_as_text().unwrap(), origin))
+        .collect();
+        let first = Some(ParentChunk {
+            text: "One two three.".into(),
+            offsets: offsets(0, 14, None),
+        });
+        let second = Some(ParentChunk {
+            text: "Four five.".into(),
+            offsets: offsets(15, 25, None),
+        });
+        assert_eq!(
+            chunks,
+            vec![
+                (
+                    "One two".into(),
+                    ChunkOrigin {
+                        offsets: Some(offsets(0, 7, None)),
+                        parent: first.clone(),
+                        region: None,
+                    }
+                ),
+                (
+                    "three.".into(),
+                    ChunkOrigin {
+                        offsets: Some(offsets(8, 14, None)),
+                        parent: first,
+                        region: None,
+                    }
+                ),
+                (
+                    "Four five.".into(),
+                    ChunkOrigin {
+                        offsets: Some(offsets(15, 25, None)),
+                        parent: second,
+                        region: None,
+                    }
+                ),
+            ]
+        );
+        assert!(validate_parent_chunker(Some(&chunker), &parent_chunker).is_ok());
+        assert!(validate_parent_chunker(None, &parent_chunker).is_err());
+        assert!(validate_parent_chunker(Some(&parent_chunker), &chunker).is_err());
+    }
+
+    #[test]
+    fn test_image_tiles() {
+        let mut source = Vec::new();
+        image::DynamicImage::new_rgb8(10, 6)
+            .write_to(&mut Cursor::new(&mut source), ImageOutputFormat::Png)
+            .unwrap();
+        let content = internal_api::Content {
+            content_type: mime::IMAGE_PNG.to_string(),
+            source,
+            feature: None,
+            metadata: HashMap::new(),
+        };
+        let chunker = Chunker::ImageTiles {
+            size: 4,
+            overlap: 0,
+        };
+        let tiles = chunk_content(&chunker, None, content.clone());
+        let regions: Vec<(u32, u32, u32, u32)> = tiles
+            .iter()
+            .map(|(_, origin)| {
+                let region = origin.region.unwrap();
+                (region.x, region.y, region.width, region.height)
+            })
+            .collect();
+        // The last tiles of a side end at the edge of the image.
+        assert_eq!(
+            regions,
+            vec![
+                (0, 0, 4, 4),
+                (4, 0, 4, 4),
+                (6, 0, 4, 4),
+                (0, 2, 4, 4),
+                (4, 2, 4, 4),
+                (6, 2, 4, 4),
+            ]
+        );
+        let tile = image::load_from_memory(&tiles[0].0.source).unwrap();
+        assert_eq!(tile.dimensions(), (4, 4));
+
+        // Images no larger than a tile, and text, are not split.
+        let chunker = Chunker::ImageTiles {
+            size: 16,
+            overlap: 0,
+        };
+        assert_eq!(chunk_content(&chunker, None, content).len(), 1);
+        let text = chunk_content(&chunker, None, text_content("a caption"));
+        assert_eq!(text.len(), 1);
+        assert_eq!(text[0].0.source_as_text().unwrap(), "a caption");
+        assert!(validate_parent_chunker(Some(&chunker), &chunker).is_err());
+    }
+
+    #[test]
+    fn test_chunk_offsets() {
+        let chunker = Chunker::Markdown {
+            size: 20,
+            overlap: 0,
+        };
+        // Offsets are in characters, and pages are counted at form feeds.
+        let text = "# Café\nréglé  ici\n\x0c## Suite\nfin";
+        let origins: Vec<Option<ChunkOffsets>> = chunk_content(&chunker, None, text_content(text))
+            .into_iter()
+            .map(|(_, origin)| origin.offsets)
+            .collect();
+        assert_eq!(
+            origins,
+            vec![
+                Some(offsets(7, 17, Some(1))),
+                Some(offsets(28, 31, Some(2)))
+            ]
+        );
+        let chunks = chunk_content(&chunker, None, text_content("  "));
+        assert_eq!(chunks[0].1, ChunkOrigin::default());
+    }
+
+    #[test]
+    fn test_transcript_times() {
+        let chunker = Chunker::FixedTokens {
+            size: 3,
+            overlap: 0,
+        };
+        let segment = |start, end, start_ms, end_ms| TranscriptSegment {
+            start,
+            end,
+            start_ms,
+            end_ms,
+        };
+        let mut content = text_content("Hello there.\nSecond part here.\nThird bit.");
+        content.metadata.insert(
+            SEGMENTS_METADATA_KEY.into(),
+            serde_json::json!([
+                segment(0, 12, 0, 1000),
+                segment(13, 30, 1000, 2500),
+                segment(31, 41, 2500, 4000),
+            ]),
+        );
+        let times: Vec<(Option<u64>, Option<u64>)> = chunk_content(&chunker, None, content)
+            .into_iter()
+            .map(|(_, origin)| {
+                let offsets = origin.offsets.unwrap();
+                (offsets.start_ms, offsets.end_ms)
+            })
+            .collect();
+        assert_eq!(
+            times,
+            vec![
+                (Some(0), Some(2500)),
+                (Some(1000), Some(4000)),
+                (Some(2500), Some(4000)),
+            ]
+        );
+        // Text without segments has no times.
+        let origins = chunk_content(&chunker, None, text_content("one two three four"));
+        assert_eq!(origins[0].1.offsets.unwrap().start_ms, None);
+    }
+
+    #[test]
+    fn test_validate_chunker() {
+        assert!(validate_chunker(&Chunker::FixedTokens {
+            size: 10,
+            overlap: 2
+        })
+        .is_ok());
+        assert!(validate_chunker(&Chunker::FixedTokens {
+            size: 10,
+            overlap: 10
+        })
+        .is_err());
+        assert!(validate_chunker(&Chunker::Sentence {
+            size: 0,
+            overlap: 0
+        })
+        .is_err());
+    }
+}
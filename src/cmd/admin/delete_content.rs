@@ -0,0 +1,52 @@
+use clap::Args as ClapArgs;
+use serde::Serialize;
+
+use super::Context;
+use crate::cmd::{
+    output::{self, CliError, CommandResult},
+    GlobalArgs,
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+    /// the repository of the content
+    #[arg(long)]
+    repository: String,
+
+    /// the ids of the content to delete
+    #[arg(required = true)]
+    content_ids: Vec<String>,
+}
+
+/// The result of `admin delete-content`. Content under a legal hold is not
+/// deleted.
+#[derive(Debug, Serialize)]
+struct DeleteContentOutput {
+    repository: String,
+    content: u64,
+    chunks: u64,
+    attributes: u64,
+    vectors: u64,
+}
+
+impl Args {
+    pub async fn run(self, context: &Context, _: GlobalArgs) -> CommandResult {
+        let repository_manager = context.repository_manager().await?;
+        let deleted = repository_manager
+            .delete_content(&context.namespace, &self.repository, &self.content_ids)
+            .await
+            .map_err(|e| {
+                CliError::failed(format!(
+                    "failed to delete the content of repository {}: {}",
+                    self.repository, e
+                ))
+            })?;
+        output::to_output(&DeleteContentOutput {
+            repository: self.repository,
+            content: deleted.content,
+            chunks: deleted.chunks,
+            attributes: deleted.attributes,
+            vectors: deleted.vectors,
+        })
+    }
+}
@@ -0,0 +1,48 @@
+use clap::Args as ClapArgs;
+use serde::Serialize;
+
+use super::Context;
+use crate::{
+    cmd::{
+        output::{self, CliError, CommandResult},
+        GlobalArgs,
+    },
+    retention::RetentionCollector,
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {}
+
+/// The result of `admin gc`, across the repositories of every namespace.
+#[derive(Debug, Serialize)]
+struct GcOutput {
+    expired: u64,
+    purged_content: u64,
+    purged_chunks: u64,
+    purged_attributes: u64,
+    purged_vectors: u64,
+    failed_repositories: u64,
+}
+
+impl Args {
+    pub async fn run(self, context: &Context, _: GlobalArgs) -> CommandResult {
+        let repository_manager = context.repository_manager().await?;
+        let collector = RetentionCollector::new(
+            context.repository.clone(),
+            repository_manager,
+            context.config.retention.clone(),
+        );
+        let summary = collector
+            .collect()
+            .await
+            .map_err(|e| CliError::failed(format!("failed to collect content: {}", e)))?;
+        output::to_output(&GcOutput {
+            expired: summary.expired,
+            purged_content: summary.purged.content,
+            purged_chunks: summary.purged.chunks,
+            purged_attributes: summary.purged.attributes,
+            purged_vectors: summary.purged.vectors,
+            failed_repositories: summary.failed_repositories,
+        })
+    }
+}
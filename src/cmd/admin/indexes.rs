@@ -0,0 +1,49 @@
+use clap::Args as ClapArgs;
+use serde::Serialize;
+
+use super::Context;
+use crate::cmd::{
+    output::{self, CliError, CommandResult},
+    GlobalArgs,
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+    /// the repository whose indexes are listed
+    #[arg(long)]
+    repository: String,
+}
+
+/// A row of `admin indexes`.
+#[derive(Debug, Serialize)]
+struct IndexOutput {
+    name: String,
+    kind: String,
+    extractor_binding: Option<String>,
+    output: Option<String>,
+}
+
+impl Args {
+    pub async fn run(self, context: &Context, _: GlobalArgs) -> CommandResult {
+        let indexes = context
+            .repository
+            .list_indexes(&context.namespace, &self.repository)
+            .await
+            .map_err(|e| {
+                CliError::failed(format!(
+                    "failed to list the indexes of repository {}: {}",
+                    self.repository, e
+                ))
+            })?;
+        let rows: Vec<IndexOutput> = indexes
+            .into_iter()
+            .map(|i| IndexOutput {
+                kind: i.schema.to_string(),
+                name: i.name,
+                extractor_binding: i.extractor_binding,
+                output: i.output,
+            })
+            .collect();
+        output::to_output(&rows)
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use clap::{Args as ClapArgs, Subcommand};
+
+use super::{
+    output::{CliError, CommandResult},
+    GlobalArgs,
+};
+use crate::{
+    attribute_index::AttributeIndexManager,
+    blob_storage::BlobStorageBuilder,
+    data_repository_manager::DataRepositoryManager,
+    persistence::Repository,
+    prelude::*,
+    server_config::ServerConfig,
+    vector_index::VectorIndexManager,
+    vectordbs::{self, resilience::ResilientVectorDb},
+};
+
+mod delete_content;
+mod gc;
+mod indexes;
+mod replay_events;
+mod repositories;
+mod requeue;
+mod work;
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+    /// path to the server config file
+    #[arg(global = true, short = 'c', long, default_value = "indexify.yaml")]
+    config_path: String,
+
+    /// the namespace the command operates on
+    #[arg(global = true, long, default_value = "default")]
+    namespace: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+impl Args {
+    pub async fn run(self, global_args: GlobalArgs) -> CommandResult {
+        let Self {
+            config_path,
+            namespace,
+            command,
+        } = self;
+
+        info!("using config file: {}", &config_path);
+        let config = ServerConfig::from_path(&config_path).map_err(|e| {
+            CliError::config(format!("failed to load config {}: {}", config_path, e))
+        })?;
+        let repository = Repository::new(&config.db_url, &config.database)
+            .await
+            .map_err(|e| {
+                CliError::connection(format!("failed to connect to the database: {}", e))
+            })?;
+        let context = Context {
+            config,
+            namespace,
+            repository: Arc::new(repository),
+        };
+        match command {
+            Command::Repositories(args) => args.run(&context, global_args).await,
+            Command::Indexes(args) => args.run(&context, global_args).await,
+            Command::Work(args) => args.run(&context, global_args).await,
+            Command::Requeue(args) => args.run(&context, global_args).await,
+            Command::ReplayEvents(args) => args.run(&context, global_args).await,
+            Command::DeleteContent(args) => args.run(&context, global_args).await,
+            Command::Gc(args) => args.run(&context, global_args).await,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// List the repositories of the namespace
+    Repositories(repositories::Args),
+    /// List the indexes of a repository
+    Indexes(indexes::Args),
+    /// Show how much work is in each state
+    Work(work::Args),
+    /// Move failed work back to the queue
+    Requeue(requeue::Args),
+    /// Process the extraction events of a repository again
+    ReplayEvents(replay_events::Args),
+    /// Delete content along with its chunks, embeddings and attributes
+    DeleteContent(delete_content::Args),
+    /// Expire and purge content by the retention policies of the repositories
+    Gc(gc::Args),
+}
+
+/// What the admin commands share: the config of the deployment and its
+/// database.
+pub struct Context {
+    config: ServerConfig,
+    namespace: String,
+    repository: Arc<Repository>,
+}
+
+impl Context {
+    /// The manager of the repositories, for the commands which also change
+    /// the vector store, built the same way the server builds it.
+    async fn repository_manager(&self) -> Result<Arc<DataRepositoryManager>, CliError> {
+        let vector_db = vectordbs::create_vectordb(
+            self.config.index_config.clone(),
+            self.repository.get_db_conn_clone(),
+        )
+        .map_err(|e| {
+            CliError::connection(format!("failed to connect to the vector store: {}", e))
+        })?;
+        let vector_db =
+            ResilientVectorDb::wrap(vector_db, self.config.index_config.resilience.clone());
+        let coordinator_addr = self
+            .config
+            .coordinator_lis_addr_sock()
+            .map_err(|e| CliError::config(format!("invalid coordinator address: {}", e)))?
+            .to_string();
+        let vector_index_manager = Arc::new(VectorIndexManager::new(
+            self.repository.clone(),
+            vector_db,
+            coordinator_addr,
+        ));
+        let attribute_index_manager = Arc::new(AttributeIndexManager::new(self.repository.clone()));
+        let blob_storage = BlobStorageBuilder::new(Arc::new(self.config.blob_storage.clone()))
+            .build()
+            .map_err(|e| CliError::config(format!("failed to create the blob store: {}", e)))?;
+        let repository_manager = DataRepositoryManager::new(
+            self.repository.clone(),
+            vector_index_manager,
+            attribute_index_manager,
+            blob_storage,
+        )
+        .await
+        .map_err(|e| CliError::failed(format!("failed to create repository manager: {}", e)))?;
+        Ok(Arc::new(repository_manager))
+    }
+}
@@ -0,0 +1,48 @@
+use clap::Args as ClapArgs;
+use serde::Serialize;
+
+use super::Context;
+use crate::{
+    cmd::{
+        output::{self, CliError, CommandResult},
+        GlobalArgs,
+    },
+    prelude::*,
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+    /// the repository whose extraction events are replayed
+    #[arg(long)]
+    repository: String,
+}
+
+/// The result of `admin replay-events`.
+#[derive(Debug, Serialize)]
+struct ReplayEventsOutput {
+    repository: String,
+    replayed: u64,
+}
+
+impl Args {
+    pub async fn run(self, context: &Context, _: GlobalArgs) -> CommandResult {
+        let replayed = context
+            .repository
+            .replay_extraction_events(&context.namespace, &self.repository)
+            .await
+            .map_err(|e| {
+                CliError::failed(format!(
+                    "failed to replay the extraction events of repository {}: {}",
+                    self.repository, e
+                ))
+            })?;
+        info!(
+            "replayed {} extraction events of repository {}",
+            replayed, self.repository
+        );
+        output::to_output(&ReplayEventsOutput {
+            repository: self.repository,
+            replayed,
+        })
+    }
+}
@@ -0,0 +1,40 @@
+use clap::Args as ClapArgs;
+use serde::Serialize;
+
+use super::Context;
+use crate::cmd::{
+    output::{self, CliError, CommandResult},
+    GlobalArgs,
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {}
+
+/// A row of `admin repositories`.
+#[derive(Debug, Serialize)]
+struct RepositoryOutput {
+    name: String,
+    extractor_bindings: usize,
+    data_connectors: usize,
+    dedup_policy: String,
+}
+
+impl Args {
+    pub async fn run(self, context: &Context, _: GlobalArgs) -> CommandResult {
+        let repositories = context
+            .repository
+            .repositories(&context.namespace)
+            .await
+            .map_err(|e| CliError::failed(format!("failed to list repositories: {}", e)))?;
+        let rows: Vec<RepositoryOutput> = repositories
+            .into_iter()
+            .map(|r| RepositoryOutput {
+                name: r.name,
+                extractor_bindings: r.extractor_bindings.len(),
+                data_connectors: r.data_connectors.len(),
+                dedup_policy: r.dedup_policy.to_string(),
+            })
+            .collect();
+        output::to_output(&rows)
+    }
+}
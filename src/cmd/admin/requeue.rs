@@ -0,0 +1,58 @@
+use clap::Args as ClapArgs;
+use serde::Serialize;
+
+use super::Context;
+use crate::{
+    cmd::{
+        output::{self, CliError, CommandResult},
+        GlobalArgs,
+    },
+    prelude::*,
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+    /// the repository whose failed work is requeued
+    #[arg(long)]
+    repository: String,
+
+    /// only requeue the work of this extractor binding
+    #[arg(long)]
+    extractor_binding: Option<String>,
+}
+
+/// The result of `admin requeue`.
+#[derive(Debug, Serialize)]
+struct RequeueOutput {
+    repository: String,
+    extractor_binding: Option<String>,
+    requeued: u64,
+}
+
+impl Args {
+    pub async fn run(self, context: &Context, _: GlobalArgs) -> CommandResult {
+        let requeued = context
+            .repository
+            .requeue_failed_work(
+                &context.namespace,
+                &self.repository,
+                self.extractor_binding.as_deref(),
+            )
+            .await
+            .map_err(|e| {
+                CliError::failed(format!(
+                    "failed to requeue the work of repository {}: {}",
+                    self.repository, e
+                ))
+            })?;
+        info!(
+            "requeued {} failed work of repository {}",
+            requeued, self.repository
+        );
+        output::to_output(&RequeueOutput {
+            repository: self.repository,
+            extractor_binding: self.extractor_binding,
+            requeued,
+        })
+    }
+}
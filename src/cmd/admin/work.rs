@@ -0,0 +1,42 @@
+use clap::Args as ClapArgs;
+use serde::Serialize;
+
+use super::Context;
+use crate::cmd::{
+    output::{self, CliError, CommandResult},
+    GlobalArgs,
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+    /// only show the work of this repository
+    #[arg(long)]
+    repository: Option<String>,
+}
+
+/// A row of `admin work`.
+#[derive(Debug, Serialize)]
+struct WorkQueueOutput {
+    repository: String,
+    state: String,
+    work: i64,
+}
+
+impl Args {
+    pub async fn run(self, context: &Context, _: GlobalArgs) -> CommandResult {
+        let depth = context
+            .repository
+            .work_queue_depth(&context.namespace, self.repository.as_deref())
+            .await
+            .map_err(|e| CliError::failed(format!("failed to read the work queue: {}", e)))?;
+        let rows: Vec<WorkQueueOutput> = depth
+            .into_iter()
+            .map(|d| WorkQueueOutput {
+                repository: d.repository,
+                state: d.state,
+                work: d.work,
+            })
+            .collect();
+        output::to_output(&rows)
+    }
+}
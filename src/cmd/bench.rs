@@ -0,0 +1,524 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use clap::Args as ClapArgs;
+use indexify_client::{
+    ClientError,
+    ContentFilter,
+    CreateRepository,
+    ExtractionState,
+    ExtractorBinding,
+    IndexifyClient,
+    ListParams,
+    SearchRequest,
+    Text,
+    TextAddRequest,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+use tokio::{
+    sync::Semaphore,
+    task::JoinSet,
+    time::{self, MissedTickBehavior},
+};
+
+use super::{
+    output::{self, CliError, CommandResult},
+    GlobalArgs,
+};
+use crate::prelude::*;
+
+/// How often the content of the repositories is listed while waiting for its
+/// extraction, which bounds the precision of the extraction latencies.
+const EXTRACTION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+const BINDING_NAME: &str = "bench";
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+    /// the url of the server to benchmark
+    #[arg(long, default_value = "http://localhost:8900")]
+    server_url: String,
+
+    #[arg(long)]
+    namespace: Option<String>,
+
+    #[arg(long)]
+    api_key: Option<String>,
+
+    /// the extractor bound to the repositories of the benchmark
+    #[arg(long, default_value = "diptanu/minilm-l6-extractor")]
+    extractor: String,
+
+    /// the repositories created, the documents are spread across them
+    #[arg(long, default_value = "1")]
+    repositories: usize,
+
+    #[arg(long, default_value = "1000")]
+    documents: usize,
+
+    /// the documents added by a request
+    #[arg(long, default_value = "10")]
+    batch_size: usize,
+
+    /// the words of a document
+    #[arg(long, default_value = "200")]
+    document_words: usize,
+
+    /// the add requests sent per second, as fast as possible when 0
+    #[arg(long, default_value = "0")]
+    ingest_rate: f64,
+
+    #[arg(long, default_value = "100")]
+    queries: usize,
+
+    /// the searches sent per second, as fast as possible when 0
+    #[arg(long, default_value = "0")]
+    search_rate: f64,
+
+    /// the requests in flight at once
+    #[arg(long, default_value = "16")]
+    concurrency: usize,
+
+    /// seconds to wait for the documents to be extracted
+    #[arg(long, default_value = "600")]
+    extraction_timeout: u64,
+
+    /// the seed of the generated documents and queries, so that runs can be
+    /// compared
+    #[arg(long, default_value = "42")]
+    seed: u64,
+
+    /// keep the repositories of the benchmark instead of deleting them
+    #[arg(long)]
+    keep: bool,
+}
+
+/// The result of a stage of `bench`. Throughput is in items per second,
+/// documents for ingestion and extraction and queries for search.
+#[derive(Debug, Serialize, PartialEq)]
+struct StageReport {
+    stage: &'static str,
+    items: u64,
+    errors: u64,
+    duration_secs: f64,
+    throughput: f64,
+    p50_ms: f64,
+    p90_ms: f64,
+    p99_ms: f64,
+    max_ms: f64,
+}
+
+/// The latencies of the items of a stage which succeeded, and the items which
+/// failed.
+#[derive(Debug, Default)]
+struct Stage {
+    latencies: Vec<Duration>,
+    errors: u64,
+}
+
+impl Stage {
+    fn report(mut self, stage: &'static str, duration: Duration) -> StageReport {
+        self.latencies.sort();
+        let millis = |latency: Option<&Duration>| latency.map_or(0.0, |l| l.as_secs_f64() * 1000.0);
+        let items = self.latencies.len() as u64;
+        let throughput = if duration.is_zero() {
+            0.0
+        } else {
+            items as f64 / duration.as_secs_f64()
+        };
+        StageReport {
+            stage,
+            items,
+            errors: self.errors,
+            duration_secs: duration.as_secs_f64(),
+            throughput,
+            p50_ms: millis(percentile(&self.latencies, 50.0)),
+            p90_ms: millis(percentile(&self.latencies, 90.0)),
+            p99_ms: millis(percentile(&self.latencies, 99.0)),
+            max_ms: millis(self.latencies.last()),
+        }
+    }
+}
+
+/// The nearest-rank percentile of sorted latencies.
+fn percentile(sorted: &[Duration], percentile: f64) -> Option<&Duration> {
+    let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.saturating_sub(1))
+}
+
+/// A repository created by the benchmark, with the index searched.
+#[derive(Debug, Clone)]
+struct BenchRepository {
+    name: String,
+    index: Option<String>,
+}
+
+impl Args {
+    pub async fn run(self, _: GlobalArgs) -> CommandResult {
+        if self.repositories == 0 || self.batch_size == 0 || self.concurrency == 0 {
+            return Err(CliError::new(
+                output::ErrorKind::Usage,
+                "repositories, batch-size and concurrency must be at least 1",
+            ));
+        }
+        let mut client = IndexifyClient::new(&self.server_url)
+            .map_err(|e| CliError::config(format!("invalid server url: {}", e)))?;
+        if let Some(namespace) = &self.namespace {
+            client = client.with_namespace(namespace);
+        }
+        if let Some(api_key) = &self.api_key {
+            client = client.with_api_key(api_key);
+        }
+
+        let run_id = format!("{:08x}", rand::random::<u32>());
+        let mut repositories = Vec::new();
+        for i in 0..self.repositories {
+            let name = format!("bench-{}-{}", run_id, i);
+            let created = self.create_repository(&client, &name).await;
+            // Repositories created before a failure are deleted with the others.
+            match created {
+                Ok(repository) => repositories.push(repository),
+                Err(err) => {
+                    self.delete_repositories(&client, &repositories).await;
+                    return Err(err);
+                }
+            }
+        }
+        info!(
+            "created {} repositories bench-{}-*, binding {}",
+            repositories.len(),
+            run_id,
+            self.extractor
+        );
+
+        let reports = self.run_stages(&client, &repositories).await;
+        self.delete_repositories(&client, &repositories).await;
+        output::to_output(&reports)
+    }
+
+    async fn create_repository(
+        &self,
+        client: &IndexifyClient,
+        name: &str,
+    ) -> Result<BenchRepository, CliError> {
+        client
+            .create_repository(&CreateRepository::new(name))
+            .await
+            .map_err(|e| client_error(format!("failed to create repository {}", name), e))?;
+        let binding = ExtractorBinding::new(&self.extractor, BINDING_NAME);
+        let bound = client
+            .bind_extractor(name, &binding)
+            .await
+            .map_err(|e| client_error(format!("failed to bind {}", self.extractor), e))?;
+        Ok(BenchRepository {
+            name: name.to_string(),
+            index: bound.index_names.into_iter().next(),
+        })
+    }
+
+    async fn run_stages(
+        &self,
+        client: &IndexifyClient,
+        repositories: &[BenchRepository],
+    ) -> Vec<StageReport> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let vocabulary = vocabulary(&mut rng, 2000);
+
+        // Ingestion.
+        let batches: Vec<(usize, Vec<Text>)> = (0..self.documents)
+            .step_by(self.batch_size)
+            .enumerate()
+            .map(|(i, first)| {
+                let texts = (first..(first + self.batch_size).min(self.documents))
+                    .map(|_| Text::new(&sentence(&mut rng, &vocabulary, self.document_words)))
+                    .collect();
+                (i % repositories.len(), texts)
+            })
+            .collect();
+        let ingest_start = Instant::now();
+        let batches = Arc::new(batches);
+        let (_, added) = run_requests(batches.len(), self.ingest_rate, self.concurrency, |i| {
+            let client = client.clone();
+            let batches = batches.clone();
+            let repository = repositories[batches[i].0].name.clone();
+            async move {
+                let (_, texts) = &batches[i];
+                let request = TextAddRequest::new(texts.clone());
+                let response = client.add_texts(&repository, &request).await?;
+                Ok((repository, response.content_ids))
+            }
+        })
+        .await;
+        let ingest_duration = ingest_start.elapsed();
+        // The latency of a document is the latency of the request which added
+        // it, and the documents of requests which failed are errors.
+        let mut ingest_stage = Stage::default();
+        let mut pending: HashMap<String, HashMap<String, Instant>> = HashMap::new();
+        for (latency, done, (repository, content_ids)) in &added {
+            for content_id in content_ids {
+                ingest_stage.latencies.push(*latency);
+                pending
+                    .entry(repository.clone())
+                    .or_default()
+                    .insert(content_id.clone(), *done);
+            }
+        }
+        ingest_stage.errors = self.documents.saturating_sub(ingest_stage.latencies.len()) as u64;
+        let mut reports = vec![ingest_stage.report("ingest", ingest_duration)];
+        info!(
+            "added {} documents in {:.1}s",
+            reports[0].items, reports[0].duration_secs
+        );
+
+        // Extraction, from the start of the ingestion until the last document
+        // is extracted.
+        let extract_stage = self.wait_for_extraction(client, pending).await;
+        reports.push(extract_stage.report("extract", ingest_start.elapsed()));
+        info!(
+            "extracted {} documents, {} were not extracted in time",
+            reports[1].items, reports[1].errors
+        );
+
+        // Search.
+        let searched: Vec<BenchRepository> = repositories
+            .iter()
+            .filter(|r| r.index.is_some())
+            .cloned()
+            .collect();
+        if searched.is_empty() {
+            warn!(
+                "extractor {} creates no index to search, skipping search",
+                self.extractor
+            );
+            return reports;
+        }
+        let queries: Arc<Vec<(BenchRepository, String)>> = Arc::new(
+            (0..self.queries)
+                .map(|i| {
+                    let words = rng.gen_range(2..=6);
+                    (
+                        searched[i % searched.len()].clone(),
+                        sentence(&mut rng, &vocabulary, words),
+                    )
+                })
+                .collect(),
+        );
+        let search_start = Instant::now();
+        let (errors, results) =
+            run_requests(queries.len(), self.search_rate, self.concurrency, |i| {
+                let client = client.clone();
+                let queries = queries.clone();
+                async move {
+                    let (repository, query) = &queries[i];
+                    let index = repository.index.as_deref().unwrap_or_default();
+                    let request = SearchRequest::new(index, query).with_k(10);
+                    client.search(&repository.name, &request).await.map(|_| ())
+                }
+            })
+            .await;
+        let search_stage = Stage {
+            latencies: results.iter().map(|(latency, ..)| *latency).collect(),
+            errors,
+        };
+        reports.push(search_stage.report("search", search_start.elapsed()));
+        reports
+    }
+
+    /// Lists the content of the repositories until every document is
+    /// extracted by the binding, or the extraction timeout. The latency of a
+    /// document is from when it was added until it was seen extracted, and
+    /// the documents which were not extracted in time are errors.
+    async fn wait_for_extraction(
+        &self,
+        client: &IndexifyClient,
+        mut pending: HashMap<String, HashMap<String, Instant>>,
+    ) -> Stage {
+        let mut stage = Stage::default();
+        let deadline = Instant::now() + Duration::from_secs(self.extraction_timeout);
+        while pending.values().any(|p| !p.is_empty()) && Instant::now() < deadline {
+            for (repository, documents) in pending.iter_mut() {
+                if documents.is_empty() {
+                    continue;
+                }
+                let mut params = Some(ListParams {
+                    cursor: None,
+                    limit: Some(1000),
+                });
+                while let Some(current) = params {
+                    let page = match client
+                        .list_content(repository, &ContentFilter::default(), &current)
+                        .await
+                    {
+                        Ok(page) => page,
+                        Err(err) => {
+                            warn!("failed to list the content of {}: {}", repository, err);
+                            break;
+                        }
+                    };
+                    let now = Instant::now();
+                    for content in &page.items {
+                        let extracted = matches!(
+                            content.extraction_state.get(BINDING_NAME),
+                            Some(ExtractionState::Completed | ExtractionState::Skipped)
+                        );
+                        if !extracted {
+                            continue;
+                        }
+                        if let Some(added) = documents.remove(&content.id) {
+                            stage.latencies.push(now - added);
+                        }
+                    }
+                    params = current.next(&page);
+                }
+            }
+            time::sleep(EXTRACTION_POLL_INTERVAL).await;
+        }
+        stage.errors = pending.values().map(|p| p.len() as u64).sum();
+        stage
+    }
+
+    async fn delete_repositories(&self, client: &IndexifyClient, repositories: &[BenchRepository]) {
+        if self.keep {
+            return;
+        }
+        for repository in repositories {
+            if let Err(err) = client.delete_repository(&repository.name, true).await {
+                warn!("failed to delete repository {}: {}", repository.name, err);
+            }
+        }
+    }
+}
+
+/// Sends `count` requests made by `request`, at most `rate` per second when it
+/// is not 0 and at most `concurrency` at once. Returns the latency, the time
+/// of the response and the result of the requests which succeeded, with the
+/// number of requests which failed.
+async fn run_requests<T, F, Fut>(
+    count: usize,
+    rate: f64,
+    concurrency: usize,
+    request: F,
+) -> (u64, Vec<(Duration, Instant, T)>)
+where
+    T: Send + 'static,
+    F: Fn(usize) -> Fut,
+    Fut: Future<Output = Result<T, ClientError>> + Send + 'static,
+{
+    let mut interval = (rate > 0.0).then(|| {
+        let mut interval = time::interval(Duration::from_secs_f64(1.0 / rate));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval
+    });
+    let permits = Arc::new(Semaphore::new(concurrency));
+    let mut requests = JoinSet::new();
+    for i in 0..count {
+        if let Some(interval) = &mut interval {
+            interval.tick().await;
+        }
+        let Ok(permit) = permits.clone().acquire_owned().await else {
+            break;
+        };
+        let request = request(i);
+        requests.spawn(async move {
+            let start = Instant::now();
+            let result = request.await;
+            drop(permit);
+            result.map(|value| (start.elapsed(), Instant::now(), value))
+        });
+    }
+    let mut errors = 0;
+    let mut results = Vec::with_capacity(count);
+    while let Some(result) = requests.join_next().await {
+        match result {
+            Ok(Ok(result)) => results.push(result),
+            Ok(Err(err)) => {
+                // Only the first errors are logged, a server which is down
+                // fails every request.
+                if errors < 5 {
+                    warn!("request failed: {}", err);
+                }
+                errors += 1;
+            }
+            Err(err) => {
+                warn!("request panicked: {}", err);
+                errors += 1;
+            }
+        }
+    }
+    (errors, results)
+}
+
+/// Words made of random syllables, so that documents and queries share
+/// terms.
+fn vocabulary(rng: &mut StdRng, size: usize) -> Vec<String> {
+    const SYLLABLES: &[&str] = &[
+        "ka", "lo", "mi", "ne", "ru", "sa", "ti", "vo", "ze", "pa", "do", "gu", "he", "ji", "ra",
+        "be", "co", "fa", "ly", "xo",
+    ];
+    (0..size)
+        .map(|_| {
+            (0..rng.gen_range(1..=4))
+                .map(|_| SYLLABLES[rng.gen_range(0..SYLLABLES.len())])
+                .collect()
+        })
+        .collect()
+}
+
+fn sentence(rng: &mut StdRng, vocabulary: &[String], words: usize) -> String {
+    (0..words)
+        .map(|_| vocabulary[rng.gen_range(0..vocabulary.len())].as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The error of a request to the server, which is a connection error when the
+/// server did not answer.
+fn client_error(context: String, err: ClientError) -> CliError {
+    match err {
+        ClientError::Api { .. } => CliError::failed(format!("{}: {}", context, err)),
+        err => CliError::connection(format!("{}: {}", context, err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_report() {
+        let stage = Stage {
+            latencies: (1..=100).rev().map(Duration::from_millis).collect(),
+            errors: 2,
+        };
+        let report = stage.report("search", Duration::from_secs(4));
+        assert_eq!(report.items, 100);
+        assert_eq!(report.errors, 2);
+        assert_eq!(report.throughput, 25.0);
+        assert_eq!(report.p50_ms, 50.0);
+        assert_eq!(report.p90_ms, 90.0);
+        assert_eq!(report.p99_ms, 99.0);
+        assert_eq!(report.max_ms, 100.0);
+
+        let empty = Stage::default().report("extract", Duration::ZERO);
+        assert_eq!(empty.items, 0);
+        assert_eq!(empty.p99_ms, 0.0);
+        assert_eq!(empty.throughput, 0.0);
+    }
+
+    #[test]
+    fn test_generated_text_is_deterministic() {
+        let generate = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let vocabulary = vocabulary(&mut rng, 100);
+            sentence(&mut rng, &vocabulary, 20)
+        };
+        assert_eq!(generate(7), generate(7));
+        assert_ne!(generate(7), generate(8));
+        assert_eq!(generate(7).split(' ').count(), 20);
+    }
+}
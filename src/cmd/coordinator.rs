@@ -2,8 +2,17 @@ use std::sync::Arc;
 
 use clap::Args as ClapArgs;
 
-use super::GlobalArgs;
-use crate::{coordinator_service::CoordinatorServer, prelude::*, server_config::ServerConfig};
+use super::{
+    output::{CliError, CommandResult},
+    GlobalArgs,
+};
+use crate::{
+    coordinator_service::CoordinatorServer,
+    id_generator,
+    prelude::*,
+    server_config::ServerConfig,
+    tls,
+};
 
 #[derive(Debug, ClapArgs)]
 pub struct Args {
@@ -12,15 +21,28 @@ pub struct Args {
 }
 
 impl Args {
-    pub async fn run(self, _: GlobalArgs) {
+    pub async fn run(self, _: GlobalArgs) -> CommandResult {
         let Self { config_path } = self;
 
         info!("starting indexify coordinator, version: {}", crate::VERSION);
-        let config = ServerConfig::from_path(&config_path)
-            .unwrap_or_else(|_| panic!("failed to load config for coordinator: {}", config_path));
+        let config = ServerConfig::from_path(&config_path).map_err(|e| {
+            CliError::config(format!(
+                "failed to load config for coordinator {}: {}",
+                config_path, e
+            ))
+        })?;
+        id_generator::configure(&config.ids);
+        tls::configure_internal(config.tls.internal.as_ref())
+            .map_err(|e| CliError::config(format!("invalid internal tls config: {}", e)))?;
         let coordinator = CoordinatorServer::new(Arc::new(config))
             .await
-            .expect("failed to create coordinator server");
-        coordinator.run().await.expect("failed to run coordinator");
+            .map_err(|e| {
+                CliError::connection(format!("failed to create coordinator server: {}", e))
+            })?;
+        coordinator
+            .run()
+            .await
+            .map_err(|e| CliError::failed(format!("failed to run coordinator: {}", e)))?;
+        Ok(None)
     }
 }
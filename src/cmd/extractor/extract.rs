@@ -1,6 +1,9 @@
 use clap::Args as ClapArgs;
 
-use crate::cmd::GlobalArgs;
+use crate::cmd::{
+    output::{self, CliError, CommandResult},
+    GlobalArgs,
+};
 
 #[derive(Debug, ClapArgs)]
 pub struct Args {
@@ -21,7 +24,7 @@ pub struct Args {
 }
 
 impl Args {
-    pub async fn run(self, _extractor_config_path: String, _: GlobalArgs) {
+    pub async fn run(self, _extractor_config_path: String, _: GlobalArgs) -> CommandResult {
         let Self {
             extractor_path,
             cache_dir,
@@ -31,18 +34,18 @@ impl Args {
         } = self;
 
         if let Some(name) = name {
-            let _ = crate::extractor::run_docker_extractor(name, cache_dir, text, file)
+            // The output of the container is streamed as the extractor in it
+            // prints it.
+            crate::extractor::run_docker_extractor(name, cache_dir, text, file)
                 .await
-                .expect("failed to run docker image");
+                .map_err(|e| CliError::connection(format!("failed to run docker image: {}", e)))?;
+            Ok(None)
         } else {
             let extracted_content =
-                crate::extractor::run_local_extractor(extractor_path, text, file)
-                    .expect("failed to run local extractor");
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&extracted_content)
-                    .expect("unable to serialize extracted content as JSON")
-            );
+                crate::extractor::run_local_extractor(extractor_path, text, file).map_err(|e| {
+                    CliError::failed(format!("failed to run local extractor: {}", e))
+                })?;
+            output::to_output(&extracted_content)
         }
     }
 }
@@ -1,6 +1,6 @@
 use clap::{Args as ClapArgs, Subcommand};
 
-use super::GlobalArgs;
+use super::{output::CommandResult, GlobalArgs};
 use crate::prelude::*;
 
 mod extract;
@@ -19,7 +19,7 @@ pub struct Args {
 }
 
 impl Args {
-    pub async fn run(self, global_args: GlobalArgs) {
+    pub async fn run(self, global_args: GlobalArgs) -> CommandResult {
         let Self {
             config_path,
             command,
@@ -1,6 +1,13 @@
 use clap::Args as ClapArgs;
+use serde::Serialize;
 
-use crate::{cmd::GlobalArgs, prelude::*};
+use crate::{
+    cmd::{
+        output::{self, CliError, CommandResult},
+        GlobalArgs,
+    },
+    prelude::*,
+};
 
 #[derive(Debug, ClapArgs)]
 pub struct Args {
@@ -13,14 +20,28 @@ pub struct Args {
     name: String,
 }
 
+/// The result of `extractor new`.
+#[derive(Debug, Serialize)]
+struct NewOutput {
+    name: String,
+    path: String,
+}
+
 impl Args {
-    pub async fn run(self, _extractor_config_path: String, _: GlobalArgs) {
+    pub async fn run(self, _extractor_config_path: String, _: GlobalArgs) -> CommandResult {
         let Self { path, name } = self;
 
-        let current_dir = std::env::current_dir().expect("cannot get current directory");
-        let current_dir = current_dir.to_str().to_owned().unwrap();
-        let path = path.unwrap_or_else(|| current_dir.to_string());
+        let path = match path {
+            Some(path) => path,
+            None => std::env::current_dir()
+                .map_err(|e| CliError::failed(format!("cannot get current directory: {}", e)))?
+                .to_string_lossy()
+                .into_owned(),
+        };
         info!("creating new extractor at: {}", path);
-        crate::extractor::create_extractor_template(&path, &name).unwrap()
+        crate::extractor::create_extractor_template(&path, &name).map_err(|e| {
+            CliError::failed(format!("failed to create extractor at {}: {}", path, e))
+        })?;
+        output::to_output(&NewOutput { name, path })
     }
 }
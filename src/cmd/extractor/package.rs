@@ -1,6 +1,13 @@
 use clap::Args as ClapArgs;
+use serde::Serialize;
 
-use crate::{cmd::GlobalArgs, prelude::*};
+use crate::{
+    cmd::{
+        output::{self, CliError, CommandResult},
+        GlobalArgs,
+    },
+    prelude::*,
+};
 
 #[derive(Debug, ClapArgs)]
 pub struct Args {
@@ -11,15 +18,25 @@ pub struct Args {
     verbose: bool,
 }
 
+/// The result of `extractor package`.
+#[derive(Debug, Serialize)]
+struct PackageOutput {
+    image: String,
+}
+
 impl Args {
-    pub async fn run(self, extractor_config_path: String, _: GlobalArgs) {
+    pub async fn run(self, extractor_config_path: String, _: GlobalArgs) -> CommandResult {
         let Self { dev, verbose } = self;
 
         info!("starting indexify packager, version: {}", crate::VERSION);
-        crate::package::Packager::new(extractor_config_path, dev)
-            .expect("failed to create packager")
+        let packager = crate::package::Packager::new(extractor_config_path, dev)
+            .map_err(|e| CliError::config(format!("failed to create packager: {}", e)))?;
+        packager
             .package(verbose)
             .await
-            .expect("failed to package extractor");
+            .map_err(|e| CliError::failed(format!("failed to package extractor: {}", e)))?;
+        output::to_output(&PackageOutput {
+            image: packager.image_name().to_string(),
+        })
     }
 }
@@ -2,11 +2,17 @@ use std::sync::Arc;
 
 use clap::Args as ClapArgs;
 
+#[cfg(feature = "fault-injection")]
+use crate::server_config::FaultConfig;
 use crate::{
-    cmd::GlobalArgs,
+    cmd::{
+        output::{CliError, CommandResult},
+        GlobalArgs,
+    },
     executor_server::ExecutorServer,
     prelude::*,
-    server_config::ExecutorConfig,
+    server_config::{ExecutorConfig, TlsConfig},
+    tls,
 };
 
 #[derive(Debug, ClapArgs)]
@@ -18,27 +24,78 @@ pub struct Args {
     /// address of the indexify server
     #[arg(long)]
     coordinator_addr: String,
+
+    /// fraction of the extractor calls to fail, for testing retries
+    #[cfg(feature = "fault-injection")]
+    #[arg(long, default_value_t = 0.0)]
+    fault_failure_rate: f64,
+
+    /// latency in milliseconds to add to every extractor call
+    #[cfg(feature = "fault-injection")]
+    #[arg(long, default_value_t = 0)]
+    fault_latency_ms: u64,
+
+    /// certificate the executor serves and presents to the coordinator,
+    /// enables tls
+    #[arg(long, requires = "tls_key")]
+    tls_cert: Option<String>,
+
+    /// private key of the certificate
+    #[arg(long, requires = "tls_cert")]
+    tls_key: Option<String>,
+
+    /// CA bundle the coordinator and the clients of the executor are verified
+    /// with
+    #[arg(long, requires = "tls_cert")]
+    tls_ca: Option<String>,
+
+    /// reject clients without a certificate signed by the CA bundle
+    #[arg(long, requires = "tls_ca")]
+    tls_verify_client_certs: bool,
+
+    /// directory to keep the work of the executor in, so that it resumes the
+    /// work after a restart
+    #[arg(long)]
+    work_store_path: Option<String>,
 }
 
 impl Args {
-    pub async fn run(self, extractor_config_path: String, _: GlobalArgs) {
-        let Self {
-            advertise_addr,
-            coordinator_addr,
-        } = self;
-
+    pub async fn run(self, extractor_config_path: String, _: GlobalArgs) -> CommandResult {
         info!("starting indexify executor, version: {}", crate::VERSION);
-        let executor_config = Arc::new(
-            ExecutorConfig::default()
-                .with_advertise_addr(advertise_addr)
-                .expect("unable to use the provided advertise address")
-                .with_coordinator_addr(coordinator_addr),
-        );
+        let executor_config = ExecutorConfig::default()
+            .with_advertise_addr(self.advertise_addr)
+            .map_err(|e| {
+                CliError::config(format!(
+                    "unable to use the provided advertise address: {}",
+                    e
+                ))
+            })?
+            .with_coordinator_addr(self.coordinator_addr)
+            .with_work_store_path(self.work_store_path)
+            .with_tls(
+                self.tls_cert
+                    .zip(self.tls_key)
+                    .map(|(cert_path, key_path)| TlsConfig {
+                        cert_path,
+                        key_path,
+                        ca_path: self.tls_ca,
+                        verify_client_certs: self.tls_verify_client_certs,
+                    }),
+            );
+        tls::configure_internal(executor_config.tls.as_ref())
+            .map_err(|e| CliError::config(format!("invalid tls config: {}", e)))?;
+        #[cfg(feature = "fault-injection")]
+        let executor_config = executor_config.with_extractor_faults(FaultConfig {
+            failure_rate: self.fault_failure_rate,
+            latency_ms: self.fault_latency_ms,
+        });
+        let executor_config = Arc::new(executor_config);
         ExecutorServer::new(&extractor_config_path, executor_config)
             .await
-            .expect("failed to create executor server")
+            .map_err(|e| CliError::failed(format!("failed to create executor server: {}", e)))?
             .run()
             .await
-            .expect("failed to run executor server");
+            .map_err(|e| CliError::failed(format!("failed to run executor server: {}", e)))?;
+        Ok(None)
     }
 }
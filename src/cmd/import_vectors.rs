@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use clap::Args as ClapArgs;
+use serde::Serialize;
+
+use super::{
+    output::{self, CliError, CommandResult},
+    GlobalArgs,
+};
+use crate::{
+    persistence::Repository,
+    prelude::*,
+    server_config::ServerConfig,
+    vector_import::{ImportOptions, VectorImport},
+    vectordbs::{self, resilience::ResilientVectorDb},
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+    /// path to the server config file
+    #[arg(long, short = 'c')]
+    config_path: String,
+
+    #[arg(long, default_value = "default")]
+    namespace: String,
+
+    /// the repository to import the collection into
+    #[arg(long)]
+    repository: String,
+
+    /// the name of the index the collection becomes
+    #[arg(long)]
+    index: String,
+
+    /// the collection of the vector store to import
+    #[arg(long)]
+    collection: String,
+
+    /// the extractor which embedded the collection
+    #[arg(long)]
+    extractor: String,
+
+    /// the payload field which holds the text of a point
+    #[arg(long, default_value = "text")]
+    text_field: String,
+
+    #[arg(long, default_value = "256")]
+    batch_size: u32,
+}
+
+/// The result of `import-vectors`.
+#[derive(Debug, Serialize)]
+struct ImportVectorsOutput {
+    namespace: String,
+    repository: String,
+    index: String,
+    collection: String,
+    imported: u64,
+    skipped: u64,
+}
+
+impl Args {
+    pub async fn run(self, _: GlobalArgs) -> CommandResult {
+        let config = ServerConfig::from_path(&self.config_path).map_err(|e| {
+            CliError::config(format!("failed to load config {}: {}", self.config_path, e))
+        })?;
+        let repository = Repository::new(&config.db_url, &config.database)
+            .await
+            .map_err(|e| {
+                CliError::connection(format!("failed to connect to the database: {}", e))
+            })?;
+        let vector_db =
+            vectordbs::create_vectordb(config.index_config.clone(), repository.get_db_conn_clone())
+                .map_err(|e| {
+                    CliError::connection(format!("failed to connect to the vector store: {}", e))
+                })?;
+        let vector_db = ResilientVectorDb::wrap(vector_db, config.index_config.resilience.clone());
+        let options = ImportOptions {
+            namespace: self.namespace,
+            repository: self.repository,
+            index: self.index,
+            collection: self.collection,
+            extractor: self.extractor,
+            text_field: self.text_field,
+            batch_size: self.batch_size,
+        };
+        let summary = VectorImport::new(Arc::new(repository), vector_db)
+            .run(&options)
+            .await
+            .map_err(|e| {
+                CliError::failed(format!(
+                    "failed to import collection {}: {}",
+                    options.collection, e
+                ))
+            })?;
+        info!(
+            "imported {} points of collection {} into index {}, skipped {} points without a {}",
+            summary.imported,
+            options.collection,
+            options.index,
+            summary.skipped,
+            options.text_field
+        );
+        output::to_output(&ImportVectorsOutput {
+            namespace: options.namespace,
+            repository: options.repository,
+            index: options.index,
+            collection: options.collection,
+            imported: summary.imported,
+            skipped: summary.skipped,
+        })
+    }
+}
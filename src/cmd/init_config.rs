@@ -1,7 +1,11 @@
 use clap::Args as ClapArgs;
+use serde::Serialize;
 
-use super::GlobalArgs;
-use crate::server_config::ServerConfig;
+use super::{
+    output::{self, CliError, CommandResult},
+    GlobalArgs,
+};
+use crate::{prelude::*, server_config::ServerConfig};
 
 #[derive(Debug, ClapArgs)]
 pub struct Args {
@@ -9,15 +13,23 @@ pub struct Args {
     config_path: String,
 }
 
+/// The result of `init-config`.
+#[derive(Debug, Serialize)]
+struct InitConfigOutput {
+    config_path: String,
+}
+
 impl Args {
-    pub async fn run(self, _: GlobalArgs) {
+    pub async fn run(self, _: GlobalArgs) -> CommandResult {
         let Self { config_path } = self;
 
-        println!("Initializing config file at: {}", &config_path);
-
-        // Initialize the error message earlier so that the String can be moved to
-        // ServerConfig
-        let error_message = format!("failed to generate config file at: {}", &config_path);
-        ServerConfig::generate(config_path).expect(&error_message);
+        info!("initializing config file at: {}", &config_path);
+        ServerConfig::generate(config_path.clone()).map_err(|e| {
+            CliError::config(format!(
+                "failed to generate config file at {}: {}",
+                config_path, e
+            ))
+        })?;
+        output::to_output(&InitConfigOutput { config_path })
     }
 }
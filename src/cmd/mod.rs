@@ -1,8 +1,16 @@
+use std::process::ExitCode;
+
 use clap::{Args, Parser, Subcommand};
 
+use self::output::{CliError, ErrorKind, OutputFormat};
+
+mod admin;
+mod bench;
 mod coordinator;
 mod extractor;
+mod import_vectors;
 mod init_config;
+mod output;
 mod server;
 
 /// Global arguments for the CLI. These are arguments that are shared across all
@@ -18,6 +26,10 @@ pub struct GlobalArgs {
         action = clap::ArgAction::Count,
     )]
     pub verbosity: u8,
+
+    /// how the result of the command is printed
+    #[arg(global = true, long, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
 }
 
 /// The list of commands that can be run on indexify.
@@ -28,6 +40,13 @@ pub enum Commands {
     Coordinator(coordinator::Args),
     InitConfig(init_config::Args),
     Extractor(extractor::Args),
+    /// Bootstrap an index from an existing collection of the vector store
+    ImportVectors(import_vectors::Args),
+    /// Operate a deployment from its database
+    Admin(admin::Args),
+    /// Measure the throughput and latency of a server, from ingestion to
+    /// search
+    Bench(bench::Args),
 }
 
 /// The main CLI struct. This is the root of the CLI tree.
@@ -42,13 +61,44 @@ pub struct Cli {
 }
 
 impl Cli {
-    /// Run the CLI
-    pub async fn run(self) {
-        match self.command {
+    /// Parse the arguments of the process. Invalid arguments are reported in
+    /// the requested output format, and help and the version as they are.
+    pub fn parse_args() -> Result<Self, ExitCode> {
+        let args: Vec<String> = std::env::args().collect();
+        Self::try_parse_from(&args).map_err(|err| {
+            let format = OutputFormat::from_args(&args);
+            if !err.use_stderr() || format == OutputFormat::Table {
+                err.exit();
+            }
+            let error = CliError::new(ErrorKind::Usage, err.to_string().trim());
+            output::print_error(format, &error);
+            error.exit_code()
+        })
+    }
+
+    /// Run the CLI. The result of the command is printed to stdout and the
+    /// exit code tells why the command failed, see `output::ErrorKind`.
+    pub async fn run(self) -> ExitCode {
+        let format = self.global_args.output;
+        let result = match self.command {
             Commands::Server(args) => args.run(self.global_args).await,
             Commands::Coordinator(args) => args.run(self.global_args).await,
             Commands::InitConfig(args) => args.run(self.global_args).await,
             Commands::Extractor(args) => args.run(self.global_args).await,
+            Commands::ImportVectors(args) => args.run(self.global_args).await,
+            Commands::Admin(args) => args.run(self.global_args).await,
+            Commands::Bench(args) => args.run(self.global_args).await,
+        };
+        match result {
+            Ok(Some(output)) => {
+                output::print_result(format, &output);
+                ExitCode::SUCCESS
+            }
+            Ok(None) => ExitCode::SUCCESS,
+            Err(error) => {
+                output::print_error(format, &error);
+                error.exit_code()
+            }
         }
     }
 }
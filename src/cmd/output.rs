@@ -0,0 +1,304 @@
+use std::{fmt, process::ExitCode};
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// How the result of a command is printed to stdout. Logs are written to
+/// stderr, so stdout only carries the result.
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Yaml,
+}
+
+impl OutputFormat {
+    /// The format requested in raw arguments, for the errors of arguments
+    /// which could not be parsed.
+    pub fn from_args(args: &[String]) -> Self {
+        let value = args.iter().enumerate().find_map(|(i, arg)| {
+            if arg == "--output" {
+                args.get(i + 1).map(|v| v.as_str())
+            } else {
+                arg.strip_prefix("--output=")
+            }
+        });
+        match value {
+            Some("json") => Self::Json,
+            Some("yaml") => Self::Yaml,
+            _ => Self::Table,
+        }
+    }
+}
+
+/// Why a command failed, which decides its exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The command failed while it ran.
+    Failed,
+    /// The arguments of the command are invalid.
+    Usage,
+    /// The configuration could not be read or is invalid.
+    Config,
+    /// A service the command depends on, such as the database, the vector
+    /// store or docker, could not be reached.
+    Connection,
+}
+
+impl ErrorKind {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Failed => 1,
+            Self::Usage => 2,
+            Self::Config => 3,
+            Self::Connection => 4,
+        }
+    }
+}
+
+/// The error of a command, printed in the output format of the command.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CliError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl CliError {
+    pub fn new(kind: ErrorKind, message: impl fmt::Display) -> Self {
+        Self {
+            kind,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn failed(message: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::Failed, message)
+    }
+
+    pub fn config(message: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::Config, message)
+    }
+
+    pub fn connection(message: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::Connection, message)
+    }
+
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(self.kind.exit_code())
+    }
+}
+
+/// The result of a command, which commands running until they are stopped,
+/// like the server, do not have.
+pub type CommandResult = Result<Option<Output>, CliError>;
+
+/// The result of a command as it is printed. The objects of a json value
+/// have sorted keys, so the order of the fields of the result is kept apart
+/// for the columns of the table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+    pub value: Value,
+    pub columns: Vec<String>,
+}
+
+/// Converts the result of a command into the value which is printed.
+pub fn to_output<T: Serialize>(result: &T) -> CommandResult {
+    let value = serde_json::to_value(result).map_err(CliError::failed)?;
+    // The mappings of yaml values keep the order of the fields.
+    let columns = serde_yaml::to_value(result)
+        .map(|v| field_order(&v))
+        .unwrap_or_default();
+    Ok(Some(Output { value, columns }))
+}
+
+/// The fields of an object, or of the objects of a list, in the order they
+/// are serialized.
+fn field_order(value: &serde_yaml::Value) -> Vec<String> {
+    let objects = match value {
+        serde_yaml::Value::Sequence(items) => items.iter().collect(),
+        value => vec![value],
+    };
+    let mut columns: Vec<String> = Vec::new();
+    for key in objects
+        .into_iter()
+        .filter_map(|o| o.as_mapping())
+        .flat_map(|o| o.keys())
+        .filter_map(|k| k.as_str())
+    {
+        if !columns.iter().any(|c| c == key) {
+            columns.push(key.into());
+        }
+    }
+    columns
+}
+
+/// Prints the result of a command to stdout.
+pub fn print_result(format: OutputFormat, output: &Output) {
+    let rendered = match format {
+        OutputFormat::Table => table(&output.value, &output.columns),
+        format => render(format, &output.value),
+    };
+    println!("{}", rendered);
+}
+
+/// Prints the error of a command, to stdout as `{"error": ...}` when the
+/// output is machine readable, so that scripts parse a single stream, and to
+/// stderr otherwise.
+pub fn print_error(format: OutputFormat, error: &CliError) {
+    match format {
+        OutputFormat::Table => eprintln!("error: {}", error.message),
+        _ => println!("{}", render(format, &serde_json::json!({ "error": error }))),
+    }
+}
+
+fn render(format: OutputFormat, value: &Value) -> String {
+    match format {
+        OutputFormat::Json => serde_json::to_string_pretty(value).unwrap_or_default(),
+        OutputFormat::Yaml => serde_yaml::to_string(value).unwrap_or_default(),
+        OutputFormat::Table => table(value, &[]),
+    }
+}
+
+/// Lists of objects are printed as a table with a column per key, objects as
+/// a table of their keys and values, and other values as they are. Keys are
+/// in the order of `columns`, followed by the keys which are not in it.
+fn table(value: &Value, columns: &[String]) -> String {
+    match value {
+        Value::Array(items) if items.iter().all(|i| i.is_object()) => {
+            let mut columns: Vec<&str> = columns
+                .iter()
+                .map(|c| c.as_str())
+                .filter(|c| items.iter().any(|i| i.get(c).is_some()))
+                .collect();
+            for item in items {
+                for key in item.as_object().into_iter().flat_map(|o| o.keys()) {
+                    if !columns.contains(&key.as_str()) {
+                        columns.push(key);
+                    }
+                }
+            }
+            let header = columns.iter().map(|c| c.to_uppercase()).collect();
+            let rows = items
+                .iter()
+                .map(|item| columns.iter().map(|c| cell(&item[*c])).collect())
+                .collect();
+            aligned(header, rows)
+        }
+        Value::Object(fields) => {
+            let mut keys: Vec<&String> =
+                columns.iter().filter(|c| fields.contains_key(*c)).collect();
+            keys.extend(fields.keys().filter(|k| !columns.contains(k)));
+            aligned(
+                vec!["KEY".into(), "VALUE".into()],
+                keys.into_iter()
+                    .map(|key| vec![key.clone(), cell(&fields[key])])
+                    .collect(),
+            )
+        }
+        Value::Array(items) => items.iter().map(cell).collect::<Vec<_>>().join("\n"),
+        value => cell(value),
+    }
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => "".into(),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn aligned(header: Vec<String>, rows: Vec<Vec<String>>) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.len()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    std::iter::once(header)
+        .chain(rows)
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Row {
+        name: &'static str,
+        indexes: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        owner: Option<&'static str>,
+    }
+
+    #[test]
+    fn test_table() {
+        let rows = vec![
+            Row {
+                name: "docs",
+                indexes: 2,
+                owner: None,
+            },
+            Row {
+                name: "mail",
+                indexes: 10,
+                owner: Some(""),
+            },
+        ];
+        let output = to_output(&rows).unwrap().unwrap();
+        assert_eq!(output.columns, vec!["name", "indexes", "owner"]);
+        assert_eq!(
+            table(&output.value, &output.columns),
+            "NAME  INDEXES  OWNER\ndocs  2\nmail  10"
+        );
+        assert_eq!(
+            table(
+                &json!({"index": "docs.embedding", "imported": 3}),
+                &["imported".into()]
+            ),
+            "KEY       VALUE\nimported  3\nindex     docs.embedding"
+        );
+        assert_eq!(table(&json!("done"), &[]), "done");
+    }
+
+    #[test]
+    fn test_output_format_from_args() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            OutputFormat::from_args(&args(&["indexify", "--output", "json", "server"])),
+            OutputFormat::Json
+        );
+        assert_eq!(
+            OutputFormat::from_args(&args(&["indexify", "--output=yaml"])),
+            OutputFormat::Yaml
+        );
+        assert_eq!(
+            OutputFormat::from_args(&args(&["indexify", "server"])),
+            OutputFormat::Table
+        );
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(CliError::config("bad config").kind.exit_code(), 3);
+        assert_eq!(
+            serde_json::to_value(CliError::connection("no database")).unwrap(),
+            json!({"kind": "connection", "message": "no database"})
+        );
+    }
+}
@@ -2,12 +2,17 @@ use std::sync::Arc;
 
 use clap::Args as ClapArgs;
 
-use super::GlobalArgs;
+use super::{
+    output::{CliError, CommandResult},
+    GlobalArgs,
+};
 use crate::{
     coordinator_service::CoordinatorServer,
+    id_generator,
     prelude::*,
     server,
     server_config::ServerConfig,
+    tls,
 };
 
 #[derive(Debug, ClapArgs)]
@@ -18,37 +23,56 @@ pub struct Args {
 
     #[arg(short, long)]
     dev_mode: bool,
+
+    /// serve searches only, rejecting requests which write
+    #[arg(long)]
+    read_only: bool,
 }
 
 impl Args {
-    pub async fn run(self, _: GlobalArgs) {
+    pub async fn run(self, _: GlobalArgs) -> CommandResult {
         let Self {
             config_path,
             dev_mode,
+            read_only,
         } = self;
 
         info!("starting indexify server, version: {}", crate::VERSION);
-        let config = ServerConfig::from_path(&config_path)
-            .unwrap_or_else(|_| panic!("failed to load config: {}", config_path));
+        let mut config = ServerConfig::from_path(&config_path).map_err(|e| {
+            CliError::config(format!("failed to load config {}: {}", config_path, e))
+        })?;
+        config.read_only |= read_only;
+        id_generator::configure(&config.ids);
+        tls::configure_internal(config.tls.internal.as_ref())
+            .map_err(|e| CliError::config(format!("invalid internal tls config: {}", e)))?;
 
         debug!("Server config is: {:?}", config);
-        let server =
-            server::Server::new(Arc::new(config.clone())).expect("failed to create server");
+        let server = server::Server::new(Arc::new(config.clone()))
+            .map_err(|e| CliError::config(format!("failed to create server: {}", e)))?;
 
-        let server_handle = tokio::spawn(async move {
-            server.run().await.unwrap();
-        });
-        if dev_mode {
+        let server_handle = tokio::spawn(async move { server.run().await });
+        if dev_mode && config.read_only {
+            warn!("not starting the coordinator of dev mode, the server is in read-only mode");
+        }
+        if dev_mode && !config.read_only {
             let coordinator = CoordinatorServer::new(Arc::new(config.clone()))
                 .await
-                .expect("failed to create coordinator server");
-            let coordinator_handle = tokio::spawn(async move {
-                coordinator.run().await.unwrap();
-            });
-            tokio::try_join!(server_handle, coordinator_handle)
-                .expect("failed to run server or coordinator server");
+                .map_err(|e| {
+                    CliError::connection(format!("failed to create coordinator server: {}", e))
+                })?;
+            let coordinator_handle = tokio::spawn(async move { coordinator.run().await });
+            let (server_result, coordinator_result) =
+                tokio::try_join!(server_handle, coordinator_handle).map_err(CliError::failed)?;
+            server_result.map_err(|e| CliError::failed(format!("failed to run server: {}", e)))?;
+            coordinator_result.map_err(|e| {
+                CliError::failed(format!("failed to run coordinator server: {}", e))
+            })?;
         } else {
-            server_handle.await.expect("failed to run server");
+            server_handle
+                .await
+                .map_err(CliError::failed)?
+                .map_err(|e| CliError::failed(format!("failed to run server: {}", e)))?;
         }
+        Ok(None)
     }
 }
@@ -0,0 +1,235 @@
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::{error, info, warn};
+
+use crate::{
+    connectors::ConnectorScheduler,
+    coordinator::Coordinator,
+    persistence::{AuditAction, AuditEvent, Repository, DEFAULT_NAMESPACE},
+    server_config::{
+        ConfigReloadConfig,
+        ConnectorConfig,
+        LoggingConfig,
+        SchedulerConfig,
+        ServerConfig,
+    },
+    telemetry,
+};
+
+/// The principal of the audit log entries of the changes made by editing the
+/// config file.
+const CONFIG_FILE_PRINCIPAL: &str = "config_file";
+
+/// The sections of the config which are applied without a restart.
+#[derive(Debug, Clone)]
+struct ReloadableConfig {
+    scheduler: SchedulerConfig,
+    connectors: ConnectorConfig,
+    logging: LoggingConfig,
+}
+
+impl From<&ServerConfig> for ReloadableConfig {
+    fn from(config: &ServerConfig) -> Self {
+        Self {
+            scheduler: config.scheduler.clone(),
+            connectors: config.connectors.clone(),
+            logging: config.logging.clone(),
+        }
+    }
+}
+
+/// A setting of the config whose value changed, named
+/// `<section>.<setting>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingChange {
+    pub setting: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+/// The settings which differ between two versions of a section of the
+/// config.
+fn changed_settings<T: Serialize>(section: &str, from: &T, to: &T) -> Vec<SettingChange> {
+    let from = serde_json::to_value(from).unwrap_or_default();
+    let to = serde_json::to_value(to).unwrap_or_default();
+    let (Value::Object(from), Value::Object(to)) = (from, to) else {
+        return vec![];
+    };
+    let settings: BTreeSet<&String> = from.keys().chain(to.keys()).collect();
+    settings
+        .into_iter()
+        .filter_map(|setting| {
+            let from = from.get(setting).cloned().unwrap_or_default();
+            let to = to.get(setting).cloned().unwrap_or_default();
+            (from != to).then(|| SettingChange {
+                setting: format!("{}.{}", section, setting),
+                from,
+                to,
+            })
+        })
+        .collect()
+}
+
+/// Re-reads the config file on an interval and applies the changes of the
+/// scheduler limits, the connector schedules and the log levels to the
+/// components of the process, without a restart. Every changed setting is
+/// recorded in the audit log of the default namespace.
+///
+/// The server and the coordinator each watch the sections they use, and the
+/// log levels are changed by whichever notices the change first.
+pub struct ConfigWatcher {
+    path: Option<String>,
+    reload: ConfigReloadConfig,
+    current: ReloadableConfig,
+    repository: Arc<Repository>,
+    coordinator: Option<Arc<Coordinator>>,
+    connector_scheduler: Option<Arc<ConnectorScheduler>>,
+}
+
+impl ConfigWatcher {
+    pub fn new(config: &ServerConfig, repository: Arc<Repository>) -> Self {
+        Self {
+            path: config.path.clone(),
+            reload: config.config_reload.clone(),
+            current: ReloadableConfig::from(config),
+            repository,
+            coordinator: None,
+            connector_scheduler: None,
+        }
+    }
+
+    pub fn with_coordinator(mut self, coordinator: Arc<Coordinator>) -> Self {
+        self.coordinator = Some(coordinator);
+        self
+    }
+
+    pub fn with_connector_scheduler(
+        mut self,
+        connector_scheduler: Arc<ConnectorScheduler>,
+    ) -> Self {
+        self.connector_scheduler = Some(connector_scheduler);
+        self
+    }
+
+    /// Applies the log levels of the config the process started with, and
+    /// watches the config file for changes if it was read from one.
+    pub async fn start(mut self) {
+        if let Err(err) = telemetry::set_log_level(&self.current.logging.level) {
+            error!("unable to set the log level: {}", err.to_string());
+        }
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        if !self.reload.enabled {
+            return;
+        }
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.reload.interval_secs.max(1)));
+        // The first tick completes immediately, and the config was just read.
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.reload(&path).await {
+                warn!(
+                    "unable to reload config {}, keeping the current settings: {}",
+                    path,
+                    err.to_string()
+                );
+            }
+        }
+    }
+
+    /// Reads the config file and applies the settings which changed since
+    /// the last read.
+    #[tracing::instrument(skip(self))]
+    async fn reload(&mut self, path: &str) -> Result<()> {
+        let config = ReloadableConfig::from(&ServerConfig::from_path(path)?);
+        let mut changes = Vec::new();
+        if let Some(coordinator) = &self.coordinator {
+            let scheduler =
+                changed_settings("scheduler", &self.current.scheduler, &config.scheduler);
+            if !scheduler.is_empty() {
+                coordinator.reconfigure_scheduler(config.scheduler.clone());
+                changes.extend(scheduler);
+            }
+        }
+        if let Some(connector_scheduler) = &self.connector_scheduler {
+            let connectors =
+                changed_settings("connectors", &self.current.connectors, &config.connectors);
+            if !connectors.is_empty() {
+                connector_scheduler.reconfigure(config.connectors.clone());
+                changes.extend(connectors);
+            }
+        }
+        let logging = changed_settings("logging", &self.current.logging, &config.logging);
+        if !logging.is_empty() {
+            match telemetry::set_log_level(&config.logging.level) {
+                Ok(true) => changes.extend(logging),
+                Ok(false) => {}
+                Err(err) => warn!("keeping the current log level: {}", err.to_string()),
+            }
+        }
+        self.current = config;
+        for change in &changes {
+            info!(
+                "config setting {} changed from {} to {}",
+                change.setting, change.from, change.to
+            );
+            let event = AuditEvent::new(
+                CONFIG_FILE_PRINCIPAL,
+                AuditAction::ConfigChange,
+                None,
+                "RELOAD",
+                &change.setting,
+                200,
+            );
+            if let Err(err) = self
+                .repository
+                .append_audit_event(DEFAULT_NAMESPACE, &event)
+                .await
+            {
+                error!("unable to append to the audit log: {}", err.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_changed_settings() {
+        let from = SchedulerConfig::default();
+        assert!(changed_settings("scheduler", &from, &from).is_empty());
+
+        let to = SchedulerConfig {
+            max_work_per_executor: from.max_work_per_executor + 1,
+            source_concurrency_limits: HashMap::from([("archive".to_string(), 2)]),
+            ..from.clone()
+        };
+        assert_eq!(
+            changed_settings("scheduler", &from, &to),
+            vec![
+                SettingChange {
+                    setting: "scheduler.max_work_per_executor".into(),
+                    from: json!(from.max_work_per_executor),
+                    to: json!(from.max_work_per_executor + 1),
+                },
+                SettingChange {
+                    setting: "scheduler.source_concurrency_limits".into(),
+                    from: json!({}),
+                    to: json!({"archive": 2}),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use rdkafka::{
+    consumer::{CommitMode, Consumer, StreamConsumer},
+    ClientConfig,
+    Message,
+    Offset,
+    TopicPartitionList,
+};
+use serde_json::Value;
+use tracing::{error, warn};
+
+use crate::{
+    data_repository_manager::DataRepositoryManager,
+    persistence::{ContentPayload, KafkaSourceConfig, Provenance},
+};
+
+const INITIAL_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Fields starting with `/` are treated as JSON pointers into nested objects.
+fn lookup<'a>(value: &'a Value, field: &str) -> Option<&'a Value> {
+    if field.starts_with('/') {
+        return value.pointer(field);
+    }
+    value.get(field)
+}
+
+/// Maps a JSON kafka message to the content added to the repository.
+pub fn content_from_message(
+    repository: &str,
+    config: &KafkaSourceConfig,
+    payload: &[u8],
+) -> Result<ContentPayload> {
+    let message: Value =
+        serde_json::from_slice(payload).map_err(|e| anyhow!("message is not valid json: {}", e))?;
+    let text = lookup(&message, &config.text_field)
+        .and_then(|v| v.as_str())
+        .ok_or(anyhow!("message has no text field `{}`", config.text_field))?;
+    let metadata = match lookup(&message, &config.metadata_field) {
+        Some(Value::Object(metadata)) => metadata.clone().into_iter().collect(),
+        Some(Value::Null) | None => HashMap::new(),
+        Some(_) => {
+            return Err(anyhow!(
+                "metadata field `{}` is not an object",
+                config.metadata_field
+            ))
+        }
+    };
+    Ok(ContentPayload::from_text(repository, text, metadata).with_source("kafka"))
+}
+
+/// Consumes a kafka topic into a repository. Offsets are committed only once
+/// the content of the message has been added to the repository, so every
+/// message is ingested at least once. With the hash id strategy, the default,
+/// content ids are derived from the text, which makes re-delivered messages
+/// idempotent. The other strategies give a re-delivered message a new id.
+pub struct KafkaConnector {
+    config: KafkaSourceConfig,
+    consumer: StreamConsumer,
+}
+
+impl KafkaConnector {
+    pub fn new(config: KafkaSourceConfig) -> Result<Self> {
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("group.id", &config.group_id)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .map_err(|e| anyhow!("unable to create kafka consumer: {}", e))?;
+        consumer
+            .subscribe(&[&config.topic])
+            .map_err(|e| anyhow!("unable to subscribe to topic {}: {}", config.topic, e))?;
+        Ok(Self { config, consumer })
+    }
+
+    /// Content carries the id of the connector, and the topic, partition and
+    /// offset of its message as its external id.
+    pub async fn run(
+        self,
+        namespace: &str,
+        repository: &str,
+        connector_id: &str,
+        repository_manager: Arc<DataRepositoryManager>,
+    ) -> Result<()> {
+        loop {
+            let message = self
+                .consumer
+                .recv()
+                .await
+                .map_err(|e| anyhow!("unable to consume topic {}: {}", self.config.topic, e))?
+                .detach();
+            let content = message
+                .payload()
+                .ok_or(anyhow!("message has no payload"))
+                .and_then(|payload| content_from_message(repository, &self.config, payload))
+                .map(|content| {
+                    content.with_provenance(Provenance {
+                        source_connector: Some(connector_id.into()),
+                        external_id: Some(format!(
+                            "{}/{}/{}",
+                            message.topic(),
+                            message.partition(),
+                            message.offset()
+                        )),
+                        source_url: None,
+                        fetched_at: SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .ok()
+                            .map(|d| d.as_secs()),
+                    })
+                });
+            match content {
+                Ok(content) => {
+                    let mut backoff = INITIAL_RETRY_BACKOFF;
+                    while let Err(err) = repository_manager
+                        .add_texts(namespace, repository, vec![content.clone()])
+                        .await
+                    {
+                        error!(
+                            "unable to add content from topic {}, retrying: {}",
+                            self.config.topic,
+                            err.to_string()
+                        );
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+                    }
+                }
+                // Malformed messages would never succeed, so they are skipped
+                // instead of blocking the partition.
+                Err(err) => warn!(
+                    "skipping message at offset {} of topic {}: {}",
+                    message.offset(),
+                    self.config.topic,
+                    err
+                ),
+            }
+            let mut offsets = TopicPartitionList::new();
+            offsets
+                .add_partition_offset(
+                    message.topic(),
+                    message.partition(),
+                    Offset::Offset(message.offset() + 1),
+                )
+                .and_then(|_| self.consumer.commit(&offsets, CommitMode::Async))
+                .map_err(|e| anyhow!("unable to commit offset: {}", e))?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_content_from_message() {
+        let config = KafkaSourceConfig::new("localhost:9092", "events");
+        let content = content_from_message(
+            "default",
+            &config,
+            br#"{"text": "hello world", "metadata": {"source": "app"}}"#,
+        )
+        .unwrap();
+        assert_eq!(content.payload, "hello world");
+        assert_eq!(content.metadata.get("source"), Some(&json!("app")));
+
+        let mut config = config;
+        config.text_field = "/body/content".into();
+        let content =
+            content_from_message("default", &config, br#"{"body": {"content": "nested"}}"#)
+                .unwrap();
+        assert_eq!(content.payload, "nested");
+        assert!(content.metadata.is_empty());
+
+        assert!(content_from_message("default", &config, b"not json").is_err());
+    }
+}
@@ -0,0 +1,357 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use tokio::{sync::Mutex, task::JoinHandle};
+use tracing::{error, info};
+
+use crate::{
+    data_repository_manager::DataRepositoryManager,
+    persistence::{
+        ConnectorState,
+        DataConnector,
+        KafkaSourceConfig,
+        Repository,
+        SourceType,
+        SyncSchedule,
+    },
+    server_config::ConnectorConfig,
+};
+
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod web_crawler;
+
+#[cfg(feature = "kafka")]
+use kafka::KafkaConnector;
+use web_crawler::WebCrawler;
+
+/// A sync which has not finished within the lease is assumed to have died
+/// with its server, and the connector may be synced again.
+const CONNECTOR_SYNC_LEASE: Duration = Duration::from_secs(60 * 60);
+
+/// Runs the data connectors of all the repositories in the background and adds
+/// the content they produce to the repositories.
+///
+/// Batch connectors such as the web crawler are synced on their schedule. The
+/// checkpoint and the run history of every sync are persisted, and a lease on
+/// the connector prevents overlapping syncs, also across servers. The poll
+/// interval and the schedules of the config can be replaced while it runs.
+pub struct ConnectorScheduler {
+    repository: Arc<Repository>,
+    repository_manager: Arc<DataRepositoryManager>,
+    config: RwLock<ConnectorConfig>,
+    #[cfg_attr(not(feature = "kafka"), allow(dead_code))]
+    kafka_consumers: Mutex<HashMap<String, JoinHandle<()>>>,
+}
+
+impl ConnectorScheduler {
+    pub fn new(
+        repository: Arc<Repository>,
+        repository_manager: Arc<DataRepositoryManager>,
+        config: ConnectorConfig,
+    ) -> Self {
+        Self {
+            repository,
+            repository_manager,
+            config: RwLock::new(config),
+            kafka_consumers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn reconfigure(&self, config: ConnectorConfig) {
+        *self.config.write().unwrap() = config;
+    }
+
+    pub async fn start(self: Arc<Self>) {
+        loop {
+            if let Err(err) = self.run_pending().await {
+                error!("unable to run data connectors: {}", err.to_string());
+            }
+            let poll_interval = self.config.read().unwrap().poll_interval_secs;
+            tokio::time::sleep(Duration::from_secs(poll_interval.max(1))).await;
+        }
+    }
+
+    /// The schedule of the config which replaces the schedules of the batch
+    /// connectors of the repository.
+    fn schedule_override(&self, namespace: &str, repository: &str) -> Option<SyncSchedule> {
+        self.config
+            .read()
+            .unwrap()
+            .schedules
+            .get(&format!("{}/{}", namespace, repository))
+            .map(|expression| SyncSchedule::Cron {
+                expression: expression.clone(),
+            })
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn run_pending(self: &Arc<Self>) -> Result<()> {
+        for repository in self.repository.repositories_in_all_namespaces().await? {
+            for connector in repository.data_connectors {
+                let result = match &connector.source {
+                    SourceType::WebCrawler(_) => {
+                        self.schedule_sync(
+                            &repository.namespace,
+                            &repository.name,
+                            connector.clone(),
+                        )
+                        .await
+                    }
+                    SourceType::Kafka(config) => {
+                        self.ensure_kafka_consumer(
+                            &repository.namespace,
+                            &repository.name,
+                            &connector,
+                            config.clone(),
+                        )
+                        .await
+                    }
+                    _ => Ok(()),
+                };
+                if let Err(err) = result {
+                    error!(
+                        "unable to run data connector for repository {} in namespace {}: {}",
+                        &repository.name,
+                        &repository.namespace,
+                        err.to_string()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts a sync of the connector in the background if it is due and no
+    /// other sync of the connector is running.
+    async fn schedule_sync(
+        self: &Arc<Self>,
+        namespace: &str,
+        repository: &str,
+        connector: DataConnector,
+    ) -> Result<()> {
+        let connector_id = connector.id(namespace, repository)?;
+        let mut connector = connector;
+        if let Some(schedule) = self.schedule_override(namespace, repository) {
+            connector.schedule = Some(schedule);
+        }
+        let state = self.repository.connector_state(&connector_id).await?;
+        if !is_sync_due(&connector, state.as_ref(), unix_now())? {
+            return Ok(());
+        }
+        let Some((state, run)) = self
+            .repository
+            .start_connector_sync(
+                &connector_id,
+                namespace,
+                repository,
+                CONNECTOR_SYNC_LEASE.as_secs(),
+            )
+            .await?
+        else {
+            info!(
+                "skipping sync of connector {}, a previous sync is still running",
+                &connector_id
+            );
+            return Ok(());
+        };
+        let scheduler = self.clone();
+        let namespace = namespace.to_string();
+        let repository = repository.to_string();
+        tokio::spawn(async move {
+            let (run, checkpoint) = match scheduler
+                .sync(&namespace, &repository, connector, state.checkpoint)
+                .await
+            {
+                Ok((items_synced, checkpoint)) => (run.succeeded(items_synced), Some(checkpoint)),
+                Err(err) => {
+                    error!(
+                        "sync of connector {} for repository {} failed: {}",
+                        &run.connector_id,
+                        &repository,
+                        err.to_string()
+                    );
+                    (run.failed(&err.to_string()), None)
+                }
+            };
+            if let Err(err) = scheduler
+                .repository
+                .finish_connector_sync(&run, checkpoint)
+                .await
+            {
+                error!(
+                    "unable to record sync of connector {}: {}",
+                    &run.connector_id,
+                    err.to_string()
+                );
+            }
+        });
+        Ok(())
+    }
+
+    /// Syncs the connector from its checkpoint, and returns the number of
+    /// items added to the repository along with the new checkpoint.
+    async fn sync(
+        &self,
+        namespace: &str,
+        repository: &str,
+        connector: DataConnector,
+        checkpoint: Option<serde_json::Value>,
+    ) -> Result<(u64, serde_json::Value)> {
+        let connector_id = connector.id(namespace, repository)?;
+        match connector.source {
+            SourceType::WebCrawler(config) => {
+                let mut crawler = WebCrawler::new(config).with_checkpoint(checkpoint);
+                let mut content = crawler.crawl(repository).await?;
+                for content in &mut content {
+                    content.provenance.source_connector = Some(connector_id.clone());
+                }
+                info!(
+                    "crawler found {} new or changed pages for repository {}",
+                    content.len(),
+                    repository
+                );
+                let items_synced = content.len() as u64;
+                if !content.is_empty() {
+                    self.repository_manager
+                        .add_texts(namespace, repository, content)
+                        .await?;
+                }
+                Ok((items_synced, crawler.checkpoint()))
+            }
+            _ => Err(anyhow!("data connector does not support scheduled syncs")),
+        }
+    }
+
+    /// Kafka consumers run until they fail, and are restarted on the next poll
+    /// after that.
+    #[cfg(feature = "kafka")]
+    async fn ensure_kafka_consumer(
+        &self,
+        namespace: &str,
+        repository: &str,
+        connector: &DataConnector,
+        config: KafkaSourceConfig,
+    ) -> Result<()> {
+        let connector_id = connector.id(namespace, repository)?;
+        let key = format!(
+            "{}/{}/{}",
+            namespace,
+            repository,
+            serde_json::to_string(&config)?
+        );
+        let mut consumers = self.kafka_consumers.lock().await;
+        if let Some(handle) = consumers.get(&key) {
+            if !handle.is_finished() {
+                return Ok(());
+            }
+        }
+        info!(
+            "starting kafka consumer for topic {} into repository {}",
+            &config.topic, repository
+        );
+        let connector = KafkaConnector::new(config)?;
+        let repository_manager = self.repository_manager.clone();
+        let namespace = namespace.to_string();
+        let repository = repository.to_string();
+        let handle = tokio::spawn(async move {
+            if let Err(err) = connector
+                .run(&namespace, &repository, &connector_id, repository_manager)
+                .await
+            {
+                error!(
+                    "kafka consumer for repository {} stopped: {}",
+                    repository,
+                    err.to_string()
+                );
+            }
+        });
+        consumers.insert(key, handle);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "kafka"))]
+    async fn ensure_kafka_consumer(
+        &self,
+        _namespace: &str,
+        _repository: &str,
+        _connector: &DataConnector,
+        _config: KafkaSourceConfig,
+    ) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "indexify was built without the `kafka` feature"
+        ))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Connectors which have never synced successfully are always due. Web
+/// crawlers without a schedule fall back to their recrawl interval, and are
+/// synced only once when that is not set either.
+fn is_sync_due(
+    connector: &DataConnector,
+    state: Option<&ConnectorState>,
+    now: u64,
+) -> Result<bool> {
+    let Some(last_synced_at) = state.and_then(|s| s.last_synced_at) else {
+        return Ok(true);
+    };
+    let schedule = match (&connector.schedule, &connector.source) {
+        (Some(schedule), _) => schedule.clone(),
+        (None, SourceType::WebCrawler(config)) => match config.recrawl_interval_secs {
+            Some(secs) => SyncSchedule::Interval { secs },
+            None => return Ok(false),
+        },
+        (None, _) => return Ok(false),
+    };
+    Ok(schedule.next_sync_after(last_synced_at)? <= now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::WebCrawlerConfig;
+
+    fn synced_at(last_synced_at: u64) -> ConnectorState {
+        ConnectorState {
+            connector_id: "connector".into(),
+            namespace: "default".into(),
+            repository: "test".into(),
+            checkpoint: None,
+            last_synced_at: Some(last_synced_at),
+            sync_started_at: None,
+        }
+    }
+
+    #[test]
+    fn test_is_sync_due() {
+        let mut config = WebCrawlerConfig::new(vec!["https://example.com".into()]);
+        let connector = DataConnector::new(SourceType::WebCrawler(config.clone()));
+        assert!(is_sync_due(&connector, None, 1000).unwrap());
+        assert!(!is_sync_due(&connector, Some(&synced_at(0)), 1000).unwrap());
+
+        config.recrawl_interval_secs = Some(600);
+        let connector = DataConnector::new(SourceType::WebCrawler(config));
+        assert!(!is_sync_due(&connector, Some(&synced_at(1000)), 1500).unwrap());
+        assert!(is_sync_due(&connector, Some(&synced_at(1000)), 1600).unwrap());
+
+        let connector = DataConnector {
+            schedule: Some(SyncSchedule::Cron {
+                expression: "0 0 * * * *".into(),
+            }),
+            ..connector
+        };
+        assert!(!is_sync_due(&connector, Some(&synced_at(3600)), 7199).unwrap());
+        assert!(is_sync_due(&connector, Some(&synced_at(3600)), 7200).unwrap());
+    }
+}
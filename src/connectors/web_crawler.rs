@@ -0,0 +1,413 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use url::Url;
+
+use crate::persistence::{ContentPayload, Provenance, WebCrawlerConfig};
+
+const USER_AGENT: &str = "indexify";
+
+const PAGE_WIDTH: usize = 120;
+
+/// Pages larger than this are not crawled.
+const MAX_PAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// The source of the content added by the crawler.
+pub const WEB_CRAWLER_CONTENT_SOURCE: &str = "web_crawler";
+
+/// The rules of a robots.txt file which apply to the crawler.
+#[derive(Debug, Default)]
+pub struct RobotsTxt {
+    allow: Vec<String>,
+    disallow: Vec<String>,
+}
+
+impl RobotsTxt {
+    pub fn parse(robots_txt: &str) -> Self {
+        let mut robots = Self::default();
+        let mut in_matching_group = false;
+        let mut last_line_was_agent = false;
+        for line in robots_txt.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let (key, value) = (key.trim().to_lowercase(), value.trim());
+            match key.as_str() {
+                "user-agent" => {
+                    let matches = value == "*" || value.eq_ignore_ascii_case(USER_AGENT);
+                    // consecutive user-agent lines share the rules that follow them
+                    in_matching_group = (last_line_was_agent && in_matching_group) || matches;
+                    last_line_was_agent = true;
+                    continue;
+                }
+                "allow" if in_matching_group && !value.is_empty() => {
+                    robots.allow.push(value.to_string())
+                }
+                "disallow" if in_matching_group && !value.is_empty() => {
+                    robots.disallow.push(value.to_string())
+                }
+                _ => {}
+            }
+            last_line_was_agent = false;
+        }
+        robots
+    }
+
+    /// The most specific matching rule wins, and allow rules win ties.
+    pub fn is_allowed(&self, path: &str) -> bool {
+        let longest_match = |rules: &[String]| {
+            rules
+                .iter()
+                .filter(|rule| path.starts_with(rule.as_str()))
+                .map(|rule| rule.len())
+                .max()
+        };
+        match (longest_match(&self.allow), longest_match(&self.disallow)) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(allow), Some(disallow)) => allow >= disallow,
+        }
+    }
+}
+
+/// Returns the absolute http(s) urls of the links in the html page.
+pub fn extract_links(base: &Url, html: &str) -> Vec<Url> {
+    let re = Regex::new(r#"(?i)<a\s[^>]*href\s*=\s*["']([^"']+)["']"#).unwrap();
+    re.captures_iter(html)
+        .filter_map(|c| base.join(c[1].trim()).ok())
+        .filter(|url| url.scheme() == "http" || url.scheme() == "https")
+        .map(|mut url| {
+            url.set_fragment(None);
+            url
+        })
+        .collect()
+}
+
+fn is_allowed_domain(allowed_domains: &[String], url: &Url) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    allowed_domains
+        .iter()
+        .any(|domain| host == domain || host.ends_with(&format!(".{}", domain)))
+}
+
+/// The hashes are kept in the checkpoint, so they must not change between
+/// releases, which `DefaultHasher` does not promise.
+fn hash_text(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
+
+/// Reads the body of a response, up to `MAX_PAGE_BYTES`.
+async fn read_body(url: &Url, mut resp: reqwest::Response) -> Result<String> {
+    let too_large = || anyhow!("{} is larger than {} bytes", url, MAX_PAGE_BYTES);
+    if resp.content_length().unwrap_or(0) > MAX_PAGE_BYTES as u64 {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| anyhow!("unable to read {}: {}", url, e))?
+    {
+        if body.len() + chunk.len() > MAX_PAGE_BYTES {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// A page fetched by the crawler, as text, with the links of html pages.
+pub struct Page {
+    pub text: String,
+    pub links: Vec<Url>,
+}
+
+/// Crawls the web starting from a set of seed urls, and remembers the pages it
+/// has seen so that re-crawls only produce new or changed pages. The pages
+/// seen are persisted as the checkpoint of the connector.
+pub struct WebCrawler {
+    config: WebCrawlerConfig,
+    client: reqwest::Client,
+    page_hashes: HashMap<String, String>,
+}
+
+impl WebCrawler {
+    pub fn new(config: WebCrawlerConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(Duration::from_secs(30))
+            .build()
+            .unwrap_or_default();
+        Self {
+            config,
+            client,
+            page_hashes: HashMap::new(),
+        }
+    }
+
+    /// Resumes from the checkpoint of a previous crawl. Checkpoints which can
+    /// not be read are ignored, and every page is treated as new.
+    pub fn with_checkpoint(mut self, checkpoint: Option<serde_json::Value>) -> Self {
+        if let Some(checkpoint) = checkpoint {
+            match serde_json::from_value(checkpoint) {
+                Ok(page_hashes) => self.page_hashes = page_hashes,
+                Err(err) => warn!("ignoring unreadable crawler checkpoint: {}", err),
+            }
+        }
+        self
+    }
+
+    pub fn checkpoint(&self) -> serde_json::Value {
+        json!(self.page_hashes)
+    }
+
+    fn allowed_domains(&self) -> Vec<String> {
+        if !self.config.allowed_domains.is_empty() {
+            return self.config.allowed_domains.clone();
+        }
+        self.config
+            .seed_urls
+            .iter()
+            .filter_map(|url| Url::parse(url).ok())
+            .filter_map(|url| url.host_str().map(|h| h.to_string()))
+            .collect()
+    }
+
+    async fn is_allowed_by_robots(
+        &self,
+        url: &Url,
+        robots: &mut HashMap<String, RobotsTxt>,
+    ) -> bool {
+        let origin = url.origin().ascii_serialization();
+        if !robots.contains_key(&origin) {
+            let robots_txt = match self
+                .client
+                .get(format!("{}/robots.txt", origin))
+                .send()
+                .await
+            {
+                Ok(resp) if resp.status().is_success() => resp.text().await.unwrap_or_default(),
+                _ => String::new(),
+            };
+            robots.insert(origin.clone(), RobotsTxt::parse(&robots_txt));
+        }
+        robots[&origin].is_allowed(url.path())
+    }
+
+    /// Fetches a page as text. Pages which redirect outside the allowed
+    /// domains are not fetched.
+    pub async fn fetch(&self, url: &Url) -> Result<Page> {
+        let resp = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| anyhow!("unable to fetch {}: {}", url, e))?;
+        if !resp.status().is_success() {
+            return Err(anyhow!(
+                "unable to fetch {}, status: {}",
+                url,
+                resp.status()
+            ));
+        }
+        let final_url = resp.url().clone();
+        if !is_allowed_domain(&self.allowed_domains(), &final_url) {
+            return Err(anyhow!(
+                "{} redirected to {}, outside the allowed domains",
+                url,
+                final_url
+            ));
+        }
+        let is_html = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.contains("text/html"))
+            .unwrap_or(true);
+        let body = read_body(url, resp).await?;
+        if !is_html {
+            return Ok(Page {
+                text: body,
+                links: vec![],
+            });
+        }
+        Ok(Page {
+            text: html2text::from_read(body.as_bytes(), PAGE_WIDTH),
+            links: extract_links(&final_url, &body),
+        })
+    }
+
+    /// Crawls the configured seeds and returns the content of the pages which
+    /// are new or have changed since the last crawl.
+    #[tracing::instrument(skip(self))]
+    pub async fn crawl(&mut self, repository: &str) -> Result<Vec<ContentPayload>> {
+        let seeds: Vec<Url> = self
+            .config
+            .seed_urls
+            .iter()
+            .filter_map(|url| match Url::parse(url) {
+                Ok(url) => Some(url),
+                Err(err) => {
+                    warn!("skipping invalid seed url {}: {}", url, err);
+                    None
+                }
+            })
+            .collect();
+        let allowed_domains = self.allowed_domains();
+        let mut queue: VecDeque<(Url, u32)> = seeds.into_iter().map(|url| (url, 0)).collect();
+        let mut visited = HashSet::new();
+        let mut robots = HashMap::new();
+        let mut content = Vec::new();
+        while let Some((url, depth)) = queue.pop_front() {
+            if visited.len() >= self.config.max_pages {
+                break;
+            }
+            if !is_allowed_domain(&allowed_domains, &url) || !visited.insert(url.to_string()) {
+                continue;
+            }
+            if self.config.respect_robots_txt && !self.is_allowed_by_robots(&url, &mut robots).await
+            {
+                continue;
+            }
+            let Page { text, links } = match self.fetch(&url).await {
+                Ok(page) => page,
+                Err(err) => {
+                    warn!("{}", err);
+                    continue;
+                }
+            };
+            if depth < self.config.max_depth {
+                queue.extend(links.into_iter().map(|link| (link, depth + 1)));
+            }
+
+            let hash = hash_text(&text);
+            if self.page_hashes.insert(url.to_string(), hash.clone()) == Some(hash) {
+                continue;
+            }
+            let crawled_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let metadata = HashMap::from([
+                ("url".to_string(), json!(url.to_string())),
+                ("crawled_at".to_string(), json!(crawled_at)),
+                ("crawl_depth".to_string(), json!(depth)),
+            ]);
+            content.push(
+                ContentPayload::from_text(repository, &text, metadata)
+                    .with_source(WEB_CRAWLER_CONTENT_SOURCE)
+                    .with_provenance(Provenance {
+                        source_url: Some(url.to_string()),
+                        fetched_at: Some(crawled_at),
+                        ..Provenance::default()
+                    }),
+            );
+        }
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use axum::{response::Redirect, routing::get, Router};
+
+    use super::*;
+
+    /// Starts a site with a page, a page larger than `MAX_PAGE_BYTES` and a
+    /// page redirecting to another host, and returns its address.
+    fn start_site() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let app = Router::new()
+            .route("/page", get(|| async { "hello" }))
+            .route("/large", get(|| async { "a".repeat(MAX_PAGE_BYTES + 1) }))
+            .route(
+                "/moved",
+                get(move || async move {
+                    Redirect::temporary(&format!("http://localhost:{}/page", port))
+                }),
+            );
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+        format!("http://127.0.0.1:{}", port)
+    }
+
+    #[tokio::test]
+    async fn test_fetch() {
+        let site = start_site();
+        let crawler = WebCrawler::new(WebCrawlerConfig::new(vec![site.clone()]));
+        let url = |path: &str| Url::parse(&format!("{}{}", site, path)).unwrap();
+        assert_eq!(crawler.fetch(&url("/page")).await.unwrap().text, "hello");
+        let error = |page: Result<Page>| page.err().unwrap().to_string();
+        assert!(error(crawler.fetch(&url("/large")).await).contains("is larger than"));
+        assert!(error(crawler.fetch(&url("/moved")).await).contains("outside the allowed domains"));
+    }
+
+    #[test]
+    fn test_robots_txt() {
+        let robots = RobotsTxt::parse(
+            "User-agent: googlebot\nDisallow: /\n\nUser-agent: *\nDisallow: /private\nAllow: /private/public # comment\n",
+        );
+        assert!(robots.is_allowed("/"));
+        assert!(!robots.is_allowed("/private/data"));
+        assert!(robots.is_allowed("/private/public/page"));
+        assert!(RobotsTxt::parse("").is_allowed("/anything"));
+    }
+
+    #[test]
+    fn test_extract_links() {
+        let base = Url::parse("https://example.com/docs/").unwrap();
+        let links = extract_links(
+            &base,
+            r#"<a href="intro.html#top">Intro</a> <A class="x" HREF='/about'>About</A> <a href="mailto:a@b.com">Mail</a>"#,
+        );
+        let links: Vec<String> = links.into_iter().map(|u| u.to_string()).collect();
+        assert_eq!(
+            links,
+            vec![
+                "https://example.com/docs/intro.html",
+                "https://example.com/about"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_allowed_domains() {
+        let domains = vec!["example.com".to_string()];
+        assert!(is_allowed_domain(
+            &domains,
+            &Url::parse("https://docs.example.com/").unwrap()
+        ));
+        assert!(!is_allowed_domain(
+            &domains,
+            &Url::parse("https://notexample.com/").unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let mut crawler = WebCrawler::new(WebCrawlerConfig::new(vec![]));
+        crawler
+            .page_hashes
+            .insert("https://example.com/".to_string(), hash_text("hello"));
+        let restored = WebCrawler::new(WebCrawlerConfig::new(vec![]))
+            .with_checkpoint(Some(crawler.checkpoint()));
+        assert_eq!(restored.page_hashes, crawler.page_hashes);
+
+        let restored = WebCrawler::new(WebCrawlerConfig::new(vec![]))
+            .with_checkpoint(Some(json!("not a checkpoint")));
+        assert!(restored.page_hashes.is_empty());
+    }
+}
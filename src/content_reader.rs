@@ -1,4 +1,14 @@
-use crate::{blob_storage::BlobStorageBuilder, internal_api::ContentPayload};
+use std::{io, ops::Range};
+
+use base64::prelude::*;
+use bytes::Bytes;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{
+    blob_storage::BlobStorageBuilder,
+    internal_api::{ContentEncoding, ContentPayload},
+};
 
 pub struct ContentReader {
     payload: ContentPayload,
@@ -14,6 +24,111 @@ impl ContentReader {
             let blob_storage_reader = BlobStorageBuilder::reader_from_link(external_url)?;
             return blob_storage_reader.get(external_url).await;
         }
-        Ok(self.payload.content.clone().into_bytes())
+        self.read_inline()
+    }
+
+    fn read_inline(&self) -> Result<Vec<u8>, anyhow::Error> {
+        match self.payload.encoding {
+            ContentEncoding::Utf8 => Ok(self.payload.content.clone().into_bytes()),
+            ContentEncoding::Base64 => Ok(BASE64_STANDARD.decode(&self.payload.content)?),
+        }
+    }
+
+    /// The size of the content, in bytes.
+    pub async fn size(&self) -> Result<u64, anyhow::Error> {
+        if let Some(external_url) = &self.payload.external_url {
+            let blob_storage_reader = BlobStorageBuilder::reader_from_link(external_url)?;
+            return blob_storage_reader.size(external_url).await;
+        }
+        Ok(self.read_inline()?.len() as u64)
+    }
+
+    /// The bytes of the content in the range. Blobs are read from the blob
+    /// storage as the stream is consumed, content kept in the database is
+    /// sent at once.
+    pub async fn stream(
+        &self,
+        range: Range<u64>,
+    ) -> Result<ReceiverStream<io::Result<Bytes>>, anyhow::Error> {
+        if let Some(external_url) = &self.payload.external_url {
+            let blob_storage_reader = BlobStorageBuilder::reader_from_link(external_url)?;
+            return blob_storage_reader.stream(external_url, range).await;
+        }
+        let data = Bytes::from(self.read_inline()?);
+        let end = (range.end as usize).min(data.len());
+        let start = (range.start as usize).min(end);
+        let (tx, rx) = mpsc::channel(1);
+        tx.try_send(Ok(data.slice(start..end)))?;
+        Ok(ReceiverStream::new(rx))
+    }
+}
+
+/// A `Range` header which no bytes of a content satisfy.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RangeNotSatisfiable;
+
+/// The bytes of a content of `size` bytes requested by the value of an HTTP
+/// `Range` header. A header which is not a single valid range of bytes is
+/// ignored, and the whole content is returned for it. Returns an error when
+/// the range starts past the end of the content.
+pub fn byte_range(header: &str, size: u64) -> Result<Option<Range<u64>>, RangeNotSatisfiable> {
+    let Some(range) = header.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
+    if range.contains(',') {
+        return Ok(None);
+    }
+    let Some((start, end)) = range.split_once('-') else {
+        return Ok(None);
+    };
+    let (start, end) = (start.trim(), end.trim());
+    // The last bytes of the content.
+    if start.is_empty() {
+        let Ok(suffix) = end.parse::<u64>() else {
+            return Ok(None);
+        };
+        if suffix == 0 || size == 0 {
+            return Err(RangeNotSatisfiable);
+        }
+        return Ok(Some(size.saturating_sub(suffix)..size));
+    }
+    let Ok(start) = start.parse::<u64>() else {
+        return Ok(None);
+    };
+    let end = match end {
+        "" => size,
+        end => match end.parse::<u64>() {
+            Ok(end) if end >= start => end.saturating_add(1).min(size),
+            _ => return Ok(None),
+        },
+    };
+    if start >= size {
+        return Err(RangeNotSatisfiable);
+    }
+    Ok(Some(start..end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_range() {
+        assert_eq!(byte_range("bytes=0-99", 1000), Ok(Some(0..100)));
+        assert_eq!(byte_range("bytes=900-", 1000), Ok(Some(900..1000)));
+        assert_eq!(byte_range("bytes=-100", 1000), Ok(Some(900..1000)));
+        assert_eq!(byte_range("bytes=-2000", 1000), Ok(Some(0..1000)));
+        // The end is capped to the content.
+        assert_eq!(byte_range("bytes=990-1999", 1000), Ok(Some(990..1000)));
+
+        assert_eq!(byte_range("bytes=1000-", 1000), Err(RangeNotSatisfiable));
+        assert_eq!(byte_range("bytes=-0", 1000), Err(RangeNotSatisfiable));
+        assert_eq!(byte_range("bytes=0-", 0), Err(RangeNotSatisfiable));
+
+        // Ranges which are not understood are ignored.
+        assert_eq!(byte_range("bytes=0-1,5-9", 1000), Ok(None));
+        assert_eq!(byte_range("bytes=9-5", 1000), Ok(None));
+        assert_eq!(byte_range("items=0-9", 1000), Ok(None));
+        assert_eq!(byte_range("bytes=a-b", 1000), Ok(None));
     }
 }
@@ -1,26 +1,58 @@
 use std::{
-    collections::HashMap,
-    sync::{Arc, RwLock},
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+        RwLock,
+    },
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::Result;
-use tokio::sync::mpsc::{self, Receiver, Sender};
-use tracing::{error, info};
+use tokio::sync::{
+    mpsc::{self, Receiver, Sender},
+    Notify,
+};
+use tracing::{error, info, info_span, warn, Instrument};
 
 use crate::{
     attribute_index::AttributeIndexManager,
-    extractor::ExtractedEmbeddings,
-    internal_api::{self, CreateWork, ExecutorInfo},
+    builtin_extractors::{self, BuiltinExtractors, BUILTIN_EXECUTOR_ID},
+    chunking::ChunkOrigin,
+    entity::work,
+    extractor::{ExtractedEmbeddings, ExtractedMultiVectors},
+    graph_index::GraphIndexManager,
+    internal_api::{self, CreateWork, ExecutorInfo, ExtractorLoad},
     persistence::{
+        ContentPayload,
         ExtractedAttributes,
+        ExtractionEvent,
         ExtractionEventPayload,
+        ExtractionEventsNotification,
         ExtractorBinding,
+        ExtractorWorkStats,
         Repository,
+        RepositoryError,
         Work,
+        WorkReport,
+        WorkState,
     },
+    server_config::SchedulerConfig,
+    sinks::{SinkManager, SinkRecord},
+    summary_index::SummaryIndexManager,
+    telemetry,
     vector_index::VectorIndexManager,
+    vectordbs::VectorDbError,
+    work_scheduler::{BindingKey, BindingLimit, FairWorkScheduler},
 };
 
+/// The work finished in this window is averaged into the work duration of the
+/// autoscaling metrics.
+const WORK_DURATION_WINDOW_SECS: u64 = 300;
+
+/// The window the `requests_per_minute` of extractor bindings is counted over.
+const BINDING_RATE_WINDOW_SECS: u64 = 60;
+
 #[derive(Debug)]
 pub struct Coordinator {
     // Executor ID -> Last Seen Timestamp
@@ -37,6 +69,21 @@ pub struct Coordinator {
 
     attribute_index_manager: Arc<AttributeIndexManager>,
 
+    graph_index_manager: GraphIndexManager,
+
+    summary_index_manager: SummaryIndexManager,
+
+    sink_manager: Arc<SinkManager>,
+
+    work_scheduler: FairWorkScheduler,
+
+    // Wakes up the built-in extractors when work was assigned.
+    builtin_work: Notify,
+
+    // Whether this coordinator processes the events and distributes the work,
+    // see `LeaderElection`.
+    leader: AtomicBool,
+
     tx: Sender<CreateWork>,
 }
 
@@ -45,6 +92,7 @@ impl Coordinator {
         repository: Arc<Repository>,
         vector_index_manager: Arc<VectorIndexManager>,
         attribute_index_manager: Arc<AttributeIndexManager>,
+        scheduler_config: SchedulerConfig,
     ) -> Arc<Self> {
         let (tx, rx) = mpsc::channel(32);
 
@@ -52,23 +100,95 @@ impl Coordinator {
             executor_health_checks: Arc::new(RwLock::new(HashMap::new())),
             executors: Arc::new(RwLock::new(HashMap::new())),
             extractors_table: Arc::new(RwLock::new(HashMap::new())),
+            graph_index_manager: GraphIndexManager::new(repository.clone()),
+            summary_index_manager: SummaryIndexManager::new(repository.clone()),
             repository,
             vector_index_manager,
             attribute_index_manager,
+            sink_manager: Arc::new(SinkManager::new()),
+            work_scheduler: FairWorkScheduler::new(scheduler_config),
+            builtin_work: Notify::new(),
+            leader: AtomicBool::new(true),
             tx,
         });
         let coordinator_clone = coordinator.clone();
         tokio::spawn(async move {
             coordinator_clone.loop_for_work(rx).await.unwrap();
         });
+        let coordinator_clone = coordinator.clone();
+        tokio::spawn(async move {
+            coordinator_clone.listen_for_extraction_events().await;
+        });
         coordinator
     }
 
+    /// Replaces the config of the scheduler, which applies from the next
+    /// distribution of work.
+    pub fn reconfigure_scheduler(&self, config: SchedulerConfig) {
+        self.work_scheduler.reconfigure(config);
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.leader.load(Ordering::SeqCst)
+    }
+
+    /// Makes the coordinator the leader, which processes the events and
+    /// distributes the work, or a standby which leaves them to the leader.
+    pub fn set_leader(&self, leader: bool) {
+        self.leader.store(leader, Ordering::SeqCst);
+    }
+
     pub async fn get_executors(&self) -> Result<Vec<ExecutorInfo>> {
         let executors = self.executors.read().unwrap();
         Ok(executors.values().cloned().collect())
     }
 
+    /// The load of every extractor which has executors or work.
+    #[tracing::instrument(skip(self))]
+    pub async fn autoscaling_metrics(&self) -> Result<Vec<ExtractorLoad>> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let mut stats: HashMap<String, ExtractorWorkStats> = self
+            .repository
+            .extractor_work_stats(now.saturating_sub(WORK_DURATION_WINDOW_SECS))
+            .await?
+            .into_iter()
+            .map(|s| (s.extractor.clone(), s))
+            .collect();
+        let mut metrics = Vec::new();
+        {
+            let extractors_table = self.extractors_table.read().unwrap();
+            for (extractor, executors) in extractors_table.iter() {
+                metrics.push(extractor_load(
+                    extractor,
+                    stats.remove(extractor).as_ref(),
+                    executors.len() as u64,
+                    now,
+                ));
+            }
+        }
+        for (extractor, stats) in stats {
+            metrics.push(extractor_load(&extractor, Some(&stats), 0, now));
+        }
+        metrics.sort_by(|a, b| a.extractor.cmp(&b.extractor));
+        Ok(metrics)
+    }
+
+    /// The load of an extractor, all zeros when it has neither executors nor
+    /// work.
+    #[tracing::instrument(skip(self))]
+    pub async fn extractor_load(&self, extractor: &str) -> Result<ExtractorLoad> {
+        let load = self
+            .autoscaling_metrics()
+            .await?
+            .into_iter()
+            .find(|l| l.extractor == extractor)
+            .unwrap_or_else(|| extractor_load(extractor, None, 0, 0));
+        Ok(load)
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn record_executor(&self, worker: ExecutorInfo) -> Result<(), anyhow::Error> {
         // First see if the executor is already in the table
@@ -101,43 +221,61 @@ impl Coordinator {
     pub async fn process_extraction_events(&self) -> Result<(), anyhow::Error> {
         let events = self.repository.unprocessed_extraction_events().await?;
         for event in &events {
-            info!("processing extraction event: {}", event.id);
-            match &event.payload {
-                ExtractionEventPayload::ExtractorBindingAdded { repository, id } => {
-                    let binding = self.repository.binding_by_id(repository, id).await?;
-                    self.generate_work_for_extractor_bindings(repository, &binding)
-                        .await?;
-                }
-                ExtractionEventPayload::CreateContent { content_id } => {
-                    if let Err(err) = self
-                        .create_work(&event.repository_id, Some(content_id))
-                        .await
-                    {
-                        error!("unable to create work: {}", &err.to_string());
-                        return Err(err);
-                    }
-                }
-            };
-
-            self.repository
-                .mark_extraction_event_as_processed(&event.id)
+            // The event continues the trace of the request which created it.
+            let span = info_span!("extraction_event", id = %event.id);
+            telemetry::set_parent(&span, &event.trace_context);
+            self.process_extraction_event(event)
+                .instrument(span)
                 .await?;
         }
         Ok(())
     }
 
+    async fn process_extraction_event(&self, event: &ExtractionEvent) -> Result<(), anyhow::Error> {
+        info!("processing extraction event: {}", event.id);
+        match &event.payload {
+            ExtractionEventPayload::ExtractorBindingAdded { repository, id } => {
+                let binding = self
+                    .repository
+                    .binding_by_id(&event.namespace, repository, id)
+                    .await?;
+                self.generate_work_for_extractor_bindings(&event.namespace, repository, &binding)
+                    .await?;
+            }
+            ExtractionEventPayload::CreateContent { content_id } => {
+                if let Err(err) = self
+                    .create_work(&event.namespace, &event.repository_id, Some(content_id))
+                    .await
+                {
+                    error!("unable to create work: {}", &err.to_string());
+                    return Err(err);
+                }
+            }
+        };
+
+        self.repository
+            .mark_extraction_event_as_processed(&event.id)
+            .await?;
+        Ok(())
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn generate_work_for_extractor_bindings(
         &self,
+        namespace: &str,
         repository: &str,
         extractor_binding: &ExtractorBinding,
     ) -> Result<(), anyhow::Error> {
+        if extractor_binding.disabled {
+            return Ok(());
+        }
         let content_list = self
             .repository
-            .content_with_unapplied_extractor(repository, extractor_binding, None)
+            .content_with_unapplied_extractor(namespace, repository, extractor_binding, None)
             .await?;
         for content in content_list {
-            self.create_work(repository, Some(&content.id)).await?;
+            self.create_work(namespace, repository, Some(&content.id))
+                .await?;
         }
         Ok(())
     }
@@ -145,41 +283,137 @@ impl Coordinator {
     #[tracing::instrument(skip(self))]
     pub async fn distribute_work(&self) -> Result<(), anyhow::Error> {
         let unallocated_work = self.repository.unallocated_work().await?;
+        let mut work_in_flight = self.repository.work_in_flight().await?;
+        let binding_limits = self.binding_limits(&unallocated_work).await?;
 
         // work_id -> executor_id
-        let mut work_assignment = HashMap::new();
-        for work in unallocated_work {
+        let (work_moves, work_assignment) = {
             let extractor_table = self.extractors_table.read().unwrap();
-            let executors = extractor_table.get(&work.extractor).ok_or(anyhow::anyhow!(
-                "no executors for extractor: {}",
-                work.extractor
-            ))?;
-            let rand_index = rand::random::<usize>() % executors.len();
-            if !executors.is_empty() {
-                let executor_id = executors[rand_index].clone();
-                work_assignment.insert(work.id.clone(), executor_id);
+            let last_synced = self.executor_health_checks.read().unwrap();
+            let work_moves =
+                self.work_scheduler
+                    .rebalance(&extractor_table, &work_in_flight, &last_synced);
+            for work in work_in_flight.iter_mut() {
+                if let Some(executor_id) = work_moves.get(&work.id) {
+                    work.worker_id = Some(executor_id.clone());
+                }
             }
+            let work_assignment = self.work_scheduler.allocate(
+                &extractor_table,
+                unallocated_work,
+                &work_in_flight,
+                &binding_limits,
+            );
+            (work_moves, work_assignment)
+        };
+        if !work_moves.is_empty() {
+            info!("moving work to idle executors: {:}", work_moves.len());
         }
         info!("finishing work assignment: {:}", work_assignment.len());
+        let assigned_builtin_work = work_assignment
+            .values()
+            .chain(work_moves.values())
+            .any(|executor_id| executor_id == BUILTIN_EXECUTOR_ID);
+        self.repository.reassign_work(work_moves).await?;
         self.repository.assign_work(work_assignment).await?;
+        if assigned_builtin_work {
+            self.builtin_work.notify_one();
+        }
         Ok(())
     }
 
+    /// The limits of the extractor bindings of the waiting work which are
+    /// paused or have limits, with the work already started in the current
+    /// minute taken off their `requests_per_minute`.
+    async fn binding_limits(
+        &self,
+        waiting: &[work::Model],
+    ) -> Result<HashMap<BindingKey, BindingLimit>> {
+        let repositories: HashSet<(&str, &str)> = waiting
+            .iter()
+            .map(|w| (w.namespace.as_str(), w.repository_id.as_str()))
+            .collect();
+        let mut limits = HashMap::new();
+        for (namespace, repository) in repositories {
+            // The repository may have been deleted since its work was created.
+            let Ok(data_repository) = self
+                .repository
+                .repository_by_name(namespace, repository)
+                .await
+            else {
+                continue;
+            };
+            for binding in data_repository.extractor_bindings {
+                let key = BindingKey {
+                    namespace: namespace.to_string(),
+                    repository: repository.to_string(),
+                    binding: binding.name,
+                };
+                if binding.disabled ||
+                    binding.max_concurrency.is_some() ||
+                    binding.requests_per_minute.is_some()
+                {
+                    limits.insert(
+                        key,
+                        BindingLimit {
+                            paused: binding.disabled,
+                            max_concurrency: binding.max_concurrency,
+                            remaining_requests: binding.requests_per_minute,
+                        },
+                    );
+                }
+            }
+        }
+        if limits.values().any(|l| l.remaining_requests.is_some()) {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            for assignments in self
+                .repository
+                .binding_assignments(now.saturating_sub(BINDING_RATE_WINDOW_SECS))
+                .await?
+            {
+                let key = BindingKey {
+                    namespace: assignments.namespace,
+                    repository: assignments.repository_id,
+                    binding: assignments.extractor_binding,
+                };
+                if let Some(remaining) = limits
+                    .get_mut(&key)
+                    .and_then(|l| l.remaining_requests.as_mut())
+                {
+                    *remaining = remaining.saturating_sub(assignments.assigned as usize);
+                }
+            }
+        }
+        Ok(limits)
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn create_work(
         &self,
+        namespace: &str,
         repository_id: &str,
         content_id: Option<&str>,
     ) -> Result<(), anyhow::Error> {
         let extractor_bindings = self
             .repository
-            .repository_by_name(repository_id)
+            .repository_by_name(namespace, repository_id)
             .await?
             .extractor_bindings;
-        for extractor_binding in &extractor_bindings {
+        // Content stays unprocessed by paused bindings, so that they create
+        // its work when they are resumed.
+        for extractor_binding in extractor_bindings.iter().filter(|b| !b.disabled) {
+            let mut processed = Vec::new();
             let content_list = self
                 .repository
-                .content_with_unapplied_extractor(repository_id, extractor_binding, content_id)
+                .content_with_unapplied_extractor(
+                    namespace,
+                    repository_id,
+                    extractor_binding,
+                    content_id,
+                )
                 .await?;
             for content in content_list {
                 info!(
@@ -191,17 +425,21 @@ impl Coordinator {
                 );
                 let work = Work::new(
                     &content.id,
+                    namespace,
                     repository_id,
                     &extractor_binding.extractor,
                     &extractor_binding.name,
                     &extractor_binding.input_params,
                     None,
-                );
+                )
+                .with_source(&content.source)
+                .with_trace_context(telemetry::current_context());
                 self.repository.insert_work(&work).await?;
-                self.repository
-                    .mark_content_as_processed(&work.content_id, &extractor_binding.name)
-                    .await?;
+                processed.push(work.content_id);
             }
+            self.repository
+                .mark_content_as_processed_batch(namespace, &processed, &extractor_binding.name)
+                .await?;
         }
 
         Ok(())
@@ -228,9 +466,20 @@ impl Coordinator {
         for work in work_list {
             let content_payload = self
                 .repository
-                .content_from_repo(&work.content_id, &work.repository_id)
+                .content_from_repo(&work.namespace, &work.content_id, &work.repository_id)
                 .await?;
-            let internal_api_work = internal_api::create_work(work, content_payload)?;
+            // Work of a binding which was removed is extracted without a
+            // chunker.
+            let binding = self
+                .repository
+                .binding_by_id(
+                    &work.namespace,
+                    &work.repository_id,
+                    &work.extractor_binding,
+                )
+                .await
+                .ok();
+            let internal_api_work = internal_api::create_work(work, content_payload, binding)?;
             result.push(internal_api_work);
         }
 
@@ -245,12 +494,68 @@ impl Coordinator {
                 info!("no work to process");
                 return Ok(());
             }
+            if !self.is_leader() {
+                continue;
+            }
             if let Err(err) = self.process_and_distribute_work().await {
                 error!("unable to process and distribute work: {}", err.to_string());
             }
         }
     }
 
+    /// Wakes up the work loop whenever the database notifies that extraction
+    /// events were added, and on every poll interval in case a notification
+    /// was lost, like while the listener reconnects.
+    #[tracing::instrument(skip(self))]
+    async fn listen_for_extraction_events(&self) {
+        let mut listener = match self.repository.extraction_events_listener().await {
+            Ok(listener) => Some(listener),
+            Err(err) => {
+                warn!(
+                    "unable to listen for extraction events, polling instead: {}",
+                    err.to_string()
+                );
+                None
+            }
+        };
+        loop {
+            // The poll interval may be changed by a reload of the config.
+            let poll_interval = self.work_scheduler.extraction_poll_interval();
+            let notification = match listener.as_mut() {
+                Some(listener) => {
+                    match tokio::time::timeout(poll_interval, listener.recv()).await {
+                        Ok(Ok(notification)) => {
+                            serde_json::from_str::<ExtractionEventsNotification>(
+                                notification.payload(),
+                            )
+                            .ok()
+                        }
+                        Ok(Err(err)) => {
+                            // The listener reconnects on the next receive.
+                            error!("extraction events listener failed: {}", err.to_string());
+                            tokio::time::sleep(poll_interval).await;
+                            None
+                        }
+                        Err(_) => None,
+                    }
+                }
+                None => {
+                    tokio::time::sleep(poll_interval).await;
+                    None
+                }
+            };
+            let work = notification.map_or_else(CreateWork::default, |n| CreateWork {
+                namespace: n.namespace,
+                repository_name: n.repository,
+                content: None,
+            });
+            if self.publish_work(work).await.is_err() {
+                info!("work loop stopped, no longer listening for extraction events");
+                return;
+            }
+        }
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn process_and_distribute_work(&self) -> Result<(), anyhow::Error> {
         info!("received work request, processing extraction events");
@@ -281,47 +586,404 @@ impl Coordinator {
         Ok(())
     }
 
+    /// Records the built-in extractors as run by an executor at `addr`, the
+    /// address of the coordinator, and does the work assigned to them as it
+    /// is assigned.
+    pub async fn run_builtin_extractors(
+        self: Arc<Self>,
+        builtin_extractors: Arc<BuiltinExtractors>,
+        addr: String,
+    ) -> Result<()> {
+        let extractors = builtin_extractors::descriptions();
+        for extractor in &extractors {
+            self.record_extractor(extractor.clone()).await?;
+        }
+        self.record_executor(ExecutorInfo {
+            id: BUILTIN_EXECUTOR_ID.into(),
+            last_seen: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            addr,
+            extractor: extractors[0].clone(),
+        })
+        .await?;
+        {
+            // The executor of the built-in extractors runs all of them.
+            let mut extractors_table = self.extractors_table.write().unwrap();
+            for extractor in &extractors[1..] {
+                extractors_table
+                    .entry(extractor.name.clone())
+                    .or_default()
+                    .push(BUILTIN_EXECUTOR_ID.into());
+            }
+        }
+        info!("running built-in extractors");
+        loop {
+            // Work assigned while the coordinator was down is picked up on the
+            // poll interval.
+            tokio::select! {
+                _ = self.builtin_work.notified() => {},
+                _ = tokio::time::sleep(self.work_scheduler.extraction_poll_interval()) => {},
+            }
+            if let Err(err) = self.do_builtin_work(&builtin_extractors).await {
+                error!(
+                    "unable to do work of built-in extractors: {}",
+                    err.to_string()
+                );
+            }
+        }
+    }
+
+    async fn do_builtin_work(&self, builtin_extractors: &BuiltinExtractors) -> Result<()> {
+        // The work assigned to the built-in extractors is done by the leader.
+        if !self.is_leader() {
+            return Ok(());
+        }
+        let work_list = self.get_work_for_worker(BUILTIN_EXECUTOR_ID).await?;
+        if work_list.is_empty() {
+            return Ok(());
+        }
+        let mut work_status_list = Vec::new();
+        for work in work_list {
+            // The work continues the trace of the content it extracts from.
+            let span = info_span!("work", id = %work.id);
+            telemetry::set_parent(&span, &work.trace_context);
+            let work_status = builtin_extractors.extract_work(work).instrument(span).await;
+            if let Some(err) = &work_status.error {
+                error!("unable to perform work {}: {}", &work_status.work_id, err);
+            }
+            work_status_list.push(work_status);
+        }
+        self.write_extracted_data(work_status_list).await
+    }
+
+    /// Leaves out the statuses the executor reported for work which was moved
+    /// to another executor in the meantime, as the other executor reports it
+    /// instead.
+    #[tracing::instrument(skip(self, work_status_list))]
+    pub async fn work_status_of_executor(
+        &self,
+        executor_id: &str,
+        work_status_list: Vec<internal_api::WorkStatus>,
+    ) -> Result<Vec<internal_api::WorkStatus>> {
+        let moved = self
+            .repository
+            .work_moved_from(
+                executor_id,
+                work_status_list
+                    .iter()
+                    .map(|status| status.work_id.clone())
+                    .collect(),
+            )
+            .await?;
+        if !moved.is_empty() {
+            info!(
+                "discarding {} work reported by executor {} which was moved to another executor",
+                moved.len(),
+                executor_id
+            );
+        }
+        Ok(work_status_list
+            .into_iter()
+            .filter(|status| !moved.contains(&status.work_id))
+            .collect())
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn write_extracted_data(
         &self,
         work_status_list: Vec<internal_api::WorkStatus>,
     ) -> Result<()> {
+        let has_finished_work = !work_status_list.is_empty();
+        // The states of work reported without anything else are written
+        // together once the output of the work is written.
+        let mut states = Vec::new();
         for work_status in work_status_list {
-            let work = self
-                .repository
-                .update_work_state(&work_status.work_id, &work_status.status.into())
-                .await?;
-            for extracted_content in work_status.extracted_content {
-                if let Some(feature) = extracted_content.feature.clone() {
-                    let index_name = format!("{}-{}", work.extractor_binding, feature.name);
-                    if let Some(text) = extracted_content.source_as_text() {
-                        if let Some(embedding) = feature.embedding() {
-                            let embeddings = ExtractedEmbeddings {
-                                content_id: work.content_id.clone(),
-                                text: text.clone(),
-                                embeddings: embedding.clone(),
-                            };
-                            self.vector_index_manager
-                                .add_embedding(&work.repository_id, &index_name, vec![embeddings])
-                                .await?;
-                        }
-                    }
-                    if let Some(metadata) = feature.metadata() {
-                        let extracted_attributes = ExtractedAttributes::new(
-                            &work.content_id,
-                            metadata.clone(),
-                            &work.extractor,
-                        );
-                        self.attribute_index_manager
-                            .add_index(&work.repository_id, &index_name, extracted_attributes)
+            let report = WorkReport::from(&work_status);
+            let state: WorkState = work_status.status.into();
+            if state != WorkState::Failed {
+                let work = self.repository.work_by_id(&work_status.work_id).await?;
+                // The writes continue the trace of the content the work extracts from.
+                let span = info_span!("write_work_output", work_id = %work.id);
+                telemetry::set_parent(&span, &work.trace_context);
+                let written = self
+                    .write_work_output(
+                        &work,
+                        work_status.extracted_content,
+                        work_status.chunk_origins,
+                    )
+                    .instrument(span)
+                    .await;
+                if let Err(err) = written {
+                    self.repository.update_work_states(states).await?;
+                    // Work is left as it is while the vector store is
+                    // unavailable, so that it is reported again once the
+                    // store is back instead of completing without its output.
+                    if VectorDbError::unavailable_for(&err).is_none() {
+                        self.repository
+                            .update_work_status(&work_status.work_id, &state, &report)
                             .await?;
                     }
+                    return Err(err);
                 }
             }
+            if report == WorkReport::default() {
+                states.push((work_status.work_id, state));
+            } else {
+                self.repository
+                    .update_work_status(&work_status.work_id, &state, &report)
+                    .await?;
+            }
         }
+        self.repository.update_work_states(states).await?;
 
+        // Work waiting for a free slot can be assigned now.
+        if has_finished_work {
+            if let Err(err) = self.distribute_work().await {
+                error!("unable to distribute work: {}", err.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    async fn write_work_output(
+        &self,
+        work: &Work,
+        extracted_content: Vec<internal_api::Content>,
+        chunk_origins: Vec<ChunkOrigin>,
+    ) -> Result<()> {
+        for (i, content) in extracted_content.iter().enumerate() {
+            if let Some(feature) = content.feature.clone() {
+                let index_name = self
+                    .repository
+                    .output_index(
+                        &work.namespace,
+                        &work.repository_id,
+                        &work.extractor_binding,
+                        &feature.name,
+                    )
+                    .await?;
+                // Embeddings of images are stored without a text, and are not
+                // cached as the cache is keyed by the text embedded.
+                let text = content.source_as_text();
+                if let Some(embedding) = feature.embedding() {
+                    let embeddings = ExtractedEmbeddings {
+                        content_id: work.content_id.clone(),
+                        text: text.clone().unwrap_or_default(),
+                        embeddings: embedding.clone(),
+                    };
+                    if text.is_some() {
+                        self.vector_index_manager
+                            .cache_embeddings(
+                                &work.extractor,
+                                Some(&work.extractor_params),
+                                std::slice::from_ref(&embeddings),
+                            )
+                            .await;
+                    }
+                    self.vector_index_manager
+                        .add_embedding(
+                            &work.namespace,
+                            &work.repository_id,
+                            &index_name,
+                            vec![embeddings],
+                            chunk_origins.get(i).cloned().unwrap_or_default(),
+                        )
+                        .await?;
+                } else if let Some(vectors) = feature.multi_vector() {
+                    self.vector_index_manager
+                        .add_multi_vectors(
+                            &work.namespace,
+                            &work.repository_id,
+                            &index_name,
+                            vec![ExtractedMultiVectors {
+                                content_id: work.content_id.clone(),
+                                text: text.unwrap_or_default(),
+                                vectors,
+                            }],
+                            chunk_origins.get(i).cloned().unwrap_or_default(),
+                        )
+                        .await?;
+                }
+                if let Some(metadata) = feature.metadata() {
+                    let extracted_attributes = ExtractedAttributes::new(
+                        &work.content_id,
+                        metadata.clone(),
+                        &work.extractor,
+                    );
+                    let binding = self
+                        .repository
+                        .binding_by_id(
+                            &work.namespace,
+                            &work.repository_id,
+                            &work.extractor_binding,
+                        )
+                        .await?;
+                    self.attribute_index_manager
+                        .add_index(
+                            &work.namespace,
+                            &work.repository_id,
+                            &index_name,
+                            extracted_attributes,
+                            binding.invalid_attributes,
+                        )
+                        .await?;
+                }
+                if let Some(graph) = feature.graph() {
+                    self.graph_index_manager
+                        .add_graph(
+                            &work.namespace,
+                            &work.repository_id,
+                            &index_name,
+                            &work.content_id,
+                            &graph,
+                        )
+                        .await?;
+                }
+                if let Some(summary) = feature.summary() {
+                    self.summary_index_manager
+                        .add_summary(
+                            &work.namespace,
+                            &work.repository_id,
+                            &index_name,
+                            &work.content_id,
+                            &work.extractor,
+                            &summary,
+                        )
+                        .await?;
+                }
+            }
+        }
+        self.write_derived_content(work, &extracted_content).await?;
+        self.deliver_to_sinks(work, extracted_content).await;
         Ok(())
     }
+
+    /// Adds the extracted content which has no feature, such as a summary or
+    /// the text of a scanned page, as content of the repository derived from
+    /// the content of the work. The bindings of the repository extract it like
+    /// added content, apart from the bindings which produced it or its
+    /// ancestors.
+    async fn write_derived_content(
+        &self,
+        work: &Work,
+        extracted_content: &[internal_api::Content],
+    ) -> Result<()> {
+        let derived: Vec<(usize, &internal_api::Content)> = extracted_content
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.feature.is_none())
+            .collect();
+        if derived.is_empty() {
+            return Ok(());
+        }
+        let parent = match self
+            .repository
+            .content_from_repo(&work.namespace, &work.content_id, &work.repository_id)
+            .await
+        {
+            Ok(parent) => parent,
+            // Content deleted while it was extracted has no derived content.
+            Err(RepositoryError::ContentNotFound(_)) => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut content = Vec::new();
+        for (position, c) in derived {
+            let Ok(content_type) = c.content_type.parse() else {
+                warn!(
+                    "dropping content of work {} with invalid content type {}",
+                    &work.id, &c.content_type
+                );
+                continue;
+            };
+            content.push(ContentPayload::derived_from(
+                &work.repository_id,
+                &parent,
+                &work.extractor_binding,
+                position,
+                content_type,
+                &c.source,
+                &c.metadata,
+            ));
+        }
+        let mut lineage: Vec<String> = self
+            .repository
+            .content_lineage(&work.namespace, &work.repository_id, &work.content_id)
+            .await?
+            .into_iter()
+            .filter(|n| n.depth <= 0)
+            .filter_map(|n| n.produced_by_binding)
+            .collect();
+        lineage.push(work.extractor_binding.clone());
+        self.repository
+            .add_derived_content(&work.namespace, &work.repository_id, content, &lineage)
+            .await
+    }
+
+    #[tracing::instrument(skip(self, extracted_content))]
+    async fn deliver_to_sinks(&self, work: &Work, extracted_content: Vec<internal_api::Content>) {
+        let binding = match self
+            .repository
+            .binding_by_id(
+                &work.namespace,
+                &work.repository_id,
+                &work.extractor_binding,
+            )
+            .await
+        {
+            Ok(binding) => binding,
+            Err(err) => {
+                error!(
+                    "unable to look up binding {} for sink delivery: {}",
+                    &work.extractor_binding,
+                    err.to_string()
+                );
+                return;
+            }
+        };
+        if binding.sinks.is_empty() {
+            return;
+        }
+        let record = SinkRecord {
+            namespace: work.namespace.clone(),
+            repository: work.repository_id.clone(),
+            extractor_binding: work.extractor_binding.clone(),
+            content_id: work.content_id.clone(),
+            work_id: work.id.clone(),
+            content: extracted_content,
+        };
+        // Sinks are delivered to in the background, so that a slow or
+        // failing sink does not hold the work from being finished.
+        let sink_manager = self.sink_manager.clone();
+        tokio::spawn(async move {
+            sink_manager.deliver(&binding.sinks, &record).await;
+        });
+    }
+}
+
+fn extractor_load(
+    extractor: &str,
+    stats: Option<&ExtractorWorkStats>,
+    executors: u64,
+    now: u64,
+) -> ExtractorLoad {
+    let pending_work = stats.map_or(0, |s| s.pending_work as u64);
+    let in_flight_work = stats.map_or(0, |s| s.in_flight_work as u64);
+    ExtractorLoad {
+        extractor: extractor.into(),
+        pending_work,
+        in_flight_work,
+        executors,
+        work_per_executor: (pending_work + in_flight_work) as f64 / executors.max(1) as f64,
+        average_work_duration_secs: stats
+            .and_then(|s| s.average_work_duration_secs)
+            .unwrap_or_default(),
+        backlog_age_secs: stats
+            .and_then(|s| s.oldest_pending_at)
+            .map_or(0, |created_at| now.saturating_sub(created_at as u64)),
+        window_secs: WORK_DURATION_WINDOW_SECS,
+    }
 }
 
 #[cfg(test)]
@@ -330,10 +992,19 @@ mod tests {
 
     use serde_json::json;
 
+    use super::*;
     use crate::{
         blob_storage::BlobStorageBuilder,
         data_repository_manager::DataRepositoryManager,
-        persistence::{ContentPayload, DataRepository, ExtractorBinding},
+        persistence::{
+            ContentPayload,
+            DataRepository,
+            DedupPolicy,
+            ExtractorBinding,
+            RetentionPolicy,
+            TextAnalysisConfig,
+            DEFAULT_NAMESPACE,
+        },
         test_util::{
             self,
             db_utils::{DEFAULT_TEST_EXTRACTOR, DEFAULT_TEST_REPOSITORY},
@@ -354,9 +1025,13 @@ mod tests {
         // Create a repository
         repository_manager
             .create(&DataRepository {
+                namespace: DEFAULT_NAMESPACE.into(),
                 name: DEFAULT_TEST_REPOSITORY.into(),
                 data_connectors: vec![],
                 metadata: HashMap::new(),
+                dedup_policy: DedupPolicy::default(),
+                retention: RetentionPolicy::default(),
+                text_analysis: TextAnalysisConfig::default(),
                 extractor_bindings: vec![ExtractorBinding::new(
                     "test_extractor_binding",
                     DEFAULT_TEST_REPOSITORY,
@@ -364,11 +1039,13 @@ mod tests {
                     vec![],
                     serde_json::json!({}),
                 )],
+                version: 0,
             })
             .await?;
 
         repository_manager
             .add_texts(
+                DEFAULT_NAMESPACE,
                 DEFAULT_TEST_REPOSITORY,
                 vec![
                     ContentPayload::from_text(
@@ -394,6 +1071,35 @@ mod tests {
 
         // Check amount of work queued for the worker
         assert_eq!(work_list.len(), 2);
+
+        let metrics = coordinator.autoscaling_metrics().await?;
+        let load = metrics
+            .iter()
+            .find(|l| l.extractor == DEFAULT_TEST_EXTRACTOR)
+            .unwrap();
+        assert_eq!(load.pending_work, 0);
+        assert_eq!(load.in_flight_work, 2);
+        assert_eq!(load.executors, 1);
         Ok(())
     }
+
+    #[test]
+    fn test_extractor_load() {
+        let stats = ExtractorWorkStats {
+            extractor: "embedder".into(),
+            pending_work: 6,
+            in_flight_work: 2,
+            oldest_pending_at: Some(940),
+            average_work_duration_secs: Some(1.5),
+        };
+        let load = extractor_load("embedder", Some(&stats), 4, 1000);
+        assert_eq!(load.work_per_executor, 2.0);
+        assert_eq!(load.backlog_age_secs, 60);
+        assert_eq!(load.average_work_duration_secs, 1.5);
+
+        let idle = extractor_load("embedder", None, 0, 1000);
+        assert_eq!(idle.pending_work, 0);
+        assert_eq!(idle.work_per_executor, 0.0);
+        assert_eq!(idle.backlog_age_secs, 0);
+    }
 }
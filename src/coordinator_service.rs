@@ -1,8 +1,10 @@
 use std::{net::SocketAddr, sync::Arc, time::SystemTime};
 
 use axum::{
-    extract::{DefaultBodyLimit, State},
-    http::StatusCode,
+    extract::{DefaultBodyLimit, Path, State},
+    http::{Request, StatusCode},
+    middleware::{self, Next},
+    response::Response,
     routing::{get, post},
     Json,
     Router,
@@ -12,57 +14,129 @@ use axum_tracing_opentelemetry::middleware::OtelAxumLayer;
 use tokio::signal;
 use tracing::{error, info};
 
+#[cfg(feature = "fault-injection")]
+use crate::fault_injection::FaultyVectorDb;
 use crate::{
     api::IndexifyAPIError,
     attribute_index::AttributeIndexManager,
+    builtin_extractors::{BuiltinExtractors, EMBEDDING_EXTRACTOR},
+    config_watcher::ConfigWatcher,
     coordinator::Coordinator,
+    event_consumers::EventConsumers,
     internal_api::{
+        AutoscalingMetrics,
         CoordinateRequest,
         CoordinateResponse,
         CreateWork,
         CreateWorkResponse,
         ExecutorInfo,
+        ExtractRequest,
+        ExtractResponse,
+        ExtractorLoad,
         ListExecutors,
         SyncExecutor,
         SyncWorkerResponse,
     },
+    leader_election::LeaderElection,
     persistence::Repository,
-    server_config::ServerConfig,
+    server_config::{ServerConfig, TlsConfig},
+    tls,
     vector_index::VectorIndexManager,
-    vectordbs,
+    vectordbs::{self, resilience::ResilientVectorDb, VectorDbError},
 };
 
 pub struct CoordinatorServer {
     addr: SocketAddr,
+    tls: Option<TlsConfig>,
     coordinator: Arc<Coordinator>,
+    builtin_extractors: Option<Arc<BuiltinExtractors>>,
 }
 
 impl CoordinatorServer {
     pub async fn new(config: Arc<ServerConfig>) -> Result<Self, anyhow::Error> {
         let addr: SocketAddr = config.coordinator_lis_addr_sock()?;
-        let repository = Arc::new(Repository::new(&config.db_url).await?);
+        let repository = Repository::new(&config.db_url, &config.database).await?;
+        #[cfg(feature = "fault-injection")]
+        let repository = repository.with_fault_injection(config.fault_injection.database.clone());
+        let repository = Arc::new(repository);
         let vector_db = vectordbs::create_vectordb(
             config.index_config.clone(),
             repository.get_db_conn_clone(),
         )?;
-        let vector_index_manager = Arc::new(VectorIndexManager::new(
-            repository.clone(),
-            vector_db,
-            config.coordinator_lis_addr_sock().unwrap().to_string(),
-        ));
+        #[cfg(feature = "fault-injection")]
+        let vector_db =
+            FaultyVectorDb::wrap(vector_db, config.fault_injection.vector_store.clone());
+        let vector_db = ResilientVectorDb::wrap(vector_db, config.index_config.resilience.clone());
+        vectordbs::warmup_vectordb(&vector_db, &config.index_config).await;
+        let vector_index_manager = Arc::new(
+            VectorIndexManager::new(
+                repository.clone(),
+                vector_db,
+                config.coordinator_lis_addr_sock().unwrap().to_string(),
+            )
+            .with_embedding_cache(config.embedding_cache.clone()),
+        );
         let attribute_index_manager = Arc::new(AttributeIndexManager::new(repository.clone()));
 
-        let coordinator =
-            Coordinator::new(repository, vector_index_manager, attribute_index_manager);
+        let coordinator = Coordinator::new(
+            repository.clone(),
+            vector_index_manager,
+            attribute_index_manager,
+            config.scheduler.clone(),
+        );
+        if config.leader_election.enabled {
+            let leader_election = LeaderElection::new(
+                repository.clone(),
+                coordinator.clone(),
+                config.leader_election.clone(),
+            );
+            tokio::spawn(leader_election.start());
+        }
+        let event_consumers = EventConsumers::new(
+            repository.clone(),
+            coordinator.clone(),
+            config.event_consumers.clone(),
+        );
+        tokio::spawn(async move {
+            if let Err(err) = event_consumers.start().await {
+                error!("unable to start event consumers: {}", err.to_string());
+            }
+        });
+        let config_watcher =
+            ConfigWatcher::new(&config, repository).with_coordinator(coordinator.clone());
+        tokio::spawn(config_watcher.start());
+        let builtin_extractors = if config.builtin_extractors.enabled {
+            let builtin_extractors =
+                Arc::new(BuiltinExtractors::new(config.builtin_extractors.clone())?);
+            let coordinator = coordinator.clone();
+            let builtin = builtin_extractors.clone();
+            tokio::spawn(async move {
+                if let Err(err) = coordinator
+                    .run_builtin_extractors(builtin, addr.to_string())
+                    .await
+                {
+                    error!("unable to run built-in extractors: {}", err.to_string());
+                }
+            });
+            Some(builtin_extractors)
+        } else {
+            None
+        };
         info!("coordinator listening on: {}", addr.to_string());
-        Ok(Self { addr, coordinator })
+        Ok(Self {
+            addr,
+            tls: config.tls.internal.clone(),
+            coordinator,
+            builtin_extractors,
+        })
     }
 
     pub async fn run(&self) -> Result<(), anyhow::Error> {
         let metrics = HttpMetricsLayerBuilder::new().build();
-        let app = Router::new()
+        let mut app = Router::new()
             .merge(metrics.routes())
             .route("/", get(root))
+            .route("/readyz", get(readyz).with_state(self.coordinator.clone()))
             .route(
                 "/sync_executor",
                 post(sync_executor).with_state(self.coordinator.clone()),
@@ -79,14 +153,32 @@ impl CoordinatorServer {
                 "/coordinates",
                 post(get_coordinate).with_state(self.coordinator.clone()),
             )
+            .route(
+                "/autoscaling/extractors",
+                get(autoscaling_metrics).with_state(self.coordinator.clone()),
+            )
+            .route(
+                "/autoscaling/extractors/:extractor_name",
+                get(extractor_load).with_state(self.coordinator.clone()),
+            );
+        // The coordinator is the executor of the built-in extractors, which
+        // extract the queries of their indexes.
+        if let Some(builtin_extractors) = &self.builtin_extractors {
+            app = app.route(
+                "/extract",
+                post(extract).with_state(builtin_extractors.clone()),
+            );
+        }
+        let app = app
+            .layer(middleware::from_fn_with_state(
+                self.coordinator.clone(),
+                require_leader,
+            ))
             //start OpenTelemetry trace on incoming request
             .layer(OtelAxumLayer::default())
             .layer(metrics)
             .layer(DefaultBodyLimit::disable());
-        axum::Server::bind(&self.addr)
-            .serve(app.into_make_service())
-            .with_graceful_shutdown(shutdown_signal())
-            .await?;
+        tls::bind_and_serve(self.addr, app, self.tls.as_ref(), shutdown_signal()).await?;
         Ok(())
     }
 
@@ -100,6 +192,34 @@ async fn root() -> &'static str {
     "Indexify Coordinator"
 }
 
+/// Ready while the coordinator is the leader, so that a load balancer sends
+/// the executors and the server to the leader only.
+async fn readyz(State(coordinator): State<Arc<Coordinator>>) -> StatusCode {
+    if coordinator.is_leader() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+/// Rejects the requests other than the health checks and the metrics while
+/// the coordinator stands by, so that executors sync with the leader which
+/// assigns them work.
+async fn require_leader<B>(
+    State(coordinator): State<Arc<Coordinator>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, IndexifyAPIError> {
+    let path = request.uri().path();
+    if coordinator.is_leader() || matches!(path, "/" | "/readyz" | "/metrics") {
+        return Ok(next.run(request).await);
+    }
+    Err(IndexifyAPIError::new(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "the coordinator is standing by, retry with the leader".to_string(),
+    ))
+}
+
 #[tracing::instrument]
 #[axum_macros::debug_handler]
 async fn list_executors(
@@ -112,6 +232,33 @@ async fn list_executors(
     Ok(Json(ListExecutors { executors }))
 }
 
+#[tracing::instrument]
+#[axum_macros::debug_handler]
+async fn autoscaling_metrics(
+    State(coordinator): State<Arc<Coordinator>>,
+) -> Result<Json<AutoscalingMetrics>, IndexifyAPIError> {
+    let extractors = coordinator
+        .autoscaling_metrics()
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(AutoscalingMetrics { extractors }))
+}
+
+/// The load of a single extractor, in the shape the KEDA `metrics-api` scaler
+/// reads with a `valueLocation` like `pending_work`.
+#[tracing::instrument]
+#[axum_macros::debug_handler]
+async fn extractor_load(
+    State(coordinator): State<Arc<Coordinator>>,
+    Path(extractor_name): Path<String>,
+) -> Result<Json<ExtractorLoad>, IndexifyAPIError> {
+    let load = coordinator
+        .extractor_load(&extractor_name)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(load))
+}
+
 #[tracing::instrument(level = "debug", skip(coordinator))]
 #[tracing::instrument(skip(coordinator, executor))]
 #[axum_macros::debug_handler]
@@ -133,11 +280,24 @@ async fn sync_executor(
         })
         .await;
 
-    coordinator
-        .write_extracted_data(executor.work_status)
+    let work_status = coordinator
+        .work_status_of_executor(&worker_id, executor.work_status)
         .await
         .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
+    // The executor keeps the work it reported until it is written, and
+    // reports it again on its next sync.
+    coordinator
+        .write_extracted_data(work_status)
+        .await
+        .map_err(|e| {
+            let status_code = match VectorDbError::unavailable_for(&e) {
+                Some(_) => StatusCode::SERVICE_UNAVAILABLE,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+
     // Record the extractors available on the executor
     coordinator
         .record_extractor(executor.extractor)
@@ -171,6 +331,25 @@ async fn get_coordinate(
     }))
 }
 
+#[tracing::instrument]
+#[axum_macros::debug_handler]
+async fn extract(
+    State(builtin_extractors): State<Arc<BuiltinExtractors>>,
+    Json(request): Json<ExtractRequest>,
+) -> Result<Json<ExtractResponse>, IndexifyAPIError> {
+    let content = builtin_extractors
+        .extract(
+            EMBEDDING_EXTRACTOR,
+            vec![request.content],
+            request.input_params.as_ref(),
+        )
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .pop()
+        .unwrap_or_default();
+    Ok(Json(ExtractResponse { content }))
+}
+
 #[axum_macros::debug_handler]
 async fn create_work(
     State(coordinator): State<Arc<Coordinator>>,
@@ -0,0 +1,79 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use tracing::{error, info};
+
+use crate::{persistence::Repository, server_config::DashboardConfig};
+
+/// Periodically computes the dashboard of every repository and stores it, so
+/// that the dashboard endpoint serves a stored summary instead of aggregating
+/// the content and the work of the repository on every request.
+pub struct DashboardRollup {
+    repository: Arc<Repository>,
+    config: DashboardConfig,
+}
+
+impl DashboardRollup {
+    pub fn new(repository: Arc<Repository>, config: DashboardConfig) -> Self {
+        Self { repository, config }
+    }
+
+    pub async fn start(self: Arc<Self>) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.rollup_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.rollup().await {
+                error!(
+                    "unable to compute the repository dashboards: {}",
+                    err.to_string()
+                );
+            }
+        }
+    }
+
+    /// Computes and stores the dashboards of all the repositories. A
+    /// repository which fails keeps its previous dashboard.
+    #[tracing::instrument(skip(self))]
+    pub async fn rollup(&self) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let since = now.saturating_sub(self.config.window_secs);
+        let repositories = self.repository.repositories_in_all_namespaces().await?;
+        for repository in &repositories {
+            let result = async {
+                let dashboard = self
+                    .repository
+                    .compute_repository_dashboard(
+                        &repository.namespace,
+                        &repository.name,
+                        since,
+                        now,
+                    )
+                    .await?;
+                self.repository
+                    .save_repository_dashboard(&repository.namespace, &repository.name, &dashboard)
+                    .await
+            }
+            .await;
+            if let Err(err) = result {
+                error!(
+                    "unable to compute the dashboard of repository {} in namespace {}: {}",
+                    repository.name,
+                    repository.namespace,
+                    err.to_string()
+                );
+            }
+        }
+        info!(
+            "computed the dashboards of {} repositories",
+            repositories.len()
+        );
+        Ok(())
+    }
+}
@@ -1,34 +1,207 @@
-use std::{collections::HashMap, fmt, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    io,
+    ops::Range,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::{anyhow, Result};
+use base64::prelude::*;
 use bytes::Bytes;
 use jsonschema::JSONSchema;
+use nanoid::nanoid;
 use sea_orm::DbConn;
+use serde_json::json;
 use thiserror::Error;
+use tokio_stream::wrappers::ReceiverStream;
 use tracing::{error, info};
+use url::Url;
 
 pub const DEFAULT_REPOSITORY_NAME: &str = "default";
 
 use crate::{
+    archive::{self, ArchiveFormat},
     attribute_index::AttributeIndexManager,
+    attribute_query::{AttributeAggregation, AttributeSort},
     blob_storage::BlobStorageTS,
+    builtin_extractors,
+    chunking::{self, ChunkOrigin},
+    connectors::web_crawler::WebCrawler,
+    content_reader::{self, ContentReader},
+    export::{ExportFormat, ExportSource, IndexExport},
+    extractor::{ExtractedEmbeddings, ExtractedMultiVectors},
+    extractor_router::ExtractorRouter,
+    filter::{Filter, Scope},
+    graph_index::{GraphIndexManager, PathQuery},
     index::IndexError,
+    internal_api,
+    manifest::MANIFEST_CONTENT_SOURCE,
     persistence::{
+        ApiKey,
+        ApiKeyScope,
+        AttributeGroup,
+        AuditEvent,
+        AuditLogFilter,
+        BindingPreview,
+        ChangeKind,
+        Chunk,
+        ConnectorSyncRun,
+        ContentLineageNode,
         ContentPayload,
         DataRepository,
+        DedupPolicy,
+        DeletedContent,
         Event,
         ExtractedAttributes,
         Extractor,
         ExtractorBinding,
+        ExtractorBindingStats,
         ExtractorOutputSchema,
+        GraphEdge,
+        GraphNode,
         Index,
+        IndexSnapshot,
+        IngestionJob,
+        IngestionRowError,
+        LegalHold,
+        ManifestFormat,
+        PayloadType,
+        Provenance,
+        ProvenanceFilter,
+        QuarantinedAttributes,
         Repository,
+        RepositoryDashboard,
+        RepositoryDeletion,
         RepositoryError,
+        RepositoryRole,
+        RepositoryTemplate,
+        RerankerBackend,
+        RerankerConfig,
+        RetentionPolicy,
+        RoleBinding,
+        TextAnalysisConfig,
+        WebCrawlerConfig,
+        Work,
+        DEFAULT_NAMESPACE,
+        SESSION_ID_METADATA_KEY,
     },
-    server_config::ServerConfig,
-    vector_index::{ScoredText, VectorIndexManager},
+    progress::ContentProgressWatch,
+    rerank,
+    server_config::{ArchiveConfig, InlineExtractionConfig, ManifestConfig, ServerConfig},
+    summary_index::SummaryIndexManager,
+    text_analysis::{TextAnalyzer, TEXT_SEARCH_LANGUAGES},
+    vector_index::{IndexWarmup, ScoredText, SearchQuery, VectorIndexManager},
+    vectordbs::SearchFilter,
 };
 
+/// A predicate over an attributes index, used to restrict a semantic search
+/// to the content whose extracted attributes match.
+#[derive(Debug, Clone)]
+pub struct AttributePrefilter {
+    pub index: String,
+    /// Every content with attributes in the index matches without it.
+    pub filter: Option<Filter>,
+}
+
+/// Diversifies the results of a search across content by maximal marginal
+/// relevance, picking them out of the top `fetch_k` candidates.
+#[derive(Debug, Clone)]
+pub struct Diversification {
+    pub lambda: f32,
+    pub fetch_k: u64,
+}
+
+/// Limits on the results of a search, applied to the candidates before the
+/// top `k` are picked.
+#[derive(Debug, Clone, Default)]
+pub struct ResultLimits {
+    /// Results scored lower are dropped.
+    pub min_score: Option<f32>,
+    /// Results of the same content beyond the best scored ones are dropped.
+    pub max_chunks_per_content: Option<usize>,
+    /// Results are the parent chunks of the chunks which matched, each parent
+    /// returned once.
+    pub parent_chunks: bool,
+}
+
+/// A file which is ingested along with events.
+#[derive(Debug, Clone)]
+pub struct IngestedFile {
+    pub name: String,
+    pub data: Bytes,
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+/// Events and the content related to them, such as a chat turn and the file
+/// attached to it. The metadata and the session are added to the metadata of
+/// every event and content, unless they set the keys themselves.
+#[derive(Debug, Clone, Default)]
+pub struct Ingestion {
+    pub session_id: Option<String>,
+    pub metadata: HashMap<String, serde_json::Value>,
+    pub events: Vec<Event>,
+    pub texts: Vec<ContentPayload>,
+    pub files: Vec<IngestedFile>,
+}
+
+/// A binding run on content while it was added inline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineExtraction {
+    pub content_id: String,
+    pub extractor_binding: String,
+    /// Why the binding did not finish inline, in which case it was queued as
+    /// work for the executors.
+    pub error: Option<String>,
+}
+
+/// A change read from the change feed of a repository along with the object
+/// it changed, to be applied to the same repository in another deployment.
+#[derive(Debug, Clone)]
+pub enum RepositoryChange {
+    /// Content with its bytes in the payload, wherever it is stored.
+    Content(ContentPayload),
+    Chunk {
+        index: String,
+        chunk: Chunk,
+    },
+    Attributes {
+        index: String,
+        attributes: ExtractedAttributes,
+    },
+}
+
+/// Content which was fetched again from its source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefetchedContent {
+    /// The id of the content, which is the id of the content which replaced
+    /// it when it changed.
+    pub content_id: String,
+    pub changed: bool,
+}
+
+/// The bytes of content streamed to a client, see
+/// [`DataRepositoryManager::download_content`].
+pub struct ContentDownload {
+    pub content_type: String,
+    /// The size of the whole content, in bytes.
+    pub size: u64,
+    /// The bytes of the content in the body, all of them without a range.
+    pub range: Option<Range<u64>>,
+    pub body: ReceiverStream<io::Result<Bytes>>,
+}
+
+/// Changes of a repository after a position in its change feed.
+#[derive(Debug, Clone)]
+pub struct ChangeBatch {
+    pub changes: Vec<RepositoryChange>,
+    /// The position of the last entry of the feed which was read, the
+    /// position to read the next batch after.
+    pub cursor: u64,
+    pub has_more: bool,
+}
+
 #[derive(Error, Debug)]
 pub enum DataRepositoryError {
     #[error(transparent)]
@@ -39,13 +212,196 @@ pub enum DataRepositoryError {
 
     #[error(transparent)]
     RetrievalError(#[from] IndexError),
+
+    #[error("unable to read content: `{0}`")]
+    ContentRead(String),
+
+    #[error("range `{0}` is not satisfiable for content of {1} bytes")]
+    RangeNotSatisfiable(String, u64),
+
+    #[error("unable to write content: `{0}`")]
+    ContentWrite(String),
+
+    #[error("unable to delete content: `{0}`")]
+    ContentDelete(String),
+
+    #[error("content `{0}` is under a legal hold")]
+    ContentHeld(String),
+
+    #[error("invalid manifest: `{0}`")]
+    InvalidManifest(String),
+
+    #[error("ingestion job `{0}` has not failed, only failed jobs can be resumed")]
+    IngestionJobNotResumable(String),
+
+    #[error("repository `{0}` is not empty, it can only be deleted with force")]
+    RepositoryNotEmpty(String),
+
+    #[error("unable to delete repository: `{0}`")]
+    RepositoryDelete(String),
+
+    #[error("invalid text analysis: `{0}`")]
+    InvalidTextAnalysis(String),
+
+    #[error("unable to replicate changes: `{0}`")]
+    Replication(String),
+
+    #[error("invalid reranker: `{0}`")]
+    InvalidReranker(String),
+
+    #[error("invalid chunker: `{0}`")]
+    InvalidChunker(String),
+
+    #[error("invalid binding limits: `{0}`")]
+    InvalidBindingLimits(String),
+
+    #[error("extractor binding `{0}` not found")]
+    ExtractorBindingNotFound(String),
+
+    #[error("unable to export index: `{0}`")]
+    Export(String),
+
+    #[error("index `{0}` is not a graph index")]
+    NotAGraphIndex(String),
+
+    #[error("index `{0}` is not a summary index")]
+    NotASummaryIndex(String),
+
+    #[error("content `{0}` can not be refetched: {1}")]
+    ContentNotRefetchable(String, String),
+
+    #[error("unable to refetch content: `{0}`")]
+    Refetch(String),
+
+    #[error("invalid repository template: `{0}`")]
+    InvalidTemplate(String),
+
+    #[error("repository `{0}` already exists")]
+    RepositoryExists(String),
+
+    #[error("unable to create repository: `{0}`")]
+    RepositoryCreate(String),
+}
+
+fn validate_text_analysis(text_analysis: &TextAnalysisConfig) -> Result<(), DataRepositoryError> {
+    if !TEXT_SEARCH_LANGUAGES.contains(&text_analysis.language.as_str()) {
+        return Err(DataRepositoryError::InvalidTextAnalysis(format!(
+            "unknown language {}, expected one of {}",
+            text_analysis.language,
+            TEXT_SEARCH_LANGUAGES.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Content about to be added with the ids the deduplication policy of its
+/// repository gives it, ids of its own when the repository allows duplicates.
+/// The ids are set before the content is added so that they can be returned.
+fn with_policy_ids(policy: DedupPolicy, content: Vec<ContentPayload>) -> Vec<ContentPayload> {
+    match policy {
+        DedupPolicy::AllowDuplicates => content
+            .into_iter()
+            .map(|content| content.with_unique_id())
+            .collect(),
+        DedupPolicy::Dedupe | DedupPolicy::UpsertMetadata => content,
+    }
+}
+
+fn validate_repository(repository: &DataRepository) -> Result<()> {
+    validate_text_analysis(&repository.text_analysis)?;
+    for extractor_binding in &repository.extractor_bindings {
+        validate_chunkers(extractor_binding)?;
+        validate_limits(extractor_binding)?;
+        validate_payload_indexes(extractor_binding)?;
+        validate_content_types(extractor_binding)?;
+        validate_filter(extractor_binding)?;
+    }
+    Ok(())
+}
+
+fn validate_limits(binding: &ExtractorBinding) -> Result<(), DataRepositoryError> {
+    if binding.max_concurrency == Some(0) || binding.requests_per_minute == Some(0) {
+        return Err(DataRepositoryError::InvalidBindingLimits(format!(
+            "the max_concurrency and requests_per_minute of binding {} must be at least 1",
+            binding.name
+        )));
+    }
+    Ok(())
+}
+
+fn validate_payload_indexes(binding: &ExtractorBinding) -> Result<()> {
+    let mut fields = HashSet::new();
+    for payload_index in &binding.payload_indexes {
+        if payload_index.field.is_empty() {
+            return Err(anyhow!(
+                "the payload indexes of binding {} need a field",
+                binding.name
+            ));
+        }
+        if !fields.insert(payload_index.field.as_str()) {
+            return Err(anyhow!(
+                "binding {} has more than one payload index of field {}",
+                binding.name,
+                payload_index.field
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_content_types(binding: &ExtractorBinding) -> Result<()> {
+    for content_type in &binding.content_types {
+        let valid = content_type
+            .split_once('/')
+            .is_some_and(|(type_, _)| type_ != "*" || content_type == "*/*") &&
+            content_type.parse::<mime::Mime>().is_ok();
+        if !valid {
+            return Err(anyhow!(
+                "content type {} of binding {} is not a mime type such as application/pdf or \
+                 text/*",
+                content_type,
+                binding.name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The filter of a binding is evaluated when content is added, before any
+/// attribute is extracted from it, so it can only filter on metadata.
+fn validate_filter(binding: &ExtractorBinding) -> Result<()> {
+    if let Some(filter) = &binding.filter {
+        filter
+            .check_scope(Scope::Metadata)
+            .map_err(|e| anyhow!("invalid filter of binding {}: {}", binding.name, e))?;
+    }
+    Ok(())
+}
+
+fn validate_chunkers(binding: &ExtractorBinding) -> Result<(), DataRepositoryError> {
+    if let Some(chunker) = &binding.chunker {
+        chunking::validate_chunker(chunker).map_err(DataRepositoryError::InvalidChunker)?;
+    }
+    if let Some(parent_chunker) = &binding.parent_chunker {
+        chunking::validate_parent_chunker(binding.chunker.as_ref(), parent_chunker)
+            .map_err(DataRepositoryError::InvalidChunker)?;
+    }
+    Ok(())
 }
 
 pub struct DataRepositoryManager {
     repository: Arc<Repository>,
     vector_index_manager: Arc<VectorIndexManager>,
     attribute_index_manager: Arc<AttributeIndexManager>,
+    graph_index_manager: GraphIndexManager,
+    summary_index_manager: SummaryIndexManager,
     blob_storage: BlobStorageTS,
+    /// Files smaller than this are stored inline with the content.
+    inline_threshold_bytes: usize,
+    /// Runs the extractors of texts added inline, absent when extraction is
+    /// only done by the executors.
+    extractor_router: Option<ExtractorRouter>,
+    inline_extraction: InlineExtractionConfig,
 }
 
 impl fmt::Debug for DataRepositoryManager {
@@ -62,10 +418,15 @@ impl DataRepositoryManager {
         blob_storage: BlobStorageTS,
     ) -> Result<Self, RepositoryError> {
         Ok(Self {
+            graph_index_manager: GraphIndexManager::new(repository.clone()),
+            summary_index_manager: SummaryIndexManager::new(repository.clone()),
             repository,
             vector_index_manager,
             attribute_index_manager,
             blob_storage,
+            inline_threshold_bytes: 0,
+            extractor_router: None,
+            inline_extraction: InlineExtractionConfig::default(),
         })
     }
 
@@ -78,26 +439,55 @@ impl DataRepositoryManager {
         let repository = Arc::new(Repository::new_with_db(db));
         let attribute_index_manager = Arc::new(AttributeIndexManager::new(repository.clone()));
         Self {
+            graph_index_manager: GraphIndexManager::new(repository.clone()),
+            summary_index_manager: SummaryIndexManager::new(repository.clone()),
             repository,
             vector_index_manager,
             attribute_index_manager,
             blob_storage,
+            inline_threshold_bytes: 0,
+            extractor_router: None,
+            inline_extraction: InlineExtractionConfig::default(),
         }
     }
 
+    /// Stores files smaller than `bytes` in the database instead of the blob
+    /// storage.
+    pub fn with_inline_threshold(mut self, bytes: usize) -> Self {
+        self.inline_threshold_bytes = bytes;
+        self
+    }
+
+    /// Runs the extractors allowed by `config` on texts added inline, through
+    /// the executors known to the coordinator at `coordinator_addr`.
+    pub fn with_inline_extraction(
+        mut self,
+        coordinator_addr: &str,
+        config: InlineExtractionConfig,
+    ) -> Self {
+        self.extractor_router = Some(ExtractorRouter::new(coordinator_addr));
+        self.inline_extraction = config;
+        self
+    }
+
     #[tracing::instrument]
     pub async fn create_default_repository(&self, _server_config: &ServerConfig) -> Result<()> {
         let resp = self
             .repository
-            .repository_by_name(DEFAULT_REPOSITORY_NAME)
+            .repository_by_name(DEFAULT_NAMESPACE, DEFAULT_REPOSITORY_NAME)
             .await;
         if resp.is_err() {
             info!("creating default repository");
             let default_repo = DataRepository {
+                namespace: DEFAULT_NAMESPACE.into(),
                 name: DEFAULT_REPOSITORY_NAME.into(),
                 extractor_bindings: vec![],
                 data_connectors: vec![],
                 metadata: HashMap::new(),
+                dedup_policy: DedupPolicy::default(),
+                retention: RetentionPolicy::default(),
+                text_analysis: TextAnalysisConfig::default(),
+                version: 0,
             };
             return self.create(&default_repo).await;
         }
@@ -105,9 +495,12 @@ impl DataRepositoryManager {
     }
 
     #[tracing::instrument]
-    pub async fn list_repositories(&self) -> Result<Vec<DataRepository>, DataRepositoryError> {
+    pub async fn list_repositories(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<DataRepository>, DataRepositoryError> {
         self.repository
-            .repositories()
+            .repositories(namespace)
             .await
             .map_err(DataRepositoryError::Persistence)
     }
@@ -116,28 +509,46 @@ impl DataRepositoryManager {
     async fn create_index(
         &self,
         extractor: &Extractor,
+        namespace: &str,
         repository: &str,
         extractor_binding: &ExtractorBinding,
     ) -> Result<Vec<String>> {
         let mut index_names = Vec::new();
 
         for (output_name, schema) in extractor.schemas.outputs.clone() {
-            let index_name = format!("{}-{}", extractor_binding.name, output_name);
+            let index_name =
+                ExtractorBinding::output_index_name(&extractor_binding.name, &output_name);
             info!(
                 "adding index to extractor bindings repository: {}, extractor: {}, binding: {}, index: {}",
                 repository, extractor_binding.extractor, extractor_binding.name, index_name
             );
             match schema {
-                ExtractorOutputSchema::Embedding(schema) => {
+                ExtractorOutputSchema::Embedding(mut schema) => {
+                    schema.quantization = extractor_binding.quantization.clone();
+                    schema.payload_indexes = extractor_binding.payload_indexes.clone();
                     self.vector_index_manager
-                        .create_index(repository, &index_name, &extractor.name, schema)
+                        .create_index(namespace, repository, &index_name, &extractor.name, schema)
                         .await
                         .map(|index_name| index_names.push(index_name.clone()))
                         .map_err(|e| DataRepositoryError::IndexCreation(e.to_string()))?;
                 }
                 ExtractorOutputSchema::Attributes { .. } => {
                     self.attribute_index_manager
-                        .create_index(repository, &index_name, extractor.clone())
+                        .create_index(namespace, repository, &index_name, extractor.clone())
+                        .await
+                        .map(|index_name| index_names.push(index_name.clone()))
+                        .map_err(|e| DataRepositoryError::IndexCreation(e.to_string()))?;
+                }
+                ExtractorOutputSchema::Graph(schema) => {
+                    self.graph_index_manager
+                        .create_index(namespace, repository, &index_name, &extractor.name, schema)
+                        .await
+                        .map(|index_name| index_names.push(index_name.clone()))
+                        .map_err(|e| DataRepositoryError::IndexCreation(e.to_string()))?;
+                }
+                ExtractorOutputSchema::Summary(schema) => {
+                    self.summary_index_manager
+                        .create_index(namespace, repository, &index_name, &extractor.name, schema)
                         .await
                         .map(|index_name| index_names.push(index_name.clone()))
                         .map_err(|e| DataRepositoryError::IndexCreation(e.to_string()))?;
@@ -149,47 +560,172 @@ impl DataRepositoryManager {
 
     #[tracing::instrument]
     pub async fn create(&self, repository: &DataRepository) -> Result<()> {
-        info!("creating data repository: {}", repository.name);
-        self.repository
-            .upsert_repository(repository.clone())
-            .await?;
+        self.update(repository, None).await?;
+        Ok(())
+    }
+
+    /// Creates or updates a repository. With an `expected_version`, the
+    /// repository is only updated if it is still at that version. Returns the
+    /// version of the repository after the update.
+    #[tracing::instrument]
+    pub async fn update(
+        &self,
+        repository: &DataRepository,
+        expected_version: Option<i64>,
+    ) -> Result<i64> {
+        info!(
+            "creating data repository: {}, namespace: {}",
+            repository.name, repository.namespace
+        );
+        validate_repository(repository)?;
+        let version = match expected_version {
+            Some(expected_version) => {
+                self.repository
+                    .update_repository(repository.clone(), expected_version)
+                    .await?
+            }
+            None => {
+                self.repository
+                    .upsert_repository(repository.clone())
+                    .await?
+            }
+        };
 
         for extractor_binding in &repository.extractor_bindings {
             let _ = self
-                .add_extractor_binding(&repository.name, extractor_binding)
+                .add_extractor_binding(&repository.namespace, &repository.name, extractor_binding)
                 .await;
         }
-        Ok(())
+        Ok(version)
     }
 
     #[tracing::instrument]
-    pub async fn get(&self, name: &str) -> Result<DataRepository, DataRepositoryError> {
+    pub async fn get(
+        &self,
+        namespace: &str,
+        name: &str,
+    ) -> Result<DataRepository, DataRepositoryError> {
         self.repository
-            .repository_by_name(name)
+            .repository_by_name(namespace, name)
             .await
             .map_err(DataRepositoryError::Persistence)
     }
 
-    pub async fn add_extractor_binding(
+    /// Deletes a repository along with its content, chunks, attributes,
+    /// indexes and events, cancels the work on it and drops the collections
+    /// of its indexes. A repository which holds content or events is only
+    /// deleted with `force`, and one with content under a legal hold is
+    /// never deleted. A dry run reports what would be removed and removes
+    /// nothing.
+    #[tracing::instrument]
+    pub async fn delete_repository(
         &self,
-        repository: &str,
-        extractor_binding: &ExtractorBinding,
-    ) -> Result<Vec<String>> {
+        namespace: &str,
+        name: &str,
+        force: bool,
+        dry_run: bool,
+    ) -> Result<RepositoryDeletion, DataRepositoryError> {
+        let mut footprint = self
+            .repository
+            .repository_footprint(namespace, name)
+            .await?;
+        footprint.vector_collections = self
+            .vector_index_manager
+            .collections_of_indexes(namespace, name, &footprint.indexes)
+            .await
+            .map_err(|e| DataRepositoryError::RepositoryDelete(e.to_string()))?;
+        if !self
+            .repository
+            .list_legal_holds(namespace, name)
+            .await?
+            .is_empty()
+        {
+            return Err(RepositoryError::RepositoryHeld(name.into()).into());
+        }
+        if dry_run {
+            return Ok(footprint);
+        }
+        if !force && !footprint.is_empty() {
+            return Err(DataRepositoryError::RepositoryNotEmpty(name.into()));
+        }
         info!(
-            "adding extractor bindings repository: {}, extractor: {}, binding: {}",
-            repository, extractor_binding.extractor, extractor_binding.name,
+            "deleting data repository: {}, namespace: {}",
+            name, namespace
         );
-        let mut data_repository = self
-            .repository
-            .repository_by_name(repository)
+        // The collections are dropped before the rows, so that a repository
+        // whose collections could not be dropped can be deleted again.
+        self.vector_index_manager
+            .drop_collections(&footprint.vector_collections)
             .await
-            .unwrap();
+            .map_err(|e| DataRepositoryError::RepositoryDelete(e.to_string()))?;
+        let deleted = self.repository.delete_repository(namespace, name).await?;
+        Ok(RepositoryDeletion {
+            vector_collections: footprint.vector_collections,
+            ..deleted
+        })
+    }
+
+    #[tracing::instrument]
+    pub async fn update_retention(
+        &self,
+        namespace: &str,
+        repository: &str,
+        retention: RetentionPolicy,
+    ) -> Result<(), DataRepositoryError> {
+        self.repository
+            .update_retention(namespace, repository, &retention)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn update_text_analysis(
+        &self,
+        namespace: &str,
+        repository: &str,
+        text_analysis: TextAnalysisConfig,
+    ) -> Result<(), DataRepositoryError> {
+        validate_text_analysis(&text_analysis)?;
+        let current = self
+            .repository
+            .repository_by_name(namespace, repository)
+            .await?;
+        self.repository
+            .update_text_analysis(namespace, repository, &text_analysis)
+            .await?;
+        if current.text_analysis.language != text_analysis.language {
+            self.repository
+                .reanalyze_chunks(namespace, repository)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Checks that the binding can be added to the repository, and returns
+    /// its extractor.
+    async fn validate_new_binding(
+        &self,
+        data_repository: &DataRepository,
+        extractor_binding: &ExtractorBinding,
+    ) -> Result<Extractor> {
+        if extractor_binding.name.contains('.') {
+            return Err(anyhow!(
+                "binding name {} can not contain `.`, which separates it from the output in \
+                 the names of its indexes",
+                extractor_binding.name
+            ));
+        }
+        validate_chunkers(extractor_binding)?;
+        validate_limits(extractor_binding)?;
+        validate_payload_indexes(extractor_binding)?;
+        validate_content_types(extractor_binding)?;
+        validate_filter(extractor_binding)?;
         for ex in &data_repository.extractor_bindings {
             if ex.name == extractor_binding.name {
                 return Err(anyhow!(
                     "binding with name {} already exists in repository: {}",
                     extractor_binding.name,
-                    repository,
+                    data_repository.name,
                 ));
             }
         }
@@ -216,110 +752,2043 @@ impl DataRepositoryManager {
                 errors.join(",")
             ));
         }
-        let index_names = self
-            .create_index(&extractor, repository, extractor_binding)
-            .await?;
-        data_repository
-            .extractor_bindings
-            .push(extractor_binding.clone());
-        self.repository.upsert_repository(data_repository).await?;
-        Ok(index_names)
-    }
-
-    #[tracing::instrument]
-    pub async fn add_texts(&self, repo_name: &str, texts: Vec<ContentPayload>) -> Result<()> {
-        let _ = self.repository.repository_by_name(repo_name).await?;
-        self.repository.add_content(repo_name, texts).await
-    }
-
-    #[tracing::instrument]
-    pub async fn list_indexes(&self, repository_name: &str) -> Result<Vec<Index>> {
-        let indexes = self
-            .repository
-            .list_indexes(repository_name)
-            .await
-            .map_err(|e| anyhow!("unable to list indexes, error: {}", e.to_string()))?;
-        Ok(indexes)
+        if let Some(quantization) = &extractor_binding.quantization {
+            let has_embeddings = extractor
+                .schemas
+                .outputs
+                .values()
+                .any(|schema| matches!(schema, ExtractorOutputSchema::Embedding(_)));
+            if !has_embeddings {
+                return Err(anyhow!(
+                    "extractor {} does not extract embeddings, which are quantized",
+                    extractor.name
+                ));
+            }
+            if !self
+                .vector_index_manager
+                .supports_quantization(quantization)
+            {
+                return Err(anyhow!(
+                    "the vector store does not support {} quantization",
+                    quantization
+                ));
+            }
+        }
+        let multi_vector = extractor.schemas.outputs.values().any(|schema| {
+            matches!(schema, ExtractorOutputSchema::Embedding(schema) if schema.multi_vector)
+        });
+        if multi_vector && !self.vector_index_manager.supports_multi_vector() {
+            return Err(anyhow!(
+                "extractor {} embeds chunks as multiple vectors, which the vector store can not \
+                 store",
+                extractor.name
+            ));
+        }
+        if !extractor_binding.payload_indexes.is_empty() {
+            let has_embeddings = extractor
+                .schemas
+                .outputs
+                .values()
+                .any(|schema| matches!(schema, ExtractorOutputSchema::Embedding(_)));
+            if !has_embeddings {
+                return Err(anyhow!(
+                    "extractor {} does not extract embeddings, which payload indexes filter",
+                    extractor.name
+                ));
+            }
+            if multi_vector {
+                return Err(anyhow!(
+                    "extractor {} embeds chunks as multiple vectors, which have no payload \
+                     indexes",
+                    extractor.name
+                ));
+            }
+            if !self.vector_index_manager.supports_payload_indexes() {
+                return Err(anyhow!("the vector store does not support payload indexes"));
+            }
+        }
+        // The outputs of the built-in extractors depend on their params.
+        builtin_extractors::for_binding(extractor, &extractor_binding.input_params)
     }
 
+    /// The content a new binding would extract, and a sample of it, without
+    /// adding the binding or creating any work.
     #[tracing::instrument]
-    pub async fn search(
+    pub async fn preview_extractor_binding(
         &self,
+        namespace: &str,
         repository: &str,
-        index_name: &str,
-        query: &str,
-        k: u64,
-    ) -> Result<Vec<ScoredText>> {
-        self.vector_index_manager
-            .search(repository, index_name, query, k as usize)
-            .await
+        extractor_binding: &ExtractorBinding,
+        sample_size: u64,
+    ) -> Result<BindingPreview> {
+        let data_repository = self
+            .repository
+            .repository_by_name(namespace, repository)
+            .await?;
+        self.validate_new_binding(&data_repository, extractor_binding)
+            .await?;
+        Ok(self
+            .repository
+            .preview_extractor_binding(namespace, repository, extractor_binding, sample_size)
+            .await?)
     }
 
-    #[tracing::instrument]
-    pub async fn attribute_lookup(
+    pub async fn add_extractor_binding(
         &self,
+        namespace: &str,
         repository: &str,
-        index_name: &str,
-        content_id: Option<&String>,
-    ) -> Result<Vec<ExtractedAttributes>, anyhow::Error> {
-        self.attribute_index_manager
-            .get_attributes(repository, index_name, content_id)
-            .await
-    }
-
-    #[tracing::instrument]
-    pub async fn list_extractors(&self) -> Result<Vec<Extractor>, DataRepositoryError> {
-        let extractors = self
+        extractor_binding: &ExtractorBinding,
+    ) -> Result<Vec<String>> {
+        info!(
+            "adding extractor bindings repository: {}, extractor: {}, binding: {}",
+            repository, extractor_binding.extractor, extractor_binding.name,
+        );
+        let mut data_repository = self
             .repository
-            .list_extractors()
-            .await
-            .map_err(DataRepositoryError::Persistence)?;
-        Ok(extractors)
+            .repository_by_name(namespace, repository)
+            .await?;
+        let extractor = self
+            .validate_new_binding(&data_repository, extractor_binding)
+            .await?;
+        let index_names = self
+            .create_index(&extractor, namespace, repository, extractor_binding)
+            .await?;
+        let version = data_repository.version;
+        data_repository
+            .extractor_bindings
+            .push(extractor_binding.clone());
+        self.repository
+            .update_repository(data_repository, version)
+            .await?;
+        Ok(index_names)
     }
 
+    /// Pauses or resumes an extractor binding. A paused binding keeps its
+    /// configuration and the state of its content: the content added while
+    /// it is paused is extracted once it is resumed, and its pending work is
+    /// assigned again.
     #[tracing::instrument]
-    pub async fn add_events(
+    pub async fn set_extractor_binding_disabled(
         &self,
+        namespace: &str,
         repository: &str,
-        events: Vec<Event>,
-    ) -> Result<(), DataRepositoryError> {
+        binding: &str,
+        disabled: bool,
+    ) -> Result<ExtractorBinding, DataRepositoryError> {
+        let mut data_repository = self
+            .repository
+            .repository_by_name(namespace, repository)
+            .await?;
+        let version = data_repository.version;
+        let extractor_binding = data_repository
+            .extractor_bindings
+            .iter_mut()
+            .find(|b| b.name == binding)
+            .ok_or_else(|| DataRepositoryError::ExtractorBindingNotFound(binding.into()))?;
+        extractor_binding.disabled = disabled;
+        let extractor_binding = extractor_binding.clone();
+        // Writing the repository records that its bindings were added, which
+        // has the coordinator create the work of a resumed binding.
         self.repository
-            .add_events(repository, events)
-            .await
-            .map_err(DataRepositoryError::Persistence)
+            .update_repository(data_repository, version)
+            .await?;
+        Ok(extractor_binding)
     }
 
     #[tracing::instrument]
-    pub async fn list_events(&self, repository: &str) -> Result<Vec<Event>, DataRepositoryError> {
+    pub async fn add_texts(
+        &self,
+        namespace: &str,
+        repo_name: &str,
+        texts: Vec<ContentPayload>,
+    ) -> Result<()> {
+        let data_repository = self
+            .repository
+            .repository_by_name(namespace, repo_name)
+            .await?;
+        let policy = data_repository.dedup_policy;
         self.repository
-            .list_events(repository)
+            .add_content(namespace, repo_name, with_policy_ids(policy, texts), policy)
             .await
-            .map_err(DataRepositoryError::Persistence)
     }
 
+    /// Adds the texts and runs the bindings of the repository whose extractor
+    /// is allowed to run inline before returning, so the texts can be found
+    /// in the indexes of those bindings once this returns. A binding which
+    /// fails or times out is queued as work for the executors, as are the
+    /// bindings of other extractors and the bindings with sinks.
     #[tracing::instrument]
-    pub async fn upload_file(
+    pub async fn add_texts_inline(
         &self,
-        repository: &str,
+        namespace: &str,
+        repo_name: &str,
+        texts: Vec<ContentPayload>,
+    ) -> Result<Vec<InlineExtraction>> {
+        let max_text_bytes = self.inline_extraction.max_text_bytes;
+        if let Some(text) = texts.iter().find(|t| t.payload.len() > max_text_bytes) {
+            return Err(anyhow!(
+                "text {} is larger than the {} bytes which can be added inline",
+                text.id,
+                max_text_bytes
+            ));
+        }
+        let data_repository = self
+            .repository
+            .repository_by_name(namespace, repo_name)
+            .await?;
+        let policy = data_repository.dedup_policy;
+        let texts = with_policy_ids(policy, texts);
+        let content_ids: Vec<String> = texts.iter().map(|t| t.id.clone()).collect();
+        self.repository
+            .add_content(namespace, repo_name, texts, policy)
+            .await?;
+        let Some(extractor_router) = &self.extractor_router else {
+            return Ok(vec![]);
+        };
+
+        // The content is marked as processed by the bindings before it is
+        // extracted, so that the coordinator does not create work for it.
+        let mut claimed = Vec::new();
+        for binding in data_repository.extractor_bindings.iter().filter(|b| {
+            !b.disabled &&
+                b.sinks.is_empty() &&
+                self.inline_extraction.extractors.contains(&b.extractor)
+        }) {
+            let mut unapplied = Vec::new();
+            for content_id in &content_ids {
+                for content in self
+                    .repository
+                    .content_with_unapplied_extractor(
+                        namespace,
+                        repo_name,
+                        binding,
+                        Some(content_id),
+                    )
+                    .await?
+                {
+                    unapplied.push(ContentPayload::try_from(content)?);
+                }
+            }
+            let unapplied_ids: Vec<String> = unapplied.iter().map(|c| c.id.clone()).collect();
+            self.repository
+                .mark_content_as_processed_batch(namespace, &unapplied_ids, &binding.name)
+                .await?;
+            claimed.extend(unapplied.into_iter().map(|content| (binding, content)));
+        }
+
+        let timeout = Duration::from_secs(self.inline_extraction.timeout_secs);
+        let mut extractions = Vec::new();
+        for (binding, content) in claimed {
+            let result = tokio::time::timeout(
+                timeout,
+                self.extract_inline(extractor_router, namespace, repo_name, binding, &content),
+            )
+            .await
+            .unwrap_or_else(|_| {
+                Err(anyhow!(
+                    "extraction did not finish within {} seconds",
+                    timeout.as_secs()
+                ))
+            });
+            let error = match result {
+                Ok(()) => None,
+                Err(err) => {
+                    error!(
+                        "unable to extract content {} with binding {} inline, queueing it: {}",
+                        &content.id,
+                        &binding.name,
+                        err.to_string()
+                    );
+                    let work = Work::new(
+                        &content.id,
+                        namespace,
+                        repo_name,
+                        &binding.extractor,
+                        &binding.name,
+                        &binding.input_params,
+                        None,
+                    )
+                    .with_source(&content.source);
+                    self.repository.insert_work(&work).await?;
+                    Some(err.to_string())
+                }
+            };
+            extractions.push(InlineExtraction {
+                content_id: content.id,
+                extractor_binding: binding.name.clone(),
+                error,
+            });
+        }
+        Ok(extractions)
+    }
+
+    /// Runs the extractor of a binding on the content and writes what it
+    /// extracts to the indexes of the binding, as the coordinator does with
+    /// the output of work.
+    async fn extract_inline(
+        &self,
+        extractor_router: &ExtractorRouter,
+        namespace: &str,
+        repository: &str,
+        binding: &ExtractorBinding,
+        content: &ContentPayload,
+    ) -> Result<()> {
+        let input = internal_api::Content {
+            content_type: content.content_type.to_string(),
+            source: content.payload.clone().into_bytes(),
+            feature: None,
+            metadata: content.metadata.clone(),
+        };
+        let inputs = match &binding.chunker {
+            Some(chunker) => {
+                chunking::chunk_content(chunker, binding.parent_chunker.as_ref(), input)
+            }
+            None => vec![(input, ChunkOrigin::default())],
+        };
+        let mut extracted_content = Vec::new();
+        for (input, origin) in inputs {
+            for extracted in extractor_router
+                .extract(
+                    &binding.extractor,
+                    input,
+                    Some(binding.input_params.clone()),
+                )
+                .await?
+            {
+                extracted_content.push((extracted, origin.clone()));
+            }
+        }
+        for (extracted, origin) in &extracted_content {
+            let Some(feature) = &extracted.feature else {
+                continue;
+            };
+            let index_name = self
+                .repository
+                .output_index(namespace, repository, &binding.name, &feature.name)
+                .await?;
+            let text = extracted.source_as_text();
+            if let (Some(text), Some(embedding)) = (text.clone(), feature.embedding()) {
+                let embeddings = ExtractedEmbeddings {
+                    content_id: content.id.clone(),
+                    text,
+                    embeddings: embedding,
+                };
+                self.vector_index_manager
+                    .cache_embeddings(
+                        &binding.extractor,
+                        Some(&binding.input_params),
+                        std::slice::from_ref(&embeddings),
+                    )
+                    .await;
+                self.vector_index_manager
+                    .add_embedding(
+                        namespace,
+                        repository,
+                        &index_name,
+                        vec![embeddings],
+                        origin.clone(),
+                    )
+                    .await?;
+            } else if let (Some(text), Some(vectors)) = (text, feature.multi_vector()) {
+                self.vector_index_manager
+                    .add_multi_vectors(
+                        namespace,
+                        repository,
+                        &index_name,
+                        vec![ExtractedMultiVectors {
+                            content_id: content.id.clone(),
+                            text,
+                            vectors,
+                        }],
+                        origin.clone(),
+                    )
+                    .await?;
+            }
+            if let Some(metadata) = feature.metadata() {
+                let extracted_attributes =
+                    ExtractedAttributes::new(&content.id, metadata, &binding.extractor);
+                self.attribute_index_manager
+                    .add_index(
+                        namespace,
+                        repository,
+                        &index_name,
+                        extracted_attributes,
+                        binding.invalid_attributes,
+                    )
+                    .await?;
+            }
+            if let Some(graph) = feature.graph() {
+                self.graph_index_manager
+                    .add_graph(namespace, repository, &index_name, &content.id, &graph)
+                    .await?;
+            }
+            if let Some(summary) = feature.summary() {
+                self.summary_index_manager
+                    .add_summary(
+                        namespace,
+                        repository,
+                        &index_name,
+                        &content.id,
+                        &binding.extractor,
+                        &summary,
+                    )
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Content written to the database in one batch.
+    pub fn insert_batch_size(&self) -> usize {
+        self.repository.insert_batch_size()
+    }
+
+    #[tracing::instrument]
+    pub async fn list_indexes(&self, namespace: &str, repository_name: &str) -> Result<Vec<Index>> {
+        let indexes = self
+            .repository
+            .list_indexes(namespace, repository_name)
+            .await
+            .map_err(|e| anyhow!("unable to list indexes, error: {}", e.to_string()))?;
+        Ok(indexes)
+    }
+
+    #[tracing::instrument]
+    #[allow(clippy::too_many_arguments)]
+    pub async fn search(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        query: &SearchQuery,
+        k: u64,
+        attribute_prefilter: Option<AttributePrefilter>,
+        metadata_filter: HashMap<String, serde_json::Value>,
+        diversification: Option<Diversification>,
+        limits: ResultLimits,
+    ) -> Result<Vec<ScoredText>> {
+        let mut filter = SearchFilter {
+            metadata: self
+                .vector_index_manager
+                .metadata_matches(namespace, repository, index_name, &metadata_filter)
+                .await?,
+            ..Default::default()
+        };
+        if let Some(prefilter) = attribute_prefilter {
+            let content_ids = self
+                .attribute_index_manager
+                .content_ids_matching(
+                    namespace,
+                    repository,
+                    &prefilter.index,
+                    prefilter.filter.as_ref(),
+                )
+                .await?;
+            if content_ids.is_empty() {
+                return Ok(vec![]);
+            }
+            filter.content_ids = Some(content_ids);
+        }
+        // Collapsing and returning parents drop candidates, more of them are
+        // fetched so that `k` results are still returned.
+        let fetch_k = match (&diversification, limits.max_chunks_per_content) {
+            (Some(diversification), _) => diversification.fetch_k.max(k),
+            (None, Some(_)) => k * 4,
+            (None, None) if limits.parent_chunks => k * 4,
+            (None, None) => k,
+        };
+        let mut candidates = self
+            .vector_index_manager
+            .search(
+                namespace,
+                repository,
+                index_name,
+                query,
+                fetch_k as usize,
+                filter,
+            )
+            .await?;
+        if let Some(min_score) = limits.min_score {
+            candidates.retain(|c| c.confidence_score >= min_score);
+        }
+        if limits.parent_chunks {
+            candidates = self
+                .vector_index_manager
+                .expand_to_parents(namespace, candidates)
+                .await?;
+        }
+        if let Some(max_chunks) = limits.max_chunks_per_content {
+            candidates = rerank::collapse(candidates, max_chunks);
+        }
+        let Some(diversification) = diversification else {
+            candidates.truncate(k as usize);
+            return Ok(candidates);
+        };
+        Ok(rerank::diversify(
+            candidates,
+            k as usize,
+            diversification.lambda,
+        ))
+    }
+
+    /// Searches an index for chunks with the terms of the query, after the
+    /// stop words and synonyms of the repository are applied to it.
+    #[tracing::instrument]
+    pub async fn keyword_search(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        query: &str,
+        k: u64,
+    ) -> Result<Vec<ScoredText>> {
+        let data_repository = self
+            .repository
+            .repository_by_name(namespace, repository)
+            .await?;
+        let analyzer = TextAnalyzer::new(&data_repository.text_analysis);
+        let Some(tsquery) = analyzer.to_tsquery(query) else {
+            return Ok(vec![]);
+        };
+        self.vector_index_manager
+            .keyword_search(namespace, repository, index_name, &tsquery, k as usize)
+            .await
+    }
+
+    #[tracing::instrument]
+    pub async fn list_content(
+        &self,
+        namespace: &str,
+        repository: &str,
+        filter: Option<&Filter>,
+        provenance: &ProvenanceFilter,
+        after: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<ContentPayload>, DataRepositoryError> {
+        self.repository
+            .list_content(namespace, repository, filter, provenance, after, limit)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn count_content(
+        &self,
+        namespace: &str,
+        repository: &str,
+        filter: Option<&Filter>,
+        provenance: &ProvenanceFilter,
+    ) -> Result<u64, DataRepositoryError> {
+        self.repository
+            .count_content(namespace, repository, filter, provenance)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn repository_dashboard(
+        &self,
+        namespace: &str,
+        repository: &str,
+    ) -> Result<Option<RepositoryDashboard>, DataRepositoryError> {
+        self.repository
+            .repository_dashboard(namespace, repository)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn extractor_binding_stats(
+        &self,
+        namespace: &str,
+        repository: &str,
+    ) -> Result<Vec<ExtractorBindingStats>, DataRepositoryError> {
+        self.repository
+            .extractor_binding_stats(namespace, repository)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn list_work(
+        &self,
+        namespace: &str,
+        repository: &str,
+        after: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<Work>, DataRepositoryError> {
+        self.repository
+            .list_work(namespace, repository, after, limit)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    /// Work of a repository, with its progress and log.
+    #[tracing::instrument]
+    pub async fn get_work(
+        &self,
+        namespace: &str,
+        repository: &str,
+        work_id: &str,
+    ) -> Result<Work, DataRepositoryError> {
+        let work = self.repository.work_by_id(work_id).await?;
+        if work.namespace != namespace || work.repository_id != repository {
+            return Err(RepositoryError::WorkNotFound(work_id.into()).into());
+        }
+        Ok(work)
+    }
+
+    #[tracing::instrument]
+    pub async fn count_work(
+        &self,
+        namespace: &str,
+        repository: &str,
+    ) -> Result<u64, DataRepositoryError> {
+        self.repository
+            .count_work(namespace, repository)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn list_audit_events(
+        &self,
+        namespace: &str,
+        filter: &AuditLogFilter,
+        after: Option<u64>,
+        limit: u64,
+    ) -> Result<Vec<AuditEvent>, DataRepositoryError> {
+        self.repository
+            .list_audit_events(namespace, filter, after, limit)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn count_audit_events(
+        &self,
+        namespace: &str,
+        filter: &AuditLogFilter,
+    ) -> Result<u64, DataRepositoryError> {
+        self.repository
+            .count_audit_events(namespace, filter)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn reindex(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+    ) -> Result<IndexSnapshot> {
+        self.vector_index_manager
+            .reindex(namespace, repository, index_name)
+            .await
+    }
+
+    pub async fn warm_index(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+    ) -> Result<IndexWarmup> {
+        self.vector_index_manager
+            .warm_index(namespace, repository, index_name)
+            .await
+    }
+
+    /// Sets the reranker of an embedding index, or removes it when
+    /// `reranker` is `None`.
+    #[tracing::instrument]
+    pub async fn set_index_reranker(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        reranker: Option<RerankerConfig>,
+    ) -> Result<(), DataRepositoryError> {
+        let index = self
+            .repository
+            .list_indexes(namespace, repository)
+            .await
+            .map_err(|e| DataRepositoryError::IndexCreation(e.to_string()))?
+            .into_iter()
+            .find(|index| index.name == index_name)
+            .ok_or(RepositoryError::IndexNotFound(index_name.into()))?;
+        if let Some(reranker) = &reranker {
+            let ExtractorOutputSchema::Embedding(_) = index.schema else {
+                return Err(DataRepositoryError::InvalidReranker(format!(
+                    "index {} is not an embedding index",
+                    index_name
+                )));
+            };
+            if reranker.candidates == 0 {
+                return Err(DataRepositoryError::InvalidReranker(
+                    "candidates has to be at least 1".into(),
+                ));
+            }
+            if let RerankerBackend::Extractor { name } = &reranker.backend {
+                self.repository
+                    .extractor_by_name(name)
+                    .await
+                    .map_err(|e| DataRepositoryError::InvalidReranker(e.to_string()))?;
+            }
+        }
+        self.repository
+            .set_index_reranker(namespace, repository, index_name, reranker.as_ref())
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    /// The attributes of an index set aside because they do not match its
+    /// schema, after the id `after`.
+    #[tracing::instrument]
+    pub async fn quarantined_attributes(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        after: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<QuarantinedAttributes>, DataRepositoryError> {
+        self.repository
+            .repository_by_name(namespace, repository)
+            .await?;
+        self.repository
+            .quarantined_attributes(namespace, repository, index_name, after, limit)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn count_quarantined_attributes(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+    ) -> Result<u64, DataRepositoryError> {
+        self.repository
+            .count_quarantined_attributes(namespace, repository, index_name)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    async fn check_graph_index(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+    ) -> Result<(), DataRepositoryError> {
+        let index = self
+            .repository
+            .list_indexes(namespace, repository)
+            .await
+            .map_err(|e| DataRepositoryError::IndexCreation(e.to_string()))?
+            .into_iter()
+            .find(|index| index.name == index_name)
+            .ok_or(RepositoryError::IndexNotFound(index_name.into()))?;
+        let ExtractorOutputSchema::Graph(_) = index.schema else {
+            return Err(DataRepositoryError::NotAGraphIndex(index_name.into()));
+        };
+        Ok(())
+    }
+
+    /// The entities of a graph index whose name contains `query`.
+    #[tracing::instrument]
+    pub async fn search_graph_entities(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        query: &str,
+        entity_type: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<GraphNode>, DataRepositoryError> {
+        self.check_graph_index(namespace, repository, index_name)
+            .await?;
+        Ok(self
+            .graph_index_manager
+            .search_entities(namespace, repository, index_name, query, entity_type, limit)
+            .await?)
+    }
+
+    /// An entity of a graph index and the entities it is related to.
+    #[tracing::instrument]
+    pub async fn graph_neighbors(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        entity: &str,
+        relation: Option<&str>,
+        limit: u64,
+    ) -> Result<(GraphNode, Vec<(GraphEdge, GraphNode)>), DataRepositoryError> {
+        self.check_graph_index(namespace, repository, index_name)
+            .await?;
+        Ok(self
+            .graph_index_manager
+            .neighbors(namespace, repository, index_name, entity, relation, limit)
+            .await?)
+    }
+
+    /// The shortest paths between two entities of a graph index, with the
+    /// entities on them.
+    #[tracing::instrument]
+    pub async fn graph_paths(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        query: PathQuery<'_>,
+    ) -> Result<(Vec<GraphNode>, Vec<Vec<GraphEdge>>), DataRepositoryError> {
+        self.check_graph_index(namespace, repository, index_name)
+            .await?;
+        Ok(self
+            .graph_index_manager
+            .paths(namespace, repository, index_name, query)
+            .await?)
+    }
+
+    /// The summaries of the content in a summary index, by content id.
+    /// Content which has not been summarized, such as content added before
+    /// the binding or short content the extractor skipped, has none.
+    #[tracing::instrument(skip(content_ids))]
+    pub async fn content_summaries(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        content_ids: &[String],
+    ) -> Result<HashMap<String, String>, DataRepositoryError> {
+        let index = self
+            .repository
+            .list_indexes(namespace, repository)
+            .await
+            .map_err(|e| DataRepositoryError::IndexCreation(e.to_string()))?
+            .into_iter()
+            .find(|index| index.name == index_name)
+            .ok_or(RepositoryError::IndexNotFound(index_name.into()))?;
+        let ExtractorOutputSchema::Summary(_) = index.schema else {
+            return Err(DataRepositoryError::NotASummaryIndex(index_name.into()));
+        };
+        Ok(self
+            .summary_index_manager
+            .summaries(namespace, repository, index_name, content_ids)
+            .await?)
+    }
+
+    #[tracing::instrument]
+    pub async fn attribute_lookup(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        content_id: Option<&String>,
+    ) -> Result<Vec<ExtractedAttributes>, anyhow::Error> {
+        self.attribute_index_manager
+            .get_attributes(namespace, repository, index_name, content_id)
+            .await
+    }
+
+    /// The attributes extracted from each content into the indexes, by
+    /// content id and then by index.
+    #[tracing::instrument]
+    pub async fn content_attributes(
+        &self,
+        namespace: &str,
+        repository: &str,
+        indexes: &[String],
+        content_ids: &[String],
+    ) -> Result<HashMap<String, HashMap<String, serde_json::Value>>, DataRepositoryError> {
+        let mut content_attributes: HashMap<String, HashMap<String, serde_json::Value>> =
+            HashMap::new();
+        for (index, attributes) in self
+            .repository
+            .attributes_of_content(namespace, repository, indexes, content_ids)
+            .await?
+        {
+            content_attributes
+                .entry(attributes.content_id)
+                .or_default()
+                .insert(index, attributes.attributes);
+        }
+        Ok(content_attributes)
+    }
+
+    /// Returns the attributes in the index which match the query, in the
+    /// order of `sort`.
+    #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument]
+    pub async fn query_attributes(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        query: Option<&Filter>,
+        sort: Option<&AttributeSort>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<ExtractedAttributes>, DataRepositoryError> {
+        let _ = self
+            .repository
+            .repository_by_name(namespace, repository)
+            .await?;
+        self.repository
+            .query_attributes(
+                namespace, repository, index_name, query, sort, offset, limit,
+            )
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn count_attributes(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        query: Option<&Filter>,
+    ) -> Result<u64, DataRepositoryError> {
+        self.repository
+            .count_attributes(namespace, repository, index_name, query)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    /// Groups the attributes of an index which match the query, with their
+    /// counts and metrics.
+    #[tracing::instrument]
+    pub async fn aggregate_attributes(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        query: Option<&Filter>,
+        aggregation: &AttributeAggregation,
+    ) -> Result<Vec<AttributeGroup>, DataRepositoryError> {
+        self.repository
+            .repository_by_name(namespace, repository)
+            .await?;
+        self.repository
+            .aggregate_attributes(namespace, repository, index_name, query, aggregation)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    /// An export of the attributes of an attribute index, or of the chunks
+    /// of the current generation of an embedding index.
+    #[tracing::instrument]
+    pub async fn export_index(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        format: ExportFormat,
+    ) -> Result<IndexExport, DataRepositoryError> {
+        self.repository
+            .repository_by_name(namespace, repository)
+            .await?;
+        let index = self
+            .repository
+            .list_indexes(namespace, repository)
+            .await
+            .map_err(|e| DataRepositoryError::Export(e.to_string()))?
+            .into_iter()
+            .find(|index| index.name == index_name)
+            .ok_or(RepositoryError::IndexNotFound(index_name.into()))?;
+        let source = match index.schema {
+            ExtractorOutputSchema::Attributes(schema) => ExportSource::Attributes {
+                index: index.name,
+                schema,
+                output: index.output,
+            },
+            ExtractorOutputSchema::Embedding(_) => ExportSource::Chunks(
+                self.repository
+                    .index_snapshot(namespace, index_name, repository)
+                    .await
+                    .map_err(|e| DataRepositoryError::Export(e.to_string()))?,
+            ),
+            ExtractorOutputSchema::Graph(_) => {
+                return Err(DataRepositoryError::Export(format!(
+                    "index {} is a graph index, which can't be exported",
+                    index_name
+                )));
+            }
+            ExtractorOutputSchema::Summary(_) => {
+                return Err(DataRepositoryError::Export(format!(
+                    "index {} is a summary index, which can't be exported",
+                    index_name
+                )));
+            }
+        };
+        Ok(IndexExport::new(
+            self.repository.clone(),
+            namespace,
+            repository,
+            source,
+            format,
+        ))
+    }
+
+    /// A watch of the extraction of a content, from its current progress.
+    #[tracing::instrument]
+    pub async fn watch_content_progress(
+        &self,
+        namespace: &str,
+        repository: &str,
+        content_id: &str,
+    ) -> Result<ContentProgressWatch, DataRepositoryError> {
+        let progress = self
+            .repository
+            .content_progress(namespace, repository, content_id)
+            .await?;
+        Ok(ContentProgressWatch::new(
+            self.repository.clone(),
+            namespace,
+            repository,
+            content_id,
+            progress,
+        ))
+    }
+
+    /// The content a content was derived from by extractor bindings, and the
+    /// content derived from it.
+    #[tracing::instrument]
+    pub async fn content_lineage(
+        &self,
+        namespace: &str,
+        repository: &str,
+        content_id: &str,
+    ) -> Result<Vec<ContentLineageNode>, DataRepositoryError> {
+        Ok(self
+            .repository
+            .content_lineage(namespace, repository, content_id)
+            .await?)
+    }
+
+    #[tracing::instrument]
+    pub async fn list_extractors(&self) -> Result<Vec<Extractor>, DataRepositoryError> {
+        let extractors = self
+            .repository
+            .list_extractors()
+            .await
+            .map_err(DataRepositoryError::Persistence)?;
+        Ok(extractors)
+    }
+
+    #[tracing::instrument]
+    pub async fn add_events(
+        &self,
+        namespace: &str,
+        repository: &str,
+        events: Vec<Event>,
+    ) -> Result<(), DataRepositoryError> {
+        self.repository
+            .add_events(namespace, repository, events)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    /// Adds events and their related content in one transaction. Returns the
+    /// ids of the events and of the content which were added.
+    #[tracing::instrument(skip(ingestion))]
+    pub async fn ingest(
+        &self,
+        namespace: &str,
+        repository: &str,
+        ingestion: Ingestion,
+    ) -> Result<(Vec<String>, Vec<String>), DataRepositoryError> {
+        let _ = self
+            .repository
+            .repository_by_name(namespace, repository)
+            .await
+            .map_err(DataRepositoryError::Persistence)?;
+        let mut shared_metadata = ingestion.metadata;
+        if let Some(session_id) = ingestion.session_id {
+            shared_metadata.insert(SESSION_ID_METADATA_KEY.into(), json!(session_id));
+        }
+        let with_shared_metadata = |metadata: &mut HashMap<String, serde_json::Value>| {
+            for (key, value) in &shared_metadata {
+                metadata.entry(key.clone()).or_insert(value.clone());
+            }
+        };
+
+        let mut events = ingestion.events;
+        for event in &mut events {
+            with_shared_metadata(&mut event.metadata);
+        }
+        let mut content = ingestion.texts;
+        // The files are written to the blob storage before the transaction, a
+        // failed transaction leaves them unreferenced.
+        for file in ingestion.files {
+            let mut payload = self
+                .store_file(repository, &file.name, &file.name, file.data)
+                .await
+                .map_err(|e| DataRepositoryError::ContentWrite(e.to_string()))?;
+            payload.metadata = file.metadata;
+            content.push(payload);
+        }
+        for payload in &mut content {
+            with_shared_metadata(&mut payload.metadata);
+        }
+
+        let event_ids = events.iter().map(|e| e.id.clone()).collect();
+        let content_ids = content.iter().map(|c| c.id.clone()).collect();
+        self.repository
+            .add_events_with_content(namespace, repository, events, content)
+            .await
+            .map_err(DataRepositoryError::Persistence)?;
+        Ok((event_ids, content_ids))
+    }
+
+    #[tracing::instrument]
+    pub async fn list_events(
+        &self,
+        namespace: &str,
+        repository: &str,
+    ) -> Result<Vec<Event>, DataRepositoryError> {
+        self.repository
+            .list_events(namespace, repository)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn list_connector_syncs(
+        &self,
+        namespace: &str,
+        repository: &str,
+    ) -> Result<Vec<ConnectorSyncRun>, DataRepositoryError> {
+        self.repository
+            .list_connector_sync_runs(namespace, repository)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    /// Stores a manifest of content and creates the job which ingests its
+    /// rows into the repository in the background. The format of the
+    /// manifest is told by the extension of its name.
+    #[tracing::instrument(skip(data, config))]
+    pub async fn create_ingestion_job(
+        &self,
+        namespace: &str,
+        repository: &str,
+        name: &str,
+        data: Bytes,
+        config: &ManifestConfig,
+    ) -> Result<IngestionJob, DataRepositoryError> {
+        self.repository
+            .repository_by_name(namespace, repository)
+            .await?;
+        let format = ManifestFormat::from_file_name(name).ok_or(
+            DataRepositoryError::InvalidManifest(format!("{} is not a jsonl or csv file", name)),
+        )?;
+        if data.len() as u64 > config.max_manifest_size_bytes {
+            return Err(DataRepositoryError::InvalidManifest(format!(
+                "manifest is larger than {} bytes",
+                config.max_manifest_size_bytes
+            )));
+        }
+        let mut job = IngestionJob::new(namespace, repository, name, "", format);
+        job.manifest_url = self
+            .blob_storage
+            .put(&format!("manifest-{}-{}", job.id, name), data)
+            .await
+            .map_err(|e| DataRepositoryError::ContentWrite(e.to_string()))?;
+        self.repository.create_ingestion_job(&job).await?;
+        info!(
+            "created ingestion job {} of manifest {} for repository {}",
+            job.id, name, repository
+        );
+        Ok(job)
+    }
+
+    #[tracing::instrument]
+    pub async fn ingestion_job(
+        &self,
+        namespace: &str,
+        repository: &str,
+        id: &str,
+    ) -> Result<IngestionJob, DataRepositoryError> {
+        self.repository
+            .ingestion_job(namespace, repository, id)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn list_ingestion_jobs(
+        &self,
+        namespace: &str,
+        repository: &str,
+    ) -> Result<Vec<IngestionJob>, DataRepositoryError> {
+        self.repository
+            .list_ingestion_jobs(namespace, repository)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn ingestion_job_errors(
+        &self,
+        namespace: &str,
+        repository: &str,
+        id: &str,
+        after: Option<u64>,
+        limit: u64,
+    ) -> Result<Vec<IngestionRowError>, DataRepositoryError> {
+        self.repository
+            .ingestion_job(namespace, repository, id)
+            .await?;
+        self.repository
+            .ingestion_job_errors(namespace, id, after, limit)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn resume_ingestion_job(
+        &self,
+        namespace: &str,
+        repository: &str,
+        id: &str,
+    ) -> Result<IngestionJob, DataRepositoryError> {
+        if !self
+            .repository
+            .resume_ingestion_job(namespace, repository, id)
+            .await?
+        {
+            return Err(DataRepositoryError::IngestionJobNotResumable(id.into()));
+        }
+        self.ingestion_job(namespace, repository, id).await
+    }
+
+    /// Adds the texts and the files of rows of a manifest to the repository,
+    /// with the `manifest` source.
+    #[tracing::instrument(skip(texts, files))]
+    pub async fn add_manifest_content(
+        &self,
+        namespace: &str,
+        repository: &str,
+        texts: Vec<ContentPayload>,
+        files: Vec<IngestedFile>,
+    ) -> Result<()> {
+        let policy = self
+            .repository
+            .repository_by_name(namespace, repository)
+            .await?
+            .dedup_policy;
+        let mut content = texts;
+        for file in files {
+            let key = match policy {
+                DedupPolicy::AllowDuplicates => format!("{}-{}", nanoid!(), file.name),
+                DedupPolicy::Dedupe | DedupPolicy::UpsertMetadata => file.name.clone(),
+            };
+            let mut payload = self
+                .store_file(repository, &file.name, &key, file.data)
+                .await?;
+            payload.metadata = file.metadata;
+            content.push(payload);
+        }
+        let content = with_policy_ids(policy, content)
+            .into_iter()
+            .map(|c| c.with_source(MANIFEST_CONTENT_SOURCE))
+            .collect();
+        self.repository
+            .add_content(namespace, repository, content, policy)
+            .await
+    }
+
+    /// Issues an api key and returns it along with its secret.
+    #[tracing::instrument]
+    pub async fn create_api_key(
+        &self,
+        namespace: &str,
+        name: &str,
+        scope: ApiKeyScope,
+        repositories: Vec<String>,
+    ) -> Result<(ApiKey, String), DataRepositoryError> {
+        let (api_key, secret) = ApiKey::generate(name, namespace, scope, repositories);
+        self.repository
+            .create_api_key(&api_key)
+            .await
+            .map_err(DataRepositoryError::Persistence)?;
+        Ok((api_key, secret))
+    }
+
+    #[tracing::instrument]
+    pub async fn list_api_keys(&self, namespace: &str) -> Result<Vec<ApiKey>> {
+        self.repository.list_api_keys(namespace).await
+    }
+
+    #[tracing::instrument]
+    pub async fn delete_api_key(
+        &self,
+        namespace: &str,
+        id: &str,
+    ) -> Result<(), DataRepositoryError> {
+        self.repository
+            .delete_api_key(namespace, id)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    /// Grants a role on a repository to an api key of the namespace.
+    #[tracing::instrument]
+    pub async fn grant_role(
+        &self,
+        namespace: &str,
+        repository: &str,
+        principal: &str,
+        role: RepositoryRole,
+    ) -> Result<RoleBinding, DataRepositoryError> {
+        self.repository
+            .repository_by_name(namespace, repository)
+            .await?;
+        self.repository
+            .api_key(namespace, principal)
+            .await?
+            .ok_or(RepositoryError::ApiKeyNotFound(principal.into()))?;
+        let binding = RoleBinding::new(principal, repository, role);
+        self.repository
+            .grant_repository_role(namespace, &binding)
+            .await?;
+        Ok(binding)
+    }
+
+    #[tracing::instrument]
+    pub async fn revoke_role(
+        &self,
+        namespace: &str,
+        repository: &str,
+        principal: &str,
+    ) -> Result<(), DataRepositoryError> {
+        self.repository
+            .revoke_repository_role(namespace, repository, principal)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn list_roles(&self, namespace: &str, repository: &str) -> Result<Vec<RoleBinding>> {
+        self.repository
+            .list_repository_roles(namespace, repository)
+            .await
+    }
+
+    /// Places a legal hold on content of the repository, or on the whole
+    /// repository when `content_id` is not set.
+    #[tracing::instrument]
+    pub async fn place_legal_hold(
+        &self,
+        namespace: &str,
+        repository: &str,
+        content_id: Option<&str>,
+        reason: &str,
+    ) -> Result<LegalHold, DataRepositoryError> {
+        self.repository
+            .repository_by_name(namespace, repository)
+            .await?;
+        if let Some(content_id) = content_id {
+            self.repository
+                .content_from_repo(namespace, content_id, repository)
+                .await?;
+        }
+        let hold = LegalHold::new(repository, content_id, reason);
+        self.repository.place_legal_hold(namespace, &hold).await?;
+        Ok(hold)
+    }
+
+    #[tracing::instrument]
+    pub async fn release_legal_hold(
+        &self,
+        namespace: &str,
+        repository: &str,
+        id: &str,
+    ) -> Result<(), DataRepositoryError> {
+        self.repository
+            .release_legal_hold(namespace, repository, id)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn list_legal_holds(
+        &self,
+        namespace: &str,
+        repository: &str,
+    ) -> Result<Vec<LegalHold>, DataRepositoryError> {
+        self.repository
+            .list_legal_holds(namespace, repository)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn held_content(
+        &self,
+        namespace: &str,
+        repository: &str,
+        content_ids: &[String],
+    ) -> Result<HashSet<String>, DataRepositoryError> {
+        self.repository
+            .held_content(namespace, repository, content_ids)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    /// Creates a repository template, or replaces the template of the same
+    /// name. The template is validated like the repositories created from
+    /// it.
+    #[tracing::instrument]
+    pub async fn create_repository_template(
+        &self,
+        namespace: &str,
+        template: RepositoryTemplate,
+    ) -> Result<RepositoryTemplate, DataRepositoryError> {
+        validate_repository(&template.repository(namespace, &template.name, HashMap::new()))
+            .map_err(|e| DataRepositoryError::InvalidTemplate(e.to_string()))?;
+        let template = RepositoryTemplate {
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            ..template
+        };
+        self.repository
+            .upsert_repository_template(namespace, &template)
+            .await?;
+        Ok(template)
+    }
+
+    #[tracing::instrument]
+    pub async fn repository_template(
+        &self,
+        namespace: &str,
+        name: &str,
+    ) -> Result<RepositoryTemplate, DataRepositoryError> {
+        self.repository
+            .repository_template(namespace, name)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn list_repository_templates(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<RepositoryTemplate>, DataRepositoryError> {
+        self.repository
+            .list_repository_templates(namespace)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    #[tracing::instrument]
+    pub async fn delete_repository_template(
+        &self,
+        namespace: &str,
+        name: &str,
+    ) -> Result<(), DataRepositoryError> {
+        self.repository
+            .delete_repository_template(namespace, name)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    /// Creates a repository with the bindings and settings of a template, and
+    /// the `metadata` given added to the metadata of the template. Existing
+    /// repositories are not replaced. Returns the version of the repository.
+    #[tracing::instrument]
+    pub async fn create_repository_from_template(
+        &self,
+        namespace: &str,
+        template: &str,
+        name: &str,
+        metadata: HashMap<String, serde_json::Value>,
+    ) -> Result<i64, DataRepositoryError> {
+        let template = self
+            .repository
+            .repository_template(namespace, template)
+            .await?;
+        match self.repository.repository_by_name(namespace, name).await {
+            Ok(_) => return Err(DataRepositoryError::RepositoryExists(name.into())),
+            Err(RepositoryError::RepositoryNotFound(_)) => {}
+            Err(err) => return Err(err.into()),
+        }
+        self.update(&template.repository(namespace, name, metadata), None)
+            .await
+            .map_err(|e| DataRepositoryError::RepositoryCreate(e.to_string()))
+    }
+
+    /// Soft deletes content, which is hidden from searches, listings and
+    /// extraction until it is restored, and purged once the grace period of
+    /// the retention configuration is over. Returns when it was deleted.
+    #[tracing::instrument]
+    pub async fn soft_delete_content(
+        &self,
+        namespace: &str,
+        repository: &str,
+        content_id: &str,
+    ) -> Result<u64, DataRepositoryError> {
+        self.repository
+            .content_from_repo(namespace, content_id, repository)
+            .await?;
+        let content_ids = vec![content_id.to_string()];
+        if !self
+            .held_content(namespace, repository, &content_ids)
+            .await?
+            .is_empty()
+        {
+            return Err(DataRepositoryError::ContentHeld(content_id.into()));
+        }
+        let deleted_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        if self
+            .repository
+            .soft_delete_content(namespace, repository, &content_ids, deleted_at)
+            .await? ==
+            0
+        {
+            return Err(RepositoryError::ContentNotFound(content_id.into()).into());
+        }
+        Ok(deleted_at)
+    }
+
+    #[tracing::instrument]
+    pub async fn restore_content(
+        &self,
+        namespace: &str,
+        repository: &str,
+        content_id: &str,
+    ) -> Result<(), DataRepositoryError> {
+        self.repository
+            .restore_content(namespace, repository, content_id)
+            .await
+            .map_err(DataRepositoryError::Persistence)
+    }
+
+    /// Deletes content along with its chunks, their embeddings and its
+    /// attributes. Content under a legal hold is left in place.
+    #[tracing::instrument(skip(content_ids))]
+    pub async fn delete_content(
+        &self,
+        namespace: &str,
+        repository: &str,
+        content_ids: &[String],
+    ) -> Result<DeletedContent, DataRepositoryError> {
+        let held = self
+            .held_content(namespace, repository, content_ids)
+            .await?;
+        let content_ids: Vec<String> = content_ids
+            .iter()
+            .filter(|id| !held.contains(*id))
+            .cloned()
+            .collect();
+        let chunk_ids = self
+            .repository
+            .embedded_chunks_of_content(namespace, &content_ids)
+            .await?;
+        // The content is deleted once its embeddings are, so that content
+        // whose embeddings could not be deleted is deleted again later.
+        let vectors = self
+            .vector_index_manager
+            .delete_embeddings(namespace, repository, chunk_ids)
+            .await
+            .map_err(|e| DataRepositoryError::ContentDelete(e.to_string()))?;
+        let deleted = self
+            .repository
+            .delete_content(namespace, repository, &content_ids)
+            .await?;
+        Ok(DeletedContent { vectors, ..deleted })
+    }
+
+    /// Fetches content again from the url it was fetched from, and re-indexes
+    /// it when it changed: the new text is added as content which keeps the
+    /// metadata, source and provenance of the old content, and the old
+    /// content is deleted. Content which did not change only has its fetch
+    /// time updated. Content without an http(s) source url, such as the
+    /// messages of Kafka topics, content derived by extractors and content
+    /// under a legal hold can not be refetched.
+    #[tracing::instrument]
+    pub async fn refetch_content(
+        &self,
+        namespace: &str,
+        repository: &str,
+        content_id: &str,
+    ) -> Result<RefetchedContent, DataRepositoryError> {
+        let content = self
+            .repository
+            .content_from_repo(namespace, content_id, repository)
+            .await?;
+        let not_refetchable = |reason: &str| {
+            DataRepositoryError::ContentNotRefetchable(content_id.into(), reason.into())
+        };
+        if content.parent_content_id.is_some() {
+            return Err(not_refetchable(
+                "it was derived by an extractor, refetch the content it was derived from",
+            ));
+        }
+        let url = content
+            .provenance
+            .source_url
+            .as_deref()
+            .and_then(|url| Url::parse(url).ok())
+            .filter(|url| url.scheme() == "http" || url.scheme() == "https")
+            .ok_or_else(|| not_refetchable("it has no http(s) source url"))?;
+        let content_ids = vec![content_id.to_string()];
+        if !self
+            .held_content(namespace, repository, &content_ids)
+            .await?
+            .is_empty()
+        {
+            return Err(DataRepositoryError::ContentHeld(content_id.into()));
+        }
+        let page = WebCrawler::new(WebCrawlerConfig::new(vec![url.to_string()]))
+            .fetch(&url)
+            .await
+            .map_err(|e| DataRepositoryError::Refetch(e.to_string()))?;
+        let fetched_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        if matches!(content.payload_type, PayloadType::EmbeddedStorage) &&
+            content.payload == page.text
+        {
+            self.repository
+                .set_content_fetched_at(namespace, repository, content_id, fetched_at)
+                .await?;
+            return Ok(RefetchedContent {
+                content_id: content_id.into(),
+                changed: false,
+            });
+        }
+        let refetched = ContentPayload::from_text(repository, &page.text, content.metadata)
+            .with_source(&content.source)
+            .with_provenance(Provenance {
+                fetched_at: Some(fetched_at),
+                ..content.provenance
+            });
+        let policy = self
+            .repository
+            .repository_by_name(namespace, repository)
+            .await?
+            .dedup_policy;
+        let refetched = with_policy_ids(policy, vec![refetched]).remove(0);
+        let refetched_id = refetched.id.clone();
+        self.repository
+            .add_content(namespace, repository, vec![refetched], policy)
+            .await
+            .map_err(|e| DataRepositoryError::ContentWrite(e.to_string()))?;
+        self.delete_content(namespace, repository, &content_ids)
+            .await?;
+        Ok(RefetchedContent {
+            content_id: refetched_id,
+            changed: true,
+        })
+    }
+
+    #[tracing::instrument]
+    pub async fn upload_file(
+        &self,
+        namespace: &str,
+        repository: &str,
         name: &str,
         file: Bytes,
     ) -> Result<(), anyhow::Error> {
         // TODO - wrap the write to blob storage in a lambda and pass it to the
         // persistence layer so that we can mark the file upload as complete if
         // the blob storage write succeeds.
-        let stored_file_path = self.blob_storage.put(name, file).await?;
+        let policy = self
+            .repository
+            .repository_by_name(namespace, repository)
+            .await?
+            .dedup_policy;
+        let payload = match policy {
+            // Every upload of a file is stored under a key of its own.
+            DedupPolicy::AllowDuplicates => {
+                let key = format!("{}-{}", nanoid!(), name);
+                self.store_file(repository, name, &key, file)
+                    .await?
+                    .with_unique_id()
+            }
+            DedupPolicy::Dedupe | DedupPolicy::UpsertMetadata => {
+                self.store_file(repository, name, name, file).await?
+            }
+        };
         self.repository
-            .add_content(
-                repository,
-                vec![ContentPayload::from_file(
-                    repository,
-                    name,
-                    &stored_file_path,
-                )],
-            )
+            .add_content(namespace, repository, vec![payload], policy)
+            .await?;
+        Ok(())
+    }
+
+    /// Stores a file inline when it is below the inline threshold, and in the
+    /// blob storage under `key` otherwise.
+    async fn store_file(
+        &self,
+        repository: &str,
+        name: &str,
+        key: &str,
+        file: Bytes,
+    ) -> Result<ContentPayload> {
+        if file.len() < self.inline_threshold_bytes {
+            return Ok(ContentPayload::from_inline_file(repository, name, &file));
+        }
+        let stored_file_path = self.blob_storage.put(key, file).await?;
+        Ok(ContentPayload::from_file(
+            repository,
+            name,
+            &stored_file_path,
+        ))
+    }
+
+    /// The bytes of content, wherever they are stored.
+    #[tracing::instrument]
+    pub async fn read_content(
+        &self,
+        namespace: &str,
+        repository: &str,
+        content_id: &str,
+    ) -> Result<(String, Vec<u8>), DataRepositoryError> {
+        let payload = self
+            .repository
+            .content_from_repo(namespace, content_id, repository)
+            .await
+            .map_err(DataRepositoryError::Persistence)?;
+        let payload = internal_api::ContentPayload::try_from(payload)
+            .map_err(|e| DataRepositoryError::ContentRead(e.to_string()))?;
+        let content_type = payload.content_type.clone();
+        let data = ContentReader::new(payload)
+            .read()
+            .await
+            .map_err(|e| DataRepositoryError::ContentRead(e.to_string()))?;
+        Ok((content_type, data))
+    }
+
+    /// Streams the bytes of content, or the bytes in the range of an HTTP
+    /// `Range` header, wherever they are stored. Blobs are streamed from the
+    /// blob storage rather than read into memory.
+    #[tracing::instrument]
+    pub async fn download_content(
+        &self,
+        namespace: &str,
+        repository: &str,
+        content_id: &str,
+        range: Option<&str>,
+    ) -> Result<ContentDownload, DataRepositoryError> {
+        let payload = self
+            .repository
+            .content_from_repo(namespace, content_id, repository)
+            .await
+            .map_err(DataRepositoryError::Persistence)?;
+        let payload = internal_api::ContentPayload::try_from(payload)
+            .map_err(|e| DataRepositoryError::ContentRead(e.to_string()))?;
+        let content_type = payload.content_type.clone();
+        let reader = ContentReader::new(payload);
+        let size = reader
+            .size()
+            .await
+            .map_err(|e| DataRepositoryError::ContentRead(e.to_string()))?;
+        let range = match range {
+            Some(header) => content_reader::byte_range(header, size)
+                .map_err(|_| DataRepositoryError::RangeNotSatisfiable(header.into(), size))?,
+            None => None,
+        };
+        let body = reader
+            .stream(range.clone().unwrap_or(0..size))
+            .await
+            .map_err(|e| DataRepositoryError::ContentRead(e.to_string()))?;
+        Ok(ContentDownload {
+            content_type,
+            size,
+            range,
+            body,
+        })
+    }
+
+    /// Reads the changes of a repository after the entry `after` of its
+    /// change feed. The objects are read as they are now, an entry whose
+    /// object is gone, such as a chunk of a generation which was replaced, is
+    /// skipped, and an object is returned once even if it changed several
+    /// times.
+    #[tracing::instrument]
+    pub async fn changes(
+        &self,
+        namespace: &str,
+        repository: &str,
+        after: u64,
+        limit: u64,
+    ) -> Result<ChangeBatch, DataRepositoryError> {
+        let _ = self
+            .repository
+            .repository_by_name(namespace, repository)
+            .await?;
+        let mut entries = self
+            .repository
+            .list_changes(namespace, repository, after, limit + 1)
+            .await?;
+        let has_more = entries.len() as u64 > limit;
+        entries.truncate(limit as usize);
+        let cursor = entries.last().map(|c| c.seq).unwrap_or(after);
+
+        let ids_of = |kind: ChangeKind| -> Vec<String> {
+            entries
+                .iter()
+                .filter(|c| c.kind == kind)
+                .map(|c| c.object_id.clone())
+                .collect()
+        };
+        let mut content: HashMap<String, ContentPayload> = self
+            .repository
+            .content_with_ids(namespace, repository, &ids_of(ChangeKind::Content))
+            .await?
+            .into_iter()
+            .map(|c| (c.id.clone(), c))
+            .collect();
+        let mut attributes: HashMap<String, (String, ExtractedAttributes)> = self
+            .repository
+            .attributes_with_ids(namespace, repository, &ids_of(ChangeKind::Attributes))
+            .await?
+            .into_iter()
+            .map(|(index, a)| (a.id.clone(), (index, a)))
+            .collect();
+        let mut chunk_ids: HashMap<String, Vec<String>> = HashMap::new();
+        for change in entries.iter().filter(|c| c.kind == ChangeKind::Chunk) {
+            if let Some(index) = &change.index_name {
+                chunk_ids
+                    .entry(index.clone())
+                    .or_default()
+                    .push(change.object_id.clone());
+            }
+        }
+        let indexes: HashSet<String> = self
+            .repository
+            .list_indexes(namespace, repository)
+            .await
+            .map_err(|e| DataRepositoryError::Replication(e.to_string()))?
+            .into_iter()
+            .map(|index| index.name)
+            .collect();
+        let mut chunks: HashMap<(String, String), Chunk> = HashMap::new();
+        for (index, ids) in chunk_ids {
+            // The index was dropped since the chunks were added.
+            if !indexes.contains(&index) {
+                continue;
+            }
+            let snapshot = self
+                .repository
+                .index_snapshot(namespace, &index, repository)
+                .await
+                .map_err(|e| DataRepositoryError::Replication(e.to_string()))?;
+            for chunk in self
+                .repository
+                .chunks_with_ids(namespace, &snapshot, &ids)
+                .await?
+            {
+                chunks.insert((index.clone(), chunk.chunk_id.clone()), chunk);
+            }
+        }
+
+        let mut changes = Vec::new();
+        for entry in entries {
+            let change = match entry.kind {
+                ChangeKind::Content => match content.remove(&entry.object_id) {
+                    Some(payload) => Some(RepositoryChange::Content(
+                        self.with_inline_bytes(payload).await?,
+                    )),
+                    None => None,
+                },
+                ChangeKind::Chunk => entry.index_name.and_then(|index| {
+                    chunks
+                        .remove(&(index.clone(), entry.object_id))
+                        .map(|chunk| RepositoryChange::Chunk { index, chunk })
+                }),
+                ChangeKind::Attributes => attributes
+                    .remove(&entry.object_id)
+                    .map(|(index, attributes)| RepositoryChange::Attributes { index, attributes }),
+            };
+            changes.extend(change);
+        }
+        Ok(ChangeBatch {
+            changes,
+            cursor,
+            has_more,
+        })
+    }
+
+    /// Content kept in the blob storage, read into an inline payload so that
+    /// it can be sent to another deployment.
+    async fn with_inline_bytes(
+        &self,
+        payload: ContentPayload,
+    ) -> Result<ContentPayload, DataRepositoryError> {
+        let PayloadType::BlobStorageLink = payload.payload_type else {
+            return Ok(payload);
+        };
+        let reader_payload = internal_api::ContentPayload::try_from(payload.clone())
+            .map_err(|e| DataRepositoryError::ContentRead(e.to_string()))?;
+        let data = ContentReader::new(reader_payload)
+            .read()
+            .await
+            .map_err(|e| DataRepositoryError::ContentRead(e.to_string()))?;
+        Ok(ContentPayload {
+            payload: BASE64_STANDARD.encode(data),
+            payload_type: PayloadType::InlineBlob,
+            ..payload
+        })
+    }
+
+    /// Applies changes read from the change feed of the repository in another
+    /// deployment. The repository has to have the indexes of the chunks and
+    /// the attributes. Content is not extracted again by the bindings of the
+    /// repository, and the chunks are embedded into the vector indexes only
+    /// when `embed_chunks` is set.
+    #[tracing::instrument(skip(changes))]
+    pub async fn apply_changes(
+        &self,
+        namespace: &str,
+        repository: &str,
+        changes: Vec<RepositoryChange>,
+        embed_chunks: bool,
+    ) -> Result<(), DataRepositoryError> {
+        let data_repository = self
+            .repository
+            .repository_by_name(namespace, repository)
+            .await?;
+        let bindings: Vec<String> = data_repository
+            .extractor_bindings
+            .iter()
+            .map(|b| b.name.clone())
+            .collect();
+        let mut content = Vec::new();
+        let mut chunks: HashMap<String, Vec<Chunk>> = HashMap::new();
+        let mut attributes = Vec::new();
+        for change in changes {
+            match change {
+                RepositoryChange::Content(payload) => {
+                    content.push(self.with_stored_bytes(payload).await?)
+                }
+                RepositoryChange::Chunk { index, chunk } => {
+                    chunks.entry(index).or_default().push(chunk)
+                }
+                RepositoryChange::Attributes {
+                    index,
+                    attributes: a,
+                } => attributes.push((index, a)),
+            }
+        }
+        self.repository
+            .replicate_content(namespace, repository, content, &bindings)
             .await?;
+        for (index, chunks) in chunks {
+            self.vector_index_manager
+                .replicate_chunks(namespace, repository, &index, chunks, embed_chunks)
+                .await
+                .map_err(|e| DataRepositoryError::Replication(e.to_string()))?;
+        }
+        for (index, a) in attributes {
+            self.repository
+                .add_attributes(namespace, repository, &index, a)
+                .await?;
+        }
         Ok(())
     }
+
+    /// Replicated content whose inline bytes are above the inline threshold,
+    /// moved to the blob storage.
+    async fn with_stored_bytes(
+        &self,
+        payload: ContentPayload,
+    ) -> Result<ContentPayload, DataRepositoryError> {
+        let PayloadType::InlineBlob = payload.payload_type else {
+            return Ok(payload);
+        };
+        let data = BASE64_STANDARD
+            .decode(&payload.payload)
+            .map_err(|e| DataRepositoryError::ContentWrite(e.to_string()))?;
+        if data.len() < self.inline_threshold_bytes {
+            return Ok(payload);
+        }
+        let path = self
+            .blob_storage
+            .put(&payload.id, Bytes::from(data))
+            .await
+            .map_err(|e| DataRepositoryError::ContentWrite(e.to_string()))?;
+        Ok(ContentPayload {
+            payload: path,
+            payload_type: PayloadType::BlobStorageLink,
+            ..payload
+        })
+    }
+
+    /// Unpacks an archive and adds every file in it as content of the
+    /// repository. The files carry their `path` in the archive, and the name
+    /// and id of the `archive` they were unpacked from. Returns the ids of the
+    /// content which was added.
+    #[tracing::instrument(skip(file, config))]
+    pub async fn upload_archive(
+        &self,
+        namespace: &str,
+        repository: &str,
+        name: &str,
+        file: Bytes,
+        config: &ArchiveConfig,
+    ) -> Result<Vec<String>, anyhow::Error> {
+        let format = ArchiveFormat::from_file_name(name)
+            .ok_or(anyhow!("{} is not a zip or tar archive", name))?;
+        let policy = self
+            .repository
+            .repository_by_name(namespace, repository)
+            .await?
+            .dedup_policy;
+        // Groups the files of the archive. With the hash id strategy it is the
+        // id the archive would have if it was uploaded as a single file, and
+        // every upload has an id of its own when duplicates are allowed.
+        let archive_id = with_policy_ids(
+            policy,
+            vec![ContentPayload::from_file(repository, name, "")],
+        )
+        .remove(0)
+        .id;
+        let config = config.clone();
+        let entries =
+            tokio::task::spawn_blocking(move || archive::unpack(format, &file, &config)).await??;
+        info!(
+            "unpacked {} files from archive {} into repository {}",
+            entries.len(),
+            name,
+            repository
+        );
+        let mut content = Vec::new();
+        for entry in entries {
+            let key = format!("{}-{}", archive_id, entry.path.replace('/', "_"));
+            let mut payload = self
+                .store_file(
+                    repository,
+                    &format!("{}/{}", name, entry.path),
+                    &key,
+                    entry.data,
+                )
+                .await?
+                .with_source("archive");
+            payload.metadata = HashMap::from([
+                ("path".to_string(), json!(entry.path)),
+                ("archive".to_string(), json!(name)),
+                ("archive_id".to_string(), json!(archive_id)),
+            ]);
+            content.push(payload);
+        }
+        let content = with_policy_ids(policy, content);
+        let content_ids = content.iter().map(|c| c.id.clone()).collect();
+        self.repository
+            .add_content(namespace, repository, content, policy)
+            .await?;
+        Ok(content_ids)
+    }
 }
 
 #[cfg(test)]
@@ -327,11 +2796,12 @@ mod tests {
     use std::collections::HashMap;
 
     use serde_json::json;
+    use tokio_stream::StreamExt;
 
     use super::*;
     use crate::{
         blob_storage::BlobStorageBuilder,
-        persistence::{DataConnector, Event, ExtractorBinding, SourceType},
+        persistence::{DataConnector, Event, ExtractorBinding, PayloadType, SourceType},
         test_util,
         test_util::db_utils::{DEFAULT_TEST_EXTRACTOR, DEFAULT_TEST_REPOSITORY},
     };
@@ -348,6 +2818,7 @@ mod tests {
         let mut meta = HashMap::new();
         meta.insert("foo".to_string(), json!(12));
         let repository = DataRepository {
+            namespace: DEFAULT_NAMESPACE.into(),
             name: "test".to_string(),
             extractor_bindings: vec![ExtractorBinding::new(
                 "test_extractor_binding",
@@ -357,19 +2828,240 @@ mod tests {
                 serde_json::json!({}),
             )],
             metadata: meta.clone(),
-            data_connectors: vec![DataConnector {
-                source: SourceType::GoogleContact {
-                    metadata: Some("data_connector_meta".to_string()),
-                },
-            }],
+            dedup_policy: DedupPolicy::default(),
+            retention: RetentionPolicy::default(),
+            data_connectors: vec![DataConnector::new(SourceType::GoogleContact {
+                metadata: Some("data_connector_meta".to_string()),
+            })],
+            text_analysis: TextAnalysisConfig {
+                stop_words: vec!["the".to_string()],
+                synonyms: HashMap::new(),
+                language: "english".to_string(),
+            },
+            version: 0,
         };
         repository_manager.create(&repository).await.unwrap();
-        let repositories = repository_manager.list_repositories().await.unwrap();
+        let repositories = repository_manager
+            .list_repositories(DEFAULT_NAMESPACE)
+            .await
+            .unwrap();
         assert_eq!(repositories.len(), 1);
         assert_eq!(repositories[0].name, "test");
         assert_eq!(repositories[0].extractor_bindings.len(), 1);
         assert_eq!(repositories[0].data_connectors.len(), 1);
         assert_eq!(repositories[0].metadata, meta);
+        assert_eq!(repositories[0].text_analysis, repository.text_analysis);
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_pause_extractor_binding() {
+        let db = test_util::db_utils::create_db().await.unwrap();
+        let (index_manager, ..) = test_util::db_utils::create_index_manager(db.clone()).await;
+        let blob_storage =
+            BlobStorageBuilder::new_disk_storage("/tmp/indexify_test".to_string()).unwrap();
+        let repository_manager =
+            DataRepositoryManager::new_with_db(db.clone(), index_manager, blob_storage);
+        repository_manager
+            .create(&test_util::db_utils::default_test_data_repository())
+            .await
+            .unwrap();
+
+        let paused = repository_manager
+            .set_extractor_binding_disabled(
+                DEFAULT_NAMESPACE,
+                DEFAULT_TEST_REPOSITORY,
+                "test_extractor_binding",
+                true,
+            )
+            .await
+            .unwrap();
+        assert!(paused.disabled);
+        let repository = repository_manager
+            .get(DEFAULT_NAMESPACE, DEFAULT_TEST_REPOSITORY)
+            .await
+            .unwrap();
+        assert!(repository.extractor_bindings[0].disabled);
+
+        let resumed = repository_manager
+            .set_extractor_binding_disabled(
+                DEFAULT_NAMESPACE,
+                DEFAULT_TEST_REPOSITORY,
+                "test_extractor_binding",
+                false,
+            )
+            .await
+            .unwrap();
+        assert!(!resumed.disabled);
+
+        assert!(matches!(
+            repository_manager
+                .set_extractor_binding_disabled(
+                    DEFAULT_NAMESPACE,
+                    DEFAULT_TEST_REPOSITORY,
+                    "missing",
+                    true
+                )
+                .await,
+            Err(DataRepositoryError::ExtractorBindingNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_upload_file_storage_tiers() {
+        let db = test_util::db_utils::create_db().await.unwrap();
+        let (index_manager, ..) = test_util::db_utils::create_index_manager(db.clone()).await;
+        let blob_storage =
+            BlobStorageBuilder::new_disk_storage("/tmp/indexify_test".to_string()).unwrap();
+        let repository_manager =
+            DataRepositoryManager::new_with_db(db.clone(), index_manager, blob_storage)
+                .with_inline_threshold(16);
+        repository_manager
+            .create(&test_util::db_utils::default_test_data_repository())
+            .await
+            .unwrap();
+
+        let small = Bytes::from_static(&[0, 159, 146, 150]);
+        let large = Bytes::from("a file which is larger than the inline threshold");
+        for (name, data) in [("small.bin", &small), ("large.txt", &large)] {
+            repository_manager
+                .upload_file(
+                    DEFAULT_NAMESPACE,
+                    DEFAULT_TEST_REPOSITORY,
+                    name,
+                    data.clone(),
+                )
+                .await
+                .unwrap();
+        }
+
+        let content = repository_manager
+            .list_content(
+                DEFAULT_NAMESPACE,
+                DEFAULT_TEST_REPOSITORY,
+                None,
+                &ProvenanceFilter::default(),
+                None,
+                10,
+            )
+            .await
+            .unwrap();
+        for (content_type, data, payload_type) in [
+            (
+                mime::APPLICATION_OCTET_STREAM,
+                &small,
+                PayloadType::InlineBlob,
+            ),
+            (mime::TEXT_PLAIN, &large, PayloadType::BlobStorageLink),
+        ] {
+            let payload = content
+                .iter()
+                .find(|c| c.content_type == content_type)
+                .unwrap();
+            assert_eq!(payload.payload_type.to_string(), payload_type.to_string());
+            let (_, read) = repository_manager
+                .read_content(DEFAULT_NAMESPACE, DEFAULT_TEST_REPOSITORY, &payload.id)
+                .await
+                .unwrap();
+            assert_eq!(read, data.to_vec());
+
+            let download = repository_manager
+                .download_content(
+                    DEFAULT_NAMESPACE,
+                    DEFAULT_TEST_REPOSITORY,
+                    &payload.id,
+                    Some("bytes=1-2"),
+                )
+                .await
+                .unwrap();
+            assert_eq!(download.content_type, content_type.to_string());
+            assert_eq!(download.size, data.len() as u64);
+            assert_eq!(download.range, Some(1..3));
+            let body: Vec<Bytes> = download.body.map(|bytes| bytes.unwrap()).collect().await;
+            assert_eq!(body.concat(), data[1..3].to_vec());
+
+            assert!(matches!(
+                repository_manager
+                    .download_content(
+                        DEFAULT_NAMESPACE,
+                        DEFAULT_TEST_REPOSITORY,
+                        &payload.id,
+                        Some("bytes=100-"),
+                    )
+                    .await,
+                Err(DataRepositoryError::RangeNotSatisfiable(_, size)) if size == data.len() as u64
+            ));
+        }
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_ingest_events_with_content() {
+        let db = test_util::db_utils::create_db().await.unwrap();
+        let (index_manager, ..) = test_util::db_utils::create_index_manager(db.clone()).await;
+        let blob_storage =
+            BlobStorageBuilder::new_disk_storage("/tmp/indexify_test".to_string()).unwrap();
+        let repository_manager =
+            DataRepositoryManager::new_with_db(db.clone(), index_manager, blob_storage)
+                .with_inline_threshold(1024);
+        repository_manager
+            .create(&test_util::db_utils::default_test_data_repository())
+            .await
+            .unwrap();
+
+        let ingestion = Ingestion {
+            session_id: Some("session-1".into()),
+            metadata: HashMap::from([("user".to_string(), json!("alice"))]),
+            events: vec![Event::new(
+                "summarize the attached notes",
+                None,
+                HashMap::from([("user".to_string(), json!("bob"))]),
+            )],
+            texts: vec![],
+            files: vec![IngestedFile {
+                name: "notes.txt".into(),
+                data: Bytes::from("the notes"),
+                metadata: HashMap::new(),
+            }],
+        };
+        let (event_ids, content_ids) = repository_manager
+            .ingest(DEFAULT_NAMESPACE, DEFAULT_TEST_REPOSITORY, ingestion)
+            .await
+            .unwrap();
+        assert_eq!(event_ids.len(), 1);
+        assert_eq!(content_ids.len(), 1);
+
+        let events = repository_manager
+            .list_events(DEFAULT_NAMESPACE, DEFAULT_TEST_REPOSITORY)
+            .await
+            .unwrap();
+        assert_eq!(events[0].metadata["session_id"], json!("session-1"));
+        assert_eq!(events[0].metadata["user"], json!("bob"));
+        let content = repository_manager
+            .list_content(
+                DEFAULT_NAMESPACE,
+                DEFAULT_TEST_REPOSITORY,
+                None,
+                &ProvenanceFilter::default(),
+                None,
+                10,
+            )
+            .await
+            .unwrap();
+        assert_eq!(content[0].id, content_ids[0]);
+        assert_eq!(content[0].metadata["session_id"], json!("session-1"));
+        assert_eq!(content[0].metadata["user"], json!("alice"));
+
+        let result = repository_manager
+            .ingest(DEFAULT_NAMESPACE, "missing", Ingestion::default())
+            .await;
+        assert!(matches!(
+            result,
+            Err(DataRepositoryError::Persistence(
+                RepositoryError::RepositoryNotFound(_)
+            ))
+        ));
     }
 
     #[tokio::test]
@@ -401,12 +3093,12 @@ mod tests {
 
         info!("adding messages to session");
         repository_manager
-            .add_events(DEFAULT_TEST_REPOSITORY, messages.clone())
+            .add_events(DEFAULT_NAMESPACE, DEFAULT_TEST_REPOSITORY, messages.clone())
             .await
             .unwrap();
 
         let retrieve_result = repository_manager
-            .list_events(DEFAULT_TEST_REPOSITORY)
+            .list_events(DEFAULT_NAMESPACE, DEFAULT_TEST_REPOSITORY)
             .await
             .unwrap();
         assert_eq!(retrieve_result.len(), 3);
@@ -424,4 +3116,116 @@ mod tests {
         //    .unwrap();
         //assert_eq!(search_results.len(), 2);
     }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_add_texts_inline_queues_failed_extraction() {
+        let db = test_util::db_utils::create_db().await.unwrap();
+        let (index_manager, ..) = test_util::db_utils::create_index_manager(db.clone()).await;
+        let blob_storage =
+            BlobStorageBuilder::new_disk_storage("/tmp/indexify_test".to_string()).unwrap();
+        // Nothing listens on the coordinator address, so the extraction fails.
+        let repository_manager =
+            DataRepositoryManager::new_with_db(db.clone(), index_manager, blob_storage)
+                .with_inline_extraction(
+                    "127.0.0.1:1",
+                    InlineExtractionConfig {
+                        extractors: vec![DEFAULT_TEST_EXTRACTOR.into()],
+                        max_text_bytes: 16,
+                        timeout_secs: 5,
+                    },
+                );
+        let data_repository = test_util::db_utils::default_test_data_repository();
+        repository_manager.create(&data_repository).await.unwrap();
+
+        let too_large = ContentPayload::from_text(
+            DEFAULT_TEST_REPOSITORY,
+            "this text is too large",
+            HashMap::new(),
+        );
+        assert!(repository_manager
+            .add_texts_inline(DEFAULT_NAMESPACE, DEFAULT_TEST_REPOSITORY, vec![too_large])
+            .await
+            .is_err());
+
+        let text = ContentPayload::from_text(DEFAULT_TEST_REPOSITORY, "hello", HashMap::new());
+        let extractions = repository_manager
+            .add_texts_inline(
+                DEFAULT_NAMESPACE,
+                DEFAULT_TEST_REPOSITORY,
+                vec![text.clone()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(extractions.len(), 1);
+        assert_eq!(extractions[0].content_id, text.id);
+        assert_eq!(extractions[0].extractor_binding, "test_extractor_binding");
+        assert!(extractions[0].error.is_some());
+
+        // The binding is left to the executors, without the coordinator
+        // creating work for the content again.
+        let work = repository_manager
+            .repository
+            .unallocated_work()
+            .await
+            .unwrap();
+        assert_eq!(work.len(), 1);
+        assert_eq!(work[0].content_id, text.id);
+        let unapplied = repository_manager
+            .repository
+            .content_with_unapplied_extractor(
+                DEFAULT_NAMESPACE,
+                DEFAULT_TEST_REPOSITORY,
+                &data_repository.extractor_bindings[0],
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(unapplied.is_empty());
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_search_with_attribute_prefilter() {
+        let db = test_util::db_utils::create_db().await.unwrap();
+        let (index_manager, ..) = test_util::db_utils::create_index_manager(db.clone()).await;
+        let blob_storage =
+            BlobStorageBuilder::new_disk_storage("/tmp/indexify_test".to_string()).unwrap();
+        let repository_manager =
+            DataRepositoryManager::new_with_db(db.clone(), index_manager, blob_storage);
+        repository_manager
+            .repository
+            .add_attributes(
+                DEFAULT_NAMESPACE,
+                DEFAULT_TEST_REPOSITORY,
+                "invoices",
+                ExtractedAttributes::new("a", json!({"price": 250}), "invoice_extractor"),
+            )
+            .await
+            .unwrap();
+        let query = SearchQuery::Text("hello".into());
+        let search = |filter: &str| {
+            repository_manager.search(
+                DEFAULT_NAMESPACE,
+                DEFAULT_TEST_REPOSITORY,
+                "embeddings",
+                &query,
+                10,
+                Some(AttributePrefilter {
+                    index: "invoices".into(),
+                    filter: Some(Filter::parse(filter).unwrap()),
+                }),
+                HashMap::new(),
+                None,
+                ResultLimits::default(),
+            )
+        };
+
+        // No content matches, so there is nothing to search, and the query
+        // is not embedded.
+        assert!(search("attributes.price > 1000").await.unwrap().is_empty());
+        // The chunks of the matching content are searched, in an index which
+        // does not exist.
+        assert!(search("attributes.price > 100").await.is_err());
+    }
 }
@@ -0,0 +1,194 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
+
+use crate::{persistence::Repository, server_config::EmbeddingCacheConfig};
+
+/// The key of the embedding of a text by an extractor run with the
+/// parameters. Running an extractor without parameters is the same as running
+/// it with an empty object of parameters, the extractor applies its defaults
+/// in both cases.
+pub fn cache_key(extractor: &str, params: Option<&serde_json::Value>, text: &str) -> String {
+    let params = params
+        .filter(|p| !p.is_null() && p.as_object().is_none_or(|o| !o.is_empty()))
+        .map(|p| p.to_string())
+        .unwrap_or_else(|| "{}".into());
+    let mut hasher = Sha256::new();
+    hasher.update(extractor.as_bytes());
+    hasher.update([0]);
+    hasher.update(params.as_bytes());
+    hasher.update([0]);
+    hasher.update(text.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// The lookups of the cache since the server started, and the embeddings it
+/// holds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddingCacheStats {
+    pub enabled: bool,
+    pub entries: u64,
+    pub max_entries: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+/// Embeddings computed by extractors, so that embedding the same text again
+/// with the same extractor and parameters does not run the extractor. The
+/// cache is kept in the database and shared by the servers and the
+/// coordinator, and the embeddings used least recently are evicted
+/// periodically once there are more than `max_entries`.
+pub struct EmbeddingCache {
+    repository: Arc<Repository>,
+    config: EmbeddingCacheConfig,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl fmt::Debug for EmbeddingCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmbeddingCache").finish()
+    }
+}
+
+impl EmbeddingCache {
+    pub fn new(repository: Arc<Repository>, config: EmbeddingCacheConfig) -> Self {
+        Self {
+            repository,
+            config,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// The cached embedding of the text. The cache is an optimization, a
+    /// lookup which fails is counted as a miss.
+    pub async fn get(
+        &self,
+        extractor: &str,
+        params: Option<&serde_json::Value>,
+        text: &str,
+    ) -> Option<Vec<f32>> {
+        if !self.config.enabled {
+            return None;
+        }
+        let key = cache_key(extractor, params, text);
+        let embedding = self
+            .repository
+            .cached_embedding(&key, now())
+            .await
+            .unwrap_or_else(|err| {
+                error!("unable to look up cached embedding: {}", err.to_string());
+                None
+            });
+        let counter = match embedding {
+            Some(_) => &self.hits,
+            None => &self.misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        embedding
+    }
+
+    /// Caches the embeddings of texts computed by the extractor.
+    pub async fn put(
+        &self,
+        extractor: &str,
+        params: Option<&serde_json::Value>,
+        embeddings: Vec<(&str, Vec<f32>)>,
+    ) {
+        if !self.config.enabled || embeddings.is_empty() {
+            return;
+        }
+        let embeddings = embeddings
+            .into_iter()
+            .map(|(text, embedding)| {
+                (
+                    cache_key(extractor, params, text),
+                    extractor.to_string(),
+                    embedding,
+                )
+            })
+            .collect();
+        if let Err(err) = self.repository.cache_embeddings(embeddings, now()).await {
+            error!("unable to cache embeddings: {}", err.to_string());
+        }
+    }
+
+    pub async fn stats(&self) -> Result<EmbeddingCacheStats> {
+        Ok(EmbeddingCacheStats {
+            enabled: self.config.enabled,
+            entries: self.repository.embedding_cache_size().await?,
+            max_entries: self.config.max_entries,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        })
+    }
+
+    pub async fn start(self: Arc<Self>) {
+        if !self.config.enabled {
+            return;
+        }
+        let mut interval = tokio::time::interval(Duration::from_secs(
+            self.config.eviction_interval_secs.max(1),
+        ));
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.evict().await {
+                error!("unable to evict cached embeddings: {}", err.to_string());
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn evict(&self) -> Result<()> {
+        let evicted = self
+            .repository
+            .evict_cached_embeddings(self.config.max_entries)
+            .await?;
+        if evicted > 0 {
+            self.evictions.fetch_add(evicted, Ordering::Relaxed);
+            info!("evicted {} cached embeddings", evicted);
+        }
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_cache_key() {
+        let key = cache_key("minilm", None, "hello");
+        assert_eq!(key, cache_key("minilm", Some(&json!({})), "hello"));
+        assert_eq!(key, cache_key("minilm", Some(&json!(null)), "hello"));
+        assert_ne!(key, cache_key("minilm", None, "hello "));
+        assert_ne!(key, cache_key("mpnet", None, "hello"));
+        assert_ne!(
+            key,
+            cache_key("minilm", Some(&json!({"normalize": false})), "hello")
+        );
+    }
+}
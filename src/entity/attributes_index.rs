@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "attributes_index")]
 pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub namespace: String,
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: String,
     pub repository_id: String,
@@ -15,6 +17,7 @@ pub struct Model {
     pub data: Json,
     pub content_id: String,
     pub created_at: i64,
+    pub schema_version: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -6,12 +6,26 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "chunked_content")]
 pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub namespace: String,
     #[sea_orm(primary_key, auto_increment = false)]
     pub chunk_id: String,
     pub content_id: String,
     #[sea_orm(column_type = "Text")]
     pub text: String,
+    #[sea_orm(primary_key, auto_increment = false)]
     pub index_name: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub generation: i64,
+    pub parent_id: Option<String>,
+    pub is_parent: bool,
+    pub start_offset: Option<i64>,
+    pub end_offset: Option<i64>,
+    pub page: Option<i32>,
+    pub start_ms: Option<i64>,
+    pub end_ms: Option<i64>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub region: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "content")]
 pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub namespace: String,
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: String,
     #[sea_orm(column_type = "Text")]
@@ -17,6 +19,17 @@ pub struct Model {
     pub repository_id: String,
     #[sea_orm(column_type = "JsonBinary", nullable)]
     pub extractor_bindings_state: Option<Json>,
+    pub source: String,
+    pub created_at: i64,
+    pub deleted_at: Option<i64>,
+    pub parent_content_id: Option<String>,
+    pub produced_by_binding: Option<String>,
+    pub source_connector: Option<String>,
+    pub external_id: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub source_url: Option<String>,
+    pub fetched_at: Option<i64>,
+    pub content_hash: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "data_repository")]
 pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub namespace: String,
     #[sea_orm(primary_key, auto_increment = false)]
     pub name: String,
     #[sea_orm(column_type = "JsonBinary", nullable)]
@@ -14,6 +16,12 @@ pub struct Model {
     pub metadata: Option<Json>,
     #[sea_orm(column_type = "JsonBinary", nullable)]
     pub data_connectors: Option<Json>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub text_analysis: Option<Json>,
+    pub dedup_policy: String,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub retention: Option<Json>,
+    pub version: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -8,11 +8,15 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: String,
+    pub namespace: String,
     pub repository_id: String,
     pub message: String,
     pub unix_time_stamp: i64,
     #[sea_orm(column_type = "JsonBinary", nullable)]
     pub metadata: Option<Json>,
+    pub session_id: Option<String>,
+    pub embedded: bool,
+    pub summarized: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
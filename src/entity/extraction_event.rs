@@ -13,6 +13,8 @@ pub struct Model {
     #[sea_orm(column_type = "JsonBinary", nullable)]
     pub allocation_info: Option<Json>,
     pub processed_at: Option<i64>,
+    pub seq: i64,
+    pub tx_id: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "index")]
 pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub namespace: String,
     #[sea_orm(primary_key, auto_increment = false)]
     pub name: String,
     pub vector_index_name: Option<String>,
@@ -13,7 +15,12 @@ pub struct Model {
     pub index_type: String,
     #[sea_orm(column_type = "JsonBinary")]
     pub index_schema: Json,
+    #[sea_orm(primary_key, auto_increment = false)]
     pub repository_id: String,
+    pub generation: i64,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub reranker: Option<Json>,
+    pub schema_version: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
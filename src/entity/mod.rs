@@ -2,12 +2,31 @@
 
 pub mod prelude;
 
+pub mod api_keys;
 pub mod attributes_index;
+pub mod audit_log;
+pub mod change_log;
 pub mod chunked_content;
+pub mod connector_state;
+pub mod connector_sync_runs;
 pub mod content;
 pub mod data_repository;
+pub mod embedding_cache;
+pub mod event_consumers;
 pub mod events;
 pub mod extraction_event;
 pub mod extractors;
+pub mod graph_edges;
+pub mod graph_nodes;
+pub mod idempotency_keys;
 pub mod index;
+pub mod ingestion_job_errors;
+pub mod ingestion_jobs;
+pub mod legal_holds;
+pub mod quarantined_attributes;
+pub mod repository_dashboards;
+pub mod repository_roles;
+pub mod repository_templates;
+pub mod summaries;
+pub mod sync_checkpoints;
 pub mod work;
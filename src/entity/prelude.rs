@@ -1,13 +1,32 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.6
 
 pub use super::{
+    api_keys::Entity as ApiKeys,
     attributes_index::Entity as AttributesIndex,
+    audit_log::Entity as AuditLog,
+    change_log::Entity as ChangeLog,
     chunked_content::Entity as ChunkedContent,
+    connector_state::Entity as ConnectorState,
+    connector_sync_runs::Entity as ConnectorSyncRuns,
     content::Entity as Content,
     data_repository::Entity as DataRepository,
+    embedding_cache::Entity as EmbeddingCache,
+    event_consumers::Entity as EventConsumers,
     events::Entity as Events,
     extraction_event::Entity as ExtractionEvent,
     extractors::Entity as Extractors,
+    graph_edges::Entity as GraphEdges,
+    graph_nodes::Entity as GraphNodes,
+    idempotency_keys::Entity as IdempotencyKeys,
     index::Entity as Index,
+    ingestion_job_errors::Entity as IngestionJobErrors,
+    ingestion_jobs::Entity as IngestionJobs,
+    legal_holds::Entity as LegalHolds,
+    quarantined_attributes::Entity as QuarantinedAttributes,
+    repository_dashboards::Entity as RepositoryDashboards,
+    repository_roles::Entity as RepositoryRoles,
+    repository_templates::Entity as RepositoryTemplates,
+    summaries::Entity as Summaries,
+    sync_checkpoints::Entity as SyncCheckpoints,
     work::Entity as Work,
 };
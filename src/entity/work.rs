@@ -3,11 +3,12 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "work")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: String,
+    pub namespace: String,
     pub state: String,
     pub worker_id: Option<String>,
     pub content_id: String,
@@ -16,6 +17,18 @@ pub struct Model {
     #[sea_orm(column_type = "JsonBinary")]
     pub extractor_params: Json,
     pub repository_id: String,
+    pub source: String,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub trace_context: Option<Json>,
+    pub created_at: i64,
+    pub assigned_at: Option<i64>,
+    pub completed_at: Option<i64>,
+    pub items_processed: i64,
+    pub percent: Option<f64>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub log: Option<Json>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_error: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
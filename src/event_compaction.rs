@@ -0,0 +1,74 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use tracing::{error, info};
+
+use crate::{persistence::Repository, server_config::EventCompactionConfig};
+
+/// Deletes or archives the extraction events processed longer ago than the
+/// retention, so that the events the coordinator dispatches from are not
+/// slowed down by the history of the deployment. Processed events which are
+/// compacted can no longer be replayed.
+pub struct EventCompaction {
+    repository: Arc<Repository>,
+    config: EventCompactionConfig,
+}
+
+impl EventCompaction {
+    pub fn new(repository: Arc<Repository>, config: EventCompactionConfig) -> Self {
+        Self { repository, config }
+    }
+
+    pub async fn start(self: Arc<Self>) {
+        if !self.config.enabled {
+            return;
+        }
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.compact().await {
+                error!("unable to compact extraction events: {}", err.to_string());
+            }
+        }
+    }
+
+    /// Compacts the expired events batch by batch, until a batch comes up
+    /// short.
+    #[tracing::instrument(skip(self))]
+    async fn compact(&self) -> Result<u64> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let processed_before = now.saturating_sub(self.config.retention_secs);
+        let batch_size = self.config.batch_size.max(1);
+        let mut compacted = 0;
+        loop {
+            let batch = self
+                .repository
+                .compact_extraction_events(processed_before, batch_size, self.config.archive)
+                .await?;
+            compacted += batch;
+            if batch < batch_size {
+                break;
+            }
+        }
+        if compacted > 0 {
+            info!(
+                "{} {} extraction events processed before {}",
+                if self.config.archive {
+                    "archived"
+                } else {
+                    "deleted"
+                },
+                compacted,
+                processed_before
+            );
+        }
+        Ok(compacted)
+    }
+}
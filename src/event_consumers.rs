@@ -0,0 +1,178 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::Serialize;
+use tracing::{error, info};
+
+use crate::{
+    coordinator::Coordinator,
+    persistence::{ExtractionEvent, Repository},
+    server_config::{EventConsumersConfig, EventWebhookConfig},
+};
+
+/// Handles the extraction events given to an event consumer.
+#[async_trait]
+pub trait EventHandler {
+    /// Handles events in the order they were added. Events are given again
+    /// when this fails, and can be given again after a restart, so handling
+    /// them has to be idempotent.
+    async fn handle(&self, events: &[ExtractionEvent]) -> Result<()>;
+}
+
+pub type EventHandlerTS = Arc<dyn EventHandler + Sync + Send>;
+
+#[derive(Debug, Serialize)]
+struct EventDelivery<'a> {
+    consumer: &'a str,
+    events: &'a [ExtractionEvent],
+}
+
+/// Posts the events to a url.
+pub struct WebhookEventHandler {
+    name: String,
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookEventHandler {
+    pub fn new(config: &EventWebhookConfig) -> Self {
+        Self {
+            name: config.name.clone(),
+            url: config.url.clone(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl EventHandler for WebhookEventHandler {
+    #[tracing::instrument(skip(self, events))]
+    async fn handle(&self, events: &[ExtractionEvent]) -> Result<()> {
+        let resp = self
+            .client
+            .post(&self.url)
+            .json(&EventDelivery {
+                consumer: &self.name,
+                events,
+            })
+            .send()
+            .await
+            .map_err(|e| anyhow!("unable to post to webhook {}: {}", self.url, e))?;
+        if !resp.status().is_success() {
+            return Err(anyhow!(
+                "webhook {} responded with status: {}",
+                self.url,
+                resp.status()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Gives the extraction events to consumers which each track the events
+/// they have handled with an offset of their own, so that subsystems can
+/// react to the content added without taking the events from the extraction
+/// of the content. A consumer acknowledges the events it handled, and is
+/// given the events after them until it does, so events are handled at least
+/// once. Consumers are run by the leader of the coordinators.
+pub struct EventConsumers {
+    repository: Arc<Repository>,
+    coordinator: Arc<Coordinator>,
+    config: EventConsumersConfig,
+    consumers: Vec<(String, EventHandlerTS)>,
+}
+
+impl EventConsumers {
+    pub fn new(
+        repository: Arc<Repository>,
+        coordinator: Arc<Coordinator>,
+        config: EventConsumersConfig,
+    ) -> Self {
+        let consumers = config
+            .webhooks
+            .iter()
+            .map(|webhook| {
+                let handler: EventHandlerTS = Arc::new(WebhookEventHandler::new(webhook));
+                (webhook.name.clone(), handler)
+            })
+            .collect();
+        Self {
+            repository,
+            coordinator,
+            config,
+            consumers,
+        }
+    }
+
+    /// Registers the consumers, forgetting the ones which are no longer
+    /// configured, and runs them.
+    pub async fn start(self) -> Result<()> {
+        let names: Vec<String> = self
+            .consumers
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect();
+        let forgotten = self
+            .repository
+            .forget_event_consumers_except(&names)
+            .await?;
+        if forgotten > 0 {
+            info!(
+                "forgot {} event consumers which are not configured",
+                forgotten
+            );
+        }
+        let this = Arc::new(self);
+        for (name, handler) in &this.consumers {
+            this.repository
+                .register_event_consumer(name, this.config.start_from_earliest)
+                .await?;
+            tokio::spawn(this.clone().consume(name.clone(), handler.clone()));
+        }
+        Ok(())
+    }
+
+    async fn consume(self: Arc<Self>, name: String, handler: EventHandlerTS) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if !self.coordinator.is_leader() {
+                continue;
+            }
+            // Batches are handled until the consumer catches up.
+            loop {
+                match self.consume_batch(&name, &handler).await {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(err) => {
+                        error!(
+                            "event consumer {} is unable to handle events: {}",
+                            name,
+                            err.to_string()
+                        );
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Handles the next batch of events of the consumer and acknowledges
+    /// them. Returns whether the batch was full.
+    async fn consume_batch(&self, name: &str, handler: &EventHandlerTS) -> Result<bool> {
+        let batch_size = self.config.batch_size.max(1);
+        let consumed = self
+            .repository
+            .events_for_consumer(name, batch_size)
+            .await?;
+        let Some(last_seq) = consumed.last().map(|c| c.seq) else {
+            return Ok(false);
+        };
+        let events: Vec<ExtractionEvent> = consumed.into_iter().map(|c| c.event).collect();
+        handler.handle(&events).await?;
+        self.repository.ack_events(name, last_seq).await?;
+        Ok(events.len() as u64 == batch_size)
+    }
+}
@@ -1,12 +1,18 @@
-use std::{collections::HashMap, fmt, sync::Arc, time::SystemTime};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Arc,
+    time::{Instant, SystemTime},
+};
 
-use anyhow::{anyhow, Ok, Result};
+use anyhow::{anyhow, Result};
 use nanoid::nanoid;
 use serde_json::json;
-use tracing::{error, info};
+use tracing::{error, info, info_span, Instrument};
 
 use crate::{
     attribute_index::AttributeIndexManager,
+    chunking,
     content_reader::ContentReader,
     extractor::{self, python_path, ExtractorTS},
     internal_api::{
@@ -22,6 +28,8 @@ use crate::{
     },
     persistence::Repository,
     server_config::{ExecutorConfig, ExtractorConfig},
+    telemetry,
+    tls,
     vector_index::VectorIndexManager,
     work_store::WorkStore,
 };
@@ -60,20 +68,36 @@ impl ExtractorExecutor {
         extractor_config_path: &str,
         listen_addr: String,
     ) -> Result<Self> {
-        let executor_id = create_executor_id();
+        let work_store = match &executor_config.work_store_path {
+            Some(path) => WorkStore::open(path)?,
+            None => WorkStore::new(),
+        };
+        let executor_id = work_store.executor_id(create_executor_id)?;
+        let pending_work = work_store.pending_work().len();
+        if pending_work > 0 {
+            info!(
+                "resuming {} work assigned to executor {} before it restarted",
+                pending_work, executor_id
+            );
+        }
         let extractor_config = Arc::new(ExtractorConfig::from_path(extractor_config_path)?);
         info!("looking up extractor at path: {}", &extractor_config_path);
         python_path::set_python_path(extractor_config_path)?;
 
         let extractor =
             extractor::create_extractor(&extractor_config.module, &extractor_config.name)?;
+        #[cfg(feature = "fault-injection")]
+        let extractor = crate::fault_injection::FaultyExtractor::wrap(
+            extractor,
+            executor_config.extractor_faults.clone(),
+        );
         let extractor_executor = Self {
             executor_config,
             extractor_config,
             executor_id,
             extractor,
             listen_addr,
-            work_store: WorkStore::new(),
+            work_store,
         };
         Ok(extractor_executor)
     }
@@ -107,6 +131,8 @@ impl ExtractorExecutor {
             let extractor::EmbeddingSchema {
                 dim,
                 distance_metric,
+                multi_vector,
+                modalities,
             } = embedding_schema;
             let distance_metric = distance_metric.to_string();
             output_schemas.insert(
@@ -114,6 +140,11 @@ impl ExtractorExecutor {
                 internal_api::OutputSchema::Embedding {
                     dim,
                     distance_metric,
+                    multi_vector,
+                    modalities: modalities
+                        .iter()
+                        .filter_map(|modality| modality.parse().ok())
+                        .collect(),
                 },
             );
         }
@@ -152,41 +183,59 @@ impl ExtractorExecutor {
             addr: self.listen_addr.clone(),
             work_status: completed_work,
         };
-        let json_resp = reqwest::Client::new()
-            .post(&format!(
-                "http://{}/sync_executor",
-                &self.executor_config.coordinator_addr
+        let resp = match self.sync_executor(&sync_executor_req).await {
+            Ok(resp) => resp,
+            Err(err) => {
+                // The work the executor kept is performed while the
+                // coordinator is unreachable, and reported once it is back.
+                if let Err(err) = self.perform_work().await {
+                    error!("unable perform work: {:?}", err);
+                }
+                return Err(err);
+            }
+        };
+
+        self.work_store.clear_completed_work()?;
+
+        let dropped = self.work_store.reconcile(resp.content_to_process)?;
+        if dropped > 0 {
+            info!(
+                "dropped {} work which is no longer assigned to the executor",
+                dropped
+            );
+        }
+
+        if let Err(err) = self.perform_work().await {
+            error!("unable perform work: {:?}", err);
+            return Err(anyhow!("unable perform work: {:?}", err));
+        }
+        Ok(0)
+    }
+
+    async fn sync_executor(&self, sync_executor_req: &SyncExecutor) -> Result<SyncWorkerResponse> {
+        let json_resp = tls::internal_http_client()
+            .post(tls::internal_url(
+                &self.executor_config.coordinator_addr,
+                "/sync_executor",
             ))
-            .json(&sync_executor_req)
+            .json(sync_executor_req)
             .send()
             .await?
             .text()
             .await?;
 
-        let resp: Result<SyncWorkerResponse, serde_json::Error> = serde_json::from_str(&json_resp);
-        if let Err(err) = resp {
-            return Err(anyhow!(
+        serde_json::from_str(&json_resp).map_err(|err| {
+            anyhow!(
                 "unable to parse server response: err: {:?}, resp: {}",
                 err,
                 &json_resp
-            ));
-        }
-
-        self.work_store.clear_completed_work();
-
-        self.work_store
-            .add_work_list(resp.unwrap().content_to_process);
-
-        if let Err(err) = self.perform_work().await {
-            error!("unable perform work: {:?}", err);
-            return Err(anyhow!("unable perform work: {:?}", err));
-        }
-        Ok(0)
+            )
+        })
     }
 
     #[tracing::instrument]
     pub async fn sync_repo_test(&self, work_list: Vec<Work>) -> Result<u64, anyhow::Error> {
-        self.work_store.add_work_list(work_list);
+        self.work_store.add_work_list(work_list)?;
         if let Err(err) = self.perform_work().await {
             error!("unable perform work: {:?}", err);
             return Err(anyhow!("unable perform work: {:?}", err));
@@ -213,26 +262,99 @@ impl ExtractorExecutor {
     #[tracing::instrument(skip(self))]
     pub async fn perform_work(&self) -> Result<(), anyhow::Error> {
         let work_list: Vec<Work> = self.work_store.pending_work();
-        let mut work_status_list = Vec::new();
         for work in work_list {
-            info!("performing work: {}", &work.id);
-            let content = self
-                .create_content_from_payload(work.content_payload)
-                .await?;
+            // The work continues the trace of the content it extracts from.
+            let span = info_span!("work", id = %work.id);
+            telemetry::set_parent(&span, &work.trace_context);
+            let work_id = work.id.clone();
+            let work_status = self
+                .extract_work(work)
+                .instrument(span)
+                .await
+                // The work is reported as failed with its error, rather than
+                // holding up the rest of the work.
+                .unwrap_or_else(|err| {
+                    error!("unable to perform work {}: {:?}", &work_id, err);
+                    vec![WorkStatus {
+                        work_id,
+                        status: WorkState::Failed,
+                        extracted_content: vec![],
+                        chunk_origins: vec![],
+                        items_processed: None,
+                        percent: None,
+                        log: vec![],
+                        error: Some(err.to_string()),
+                    }]
+                });
+            // The status is kept as soon as the work is performed, so that
+            // it is not performed again if the executor restarts before the
+            // rest of the work.
+            self.work_store.update_work_status(work_status)?;
+        }
+        Ok(())
+    }
+
+    async fn extract_work(&self, work: Work) -> Result<Vec<WorkStatus>, anyhow::Error> {
+        info!("performing work: {}", &work.id);
+        let content = self
+            .create_content_from_payload(work.content_payload)
+            .await?;
+        let started_at = Instant::now();
+        let Some(chunker) = &work.chunker else {
             let extracted_content_batch =
                 self.extractor.extract(vec![content], work.params.clone())?;
-
-            for extracted_content_list in extracted_content_batch {
-                let work_status = WorkStatus {
+            return Ok(extracted_content_batch
+                .into_iter()
+                .map(|extracted_content_list| WorkStatus {
                     work_id: work.id.clone(),
                     status: WorkState::Completed,
+                    items_processed: Some(extracted_content_list.len() as u64),
+                    percent: Some(100.0),
+                    log: vec![format!(
+                        "extracted {} items in {} ms",
+                        extracted_content_list.len(),
+                        started_at.elapsed().as_millis()
+                    )],
                     extracted_content: extracted_content_list,
-                };
-                work_status_list.push(work_status);
-            }
+                    chunk_origins: vec![],
+                    error: None,
+                })
+                .collect());
+        };
+        // The chunks are extracted in one batch, and what is extracted from
+        // all of them is the output of the work, along with where the chunk
+        // it was extracted from is in the content.
+        let (chunks, origins): (Vec<_>, Vec<_>) =
+            chunking::chunk_content(chunker, work.parent_chunker.as_ref(), content)
+                .into_iter()
+                .unzip();
+        let chunk_count = chunks.len();
+        let mut extracted_content = Vec::new();
+        let mut chunk_origins = Vec::new();
+        for (extracted, origin) in self
+            .extractor
+            .extract(chunks, work.params.clone())?
+            .into_iter()
+            .zip(origins)
+        {
+            chunk_origins.extend(std::iter::repeat_n(origin, extracted.len()));
+            extracted_content.extend(extracted);
         }
-        self.work_store.update_work_status(work_status_list);
-        Ok(())
+        Ok(vec![WorkStatus {
+            work_id: work.id.clone(),
+            status: WorkState::Completed,
+            items_processed: Some(chunk_count as u64),
+            percent: Some(100.0),
+            log: vec![format!(
+                "extracted {} items from {} chunks in {} ms",
+                extracted_content.len(),
+                chunk_count,
+                started_at.elapsed().as_millis()
+            )],
+            extracted_content,
+            chunk_origins,
+            error: None,
+        }])
     }
 
     async fn create_content_from_payload(
@@ -245,6 +367,7 @@ impl ExtractorExecutor {
             content_type: content_payload.content_type,
             source: data,
             feature: None,
+            metadata: content_payload.metadata,
         };
         Ok(extracted_content)
     }
@@ -21,6 +21,7 @@ use crate::{
     executor::ExtractorExecutor,
     internal_api::{ExtractRequest, ExtractResponse},
     server_config::ExecutorConfig,
+    tls,
 };
 
 enum TickerMessage {
@@ -113,10 +114,13 @@ impl ExecutorServer {
             error!("unable to send heartbeat: {:?}", err.to_string());
         }
         tokio::spawn(heartbeat(tx.clone(), rx, executor.clone()));
-        axum::Server::from_tcp(listener)?
-            .serve(app.into_make_service())
-            .with_graceful_shutdown(shutdown_signal(tx.clone()))
-            .await?;
+        tls::serve(
+            listener,
+            app,
+            self.executor_config.tls.as_ref(),
+            shutdown_signal(tx.clone()),
+        )
+        .await?;
         Ok(())
     }
 }
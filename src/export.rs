@@ -0,0 +1,523 @@
+use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use arrow::{
+    array::{
+        ArrayRef,
+        BooleanBuilder,
+        Float64Builder,
+        Int64Builder,
+        StringBuilder,
+        UInt32Builder,
+        UInt64Builder,
+    },
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    ipc::writer::StreamWriter,
+    record_batch::RecordBatch,
+};
+use bytes::Bytes;
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::error;
+
+use crate::persistence::{Chunk, ExtractedAttributes, IndexSnapshot, MetadataSchema, Repository};
+
+/// Rows read from the database, and written as one record batch, at once.
+/// Parquet exports get a row group per batch.
+const EXPORT_BATCH_SIZE: u64 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Parquet,
+    /// The Arrow IPC streaming format.
+    Arrow,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Parquet => "application/vnd.apache.parquet",
+            Self::Arrow => "application/vnd.apache.arrow.stream",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Parquet => "parquet",
+            Self::Arrow => "arrows",
+        }
+    }
+}
+
+/// A column of an attribute index, derived from a property of the JSON
+/// schema of the attributes, or holding the whole attributes when `property`
+/// is `None`.
+#[derive(Debug, Clone, PartialEq)]
+struct AttributeColumn {
+    name: String,
+    property: Option<String>,
+    data_type: DataType,
+}
+
+impl AttributeColumn {
+    /// Strings, numbers and booleans get a column of their type, every
+    /// other property is written as JSON text.
+    fn from_property(name: &str, property: &serde_json::Value) -> Self {
+        let types = match property.get("type") {
+            Some(serde_json::Value::String(t)) => vec![t.as_str()],
+            Some(serde_json::Value::Array(types)) => {
+                types.iter().filter_map(|t| t.as_str()).collect()
+            }
+            _ => vec![],
+        };
+        let data_type = match types.into_iter().find(|t| *t != "null") {
+            Some("integer") => DataType::Int64,
+            Some("number") => DataType::Float64,
+            Some("boolean") => DataType::Boolean,
+            _ => DataType::Utf8,
+        };
+        Self {
+            name: name.into(),
+            property: Some(name.into()),
+            data_type,
+        }
+    }
+}
+
+/// The columns of an attribute index, from the schema of its output.
+/// Attributes without properties in their schema are exported whole, as JSON
+/// text, in an `attributes` column.
+fn attribute_columns(schema: &MetadataSchema, output: Option<&str>) -> Vec<AttributeColumn> {
+    let schema = schema.output_schema(output);
+    match schema.get("properties").and_then(|p| p.as_object()) {
+        Some(properties) if !properties.is_empty() => properties
+            .iter()
+            // The columns of every row come first.
+            .filter(|(name, _)| !matches!(name.as_str(), "id" | "content_id"))
+            .map(|(name, property)| AttributeColumn::from_property(name, property))
+            .collect(),
+        _ => vec![AttributeColumn {
+            name: "attributes".into(),
+            property: None,
+            data_type: DataType::Utf8,
+        }],
+    }
+}
+
+fn attributes_arrow_schema(columns: &[AttributeColumn]) -> SchemaRef {
+    let mut fields = vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("content_id", DataType::Utf8, false),
+    ];
+    fields.extend(
+        columns
+            .iter()
+            .map(|c| Field::new(&c.name, c.data_type.clone(), true)),
+    );
+    Arc::new(Schema::new(fields))
+}
+
+fn attributes_batch(
+    schema: SchemaRef,
+    columns: &[AttributeColumn],
+    rows: &[ExtractedAttributes],
+) -> Result<RecordBatch> {
+    let mut ids = StringBuilder::new();
+    let mut content_ids = StringBuilder::new();
+    for row in rows {
+        ids.append_value(&row.id);
+        content_ids.append_value(&row.content_id);
+    }
+    let mut arrays: Vec<ArrayRef> = vec![Arc::new(ids.finish()), Arc::new(content_ids.finish())];
+    for column in columns {
+        let values = rows.iter().map(|row| match &column.property {
+            Some(property) => row.attributes.get(property).filter(|v| !v.is_null()),
+            None => Some(&row.attributes),
+        });
+        let array: ArrayRef = match column.data_type {
+            DataType::Int64 => {
+                let mut builder = Int64Builder::new();
+                values.for_each(|v| builder.append_option(v.and_then(|v| v.as_i64())));
+                Arc::new(builder.finish())
+            }
+            DataType::Float64 => {
+                let mut builder = Float64Builder::new();
+                values.for_each(|v| builder.append_option(v.and_then(|v| v.as_f64())));
+                Arc::new(builder.finish())
+            }
+            DataType::Boolean => {
+                let mut builder = BooleanBuilder::new();
+                values.for_each(|v| builder.append_option(v.and_then(|v| v.as_bool())));
+                Arc::new(builder.finish())
+            }
+            _ => {
+                let mut builder = StringBuilder::new();
+                values.for_each(|v| match v {
+                    Some(serde_json::Value::String(s)) => builder.append_value(s),
+                    Some(v) => builder.append_value(v.to_string()),
+                    None => builder.append_null(),
+                });
+                Arc::new(builder.finish())
+            }
+        };
+        arrays.push(array);
+    }
+    Ok(RecordBatch::try_new(schema, arrays)?)
+}
+
+fn chunks_arrow_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("chunk_id", DataType::Utf8, false),
+        Field::new("content_id", DataType::Utf8, false),
+        Field::new("text", DataType::Utf8, false),
+        Field::new("parent_id", DataType::Utf8, true),
+        Field::new("is_parent", DataType::Boolean, false),
+        Field::new("start_offset", DataType::UInt64, true),
+        Field::new("end_offset", DataType::UInt64, true),
+        Field::new("page", DataType::UInt32, true),
+        Field::new("start_ms", DataType::UInt64, true),
+        Field::new("end_ms", DataType::UInt64, true),
+    ]))
+}
+
+fn chunks_batch(schema: SchemaRef, rows: &[Chunk]) -> Result<RecordBatch> {
+    let mut chunk_ids = StringBuilder::new();
+    let mut content_ids = StringBuilder::new();
+    let mut texts = StringBuilder::new();
+    let mut parent_ids = StringBuilder::new();
+    let mut is_parent = BooleanBuilder::new();
+    let mut start_offsets = UInt64Builder::new();
+    let mut end_offsets = UInt64Builder::new();
+    let mut pages = UInt32Builder::new();
+    let mut start_ms = UInt64Builder::new();
+    let mut end_ms = UInt64Builder::new();
+    for chunk in rows {
+        chunk_ids.append_value(&chunk.chunk_id);
+        content_ids.append_value(&chunk.content_id);
+        texts.append_value(&chunk.text);
+        parent_ids.append_option(chunk.parent_id.as_ref());
+        is_parent.append_value(chunk.is_parent);
+        start_offsets.append_option(chunk.offsets.as_ref().map(|o| o.start as u64));
+        end_offsets.append_option(chunk.offsets.as_ref().map(|o| o.end as u64));
+        pages.append_option(chunk.offsets.as_ref().and_then(|o| o.page));
+        start_ms.append_option(chunk.offsets.as_ref().and_then(|o| o.start_ms));
+        end_ms.append_option(chunk.offsets.as_ref().and_then(|o| o.end_ms));
+    }
+    Ok(RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(chunk_ids.finish()),
+            Arc::new(content_ids.finish()),
+            Arc::new(texts.finish()),
+            Arc::new(parent_ids.finish()),
+            Arc::new(is_parent.finish()),
+            Arc::new(start_offsets.finish()),
+            Arc::new(end_offsets.finish()),
+            Arc::new(pages.finish()),
+            Arc::new(start_ms.finish()),
+            Arc::new(end_ms.finish()),
+        ],
+    )?)
+}
+
+/// The bytes written by a writer so far, taken after every batch to be
+/// streamed to the client.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn take(&self) -> Bytes {
+        Bytes::from(std::mem::take(&mut *self.0.lock().unwrap()))
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+enum BatchWriter {
+    Parquet(ArrowWriter<SharedBuffer>),
+    Arrow(StreamWriter<SharedBuffer>),
+}
+
+impl BatchWriter {
+    fn new(format: ExportFormat, buffer: SharedBuffer, schema: SchemaRef) -> Result<Self> {
+        Ok(match format {
+            ExportFormat::Parquet => {
+                let properties = WriterProperties::builder()
+                    .set_compression(Compression::SNAPPY)
+                    .build();
+                Self::Parquet(ArrowWriter::try_new(buffer, schema, Some(properties))?)
+            }
+            ExportFormat::Arrow => Self::Arrow(StreamWriter::try_new(buffer, &schema)?),
+        })
+    }
+
+    fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        match self {
+            Self::Parquet(writer) => {
+                writer.write(batch)?;
+                // Ends the row group, so that it is not kept in memory until
+                // the end of the export.
+                writer.flush()?;
+            }
+            Self::Arrow(writer) => writer.write(batch)?,
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        match self {
+            Self::Parquet(writer) => {
+                writer.close()?;
+            }
+            Self::Arrow(mut writer) => writer.finish()?,
+        }
+        Ok(())
+    }
+}
+
+/// What is exported: the attributes of an attribute index, or the chunks of
+/// the current generation of an embedding index.
+#[derive(Debug, Clone)]
+pub enum ExportSource {
+    Attributes {
+        index: String,
+        schema: MetadataSchema,
+        output: Option<String>,
+    },
+    Chunks(IndexSnapshot),
+}
+
+/// Streams an index as Parquet or Arrow IPC, reading it from the database in
+/// batches so that large indexes are not held in memory.
+pub struct IndexExport {
+    repository: Arc<Repository>,
+    namespace: String,
+    repository_name: String,
+    source: ExportSource,
+    format: ExportFormat,
+}
+
+impl IndexExport {
+    pub fn new(
+        repository: Arc<Repository>,
+        namespace: &str,
+        repository_name: &str,
+        source: ExportSource,
+        format: ExportFormat,
+    ) -> Self {
+        Self {
+            repository,
+            namespace: namespace.into(),
+            repository_name: repository_name.into(),
+            source,
+            format,
+        }
+    }
+
+    /// The export, written in the background while the client reads it. An
+    /// error ends the stream with the error, which aborts the response.
+    pub fn stream(self) -> ReceiverStream<io::Result<Bytes>> {
+        let (tx, rx) = mpsc::channel(2);
+        tokio::spawn(async move {
+            if let Err(err) = self.write(&tx).await {
+                error!(
+                    "unable to export index of repository {}: {}",
+                    self.repository_name,
+                    err.to_string()
+                );
+                let _ = tx.send(Err(io::Error::other(err.to_string()))).await;
+            }
+        });
+        ReceiverStream::new(rx)
+    }
+
+    async fn write(&self, tx: &mpsc::Sender<io::Result<Bytes>>) -> Result<()> {
+        let buffer = SharedBuffer::default();
+        match &self.source {
+            ExportSource::Attributes {
+                index,
+                schema,
+                output,
+            } => {
+                let columns = attribute_columns(schema, output.as_deref());
+                let arrow_schema = attributes_arrow_schema(&columns);
+                let mut writer =
+                    BatchWriter::new(self.format, buffer.clone(), arrow_schema.clone())?;
+                let mut after: Option<String> = None;
+                loop {
+                    let rows = self
+                        .repository
+                        .attributes_after(
+                            &self.namespace,
+                            &self.repository_name,
+                            index,
+                            after.as_deref(),
+                            EXPORT_BATCH_SIZE,
+                        )
+                        .await?;
+                    let Some(last) = rows.last() else {
+                        break;
+                    };
+                    after = Some(last.id.clone());
+                    writer.write(&attributes_batch(arrow_schema.clone(), &columns, &rows)?)?;
+                    Self::send(tx, &buffer).await?;
+                }
+                writer.finish()?;
+            }
+            ExportSource::Chunks(snapshot) => {
+                let arrow_schema = chunks_arrow_schema();
+                let mut writer =
+                    BatchWriter::new(self.format, buffer.clone(), arrow_schema.clone())?;
+                let mut after: Option<String> = None;
+                loop {
+                    let rows = self
+                        .repository
+                        .chunks_in_snapshot_after(
+                            &self.namespace,
+                            snapshot,
+                            after.as_deref(),
+                            EXPORT_BATCH_SIZE,
+                        )
+                        .await?;
+                    let Some(last) = rows.last() else {
+                        break;
+                    };
+                    after = Some(last.chunk_id.clone());
+                    writer.write(&chunks_batch(arrow_schema.clone(), &rows)?)?;
+                    Self::send(tx, &buffer).await?;
+                }
+                writer.finish()?;
+            }
+        }
+        Self::send(tx, &buffer).await
+    }
+
+    async fn send(tx: &mpsc::Sender<io::Result<Bytes>>, buffer: &SharedBuffer) -> Result<()> {
+        let bytes = buffer.take();
+        if !bytes.is_empty() {
+            tx.send(Ok(bytes))
+                .await
+                .map_err(|_| anyhow::anyhow!("the client went away"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Array, Float64Array, Int64Array, StringArray};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use serde_json::json;
+
+    use super::*;
+
+    fn extractor_schema() -> MetadataSchema {
+        MetadataSchema {
+            schema: json!({
+                "outputs": {
+                    "invoice": {
+                        "attributes": {
+                            "schema": {
+                                "type": "object",
+                                "properties": {
+                                    "vendor": {"type": "string"},
+                                    "total": {"type": "number"},
+                                    "lines": {"type": ["integer", "null"]},
+                                    "tags": {"type": "array"}
+                                }
+                            }
+                        }
+                    }
+                }
+            }),
+        }
+    }
+
+    #[test]
+    fn test_attribute_columns() {
+        let columns = attribute_columns(&extractor_schema(), Some("invoice"));
+        let column = |name: &str| {
+            columns
+                .iter()
+                .find(|c| c.name == name)
+                .map(|c| c.data_type.clone())
+        };
+        assert_eq!(columns.len(), 4);
+        assert_eq!(column("vendor"), Some(DataType::Utf8));
+        assert_eq!(column("total"), Some(DataType::Float64));
+        assert_eq!(column("lines"), Some(DataType::Int64));
+        assert_eq!(column("tags"), Some(DataType::Utf8));
+
+        let columns = attribute_columns(&MetadataSchema { schema: json!({}) }, None);
+        assert_eq!(
+            columns,
+            vec![AttributeColumn {
+                name: "attributes".into(),
+                property: None,
+                data_type: DataType::Utf8,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_attributes_to_parquet() {
+        let columns = attribute_columns(&extractor_schema(), Some("invoice"));
+        let schema = attributes_arrow_schema(&columns);
+        let rows = vec![
+            ExtractedAttributes::new(
+                "content-1",
+                json!({"vendor": "acme", "total": 12.5, "lines": 3, "tags": ["q1"]}),
+                "invoices",
+            ),
+            // Values which don't match their column are written as nulls.
+            ExtractedAttributes::new(
+                "content-2",
+                json!({"vendor": "globex", "lines": "many"}),
+                "invoices",
+            ),
+        ];
+        let batch = attributes_batch(schema.clone(), &columns, &rows).unwrap();
+        let buffer = SharedBuffer::default();
+        let mut writer = BatchWriter::new(ExportFormat::Parquet, buffer.clone(), schema).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+
+        let batches = ParquetRecordBatchReaderBuilder::try_new(buffer.take())
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        let column = |name: &str| batch.column(batch.schema().index_of(name).unwrap()).clone();
+        let vendors = column("vendor");
+        let vendors = vendors.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(vendors.value(1), "globex");
+        let totals = column("total");
+        let totals = totals.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(totals.value(0), 12.5);
+        assert!(totals.is_null(1));
+        let lines = column("lines");
+        let lines = lines.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(lines.value(0), 3);
+        assert!(lines.is_null(1));
+        let tags = column("tags");
+        let tags = tags.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(tags.value(0), "[\"q1\"]");
+    }
+}
@@ -25,6 +25,9 @@ mod scaffold;
 pub struct EmbeddingSchema {
     pub distance_metric: String,
     pub dim: usize,
+    pub multi_vector: bool,
+    /// `text` and `image`, the content the extractor embeds.
+    pub modalities: Vec<String>,
 }
 
 pub trait Extractor {
@@ -51,6 +54,14 @@ pub struct ExtractedEmbeddings {
     pub embeddings: Vec<f32>,
 }
 
+/// The vectors of a text embedded by an extractor with a multi-vector schema.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ExtractedMultiVectors {
+    pub content_id: String,
+    pub text: String,
+    pub vectors: Vec<Vec<f32>>,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct AttributeData {
     pub content_id: String,
@@ -47,6 +47,8 @@ impl TryFrom<PyContent> for internal_api::Content {
                     "embedding" => internal_api::FeatureType::Embedding,
                     "named_entity" => internal_api::FeatureType::NamedEntity,
                     "metadata" => internal_api::FeatureType::Metadata,
+                    "graph" => internal_api::FeatureType::Graph,
+                    "summary" => internal_api::FeatureType::Summary,
                     _ => internal_api::FeatureType::Unknown,
                 };
                 let data = serde_json::from_str(&py_feature.value)?;
@@ -63,6 +65,7 @@ impl TryFrom<PyContent> for internal_api::Content {
             content_type: mime_type.to_string(),
             source: py_content.data,
             feature,
+            metadata: HashMap::new(),
         };
         Ok(extracted_content)
     }
@@ -217,6 +220,7 @@ impl Extractor for PythonExtractor {
                         content_type,
                         source: data,
                         feature,
+                        metadata: HashMap::new(),
                     });
                 }
                 extracted_content.push(temp);
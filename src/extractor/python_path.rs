@@ -10,8 +10,10 @@ pub fn set_python_path(path: &str) -> Result<(), anyhow::Error> {
     let path_str = parent_path
         .to_str()
         .ok_or(anyhow!("error setting PYTHONPATH: invalid path"))?;
-    let python_path = std::env::var("PYTHONPATH").unwrap_or("".to_string());
-    let mut site_packages: String = "".into();
+    let mut python_paths: Vec<PathBuf> = env::var_os("PYTHONPATH")
+        .map(|p| env::split_paths(&p).collect())
+        .unwrap_or_default();
+    python_paths.push(PathBuf::from(path_str));
     // THIS IS NEEEDED FOR MAC OS.
     if env::var("VIRTUAL_ENV").is_ok() {
         // Use Python itself to get the site-packages path
@@ -29,13 +31,12 @@ pub fn set_python_path(path: &str) -> Result<(), anyhow::Error> {
             None
         };
         if let Some(site_packages_path) = site_packages_path {
-            site_packages = site_packages_path
-                .to_str()
-                .ok_or(anyhow!("error setting PYTHONPATH: invalid path"))?
-                .into();
+            python_paths.push(site_packages_path);
         }
     }
-    let new_python_path = format!("{}:{}:{}", python_path, path_str, site_packages);
+    // The separator is `;` on Windows and `:` everywhere else.
+    let new_python_path =
+        env::join_paths(python_paths).map_err(|e| anyhow!("error setting PYTHONPATH: {}", e))?;
     env::set_var("PYTHONPATH", new_python_path);
     Ok(())
 }
@@ -1,8 +1,11 @@
+use std::collections::HashMap;
+
 use anyhow::{anyhow, Result};
 
 use crate::{
     api::Content,
     internal_api::{self, CoordinateResponse, ExtractResponse},
+    tls,
 };
 
 pub struct ExtractorRouter {
@@ -22,12 +25,31 @@ impl ExtractorRouter {
         content: Content,
         input_params: Option<serde_json::Value>,
     ) -> Result<Vec<Content>, anyhow::Error> {
+        let content = internal_api::Content {
+            content_type: content.content_type,
+            source: content.source,
+            feature: None,
+            metadata: HashMap::new(),
+        };
+        let content_list = self
+            .extract(extractor_name, content, input_params)
+            .await?
+            .into_iter()
+            .map(|c| c.into())
+            .collect();
+        Ok(content_list)
+    }
+
+    /// Runs an extractor on the content through one of its executors and
+    /// returns the content it extracted.
+    pub async fn extract(
+        &self,
+        extractor_name: &str,
+        content: internal_api::Content,
+        input_params: Option<serde_json::Value>,
+    ) -> Result<Vec<internal_api::Content>, anyhow::Error> {
         let request = internal_api::ExtractRequest {
-            content: internal_api::Content {
-                content_type: content.content_type,
-                source: content.source,
-                feature: None,
-            },
+            content,
             input_params,
         };
 
@@ -35,8 +57,8 @@ impl ExtractorRouter {
             extractor_name: extractor_name.to_string(),
         };
 
-        let coordinate_response = reqwest::Client::new()
-            .post(&format!("http://{}/coordinates", self.coordinator_addr))
+        let coordinate_response = tls::internal_http_client()
+            .post(&tls::internal_url(&self.coordinator_addr, "/coordinates"))
             .json(&coordinate_request)
             .send()
             .await
@@ -48,8 +70,8 @@ impl ExtractorRouter {
             .content
             .get(0)
             .ok_or(anyhow!("no extractor found"))?;
-        let resp = reqwest::Client::new()
-            .post(&format!("http://{}/extract", extractor_addr))
+        let resp = tls::internal_http_client()
+            .post(&tls::internal_url(extractor_addr, "/extract"))
             .json(&request)
             .send()
             .await
@@ -70,11 +92,6 @@ impl ExtractorRouter {
         let extractor_response: ExtractResponse = serde_json::from_str(&response_body)
             .map_err(|e| anyhow!("unable to extract response from json: {}", e))?;
 
-        let content_list = extractor_response
-            .content
-            .into_iter()
-            .map(|c| c.into())
-            .collect();
-        Ok(content_list)
+        Ok(extractor_response.content)
     }
 }
@@ -0,0 +1,308 @@
+//! Fault injection for validating the retry and recovery behavior of the
+//! pipeline in staging. Everything in this module is compiled only with the
+//! `fault-injection` feature, and faults are only injected for the targets
+//! which have a non-zero failure rate or latency configured.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use thiserror::Error;
+use tracing::warn;
+
+use crate::{
+    blob_storage::{BlobStorage, BlobStorageTS},
+    extractor::{Extractor, ExtractorSchema, ExtractorTS},
+    internal_api::Content,
+    server_config::FaultConfig,
+    vectordbs::{
+        CreateIndexParams,
+        MultiVectorChunk,
+        Quantization,
+        ScrollPage,
+        SearchFilter,
+        SearchResult,
+        VectorChunk,
+        VectorDBTS,
+        VectorDb,
+        VectorDbError,
+    },
+};
+
+#[derive(Error, Debug)]
+#[error("injected fault in `{target}` while running `{operation}`")]
+pub struct InjectedFault {
+    pub target: String,
+    pub operation: String,
+}
+
+/// Decides whether an operation against a target should be delayed or failed.
+#[derive(Debug, Clone)]
+pub struct FaultInjector {
+    target: String,
+    config: FaultConfig,
+}
+
+impl FaultInjector {
+    pub fn new(target: &str, config: FaultConfig) -> Self {
+        Self {
+            target: target.to_string(),
+            config,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.failure_rate > 0.0 || self.config.latency_ms > 0
+    }
+
+    fn latency(&self) -> Option<Duration> {
+        (self.config.latency_ms > 0).then(|| Duration::from_millis(self.config.latency_ms))
+    }
+
+    /// Returns an error for the configured fraction of the calls, without
+    /// adding any latency.
+    pub fn maybe_fail(&self, operation: &str) -> Result<(), InjectedFault> {
+        if rand::random::<f64>() < self.config.failure_rate {
+            warn!("injecting fault in {} during {}", self.target, operation);
+            return Err(InjectedFault {
+                target: self.target.clone(),
+                operation: operation.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Delays the calling task by the configured latency and then fails the
+    /// configured fraction of the calls.
+    pub async fn inject(&self, operation: &str) -> Result<(), InjectedFault> {
+        if let Some(latency) = self.latency() {
+            tokio::time::sleep(latency).await;
+        }
+        self.maybe_fail(operation)
+    }
+
+    /// Same as `inject` but blocks the calling thread, for use from the
+    /// synchronous extractor interface.
+    pub fn inject_blocking(&self, operation: &str) -> Result<(), InjectedFault> {
+        if let Some(latency) = self.latency() {
+            std::thread::sleep(latency);
+        }
+        self.maybe_fail(operation)
+    }
+}
+
+pub struct FaultyVectorDb {
+    inner: VectorDBTS,
+    injector: FaultInjector,
+}
+
+impl FaultyVectorDb {
+    pub fn wrap(inner: VectorDBTS, config: FaultConfig) -> VectorDBTS {
+        let injector = FaultInjector::new("vector_store", config);
+        if !injector.is_enabled() {
+            return inner;
+        }
+        Arc::new(Self { inner, injector })
+    }
+
+    async fn inject(&self, operation: &str) -> Result<(), VectorDbError> {
+        self.injector
+            .inject(operation)
+            .await
+            .map_err(|e| VectorDbError::Internal(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl VectorDb for FaultyVectorDb {
+    async fn warmup(&self) -> Result<(), VectorDbError> {
+        self.inner.warmup().await
+    }
+
+    async fn health_check(&self) -> Result<(), VectorDbError> {
+        self.inject("health_check").await?;
+        self.inner.health_check().await
+    }
+
+    fn supports_quantization(&self, quantization: &Quantization) -> bool {
+        self.inner.supports_quantization(quantization)
+    }
+
+    fn supports_payload_indexes(&self) -> bool {
+        self.inner.supports_payload_indexes()
+    }
+
+    async fn create_index(&self, index: CreateIndexParams) -> Result<(), VectorDbError> {
+        self.inject("create_index").await?;
+        self.inner.create_index(index).await
+    }
+
+    async fn add_embedding(
+        &self,
+        index: &str,
+        chunks: Vec<VectorChunk>,
+    ) -> Result<(), VectorDbError> {
+        self.inject("add_embedding").await?;
+        self.inner.add_embedding(index, chunks).await
+    }
+
+    fn supports_multi_vector(&self) -> bool {
+        self.inner.supports_multi_vector()
+    }
+
+    async fn add_multi_vectors(
+        &self,
+        index: &str,
+        chunks: Vec<MultiVectorChunk>,
+    ) -> Result<(), VectorDbError> {
+        self.inject("add_multi_vectors").await?;
+        self.inner.add_multi_vectors(index, chunks).await
+    }
+
+    async fn search_multi_vector(
+        &self,
+        index: String,
+        query_vectors: Vec<Vec<f32>>,
+        k: u64,
+        filter: SearchFilter,
+    ) -> Result<Vec<SearchResult>, VectorDbError> {
+        self.inject("search").await?;
+        self.inner
+            .search_multi_vector(index, query_vectors, k, filter)
+            .await
+    }
+
+    async fn delete_embeddings(
+        &self,
+        index: &str,
+        chunk_ids: Vec<String>,
+    ) -> Result<(), VectorDbError> {
+        self.inject("delete_embeddings").await?;
+        self.inner.delete_embeddings(index, chunk_ids).await
+    }
+
+    async fn search(
+        &self,
+        index: String,
+        query_embedding: Vec<f32>,
+        k: u64,
+        filter: SearchFilter,
+    ) -> Result<Vec<SearchResult>, VectorDbError> {
+        self.inject("search").await?;
+        self.inner.search(index, query_embedding, k, filter).await
+    }
+
+    async fn drop_index(&self, index: String) -> Result<(), VectorDbError> {
+        self.inject("drop_index").await?;
+        self.inner.drop_index(index).await
+    }
+
+    async fn warm_index(&self, index: &str) -> Result<(), VectorDbError> {
+        self.inject("warm_index").await?;
+        self.inner.warm_index(index).await
+    }
+
+    async fn num_vectors(&self, index: &str) -> Result<u64, VectorDbError> {
+        self.inject("num_vectors").await?;
+        self.inner.num_vectors(index).await
+    }
+
+    async fn scroll(
+        &self,
+        index: &str,
+        offset: Option<String>,
+        limit: u32,
+    ) -> Result<ScrollPage, VectorDbError> {
+        self.inject("scroll").await?;
+        self.inner.scroll(index, offset, limit).await
+    }
+
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+}
+
+pub struct FaultyBlobStorage {
+    inner: BlobStorageTS,
+    injector: FaultInjector,
+}
+
+impl FaultyBlobStorage {
+    pub fn wrap(inner: BlobStorageTS, config: FaultConfig) -> BlobStorageTS {
+        let injector = FaultInjector::new("blob_store", config);
+        if !injector.is_enabled() {
+            return inner;
+        }
+        Arc::new(Self { inner, injector })
+    }
+}
+
+#[async_trait]
+impl BlobStorage for FaultyBlobStorage {
+    async fn put(&self, key: &str, data: Bytes) -> Result<String, anyhow::Error> {
+        self.injector.inject("put").await?;
+        self.inner.put(key, data).await
+    }
+
+    fn delete(&self, key: &str) -> Result<(), anyhow::Error> {
+        self.injector.maybe_fail("delete")?;
+        self.inner.delete(key)
+    }
+
+    async fn health_check(&self) -> Result<(), anyhow::Error> {
+        self.injector.inject("health_check").await?;
+        self.inner.health_check().await
+    }
+}
+
+pub struct FaultyExtractor {
+    inner: ExtractorTS,
+    injector: FaultInjector,
+}
+
+impl FaultyExtractor {
+    pub fn wrap(inner: ExtractorTS, config: FaultConfig) -> ExtractorTS {
+        let injector = FaultInjector::new("extractor", config);
+        if !injector.is_enabled() {
+            return inner;
+        }
+        Arc::new(Self { inner, injector })
+    }
+}
+
+impl Extractor for FaultyExtractor {
+    fn schemas(&self) -> Result<ExtractorSchema, anyhow::Error> {
+        self.inner.schemas()
+    }
+
+    fn extract(
+        &self,
+        content: Vec<Content>,
+        input_params: serde_json::Value,
+    ) -> Result<Vec<Vec<Content>>, anyhow::Error> {
+        self.injector.inject_blocking("extract")?;
+        self.inner.extract(content, input_params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_rate_bounds() {
+        let always = FaultInjector::new(
+            "test",
+            FaultConfig {
+                failure_rate: 1.0,
+                latency_ms: 0,
+            },
+        );
+        assert!(always.maybe_fail("op").is_err());
+
+        let never = FaultInjector::new("test", FaultConfig::default());
+        assert!(!never.is_enabled());
+        assert!(never.maybe_fail("op").is_ok());
+    }
+}
@@ -0,0 +1,841 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use sea_orm::Value;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// What the fields of a filter are read from: the metadata of content, or
+/// the attributes extracted from content into an attributes index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Metadata,
+    Attributes,
+}
+
+impl Scope {
+    /// The word fields of the scope start with.
+    fn keyword(&self) -> &'static str {
+        match self {
+            Self::Metadata => "metadata",
+            Self::Attributes => "attributes",
+        }
+    }
+
+    /// The jsonb column of the fields, in `content` and `attributes_index`.
+    pub fn column(&self) -> &'static str {
+        match self {
+            Self::Metadata => "metadata",
+            Self::Attributes => "data",
+        }
+    }
+}
+
+/// A step into a field, a field of an object or an element of an array.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathSegment {
+    Field(String),
+    Element(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Eq,
+    Neq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    /// An array contains the value, or an object contains the fields of the
+    /// value.
+    Contains,
+}
+
+impl Comparison {
+    fn sql(&self) -> &'static str {
+        match self {
+            Self::Eq => "=",
+            Self::Neq => "!=",
+            Self::Gt => ">",
+            Self::Gte => ">=",
+            Self::Lt => "<",
+            Self::Lte => "<=",
+            Self::Contains => "@>",
+        }
+    }
+
+    fn is_ordering(&self) -> bool {
+        matches!(self, Self::Gt | Self::Gte | Self::Lt | Self::Lte)
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Contains => write!(f, "contains"),
+            op => write!(f, "{}", op.sql()),
+        }
+    }
+}
+
+/// A predicate over the metadata of content or the attributes extracted from
+/// it, such as `metadata.lang = "en" and not metadata.tags contains "draft"`
+/// or `attributes.price > 100`. Comparisons combine with `and`, `or` and
+/// `not` and are grouped with parentheses. Values are JSON literals, and a
+/// field which is missing satisfies no comparison.
+///
+/// The same filters select the content of extractor bindings, filter content
+/// listings and attribute queries, and restrict searches. They are compiled
+/// to SQL for the database, evaluated in memory against the metadata of
+/// content, and compiled to payload matches for the vector store. Filters
+/// are read and written as their text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Compare {
+        scope: Scope,
+        path: Vec<PathSegment>,
+        op: Comparison,
+        value: serde_json::Value,
+    },
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    Ident(String),
+    Value(serde_json::Value),
+    Op(Comparison),
+    Dot,
+    LeftBracket,
+    RightBracket,
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '.' => {
+                tokens.push(Token::Dot);
+                i += 1;
+            }
+            '[' => {
+                tokens.push(Token::LeftBracket);
+                i += 1;
+            }
+            ']' => {
+                tokens.push(Token::RightBracket);
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LeftParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RightParen);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            '=' => {
+                tokens.push(Token::Op(Comparison::Eq));
+                i += 1;
+            }
+            '!' | '<' | '>' => {
+                let with_eq = chars.get(i + 1) == Some(&'=');
+                let op = match (c, with_eq) {
+                    ('!', true) => Comparison::Neq,
+                    ('<', true) => Comparison::Lte,
+                    ('>', true) => Comparison::Gte,
+                    ('<', false) => Comparison::Lt,
+                    ('>', false) => Comparison::Gt,
+                    _ => return Err(format!("unexpected `{}` at {}", c, i)),
+                };
+                tokens.push(Token::Op(op));
+                i += if with_eq { 2 } else { 1 };
+            }
+            '"' => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err(format!("unterminated string at {}", start));
+                }
+                i += 1;
+                let literal: String = chars[start..i].iter().collect();
+                let value = serde_json::from_str(&literal)
+                    .map_err(|e| format!("invalid string {}: {}", literal, e))?;
+                tokens.push(Token::Value(value));
+            }
+            c if c == '-' || c.is_ascii_digit() => {
+                let start = i;
+                i += 1;
+                while i < chars.len() &&
+                    (chars[i].is_ascii_digit() || ['.', 'e', 'E', '+', '-'].contains(&chars[i]))
+                {
+                    i += 1;
+                }
+                let literal: String = chars[start..i].iter().collect();
+                let value = serde_json::from_str::<serde_json::Number>(&literal)
+                    .map_err(|_| format!("invalid number {}", literal))?;
+                tokens.push(Token::Value(value.into()));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                let token = match word.as_str() {
+                    "true" => Token::Value(true.into()),
+                    "false" => Token::Value(false.into()),
+                    "null" => Token::Value(serde_json::Value::Null),
+                    "contains" => Token::Op(Comparison::Contains),
+                    _ => Token::Ident(word),
+                };
+                tokens.push(token);
+            }
+            _ => return Err(format!("unexpected `{}` at {}", c, i)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// The deepest a filter can nest, counting parentheses, `not` and every
+/// operand of a chain of `and` or `or`. Filters come from requests and are
+/// parsed, compiled and evaluated recursively, so deeper ones are rejected
+/// rather than overflowing the stack.
+const MAX_DEPTH: usize = 256;
+
+pub(crate) struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    pub(crate) fn new(input: &str) -> Result<Self, String> {
+        Ok(Self {
+            tokens: tokenize(input)?,
+            position: 0,
+            depth: 0,
+        })
+    }
+
+    pub(crate) fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    pub(crate) fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    pub(crate) fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    pub(crate) fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            token => Err(format!("expected {:?}, found {:?}", expected, token)),
+        }
+    }
+
+    pub(crate) fn finish(&self) -> Result<(), String> {
+        match self.peek() {
+            None => Ok(()),
+            Some(token) => Err(format!("unexpected {:?}", token)),
+        }
+    }
+
+    /// Goes one level deeper into the filter.
+    fn descend(&mut self) -> Result<(), String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!("filter is nested deeper than {} levels", MAX_DEPTH));
+        }
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<Filter, String> {
+        let depth = self.depth;
+        let mut filter = self.and()?;
+        while self.is_keyword("or") {
+            self.next();
+            self.descend()?;
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        self.depth = depth;
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter, String> {
+        let depth = self.depth;
+        let mut filter = self.unary()?;
+        while self.is_keyword("and") {
+            self.next();
+            self.descend()?;
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+        self.depth = depth;
+        Ok(filter)
+    }
+
+    fn unary(&mut self) -> Result<Filter, String> {
+        if self.is_keyword("not") {
+            self.next();
+            self.descend()?;
+            let filter = Filter::Not(Box::new(self.unary()?));
+            self.depth -= 1;
+            return Ok(filter);
+        }
+        if self.peek() == Some(&Token::LeftParen) {
+            self.next();
+            self.descend()?;
+            let filter = self.or()?;
+            self.expect(Token::RightParen)?;
+            self.depth -= 1;
+            return Ok(filter);
+        }
+        let scope = if self.is_keyword(Scope::Metadata.keyword()) {
+            Scope::Metadata
+        } else if self.is_keyword(Scope::Attributes.keyword()) {
+            Scope::Attributes
+        } else {
+            return Err(format!(
+                "expected `metadata` or `attributes`, found {:?}",
+                self.peek()
+            ));
+        };
+        let path = self.path(scope)?;
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            token => return Err(format!("expected a comparison, found {:?}", token)),
+        };
+        let value = match self.next() {
+            Some(Token::Value(value)) => value,
+            token => return Err(format!("expected a value, found {:?}", token)),
+        };
+        let is_ordered = matches!(
+            value,
+            serde_json::Value::Number(_) | serde_json::Value::String(_)
+        );
+        if op.is_ordering() && !is_ordered {
+            return Err(format!("{} can not be ordered against", value));
+        }
+        Ok(Filter::Compare {
+            scope,
+            path,
+            op,
+            value,
+        })
+    }
+
+    /// The keyword of the scope followed by `.field`, `["field"]` or
+    /// `[element]` steps.
+    pub(crate) fn path(&mut self, scope: Scope) -> Result<Vec<PathSegment>, String> {
+        match self.next() {
+            Some(Token::Ident(word)) if word == scope.keyword() => {}
+            token => return Err(format!("expected `{}`, found {:?}", scope.keyword(), token)),
+        }
+        let mut path = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Dot) => {
+                    self.next();
+                    match self.next() {
+                        Some(Token::Ident(field)) => path.push(PathSegment::Field(field)),
+                        token => return Err(format!("expected a field, found {:?}", token)),
+                    }
+                }
+                Some(Token::LeftBracket) => {
+                    self.next();
+                    let segment = match self.next() {
+                        Some(Token::Value(serde_json::Value::String(field))) => {
+                            PathSegment::Field(field)
+                        }
+                        Some(Token::Value(serde_json::Value::Number(n))) => n
+                            .as_u64()
+                            .map(|n| PathSegment::Element(n as usize))
+                            .ok_or(format!("invalid element {}", n))?,
+                        token => {
+                            return Err(format!("expected a field or element, found {:?}", token))
+                        }
+                    };
+                    self.expect(Token::RightBracket)?;
+                    path.push(segment);
+                }
+                _ => break,
+            }
+        }
+        if path.is_empty() {
+            return Err(format!("expected a field of the {}", scope.keyword()));
+        }
+        Ok(path)
+    }
+}
+
+/// Writes a path back the way it is parsed.
+pub(crate) fn fmt_path(
+    scope: Scope,
+    path: &[PathSegment],
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    write!(f, "{}", scope.keyword())?;
+    for segment in path {
+        match segment {
+            PathSegment::Field(field)
+                if !field.is_empty() &&
+                    field.chars().all(|c| c.is_alphanumeric() || c == '_') &&
+                    !field.starts_with(|c: char| c.is_ascii_digit()) =>
+            {
+                write!(f, ".{}", field)?
+            }
+            PathSegment::Field(field) => {
+                write!(f, "[{}]", serde_json::Value::from(field.as_str()))?
+            }
+            PathSegment::Element(element) => write!(f, "[{}]", element)?,
+        }
+    }
+    Ok(())
+}
+
+/// The jsonb at the path of the column of the scope. Fields are bound as
+/// parameters, elements are numbers and written into the SQL.
+pub(crate) fn path_sql(scope: Scope, path: &[PathSegment], values: &mut Vec<Value>) -> String {
+    let mut sql = scope.column().to_string();
+    for segment in path {
+        match segment {
+            PathSegment::Field(field) => {
+                values.push(field.clone().into());
+                sql.push_str(&format!(" -> ${}", values.len()));
+            }
+            PathSegment::Element(element) => sql.push_str(&format!(" -> {}", element)),
+        }
+    }
+    sql
+}
+
+/// The value at the path of the metadata of content.
+fn lookup<'a>(
+    metadata: &'a HashMap<String, serde_json::Value>,
+    path: &[PathSegment],
+) -> Option<&'a serde_json::Value> {
+    let (first, rest) = path.split_first()?;
+    let PathSegment::Field(key) = first else {
+        return None;
+    };
+    rest.iter()
+        .try_fold(metadata.get(key)?, |value, segment| match segment {
+            PathSegment::Field(field) => value.as_object()?.get(field),
+            PathSegment::Element(element) => value.as_array()?.get(*element),
+        })
+}
+
+/// Whether `value` contains `other` the way jsonb `@>` does: arrays contain
+/// the elements of an array or a scalar, objects contain the fields of an
+/// object, and scalars contain equal scalars.
+fn json_contains(value: &serde_json::Value, other: &serde_json::Value) -> bool {
+    use serde_json::Value::{Array, Object};
+    match (value, other) {
+        (Array(elements), Array(others)) => others
+            .iter()
+            .all(|o| elements.iter().any(|e| json_contains(e, o))),
+        (Array(elements), other) if !other.is_object() => elements.contains(other),
+        (Object(fields), Object(others)) => others
+            .iter()
+            .all(|(key, o)| fields.get(key).is_some_and(|field| json_contains(field, o))),
+        (value, other) => value == other,
+    }
+}
+
+/// Orders values of the same type the way jsonb does, numbers by their
+/// value and strings by their characters.
+fn json_cmp(value: &serde_json::Value, other: &serde_json::Value) -> Option<std::cmp::Ordering> {
+    match (value, other) {
+        (serde_json::Value::Number(a), serde_json::Value::Number(b)) => {
+            a.as_f64()?.partial_cmp(&b.as_f64()?)
+        }
+        (serde_json::Value::String(a), serde_json::Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+impl Filter {
+    pub fn parse(filter: &str) -> Result<Self, String> {
+        let mut parser = Parser::new(filter)?;
+        let filter = parser.or()?;
+        parser.finish()?;
+        Ok(filter)
+    }
+
+    /// Parses a filter whose fields are all in the scope.
+    pub fn parse_in(filter: &str, scope: Scope) -> Result<Self, String> {
+        let filter = Self::parse(filter)?;
+        filter.check_scope(scope)?;
+        Ok(filter)
+    }
+
+    /// Fails when a field of the filter is not in the scope, such as an
+    /// attribute in the filter of a binding, which only sees metadata.
+    pub fn check_scope(&self, scope: Scope) -> Result<(), String> {
+        match self {
+            Self::Compare {
+                scope: field_scope, ..
+            } if *field_scope != scope => Err(format!(
+                "only fields of the {} can be filtered on here, found {}",
+                scope.keyword(),
+                self
+            )),
+            Self::Compare { .. } => Ok(()),
+            Self::And(left, right) | Self::Or(left, right) => {
+                left.check_scope(scope)?;
+                right.check_scope(scope)
+            }
+            Self::Not(filter) => filter.check_scope(scope),
+        }
+    }
+
+    /// The filter matching what all the filters match, none without filters.
+    pub fn all(filters: impl IntoIterator<Item = Filter>) -> Option<Self> {
+        filters
+            .into_iter()
+            .reduce(|all, filter| Self::And(Box::new(all), Box::new(filter)))
+    }
+
+    /// The filter as a condition on the jsonb columns of its scopes. Its
+    /// parameters are appended to `values` and numbered after the ones
+    /// already there.
+    pub fn to_sql(&self, values: &mut Vec<Value>) -> String {
+        match self {
+            Self::Compare {
+                scope,
+                path,
+                op,
+                value,
+            } => {
+                let path = path_sql(*scope, path, values);
+                values.push(value.to_string().into());
+                let comparison = format!("({}) {} ${}::jsonb", path, op.sql(), values.len());
+                if op.is_ordering() {
+                    // jsonb orders values of different types by their type, so
+                    // only values of the same type are compared.
+                    let json_type = if value.is_number() {
+                        "number"
+                    } else {
+                        "string"
+                    };
+                    format!(
+                        "(jsonb_typeof({}) = '{}' and {})",
+                        path, json_type, comparison
+                    )
+                } else {
+                    comparison
+                }
+            }
+            Self::And(left, right) => {
+                format!("({} and {})", left.to_sql(values), right.to_sql(values))
+            }
+            Self::Or(left, right) => {
+                format!("({} or {})", left.to_sql(values), right.to_sql(values))
+            }
+            Self::Not(filter) => format!("not coalesce({}, false)", filter.to_sql(values)),
+        }
+    }
+
+    /// Whether the metadata of a content satisfies the filter, the same way
+    /// as in the database.
+    pub fn matches(&self, metadata: &HashMap<String, serde_json::Value>) -> bool {
+        match self {
+            Self::Compare {
+                path, op, value, ..
+            } => {
+                let Some(found) = lookup(metadata, path) else {
+                    return false;
+                };
+                match op {
+                    Comparison::Eq => found == value,
+                    Comparison::Neq => found != value,
+                    Comparison::Contains => json_contains(found, value),
+                    Comparison::Gt => json_cmp(found, value).is_some_and(|o| o.is_gt()),
+                    Comparison::Gte => json_cmp(found, value).is_some_and(|o| o.is_ge()),
+                    Comparison::Lt => json_cmp(found, value).is_some_and(|o| o.is_lt()),
+                    Comparison::Lte => json_cmp(found, value).is_some_and(|o| o.is_le()),
+                }
+            }
+            Self::And(left, right) => left.matches(metadata) && right.matches(metadata),
+            Self::Or(left, right) => left.matches(metadata) || right.matches(metadata),
+            Self::Not(filter) => !filter.matches(metadata),
+        }
+    }
+
+    /// The fields of the metadata and the values the vector store matches
+    /// the payloads of chunks with. Vector stores only match payloads by
+    /// equality, so the filter has to be comparisons of fields of the
+    /// metadata with `=` joined by `and`.
+    pub fn payload_matches(&self) -> Result<Vec<(String, serde_json::Value)>, String> {
+        match self {
+            Self::Compare {
+                scope: Scope::Metadata,
+                path,
+                op: Comparison::Eq,
+                value,
+            } => {
+                let fields = path
+                    .iter()
+                    .map(|segment| match segment {
+                        PathSegment::Field(field) => Some(field.as_str()),
+                        PathSegment::Element(_) => None,
+                    })
+                    .collect::<Option<Vec<&str>>>()
+                    .ok_or(format!(
+                        "{} can not be matched by the vector store, it has an element",
+                        self
+                    ))?;
+                Ok(vec![(fields.join("."), value.clone())])
+            }
+            Self::And(left, right) => {
+                let mut matches = left.payload_matches()?;
+                matches.extend(right.payload_matches()?);
+                Ok(matches)
+            }
+            _ => Err(format!(
+                "{} can not be matched by the vector store, which only matches fields of the \
+                 metadata with = joined by and",
+                self
+            )),
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, grouped: bool) -> fmt::Result {
+        if grouped {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    /// Writes the filter back the way it is parsed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Compare {
+                scope,
+                path,
+                op,
+                value,
+            } => {
+                fmt_path(*scope, path, f)?;
+                write!(f, " {} {}", op, value)
+            }
+            Self::And(left, right) => {
+                left.fmt_operand(f, matches!(**left, Self::Or(..)))?;
+                write!(f, " and ")?;
+                right.fmt_operand(f, matches!(**right, Self::Or(..) | Self::And(..)))
+            }
+            Self::Or(left, right) => {
+                left.fmt_operand(f, false)?;
+                write!(f, " or ")?;
+                right.fmt_operand(f, matches!(**right, Self::Or(..)))
+            }
+            Self::Not(filter) => {
+                write!(f, "not ")?;
+                filter.fmt_operand(f, matches!(**filter, Self::And(..) | Self::Or(..)))
+            }
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(filter: &str) -> Result<Self, String> {
+        Self::parse(filter)
+    }
+}
+
+impl Serialize for Filter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Filter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let filter = String::deserialize(deserializer)?;
+        Self::parse(&filter).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let filter =
+            Filter::parse(r#"attributes.price > 100 and attributes.tags contains "legal""#)
+                .unwrap();
+        assert_eq!(
+            filter,
+            Filter::And(
+                Box::new(Filter::Compare {
+                    scope: Scope::Attributes,
+                    path: vec![PathSegment::Field("price".into())],
+                    op: Comparison::Gt,
+                    value: json!(100),
+                }),
+                Box::new(Filter::Compare {
+                    scope: Scope::Attributes,
+                    path: vec![PathSegment::Field("tags".into())],
+                    op: Comparison::Contains,
+                    value: json!("legal"),
+                }),
+            )
+        );
+
+        let filter = Filter::parse(
+            r#"not (attributes.author["first name"] = "Ada" or attributes.pages[0] <= -1.5)"#,
+        )
+        .unwrap();
+        let mut values = Vec::new();
+        assert_eq!(
+            filter.to_sql(&mut values),
+            "not coalesce(((data -> $1 -> $2) = $3::jsonb or (jsonb_typeof(data -> $4 -> 0) = 'number' and (data -> $4 -> 0) <= $5::jsonb)), false)"
+        );
+        assert_eq!(values.len(), 5);
+
+        let filter = Filter::parse(r#"metadata.source.kind = "web""#).unwrap();
+        let mut values = vec!["default".into()];
+        assert_eq!(
+            filter.to_sql(&mut values),
+            "(metadata -> $2 -> $3) = $4::jsonb"
+        );
+
+        assert!(Filter::parse("attributes.price >").is_err());
+        assert!(Filter::parse("price = 1").is_err());
+        assert!(Filter::parse("attributes = 1").is_err());
+        assert!(Filter::parse("attributes.tags > [1]").is_err());
+        assert!(Filter::parse("attributes.price > true").is_err());
+        assert!(Filter::parse(r#"attributes.name = "unterminated"#).is_err());
+        assert!(Filter::parse("attributes.a = 1 attributes.b = 2").is_err());
+    }
+
+    #[test]
+    fn test_scope() {
+        let filter = r#"metadata.lang = "en" or attributes.price > 1"#;
+        assert!(Filter::parse(filter).is_ok());
+        assert!(Filter::parse_in(filter, Scope::Metadata).is_err());
+        assert!(Filter::parse_in(filter, Scope::Attributes).is_err());
+        assert!(Filter::parse_in(r#"not metadata.lang = "en""#, Scope::Metadata).is_ok());
+    }
+
+    #[test]
+    fn test_display() {
+        for filter in [
+            r#"metadata.lang = "en""#,
+            r#"metadata["first name"] != "Ada" and metadata.pages[0] >= 2"#,
+            r#"(metadata.a = 1 or metadata.b = 2) and not (metadata.c = 3 and metadata.d = 4)"#,
+            r#"metadata.a = 1 or metadata.b = 2 and metadata.c = 3"#,
+            r#"metadata.a = 1 and (metadata.b = 2 and metadata.c = 3)"#,
+            r#"not not metadata.tags contains "legal""#,
+        ] {
+            let parsed = Filter::parse(filter).unwrap();
+            assert_eq!(parsed.to_string(), filter);
+            assert_eq!(Filter::parse(&parsed.to_string()).unwrap(), parsed);
+        }
+
+        let filter = Filter::parse(r#"metadata.lang = "en""#).unwrap();
+        assert_eq!(
+            serde_json::to_value(&filter).unwrap(),
+            json!(r#"metadata.lang = "en""#)
+        );
+        assert_eq!(
+            serde_json::from_value::<Filter>(json!(r#"metadata.lang = "en""#)).unwrap(),
+            filter
+        );
+        assert!(serde_json::from_value::<Filter>(json!("metadata.lang =")).is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let metadata = HashMap::from([
+            ("lang".to_string(), json!("en")),
+            ("pages".to_string(), json!(12)),
+            ("tags".to_string(), json!(["legal", "draft"])),
+            ("author".to_string(), json!({"name": "Ada", "team": "core"})),
+        ]);
+        let matches = |filter: &str| Filter::parse(filter).unwrap().matches(&metadata);
+        assert!(matches(r#"metadata.lang = "en""#));
+        assert!(!matches(r#"metadata.lang != "en""#));
+        assert!(matches("metadata.pages > 10 and metadata.pages <= 12"));
+        assert!(!matches(r#"metadata.pages > "10""#));
+        assert!(matches(r#"metadata.tags contains "legal""#));
+        assert!(!matches(r#"metadata.tags contains "final""#));
+        assert!(matches(r#"metadata.lang contains "en""#));
+        assert!(matches(r#"metadata.author.name = "Ada""#));
+        assert!(matches(r#"metadata.tags[1] = "draft""#));
+        // A field which is missing satisfies no comparison.
+        assert!(!matches(r#"metadata.missing != "en""#));
+        assert!(matches(r#"not metadata.missing = "en""#));
+        assert!(matches(r#"metadata.missing = 1 or metadata.lang = "en""#));
+    }
+
+    #[test]
+    fn test_payload_matches() {
+        let filter =
+            Filter::parse(r#"metadata.lang = "en" and metadata.source.kind = "web""#).unwrap();
+        assert_eq!(
+            filter.payload_matches().unwrap(),
+            vec![
+                ("lang".to_string(), json!("en")),
+                ("source.kind".to_string(), json!("web")),
+            ]
+        );
+        for filter in [
+            r#"metadata.lang = "en" or metadata.lang = "fr""#,
+            r#"not metadata.lang = "en""#,
+            "metadata.pages > 1",
+            r#"metadata.tags[0] = "legal""#,
+            "attributes.price = 1",
+        ] {
+            assert!(Filter::parse(filter).unwrap().payload_matches().is_err());
+        }
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let comparison = r#"metadata.lang = "en""#;
+        let nested =
+            |depth: usize| format!("{}{}{}", "(".repeat(depth), comparison, ")".repeat(depth));
+        assert!(Filter::parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(Filter::parse(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(Filter::parse(&nested(100_000)).is_err());
+        assert!(Filter::parse(&format!("{}{}", "not ".repeat(100_000), comparison)).is_err());
+        // Chains of `and` and `or` nest as deep as they are long.
+        let chain = |length: usize| vec![comparison; length].join(" or ");
+        assert!(Filter::parse(&chain(MAX_DEPTH)).is_ok());
+        assert!(Filter::parse(&chain(100_000)).is_err());
+        // Levels which are closed do not count towards the others.
+        let siblings = vec![nested(MAX_DEPTH - 1); 2].join(" and ");
+        assert!(Filter::parse(&siblings).is_ok());
+    }
+}
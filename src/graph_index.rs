@@ -0,0 +1,385 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::Arc,
+};
+
+use anyhow::Result;
+
+use crate::persistence::{
+    ExtractedGraph,
+    GraphEdge,
+    GraphEntity,
+    GraphNode,
+    GraphSchema,
+    NewIndex,
+    Repository,
+    RepositoryError,
+};
+
+/// The most relationships followed from the entities of one hop of a path
+/// search, so that searches through highly connected entities stay bounded.
+const MAX_EDGES_PER_HOP: u64 = 10_000;
+
+/// The shortest paths to look for between two entities.
+#[derive(Debug, Clone, Copy)]
+pub struct PathQuery<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    /// The most relationships on a path.
+    pub max_hops: usize,
+    /// The most paths returned.
+    pub limit: usize,
+}
+
+pub struct GraphIndexManager {
+    repository: Arc<Repository>,
+}
+
+impl fmt::Debug for GraphIndexManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GraphIndexManager").finish()
+    }
+}
+
+impl GraphIndexManager {
+    pub fn new(repository: Arc<Repository>) -> Self {
+        Self { repository }
+    }
+
+    pub async fn create_index(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        extractor: &str,
+        schema: GraphSchema,
+    ) -> Result<String> {
+        self.repository
+            .create_index_metadata(
+                namespace,
+                repository,
+                NewIndex {
+                    extractor_name: extractor,
+                    name: index_name,
+                    storage_name: "graph_store",
+                    schema: serde_json::json!(schema),
+                    index_type: "graph",
+                },
+            )
+            .await?;
+        Ok(index_name.to_string())
+    }
+
+    #[tracing::instrument(skip(self, graph))]
+    pub async fn add_graph(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        content_id: &str,
+        graph: &ExtractedGraph,
+    ) -> Result<()> {
+        self.repository
+            .add_graph(namespace, repository, index_name, content_id, graph)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn search_entities(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        query: &str,
+        entity_type: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<GraphNode>, RepositoryError> {
+        self.repository
+            .search_graph_nodes(namespace, repository, index_name, query, entity_type, limit)
+            .await
+    }
+
+    /// The entity with the name and the relationships from and to it, along
+    /// with the entities at their other end.
+    pub async fn neighbors(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        entity: &str,
+        relation: Option<&str>,
+        limit: u64,
+    ) -> Result<(GraphNode, Vec<(GraphEdge, GraphNode)>), RepositoryError> {
+        let entity_id = GraphEntity::id_of(entity);
+        let edges = self
+            .repository
+            .graph_edges(
+                namespace,
+                repository,
+                index_name,
+                std::slice::from_ref(&entity_id),
+                relation,
+                limit,
+            )
+            .await?;
+        let mut ids: Vec<String> = edges
+            .iter()
+            .map(|e| e.other(&entity_id).to_string())
+            .collect();
+        ids.push(entity_id.clone());
+        let mut nodes: HashMap<String, GraphNode> = self
+            .repository
+            .graph_nodes(namespace, repository, index_name, &ids)
+            .await?
+            .into_iter()
+            .map(|n| (n.id.clone(), n))
+            .collect();
+        let node = nodes
+            .remove(&entity_id)
+            .ok_or(RepositoryError::GraphEntityNotFound(entity.into()))?;
+        let neighbors = edges
+            .into_iter()
+            .filter_map(|e| {
+                let other = nodes.get(e.other(&entity_id))?.clone();
+                Some((e, other))
+            })
+            .collect();
+        Ok((node, neighbors))
+    }
+
+    /// The shortest paths between two entities, of at most `max_hops`
+    /// relationships, followed in either direction.
+    pub async fn paths(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        query: PathQuery<'_>,
+    ) -> Result<(Vec<GraphNode>, Vec<Vec<GraphEdge>>), RepositoryError> {
+        let PathQuery {
+            from,
+            to,
+            max_hops,
+            limit,
+        } = query;
+        let from_id = GraphEntity::id_of(from);
+        let to_id = GraphEntity::id_of(to);
+        let endpoints = self
+            .repository
+            .graph_nodes(
+                namespace,
+                repository,
+                index_name,
+                &[from_id.clone(), to_id.clone()],
+            )
+            .await?;
+        for (name, id) in [(from, &from_id), (to, &to_id)] {
+            if !endpoints.iter().any(|n| &n.id == id) {
+                return Err(RepositoryError::GraphEntityNotFound(name.into()));
+            }
+        }
+        let mut search = PathSearch::new(&from_id);
+        for hop in 1..=max_hops {
+            if search.reached(&to_id) || search.frontier.is_empty() {
+                break;
+            }
+            let edges = self
+                .repository
+                .graph_edges(
+                    namespace,
+                    repository,
+                    index_name,
+                    &search.frontier,
+                    None,
+                    MAX_EDGES_PER_HOP,
+                )
+                .await?;
+            search.expand(hop, edges);
+        }
+        let paths = search.paths(&to_id, limit);
+        let ids: Vec<String> = paths
+            .iter()
+            .flatten()
+            .flat_map(|e| [e.source_id.clone(), e.target_id.clone()])
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let nodes = self
+            .repository
+            .graph_nodes(namespace, repository, index_name, &ids)
+            .await?;
+        Ok((nodes, paths))
+    }
+}
+
+/// A breadth first search from an entity, which keeps every relationship
+/// which reaches an entity in the fewest hops, so that all the shortest
+/// paths to it can be listed.
+pub struct PathSearch {
+    from: String,
+    hops: HashMap<String, usize>,
+    predecessors: HashMap<String, Vec<GraphEdge>>,
+    frontier: Vec<String>,
+}
+
+impl PathSearch {
+    pub fn new(from: &str) -> Self {
+        Self {
+            from: from.into(),
+            hops: HashMap::from([(from.to_string(), 0)]),
+            predecessors: HashMap::new(),
+            frontier: vec![from.into()],
+        }
+    }
+
+    pub fn reached(&self, entity_id: &str) -> bool {
+        self.hops.contains_key(entity_id)
+    }
+
+    /// Follows the `edges` of the entities of the frontier, which are `hop`
+    /// hops away from the entities they reach.
+    pub fn expand(&mut self, hop: usize, edges: Vec<GraphEdge>) {
+        let frontier: HashSet<&String> = self.frontier.iter().collect();
+        let mut next = Vec::new();
+        for edge in edges {
+            for (from, to) in [
+                (&edge.source_id, &edge.target_id),
+                (&edge.target_id, &edge.source_id),
+            ] {
+                if !frontier.contains(from) {
+                    continue;
+                }
+                match self.hops.get(to) {
+                    Some(h) if *h < hop => continue,
+                    Some(_) => {}
+                    None => {
+                        self.hops.insert(to.clone(), hop);
+                        next.push(to.clone());
+                    }
+                }
+                self.predecessors
+                    .entry(to.clone())
+                    .or_default()
+                    .push(edge.clone());
+            }
+        }
+        self.frontier = next;
+    }
+
+    /// Up to `limit` shortest paths to the entity, as the relationships from
+    /// the entity the search started from.
+    pub fn paths(&self, to: &str, limit: usize) -> Vec<Vec<GraphEdge>> {
+        let mut paths = Vec::new();
+        if self.reached(to) && to != self.from {
+            let mut path = Vec::new();
+            self.collect_paths(to, &mut path, &mut paths, limit);
+        }
+        paths
+    }
+
+    fn collect_paths(
+        &self,
+        entity_id: &str,
+        path: &mut Vec<GraphEdge>,
+        paths: &mut Vec<Vec<GraphEdge>>,
+        limit: usize,
+    ) {
+        if paths.len() >= limit {
+            return;
+        }
+        if entity_id == self.from {
+            paths.push(path.iter().rev().cloned().collect());
+            return;
+        }
+        for edge in self.predecessors.get(entity_id).into_iter().flatten() {
+            path.push(edge.clone());
+            self.collect_paths(edge.other(entity_id), path, paths, limit);
+            path.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(source: &str, relation: &str, target: &str) -> GraphEdge {
+        GraphEdge {
+            source_id: source.into(),
+            relation: relation.into(),
+            target_id: target.into(),
+            content_ids: vec!["c1".into()],
+        }
+    }
+
+    fn edges_of(graph: &[GraphEdge], ids: &[String]) -> Vec<GraphEdge> {
+        graph
+            .iter()
+            .filter(|e| ids.contains(&e.source_id) || ids.contains(&e.target_id))
+            .cloned()
+            .collect()
+    }
+
+    /// The entities a path goes through.
+    fn entities(from: &str, path: &[GraphEdge]) -> Vec<String> {
+        let mut entities = vec![from.to_string()];
+        for edge in path {
+            let next = edge.other(entities.last().unwrap()).to_string();
+            entities.push(next);
+        }
+        entities
+    }
+
+    fn search(graph: &[GraphEdge], from: &str, to: &str, max_hops: usize) -> PathSearch {
+        let mut search = PathSearch::new(from);
+        for hop in 1..=max_hops {
+            if search.reached(to) {
+                break;
+            }
+            let edges = edges_of(graph, &search.frontier);
+            search.expand(hop, edges);
+        }
+        search
+    }
+
+    #[test]
+    fn test_path_search() {
+        let graph = vec![
+            edge("alice", "works_at", "acme"),
+            edge("bob", "works_at", "acme"),
+            edge("alice", "knows", "carol"),
+            edge("carol", "knows", "bob"),
+            edge("bob", "lives_in", "paris"),
+            edge("paris", "capital_of", "france"),
+            edge("dave", "lives_in", "berlin"),
+        ];
+        let mut paths: Vec<Vec<String>> = search(&graph, "alice", "paris", 4)
+            .paths("paris", 10)
+            .iter()
+            .map(|p| entities("alice", p))
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                vec!["alice", "acme", "bob", "paris"],
+                vec!["alice", "carol", "bob", "paris"],
+            ]
+        );
+        assert_eq!(
+            search(&graph, "alice", "paris", 4).paths("paris", 1).len(),
+            1
+        );
+        // Paths longer than the hops searched are not found.
+        assert!(search(&graph, "alice", "france", 3)
+            .paths("france", 10)
+            .is_empty());
+        assert!(search(&graph, "alice", "berlin", 4)
+            .paths("berlin", 10)
+            .is_empty());
+        assert!(search(&graph, "alice", "alice", 4)
+            .paths("alice", 10)
+            .is_empty());
+    }
+}
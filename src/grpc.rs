@@ -0,0 +1,602 @@
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+
+use anyhow::Result;
+use axum::http::{Method, StatusCode};
+use tonic::{Code, Request, Response, Status};
+use tracing::{info, warn};
+
+use crate::{
+    api::{self, IndexifyAPIError, Namespace},
+    audit,
+    auth::{AuthState, Principal},
+    data_repository_manager::{DataRepositoryError, DataRepositoryManager},
+    persistence::{self, Repository, RepositoryError},
+    query_throttle::QueryThrottle,
+    read_only,
+    server::{schedule_extraction, DEFAULT_SEARCH_LIMIT},
+    server_config::TlsConfig,
+    tls,
+    vector_index::SearchQuery,
+};
+
+pub mod proto {
+    tonic::include_proto!("indexify");
+}
+
+use proto::indexify_server::{Indexify, IndexifyServer};
+
+impl From<IndexifyAPIError> for Status {
+    fn from(value: IndexifyAPIError) -> Self {
+        let code = match value.status_code() {
+            StatusCode::BAD_REQUEST => Code::InvalidArgument,
+            StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+            StatusCode::FORBIDDEN => Code::PermissionDenied,
+            StatusCode::NOT_FOUND => Code::NotFound,
+            StatusCode::CONFLICT => Code::FailedPrecondition,
+            StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+            _ => Code::Internal,
+        };
+        Status::new(code, value.message())
+    }
+}
+
+fn value_to_json(value: prost_types::Value) -> serde_json::Value {
+    use prost_types::value::Kind;
+    match value.kind {
+        None | Some(Kind::NullValue(_)) => serde_json::Value::Null,
+        Some(Kind::BoolValue(b)) => serde_json::Value::Bool(b),
+        // Numbers are doubles in protobuf, whole numbers are read back as
+        // integers.
+        Some(Kind::NumberValue(n)) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => {
+            serde_json::Value::from(n as i64)
+        }
+        Some(Kind::NumberValue(n)) => serde_json::Number::from_f64(n)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        Some(Kind::StringValue(s)) => serde_json::Value::String(s),
+        Some(Kind::ListValue(list)) => {
+            serde_json::Value::Array(list.values.into_iter().map(value_to_json).collect())
+        }
+        Some(Kind::StructValue(s)) => {
+            serde_json::Value::Object(struct_to_json(Some(s)).into_iter().collect())
+        }
+    }
+}
+
+fn struct_to_json(value: Option<prost_types::Struct>) -> HashMap<String, serde_json::Value> {
+    value
+        .map(|s| {
+            s.fields
+                .into_iter()
+                .map(|(k, v)| (k, value_to_json(v)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn json_to_value(value: serde_json::Value) -> prost_types::Value {
+    use prost_types::value::Kind;
+    let kind = match value {
+        serde_json::Value::Null => Kind::NullValue(prost_types::NullValue::NullValue as i32),
+        serde_json::Value::Bool(b) => Kind::BoolValue(b),
+        serde_json::Value::Number(n) => Kind::NumberValue(n.as_f64().unwrap_or_default()),
+        serde_json::Value::String(s) => Kind::StringValue(s),
+        serde_json::Value::Array(values) => Kind::ListValue(prost_types::ListValue {
+            values: values.into_iter().map(json_to_value).collect(),
+        }),
+        serde_json::Value::Object(fields) => Kind::StructValue(json_to_struct(fields)),
+    };
+    prost_types::Value { kind: Some(kind) }
+}
+
+fn json_to_struct(
+    fields: impl IntoIterator<Item = (String, serde_json::Value)>,
+) -> prost_types::Struct {
+    prost_types::Struct {
+        fields: fields
+            .into_iter()
+            .map(|(k, v)| (k, json_to_value(v)))
+            .collect(),
+    }
+}
+
+/// The path of the HTTP request a call mirrors, which the call is
+/// authorized, checked against read-only mode and audited as.
+fn repository_path(repository: &str, operation: Option<&str>) -> Result<String, IndexifyAPIError> {
+    if repository.is_empty() || repository.contains('/') {
+        return Err(IndexifyAPIError::new(
+            StatusCode::BAD_REQUEST,
+            format!("invalid repository name: {:?}", repository),
+        ));
+    }
+    Ok(match operation {
+        Some(operation) => format!("/repositories/{}/{}", repository, operation),
+        None => format!("/repositories/{}", repository),
+    })
+}
+
+/// A call which was admitted, with what it is audited as.
+struct Call {
+    namespace: String,
+    principal: Option<Principal>,
+    method: Method,
+    path: String,
+}
+
+/// The gRPC API, which mirrors the main operations of the HTTP API. Every
+/// call goes through the same authorization, read-only check, query
+/// throttling and audit log as the HTTP request it mirrors.
+pub struct GrpcService {
+    repository: Arc<Repository>,
+    repository_manager: Arc<DataRepositoryManager>,
+    query_throttle: Arc<QueryThrottle>,
+    auth: Option<AuthState>,
+    read_only: bool,
+    coordinator_addr: String,
+}
+
+impl GrpcService {
+    pub fn new(
+        repository: Arc<Repository>,
+        repository_manager: Arc<DataRepositoryManager>,
+        query_throttle: Arc<QueryThrottle>,
+        auth: Option<AuthState>,
+        read_only: bool,
+        coordinator_addr: &str,
+    ) -> Self {
+        Self {
+            repository,
+            repository_manager,
+            query_throttle,
+            auth,
+            read_only,
+            coordinator_addr: coordinator_addr.into(),
+        }
+    }
+
+    pub async fn serve(
+        self,
+        addr: SocketAddr,
+        tls: Option<&TlsConfig>,
+        shutdown: impl std::future::Future<Output = ()>,
+    ) -> Result<()> {
+        info!("grpc server is listening at addr {}", addr);
+        let mut builder = tonic::transport::Server::builder();
+        if let Some(tls) = tls {
+            builder = builder.tls_config(tls::grpc_config(tls)?)?;
+        }
+        builder
+            .add_service(IndexifyServer::new(self))
+            .serve_with_shutdown(addr, shutdown)
+            .await?;
+        Ok(())
+    }
+
+    async fn admit<T>(
+        &self,
+        request: &Request<T>,
+        method: Method,
+        path: Result<String, IndexifyAPIError>,
+    ) -> Result<Call, IndexifyAPIError> {
+        let path = path?;
+        let headers = request.metadata().clone().into_headers();
+        let Namespace(namespace) = Namespace::from_headers(&headers)?;
+        if self.read_only && read_only::is_write(&method, &path) {
+            return Err(IndexifyAPIError::new(
+                StatusCode::FORBIDDEN,
+                "the server is in read-only mode".to_string(),
+            ));
+        }
+        let principal = match &self.auth {
+            Some(auth) => auth.authorize(&method, &path, &headers).await?,
+            None => None,
+        };
+        Ok(Call {
+            namespace,
+            principal,
+            method,
+            path,
+        })
+    }
+
+    /// Admits a call, runs it and records it in the audit log.
+    async fn handle<T, R, F>(
+        &self,
+        request: Request<T>,
+        method: Method,
+        path: Result<String, IndexifyAPIError>,
+        call: impl FnOnce(String, T) -> F,
+    ) -> Result<Response<R>, Status>
+    where
+        F: std::future::Future<Output = Result<R, IndexifyAPIError>>,
+    {
+        let admitted = self.admit(&request, method, path).await?;
+        let result = call(admitted.namespace.clone(), request.into_inner()).await;
+        let status = match &result {
+            Ok(_) => StatusCode::OK,
+            Err(e) => e.status_code(),
+        };
+        audit::record(
+            &self.repository,
+            &admitted.namespace,
+            admitted.principal.as_ref(),
+            &admitted.method,
+            &admitted.path,
+            status.as_u16(),
+        )
+        .await;
+        Ok(Response::new(result?))
+    }
+}
+
+fn manager_error(e: DataRepositoryError) -> IndexifyAPIError {
+    let status_code = match &e {
+        DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) |
+        DataRepositoryError::Persistence(RepositoryError::IndexNotFound(_)) => {
+            StatusCode::NOT_FOUND
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    IndexifyAPIError::new(status_code, e.to_string())
+}
+
+fn internal_error(e: anyhow::Error) -> IndexifyAPIError {
+    IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+fn repository_write_error(e: anyhow::Error) -> IndexifyAPIError {
+    let status_code = match e.downcast_ref::<RepositoryError>() {
+        Some(RepositoryError::RepositoryVersionConflict(_)) => StatusCode::CONFLICT,
+        Some(RepositoryError::RepositoryNotFound(_)) => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    IndexifyAPIError::new(status_code, e.to_string())
+}
+
+#[tonic::async_trait]
+impl Indexify for GrpcService {
+    async fn create_repository(
+        &self,
+        request: Request<proto::CreateRepositoryRequest>,
+    ) -> Result<Response<proto::CreateRepositoryResponse>, Status> {
+        let path = Ok("/repositories".to_string());
+        self.handle(
+            request,
+            Method::POST,
+            path,
+            |namespace, request| async move {
+                repository_path(&request.name, None)?;
+                let extractor_bindings = request
+                    .extractor_bindings
+                    .into_iter()
+                    .map(|b| {
+                        api::into_persistence_extractor_binding(
+                            &request.name,
+                            api::ExtractorBinding {
+                                extractor: b.extractor,
+                                name: b.name,
+                                filters: None,
+                                filter: None,
+                                input_params: b
+                                    .input_params
+                                    .map(|p| serde_json::json!(struct_to_json(Some(p)))),
+                                sinks: vec![],
+                                chunker: None,
+                                parent_chunker: None,
+                                max_concurrency: None,
+                                requests_per_minute: None,
+                                disabled: false,
+                                quantization: None,
+                                payload_indexes: vec![],
+                                content_types: vec![],
+                                // Bindings of the gRPC API have no policy of
+                                // their own, and reject invalid attributes.
+                                invalid_attributes: api::InvalidAttributesPolicy::default(),
+                            },
+                        )
+                    })
+                    .collect();
+                let data_repository = persistence::DataRepository {
+                    namespace,
+                    name: request.name.clone(),
+                    extractor_bindings,
+                    metadata: struct_to_json(request.metadata),
+                    dedup_policy: persistence::DedupPolicy::default(),
+                    retention: persistence::RetentionPolicy::default(),
+                    data_connectors: vec![],
+                    text_analysis: persistence::TextAnalysisConfig::default(),
+                    version: 0,
+                };
+                let version = self
+                    .repository_manager
+                    .update(&data_repository, request.expected_version)
+                    .await
+                    .map_err(repository_write_error)?;
+                Ok(proto::CreateRepositoryResponse { version })
+            },
+        )
+        .await
+    }
+
+    async fn get_repository(
+        &self,
+        request: Request<proto::GetRepositoryRequest>,
+    ) -> Result<Response<proto::GetRepositoryResponse>, Status> {
+        let path = repository_path(&request.get_ref().name, None);
+        self.handle(
+            request,
+            Method::GET,
+            path,
+            |namespace, request| async move {
+                let repository = self
+                    .repository_manager
+                    .get(&namespace, &request.name)
+                    .await
+                    .map_err(manager_error)?;
+                Ok(proto::GetRepositoryResponse {
+                    repository: Some(proto::Repository {
+                        name: repository.name,
+                        extractor_bindings: repository
+                            .extractor_bindings
+                            .into_iter()
+                            .map(|b| proto::ExtractorBinding {
+                                name: b.name,
+                                extractor: b.extractor,
+                                input_params: match b.input_params {
+                                    serde_json::Value::Object(params) => {
+                                        Some(json_to_struct(params))
+                                    }
+                                    _ => None,
+                                },
+                            })
+                            .collect(),
+                        metadata: Some(json_to_struct(repository.metadata)),
+                        version: repository.version,
+                    }),
+                })
+            },
+        )
+        .await
+    }
+
+    async fn add_texts(
+        &self,
+        request: Request<proto::AddTextsRequest>,
+    ) -> Result<Response<proto::AddTextsResponse>, Status> {
+        let path = repository_path(&request.get_ref().repository, Some("add_texts"));
+        self.handle(
+            request,
+            Method::POST,
+            path,
+            |namespace, request| async move {
+                let texts = request
+                    .texts
+                    .into_iter()
+                    .map(|t| {
+                        persistence::ContentPayload::from_text(
+                            &request.repository,
+                            &t.text,
+                            struct_to_json(t.metadata),
+                        )
+                    })
+                    .collect();
+                self.repository_manager
+                    .add_texts(&namespace, &request.repository, texts)
+                    .await
+                    .map_err(|e| {
+                        IndexifyAPIError::new(
+                            StatusCode::BAD_REQUEST,
+                            format!("failed to add text: {}", e),
+                        )
+                    })?;
+                Ok(proto::AddTextsResponse {})
+            },
+        )
+        .await
+    }
+
+    async fn search(
+        &self,
+        request: Request<proto::SearchRequest>,
+    ) -> Result<Response<proto::SearchResponse>, Status> {
+        let path = repository_path(&request.get_ref().repository, Some("search"));
+        self.handle(
+            request,
+            Method::POST,
+            path,
+            |namespace, request| async move {
+                let Some(_permit) = self
+                    .query_throttle
+                    .admit(&namespace, &request.repository)
+                    .await
+                else {
+                    warn!(
+                        "shed a query of repository {} in namespace {}, the backends are saturated",
+                        request.repository, namespace
+                    );
+                    return Err(IndexifyAPIError::new(
+                        StatusCode::TOO_MANY_REQUESTS,
+                        format!(
+                            "the queries of repository {} are throttled, retry later",
+                            request.repository
+                        ),
+                    ));
+                };
+                let results = self
+                    .repository_manager
+                    .search(
+                        &namespace,
+                        &request.repository,
+                        &request.index,
+                        &SearchQuery::Text(request.query.clone()),
+                        request.k.unwrap_or(DEFAULT_SEARCH_LIMIT),
+                        None,
+                        HashMap::new(),
+                        None,
+                        Default::default(),
+                    )
+                    .await
+                    .map_err(internal_error)?;
+                Ok(proto::SearchResponse {
+                    results: results
+                        .into_iter()
+                        .map(|r| proto::SearchResult {
+                            content_id: r.content_id,
+                            text: r.text,
+                            confidence_score: r.confidence_score,
+                            metadata: Some(json_to_struct(r.metadata)),
+                        })
+                        .collect(),
+                })
+            },
+        )
+        .await
+    }
+
+    async fn get_attributes(
+        &self,
+        request: Request<proto::GetAttributesRequest>,
+    ) -> Result<Response<proto::GetAttributesResponse>, Status> {
+        let path = repository_path(&request.get_ref().repository, Some("attributes"));
+        self.handle(
+            request,
+            Method::GET,
+            path,
+            |namespace, request| async move {
+                let attributes = self
+                    .repository_manager
+                    .attribute_lookup(
+                        &namespace,
+                        &request.repository,
+                        &request.index,
+                        request.content_id.as_ref(),
+                    )
+                    .await
+                    .map_err(internal_error)?;
+                Ok(proto::GetAttributesResponse {
+                    attributes: attributes
+                        .into_iter()
+                        .map(|a| proto::Attributes {
+                            id: a.id,
+                            content_id: a.content_id,
+                            extractor: a.extractor_name,
+                            attributes: match a.attributes {
+                                serde_json::Value::Object(fields) => Some(json_to_struct(fields)),
+                                _ => None,
+                            },
+                        })
+                        .collect(),
+                })
+            },
+        )
+        .await
+    }
+
+    async fn list_work(
+        &self,
+        request: Request<proto::ListWorkRequest>,
+    ) -> Result<Response<proto::ListWorkResponse>, Status> {
+        let path = repository_path(&request.get_ref().repository, Some("work"));
+        self.handle(
+            request,
+            Method::GET,
+            path,
+            |namespace, request| async move {
+                let params = api::ListParams {
+                    cursor: request.cursor,
+                    limit: request.limit,
+                };
+                let work_list = self
+                    .repository_manager
+                    .list_work(
+                        &namespace,
+                        &request.repository,
+                        params.cursor.as_deref(),
+                        params.limit() + 1,
+                    )
+                    .await
+                    .map_err(manager_error)?;
+                let total_estimate = self
+                    .repository_manager
+                    .count_work(&namespace, &request.repository)
+                    .await
+                    .map_err(manager_error)?;
+                let page =
+                    api::Page::from_keyset(work_list, &params, total_estimate, |w| w.id.clone());
+                Ok(proto::ListWorkResponse {
+                    work: page
+                        .items
+                        .into_iter()
+                        .map(|w| proto::Work {
+                            id: w.id,
+                            content_id: w.content_id,
+                            extractor: w.extractor,
+                            extractor_binding: w.extractor_binding,
+                            state: w.work_state.to_string(),
+                            executor_id: w.executor_id,
+                            items_processed: w.items_processed,
+                            percent: w.percent,
+                            last_error: w.last_error,
+                        })
+                        .collect(),
+                    next_cursor: page.next_cursor,
+                    total_estimate: page.total_estimate,
+                })
+            },
+        )
+        .await
+    }
+
+    async fn run_extractors(
+        &self,
+        request: Request<proto::RunExtractorsRequest>,
+    ) -> Result<Response<proto::RunExtractorsResponse>, Status> {
+        let path = repository_path(&request.get_ref().repository, Some("run_extractors"));
+        self.handle(
+            request,
+            Method::POST,
+            path,
+            |namespace, request| async move {
+                schedule_extraction(&namespace, &request.repository, &self.coordinator_addr)
+                    .await
+                    .map_err(internal_error)?;
+                Ok(proto::RunExtractorsResponse {})
+            },
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_struct_round_trip() {
+        let metadata: HashMap<String, serde_json::Value> = serde_json::from_value(json!({
+            "author": "alice",
+            "pages": 12,
+            "score": 0.5,
+            "draft": false,
+            "tags": ["q1", null],
+            "source": {"kind": "wiki"}
+        }))
+        .unwrap();
+        let value = json_to_struct(metadata.clone());
+        assert_eq!(struct_to_json(Some(value)), metadata);
+    }
+
+    #[test]
+    fn test_repository_path() {
+        assert_eq!(
+            repository_path("docs", Some("add_texts")).unwrap(),
+            "/repositories/docs/add_texts"
+        );
+        assert_eq!(repository_path("docs", None).unwrap(), "/repositories/docs");
+        // A name with a slash would be authorized as another operation.
+        assert!(repository_path("docs/search", Some("add_texts")).is_err());
+        assert!(repository_path("", None).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    fmt,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{blob_storage::BlobStorageTS, persistence::Repository, vectordbs::VectorDBTS};
+
+/// Dependencies which don't answer within this long are unhealthy, so that a
+/// probe is answered before Kubernetes gives up on it.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The result of checking a dependency of the server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DependencyHealth {
+    pub name: String,
+    /// The database, vector store or blob store behind the dependency, such
+    /// as `qdrant`.
+    pub backend: String,
+    pub healthy: bool,
+    pub latency: Duration,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthReport {
+    pub dependencies: Vec<DependencyHealth>,
+}
+
+impl HealthReport {
+    pub fn is_healthy(&self) -> bool {
+        self.dependencies.iter().all(|d| d.healthy)
+    }
+}
+
+/// Checks the database, the vector store and the blob store the server
+/// depends on, all of them at once.
+pub struct HealthChecker {
+    repository: Arc<Repository>,
+    vector_db: VectorDBTS,
+    blob_storage: BlobStorageTS,
+    blob_storage_backend: String,
+}
+
+impl fmt::Debug for HealthChecker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HealthChecker").finish()
+    }
+}
+
+impl HealthChecker {
+    pub fn new(
+        repository: Arc<Repository>,
+        vector_db: VectorDBTS,
+        blob_storage: BlobStorageTS,
+        blob_storage_backend: &str,
+    ) -> Self {
+        Self {
+            repository,
+            vector_db,
+            blob_storage,
+            blob_storage_backend: blob_storage_backend.into(),
+        }
+    }
+
+    pub async fn check(&self) -> HealthReport {
+        let vector_store_backend = self.vector_db.name();
+        let (database, vector_store, blob_store) = tokio::join!(
+            check_dependency(
+                "database",
+                "postgres",
+                HEALTH_CHECK_TIMEOUT,
+                self.repository.health_check()
+            ),
+            check_dependency(
+                "vector_store",
+                &vector_store_backend,
+                HEALTH_CHECK_TIMEOUT,
+                self.vector_db.health_check()
+            ),
+            check_dependency(
+                "blob_store",
+                &self.blob_storage_backend,
+                HEALTH_CHECK_TIMEOUT,
+                self.blob_storage.health_check()
+            ),
+        );
+        HealthReport {
+            dependencies: vec![database, vector_store, blob_store],
+        }
+    }
+}
+
+async fn check_dependency<E: fmt::Display>(
+    name: &str,
+    backend: &str,
+    timeout: Duration,
+    check: impl Future<Output = Result<(), E>>,
+) -> DependencyHealth {
+    let started = Instant::now();
+    let error = match tokio::time::timeout(timeout, check).await {
+        Ok(Ok(())) => None,
+        Ok(Err(err)) => Some(err.to_string()),
+        Err(_) => Some(format!("no answer within {}s", timeout.as_secs_f32())),
+    };
+    DependencyHealth {
+        name: name.into(),
+        backend: backend.into(),
+        healthy: error.is_none(),
+        latency: started.elapsed(),
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_check_dependency() {
+        let timeout = Duration::from_millis(50);
+        let healthy = check_dependency("database", "postgres", timeout, async {
+            Ok::<_, String>(())
+        })
+        .await;
+        assert!(healthy.healthy);
+        assert_eq!(healthy.error, None);
+
+        let failed = check_dependency("vector_store", "qdrant", timeout, async {
+            Err(anyhow!("connection refused"))
+        })
+        .await;
+        assert!(!failed.healthy);
+        assert_eq!(failed.error.as_deref(), Some("connection refused"));
+
+        let hung = check_dependency("blob_store", "disk", timeout, async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok::<_, String>(())
+        })
+        .await;
+        assert!(!hung.healthy);
+        assert_eq!(hung.error.as_deref(), Some("no answer within 0.05s"));
+
+        let report = HealthReport {
+            dependencies: vec![healthy.clone(), failed],
+        };
+        assert!(!report.is_healthy());
+        assert!(HealthReport {
+            dependencies: vec![healthy]
+        }
+        .is_healthy());
+    }
+}
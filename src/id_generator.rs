@@ -0,0 +1,171 @@
+use std::{
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::server_config::{IdConfig, IdStrategy};
+
+/// Milliseconds from the unix epoch to 2023-01-01, the epoch of the snowflake
+/// ids. 41 bits of milliseconds last for about 69 years from there.
+const SNOWFLAKE_EPOCH_MS: u64 = 1_672_531_200_000;
+const SNOWFLAKE_NODE_BITS: u32 = 10;
+const SNOWFLAKE_SEQUENCE_BITS: u32 = 12;
+
+static CONFIG: OnceLock<IdConfig> = OnceLock::new();
+
+/// The last snowflake id handed out, without the node id.
+static LAST_SNOWFLAKE: AtomicU64 = AtomicU64::new(0);
+
+/// Selects how the ids of content, chunks and work are generated by this
+/// process. Ids generated before are kept by their objects, so this is called
+/// once at startup.
+pub fn configure(config: &IdConfig) {
+    if CONFIG.set(config.clone()).is_err() && CONFIG.get() != Some(config) {
+        warn!(
+            "the id strategy is already configured, ignoring {:?}",
+            config
+        );
+    }
+}
+
+fn config() -> &'static IdConfig {
+    CONFIG.get_or_init(IdConfig::default)
+}
+
+/// A new id for an object identified by `key`. The hash strategy derives the
+/// id from the key, so adding the same object again gives it the same id. The
+/// other strategies give every object a new id, in the order they are made.
+pub fn generate_id(key: impl Hash) -> String {
+    let config = config();
+    match config.strategy {
+        IdStrategy::Hash => hash_id(key),
+        IdStrategy::UuidV7 => Uuid::now_v7().to_string(),
+        IdStrategy::Snowflake => format!("{:x}", snowflake_id(config.node_id)),
+    }
+}
+
+/// The first 64 bits of the sha256 of the key. Hash ids are stored, so unlike
+/// `DefaultHasher`, whose algorithm may change between Rust releases, the
+/// hash must not change between releases or platforms.
+fn hash_id(key: impl Hash) -> String {
+    let mut s = Sha256Hasher::default();
+    key.hash(&mut s);
+    format!("{:x}", s.finish())
+}
+
+/// Feeds what a key hashes into sha256. Integers are written as little endian
+/// and `usize`s as 64 bits, so that the hash is the same on every platform.
+#[derive(Default)]
+struct Sha256Hasher(Sha256);
+
+impl Hasher for Sha256Hasher {
+    fn finish(&self) -> u64 {
+        let digest = self.0.clone().finalize();
+        u64::from_be_bytes(digest[..8].try_into().unwrap())
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as u64);
+    }
+}
+
+/// 41 bits of milliseconds since the snowflake epoch, 10 bits of node id and
+/// 12 bits of sequence within the millisecond. When the sequence of a
+/// millisecond runs out the ids borrow from the next millisecond, so they stay
+/// unique and increasing.
+fn snowflake_id(node_id: u16) -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    let timestamp = now.saturating_sub(SNOWFLAKE_EPOCH_MS) << SNOWFLAKE_SEQUENCE_BITS;
+    let mut last = LAST_SNOWFLAKE.load(Ordering::Relaxed);
+    let id = loop {
+        let next = timestamp.max(last + 1);
+        match LAST_SNOWFLAKE.compare_exchange_weak(last, next, Ordering::Relaxed, Ordering::Relaxed)
+        {
+            Ok(_) => break next,
+            Err(current) => last = current,
+        }
+    };
+    let node_id = node_id as u64 & ((1 << SNOWFLAKE_NODE_BITS) - 1);
+    let timestamp = id >> SNOWFLAKE_SEQUENCE_BITS;
+    let sequence = id & ((1 << SNOWFLAKE_SEQUENCE_BITS) - 1);
+    (timestamp << (SNOWFLAKE_NODE_BITS + SNOWFLAKE_SEQUENCE_BITS)) |
+        (node_id << SNOWFLAKE_SEQUENCE_BITS) |
+        sequence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_id_is_stable() {
+        // A str hashes as its bytes followed by 0xff.
+        let digest = Sha256::digest(b"docs\xffhello\xff");
+        assert_eq!(
+            hash_id(("docs", "hello")),
+            format!("{:x}", u64::from_be_bytes(digest[..8].try_into().unwrap()))
+        );
+        assert_eq!(hash_id(("docs", 7usize)), hash_id(("docs", 7u64)));
+        assert_ne!(hash_id(("docs", "hello")), hash_id(("docs", "world")));
+    }
+
+    #[test]
+    fn test_snowflake_ids_increase() {
+        let ids: Vec<u64> = (0..10_000).map(|_| snowflake_id(3)).collect();
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert!(ids
+            .iter()
+            .all(|id| (id >> SNOWFLAKE_SEQUENCE_BITS) & ((1 << SNOWFLAKE_NODE_BITS) - 1) == 3));
+    }
+}
@@ -0,0 +1,181 @@
+use std::{
+    fmt,
+    future::Future,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use axum::http::StatusCode;
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
+
+use crate::{
+    api::IndexifyAPIError,
+    persistence::{IdempotencyClaim, Repository},
+    server_config::IdempotencyConfig,
+};
+
+/// Identifies a request by the operation it calls and its body, so that a key
+/// reused for a different request is told apart from a retry. The body is
+/// hashed as a JSON value, whose objects have sorted keys, so that the order of
+/// the fields of the request does not matter.
+pub fn request_hash(operation: &str, request: &impl Serialize) -> String {
+    let body = serde_json::to_value(request)
+        .map(|v| v.to_string())
+        .unwrap_or_default();
+    let mut hasher = Sha256::new();
+    hasher.update(operation.as_bytes());
+    hasher.update([0]);
+    hasher.update(body.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Runs requests with an idempotency key once, and answers their retries with
+/// the response of the first request, and deletes the keys once they expire.
+pub struct IdempotencyKeys {
+    repository: Arc<Repository>,
+    config: IdempotencyConfig,
+}
+
+impl fmt::Debug for IdempotencyKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdempotencyKeys").finish()
+    }
+}
+
+impl IdempotencyKeys {
+    pub fn new(repository: Arc<Repository>, config: IdempotencyConfig) -> Self {
+        Self { repository, config }
+    }
+
+    /// Runs `request`, unless a request with the same key already ran, in
+    /// which case its response is returned. A request which fails releases
+    /// its key, so that it can be retried.
+    pub async fn run<T, F>(
+        &self,
+        namespace: &str,
+        repository: &str,
+        key: Option<&str>,
+        request_hash: &str,
+        request: F,
+    ) -> Result<T, IndexifyAPIError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<T, IndexifyAPIError>>,
+    {
+        let Some(key) = key else {
+            return request.await;
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let claim = self
+            .repository
+            .claim_idempotency_key(
+                namespace,
+                repository,
+                key,
+                request_hash,
+                now,
+                now.saturating_sub(self.config.retention_secs),
+            )
+            .await
+            .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        match claim {
+            IdempotencyClaim::Claimed => {}
+            IdempotencyClaim::Completed(response) => {
+                return serde_json::from_value(response).map_err(|e| {
+                    IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                });
+            }
+            IdempotencyClaim::InProgress => {
+                return Err(IndexifyAPIError::new(
+                    StatusCode::CONFLICT,
+                    format!("a request with idempotency key {} is in progress", key),
+                ));
+            }
+            IdempotencyClaim::Mismatch => {
+                return Err(IndexifyAPIError::new(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    format!("idempotency key {} was used for a different request", key),
+                ));
+            }
+        }
+        let result = request.await;
+        let recorded = match &result {
+            Ok(response) => {
+                let response = serde_json::to_value(response).unwrap_or_default();
+                self.repository
+                    .complete_idempotency_key(namespace, repository, key, response)
+                    .await
+            }
+            Err(_) => {
+                self.repository
+                    .release_idempotency_key(namespace, repository, key)
+                    .await
+            }
+        };
+        // The request already ran. A key whose response could not be recorded
+        // answers its retries as in progress until it expires.
+        if let Err(err) = recorded {
+            error!(
+                "unable to record idempotency key {} of repository {}: {}",
+                key,
+                repository,
+                err.to_string()
+            );
+        }
+        result
+    }
+
+    pub async fn start(self: Arc<Self>) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.purge_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.purge().await {
+                error!("unable to purge idempotency keys: {}", err.to_string());
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn purge(&self) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let purged = self
+            .repository
+            .purge_idempotency_keys(now.saturating_sub(self.config.retention_secs))
+            .await?;
+        if purged > 0 {
+            info!("purged {} expired idempotency keys", purged);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_request_hash() {
+        let hash = request_hash("add_texts", &json!({"a": 1, "b": 2}));
+        assert_eq!(
+            hash,
+            request_hash(
+                "add_texts",
+                &serde_json::from_str::<serde_json::Value>(r#"{"b": 2, "a": 1}"#).unwrap()
+            )
+        );
+        assert_ne!(hash, request_hash("add_events", &json!({"a": 1, "b": 2})));
+        assert_ne!(hash, request_hash("add_texts", &json!({"a": 1, "b": 3})));
+    }
+}
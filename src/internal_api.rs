@@ -8,14 +8,26 @@ use strum::{Display, EnumString};
 
 use crate::{
     api,
+    chunking,
     persistence::{self, EmbeddingSchema},
+    telemetry::TraceContext,
     vectordbs::IndexDistance,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum OutputSchema {
-    Embedding { dim: usize, distance_metric: String },
+    Embedding {
+        dim: usize,
+        distance_metric: String,
+        #[serde(default)]
+        multi_vector: bool,
+        /// Text only by default, text and images for CLIP-style extractors.
+        #[serde(default = "persistence::default_modalities")]
+        modalities: Vec<persistence::Modality>,
+    },
     Feature(serde_json::Value),
+    Graph(persistence::GraphSchema),
+    Summary(persistence::SummarySchema),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +53,8 @@ impl TryFrom<ExtractorDescription> for persistence::Extractor {
                 OutputSchema::Embedding {
                     dim,
                     distance_metric,
+                    multi_vector,
+                    modalities,
                 } => {
                     let distance = IndexDistance::from_str(&distance_metric)?;
                     output_schema.insert(
@@ -48,6 +62,10 @@ impl TryFrom<ExtractorDescription> for persistence::Extractor {
                         persistence::ExtractorOutputSchema::Embedding(EmbeddingSchema {
                             dim,
                             distance,
+                            quantization: None,
+                            multi_vector,
+                            payload_indexes: vec![],
+                            modalities,
                         }),
                     );
                 }
@@ -59,6 +77,18 @@ impl TryFrom<ExtractorDescription> for persistence::Extractor {
                         ),
                     );
                 }
+                OutputSchema::Graph(schema) => {
+                    output_schema.insert(
+                        output_name,
+                        persistence::ExtractorOutputSchema::Graph(schema),
+                    );
+                }
+                OutputSchema::Summary(schema) => {
+                    output_schema.insert(
+                        output_name,
+                        persistence::ExtractorOutputSchema::Summary(schema),
+                    );
+                }
             }
         }
         Ok(Self {
@@ -84,12 +114,20 @@ impl From<persistence::Extractor> for ExtractorDescription {
                         OutputSchema::Embedding {
                             dim: schema.dim,
                             distance_metric,
+                            multi_vector: schema.multi_vector,
+                            modalities: schema.modalities,
                         },
                     );
                 }
                 persistence::ExtractorOutputSchema::Attributes(schema) => {
                     output_schema.insert(output_name, OutputSchema::Feature(schema.schema));
                 }
+                persistence::ExtractorOutputSchema::Graph(schema) => {
+                    output_schema.insert(output_name, OutputSchema::Graph(schema));
+                }
+                persistence::ExtractorOutputSchema::Summary(schema) => {
+                    output_schema.insert(output_name, OutputSchema::Summary(schema));
+                }
             }
         }
         Self {
@@ -160,6 +198,34 @@ pub struct WorkStatus {
     pub work_id: String,
     pub status: WorkState,
     pub extracted_content: Vec<Content>,
+    /// Where the chunk each extracted content was extracted from is in the
+    /// content of the work, in the order of `extracted_content`, when the
+    /// binding of the work has a chunker.
+    #[serde(default)]
+    pub chunk_origins: Vec<chunking::ChunkOrigin>,
+    /// The items, such as chunks, extracted so far.
+    #[serde(default)]
+    pub items_processed: Option<u64>,
+    /// How much of the work is done, from 0 to 100.
+    #[serde(default)]
+    pub percent: Option<f64>,
+    /// Lines logged while doing the work, appended to its log.
+    #[serde(default)]
+    pub log: Vec<String>,
+    /// Why the work failed.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl From<&WorkStatus> for persistence::WorkReport {
+    fn from(work_status: &WorkStatus) -> Self {
+        Self {
+            items_processed: work_status.items_processed,
+            percent: work_status.percent,
+            log: work_status.log.clone(),
+            error: work_status.error.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -175,6 +241,34 @@ pub struct ListExecutors {
     pub executors: Vec<ExecutorInfo>,
 }
 
+/// The queue pressure on the executors of an extractor, for autoscalers like
+/// KEDA or the HPA. An extractor nothing is known about reports zeros, so that
+/// a fleet scaled to zero executors reads an empty queue rather than an error.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtractorLoad {
+    pub extractor: String,
+    /// Work which is waiting to be assigned to an executor.
+    pub pending_work: u64,
+    /// Work which is assigned to an executor and not finished yet.
+    pub in_flight_work: u64,
+    /// Executors of the extractor which have synced with the coordinator.
+    pub executors: u64,
+    /// Pending and in flight work per executor, or the pending and in flight
+    /// work when there are no executors.
+    pub work_per_executor: f64,
+    /// Mean seconds from the assignment to the completion of the work
+    /// finished in the last `window_secs`, 0 when no work finished.
+    pub average_work_duration_secs: f64,
+    /// Seconds the oldest pending work has been waiting, 0 when none is.
+    pub backlog_age_secs: u64,
+    pub window_secs: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct AutoscalingMetrics {
+    pub extractors: Vec<ExtractorLoad>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct ListExtractors {
     pub extractors: Vec<ExtractorDescription>,
@@ -187,6 +281,7 @@ pub struct SyncWorkerResponse {
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct CreateWork {
+    pub namespace: String,
     pub repository_name: String,
     pub content: Option<String>,
 }
@@ -202,6 +297,12 @@ pub enum FeatureType {
     NamedEntity,
     #[strum(serialize = "metadata")]
     Metadata,
+    /// Entities and relationships, read as an `ExtractedGraph`.
+    #[strum(serialize = "graph")]
+    Graph,
+    /// The summary of the content, as a string.
+    #[strum(serialize = "summary")]
+    Summary,
     #[strum(serialize = "unknown")]
     Unknown,
 }
@@ -212,6 +313,8 @@ impl From<FeatureType> for api::FeatureType {
             FeatureType::Embedding => api::FeatureType::Embedding,
             FeatureType::NamedEntity => api::FeatureType::NamedEntity,
             FeatureType::Metadata => api::FeatureType::Metadata,
+            FeatureType::Graph => api::FeatureType::Graph,
+            FeatureType::Summary => api::FeatureType::Summary,
             FeatureType::Unknown => api::FeatureType::Unknown,
         }
     }
@@ -232,12 +335,35 @@ impl Feature {
         }
     }
 
+    /// The vectors of an embedding of an extractor with a multi-vector
+    /// schema, which are sent as the same feature type as single embeddings.
+    pub fn multi_vector(&self) -> Option<Vec<Vec<f32>>> {
+        match self.feature_type {
+            FeatureType::Embedding => serde_json::from_value(self.data.clone()).ok(),
+            _ => None,
+        }
+    }
+
     pub fn metadata(&self) -> Option<serde_json::Value> {
         match self.feature_type {
             FeatureType::Metadata | FeatureType::NamedEntity => Some(self.data.clone()),
             _ => None,
         }
     }
+
+    pub fn graph(&self) -> Option<persistence::ExtractedGraph> {
+        match self.feature_type {
+            FeatureType::Graph => serde_json::from_value(self.data.clone()).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn summary(&self) -> Option<String> {
+        match self.feature_type {
+            FeatureType::Summary => self.data.as_str().map(|s| s.to_string()),
+            _ => None,
+        }
+    }
 }
 
 #[serde_as]
@@ -247,6 +373,10 @@ pub struct Content {
     #[serde_as(as = "BytesOrString")]
     pub source: Vec<u8>,
     pub feature: Option<Feature>,
+    /// Added to the metadata of the content the extracted content is derived
+    /// from, such as the page of a document its text was extracted from.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, serde_json::Value>,
 }
 
 impl Content {
@@ -275,11 +405,26 @@ impl From<Content> for api::Content {
     }
 }
 
+/// How the bytes of content are encoded in `ContentPayload::content`.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentEncoding {
+    #[default]
+    Utf8,
+    Base64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ContentPayload {
     pub content_type: String,
     pub content: String,
     pub external_url: Option<String>,
+    #[serde(default)]
+    pub encoding: ContentEncoding,
+    /// The metadata of the content, which chunkers read the segments of
+    /// transcripts from.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, serde_json::Value>,
 }
 
 impl TryFrom<persistence::ContentPayload> for ContentPayload {
@@ -287,14 +432,23 @@ impl TryFrom<persistence::ContentPayload> for ContentPayload {
 
     fn try_from(payload: persistence::ContentPayload) -> Result<Self> {
         let content_type = payload.content_type.to_string();
-        let (external_url, content) = match payload.payload_type {
-            persistence::PayloadType::BlobStorageLink => (Some(payload.payload), "".to_string()),
-            _ => (None, payload.payload),
+        let (external_url, content, encoding) = match payload.payload_type {
+            persistence::PayloadType::BlobStorageLink => {
+                (Some(payload.payload), "".to_string(), ContentEncoding::Utf8)
+            }
+            persistence::PayloadType::InlineBlob => {
+                (None, payload.payload, ContentEncoding::Base64)
+            }
+            persistence::PayloadType::EmbeddedStorage => {
+                (None, payload.payload, ContentEncoding::Utf8)
+            }
         };
         Ok(Self {
             content_type,
             content,
             external_url,
+            encoding,
+            metadata: payload.metadata,
         })
     }
 }
@@ -302,18 +456,39 @@ impl TryFrom<persistence::ContentPayload> for ContentPayload {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Work {
     pub id: String,
+    /// The extractor the work is done with.
+    #[serde(default)]
+    pub extractor: String,
     pub content_payload: ContentPayload,
     pub params: serde_json::Value,
+    #[serde(default)]
+    pub trace_context: TraceContext,
+    /// How the text of the content is split into chunks before it is
+    /// extracted, the chunker of the binding of the work.
+    #[serde(default)]
+    pub chunker: Option<persistence::Chunker>,
+    /// How the content is split into parent chunks before they are split by
+    /// the chunker.
+    #[serde(default)]
+    pub parent_chunker: Option<persistence::Chunker>,
 }
 
 pub fn create_work(
     work: persistence::Work,
     content_payload: persistence::ContentPayload,
+    binding: Option<persistence::ExtractorBinding>,
 ) -> Result<Work> {
     let content_payload = ContentPayload::try_from(content_payload)?;
+    let (chunker, parent_chunker) = binding
+        .map(|binding| (binding.chunker, binding.parent_chunker))
+        .unwrap_or_default();
     Ok(Work {
         id: work.id,
+        extractor: work.extractor,
         content_payload,
         params: work.extractor_params,
+        trace_context: work.trace_context,
+        chunker,
+        parent_chunker,
     })
 }
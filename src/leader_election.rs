@@ -0,0 +1,84 @@
+use std::{sync::Arc, time::Duration};
+
+use tracing::{error, info, warn};
+
+use crate::{
+    coordinator::Coordinator,
+    internal_api::CreateWork,
+    persistence::{AdvisoryLock, Repository},
+    server_config::LeaderElectionConfig,
+};
+
+/// Elects one of the coordinators sharing a database as the leader, which
+/// processes the extraction events and distributes the work, so that events
+/// are not processed twice and work is not assigned twice. The leader is the
+/// coordinator holding an advisory lock of the database. The database
+/// releases the lock when the session of the leader ends, and a standby takes
+/// it over on its next try.
+pub struct LeaderElection {
+    repository: Arc<Repository>,
+    coordinator: Arc<Coordinator>,
+    config: LeaderElectionConfig,
+}
+
+impl LeaderElection {
+    pub fn new(
+        repository: Arc<Repository>,
+        coordinator: Arc<Coordinator>,
+        config: LeaderElectionConfig,
+    ) -> Self {
+        // Stands by until the lock is taken.
+        coordinator.set_leader(false);
+        Self {
+            repository,
+            coordinator,
+            config,
+        }
+    }
+
+    pub async fn start(self) {
+        let check_interval = Duration::from_secs(self.config.check_interval_secs.max(1));
+        info!(
+            "standing by until elected as the leader of the coordinators, lock {}",
+            self.config.lock_key
+        );
+        loop {
+            match self
+                .repository
+                .try_advisory_lock(self.config.lock_key)
+                .await
+            {
+                Ok(Some(lock)) => {
+                    self.lead(lock, check_interval).await;
+                    warn!("lost the leadership of the coordinators, standing by");
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    error!(
+                        "unable to take the leadership of the coordinators: {}",
+                        err.to_string()
+                    );
+                }
+            }
+            tokio::time::sleep(check_interval).await;
+        }
+    }
+
+    /// Leads until the session holding the lock ends.
+    async fn lead(&self, mut lock: AdvisoryLock, check_interval: Duration) {
+        info!("elected as the leader of the coordinators");
+        self.coordinator.set_leader(true);
+        // The events added and the work left by the previous leader are
+        // picked up right away.
+        if let Err(err) = self.coordinator.publish_work(CreateWork::default()).await {
+            error!("unable to start processing work: {}", err.to_string());
+        }
+        loop {
+            tokio::time::sleep(check_interval).await;
+            if !lock.is_held().await {
+                break;
+            }
+        }
+        self.coordinator.set_leader(false);
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::Hash,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Entries beyond this many are pruned of the expired ones, and the cache is
+/// cleared when none of them have expired, so that lookups of names which come
+/// and go do not grow the cache without bound.
+const MAX_ENTRIES: usize = 10_000;
+
+/// Caches the rows looked up on the hot path of the work, such as
+/// repositories, extractors and bindings, for a while. Entries are forgotten
+/// by the writes of this process; writes made by other processes sharing the
+/// database are seen once the entries expire. A ttl of zero disables the
+/// cache.
+pub struct LookupCache<K, V> {
+    ttl: Duration,
+    entries: Mutex<HashMap<K, (V, Instant)>>,
+}
+
+impl<K, V> fmt::Debug for LookupCache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LookupCache")
+            .field("ttl", &self.ttl)
+            .field("entries", &self.entries.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl<K: Eq + Hash, V: Clone> LookupCache<K, V> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.get_at(key, Instant::now())
+    }
+
+    fn get_at(&self, key: &K, now: Instant) -> Option<V> {
+        let entries = self.entries.lock().unwrap();
+        let (value, cached_at) = entries.get(key)?;
+        (now.saturating_duration_since(*cached_at) < self.ttl).then(|| value.clone())
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        self.insert_at(key, value, Instant::now())
+    }
+
+    fn insert_at(&self, key: K, value: V, now: Instant) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_ENTRIES {
+            entries
+                .retain(|_, (_, cached_at)| now.saturating_duration_since(*cached_at) < self.ttl);
+            if entries.len() >= MAX_ENTRIES {
+                entries.clear();
+            }
+        }
+        entries.insert(key, (value, now));
+    }
+
+    /// Forgets the entries whose keys match.
+    pub fn invalidate(&self, matches: impl Fn(&K) -> bool) {
+        self.entries.lock().unwrap().retain(|key, _| !matches(key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_expire() {
+        let cache = LookupCache::new(Duration::from_secs(30));
+        let now = Instant::now();
+        cache.insert_at("repository", 1, now);
+        assert_eq!(cache.get_at(&"repository", now), Some(1));
+        assert_eq!(
+            cache.get_at(&"repository", now + Duration::from_secs(29)),
+            Some(1)
+        );
+        assert_eq!(
+            cache.get_at(&"repository", now + Duration::from_secs(30)),
+            None
+        );
+        assert_eq!(cache.get_at(&"extractor", now), None);
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let cache = LookupCache::new(Duration::from_secs(30));
+        cache.insert(("default", "docs", "embeddings"), 1);
+        cache.insert(("default", "docs", "entities"), 2);
+        cache.insert(("default", "notes", "embeddings"), 3);
+        cache.invalidate(|(namespace, repository, _)| {
+            *namespace == "default" && *repository == "docs"
+        });
+        assert_eq!(cache.get(&("default", "docs", "embeddings")), None);
+        assert_eq!(cache.get(&("default", "docs", "entities")), None);
+        assert_eq!(cache.get(&("default", "notes", "embeddings")), Some(3));
+    }
+
+    #[test]
+    fn test_zero_ttl_disables() {
+        let cache = LookupCache::new(Duration::ZERO);
+        cache.insert("repository", 1);
+        assert_eq!(cache.get(&"repository"), None);
+    }
+}
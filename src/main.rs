@@ -1,10 +1,6 @@
-use clap::Parser;
-use tracing_core::{Level, LevelFilter};
-use tracing_subscriber::{
-    prelude::__tracing_subscriber_SubscriberExt,
-    util::SubscriberInitExt,
-    Layer,
-};
+use std::process::ExitCode;
+
+use tracing_subscriber::{prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt};
 
 pub mod coordinator_service;
 pub mod executor_server;
@@ -14,33 +10,78 @@ pub mod server;
 pub mod server_config;
 
 mod api;
+mod archive;
 mod attribute_index;
+mod attribute_query;
+mod audit;
+mod auth;
 mod blob_storage;
+mod body_limit;
+mod builtin_extractors;
+mod chunking;
 mod cmd;
+mod config_watcher;
+mod connectors;
 mod content_reader;
 mod coordinator;
+mod dashboard;
 mod data_repository_manager;
+mod embedding_cache;
 mod entity;
+mod event_compaction;
+mod event_consumers;
 mod executor;
+mod export;
 mod extractor_router;
+#[cfg(feature = "fault-injection")]
+mod fault_injection;
+mod filter;
+mod graph_index;
+mod grpc;
+mod health;
+mod id_generator;
+mod idempotency;
 mod index;
 mod internal_api;
+mod leader_election;
+mod lookup_cache;
+mod manifest;
+mod memory;
 mod persistence;
+mod progress;
+mod query_throttle;
+mod rate_limit;
+mod read_only;
+mod repository_sync;
+mod rerank;
+mod retention;
+mod sinks;
+mod summary_index;
+mod telemetry;
 mod test_util;
+mod text_analysis;
+mod tls;
+mod vector_collections;
+mod vector_import;
 mod vector_index;
 mod vectordbs;
+mod work_scheduler;
 mod work_store;
 
 struct OtelGuard;
 
 impl OtelGuard {
     fn new() -> Self {
+        let otlp_tracer = telemetry::otlp_tracer().unwrap_or_else(|err| {
+            eprintln!("unable to install the otlp exporter: {}", err);
+            None
+        });
+        // The levels of the logs are set by the config once it is read, and
+        // when the config file changes.
         tracing_subscriber::registry()
-            .with(
-                tracing_subscriber::fmt::layer()
-                    .with_writer(std::io::stderr)
-                    .with_filter(LevelFilter::from_level(Level::INFO)),
-            )
+            .with(telemetry::log_filter())
+            .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+            .with(otlp_tracer.map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer)))
             .init();
 
         OtelGuard
@@ -71,10 +112,13 @@ pub mod prelude {
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     // When this guard is dropped (at the end of this function, by default), the
     // opentelemetry tracer is automatically shut down.
     let _otel_guard = OtelGuard::new();
 
-    cmd::Cli::parse().run().await;
+    match cmd::Cli::parse_args() {
+        Ok(cli) => cli.run().await,
+        Err(exit_code) => exit_code,
+    }
 }
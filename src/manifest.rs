@@ -0,0 +1,396 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use bytes::Bytes;
+use serde_json::json;
+use tracing::{error, info};
+
+use crate::{
+    blob_storage::BlobStorageBuilder,
+    data_repository_manager::{DataRepositoryManager, IngestedFile},
+    persistence::{ContentPayload, IngestionJob, IngestionRowError, ManifestFormat, Repository},
+    server_config::ManifestConfig,
+};
+
+/// The source of the content added by the rows of manifests.
+pub const MANIFEST_CONTENT_SOURCE: &str = "manifest";
+
+/// What a row of a manifest adds to the repository.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ManifestSource {
+    Text(String),
+    /// An `http` or `https` link to a file.
+    Url(String),
+}
+
+/// A row of a manifest. Every column other than `text` and `url` is metadata
+/// of the content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestRow {
+    pub source: ManifestSource,
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+/// Parses the rows of a manifest. A row which can not be parsed is returned
+/// as its error, so that the other rows are still ingested. Blank lines of
+/// JSONL manifests are not rows.
+pub fn parse(format: ManifestFormat, data: &[u8]) -> Result<Vec<Result<ManifestRow, String>>> {
+    let data = std::str::from_utf8(data).map_err(|e| anyhow!("manifest is not utf-8: {}", e))?;
+    match format {
+        ManifestFormat::Jsonl => Ok(data
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(parse_json_row)
+            .collect()),
+        ManifestFormat::Csv => parse_csv(data),
+    }
+}
+
+fn parse_json_row(line: &str) -> Result<ManifestRow, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(line).map_err(|e| format!("invalid json: {}", e))?;
+    let serde_json::Value::Object(mut object) = value else {
+        return Err("row is not a json object".into());
+    };
+    let text = object.remove("text");
+    let url = object.remove("url");
+    let source = match (text, url) {
+        (Some(serde_json::Value::String(text)), None) => ManifestSource::Text(text),
+        (None, Some(serde_json::Value::String(url))) => ManifestSource::Url(url),
+        (Some(_), Some(_)) => return Err("row has both a text and a url".into()),
+        (None, None) => return Err("row has neither a text nor a url".into()),
+        _ => return Err("text and url have to be strings".into()),
+    };
+    Ok(ManifestRow {
+        source,
+        metadata: object.into_iter().collect(),
+    })
+}
+
+fn parse_csv(data: &str) -> Result<Vec<Result<ManifestRow, String>>> {
+    let mut records = csv_records(data)?.into_iter();
+    let header = records
+        .next()
+        .ok_or(anyhow!("manifest has no header row"))?;
+    if !header
+        .iter()
+        .any(|column| column == "text" || column == "url")
+    {
+        return Err(anyhow!("manifest has neither a text nor a url column"));
+    }
+    Ok(records
+        .map(|record| {
+            if record.len() != header.len() {
+                return Err(format!(
+                    "row has {} columns, the header has {}",
+                    record.len(),
+                    header.len()
+                ));
+            }
+            let mut text = None;
+            let mut url = None;
+            let mut metadata = HashMap::new();
+            for (column, value) in header.iter().zip(record) {
+                if value.is_empty() {
+                    continue;
+                }
+                match column.as_str() {
+                    "text" => text = Some(value),
+                    "url" => url = Some(value),
+                    _ => {
+                        metadata.insert(column.clone(), json!(value));
+                    }
+                }
+            }
+            let source = match (text, url) {
+                (Some(text), None) => ManifestSource::Text(text),
+                (None, Some(url)) => ManifestSource::Url(url),
+                (Some(_), Some(_)) => return Err("row has both a text and a url".into()),
+                (None, None) => return Err("row has neither a text nor a url".into()),
+            };
+            Ok(ManifestRow { source, metadata })
+        })
+        .collect())
+}
+
+/// Splits CSV into records of fields. Fields may be quoted, with `""` for a
+/// quote, and quoted fields may span lines. Blank lines are skipped.
+fn csv_records(data: &str) -> Result<Vec<Vec<String>>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = data.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                if !record.is_empty() || !field.is_empty() {
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                }
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(anyhow!("manifest ends inside a quoted field"));
+    }
+    if !record.is_empty() || !field.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Runs the ingestion jobs of manifests in the background. A job records its
+/// progress after every batch of rows, and a job which stops, because its
+/// server died or on an error, resumes from the first batch it has not
+/// recorded.
+pub struct ManifestIngestion {
+    repository: Arc<Repository>,
+    repository_manager: Arc<DataRepositoryManager>,
+    config: ManifestConfig,
+    client: reqwest::Client,
+}
+
+impl ManifestIngestion {
+    pub fn new(
+        repository: Arc<Repository>,
+        repository_manager: Arc<DataRepositoryManager>,
+        config: ManifestConfig,
+    ) -> Self {
+        Self {
+            repository,
+            repository_manager,
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn start(self: Arc<Self>) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            loop {
+                let job = match self
+                    .repository
+                    .claim_ingestion_job(self.config.lease_secs)
+                    .await
+                {
+                    Ok(Some(job)) => job,
+                    Ok(None) => break,
+                    Err(err) => {
+                        error!("unable to claim an ingestion job: {}", err.to_string());
+                        break;
+                    }
+                };
+                let error = self.run(&job).await.err().map(|err| {
+                    error!(
+                        "ingestion job {} for repository {} failed: {}",
+                        job.id,
+                        job.repository,
+                        err.to_string()
+                    );
+                    err.to_string()
+                });
+                if let Err(err) = self.repository.finish_ingestion_job(&job, error).await {
+                    error!(
+                        "unable to record the end of ingestion job {}: {}",
+                        job.id,
+                        err.to_string()
+                    );
+                }
+            }
+        }
+    }
+
+    /// Ingests the rows of the manifest of the job from the first row it has
+    /// not ingested.
+    #[tracing::instrument(skip(self))]
+    pub async fn run(&self, job: &IngestionJob) -> Result<()> {
+        let data = BlobStorageBuilder::reader_from_link(&job.manifest_url)?
+            .get(&job.manifest_url)
+            .await?;
+        let format = job.format;
+        let rows = tokio::task::spawn_blocking(move || parse(format, &data)).await??;
+        let total_rows = rows.len() as u64;
+        if job.total_rows != Some(total_rows) {
+            self.repository
+                .record_ingestion_progress(job, job.next_row, total_rows, 0, vec![])
+                .await?;
+        }
+        let batch_size = self.config.batch_size.max(1) as usize;
+        let mut next_row = job.next_row as usize;
+        while next_row < rows.len() {
+            let end = (next_row + batch_size).min(rows.len());
+            let mut texts = Vec::new();
+            let mut files = Vec::new();
+            let mut errors = Vec::new();
+            for (i, row) in rows[next_row..end].iter().enumerate() {
+                let row_number = (next_row + i + 1) as u64;
+                let result = match row {
+                    Ok(ManifestRow {
+                        source: ManifestSource::Text(text),
+                        metadata,
+                    }) => {
+                        texts.push(ContentPayload::from_text(
+                            &job.repository,
+                            text,
+                            metadata.clone(),
+                        ));
+                        Ok(())
+                    }
+                    Ok(ManifestRow {
+                        source: ManifestSource::Url(url),
+                        metadata,
+                    }) => self.fetch(url).await.map(|(name, data)| {
+                        let mut metadata = metadata.clone();
+                        metadata.entry("url".into()).or_insert(json!(url));
+                        files.push(IngestedFile {
+                            name,
+                            data,
+                            metadata,
+                        });
+                    }),
+                    Err(err) => Err(anyhow!("{}", err)),
+                };
+                if let Err(err) = result {
+                    errors.push(IngestionRowError {
+                        row: row_number,
+                        error: err.to_string(),
+                    });
+                }
+            }
+            // A batch which can not be added fails the job, and is added
+            // again when the job is resumed.
+            let rows_succeeded = (texts.len() + files.len()) as u64;
+            if rows_succeeded > 0 {
+                self.repository_manager
+                    .add_manifest_content(&job.namespace, &job.repository, texts, files)
+                    .await?;
+            }
+            self.repository
+                .record_ingestion_progress(job, end as u64, total_rows, rows_succeeded, errors)
+                .await?;
+            next_row = end;
+        }
+        if total_rows > job.next_row {
+            info!(
+                "ingested rows {} to {} of manifest {} into repository {}",
+                job.next_row + 1,
+                total_rows,
+                job.manifest_name,
+                job.repository
+            );
+        }
+        Ok(())
+    }
+
+    /// Downloads the file a row links to, along with its name. Only `http`
+    /// and `https` links are followed, so that a manifest can not read the
+    /// files of the server.
+    async fn fetch(&self, url: &str) -> Result<(String, Bytes)> {
+        let parsed = url::Url::parse(url).map_err(|e| anyhow!("invalid url {}: {}", url, e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(anyhow!("only http and https urls are supported: {}", url));
+        }
+        let name = parsed
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .filter(|name| !name.is_empty())
+            .unwrap_or(url)
+            .to_string();
+        let response = self.client.get(url).send().await?.error_for_status()?;
+        if let Some(length) = response.content_length() {
+            if length > self.config.max_file_size_bytes {
+                return Err(anyhow!(
+                    "file is larger than {} bytes",
+                    self.config.max_file_size_bytes
+                ));
+            }
+        }
+        let data = response.bytes().await?;
+        if data.len() as u64 > self.config.max_file_size_bytes {
+            return Err(anyhow!(
+                "file is larger than {} bytes",
+                self.config.max_file_size_bytes
+            ));
+        }
+        Ok((name, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_jsonl() {
+        let data = concat!(
+            "{\"text\": \"hello\", \"author\": \"alice\"}\n",
+            "\n",
+            "{\"url\": \"https://example.com/a.pdf\"}\n",
+            "{\"author\": \"bob\"}\n",
+            "not json\n",
+        );
+        let rows = parse(ManifestFormat::Jsonl, data.as_bytes()).unwrap();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(
+            rows[0],
+            Ok(ManifestRow {
+                source: ManifestSource::Text("hello".into()),
+                metadata: HashMap::from([("author".to_string(), json!("alice"))]),
+            })
+        );
+        assert_eq!(
+            rows[1].as_ref().unwrap().source,
+            ManifestSource::Url("https://example.com/a.pdf".into())
+        );
+        assert!(rows[2].is_err());
+        assert!(rows[3].is_err());
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let data = "text,url,author\r\n\"hello, \"\"world\"\"\",,alice\n,https://example.com/a.txt,\n\"two\nlines\",,bob\nshort\n";
+        let rows = parse(ManifestFormat::Csv, data.as_bytes()).unwrap();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(
+            rows[0],
+            Ok(ManifestRow {
+                source: ManifestSource::Text("hello, \"world\"".into()),
+                metadata: HashMap::from([("author".to_string(), json!("alice"))]),
+            })
+        );
+        assert_eq!(
+            rows[1],
+            Ok(ManifestRow {
+                source: ManifestSource::Url("https://example.com/a.txt".into()),
+                metadata: HashMap::new(),
+            })
+        );
+        assert_eq!(
+            rows[2].as_ref().unwrap().source,
+            ManifestSource::Text("two\nlines".into())
+        );
+        assert!(rows[3].is_err());
+
+        assert!(parse(ManifestFormat::Csv, b"author,year\nalice,2020\n").is_err());
+        assert!(parse(ManifestFormat::Csv, b"text\n\"open").is_err());
+    }
+}
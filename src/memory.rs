@@ -0,0 +1,469 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use serde_json::json;
+use tracing::{error, info};
+
+use crate::{
+    extractor_router::ExtractorRouter,
+    internal_api,
+    persistence::{Event, ExtractorOutputSchema, Repository, SESSION_ID_METADATA_KEY},
+    server_config::{MemoryConfig, MemorySummaryConfig},
+    vector_index::{SearchQuery, VectorIndexManager},
+    vectordbs::SearchFilter,
+};
+
+/// The index of a repository which holds the embeddings of the events of its
+/// sessions.
+pub const MEMORY_INDEX: &str = "memory";
+
+/// Events embedded per batch by the background worker.
+const EMBED_BATCH_SIZE: u64 = 256;
+
+/// The metadata key set on the events which summarize older events.
+pub const SUMMARY_METADATA_KEY: &str = "summary_of";
+
+/// An event retrieved from the memory of a session.
+#[derive(Debug, Clone)]
+pub struct MemoryHit {
+    pub event: Event,
+    pub score: f32,
+    pub similarity: f32,
+    pub recency: f32,
+}
+
+/// How the recency of events weighs in their ranking. The recency of an
+/// event decays exponentially with its age, halving every `half_life_secs`,
+/// and `weight`, between 0 and 1, is its weight against the similarity of the
+/// event to the query. A weight of 0 ranks by similarity only.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecencyRanking {
+    pub half_life_secs: u64,
+    pub weight: f32,
+}
+
+impl RecencyRanking {
+    /// The recency of an event of age `age_secs` and its score, or its
+    /// recency alone without a similarity.
+    pub fn score(&self, age_secs: u64, similarity: Option<f32>) -> (f32, f32) {
+        let recency = recency(age_secs, self.half_life_secs);
+        let score = match similarity {
+            Some(similarity) => {
+                let weight = self.weight.clamp(0.0, 1.0);
+                (1.0 - weight) * similarity + weight * recency
+            }
+            None => recency,
+        };
+        (recency, score)
+    }
+}
+
+/// The conversation memory of agents. Events are added to sessions within a
+/// repository, embedded into the memory index of the repository, and
+/// retrieved by their similarity to a query weighted by how recent they are.
+/// The oldest events of long sessions are summarized into one event.
+pub struct Memory {
+    repository: Arc<Repository>,
+    vector_index_manager: Arc<VectorIndexManager>,
+    extractor_router: ExtractorRouter,
+    config: MemoryConfig,
+}
+
+impl fmt::Debug for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Memory")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl Memory {
+    pub fn new(
+        repository: Arc<Repository>,
+        vector_index_manager: Arc<VectorIndexManager>,
+        coordinator_addr: &str,
+        config: MemoryConfig,
+    ) -> Self {
+        Self {
+            repository,
+            vector_index_manager,
+            extractor_router: ExtractorRouter::new(coordinator_addr),
+            config,
+        }
+    }
+
+    /// Embeds events which could not be embedded when they were added and
+    /// summarizes long sessions, at the configured interval.
+    pub async fn start(self: Arc<Self>) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.embed_pending().await {
+                error!(
+                    "unable to embed the events of sessions: {}",
+                    err.to_string()
+                );
+            }
+            if let Some(summary) = &self.config.summary {
+                if let Err(err) = self.summarize_sessions(summary).await {
+                    error!("unable to summarize sessions: {}", err.to_string());
+                }
+            }
+        }
+    }
+
+    /// Adds events to a session and embeds them. Events which fail to be
+    /// embedded are retried in the background, and are not retrieved by
+    /// queries until then.
+    #[tracing::instrument(skip(self, events))]
+    pub async fn add_events(
+        &self,
+        namespace: &str,
+        repository: &str,
+        session_id: &str,
+        mut events: Vec<Event>,
+    ) -> Result<Vec<String>> {
+        for event in &mut events {
+            event
+                .metadata
+                .insert(SESSION_ID_METADATA_KEY.into(), json!(session_id));
+        }
+        let event_ids = events.iter().map(|e| e.id.clone()).collect();
+        self.repository
+            .add_events(namespace, repository, events.clone())
+            .await?;
+        if let Err(err) = self.embed(namespace, repository, &events).await {
+            error!(
+                "unable to embed the events of session {}: {}",
+                session_id,
+                err.to_string()
+            );
+        }
+        Ok(event_ids)
+    }
+
+    /// The events of a session which have not been summarized, oldest first.
+    pub async fn session_events(
+        &self,
+        namespace: &str,
+        repository: &str,
+        session_id: &str,
+    ) -> Result<Vec<Event>> {
+        Ok(self
+            .repository
+            .session_events(namespace, repository, session_id)
+            .await?)
+    }
+
+    /// The ranking of events of the config of the memory, for queries which
+    /// do not rank events their own way.
+    pub fn default_ranking(&self) -> RecencyRanking {
+        RecencyRanking {
+            half_life_secs: self.config.recency_half_life_secs,
+            weight: self.config.recency_weight,
+        }
+    }
+
+    /// The `k` events of a session which are most similar to the query,
+    /// weighted by their recency. Without an extractor embedding the events,
+    /// the `k` most recent events are returned.
+    #[tracing::instrument(skip(self))]
+    pub async fn query(
+        &self,
+        namespace: &str,
+        repository: &str,
+        session_id: &str,
+        query: &str,
+        k: usize,
+        ranking: RecencyRanking,
+    ) -> Result<Vec<MemoryHit>> {
+        let events = self
+            .session_events(namespace, repository, session_id)
+            .await?;
+        if events.is_empty() {
+            return Ok(vec![]);
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let similarities = match &self.config.extractor {
+            Some(_) => {
+                let results = self
+                    .vector_index_manager
+                    .search(
+                        namespace,
+                        repository,
+                        MEMORY_INDEX,
+                        &SearchQuery::Text(query.to_string()),
+                        (k * 4).min(events.len()),
+                        SearchFilter {
+                            content_ids: Some(events.iter().map(|e| e.id.clone()).collect()),
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+                Some(
+                    results
+                        .into_iter()
+                        .map(|r| (r.content_id, r.confidence_score))
+                        .collect::<HashMap<String, f32>>(),
+                )
+            }
+            None => None,
+        };
+        let mut hits: Vec<MemoryHit> = events
+            .into_iter()
+            .filter_map(|event| {
+                let age_secs = now.saturating_sub(event.unix_timestamp);
+                let similarity = match &similarities {
+                    Some(similarities) => Some(*similarities.get(&event.id)?),
+                    None => None,
+                };
+                let (recency, score) = ranking.score(age_secs, similarity);
+                let similarity = similarity.unwrap_or_default();
+                Some(MemoryHit {
+                    event,
+                    score,
+                    similarity,
+                    recency,
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(k);
+        Ok(hits)
+    }
+
+    async fn embed(&self, namespace: &str, repository: &str, events: &[Event]) -> Result<()> {
+        let Some(extractor) = &self.config.extractor else {
+            return Ok(());
+        };
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.ensure_index(namespace, repository, extractor).await?;
+        self.vector_index_manager
+            .embed_texts(
+                namespace,
+                repository,
+                MEMORY_INDEX,
+                events
+                    .iter()
+                    .map(|e| (e.id.clone(), e.message.clone()))
+                    .collect(),
+            )
+            .await?;
+        let event_ids: Vec<String> = events.iter().map(|e| e.id.clone()).collect();
+        self.repository
+            .mark_events_embedded(namespace, &event_ids)
+            .await?;
+        Ok(())
+    }
+
+    /// Creates the memory index of a repository the first time events of it
+    /// are embedded.
+    async fn ensure_index(&self, namespace: &str, repository: &str, extractor: &str) -> Result<()> {
+        if self
+            .repository
+            .get_index(namespace, MEMORY_INDEX, repository)
+            .await
+            .is_ok()
+        {
+            return Ok(());
+        }
+        let schema = self
+            .repository
+            .extractor_by_name(extractor)
+            .await?
+            .schemas
+            .outputs
+            .into_values()
+            .find_map(|schema| match schema {
+                ExtractorOutputSchema::Embedding(schema) => Some(schema),
+                _ => None,
+            })
+            .ok_or(anyhow!(
+                "extractor {} does not extract embeddings",
+                extractor
+            ))?;
+        self.vector_index_manager
+            .create_index(namespace, repository, MEMORY_INDEX, extractor, schema)
+            .await?;
+        Ok(())
+    }
+
+    async fn embed_pending(&self) -> Result<()> {
+        if self.config.extractor.is_none() {
+            return Ok(());
+        }
+        let pending = self
+            .repository
+            .unembedded_session_events(EMBED_BATCH_SIZE)
+            .await?;
+        let mut by_repository: HashMap<(String, String), Vec<Event>> = HashMap::new();
+        for session_event in pending {
+            by_repository
+                .entry((session_event.namespace, session_event.repository))
+                .or_default()
+                .push(session_event.event);
+        }
+        for ((namespace, repository), events) in by_repository {
+            self.embed(&namespace, &repository, &events).await?;
+        }
+        Ok(())
+    }
+
+    async fn summarize_sessions(&self, config: &MemorySummaryConfig) -> Result<()> {
+        let window = config.window.max(2);
+        let sessions = self
+            .repository
+            .sessions_with_events_over(window + config.keep_recent)
+            .await?;
+        for (namespace, repository, session_id) in sessions {
+            if let Err(err) = self
+                .summarize(config, &namespace, &repository, &session_id, window)
+                .await
+            {
+                error!(
+                    "unable to summarize session {} of repository {}: {}",
+                    session_id,
+                    repository,
+                    err.to_string()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the oldest `window` events of a session with their summary.
+    async fn summarize(
+        &self,
+        config: &MemorySummaryConfig,
+        namespace: &str,
+        repository: &str,
+        session_id: &str,
+        window: u64,
+    ) -> Result<()> {
+        let mut events = self
+            .session_events(namespace, repository, session_id)
+            .await?;
+        events.truncate(window as usize);
+        let Some(last) = events.last() else {
+            return Ok(());
+        };
+        let content = internal_api::Content {
+            content_type: mime::TEXT_PLAIN.to_string(),
+            source: summary_input(&events).into_bytes(),
+            feature: None,
+            metadata: HashMap::new(),
+        };
+        let summary_text = self
+            .extractor_router
+            .extract(&config.extractor, content, None)
+            .await?
+            .first()
+            .and_then(|c| c.source_as_text())
+            .ok_or(anyhow!(
+                "extractor {} did not return a summary",
+                config.extractor
+            ))?;
+        let summary = Event::new(
+            &summary_text,
+            Some(last.unix_timestamp),
+            HashMap::from([
+                (SESSION_ID_METADATA_KEY.into(), json!(session_id)),
+                (SUMMARY_METADATA_KEY.into(), json!(events.len())),
+            ]),
+        );
+        let event_ids: Vec<String> = events.iter().map(|e| e.id.clone()).collect();
+        self.repository
+            .summarize_events(namespace, repository, &event_ids, summary.clone())
+            .await?;
+        info!(
+            "summarized {} events of session {} of repository {}",
+            event_ids.len(),
+            session_id,
+            repository
+        );
+        if let Err(err) = self.embed(namespace, repository, &[summary]).await {
+            error!(
+                "unable to embed the summary of session {}: {}",
+                session_id,
+                err.to_string()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Halves every `half_life_secs`, from 1 for an event which just happened.
+pub fn recency(age_secs: u64, half_life_secs: u64) -> f32 {
+    0.5_f32.powf(age_secs as f32 / half_life_secs.max(1) as f32)
+}
+
+/// The text the summarizer is given, one event per line. Summaries of older
+/// events are included as they are, so the summary covers them too.
+fn summary_input(events: &[Event]) -> String {
+    events
+        .iter()
+        .map(|e| {
+            if e.metadata.contains_key(SUMMARY_METADATA_KEY) {
+                format!("Summary of earlier events: {}", e.message)
+            } else {
+                e.message.clone()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recency() {
+        assert_eq!(recency(0, 100), 1.0);
+        assert_eq!(recency(100, 100), 0.5);
+        assert_eq!(recency(200, 100), 0.25);
+        assert_eq!(recency(10, 0), 0.5_f32.powf(10.0));
+    }
+
+    #[test]
+    fn test_recency_ranking() {
+        let ranking = RecencyRanking {
+            half_life_secs: 100,
+            weight: 0.5,
+        };
+        let (recency, score) = ranking.score(100, Some(0.9));
+        assert_eq!(recency, 0.5);
+        assert!((score - 0.7).abs() < 1e-6);
+        assert_eq!(ranking.score(100, None), (0.5, 0.5));
+        // An older event which is more similar ranks below a recent one.
+        assert!(ranking.score(1000, Some(0.9)).1 < ranking.score(0, Some(0.6)).1);
+
+        let similarity_only = RecencyRanking {
+            half_life_secs: 100,
+            weight: 0.0,
+        };
+        assert_eq!(similarity_only.score(1000, Some(0.9)).1, 0.9);
+    }
+
+    #[test]
+    fn test_summary_input() {
+        let summary = Event::new(
+            "the user asked about pricing",
+            None,
+            HashMap::from([(SUMMARY_METADATA_KEY.into(), json!(10))]),
+        );
+        let events = vec![summary, Event::new("hello", None, HashMap::new())];
+        assert_eq!(
+            summary_input(&events),
+            "Summary of earlier events: the user asked about pricing\nhello"
+        );
+    }
+}
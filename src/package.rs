@@ -50,6 +50,11 @@ impl Packager {
         })
     }
 
+    /// The name of the image the extractor is packaged as.
+    pub fn image_name(&self) -> &str {
+        &self.config.name
+    }
+
     pub async fn package(&self, verbose: bool) -> Result<()> {
         let docker_file = self.create_docker_file()?;
         let mut header = tar::Header::new_gnu();
@@ -104,7 +109,7 @@ impl Packager {
                     .collect();
                 for vertex in &status.vertexes {
                     if verbose && !vertex.name.is_empty() {
-                        print!("{}", vertex.name);
+                        eprint!("{}", vertex.name);
                     }
                     if !vertex.error.is_empty() {
                         eprint!("{}", vertex.error);
@@ -112,7 +117,7 @@ impl Packager {
                     }
                 }
                 if verbose && !messages.is_empty() {
-                    print!("{}", messages.join("\n"));
+                    eprint!("{}", messages.join("\n"));
                 }
             }
         }
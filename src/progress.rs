@@ -0,0 +1,294 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use strum::Display;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::error;
+
+use crate::persistence::{ContentProgress, Repository, RepositoryError, WorkState};
+
+/// How often the extraction progress of a content is read while it is
+/// streamed.
+const PROGRESS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum ProgressEventKind {
+    /// The content was added, and an event created to have it extracted.
+    Created,
+    /// The work of an extractor binding on the content was created.
+    Scheduled,
+    /// An executor started the work of an extractor binding.
+    Started,
+    Completed,
+    Failed,
+    /// All the work on the content has completed or failed.
+    Done,
+}
+
+/// A transition of the extraction of a content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressEvent {
+    pub kind: ProgressEventKind,
+    pub content_id: String,
+    pub extractor_binding: Option<String>,
+    pub extractor: Option<String>,
+    pub work_id: Option<String>,
+}
+
+impl ProgressEvent {
+    fn content(kind: ProgressEventKind, content_id: &str) -> Self {
+        Self {
+            kind,
+            content_id: content_id.into(),
+            extractor_binding: None,
+            extractor: None,
+            work_id: None,
+        }
+    }
+}
+
+/// Whether the content has no extraction left to wait for.
+pub fn is_done(progress: &ContentProgress) -> bool {
+    progress.scheduled &&
+        progress
+            .work
+            .iter()
+            .all(|w| matches!(w.work_state, WorkState::Completed | WorkState::Failed))
+}
+
+/// The transitions from the `previous` progress of a content to its
+/// `current` progress. Without a previous progress, the transitions replay
+/// how the content got to its current progress. Work which skipped a state
+/// between two reads gets the transitions of the states it skipped.
+pub fn transitions(
+    content_id: &str,
+    previous: Option<&ContentProgress>,
+    current: &ContentProgress,
+) -> Vec<ProgressEvent> {
+    let mut events = Vec::new();
+    if previous.is_none() {
+        events.push(ProgressEvent::content(
+            ProgressEventKind::Created,
+            content_id,
+        ));
+    }
+    let previous_states: HashMap<&str, &WorkState> = previous
+        .map(|p| {
+            p.work
+                .iter()
+                .map(|w| (w.id.as_str(), &w.work_state))
+                .collect()
+        })
+        .unwrap_or_default();
+    for work in &current.work {
+        let reached = stage(&work.work_state);
+        let from = previous_states
+            .get(work.id.as_str())
+            .map_or(0, |state| stage(state));
+        let finished = match work.work_state {
+            WorkState::Failed => ProgressEventKind::Failed,
+            _ => ProgressEventKind::Completed,
+        };
+        let kinds = [
+            ProgressEventKind::Scheduled,
+            ProgressEventKind::Started,
+            finished,
+        ]
+        .into_iter()
+        .zip(1..)
+        .filter(|(_, s)| from < *s && *s <= reached)
+        .map(|(kind, _)| kind);
+        for kind in kinds {
+            events.push(ProgressEvent {
+                kind,
+                content_id: content_id.into(),
+                extractor_binding: Some(work.extractor_binding.clone()),
+                extractor: Some(work.extractor.clone()),
+                work_id: Some(work.id.clone()),
+            });
+        }
+    }
+    if is_done(current) && !previous.is_some_and(is_done) {
+        events.push(ProgressEvent::content(ProgressEventKind::Done, content_id));
+    }
+    events
+}
+
+/// How far work has got: scheduled, started, or finished.
+fn stage(state: &WorkState) -> u8 {
+    match state {
+        WorkState::Unknown | WorkState::Pending => 1,
+        WorkState::InProgress => 2,
+        WorkState::Completed | WorkState::Failed => 3,
+    }
+}
+
+/// Follows the extraction of a content until all of its work has completed
+/// or failed.
+pub struct ContentProgressWatch {
+    repository: Arc<Repository>,
+    namespace: String,
+    repository_name: String,
+    content_id: String,
+    progress: ContentProgress,
+}
+
+impl ContentProgressWatch {
+    pub fn new(
+        repository: Arc<Repository>,
+        namespace: &str,
+        repository_name: &str,
+        content_id: &str,
+        progress: ContentProgress,
+    ) -> Self {
+        Self {
+            repository,
+            namespace: namespace.into(),
+            repository_name: repository_name.into(),
+            content_id: content_id.into(),
+            progress,
+        }
+    }
+
+    /// The transitions of the extraction, read in the background until the
+    /// extraction is done, the content is deleted or the client goes away.
+    pub fn stream(self) -> ReceiverStream<ProgressEvent> {
+        let (tx, rx) = mpsc::channel(16);
+        tokio::spawn(async move {
+            if let Err(err) = self.follow(&tx).await {
+                error!(
+                    "unable to follow the extraction of content {}: {}",
+                    self.content_id,
+                    err.to_string()
+                );
+            }
+        });
+        ReceiverStream::new(rx)
+    }
+
+    async fn follow(&self, tx: &mpsc::Sender<ProgressEvent>) -> Result<(), RepositoryError> {
+        let mut previous: Option<ContentProgress> = None;
+        let mut current = self.progress.clone();
+        loop {
+            for event in transitions(&self.content_id, previous.as_ref(), &current) {
+                if tx.send(event).await.is_err() {
+                    return Ok(());
+                }
+            }
+            if is_done(&current) {
+                return Ok(());
+            }
+            tokio::select! {
+                _ = tx.closed() => return Ok(()),
+                _ = tokio::time::sleep(PROGRESS_POLL_INTERVAL) => {}
+            }
+            let next = match self
+                .repository
+                .content_progress(&self.namespace, &self.repository_name, &self.content_id)
+                .await
+            {
+                Ok(next) => next,
+                Err(RepositoryError::ContentNotFound(_)) => return Ok(()),
+                Err(err) => return Err(err),
+            };
+            previous = Some(std::mem::replace(&mut current, next));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::persistence::Work;
+
+    fn work(binding: &str, state: WorkState) -> Work {
+        let mut work = Work::new("c1", "default", "docs", binding, binding, &json!({}), None);
+        work.work_state = state;
+        work
+    }
+
+    /// The same work in other states, as a later read of the progress sees
+    /// it.
+    fn with_states(work: &[Work], states: [WorkState; 2]) -> Vec<Work> {
+        work.iter()
+            .zip(states)
+            .map(|(work, state)| Work {
+                work_state: state,
+                ..work.clone()
+            })
+            .collect()
+    }
+
+    fn kinds(events: &[ProgressEvent]) -> Vec<(ProgressEventKind, Option<String>)> {
+        events
+            .iter()
+            .map(|e| (e.kind, e.extractor_binding.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_transitions() {
+        let added = ContentProgress {
+            scheduled: false,
+            work: vec![],
+        };
+        assert_eq!(
+            kinds(&transitions("c1", None, &added)),
+            vec![(ProgressEventKind::Created, None)]
+        );
+
+        let work = [
+            work("embeddings", WorkState::Pending),
+            work("ner", WorkState::InProgress),
+        ];
+        let scheduled = ContentProgress {
+            scheduled: true,
+            work: work.to_vec(),
+        };
+        assert_eq!(
+            kinds(&transitions("c1", Some(&added), &scheduled)),
+            vec![
+                (ProgressEventKind::Scheduled, Some("embeddings".into())),
+                (ProgressEventKind::Scheduled, Some("ner".into())),
+                (ProgressEventKind::Started, Some("ner".into())),
+            ]
+        );
+        assert!(transitions("c1", Some(&scheduled), &scheduled).is_empty());
+
+        let finished = ContentProgress {
+            scheduled: true,
+            work: with_states(&work, [WorkState::Completed, WorkState::Failed]),
+        };
+        assert_eq!(
+            kinds(&transitions("c1", Some(&scheduled), &finished)),
+            vec![
+                (ProgressEventKind::Started, Some("embeddings".into())),
+                (ProgressEventKind::Completed, Some("embeddings".into())),
+                (ProgressEventKind::Failed, Some("ner".into())),
+                (ProgressEventKind::Done, None),
+            ]
+        );
+        assert!(transitions("c1", Some(&finished), &finished).is_empty());
+    }
+
+    #[test]
+    fn test_transitions_replay() {
+        let finished = ContentProgress {
+            scheduled: true,
+            work: vec![work("embeddings", WorkState::Completed)],
+        };
+        assert_eq!(
+            kinds(&transitions("c1", None, &finished)),
+            vec![
+                (ProgressEventKind::Created, None),
+                (ProgressEventKind::Scheduled, Some("embeddings".into())),
+                (ProgressEventKind::Started, Some("embeddings".into())),
+                (ProgressEventKind::Completed, Some("embeddings".into())),
+                (ProgressEventKind::Done, None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,438 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::State,
+    http::{header, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tokio::sync::Notify;
+use tracing::warn;
+
+use crate::{
+    api::{IndexifyAPIError, Namespace},
+    server_config::QueryThrottleConfig,
+};
+
+/// The weight of the latest query in the average latency of a repository.
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+/// How much the limit of queries in flight shrinks after a query slower than
+/// the latency target.
+const LIMIT_DECREASE: f64 = 0.95;
+
+/// The repository a request queries, for the requests which search or read
+/// the indexes of a repository.
+pub fn queried_repository<'a>(method: &Method, path: &'a str) -> Option<&'a str> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        (&Method::POST, ["repositories", repository, "search" | "keyword_search"]) |
+        (&Method::POST, ["repositories", repository, "sessions", _, "query"]) |
+        (&Method::GET, ["repositories", repository, "attributes"]) |
+        (&Method::GET, ["repositories", repository, "attributes", "query" | "aggregate"]) |
+        (&Method::GET, ["repositories", repository, "indexes", _, "graph", _]) => Some(repository),
+        _ => None,
+    }
+}
+
+/// The query load of a repository.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RepositoryLoad {
+    pub namespace: String,
+    pub repository: String,
+    pub in_flight: usize,
+    /// The moving average of the latency of the queries of the repository.
+    pub latency_ms: f64,
+    pub queries: u64,
+    /// Queries waiting for a slot.
+    pub queued: usize,
+    /// Queries which had to wait for a slot.
+    pub throttled: u64,
+    /// Queries which were rejected after waiting for a slot.
+    pub shed: u64,
+}
+
+/// The query load of the server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryLoad {
+    /// The queries which may run at once, adapted to the latency of the
+    /// backends.
+    pub limit: usize,
+    pub in_flight: usize,
+    pub saturated: bool,
+    pub repositories: Vec<RepositoryLoad>,
+}
+
+#[derive(Debug)]
+struct ThrottleState {
+    limit: f64,
+    in_flight: usize,
+    repositories: HashMap<(String, String), RepositoryLoad>,
+}
+
+impl ThrottleState {
+    fn new(config: &QueryThrottleConfig) -> Self {
+        Self {
+            limit: config.max_concurrent_queries.max(1) as f64,
+            in_flight: 0,
+            repositories: HashMap::new(),
+        }
+    }
+
+    fn limit(&self) -> usize {
+        (self.limit as usize).max(1)
+    }
+
+    /// The backends approach saturation once the queries in flight come
+    /// close to the limit.
+    fn saturated(&self, config: &QueryThrottleConfig) -> bool {
+        self.in_flight as f64 >= self.limit() as f64 * config.saturation_ratio
+    }
+
+    /// The queries a repository may have in flight while the backends are
+    /// saturated, an equal part of the limit for every repository with
+    /// queries in flight or waiting.
+    fn fair_share(&self, key: &(String, String)) -> usize {
+        let is_active = |load: &RepositoryLoad| load.in_flight > 0 || load.queued > 0;
+        let mut active = self.repositories.values().filter(|l| is_active(l)).count();
+        if !self.repositories.get(key).is_some_and(is_active) {
+            active += 1;
+        }
+        (self.limit() / active).max(1)
+    }
+
+    fn try_admit(&mut self, key: &(String, String), config: &QueryThrottleConfig) -> bool {
+        let admitted = !config.enabled ||
+            (self.in_flight < self.limit() &&
+                (!self.saturated(config) ||
+                    self.repositories.get(key).map_or(0, |l| l.in_flight) <
+                        self.fair_share(key)));
+        if admitted {
+            self.in_flight += 1;
+            let load = self.load(key);
+            load.in_flight += 1;
+            load.queries += 1;
+        }
+        admitted
+    }
+
+    fn enqueue(&mut self, key: &(String, String)) {
+        let load = self.load(key);
+        load.queued += 1;
+        load.throttled += 1;
+    }
+
+    fn dequeue(&mut self, key: &(String, String)) {
+        let load = self.load(key);
+        load.queued = load.queued.saturating_sub(1);
+    }
+
+    fn release(&mut self, key: &(String, String), latency: Duration, config: &QueryThrottleConfig) {
+        self.in_flight = self.in_flight.saturating_sub(1);
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        let load = self.load(key);
+        load.in_flight = load.in_flight.saturating_sub(1);
+        load.latency_ms = if load.latency_ms == 0.0 {
+            latency_ms
+        } else {
+            LATENCY_SMOOTHING * latency_ms + (1.0 - LATENCY_SMOOTHING) * load.latency_ms
+        };
+
+        // Shrinks the limit while the backends are slower than the target
+        // and grows it back by one slot for every `limit` fast queries.
+        let min = config.min_concurrent_queries.max(1) as f64;
+        let max = (config.max_concurrent_queries.max(1) as f64).max(min);
+        self.limit = if latency_ms > config.latency_target_ms as f64 {
+            self.limit * LIMIT_DECREASE
+        } else {
+            self.limit + 1.0 / self.limit
+        }
+        .clamp(min, max);
+    }
+
+    fn load(&mut self, key: &(String, String)) -> &mut RepositoryLoad {
+        self.repositories
+            .entry(key.clone())
+            .or_insert_with(|| RepositoryLoad {
+                namespace: key.0.clone(),
+                repository: key.1.clone(),
+                ..Default::default()
+            })
+    }
+}
+
+/// Protects the repositories from the query load of each other. The server
+/// tracks the queries in flight and their latency per repository, and while
+/// the database and the vector store approach saturation, the queries of a
+/// repository beyond its fair share wait for a slot and are rejected when no
+/// slot frees up in time. The limit of queries in flight adapts to the
+/// latency of the queries, so the backends are not pushed past the latency
+/// target.
+pub struct QueryThrottle {
+    config: QueryThrottleConfig,
+    state: Mutex<ThrottleState>,
+    released: Notify,
+}
+
+impl fmt::Debug for QueryThrottle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryThrottle")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// A slot for a query, released when this is dropped.
+pub struct QueryPermit {
+    throttle: Arc<QueryThrottle>,
+    key: (String, String),
+    started_at: Instant,
+}
+
+impl Drop for QueryPermit {
+    fn drop(&mut self) {
+        self.throttle.state.lock().unwrap().release(
+            &self.key,
+            self.started_at.elapsed(),
+            &self.throttle.config,
+        );
+        self.throttle.released.notify_waiters();
+    }
+}
+
+impl QueryThrottle {
+    pub fn new(config: QueryThrottleConfig) -> Self {
+        Self {
+            state: Mutex::new(ThrottleState::new(&config)),
+            config,
+            released: Notify::new(),
+        }
+    }
+
+    /// Waits for a slot for a query of a repository. Returns `None` when no
+    /// slot frees up within the queue timeout.
+    pub async fn admit(self: &Arc<Self>, namespace: &str, repository: &str) -> Option<QueryPermit> {
+        let key = (namespace.to_string(), repository.to_string());
+        let deadline = Instant::now() + Duration::from_millis(self.config.queue_timeout_ms);
+        let mut throttled = false;
+        loop {
+            // Created before the slots are checked, so that a slot released
+            // in between is not missed.
+            let released = self.released.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.try_admit(&key, &self.config) {
+                    if throttled {
+                        state.dequeue(&key);
+                    }
+                    return Some(QueryPermit {
+                        throttle: self.clone(),
+                        key,
+                        started_at: Instant::now(),
+                    });
+                }
+                if !throttled {
+                    state.enqueue(&key);
+                    throttled = true;
+                }
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || tokio::time::timeout(remaining, released).await.is_err() {
+                let mut state = self.state.lock().unwrap();
+                state.dequeue(&key);
+                state.load(&key).shed += 1;
+                return None;
+            }
+        }
+    }
+
+    /// The query load of the repositories of a namespace.
+    pub fn load(&self, namespace: &str) -> QueryLoad {
+        let state = self.state.lock().unwrap();
+        let mut repositories: Vec<RepositoryLoad> = state
+            .repositories
+            .values()
+            .filter(|load| load.namespace == namespace)
+            .cloned()
+            .collect();
+        repositories.sort_by(|a, b| a.repository.cmp(&b.repository));
+        QueryLoad {
+            limit: state.limit(),
+            in_flight: state.in_flight,
+            saturated: state.saturated(&self.config),
+            repositories,
+        }
+    }
+}
+
+/// Admits the queries of repositories through the throttle, and rejects the
+/// queries which were shed with `429 Too Many Requests`.
+pub async fn throttle_queries<B>(
+    State(throttle): State<Arc<QueryThrottle>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let Some(repository) = queried_repository(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let repository = repository.to_string();
+    let Ok(Namespace(namespace)) = Namespace::from_headers(request.headers()) else {
+        return next.run(request).await;
+    };
+    let Some(_permit) = throttle.admit(&namespace, &repository).await else {
+        warn!(
+            "shed a query of repository {} in namespace {}, the backends are saturated",
+            repository, namespace
+        );
+        let mut response = IndexifyAPIError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            format!(
+                "the queries of repository {} are throttled, retry later",
+                repository
+            ),
+        )
+        .into_response();
+        let retry_after = throttle.config.queue_timeout_ms.div_ceil(1000).max(1);
+        if let Ok(value) = HeaderValue::from_str(&retry_after.to_string()) {
+            response.headers_mut().insert(header::RETRY_AFTER, value);
+        }
+        return response;
+    };
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> QueryThrottleConfig {
+        QueryThrottleConfig {
+            enabled: true,
+            max_concurrent_queries: 4,
+            min_concurrent_queries: 2,
+            latency_target_ms: 100,
+            saturation_ratio: 0.5,
+            queue_timeout_ms: 50,
+        }
+    }
+
+    fn key(repository: &str) -> (String, String) {
+        ("default".into(), repository.into())
+    }
+
+    #[test]
+    fn test_queried_repository() {
+        assert_eq!(
+            queried_repository(&Method::POST, "/repositories/docs/search"),
+            Some("docs")
+        );
+        assert_eq!(
+            queried_repository(&Method::POST, "/repositories/docs/sessions/chat-1/query"),
+            Some("docs")
+        );
+        assert_eq!(
+            queried_repository(&Method::GET, "/repositories/docs/attributes/query"),
+            Some("docs")
+        );
+        assert_eq!(
+            queried_repository(&Method::GET, "/repositories/docs/attributes/aggregate"),
+            Some("docs")
+        );
+        assert_eq!(
+            queried_repository(
+                &Method::GET,
+                "/repositories/docs/indexes/kg.graph/graph/paths"
+            ),
+            Some("docs")
+        );
+        assert_eq!(
+            queried_repository(&Method::POST, "/repositories/docs/add_texts"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_fair_share_when_saturated() {
+        let config = config();
+        let mut state = ThrottleState::new(&config);
+        // Alone, a repository may use the whole limit.
+        for _ in 0..4 {
+            assert!(state.try_admit(&key("noisy"), &config));
+        }
+        assert!(!state.try_admit(&key("noisy"), &config));
+        assert!(!state.try_admit(&key("quiet"), &config));
+        state.enqueue(&key("quiet"));
+
+        // The slot which frees up goes to the quiet repository, the noisy
+        // one is over its share of the limit.
+        state.release(&key("noisy"), Duration::from_millis(10), &config);
+        assert!(!state.try_admit(&key("noisy"), &config));
+        assert!(state.try_admit(&key("quiet"), &config));
+        state.dequeue(&key("quiet"));
+        assert_eq!(state.in_flight, 4);
+        assert_eq!(state.load(&key("quiet")).throttled, 1);
+
+        let disabled = QueryThrottleConfig {
+            enabled: false,
+            ..config
+        };
+        let mut state = ThrottleState::new(&disabled);
+        for _ in 0..10 {
+            assert!(state.try_admit(&key("noisy"), &disabled));
+        }
+    }
+
+    #[test]
+    fn test_limit_adapts_to_latency() {
+        let config = config();
+        let mut state = ThrottleState::new(&config);
+        for _ in 0..20 {
+            state.try_admit(&key("docs"), &config);
+            state.release(&key("docs"), Duration::from_millis(500), &config);
+        }
+        assert_eq!(state.limit(), 2);
+        for _ in 0..20 {
+            state.try_admit(&key("docs"), &config);
+            state.release(&key("docs"), Duration::from_millis(10), &config);
+        }
+        assert_eq!(state.limit(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_shed_after_queue_timeout() {
+        let throttle = Arc::new(QueryThrottle::new(config()));
+        let mut noisy = Vec::new();
+        for _ in 0..4 {
+            noisy.push(throttle.admit("default", "noisy").await.unwrap());
+        }
+        assert!(throttle.admit("default", "noisy").await.is_none());
+
+        let (quiet, _) = tokio::join!(throttle.admit("default", "quiet"), async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            noisy.pop();
+        });
+        assert!(quiet.is_some());
+
+        let load = throttle.load("default");
+        assert_eq!(load.in_flight, 4);
+        assert!(load.saturated);
+        let noisy = &load.repositories[0];
+        assert_eq!(
+            (
+                noisy.repository.as_str(),
+                noisy.queries,
+                noisy.throttled,
+                noisy.shed
+            ),
+            ("noisy", 4, 1, 1)
+        );
+        let quiet = &load.repositories[1];
+        assert_eq!((quiet.queued, quiet.throttled, quiet.shed), (0, 1, 0));
+        assert!(throttle.load("other").repositories.is_empty());
+    }
+}
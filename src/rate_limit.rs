@@ -0,0 +1,300 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+use crate::{
+    api::IndexifyAPIError,
+    auth::Principal,
+    query_throttle,
+    server_config::{EndpointRateLimit, RateLimitConfig},
+};
+
+/// Buckets beyond this many are pruned of the clients which have not sent a
+/// request for a minute, so that clients which come and go do not grow the
+/// limiter without bound.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// The kinds of endpoints which are rate limited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    Ingestion,
+    Search,
+}
+
+/// The kind of endpoint a request is sent to, for the requests which are
+/// rate limited.
+pub fn rate_limited_endpoint(method: &Method, path: &str) -> Option<Endpoint> {
+    if query_throttle::queried_repository(method, path).is_some() {
+        return Some(Endpoint::Search);
+    }
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        (
+            &Method::POST,
+            ["repositories", _, "add_texts" | "bulk_add_texts" | "upload_file" | "upload_archive" | "ingest" |
+            "ingestion_jobs"],
+        ) => Some(Endpoint::Ingestion),
+        _ => None,
+    }
+}
+
+/// Who a limit is counted for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Client {
+    Key(String),
+    Ip(String),
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    /// Takes a token of a bucket which holds a minute of requests and refills
+    /// continuously. Returns how long until a token is available when the
+    /// bucket is empty.
+    fn take(&mut self, per_minute: u32, now: Instant) -> Result<(), Duration> {
+        let capacity = per_minute.max(1) as f64;
+        let per_sec = capacity / 60.0;
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_sec).min(capacity);
+        self.updated_at = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        Err(Duration::from_secs_f64((1.0 - self.tokens) / per_sec))
+    }
+}
+
+/// Limits the requests per minute of each api key and each client address to
+/// the ingestion and search endpoints, so that a client can not take the
+/// server from the others.
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<(Endpoint, Client), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn limits(&self, endpoint: Endpoint) -> &EndpointRateLimit {
+        match endpoint {
+            Endpoint::Ingestion => &self.config.ingestion,
+            Endpoint::Search => &self.config.search,
+        }
+    }
+
+    fn check(&self, endpoint: Endpoint, client: Client, now: Instant) -> Result<(), Duration> {
+        let limits = self.limits(endpoint);
+        let per_minute = match &client {
+            Client::Key(_) => limits.per_key_per_minute,
+            Client::Ip(_) => limits.per_ip_per_minute,
+        };
+        let Some(per_minute) = per_minute else {
+            return Ok(());
+        };
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            buckets.retain(|_, bucket| {
+                now.saturating_duration_since(bucket.updated_at) < Duration::from_secs(60)
+            });
+        }
+        buckets
+            .entry((endpoint, client))
+            .or_insert_with(|| Bucket {
+                tokens: per_minute.max(1) as f64,
+                updated_at: now,
+            })
+            .take(per_minute, now)
+    }
+
+    /// The address of the client of a request.
+    fn client_ip(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<String> {
+        if self.config.trust_forwarded_for {
+            let forwarded = headers
+                .get_all("x-forwarded-for")
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .flat_map(|v| v.split(','))
+                .map(str::trim)
+                .rfind(|v| !v.is_empty());
+            if let Some(forwarded) = forwarded {
+                return Some(forwarded.to_string());
+            }
+        }
+        peer.map(|peer| peer.ip().to_string())
+    }
+}
+
+fn too_many_requests(endpoint: Endpoint, retry_after: Duration) -> Response {
+    let mut response = IndexifyAPIError::new(
+        StatusCode::TOO_MANY_REQUESTS,
+        format!(
+            "the rate limit of the {} endpoints is exceeded, retry later",
+            match endpoint {
+                Endpoint::Ingestion => "ingestion",
+                Endpoint::Search => "search",
+            }
+        ),
+    )
+    .into_response();
+    let retry_after = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    if let Ok(value) = HeaderValue::from_str(&retry_after.to_string()) {
+        response.headers_mut().insert(header::RETRY_AFTER, value);
+    }
+    response
+}
+
+/// Rejects the requests of a client address over its limit with `429 Too
+/// Many Requests`. Runs before the authentication, so that unauthenticated
+/// clients are limited as well.
+pub async fn limit_by_ip<B>(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let Some(endpoint) = rate_limited_endpoint(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+    let Some(ip) = limiter.client_ip(request.headers(), peer) else {
+        return next.run(request).await;
+    };
+    if let Err(retry_after) = limiter.check(endpoint, Client::Ip(ip.clone()), Instant::now()) {
+        warn!("rate limited the {:?} requests of {}", endpoint, ip);
+        return too_many_requests(endpoint, retry_after);
+    }
+    next.run(request).await
+}
+
+/// Rejects the requests of an api key over its limit with `429 Too Many
+/// Requests`. Runs after the authentication, which sets the principal.
+pub async fn limit_by_key<B>(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let Some(endpoint) = rate_limited_endpoint(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let Some(Principal(principal)) = request.extensions().get::<Principal>().cloned() else {
+        return next.run(request).await;
+    };
+    if let Err(retry_after) = limiter.check(endpoint, Client::Key(principal), Instant::now()) {
+        return too_many_requests(endpoint, retry_after);
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limited_endpoint() {
+        assert_eq!(
+            rate_limited_endpoint(&Method::POST, "/repositories/docs/search"),
+            Some(Endpoint::Search)
+        );
+        assert_eq!(
+            rate_limited_endpoint(&Method::POST, "/repositories/docs/upload_file"),
+            Some(Endpoint::Ingestion)
+        );
+        assert_eq!(
+            rate_limited_endpoint(&Method::POST, "/repositories/docs/ingestion_jobs"),
+            Some(Endpoint::Ingestion)
+        );
+        assert_eq!(
+            rate_limited_endpoint(&Method::GET, "/repositories/docs/ingestion_jobs"),
+            None
+        );
+        assert_eq!(rate_limited_endpoint(&Method::GET, "/repositories"), None);
+    }
+
+    #[test]
+    fn test_limits_per_client() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            search: EndpointRateLimit {
+                per_key_per_minute: None,
+                per_ip_per_minute: Some(2),
+            },
+            ..Default::default()
+        });
+        let now = Instant::now();
+        let ip = |ip: &str| Client::Ip(ip.to_string());
+        assert!(limiter.check(Endpoint::Search, ip("10.0.0.1"), now).is_ok());
+        assert!(limiter.check(Endpoint::Search, ip("10.0.0.1"), now).is_ok());
+        let retry_after = limiter
+            .check(Endpoint::Search, ip("10.0.0.1"), now)
+            .unwrap_err();
+        assert_eq!(retry_after.as_secs_f64().round(), 30.0);
+
+        // Other clients, api keys and endpoints have limits of their own.
+        assert!(limiter.check(Endpoint::Search, ip("10.0.0.2"), now).is_ok());
+        assert!(limiter
+            .check(Endpoint::Search, Client::Key("key".into()), now)
+            .is_ok());
+        assert!(limiter
+            .check(Endpoint::Ingestion, ip("10.0.0.1"), now)
+            .is_ok());
+
+        // A token is refilled every 30 seconds.
+        let later = now + Duration::from_secs(31);
+        assert!(limiter
+            .check(Endpoint::Search, ip("10.0.0.1"), later)
+            .is_ok());
+        assert!(limiter
+            .check(Endpoint::Search, ip("10.0.0.1"), later)
+            .is_err());
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let peer: SocketAddr = "10.0.0.1:5000".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("1.2.3.4, 192.168.0.7"),
+        );
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        assert_eq!(
+            limiter.client_ip(&headers, Some(peer)),
+            Some("10.0.0.1".into())
+        );
+        let limiter = RateLimiter::new(RateLimitConfig {
+            trust_forwarded_for: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            limiter.client_ip(&headers, Some(peer)),
+            Some("192.168.0.7".into())
+        );
+        assert_eq!(
+            limiter.client_ip(&HeaderMap::new(), Some(peer)),
+            Some("10.0.0.1".into())
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use axum::{
+    http::{Method, Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::api::IndexifyAPIError;
+
+/// Whether a request changes the state of the server. Searching, querying
+/// memory, extracting and warming up indexes are sent with `POST` but only
+/// read.
+pub fn is_write(method: &Method, path: &str) -> bool {
+    if method == Method::GET || method == Method::HEAD || method == Method::OPTIONS {
+        return false;
+    }
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    !matches!(
+        segments.as_slice(),
+        ["repositories", _, "search" | "keyword_search"] |
+            ["repositories", _, "sessions", _, "query"] |
+            ["repositories", _, "indexes", _, "warm"] |
+            ["extractors", "extract"]
+    )
+}
+
+/// Rejects the requests which would change the state of a server running in
+/// read-only mode.
+pub async fn reject_writes<B>(
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, IndexifyAPIError> {
+    if is_write(request.method(), request.uri().path()) {
+        return Err(IndexifyAPIError::new(
+            StatusCode::FORBIDDEN,
+            "the server is in read-only mode".to_string(),
+        ));
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_write() {
+        assert!(!is_write(&Method::GET, "/repositories/docs/indexes"));
+        assert!(!is_write(&Method::POST, "/repositories/docs/search"));
+        assert!(!is_write(
+            &Method::POST,
+            "/repositories/docs/keyword_search"
+        ));
+        assert!(!is_write(
+            &Method::POST,
+            "/repositories/docs/indexes/embeddings/warm"
+        ));
+        assert!(!is_write(
+            &Method::POST,
+            "/repositories/docs/sessions/chat-1/query"
+        ));
+        assert!(!is_write(&Method::POST, "/extractors/extract"));
+        assert!(is_write(
+            &Method::POST,
+            "/repositories/docs/sessions/chat-1/events"
+        ));
+        assert!(is_write(&Method::POST, "/repositories/docs/add_texts"));
+        assert!(is_write(&Method::PUT, "/repositories/docs/text_analysis"));
+        assert!(is_write(&Method::DELETE, "/api_keys/abc"));
+    }
+}
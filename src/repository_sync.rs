@@ -0,0 +1,130 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use tracing::{error, info};
+
+use crate::{
+    api::{ChangeFeedResponse, NAMESPACE_HEADER},
+    data_repository_manager::{DataRepositoryManager, RepositoryChange},
+    persistence::Repository,
+    server_config::{RepositorySyncConfig, SyncSourceConfig},
+};
+
+/// Periodically replicates repositories of other deployments into this one,
+/// by reading the change feed of each source after the position it was last
+/// synced to. Edge deployments serve searches close to their users this way,
+/// and staging deployments are refreshed from production.
+pub struct RepositorySync {
+    repository: Arc<Repository>,
+    repository_manager: Arc<DataRepositoryManager>,
+    config: RepositorySyncConfig,
+    client: reqwest::Client,
+}
+
+impl RepositorySync {
+    pub fn new(
+        repository: Arc<Repository>,
+        repository_manager: Arc<DataRepositoryManager>,
+        config: RepositorySyncConfig,
+    ) -> Self {
+        Self {
+            repository,
+            repository_manager,
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn start(self: Arc<Self>) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            for source in &self.config.sources {
+                if let Err(err) = self.sync(source).await {
+                    error!(
+                        "unable to sync repository {} from {}: {}",
+                        source.repository,
+                        source.address,
+                        err.to_string()
+                    );
+                }
+            }
+        }
+    }
+
+    /// Applies the changes the source made since the last sync, a batch at a
+    /// time. The checkpoint moves after every batch, so a sync which fails
+    /// resumes from the batch it failed on. Returns the number of changes
+    /// applied.
+    #[tracing::instrument(skip(self))]
+    pub async fn sync(&self, source: &SyncSourceConfig) -> Result<u64> {
+        let checkpoint = format!(
+            "{}/{}/{}",
+            source.address.trim_end_matches('/'),
+            source.namespace,
+            source.repository
+        );
+        let target = source.target_repository();
+        let mut cursor = self
+            .repository
+            .sync_checkpoint(&source.namespace, target, &checkpoint)
+            .await?
+            .unwrap_or(0);
+        let mut applied = 0;
+        loop {
+            let feed = self.read_changes(source, cursor).await?;
+            let changes = feed
+                .changes
+                .into_iter()
+                .map(RepositoryChange::try_from)
+                .collect::<Result<Vec<_>>>()?;
+            applied += changes.len() as u64;
+            self.repository_manager
+                .apply_changes(&source.namespace, target, changes, source.vectors)
+                .await?;
+            let next: u64 = feed.cursor.parse()?;
+            if next != cursor {
+                self.repository
+                    .save_sync_checkpoint(&source.namespace, target, &checkpoint, next)
+                    .await?;
+                cursor = next;
+            }
+            if !feed.has_more {
+                break;
+            }
+        }
+        if applied > 0 {
+            info!(
+                "synced {} changes of repository {} from {}",
+                applied, source.repository, source.address
+            );
+        }
+        Ok(applied)
+    }
+
+    async fn read_changes(
+        &self,
+        source: &SyncSourceConfig,
+        cursor: u64,
+    ) -> Result<ChangeFeedResponse> {
+        let url = format!(
+            "{}/repositories/{}/changes",
+            source.address.trim_end_matches('/'),
+            source.repository
+        );
+        let mut request = self
+            .client
+            .get(url)
+            .query(&[
+                ("cursor", cursor.to_string()),
+                ("limit", self.config.batch_size.to_string()),
+            ])
+            .header(NAMESPACE_HEADER, &source.namespace);
+        if let Some(api_key) = &source.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let feed = request.send().await?.error_for_status()?.json().await?;
+        Ok(feed)
+    }
+}
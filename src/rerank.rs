@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    extractor_router::ExtractorRouter,
+    internal_api,
+    persistence::{RerankerBackend, RerankerConfig},
+    vector_index::ScoredText,
+};
+
+/// The body posted to a reranking service.
+#[derive(Debug, Serialize)]
+struct RerankRequest<'a> {
+    query: &'a str,
+    documents: Vec<&'a str>,
+}
+
+/// The scores of the documents of a `RerankRequest`, in the same order.
+#[derive(Debug, Deserialize)]
+struct RerankResponse {
+    scores: Vec<f32>,
+}
+
+/// Scores the candidates of a search against the query with the reranker of
+/// the index which was searched.
+pub struct Reranker {
+    extractor_router: ExtractorRouter,
+    client: reqwest::Client,
+}
+
+impl Reranker {
+    pub fn new(coordinator_addr: &str) -> Self {
+        Self {
+            extractor_router: ExtractorRouter::new(coordinator_addr),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// The score of every candidate, in the order of the candidates.
+    #[tracing::instrument(skip(self, candidates))]
+    pub async fn scores(
+        &self,
+        config: &RerankerConfig,
+        query: &str,
+        candidates: &[ScoredText],
+    ) -> Result<Vec<f32>> {
+        let documents: Vec<&str> = candidates.iter().map(|c| c.text.as_str()).collect();
+        let scores = match &config.backend {
+            RerankerBackend::Extractor { name } => {
+                self.extractor_scores(name, query, &documents).await?
+            }
+            RerankerBackend::Http { url } => self.http_scores(url, query, documents).await?,
+        };
+        if scores.len() != candidates.len() {
+            return Err(anyhow!(
+                "the reranker returned {} scores for {} candidates",
+                scores.len(),
+                candidates.len()
+            ));
+        }
+        Ok(scores)
+    }
+
+    async fn extractor_scores(
+        &self,
+        extractor_name: &str,
+        query: &str,
+        documents: &[&str],
+    ) -> Result<Vec<f32>> {
+        let mut scores = Vec::new();
+        for document in documents {
+            let content = internal_api::Content {
+                content_type: mime::TEXT_PLAIN.to_string(),
+                source: document.as_bytes().into(),
+                feature: None,
+                metadata: HashMap::new(),
+            };
+            let extracted = self
+                .extractor_router
+                .extract(extractor_name, content, Some(json!({ "query": query })))
+                .await?;
+            let score = extracted
+                .first()
+                .and_then(|c| c.feature.as_ref())
+                .and_then(|f| f.data.as_f64())
+                .ok_or(anyhow!(
+                    "extractor {} did not return a score",
+                    extractor_name
+                ))?;
+            scores.push(score as f32);
+        }
+        Ok(scores)
+    }
+
+    async fn http_scores(&self, url: &str, query: &str, documents: Vec<&str>) -> Result<Vec<f32>> {
+        let response: RerankResponse = self
+            .client
+            .post(url)
+            .json(&RerankRequest { query, documents })
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response.scores)
+    }
+}
+
+/// Orders the candidates by the scores the reranker gave them and keeps the
+/// top `k`. The score of the reranker becomes the score of the result.
+pub fn apply_scores(candidates: Vec<ScoredText>, scores: Vec<f32>, k: usize) -> Vec<ScoredText> {
+    let mut reranked: Vec<ScoredText> = candidates
+        .into_iter()
+        .zip(scores)
+        .map(|(mut candidate, score)| {
+            candidate.confidence_score = score;
+            candidate.explanation.reranker_score = Some(score);
+            candidate
+        })
+        .collect();
+    reranked.sort_by(|a, b| b.confidence_score.total_cmp(&a.confidence_score));
+    reranked.truncate(k);
+    reranked
+}
+
+/// Picks `k` of the candidates by maximal marginal relevance, so that the
+/// results are not all chunks of the same content. Two candidates are as
+/// similar as they can be when they are chunks of the same content and not
+/// similar otherwise, and the relevance of the candidates is their score
+/// scaled to between 0 and 1. `lambda` weighs the relevance against the
+/// similarity to the candidates picked before, 1 keeps the order of the
+/// scores.
+pub fn diversify(candidates: Vec<ScoredText>, k: usize, lambda: f32) -> Vec<ScoredText> {
+    let (min, max) = candidates
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(min, max), c| {
+            (min.min(c.confidence_score), max.max(c.confidence_score))
+        });
+    let relevance = |c: &ScoredText| {
+        if max > min {
+            (c.confidence_score - min) / (max - min)
+        } else {
+            1.0
+        }
+    };
+    let mut remaining = candidates;
+    let mut picked: Vec<ScoredText> = Vec::new();
+    while picked.len() < k && !remaining.is_empty() {
+        let mut best = 0;
+        let mut best_score = f32::MIN;
+        for (i, candidate) in remaining.iter().enumerate() {
+            let similarity = if picked.iter().any(|p| p.content_id == candidate.content_id) {
+                1.0
+            } else {
+                0.0
+            };
+            let score = lambda * relevance(candidate) - (1.0 - lambda) * similarity;
+            if score > best_score {
+                best = i;
+                best_score = score;
+            }
+        }
+        picked.push(remaining.remove(best));
+    }
+    picked
+}
+
+/// Keeps at most `max_chunks` of the candidates of every content, the best
+/// scored ones, in the order of the candidates.
+pub fn collapse(candidates: Vec<ScoredText>, max_chunks: usize) -> Vec<ScoredText> {
+    let mut chunks_per_content: HashMap<String, usize> = HashMap::new();
+    candidates
+        .into_iter()
+        .filter(|candidate| {
+            let chunks = chunks_per_content
+                .entry(candidate.content_id.clone())
+                .or_default();
+            *chunks += 1;
+            *chunks <= max_chunks
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{persistence::IndexSnapshot, vector_index::HitExplanation};
+
+    fn candidate(rank: usize, text: &str, score: f32) -> ScoredText {
+        chunk_of(rank, text, text, score)
+    }
+
+    fn chunk_of(rank: usize, content_id: &str, text: &str, score: f32) -> ScoredText {
+        ScoredText {
+            text: text.into(),
+            content_id: content_id.into(),
+            offsets: None,
+            region: None,
+            metadata: HashMap::new(),
+            confidence_score: score,
+            explanation: HitExplanation {
+                rank,
+                chunk_id: text.into(),
+                parent_chunk_id: None,
+                similarity_score: score,
+                keyword_score: None,
+                reranker_score: None,
+                snapshot: IndexSnapshot {
+                    repository: "docs".into(),
+                    index_name: "embeddings".into(),
+                    vector_index_name: "default-docs-embeddings".into(),
+                    generation: 0,
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn test_apply_scores() {
+        let candidates = vec![
+            candidate(0, "a", 0.9),
+            candidate(1, "b", 0.8),
+            candidate(2, "c", 0.7),
+        ];
+        let reranked = apply_scores(candidates, vec![0.1, 0.5, 0.9], 2);
+        assert_eq!(
+            reranked
+                .iter()
+                .map(|r| (
+                    r.text.as_str(),
+                    r.explanation.rank,
+                    r.explanation.similarity_score,
+                    r.explanation.reranker_score
+                ))
+                .collect::<Vec<_>>(),
+            vec![("c", 2, 0.7, Some(0.9)), ("b", 1, 0.8, Some(0.5))]
+        );
+    }
+
+    #[test]
+    fn test_diversify() {
+        let candidates = || {
+            vec![
+                chunk_of(0, "a", "a1", 0.9),
+                chunk_of(1, "a", "a2", 0.88),
+                chunk_of(2, "a", "a3", 0.85),
+                chunk_of(3, "b", "b1", 0.7),
+                chunk_of(4, "c", "c1", 0.5),
+            ]
+        };
+        let texts =
+            |results: Vec<ScoredText>| results.into_iter().map(|r| r.text).collect::<Vec<String>>();
+        assert_eq!(
+            texts(diversify(candidates(), 3, 0.5)),
+            vec!["a1", "b1", "c1"]
+        );
+        assert_eq!(
+            texts(diversify(candidates(), 3, 1.0)),
+            vec!["a1", "a2", "a3"]
+        );
+        assert_eq!(texts(diversify(candidates(), 10, 0.5)).len(), 5);
+    }
+
+    #[test]
+    fn test_collapse() {
+        let candidates = vec![
+            chunk_of(0, "a", "a1", 0.9),
+            chunk_of(1, "a", "a2", 0.88),
+            chunk_of(2, "b", "b1", 0.8),
+            chunk_of(3, "a", "a3", 0.7),
+            chunk_of(4, "b", "b2", 0.6),
+        ];
+        let texts = collapse(candidates, 1)
+            .into_iter()
+            .map(|r| r.text)
+            .collect::<Vec<String>>();
+        assert_eq!(texts, vec!["a1", "b1"]);
+    }
+}
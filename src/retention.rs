@@ -0,0 +1,172 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use serde::Serialize;
+use tracing::{error, info};
+
+use crate::{
+    data_repository_manager::DataRepositoryManager,
+    persistence::{DataRepository, DeletedContent, Repository},
+    server_config::RetentionConfig,
+};
+
+/// What a run of the collector removed across all the repositories.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct RetentionSummary {
+    /// Content soft deleted because its retention policy expired it.
+    pub expired: u64,
+    pub purged: DeletedContent,
+    /// Repositories whose content could not be collected, which keep it
+    /// until the next run.
+    pub failed_repositories: u64,
+}
+
+/// Periodically soft deletes the content which the retention policy of its
+/// repository expires, and purges soft deleted content once its grace period
+/// is over, along with its chunks, their embeddings and its attributes.
+/// Content under a legal hold is kept.
+pub struct RetentionCollector {
+    repository: Arc<Repository>,
+    repository_manager: Arc<DataRepositoryManager>,
+    config: RetentionConfig,
+}
+
+impl RetentionCollector {
+    pub fn new(
+        repository: Arc<Repository>,
+        repository_manager: Arc<DataRepositoryManager>,
+        config: RetentionConfig,
+    ) -> Self {
+        Self {
+            repository,
+            repository_manager,
+            config,
+        }
+    }
+
+    pub async fn start(self: Arc<Self>) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.collect().await {
+                error!("unable to delete expired content: {}", err.to_string());
+            }
+        }
+    }
+
+    /// Soft deletes the expired content of all the repositories and purges
+    /// the content which was soft deleted before the grace period. A
+    /// repository which fails keeps its content until the next run.
+    #[tracing::instrument(skip(self))]
+    pub async fn collect(&self) -> Result<RetentionSummary> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let repositories = self.repository.repositories_in_all_namespaces().await?;
+        let mut summary = RetentionSummary::default();
+        for repository in &repositories {
+            let result = async {
+                let expired = self.expire(repository, now).await?;
+                let purged = self
+                    .purge(
+                        repository,
+                        now.saturating_sub(self.config.grace_period_secs),
+                    )
+                    .await?;
+                Ok::<_, anyhow::Error>((expired, purged))
+            }
+            .await;
+            match result {
+                Ok((expired, purged)) => {
+                    if expired > 0 || purged.content > 0 {
+                        info!(
+                            "expired {} content and reclaimed {} content, {} chunks, {} attributes and {} vectors of repository {} in namespace {}",
+                            expired,
+                            purged.content,
+                            purged.chunks,
+                            purged.attributes,
+                            purged.vectors,
+                            repository.name,
+                            repository.namespace
+                        );
+                    }
+                    summary.expired += expired;
+                    summary.purged += purged;
+                }
+                Err(err) => {
+                    error!(
+                        "unable to delete the expired content of repository {} in namespace {}: {}",
+                        repository.name,
+                        repository.namespace,
+                        err.to_string()
+                    );
+                    summary.failed_repositories += 1;
+                }
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Soft deletes the content which the retention policy of the repository
+    /// expires, and returns how many were expired.
+    async fn expire(&self, repository: &DataRepository, now: u64) -> Result<u64> {
+        let batch_size = self.config.batch_size.max(1);
+        let mut expired = 0;
+        loop {
+            let content_ids = self
+                .repository
+                .expired_content(
+                    &repository.namespace,
+                    &repository.name,
+                    &repository.retention,
+                    now,
+                    batch_size,
+                )
+                .await?;
+            let batch = self
+                .repository
+                .soft_delete_content(&repository.namespace, &repository.name, &content_ids, now)
+                .await?;
+            expired += batch;
+            if (content_ids.len() as u64) < batch_size || batch == 0 {
+                return Ok(expired);
+            }
+        }
+    }
+
+    /// Deletes the content which was soft deleted before `deleted_before`.
+    async fn purge(
+        &self,
+        repository: &DataRepository,
+        deleted_before: u64,
+    ) -> Result<DeletedContent> {
+        let batch_size = self.config.batch_size.max(1);
+        let mut deleted = DeletedContent::default();
+        loop {
+            let content_ids = self
+                .repository
+                .purgeable_content(
+                    &repository.namespace,
+                    &repository.name,
+                    deleted_before,
+                    batch_size,
+                )
+                .await?;
+            let batch = self
+                .repository_manager
+                .delete_content(&repository.namespace, &repository.name, &content_ids)
+                .await?;
+            deleted += batch;
+            // Content held while the batch was read is left in place, and
+            // would be read again by the next batch.
+            if (content_ids.len() as u64) < batch_size || batch.content == 0 {
+                return Ok(deleted);
+            }
+        }
+    }
+}
@@ -1,43 +1,99 @@
-use std::{net::SocketAddr, sync::Arc};
+use std::{collections::HashSet, net::SocketAddr, sync::Arc, time::Duration};
 
 use anyhow::Result;
 use axum::{
-    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
-    http::StatusCode,
-    routing::{get, post},
+    body::StreamBody,
+    extract::{BodyStream, DefaultBodyLimit, Multipart, Path, Query, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware,
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        IntoResponse,
+        Response,
+    },
+    routing::{delete, get, post, put},
     Json,
     Router,
 };
 use axum_otel_metrics::HttpMetricsLayerBuilder;
 use axum_tracing_opentelemetry::middleware::OtelAxumLayer;
+use base64::prelude::*;
+use bytes::Bytes;
 use pyo3::Python;
 use tokio::signal;
-use tracing::{error, info};
+use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing::{error, info, warn};
 use utoipa::OpenApi;
 use utoipa_rapidoc::RapiDoc;
 use utoipa_redoc::{Redoc, Servable};
 use utoipa_swagger_ui::SwaggerUi;
 
+#[cfg(feature = "fault-injection")]
+use crate::fault_injection::{FaultyBlobStorage, FaultyVectorDb};
 use crate::{
     api::*,
     attribute_index::AttributeIndexManager,
+    audit,
+    auth::{self, AuthState},
     blob_storage::BlobStorageBuilder,
-    data_repository_manager::DataRepositoryManager,
+    body_limit,
+    config_watcher::ConfigWatcher,
+    connectors::ConnectorScheduler,
+    dashboard::DashboardRollup,
+    data_repository_manager::{
+        DataRepositoryError,
+        DataRepositoryManager,
+        IngestedFile,
+        Ingestion,
+    },
+    embedding_cache::EmbeddingCache,
+    event_compaction::EventCompaction,
+    export,
     extractor_router::ExtractorRouter,
+    graph_index::PathQuery,
+    grpc::GrpcService,
+    health::HealthChecker,
+    idempotency::{self, IdempotencyKeys},
     internal_api::{CreateWork, CreateWorkResponse},
+    manifest::ManifestIngestion,
+    memory::Memory,
     persistence,
-    persistence::Repository,
-    server_config::ServerConfig,
+    persistence::{Repository, RepositoryError},
+    query_throttle::{self, QueryThrottle},
+    rate_limit::{self, RateLimiter},
+    read_only,
+    repository_sync::RepositorySync,
+    retention::RetentionCollector,
+    server_config::{ArchiveConfig, CorsConfig, ManifestConfig, ServerConfig},
+    tls,
     vector_index::VectorIndexManager,
-    vectordbs,
+    vectordbs::{self, resilience::ResilientVectorDb},
 };
 
-const DEFAULT_SEARCH_LIMIT: u64 = 5;
+pub const DEFAULT_SEARCH_LIMIT: u64 = 5;
+
+const DEFAULT_BINDING_PREVIEW_SAMPLE_SIZE: u64 = 10;
+
+const MAX_BINDING_PREVIEW_SAMPLE_SIZE: u64 = 100;
+
+/// The headers a handler sets on its response, beside its body.
+type ResponseHeaders<const N: usize> = [(header::HeaderName, String); N];
+
+/// A response body streamed from a channel, such as an index export.
+type ChannelBody = StreamBody<ReceiverStream<std::io::Result<Bytes>>>;
 
 #[derive(Clone, Debug)]
 pub struct RepositoryEndpointState {
     repository_manager: Arc<DataRepositoryManager>,
+    memory: Arc<Memory>,
+    query_throttle: Arc<QueryThrottle>,
     coordinator_addr: String,
+    archive_config: ArchiveConfig,
+    manifest_config: ManifestConfig,
+    idempotency_keys: Arc<IdempotencyKeys>,
+    embedding_cache: Arc<EmbeddingCache>,
+    health_checker: Arc<HealthChecker>,
 }
 
 #[derive(OpenApi)]
@@ -46,22 +102,83 @@ pub struct RepositoryEndpointState {
             create_repository,
             list_repositories,
             get_repository,
+            delete_repository,
             add_texts,
+            bulk_add_texts,
             list_indexes,
+            reindex,
+            warm_index,
+            graph_entities,
+            graph_neighbors,
+            graph_paths,
+            export_index,
+            update_index_reranker,
+            list_quarantined_attributes,
             index_search,
+            keyword_search,
             list_extractors,
             bind_extractor,
+            list_content,
+            read_content,
+            download_content,
+            delete_content,
+            restore_content,
+            refetch_content,
+            content_progress,
+            content_lineage,
+            list_work,
+            get_work,
+            repository_dashboard,
+            extractor_binding_stats,
+            pause_extractor_binding,
+            resume_extractor_binding,
+            list_legal_holds,
+            place_legal_hold,
+            release_legal_hold,
             list_events,
+            add_session_events,
+            list_session_events,
+            query_memory,
+            list_connector_syncs,
+            upload_archive,
+            create_ingestion_job,
+            list_ingestion_jobs,
+            get_ingestion_job,
+            list_ingestion_job_errors,
+            resume_ingestion_job,
+            create_api_key,
+            list_api_keys,
+            delete_api_key,
+            create_repository_template,
+            list_repository_templates,
+            get_repository_template,
+            delete_repository_template,
+            create_repository_from_template,
+            list_roles,
+            grant_role,
+            revoke_role,
+            list_audit_log,
+            query_load,
+            embedding_cache_stats,
+            healthz,
+            readyz,
             add_events,
+            ingest,
             attribute_lookup,
-            list_executors
+            query_attributes,
+            aggregate_attributes,
+            list_changes,
+            list_executors,
+            get_text_analysis,
+            update_text_analysis,
+            update_retention
         ),
         components(
-            schemas(CreateRepository, CreateRepositoryResponse, IndexDistance,
-                TextAddRequest, TextAdditionResponse, Text, IndexSearchResponse,
-                DocumentFragment, ListIndexesResponse, ExtractorOutputSchema, Index, SearchRequest, ListRepositoriesResponse, ListExtractorsResponse
-            , ExtractorDescription, DataRepository, ExtractorBinding, ExtractorFilter, ExtractorBindRequest, ExtractorBindResponse, Executor,
-        ListEventsResponse, EventAddRequest, EventAddResponse, Event, AttributeLookupResponse, ExtractedAttributes, ListExecutorsResponse)
+            schemas(CreateRepository, CreateRepositoryResponse, RepositoryVersionConflict, IndexDistance, Modality, Quantization, QuantizationTradeoff, PayloadIndex, PayloadFieldType,
+                TextAddRequest, TextAdditionResponse, InlineExtractionStatus, InlineExtraction, Text, BulkItemStatus, BulkItemResult, BulkAddTextsResponse, IndexSearchResponse,
+                DocumentFragment, ChunkOffsets, ImageRegion, SearchExplanation, ListIndexesResponse, ReindexResponse, WarmIndexResponse, RerankerBackend, RerankerConfig, UpdateRerankerRequest, UpdateRerankerResponse, ExtractorOutputSchema, Index, SearchRequest, ImageQuery, MmrOptions, SummaryOptions, SummaryMode, KeywordSearchRequest, AttributeFilter, ListRepositoriesResponse, ListExtractorsResponse
+            , ExtractorDescription, DataRepository, ExtractorBinding, Chunker, ExtractorFilter, OutputSink, DataConnector, SyncSchedule, SourceType, WebCrawlerConfig, KafkaSourceConfig, ExtractorBindRequest, ExtractorBindResponse, BindingPreview, MatchedContent, Executor,
+        ListEventsResponse, EventAddRequest, EventAddResponse, Event, AddSessionEventsResponse, ListSessionEventsResponse, QueryMemoryRequest, MemoryResult, QueryMemoryResponse, IngestRequest, IngestResponse, IngestFile, AttributeLookupResponse, ExtractedAttributes, QueryAttributesResponse, AttributeGroup, AggregateAttributesResponse, InvalidAttributesPolicy, QuarantinedAttributes, ListQuarantinedAttributesResponse, PayloadEncoding, ReplicatedChange, ChangeFeedResponse, ListExecutorsResponse, TextAnalysisConfig, DedupPolicy, RetentionPolicy, TextAnalysisResponse, ConnectorSync, ListConnectorSyncsResponse, UploadArchiveResponse, ExportFormat, IngestionJob, ListIngestionJobsResponse, IngestionRowError, ListIngestionErrorsResponse, ApiKeyScope, CreateApiKeyRequest, CreateApiKeyResponse, ApiKey, ListApiKeysResponse, ListContentResponse, ContentInfo, Provenance, DeleteContentResponse, RefetchContentResponse, ContentProgressEvent, LineageContent, ContentLineage, GraphEntity, GraphRelationship, GraphEntitiesResponse, GraphNeighbor, GraphNeighborsResponse, GraphPathsResponse, DeleteRepositoryResponse, ExtractionState, ListWorkResponse, WorkInfo, GetWorkResponse, RepositoryDashboard, ExtractorVolume, IndexSize, WorkFailure, ExtractorBindingStats, ExtractorBindingStatsResponse, UpdateExtractorBindingResponse, LegalHold, PlaceLegalHoldRequest, ListLegalHoldsResponse, RepositoryTemplate, ListRepositoryTemplatesResponse, CreateRepositoryFromTemplate, RepositoryRole, GrantRoleRequest, RoleBinding, ListRoleBindingsResponse, AuditAction, AuditEvent, ListAuditEventsResponse, RepositoryQueryLoad, QueryLoadResponse, EmbeddingCacheResponse, DependencyStatus, HealthResponse)
         ),
         tags(
             (name = "indexify", description = "Indexify API")
@@ -80,21 +197,50 @@ impl Server {
     }
 
     pub async fn run(&self) -> Result<()> {
-        let repository = Arc::new(Repository::new(&self.config.db_url).await?);
+        let repository = Repository::new(&self.config.db_url, &self.config.database).await?;
+        #[cfg(feature = "fault-injection")]
+        let repository =
+            repository.with_fault_injection(self.config.fault_injection.database.clone());
+        let repository = Arc::new(repository);
         let vector_db = vectordbs::create_vectordb(
             self.config.index_config.clone(),
             repository.get_db_conn_clone(),
         )?;
-        let vector_index_manager = Arc::new(VectorIndexManager::new(
-            repository.clone(),
-            vector_db.clone(),
-            self.config.coordinator_lis_addr_sock().unwrap().to_string(),
-        ));
+        #[cfg(feature = "fault-injection")]
+        let vector_db =
+            FaultyVectorDb::wrap(vector_db, self.config.fault_injection.vector_store.clone());
+        let vector_db =
+            ResilientVectorDb::wrap(vector_db, self.config.index_config.resilience.clone());
+        vectordbs::warmup_vectordb(&vector_db, &self.config.index_config).await;
+        let vector_index_manager = Arc::new(
+            VectorIndexManager::new(
+                repository.clone(),
+                vector_db.clone(),
+                self.config.coordinator_lis_addr_sock().unwrap().to_string(),
+            )
+            .with_embedding_cache(self.config.embedding_cache.clone()),
+        );
+        let embedding_cache = vector_index_manager.embedding_cache();
         let attribute_index_manager = Arc::new(AttributeIndexManager::new(repository.clone()));
 
         let blob_storage =
             BlobStorageBuilder::new(Arc::new(self.config.blob_storage.clone())).build()?;
+        #[cfg(feature = "fault-injection")]
+        let blob_storage =
+            FaultyBlobStorage::wrap(blob_storage, self.config.fault_injection.blob_store.clone());
 
+        let health_checker = Arc::new(HealthChecker::new(
+            repository.clone(),
+            vector_db.clone(),
+            blob_storage.clone(),
+            &self.config.blob_storage.backend,
+        ));
+        let memory = Arc::new(Memory::new(
+            repository.clone(),
+            vector_index_manager.clone(),
+            &self.config.coordinator_lis_addr_sock()?.to_string(),
+            self.config.memory.clone(),
+        ));
         let repository_manager = Arc::new(
             DataRepositoryManager::new(
                 repository.clone(),
@@ -102,18 +248,112 @@ impl Server {
                 attribute_index_manager,
                 blob_storage.clone(),
             )
-            .await?,
+            .await?
+            .with_inline_threshold(self.config.blob_storage.inline_threshold_bytes)
+            .with_inline_extraction(
+                &self.config.coordinator_lis_addr_sock()?.to_string(),
+                self.config.inline_extraction.clone(),
+            ),
         );
-        if let Err(err) = repository_manager
-            .create_default_repository(&self.config)
-            .await
-        {
-            panic!("failed to create default repository: {}", err)
+        let idempotency_keys = Arc::new(IdempotencyKeys::new(
+            repository.clone(),
+            self.config.idempotency.clone(),
+        ));
+        let mut config_watcher = ConfigWatcher::new(&self.config, repository.clone());
+        if self.config.read_only {
+            info!(
+                "server is in read-only mode, connectors, dashboard rollups, memory \
+                 summarization, retention, ingestion, idempotency key purge, embedding cache \
+                 eviction and event compaction jobs are not run"
+            );
+        } else {
+            if let Err(err) = repository_manager
+                .create_default_repository(&self.config)
+                .await
+            {
+                panic!("failed to create default repository: {}", err)
+            }
+            let connector_scheduler = Arc::new(ConnectorScheduler::new(
+                repository.clone(),
+                repository_manager.clone(),
+                self.config.connectors.clone(),
+            ));
+            tokio::spawn(connector_scheduler.clone().start());
+            config_watcher = config_watcher.with_connector_scheduler(connector_scheduler);
+            let dashboard_rollup = Arc::new(DashboardRollup::new(
+                repository.clone(),
+                self.config.dashboards.clone(),
+            ));
+            tokio::spawn(dashboard_rollup.start());
+            tokio::spawn(memory.clone().start());
+            let retention_collector = Arc::new(RetentionCollector::new(
+                repository.clone(),
+                repository_manager.clone(),
+                self.config.retention.clone(),
+            ));
+            tokio::spawn(retention_collector.start());
+            let manifest_ingestion = Arc::new(ManifestIngestion::new(
+                repository.clone(),
+                repository_manager.clone(),
+                self.config.manifests.clone(),
+            ));
+            tokio::spawn(manifest_ingestion.start());
+            tokio::spawn(idempotency_keys.clone().start());
+            tokio::spawn(embedding_cache.clone().start());
+            let event_compaction = Arc::new(EventCompaction::new(
+                repository.clone(),
+                self.config.event_compaction.clone(),
+            ));
+            tokio::spawn(event_compaction.start());
         }
+        tokio::spawn(config_watcher.start());
+        // Replicas are read-only to their clients, but still apply the changes
+        // of the deployments they replicate.
+        if !self.config.sync.sources.is_empty() {
+            let repository_sync = Arc::new(RepositorySync::new(
+                repository.clone(),
+                repository_manager.clone(),
+                self.config.sync.clone(),
+            ));
+            tokio::spawn(repository_sync.start());
+        }
+        let query_throttle = Arc::new(QueryThrottle::new(self.config.query_throttle.clone()));
         let repository_endpoint_state = RepositoryEndpointState {
             repository_manager: repository_manager.clone(),
+            memory,
+            query_throttle: query_throttle.clone(),
             coordinator_addr: self.config.coordinator_lis_addr_sock().unwrap().to_string(),
+            archive_config: self.config.archives.clone(),
+            manifest_config: self.config.manifests.clone(),
+            idempotency_keys,
+            embedding_cache,
+            health_checker,
         };
+        if self.config.grpc.enabled {
+            let auth = self
+                .config
+                .auth
+                .enabled
+                .then(|| AuthState::new(repository.clone(), &self.config.auth));
+            let grpc_service = GrpcService::new(
+                repository.clone(),
+                repository_manager.clone(),
+                query_throttle.clone(),
+                auth,
+                self.config.read_only,
+                &self.config.coordinator_lis_addr_sock()?.to_string(),
+            );
+            let grpc_addr = self.config.grpc_addr_sock()?;
+            let grpc_tls = self.config.tls.api.clone();
+            tokio::spawn(async move {
+                if let Err(err) = grpc_service
+                    .serve(grpc_addr, grpc_tls.as_ref(), shutdown_signal())
+                    .await
+                {
+                    error!("grpc server failed: {}", err.to_string());
+                }
+            });
+        }
         let metrics = HttpMetricsLayerBuilder::new().build();
         let app = Router::new()
             .merge(metrics.routes())
@@ -121,6 +361,14 @@ impl Server {
             .merge(Redoc::with_url("/redoc", ApiDoc::openapi()))
             .merge(RapiDoc::new("/api-docs/openapi.json").path("/rapidoc"))
             .route("/", get(root))
+            .route(
+                "/healthz",
+                get(healthz).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/readyz",
+                get(readyz).with_state(repository_endpoint_state.clone()),
+            )
             .route(
                 "/repositories/:repository_name/extractor_bindings",
                 post(bind_extractor).with_state(repository_endpoint_state.clone()),
@@ -129,14 +377,72 @@ impl Server {
                 "/repositories/:repository_name/indexes",
                 get(list_indexes).with_state(repository_endpoint_state.clone()),
             )
+            .route(
+                "/repositories/:repository_name/indexes/:index_name/reindex",
+                post(reindex).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/indexes/:index_name/warm",
+                post(warm_index).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/indexes/:index_name/graph/entities",
+                get(graph_entities).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/indexes/:index_name/graph/neighbors",
+                get(graph_neighbors).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/indexes/:index_name/graph/paths",
+                get(graph_paths).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/indexes/:index_name/export",
+                get(export_index).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/indexes/:index_name/reranker",
+                put(update_index_reranker).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/indexes/:index_name/quarantine",
+                get(list_quarantined_attributes).with_state(repository_endpoint_state.clone()),
+            )
             .route(
                 "/repositories/:repository_name/add_texts",
                 post(add_texts).with_state(repository_endpoint_state.clone()),
             )
+            .route(
+                "/repositories/:repository_name/bulk_add_texts",
+                post(bulk_add_texts).with_state(repository_endpoint_state.clone()),
+            )
             .route(
                 "/repositories/:repository_name/upload_file",
                 post(upload_file).with_state(repository_endpoint_state.clone()),
             )
+            .route(
+                "/repositories/:repository_name/upload_archive",
+                post(upload_archive).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/ingestion_jobs",
+                post(create_ingestion_job)
+                    .get(list_ingestion_jobs)
+                    .with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/ingestion_jobs/:job_id",
+                get(get_ingestion_job).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/ingestion_jobs/:job_id/errors",
+                get(list_ingestion_job_errors).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/ingestion_jobs/:job_id/resume",
+                post(resume_ingestion_job).with_state(repository_endpoint_state.clone()),
+            )
             .route(
                 "/repositories/:repository_name/run_extractors",
                 post(run_extractors).with_state(repository_endpoint_state.clone()),
@@ -145,18 +451,146 @@ impl Server {
                 "/repositories/:repository_name/search",
                 post(index_search).with_state(repository_endpoint_state.clone()),
             )
+            .route(
+                "/repositories/:repository_name/keyword_search",
+                post(keyword_search).with_state(repository_endpoint_state.clone()),
+            )
             .route(
                 "/repositories/:repository_name/attributes",
                 get(attribute_lookup).with_state(repository_endpoint_state.clone()),
             )
+            .route(
+                "/repositories/:repository_name/attributes/query",
+                get(query_attributes).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/attributes/aggregate",
+                get(aggregate_attributes).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/changes",
+                get(list_changes).with_state(repository_endpoint_state.clone()),
+            )
             .route(
                 "/repositories/:repository_name/events",
                 post(add_events).with_state(repository_endpoint_state.clone()),
             )
+            .route(
+                "/repositories/:repository_name/ingest",
+                post(ingest).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/content",
+                get(list_content).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/content/:content_id/data",
+                get(read_content).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/content/:content_id/download",
+                get(download_content).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/content/:content_id",
+                delete(delete_content).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/content/:content_id/restore",
+                post(restore_content).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/content/:content_id/refetch",
+                post(refetch_content).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/content/:content_id/progress",
+                get(content_progress).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/content/:content_id/lineage",
+                get(content_lineage).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/work",
+                get(list_work).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/work/:work_id",
+                get(get_work).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/extractor_bindings/stats",
+                get(extractor_binding_stats).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/extractor_bindings/:binding/pause",
+                post(pause_extractor_binding).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/extractor_bindings/:binding/resume",
+                post(resume_extractor_binding).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/dashboard",
+                get(repository_dashboard).with_state(repository_endpoint_state.clone()),
+            )
             .route(
                 "/repositories/:repository_name/events",
                 get(list_events).with_state(repository_endpoint_state.clone()),
             )
+            .route(
+                "/repositories/:repository_name/sessions/:session_id/events",
+                post(add_session_events).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/sessions/:session_id/events",
+                get(list_session_events).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/sessions/:session_id/query",
+                post(query_memory).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/connector_syncs",
+                get(list_connector_syncs).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/text_analysis",
+                get(get_text_analysis).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/text_analysis",
+                put(update_text_analysis).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/retention",
+                put(update_retention).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/roles",
+                get(list_roles).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/roles/:principal",
+                put(grant_role).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/roles/:principal",
+                delete(revoke_role).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/legal_holds",
+                get(list_legal_holds).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/legal_holds",
+                post(place_legal_hold).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repositories/:repository_name/legal_holds/:hold_id",
+                delete(release_legal_hold).with_state(repository_endpoint_state.clone()),
+            )
             .route(
                 "/repositories",
                 post(create_repository).with_state(repository_endpoint_state.clone()),
@@ -167,7 +601,9 @@ impl Server {
             )
             .route(
                 "/repositories/:repository_name",
-                get(get_repository).with_state(repository_endpoint_state.clone()),
+                get(get_repository)
+                    .delete(delete_repository)
+                    .with_state(repository_endpoint_state.clone()),
             )
             .route(
                 "/executors",
@@ -181,18 +617,141 @@ impl Server {
                 "/extractors/extract",
                 post(extract_content).with_state(repository_endpoint_state.clone()),
             )
+            .route(
+                "/api_keys",
+                post(create_api_key).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/api_keys",
+                get(list_api_keys).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/api_keys/:api_key_id",
+                delete(delete_api_key).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repository_templates",
+                post(create_repository_template).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repository_templates",
+                get(list_repository_templates).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repository_templates/:template_name",
+                get(get_repository_template).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repository_templates/:template_name",
+                delete(delete_repository_template).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/repository_templates/:template_name/repositories",
+                post(create_repository_from_template).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/audit_log",
+                get(list_audit_log).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/query_load",
+                get(query_load).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/embedding_cache",
+                get(embedding_cache_stats).with_state(repository_endpoint_state.clone()),
+            );
+        // Queries are throttled after they are authenticated, so that requests
+        // which are rejected do not take the slots of queries.
+        let app = app.layer(middleware::from_fn_with_state(
+            query_throttle,
+            query_throttle::throttle_queries,
+        ));
+        // The audit log runs inside the authentication to record the principal.
+        let app = app.layer(middleware::from_fn_with_state(
+            repository.clone(),
+            audit::record_mutations,
+        ));
+        // Api keys are limited once they are authenticated, and addresses
+        // before, so that unauthenticated clients are limited as well.
+        let rate_limiter = Arc::new(RateLimiter::new(self.config.http.rate_limits.clone()));
+        let app = app.layer(middleware::from_fn_with_state(
+            rate_limiter.clone(),
+            rate_limit::limit_by_key,
+        ));
+        let app = if self.config.auth.enabled {
+            info!("api key authentication is enabled");
+            app.layer(middleware::from_fn_with_state(
+                AuthState::new(repository.clone(), &self.config.auth),
+                auth::authenticate,
+            ))
+        } else {
+            app
+        };
+        let app = app.layer(middleware::from_fn_with_state(
+            rate_limiter,
+            rate_limit::limit_by_ip,
+        ));
+        let app = if self.config.read_only {
+            app.layer(middleware::from_fn(read_only::reject_writes))
+        } else {
+            app
+        };
+        let app = app
             .layer(OtelAxumLayer::default())
             .layer(metrics)
+            .layer(middleware::from_fn_with_state(
+                self.config.http.body_limits.clone(),
+                body_limit::limit_bodies,
+            ))
             .layer(DefaultBodyLimit::disable());
+        // Preflight requests are answered before they are authenticated.
+        let app = match cors_layer(&self.config.http.cors) {
+            Some(cors) => app.layer(cors),
+            None => app,
+        };
         info!("server is listening at addr {}", &self.addr.to_string());
-        axum::Server::bind(&self.addr)
-            .serve(app.into_make_service())
-            .with_graceful_shutdown(shutdown_signal())
-            .await?;
+        tls::bind_and_serve(
+            self.addr,
+            app,
+            self.config.tls.api.as_ref(),
+            shutdown_signal(),
+        )
+        .await?;
         Ok(())
     }
 }
 
+/// The CORS layer of the allowed origins, `None` when no origin is allowed.
+fn cors_layer(config: &CorsConfig) -> Option<CorsLayer> {
+    if config.allowed_origins.is_empty() {
+        return None;
+    }
+    let allow_origin = if config.allowed_origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(config.allowed_origins.iter().filter_map(|origin| {
+            HeaderValue::from_str(origin)
+                .map_err(|_| warn!("ignoring the invalid cors origin {}", origin))
+                .ok()
+        }))
+    };
+    Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods(Any)
+            .allow_headers(Any)
+            .expose_headers([
+                header::ETAG,
+                header::RETRY_AFTER,
+                header::ACCEPT_RANGES,
+                header::CONTENT_RANGE,
+                header::CONTENT_LENGTH,
+            ])
+            .max_age(Duration::from_secs(config.max_age_secs)),
+    )
+}
+
 #[tracing::instrument]
 async fn root() -> &'static str {
     "Indexify Server"
@@ -207,13 +766,18 @@ async fn root() -> &'static str {
     tag = "indexify",
     responses(
         (status = 200, description = "Repository synced successfully", body = CreateRepositoryResponse),
+        (status = BAD_REQUEST, description = "The If-Match header is not the entity tag of a repository version"),
+        (status = 404, description = "The repository of the If-Match header does not exist"),
+        (status = CONFLICT, description = "The repository was updated since the version of the If-Match header", body = RepositoryVersionConflict),
         (status = INTERNAL_SERVER_ERROR, description = "Unable to sync repository")
     ),
 )]
 async fn create_repository(
     State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    IfMatch(expected_version): IfMatch,
     Json(payload): Json<CreateRepository>,
-) -> Result<Json<CreateRepositoryResponse>, IndexifyAPIError> {
+) -> Result<Response, IndexifyAPIError> {
     let extractor_bindings = payload
         .extractor_bindings
         .clone()
@@ -221,22 +785,53 @@ async fn create_repository(
         .map(|e| into_persistence_extractor_binding(&payload.name, e))
         .collect();
     let data_repository = &persistence::DataRepository {
+        namespace,
         name: payload.name.clone(),
         extractor_bindings,
         metadata: payload.metadata.clone(),
-        data_connectors: vec![],
+        dedup_policy: payload.dedup_policy.into(),
+        retention: payload.retention.clone().into(),
+        data_connectors: payload
+            .data_connectors
+            .clone()
+            .into_iter()
+            .map(|c| c.into())
+            .collect(),
+        text_analysis: payload.text_analysis.clone().into(),
+        version: 0,
     };
-    state
+    let version = match state
         .repository_manager
-        .create(data_repository)
+        .update(data_repository, expected_version)
         .await
-        .map_err(|e| {
-            IndexifyAPIError::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("failed to sync repository: {}", e),
-            )
-        })?;
-    Ok(Json(CreateRepositoryResponse {}))
+    {
+        Ok(version) => version,
+        Err(e) => {
+            return match e.downcast_ref::<RepositoryError>() {
+                Some(RepositoryError::RepositoryVersionConflict(current)) => Ok((
+                    StatusCode::CONFLICT,
+                    [(header::ETAG, repository_etag(current.version))],
+                    Json(RepositoryVersionConflict {
+                        message: e.to_string(),
+                        repository: current.as_ref().clone().into(),
+                    }),
+                )
+                    .into_response()),
+                Some(RepositoryError::RepositoryNotFound(_)) => {
+                    Err(IndexifyAPIError::new(StatusCode::NOT_FOUND, e.to_string()))
+                }
+                _ => Err(IndexifyAPIError::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("failed to sync repository: {}", e),
+                )),
+            };
+        }
+    };
+    Ok((
+        [(header::ETAG, repository_etag(version))],
+        Json(CreateRepositoryResponse { version }),
+    )
+        .into_response())
 }
 
 #[tracing::instrument]
@@ -244,6 +839,7 @@ async fn create_repository(
     get,
     path = "/repositories",
     tag = "indexify",
+    params(ListParams),
     responses(
         (status = 200, description = "List of Data Repositories registered on the server", body = ListRepositoriesResponse),
         (status = INTERNAL_SERVER_ERROR, description = "Unable to sync repository")
@@ -251,10 +847,12 @@ async fn create_repository(
 )]
 async fn list_repositories(
     State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
 ) -> Result<Json<ListRepositoriesResponse>, IndexifyAPIError> {
     let repositories = state
         .repository_manager
-        .list_repositories()
+        .list_repositories(&namespace)
         .await
         .map_err(|e| {
             IndexifyAPIError::new(
@@ -263,9 +861,7 @@ async fn list_repositories(
             )
         })?;
     let data_repos = repositories.into_iter().map(|r| r.into()).collect();
-    Ok(Json(ListRepositoriesResponse {
-        repositories: data_repos,
-    }))
+    Ok(Json(Page::from_items(data_repos, &params)?))
 }
 
 #[tracing::instrument]
@@ -282,10 +878,11 @@ async fn list_repositories(
 async fn get_repository(
     Path(repository_name): Path<String>,
     State(state): State<RepositoryEndpointState>,
-) -> Result<Json<GetRepositoryResponse>, IndexifyAPIError> {
+    Namespace(namespace): Namespace,
+) -> Result<(ResponseHeaders<1>, Json<GetRepositoryResponse>), IndexifyAPIError> {
     let data_repo = state
         .repository_manager
-        .get(&repository_name)
+        .get(&namespace, &repository_name)
         .await
         .map_err(|e| {
             IndexifyAPIError::new(
@@ -293,18 +890,163 @@ async fn get_repository(
                 format!("failed to get repository: {}", e),
             )
         })?;
-    Ok(Json(GetRepositoryResponse {
-        repository: data_repo.into(),
+    Ok((
+        [(header::ETAG, repository_etag(data_repo.version))],
+        Json(GetRepositoryResponse {
+            repository: data_repo.into(),
+        }),
+    ))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    delete,
+    path = "/repositories/{repository_name}",
+    tag = "indexify",
+    params(DeleteRepositoryParams),
+    responses(
+        (status = 200, description = "Repository was deleted, or would be on a dry run", body = DeleteRepositoryResponse),
+        (status = 404, description = "Repository not found"),
+        (status = CONFLICT, description = "Repository is not empty or is under a legal hold"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to delete repository")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn delete_repository(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<DeleteRepositoryParams>,
+) -> Result<Json<DeleteRepositoryResponse>, IndexifyAPIError> {
+    let deletion = state
+        .repository_manager
+        .delete_repository(&namespace, &repository_name, params.force, params.dry_run)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                DataRepositoryError::Persistence(RepositoryError::RepositoryHeld(_)) |
+                DataRepositoryError::RepositoryNotEmpty(_) => StatusCode::CONFLICT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(DeleteRepositoryResponse::new(
+        deletion,
+        params.dry_run,
+    )))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/text_analysis",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Stop words and synonyms of the repository", body = TextAnalysisResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to get repository")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn get_text_analysis(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<TextAnalysisResponse>, IndexifyAPIError> {
+    let data_repo = state
+        .repository_manager
+        .get(&namespace, &repository_name)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(TextAnalysisResponse {
+        text_analysis: data_repo.text_analysis.into(),
+    }))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    put,
+    path = "/repositories/{repository_name}/text_analysis",
+    request_body = TextAnalysisConfig,
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Stop words and synonyms were updated", body = TextAnalysisResponse),
+        (status = BAD_REQUEST, description = "Unknown language"),
+        (status = 404, description = "Repository not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to update the repository")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn update_text_analysis(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Json(payload): Json<TextAnalysisConfig>,
+) -> Result<Json<TextAnalysisResponse>, IndexifyAPIError> {
+    state
+        .repository_manager
+        .update_text_analysis(&namespace, &repository_name, payload.clone().into())
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::InvalidTextAnalysis(_) => StatusCode::BAD_REQUEST,
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(TextAnalysisResponse {
+        text_analysis: payload,
     }))
 }
 
+#[tracing::instrument]
+#[utoipa::path(
+    put,
+    path = "/repositories/{repository_name}/retention",
+    request_body = RetentionPolicy,
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Retention policy was updated", body = RetentionPolicy),
+        (status = 404, description = "Repository not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to update the repository")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn update_retention(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Json(payload): Json<RetentionPolicy>,
+) -> Result<Json<RetentionPolicy>, IndexifyAPIError> {
+    state
+        .repository_manager
+        .update_retention(&namespace, &repository_name, payload.clone().into())
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(payload))
+}
+
 #[utoipa::path(
     post,
     path = "/repositories/{repository_name}/extractor_bindings",
     request_body = ExtractorBindRequest,
     tag = "indexify",
+    params(ExtractorBindParams),
     responses(
-        (status = 200, description = "Extractor binded successfully", body = ExtractorBindResponse),
+        (status = 200, description = "Extractor binded successfully, or the content it would extract on a dry run", body = ExtractorBindResponse),
         (status = INTERNAL_SERVER_ERROR, description = "Unable to bind extractor to repository")
     ),
 )]
@@ -314,14 +1056,39 @@ async fn bind_extractor(
     // FIXME: also throws a 500 when the index name already exists
     Path(repository_name): Path<String>,
     State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ExtractorBindParams>,
     Json(payload): Json<ExtractorBindRequest>,
 ) -> Result<Json<ExtractorBindResponse>, IndexifyAPIError> {
+    let extractor_binding =
+        into_persistence_extractor_binding(&repository_name, payload.extractor_binding);
+    if params.dry_run {
+        let preview = state
+            .repository_manager
+            .preview_extractor_binding(
+                &namespace,
+                &repository_name,
+                &extractor_binding,
+                params
+                    .sample_size
+                    .unwrap_or(DEFAULT_BINDING_PREVIEW_SAMPLE_SIZE)
+                    .min(MAX_BINDING_PREVIEW_SAMPLE_SIZE),
+            )
+            .await
+            .map_err(|e| {
+                IndexifyAPIError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("failed to preview extractor binding: {}", e),
+                )
+            })?;
+        return Ok(Json(ExtractorBindResponse {
+            index_names: vec![],
+            preview: Some(preview.into()),
+        }));
+    }
     let index_names = state
         .repository_manager
-        .add_extractor_binding(
-            &repository_name,
-            &into_persistence_extractor_binding(&repository_name, payload.extractor_binding),
-        )
+        .add_extractor_binding(&namespace, &repository_name, &extractor_binding)
         .await
         .map_err(|e| {
             IndexifyAPIError::new(
@@ -333,13 +1100,10 @@ async fn bind_extractor(
         .map(|i| i.into())
         .collect();
 
-    if let Err(err) =
-        schedule_extraction(&repository_name, &state.coordinator_addr.to_string()).await
-    {
-        error!("unable to run extractors: {}", err.to_string());
-    }
-
-    Ok(Json(ExtractorBindResponse { index_names }))
+    Ok(Json(ExtractorBindResponse {
+        index_names,
+        preview: None,
+    }))
 }
 
 #[tracing::instrument]
@@ -350,38 +1114,213 @@ async fn bind_extractor(
     tag = "indexify",
     responses(
         (status = 200, description = "Texts were successfully added to the repository", body = TextAdditionResponse),
-        (status = BAD_REQUEST, description = "Unable to add texts")
+        (status = BAD_REQUEST, description = "Unable to add texts"),
+        (status = CONFLICT, description = "A request with the same idempotency key is in progress"),
+        (status = UNPROCESSABLE_ENTITY, description = "The idempotency key was used for a different request")
     ),
 )]
 #[axum_macros::debug_handler]
 async fn add_texts(
     Path(repository_name): Path<String>,
     State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    IdempotencyKey(idempotency_key): IdempotencyKey,
     Json(payload): Json<TextAddRequest>,
 ) -> Result<Json<TextAdditionResponse>, IndexifyAPIError> {
-    let texts = payload
+    let request_hash = idempotency::request_hash("add_texts", &payload);
+    let texts: Vec<persistence::ContentPayload> = payload
         .documents
         .iter()
-        .map(|d| {
-            persistence::ContentPayload::from_text(&repository_name, &d.text, d.metadata.clone())
-        })
+        .map(|d| d.content_payload(&repository_name))
         .collect();
+    let content_ids = texts.iter().map(|t| t.id.clone()).collect();
+    let response = state
+        .idempotency_keys
+        .run(
+            &namespace,
+            &repository_name,
+            idempotency_key.as_deref(),
+            &request_hash,
+            async {
+                if payload.inline {
+                    let inline_extractions = state
+                        .repository_manager
+                        .add_texts_inline(&namespace, &repository_name, texts)
+                        .await
+                        .map_err(|e| {
+                            IndexifyAPIError::new(
+                                StatusCode::BAD_REQUEST,
+                                format!("failed to add text: {}", e),
+                            )
+                        })?;
+                    return Ok(TextAdditionResponse {
+                        content_ids,
+                        inline_extractions: inline_extractions
+                            .into_iter()
+                            .map(|e| e.into())
+                            .collect(),
+                    });
+                }
+                state
+                    .repository_manager
+                    .add_texts(&namespace, &repository_name, texts)
+                    .await
+                    .map_err(|e| {
+                        IndexifyAPIError::new(
+                            StatusCode::BAD_REQUEST,
+                            format!("failed to add text: {}", e),
+                        )
+                    })?;
+                Ok(TextAdditionResponse {
+                    content_ids,
+                    ..Default::default()
+                })
+            },
+        )
+        .await?;
+    Ok(Json(response))
+}
+
+/// Writes the documents of a bulk request in batches, and keeps the outcome of
+/// every line.
+struct BulkTextsWriter<'a> {
+    repository_manager: &'a DataRepositoryManager,
+    namespace: &'a str,
+    repository: &'a str,
+    batch_size: usize,
+    line: usize,
+    batch: Vec<(usize, persistence::ContentPayload)>,
+    results: Vec<BulkItemResult>,
+}
+
+impl<'a> BulkTextsWriter<'a> {
+    fn new(
+        repository_manager: &'a DataRepositoryManager,
+        namespace: &'a str,
+        repository: &'a str,
+    ) -> Self {
+        Self {
+            repository_manager,
+            namespace,
+            repository,
+            batch_size: repository_manager.insert_batch_size(),
+            line: 0,
+            batch: Vec::new(),
+            results: Vec::new(),
+        }
+    }
+
+    async fn add_line(&mut self, line: &[u8]) {
+        self.line += 1;
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return;
+        }
+        match serde_json::from_slice::<Text>(line) {
+            Ok(text) => {
+                let payload = text.content_payload(self.repository);
+                self.batch.push((self.line, payload));
+                if self.batch.len() >= self.batch_size {
+                    self.flush().await;
+                }
+            }
+            Err(err) => self.results.push(BulkItemResult {
+                line: self.line,
+                status: BulkItemStatus::Invalid,
+                content_id: None,
+                error: Some(err.to_string()),
+            }),
+        }
+    }
+
+    async fn flush(&mut self) {
+        if self.batch.is_empty() {
+            return;
+        }
+        let (lines, payloads): (Vec<usize>, Vec<persistence::ContentPayload>) =
+            std::mem::take(&mut self.batch).into_iter().unzip();
+        let content_ids: Vec<String> = payloads.iter().map(|p| p.id.clone()).collect();
+        let result = self
+            .repository_manager
+            .add_texts(self.namespace, self.repository, payloads)
+            .await;
+        if let Err(err) = &result {
+            error!("unable to add a batch of {} texts: {}", lines.len(), err);
+        }
+        for (line, content_id) in lines.into_iter().zip(content_ids) {
+            self.results.push(BulkItemResult {
+                line,
+                status: match result {
+                    Ok(_) => BulkItemStatus::Added,
+                    Err(_) => BulkItemStatus::Failed,
+                },
+                content_id: Some(content_id),
+                error: result.as_ref().err().map(|e| e.to_string()),
+            });
+        }
+    }
+
+    async fn finish(mut self) -> BulkAddTextsResponse {
+        self.flush().await;
+        self.results.sort_by_key(|r| r.line);
+        let added = self
+            .results
+            .iter()
+            .filter(|r| r.status == BulkItemStatus::Added)
+            .count();
+        BulkAddTextsResponse {
+            added,
+            failed: self.results.len() - added,
+            results: self.results,
+        }
+    }
+}
+
+#[tracing::instrument(skip(body))]
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/bulk_add_texts",
+    request_body(content = String, content_type = "application/x-ndjson", description = "One document per line, e.g. {\"text\": \"hello\", \"metadata\": {}}"),
+    tag = "indexify",
+    responses(
+        (status = 200, description = "The outcome of every document", body = BulkAddTextsResponse),
+        (status = 404, description = "Repository not found")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn bulk_add_texts(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    mut body: BodyStream,
+) -> Result<Json<BulkAddTextsResponse>, IndexifyAPIError> {
     state
         .repository_manager
-        .add_texts(&repository_name, texts)
+        .get(&namespace, &repository_name)
         .await
         .map_err(|e| {
-            IndexifyAPIError::new(
-                StatusCode::BAD_REQUEST,
-                format!("failed to add text: {}", e),
-            )
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
         })?;
-
-    if let Err(err) = schedule_extraction(&repository_name, &state.coordinator_addr.clone()).await {
-        error!("unable to run extractors: {}", err.to_string());
+    let mut writer = BulkTextsWriter::new(&state.repository_manager, &namespace, &repository_name);
+    // The documents are written as the body is received, so the body is never
+    // held in memory as a whole.
+    let mut buffer = Vec::new();
+    while let Some(chunk) = body.next().await {
+        let chunk =
+            chunk.map_err(|e| IndexifyAPIError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+        buffer.extend_from_slice(&chunk);
+        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            writer.add_line(&line).await;
+        }
     }
-
-    Ok(Json(TextAdditionResponse::default()))
+    writer.add_line(&buffer).await;
+    Ok(Json(writer.finish().await))
 }
 
 #[tracing::instrument]
@@ -389,6 +1328,7 @@ async fn add_texts(
 async fn upload_file(
     Path(repository_name): Path<String>,
     State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
     mut files: Multipart,
 ) -> Result<(), IndexifyAPIError> {
     while let Some(file) = files.next_field().await.unwrap() {
@@ -401,7 +1341,7 @@ async fn upload_file(
         );
         state
             .repository_manager
-            .upload_file(&repository_name, &name, data)
+            .upload_file(&namespace, &repository_name, &name, data)
             .await
             .map_err(|e| {
                 IndexifyAPIError::new(
@@ -413,94 +1353,1827 @@ async fn upload_file(
     Ok(())
 }
 
-async fn schedule_extraction(
-    repository: &str,
-    coordinator_addr: &str,
-) -> Result<(), anyhow::Error> {
-    let req = CreateWork {
-        repository_name: repository.into(),
-        content: None,
-    };
-    let _resp = reqwest::Client::new()
-        .post(&format!("http://{}/create_work", coordinator_addr,))
-        .json(&req)
-        .send()
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/upload_archive",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Files in the archives were added to the repository", body = UploadArchiveResponse),
+        (status = BAD_REQUEST, description = "Unable to unpack the archives")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn upload_archive(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    mut files: Multipart,
+) -> Result<Json<UploadArchiveResponse>, IndexifyAPIError> {
+    let mut content_ids = Vec::new();
+    while let Some(file) = files
+        .next_field()
         .await
-        .map_err(|e| anyhow::anyhow!("failed to send create work request: {}", e))?
-        .json::<CreateWorkResponse>()
-        .await?;
-    Ok(())
+        .map_err(|e| IndexifyAPIError::new(StatusCode::BAD_REQUEST, e.to_string()))?
+    {
+        let name = file
+            .file_name()
+            .ok_or(IndexifyAPIError::new(
+                StatusCode::BAD_REQUEST,
+                "archive has no file name".to_string(),
+            ))?
+            .to_string();
+        let data = file
+            .bytes()
+            .await
+            .map_err(|e| IndexifyAPIError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+        info!(
+            "unpacking archive, file name = {:?}, data = {:?}",
+            name,
+            data.len()
+        );
+        let ids = state
+            .repository_manager
+            .upload_archive(
+                &namespace,
+                &repository_name,
+                &name,
+                data,
+                &state.archive_config,
+            )
+            .await
+            .map_err(|e| {
+                IndexifyAPIError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("failed to upload archive: {}", e),
+                )
+            })?;
+        content_ids.extend(ids);
+    }
+    Ok(Json(UploadArchiveResponse { content_ids }))
 }
 
 #[tracing::instrument]
-async fn run_extractors(
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/ingestion_jobs",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "A job was created to ingest the manifest in the background", body = IngestionJob),
+        (status = BAD_REQUEST, description = "The manifest is not a jsonl or csv file, or is too large"),
+        (status = NOT_FOUND, description = "Repository not found")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn create_ingestion_job(
     Path(repository_name): Path<String>,
     State(state): State<RepositoryEndpointState>,
-) -> Result<Json<RunExtractorsResponse>, IndexifyAPIError> {
-    schedule_extraction(&repository_name, &state.coordinator_addr.to_string())
+    Namespace(namespace): Namespace,
+    mut files: Multipart,
+) -> Result<Json<IngestionJob>, IndexifyAPIError> {
+    let file = files
+        .next_field()
         .await
-        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    Ok(Json(RunExtractorsResponse {}))
+        .map_err(|e| IndexifyAPIError::new(StatusCode::BAD_REQUEST, e.to_string()))?
+        .ok_or(IndexifyAPIError::new(
+            StatusCode::BAD_REQUEST,
+            "no manifest was uploaded".to_string(),
+        ))?;
+    let name = file
+        .file_name()
+        .ok_or(IndexifyAPIError::new(
+            StatusCode::BAD_REQUEST,
+            "manifest has no file name".to_string(),
+        ))?
+        .to_string();
+    let data = file
+        .bytes()
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+    let job = state
+        .repository_manager
+        .create_ingestion_job(
+            &namespace,
+            &repository_name,
+            &name,
+            data,
+            &state.manifest_config,
+        )
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                DataRepositoryError::InvalidManifest(_) => StatusCode::BAD_REQUEST,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(job.into()))
 }
 
 #[tracing::instrument]
 #[utoipa::path(
-    post,
-    path = "/repositories/{repository_name}/events",
-    request_body =  EventAddRequest,
+    get,
+    path = "/repositories/{repository_name}/ingestion_jobs",
     tag = "indexify",
+    params(ListParams),
     responses(
-        (status = 200, description = "Events were successfully added to the repository", body = EventAddResponse),
-        (status = BAD_REQUEST, description = "Unable to add event")
+        (status = 200, description = "Ingestion jobs of a repository, latest first", body = ListIngestionJobsResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to list ingestion jobs")
     ),
 )]
 #[axum_macros::debug_handler]
-async fn add_events(
+async fn list_ingestion_jobs(
     Path(repository_name): Path<String>,
     State(state): State<RepositoryEndpointState>,
-    Json(payload): Json<EventAddRequest>,
-) -> Result<Json<EventAddResponse>, IndexifyAPIError> {
-    let events = payload.events.iter().map(|m| m.clone().into()).collect();
-    state
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
+) -> Result<Json<ListIngestionJobsResponse>, IndexifyAPIError> {
+    let jobs = state
+        .repository_manager
+        .list_ingestion_jobs(&namespace, &repository_name)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|j| j.into())
+        .collect();
+    Ok(Json(Page::from_items(jobs, &params)?))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/ingestion_jobs/{job_id}",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "The progress of an ingestion job", body = IngestionJob),
+        (status = NOT_FOUND, description = "Ingestion job not found")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn get_ingestion_job(
+    Path((repository_name, job_id)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<IngestionJob>, IndexifyAPIError> {
+    let job = state
+        .repository_manager
+        .ingestion_job(&namespace, &repository_name, &job_id)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::IngestionJobNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(job.into()))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/ingestion_jobs/{job_id}/errors",
+    tag = "indexify",
+    params(ListParams),
+    responses(
+        (status = 200, description = "Rows of the manifest which failed to be ingested, in order", body = ListIngestionErrorsResponse),
+        (status = BAD_REQUEST, description = "Invalid cursor"),
+        (status = NOT_FOUND, description = "Ingestion job not found")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn list_ingestion_job_errors(
+    Path((repository_name, job_id)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
+) -> Result<Json<ListIngestionErrorsResponse>, IndexifyAPIError> {
+    let after = params
+        .cursor
+        .as_deref()
+        .map(|cursor| {
+            cursor.parse::<u64>().map_err(|_| {
+                IndexifyAPIError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("invalid cursor: {}", cursor),
+                )
+            })
+        })
+        .transpose()?;
+    let job = state
+        .repository_manager
+        .ingestion_job(&namespace, &repository_name, &job_id)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::IngestionJobNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    let errors = state
+        .repository_manager
+        .ingestion_job_errors(
+            &namespace,
+            &repository_name,
+            &job_id,
+            after,
+            params.limit() + 1,
+        )
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::IngestionJobNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?
+        .into_iter()
+        .map(IngestionRowError::from)
+        .collect();
+    let page = Page::from_keyset(errors, &params, job.rows_failed, |e| e.row.to_string());
+    Ok(Json(page))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/ingestion_jobs/{job_id}/resume",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "The job continues after the last row it processed", body = IngestionJob),
+        (status = NOT_FOUND, description = "Ingestion job not found"),
+        (status = CONFLICT, description = "The job has not failed")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn resume_ingestion_job(
+    Path((repository_name, job_id)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<IngestionJob>, IndexifyAPIError> {
+    let job = state
+        .repository_manager
+        .resume_ingestion_job(&namespace, &repository_name, &job_id)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::IngestionJobNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                DataRepositoryError::IngestionJobNotResumable(_) => StatusCode::CONFLICT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(job.into()))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/api_keys",
+    request_body = CreateApiKeyRequest,
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Api key was created", body = CreateApiKeyResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to create the api key")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn create_api_key(
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<Json<CreateApiKeyResponse>, IndexifyAPIError> {
+    let (api_key, key) = state
         .repository_manager
-        .add_events(&repository_name, events)
+        .create_api_key(
+            &namespace,
+            &payload.name,
+            payload.scope.into(),
+            payload.repositories,
+        )
         .await
         .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(CreateApiKeyResponse {
+        id: api_key.id,
+        key,
+    }))
+}
 
-    if let Err(err) =
-        schedule_extraction(&repository_name, &state.coordinator_addr.to_string()).await
-    {
-        error!("unable to run extractors: {}", err.to_string());
-    }
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/api_keys",
+    tag = "indexify",
+    params(ListParams),
+    responses(
+        (status = 200, description = "Api keys issued by the server", body = ListApiKeysResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to list api keys")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn list_api_keys(
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
+) -> Result<Json<ListApiKeysResponse>, IndexifyAPIError> {
+    let api_keys = state
+        .repository_manager
+        .list_api_keys(&namespace)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|k| k.into())
+        .collect();
+    Ok(Json(Page::from_items(api_keys, &params)?))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    delete,
+    path = "/api_keys/{api_key_id}",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Api key was revoked"),
+        (status = 404, description = "Api key not found")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn delete_api_key(
+    Path(api_key_id): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<(), IndexifyAPIError> {
+    state
+        .repository_manager
+        .delete_api_key(&namespace, &api_key_id)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::ApiKeyNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })
+}
+
+fn repository_template_error(e: DataRepositoryError) -> IndexifyAPIError {
+    let status_code = match &e {
+        DataRepositoryError::Persistence(RepositoryError::RepositoryTemplateNotFound(_)) => {
+            StatusCode::NOT_FOUND
+        }
+        DataRepositoryError::InvalidTemplate(_) => StatusCode::BAD_REQUEST,
+        DataRepositoryError::RepositoryExists(_) => StatusCode::CONFLICT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    IndexifyAPIError::new(status_code, e.to_string())
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/repository_templates",
+    request_body = RepositoryTemplate,
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Repository template was created or replaced", body = RepositoryTemplate),
+        (status = BAD_REQUEST, description = "Invalid extractor bindings or settings"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to create the repository template")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn create_repository_template(
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Json(payload): Json<RepositoryTemplate>,
+) -> Result<Json<RepositoryTemplate>, IndexifyAPIError> {
+    let template = state
+        .repository_manager
+        .create_repository_template(&namespace, payload.into())
+        .await
+        .map_err(repository_template_error)?;
+    Ok(Json(template.into()))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repository_templates",
+    tag = "indexify",
+    params(ListParams),
+    responses(
+        (status = 200, description = "Repository templates of the namespace", body = ListRepositoryTemplatesResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to list repository templates")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn list_repository_templates(
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
+) -> Result<Json<ListRepositoryTemplatesResponse>, IndexifyAPIError> {
+    let templates = state
+        .repository_manager
+        .list_repository_templates(&namespace)
+        .await
+        .map_err(repository_template_error)?
+        .into_iter()
+        .map(|t| t.into())
+        .collect();
+    Ok(Json(Page::from_items(templates, &params)?))
+}
 
-    Ok(Json(EventAddResponse {}))
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repository_templates/{template_name}",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Repository template", body = RepositoryTemplate),
+        (status = 404, description = "Repository template not found")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn get_repository_template(
+    Path(template_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<RepositoryTemplate>, IndexifyAPIError> {
+    let template = state
+        .repository_manager
+        .repository_template(&namespace, &template_name)
+        .await
+        .map_err(repository_template_error)?;
+    Ok(Json(template.into()))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    delete,
+    path = "/repository_templates/{template_name}",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Repository template was deleted, the repositories created from it are kept"),
+        (status = 404, description = "Repository template not found")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn delete_repository_template(
+    Path(template_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<(), IndexifyAPIError> {
+    state
+        .repository_manager
+        .delete_repository_template(&namespace, &template_name)
+        .await
+        .map_err(repository_template_error)
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/repository_templates/{template_name}/repositories",
+    request_body = CreateRepositoryFromTemplate,
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Repository was created from the template", body = CreateRepositoryResponse),
+        (status = 404, description = "Repository template not found"),
+        (status = CONFLICT, description = "A repository of the name already exists"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to create the repository")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn create_repository_from_template(
+    Path(template_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Json(payload): Json<CreateRepositoryFromTemplate>,
+) -> Result<Response, IndexifyAPIError> {
+    let version = state
+        .repository_manager
+        .create_repository_from_template(
+            &namespace,
+            &template_name,
+            &payload.name,
+            payload.metadata,
+        )
+        .await
+        .map_err(repository_template_error)?;
+    Ok((
+        [(header::ETAG, repository_etag(version))],
+        Json(CreateRepositoryResponse { version }),
+    )
+        .into_response())
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/roles",
+    tag = "indexify",
+    params(ListParams),
+    responses(
+        (status = 200, description = "Roles granted on a repository", body = ListRoleBindingsResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to list roles")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn list_roles(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
+) -> Result<Json<ListRoleBindingsResponse>, IndexifyAPIError> {
+    let roles = state
+        .repository_manager
+        .list_roles(&namespace, &repository_name)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|r| r.into())
+        .collect();
+    Ok(Json(Page::from_items(roles, &params)?))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    put,
+    path = "/repositories/{repository_name}/roles/{principal}",
+    request_body = GrantRoleRequest,
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Role was granted", body = RoleBinding),
+        (status = 404, description = "Repository or api key not found")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn grant_role(
+    Path((repository_name, principal)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Json(payload): Json<GrantRoleRequest>,
+) -> Result<Json<RoleBinding>, IndexifyAPIError> {
+    let binding = state
+        .repository_manager
+        .grant_role(
+            &namespace,
+            &repository_name,
+            &principal,
+            payload.role.into(),
+        )
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(
+                    RepositoryError::RepositoryNotFound(_) | RepositoryError::ApiKeyNotFound(_),
+                ) => StatusCode::NOT_FOUND,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(binding.into()))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    delete,
+    path = "/repositories/{repository_name}/roles/{principal}",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Role was revoked"),
+        (status = 404, description = "Principal has no role on the repository")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn revoke_role(
+    Path((repository_name, principal)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<(), IndexifyAPIError> {
+    state
+        .repository_manager
+        .revoke_role(&namespace, &repository_name, &principal)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RoleBindingNotFound(..)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/legal_holds",
+    tag = "indexify",
+    params(ListParams),
+    responses(
+        (status = 200, description = "Legal holds on a repository and its content", body = ListLegalHoldsResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to list legal holds")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn list_legal_holds(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
+) -> Result<Json<ListLegalHoldsResponse>, IndexifyAPIError> {
+    let holds = state
+        .repository_manager
+        .list_legal_holds(&namespace, &repository_name)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|h| h.into())
+        .collect();
+    Ok(Json(Page::from_items(holds, &params)?))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/legal_holds",
+    request_body = PlaceLegalHoldRequest,
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Legal hold was placed", body = LegalHold),
+        (status = 404, description = "Repository or content not found")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn place_legal_hold(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Json(payload): Json<PlaceLegalHoldRequest>,
+) -> Result<Json<LegalHold>, IndexifyAPIError> {
+    let hold = state
+        .repository_manager
+        .place_legal_hold(
+            &namespace,
+            &repository_name,
+            payload.content_id.as_deref(),
+            &payload.reason,
+        )
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(
+                    RepositoryError::RepositoryNotFound(_) | RepositoryError::ContentNotFound(_),
+                ) => StatusCode::NOT_FOUND,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(hold.into()))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    delete,
+    path = "/repositories/{repository_name}/legal_holds/{hold_id}",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Legal hold was released"),
+        (status = 404, description = "Legal hold not found")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn release_legal_hold(
+    Path((repository_name, hold_id)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<(), IndexifyAPIError> {
+    state
+        .repository_manager
+        .release_legal_hold(&namespace, &repository_name, &hold_id)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::LegalHoldNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/audit_log",
+    tag = "indexify",
+    params(ListParams, AuditLogParams),
+    responses(
+        (status = 200, description = "Mutations of the namespace, oldest first", body = ListAuditEventsResponse),
+        (status = BAD_REQUEST, description = "Invalid cursor"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to list the audit log")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn list_audit_log(
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
+    Query(filter): Query<AuditLogParams>,
+) -> Result<Json<ListAuditEventsResponse>, IndexifyAPIError> {
+    let after = params
+        .cursor
+        .as_deref()
+        .map(|cursor| {
+            cursor.parse::<u64>().map_err(|_| {
+                IndexifyAPIError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("invalid cursor: {}", cursor),
+                )
+            })
+        })
+        .transpose()?;
+    let filter: persistence::AuditLogFilter = filter.into();
+    let events = state
+        .repository_manager
+        .list_audit_events(&namespace, &filter, after, params.limit() + 1)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let total_estimate = state
+        .repository_manager
+        .count_audit_events(&namespace, &filter)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let page = Page::from_keyset(events, &params, total_estimate, |e| e.seq.to_string());
+    Ok(Json(page.map(|e| e.into())))
+}
+
+pub async fn schedule_extraction(
+    namespace: &str,
+    repository: &str,
+    coordinator_addr: &str,
+) -> Result<(), anyhow::Error> {
+    let req = CreateWork {
+        namespace: namespace.into(),
+        repository_name: repository.into(),
+        content: None,
+    };
+    let _resp = tls::internal_http_client()
+        .post(&tls::internal_url(coordinator_addr, "/create_work"))
+        .json(&req)
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to send create work request: {}", e))?
+        .json::<CreateWorkResponse>()
+        .await?;
+    Ok(())
+}
+
+#[tracing::instrument]
+async fn run_extractors(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<RunExtractorsResponse>, IndexifyAPIError> {
+    schedule_extraction(
+        &namespace,
+        &repository_name,
+        &state.coordinator_addr.to_string(),
+    )
+    .await
+    .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(RunExtractorsResponse {}))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/events",
+    request_body =  EventAddRequest,
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Events were successfully added to the repository", body = EventAddResponse),
+        (status = BAD_REQUEST, description = "Unable to add event"),
+        (status = CONFLICT, description = "A request with the same idempotency key is in progress"),
+        (status = UNPROCESSABLE_ENTITY, description = "The idempotency key was used for a different request")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn add_events(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    IdempotencyKey(idempotency_key): IdempotencyKey,
+    Json(payload): Json<EventAddRequest>,
+) -> Result<Json<EventAddResponse>, IndexifyAPIError> {
+    let request_hash = idempotency::request_hash("add_events", &payload);
+    let events: Vec<persistence::Event> = payload.events.iter().map(|m| m.clone().into()).collect();
+    let event_ids = events.iter().map(|e| e.id.clone()).collect();
+    let response = state
+        .idempotency_keys
+        .run(
+            &namespace,
+            &repository_name,
+            idempotency_key.as_deref(),
+            &request_hash,
+            async {
+                state
+                    .repository_manager
+                    .add_events(&namespace, &repository_name, events)
+                    .await
+                    .map_err(|e| {
+                        IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                    })?;
+                Ok(EventAddResponse { event_ids })
+            },
+        )
+        .await?;
+
+    if let Err(err) = schedule_extraction(
+        &namespace,
+        &repository_name,
+        &state.coordinator_addr.to_string(),
+    )
+    .await
+    {
+        error!("unable to run extractors: {}", err.to_string());
+    }
+
+    Ok(Json(response))
+}
+
+#[tracing::instrument(skip(payload))]
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/sessions/{session_id}/events",
+    request_body = EventAddRequest,
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Events were added to the session and embedded", body = AddSessionEventsResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to add the events")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn add_session_events(
+    Path((repository_name, session_id)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Json(payload): Json<EventAddRequest>,
+) -> Result<Json<AddSessionEventsResponse>, IndexifyAPIError> {
+    let events = payload.events.into_iter().map(|e| e.into()).collect();
+    let event_ids = state
+        .memory
+        .add_events(&namespace, &repository_name, &session_id, events)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(AddSessionEventsResponse { event_ids }))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/sessions/{session_id}/events",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "The events of the session which have not been summarized", body = ListSessionEventsResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to list the events of the session")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn list_session_events(
+    Path((repository_name, session_id)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<ListSessionEventsResponse>, IndexifyAPIError> {
+    let events = state
+        .memory
+        .session_events(&namespace, &repository_name, &session_id)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|e| e.into())
+        .collect();
+    Ok(Json(ListSessionEventsResponse { events }))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/sessions/{session_id}/query",
+    request_body = QueryMemoryRequest,
+    tag = "indexify",
+    responses(
+        (status = 200, description = "The events of the session most relevant to the query", body = QueryMemoryResponse),
+        (status = BAD_REQUEST, description = "Invalid recency ranking"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to query the memory of the session")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn query_memory(
+    Path((repository_name, session_id)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Json(query): Json<QueryMemoryRequest>,
+) -> Result<Json<QueryMemoryResponse>, IndexifyAPIError> {
+    let k = query.k.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let ranking = query.ranking(state.memory.default_ranking())?;
+    let results = state
+        .memory
+        .query(
+            &namespace,
+            &repository_name,
+            &session_id,
+            &query.query,
+            k as usize,
+            ranking,
+        )
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|hit| hit.into())
+        .collect();
+    Ok(Json(QueryMemoryResponse { results }))
+}
+
+#[tracing::instrument(skip(payload))]
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/ingest",
+    request_body = IngestRequest,
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Events and content were added to the repository", body = IngestResponse),
+        (status = BAD_REQUEST, description = "A file is not valid base64"),
+        (status = 404, description = "Repository not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to add the events and content"),
+        (status = CONFLICT, description = "A request with the same idempotency key is in progress"),
+        (status = UNPROCESSABLE_ENTITY, description = "The idempotency key was used for a different request")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn ingest(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    IdempotencyKey(idempotency_key): IdempotencyKey,
+    Json(payload): Json<IngestRequest>,
+) -> Result<Json<IngestResponse>, IndexifyAPIError> {
+    let request_hash = idempotency::request_hash("ingest", &payload);
+    let mut files = Vec::new();
+    for file in payload.files {
+        let data = BASE64_STANDARD.decode(&file.data).map_err(|e| {
+            IndexifyAPIError::new(
+                StatusCode::BAD_REQUEST,
+                format!("file {} is not valid base64: {}", file.name, e),
+            )
+        })?;
+        files.push(IngestedFile {
+            name: file.name,
+            data: data.into(),
+            metadata: file.metadata,
+        });
+    }
+    let ingestion = Ingestion {
+        session_id: payload.session_id,
+        metadata: payload.metadata,
+        events: payload.events.into_iter().map(|e| e.into()).collect(),
+        texts: payload
+            .documents
+            .into_iter()
+            .map(|d| d.content_payload(&repository_name))
+            .collect(),
+        files,
+    };
+    let response = state
+        .idempotency_keys
+        .run(
+            &namespace,
+            &repository_name,
+            idempotency_key.as_deref(),
+            &request_hash,
+            async {
+                let (event_ids, content_ids) = state
+                    .repository_manager
+                    .ingest(&namespace, &repository_name, ingestion)
+                    .await
+                    .map_err(|e| {
+                        let status_code = match &e {
+                            DataRepositoryError::Persistence(
+                                RepositoryError::RepositoryNotFound(_),
+                            ) => StatusCode::NOT_FOUND,
+                            _ => StatusCode::INTERNAL_SERVER_ERROR,
+                        };
+                        IndexifyAPIError::new(status_code, e.to_string())
+                    })?;
+                Ok(IngestResponse {
+                    event_ids,
+                    content_ids,
+                })
+            },
+        )
+        .await?;
+    Ok(Json(response))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/keyword_search",
+    request_body = KeywordSearchRequest,
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Chunks with the terms of the query, best first", body = IndexSearchResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to search index")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn keyword_search(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Json(query): Json<KeywordSearchRequest>,
+) -> Result<Json<IndexSearchResponse>, IndexifyAPIError> {
+    let results = state
+        .repository_manager
+        .keyword_search(
+            &namespace,
+            &repository_name,
+            &query.index,
+            &query.query,
+            query.k.unwrap_or(DEFAULT_SEARCH_LIMIT),
+        )
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let document_fragments: Vec<DocumentFragment> = results
+        .into_iter()
+        .map(|text| DocumentFragment {
+            content_id: text.content_id,
+            text: text.text,
+            offsets: text.offsets.map(|offsets| offsets.into()),
+            region: text.region.map(|region| region.into()),
+            metadata: text.metadata,
+            confidence_score: text.confidence_score,
+            explanation: query
+                .explain
+                .then(|| SearchExplanation::new(text.explanation, None)),
+            attributes: None,
+            summary: None,
+        })
+        .collect();
+    let document_fragments = with_attributes(
+        &state,
+        &namespace,
+        &repository_name,
+        &query.include_attributes,
+        document_fragments,
+    )
+    .await?;
+    let document_fragments = with_summaries(
+        &state,
+        &namespace,
+        &repository_name,
+        query.summary.as_ref(),
+        document_fragments,
+    )
+    .await?;
+    Ok(Json(IndexSearchResponse {
+        results: document_fragments,
+    }))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/content",
+    tag = "indexify",
+    params(ListParams, ContentFilterParams),
+    responses(
+        (status = 200, description = "Content of a repository", body = ListContentResponse),
+        (status = BAD_REQUEST, description = "Invalid filters"),
+        (status = 404, description = "Repository not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to list content")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn list_content(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
+    Query(filter_params): Query<ContentFilterParams>,
+) -> Result<Json<ListContentResponse>, IndexifyAPIError> {
+    let filter = filter_params.filter()?;
+    let provenance = filter_params.provenance();
+    let repository = state
+        .repository_manager
+        .get(&namespace, &repository_name)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    let content = state
+        .repository_manager
+        .list_content(
+            &namespace,
+            &repository_name,
+            filter.as_ref(),
+            &provenance,
+            params.cursor.as_deref(),
+            params.limit() + 1,
+        )
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let total_estimate = state
+        .repository_manager
+        .count_content(&namespace, &repository_name, filter.as_ref(), &provenance)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let content_ids: Vec<String> = content.iter().map(|c| c.id.clone()).collect();
+    let held = state
+        .repository_manager
+        .held_content(&namespace, &repository_name, &content_ids)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let page = Page::from_keyset(content, &params, total_estimate, |c| c.id.clone());
+    Ok(Json(page.map(|c| {
+        let legal_hold = held.contains(&c.id);
+        ContentInfo::new(c, &repository.extractor_bindings, legal_hold)
+    })))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/content/{content_id}/data",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Bytes of the content, with its content type", content_type = "application/octet-stream"),
+        (status = 404, description = "Content not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to read content")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn read_content(
+    Path((repository_name, content_id)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<(ResponseHeaders<1>, Vec<u8>), IndexifyAPIError> {
+    let (content_type, data) = state
+        .repository_manager
+        .read_content(&namespace, &repository_name, &content_id)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::ContentNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(([(header::CONTENT_TYPE, content_type)], data))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/content/{content_id}/download",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "The original bytes of the content, streamed with its content type", content_type = "application/octet-stream"),
+        (status = 206, description = "The bytes of the content in the range of the Range header", content_type = "application/octet-stream"),
+        (status = 404, description = "Content not found"),
+        (status = 416, description = "The range starts past the end of the content"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to read content")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn download_content(
+    Path((repository_name, content_id)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    RangeHeader(range): RangeHeader,
+) -> Result<Response, IndexifyAPIError> {
+    let download = state
+        .repository_manager
+        .download_content(&namespace, &repository_name, &content_id, range.as_deref())
+        .await;
+    let download = match download {
+        Ok(download) => download,
+        Err(DataRepositoryError::RangeNotSatisfiable(_, size)) => {
+            return Ok((
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                [(header::CONTENT_RANGE, format!("bytes */{}", size))],
+            )
+                .into_response());
+        }
+        Err(e) => {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::ContentNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            return Err(IndexifyAPIError::new(status_code, e.to_string()));
+        }
+    };
+    let (status_code, content_range, content_length) = match &download.range {
+        Some(range) => (
+            StatusCode::PARTIAL_CONTENT,
+            Some(format!(
+                "bytes {}-{}/{}",
+                range.start,
+                range.end - 1,
+                download.size
+            )),
+            range.end - range.start,
+        ),
+        None => (StatusCode::OK, None, download.size),
+    };
+    let mut response = (
+        status_code,
+        [
+            (header::CONTENT_TYPE, download.content_type),
+            (header::ACCEPT_RANGES, "bytes".to_string()),
+            (header::CONTENT_LENGTH, content_length.to_string()),
+            // Browsers show the content rather than save it.
+            (header::CONTENT_DISPOSITION, "inline".to_string()),
+        ],
+        StreamBody::new(download.body),
+    )
+        .into_response();
+    if let Some(content_range) = content_range {
+        response.headers_mut().insert(
+            header::CONTENT_RANGE,
+            HeaderValue::from_str(&content_range).unwrap(),
+        );
+    }
+    Ok(response)
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    delete,
+    path = "/repositories/{repository_name}/content/{content_id}",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Content was soft deleted", body = DeleteContentResponse),
+        (status = 404, description = "Content not found"),
+        (status = CONFLICT, description = "Content is under a legal hold"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to delete content")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn delete_content(
+    Path((repository_name, content_id)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<DeleteContentResponse>, IndexifyAPIError> {
+    let deleted_at = state
+        .repository_manager
+        .soft_delete_content(&namespace, &repository_name, &content_id)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::ContentNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                DataRepositoryError::ContentHeld(_) => StatusCode::CONFLICT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(DeleteContentResponse { deleted_at }))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/content/{content_id}/restore",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Content was restored"),
+        (status = 404, description = "Deleted content not found")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn restore_content(
+    Path((repository_name, content_id)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<(), IndexifyAPIError> {
+    state
+        .repository_manager
+        .restore_content(&namespace, &repository_name, &content_id)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::ContentNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/content/{content_id}/refetch",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Content was fetched again from its source url, and replaced when it changed", body = RefetchContentResponse),
+        (status = BAD_REQUEST, description = "Content can not be refetched"),
+        (status = 404, description = "Content not found"),
+        (status = CONFLICT, description = "Content is under a legal hold"),
+        (status = BAD_GATEWAY, description = "Unable to fetch the source url"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to refetch content")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn refetch_content(
+    Path((repository_name, content_id)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<RefetchContentResponse>, IndexifyAPIError> {
+    let refetched = state
+        .repository_manager
+        .refetch_content(&namespace, &repository_name, &content_id)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::ContentNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                DataRepositoryError::ContentNotRefetchable(..) => StatusCode::BAD_REQUEST,
+                DataRepositoryError::ContentHeld(_) => StatusCode::CONFLICT,
+                DataRepositoryError::Refetch(_) => StatusCode::BAD_GATEWAY,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(refetched.into()))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/content/{content_id}/progress",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "A server-sent event for every transition of the extraction of the content, until the work of all the extractor bindings has completed or failed", body = ContentProgressEvent, content_type = "text/event-stream"),
+        (status = 404, description = "Content not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to read the extraction progress")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn content_progress(
+    Path((repository_name, content_id)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Sse<impl Stream<Item = Result<SseEvent, axum::Error>>>, IndexifyAPIError> {
+    let watch = state
+        .repository_manager
+        .watch_content_progress(&namespace, &repository_name, &content_id)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::ContentNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    let events = watch.stream().map(|event| {
+        let event = ContentProgressEvent::from(event);
+        SseEvent::default()
+            .event(event.event.clone())
+            .json_data(event)
+            .map_err(axum::Error::new)
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/content/{content_id}/lineage",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "The content a content was derived from and the content derived from it", body = ContentLineage),
+        (status = 404, description = "Content not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to read the lineage")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn content_lineage(
+    Path((repository_name, content_id)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<ContentLineage>, IndexifyAPIError> {
+    let lineage = state
+        .repository_manager
+        .content_lineage(&namespace, &repository_name, &content_id)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::ContentNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    ContentLineage::new(lineage).map(Json).ok_or_else(|| {
+        IndexifyAPIError::new(
+            StatusCode::NOT_FOUND,
+            format!("content {} not found", content_id),
+        )
+    })
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/work",
+    tag = "indexify",
+    params(ListParams),
+    responses(
+        (status = 200, description = "Extraction work of a repository", body = ListWorkResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to list work")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn list_work(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
+) -> Result<Json<ListWorkResponse>, IndexifyAPIError> {
+    let work_list = state
+        .repository_manager
+        .list_work(
+            &namespace,
+            &repository_name,
+            params.cursor.as_deref(),
+            params.limit() + 1,
+        )
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let total_estimate = state
+        .repository_manager
+        .count_work(&namespace, &repository_name)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let page = Page::from_keyset(work_list, &params, total_estimate, |w| w.id.clone());
+    Ok(Json(page.map(|w| w.into())))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/work/{work_id}",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Extraction work with its progress and log", body = GetWorkResponse),
+        (status = 404, description = "Work not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to read the work")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn get_work(
+    Path((repository_name, work_id)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<GetWorkResponse>, IndexifyAPIError> {
+    let work = state
+        .repository_manager
+        .get_work(&namespace, &repository_name, &work_id)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::WorkNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(work.into()))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/extractor_bindings/stats",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Statistics of the extractor bindings of a repository", body = ExtractorBindingStatsResponse),
+        (status = 404, description = "Repository not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to compute the statistics")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn extractor_binding_stats(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<ExtractorBindingStatsResponse>, IndexifyAPIError> {
+    let stats = state
+        .repository_manager
+        .extractor_binding_stats(&namespace, &repository_name)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(ExtractorBindingStatsResponse {
+        extractor_bindings: stats.into_iter().map(|s| s.into()).collect(),
+    }))
+}
+
+async fn set_extractor_binding_disabled(
+    state: &RepositoryEndpointState,
+    namespace: &str,
+    repository_name: &str,
+    binding: &str,
+    disabled: bool,
+) -> Result<Json<UpdateExtractorBindingResponse>, IndexifyAPIError> {
+    let extractor_binding = state
+        .repository_manager
+        .set_extractor_binding_disabled(namespace, repository_name, binding, disabled)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) |
+                DataRepositoryError::ExtractorBindingNotFound(_) => StatusCode::NOT_FOUND,
+                DataRepositoryError::Persistence(RepositoryError::RepositoryVersionConflict(_)) => {
+                    StatusCode::CONFLICT
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(UpdateExtractorBindingResponse {
+        extractor_binding: extractor_binding.into(),
+    }))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/extractor_bindings/{binding}/pause",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "The binding was paused", body = UpdateExtractorBindingResponse),
+        (status = 404, description = "Repository or extractor binding not found"),
+        (status = CONFLICT, description = "The repository was updated at the same time"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to pause the binding")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn pause_extractor_binding(
+    Path((repository_name, binding)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<UpdateExtractorBindingResponse>, IndexifyAPIError> {
+    set_extractor_binding_disabled(&state, &namespace, &repository_name, &binding, true).await
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/extractor_bindings/{binding}/resume",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "The binding was resumed", body = UpdateExtractorBindingResponse),
+        (status = 404, description = "Repository or extractor binding not found"),
+        (status = CONFLICT, description = "The repository was updated at the same time"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to resume the binding")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn resume_extractor_binding(
+    Path((repository_name, binding)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<UpdateExtractorBindingResponse>, IndexifyAPIError> {
+    set_extractor_binding_disabled(&state, &namespace, &repository_name, &binding, false).await
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/dashboard",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Summary of the activity of a repository", body = RepositoryDashboard),
+        (status = 404, description = "Repository not found or its dashboard not computed yet"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to read the dashboard")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn repository_dashboard(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<RepositoryDashboard>, IndexifyAPIError> {
+    state
+        .repository_manager
+        .get(&namespace, &repository_name)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    let dashboard = state
+        .repository_manager
+        .repository_dashboard(&namespace, &repository_name)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or(IndexifyAPIError::new(
+            StatusCode::NOT_FOUND,
+            format!(
+                "the dashboard of repository {} has not been computed yet",
+                repository_name
+            ),
+        ))?;
+    Ok(Json(dashboard.into()))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/events",
+    tag = "indexify",
+    params(ListParams),
+    responses(
+        (status = 200, description = "List of Events in a repository", body = ListEventsResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to list events in repository")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn list_events(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
+) -> Result<Json<ListEventsResponse>, IndexifyAPIError> {
+    let events = state
+        .repository_manager
+        .list_events(&namespace, &repository_name)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .iter()
+        .map(|m| m.to_owned().into())
+        .collect();
+    Ok(Json(Page::from_items(events, &params)?))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/connector_syncs",
+    tag = "indexify",
+    params(ListParams),
+    responses(
+        (status = 200, description = "Sync runs of the data connectors of a repository, latest first", body = ListConnectorSyncsResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to list connector syncs")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn list_connector_syncs(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
+) -> Result<Json<ListConnectorSyncsResponse>, IndexifyAPIError> {
+    let syncs = state
+        .repository_manager
+        .list_connector_syncs(&namespace, &repository_name)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|s| s.into())
+        .collect();
+    Ok(Json(Page::from_items(syncs, &params)?))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/query_load",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "The query load of the repositories of the namespace", body = QueryLoadResponse)
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn query_load(
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Json<QueryLoadResponse> {
+    Json(state.query_throttle.load(&namespace).into())
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/embedding_cache",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "The size of the embedding cache and its hits and misses", body = EmbeddingCacheResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to read the embedding cache")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn embedding_cache_stats(
+    State(state): State<RepositoryEndpointState>,
+) -> Result<Json<EmbeddingCacheResponse>, IndexifyAPIError> {
+    let stats = state
+        .embedding_cache
+        .stats()
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(stats.into()))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "The server is live, along with the health of its dependencies", body = HealthResponse),
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn healthz(State(state): State<RepositoryEndpointState>) -> Json<HealthResponse> {
+    // A server whose dependencies are down is still live, restarting it
+    // would not bring them back.
+    Json(state.health_checker.check().await.into())
 }
 
 #[tracing::instrument]
 #[utoipa::path(
     get,
-    path = "/repositories/{repository_name}/events",
+    path = "/readyz",
     tag = "indexify",
     responses(
-        (status = 200, description = "List of Events in a repository", body = ListEventsResponse),
-        (status = INTERNAL_SERVER_ERROR, description = "Unable to list events in repository")
+        (status = 200, description = "Every dependency of the server is healthy", body = HealthResponse),
+        (status = SERVICE_UNAVAILABLE, description = "A dependency of the server is down", body = HealthResponse)
     ),
 )]
 #[axum_macros::debug_handler]
-async fn list_events(
-    Path(repository_name): Path<String>,
+async fn readyz(
     State(state): State<RepositoryEndpointState>,
-) -> Result<Json<ListEventsResponse>, IndexifyAPIError> {
-    let messages = state
-        .repository_manager
-        .list_events(&repository_name)
-        .await
-        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .iter()
-        .map(|m| m.to_owned().into())
-        .collect();
-
-    Ok(Json(ListEventsResponse { messages }))
+) -> (StatusCode, Json<HealthResponse>) {
+    let report = state.health_checker.check().await;
+    if !report.is_healthy() {
+        let down: Vec<&str> = report
+            .dependencies
+            .iter()
+            .filter(|d| !d.healthy)
+            .map(|d| d.name.as_str())
+            .collect();
+        warn!("server is not ready, {} down", down.join(", "));
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(report.into()));
+    }
+    (StatusCode::OK, Json(report.into()))
 }
 
 #[tracing::instrument]
@@ -508,6 +3181,7 @@ async fn list_events(
     get,
     path = "/executors",
     tag = "indexify",
+    params(ListParams),
     responses(
         (status = 200, description = "List of currently running executors", body = ListExecutorsResponse),
         (status = INTERNAL_SERVER_ERROR, description = "Unable to load executors")
@@ -516,8 +3190,9 @@ async fn list_events(
 #[axum_macros::debug_handler]
 async fn list_executors(
     State(_state): State<RepositoryEndpointState>,
+    Query(params): Query<ListParams>,
 ) -> Result<Json<ListExecutorsResponse>, IndexifyAPIError> {
-    Ok(Json(ListExecutorsResponse { executors: vec![] }))
+    Ok(Json(Page::from_items(vec![], &params)?))
 }
 
 #[tracing::instrument]
@@ -525,6 +3200,7 @@ async fn list_executors(
     get,
     path = "/extractors",
     tag = "indexify",
+    params(ListParams),
     responses(
         (status = 200, description = "List of extractors available", body = ListExtractorsResponse),
         (status = INTERNAL_SERVER_ERROR, description = "Unable to search index")
@@ -533,6 +3209,7 @@ async fn list_executors(
 #[axum_macros::debug_handler]
 async fn list_extractors(
     State(state): State<RepositoryEndpointState>,
+    Query(params): Query<ListParams>,
 ) -> Result<Json<ListExtractorsResponse>, IndexifyAPIError> {
     let extractors = state
         .repository_manager
@@ -542,7 +3219,7 @@ async fn list_extractors(
         .into_iter()
         .map(|e| e.into())
         .collect();
-    Ok(Json(ListExtractorsResponse { extractors }))
+    Ok(Json(Page::from_items(extractors, &params)?))
 }
 
 #[axum_macros::debug_handler]
@@ -570,6 +3247,7 @@ async fn extract_content(
     get,
     path = "/repositories/{repository_name}/indexes",
     tag = "indexify",
+    params(ListParams),
     responses(
         (status = 200, description = "List of indexes in a repository", body = ListIndexesResponse),
         (status = INTERNAL_SERVER_ERROR, description = "Unable to list indexes in repository")
@@ -579,16 +3257,374 @@ async fn extract_content(
 async fn list_indexes(
     Path(repository_name): Path<String>,
     State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
 ) -> Result<Json<ListIndexesResponse>, IndexifyAPIError> {
     let indexes = state
         .repository_manager
-        .list_indexes(&repository_name)
+        .list_indexes(&namespace, &repository_name)
         .await
         .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
         .into_iter()
         .map(|i| i.into())
         .collect();
-    Ok(Json(ListIndexesResponse { indexes }))
+    Ok(Json(Page::from_items(indexes, &params)?))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/indexes/{index_name}/reindex",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Index rebuilt and flipped to a new generation", body = ReindexResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to rebuild the index")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn reindex(
+    Path((repository_name, index_name)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<ReindexResponse>, IndexifyAPIError> {
+    let snapshot = state
+        .repository_manager
+        .reindex(&namespace, &repository_name, &index_name)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(ReindexResponse {
+        index: snapshot.index_name,
+        generation: snapshot.generation,
+    }))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/repositories/{repository_name}/indexes/{index_name}/warm",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Index loaded by the vector store and its extractor", body = WarmIndexResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to warm up the index")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn warm_index(
+    Path((repository_name, index_name)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+) -> Result<Json<WarmIndexResponse>, IndexifyAPIError> {
+    let warmup = state
+        .repository_manager
+        .warm_index(&namespace, &repository_name, &index_name)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(warmup.into()))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/indexes/{index_name}/graph/entities",
+    tag = "indexify",
+    params(GraphEntitiesParams),
+    responses(
+        (status = 200, description = "The entities of a graph index whose name contains the query, the most mentioned first", body = GraphEntitiesResponse),
+        (status = BAD_REQUEST, description = "The index is not a graph index"),
+        (status = 404, description = "Repository or index not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to search the graph")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn graph_entities(
+    Path((repository_name, index_name)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<GraphEntitiesParams>,
+) -> Result<Json<GraphEntitiesResponse>, IndexifyAPIError> {
+    let entities = state
+        .repository_manager
+        .search_graph_entities(
+            &namespace,
+            &repository_name,
+            &index_name,
+            &params.query,
+            params.entity_type.as_deref(),
+            params.limit(),
+        )
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) |
+                DataRepositoryError::Persistence(RepositoryError::IndexNotFound(_)) |
+                DataRepositoryError::Persistence(RepositoryError::GraphEntityNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                DataRepositoryError::NotAGraphIndex(_) => StatusCode::BAD_REQUEST,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(GraphEntitiesResponse {
+        entities: entities.into_iter().map(Into::into).collect(),
+    }))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/indexes/{index_name}/graph/neighbors",
+    tag = "indexify",
+    params(GraphNeighborsParams),
+    responses(
+        (status = 200, description = "An entity of a graph index and the entities it is related to", body = GraphNeighborsResponse),
+        (status = BAD_REQUEST, description = "The index is not a graph index"),
+        (status = 404, description = "Repository, index or entity not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to read the graph")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn graph_neighbors(
+    Path((repository_name, index_name)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<GraphNeighborsParams>,
+) -> Result<Json<GraphNeighborsResponse>, IndexifyAPIError> {
+    let (entity, neighbors) = state
+        .repository_manager
+        .graph_neighbors(
+            &namespace,
+            &repository_name,
+            &index_name,
+            &params.entity,
+            params.relation.as_deref(),
+            params.limit(),
+        )
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) |
+                DataRepositoryError::Persistence(RepositoryError::IndexNotFound(_)) |
+                DataRepositoryError::Persistence(RepositoryError::GraphEntityNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                DataRepositoryError::NotAGraphIndex(_) => StatusCode::BAD_REQUEST,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(GraphNeighborsResponse {
+        entity: entity.into(),
+        neighbors: neighbors
+            .into_iter()
+            .map(|(relationship, entity)| GraphNeighbor {
+                relationship: relationship.into(),
+                entity: entity.into(),
+            })
+            .collect(),
+    }))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/indexes/{index_name}/graph/paths",
+    tag = "indexify",
+    params(GraphPathsParams),
+    responses(
+        (status = 200, description = "The shortest paths between two entities of a graph index", body = GraphPathsResponse),
+        (status = BAD_REQUEST, description = "The index is not a graph index"),
+        (status = 404, description = "Repository, index or entity not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to read the graph")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn graph_paths(
+    Path((repository_name, index_name)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<GraphPathsParams>,
+) -> Result<Json<GraphPathsResponse>, IndexifyAPIError> {
+    let (entities, paths) = state
+        .repository_manager
+        .graph_paths(
+            &namespace,
+            &repository_name,
+            &index_name,
+            PathQuery {
+                from: &params.from,
+                to: &params.to,
+                max_hops: params.max_hops(),
+                limit: params.limit(),
+            },
+        )
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) |
+                DataRepositoryError::Persistence(RepositoryError::IndexNotFound(_)) |
+                DataRepositoryError::Persistence(RepositoryError::GraphEntityNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                DataRepositoryError::NotAGraphIndex(_) => StatusCode::BAD_REQUEST,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(GraphPathsResponse {
+        entities: entities.into_iter().map(Into::into).collect(),
+        paths: paths
+            .into_iter()
+            .map(|path| path.into_iter().map(Into::into).collect())
+            .collect(),
+    }))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/indexes/{index_name}/export",
+    tag = "indexify",
+    params(ExportIndexParams),
+    responses(
+        (status = 200, description = "The attributes of an attribute index, or the chunks of an embedding index, as a Parquet file or an Arrow IPC stream", content_type = "application/octet-stream"),
+        (status = 404, description = "Repository or index not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to export the index")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn export_index(
+    Path((repository_name, index_name)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ExportIndexParams>,
+) -> Result<(ResponseHeaders<2>, ChannelBody), IndexifyAPIError> {
+    let format: export::ExportFormat = params.format.into();
+    let export = state
+        .repository_manager
+        .export_index(&namespace, &repository_name, &index_name, format)
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) |
+                DataRepositoryError::Persistence(RepositoryError::IndexNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"{}.{}\"",
+                    index_name,
+                    format.extension()
+                ),
+            ),
+        ],
+        StreamBody::new(export.stream()),
+    ))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    put,
+    path = "/repositories/{repository_name}/indexes/{index_name}/reranker",
+    request_body = UpdateRerankerRequest,
+    tag = "indexify",
+    responses(
+        (status = 200, description = "The reranker of the index was set or removed", body = UpdateRerankerResponse),
+        (status = BAD_REQUEST, description = "Invalid reranker"),
+        (status = 404, description = "Index not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to update the index")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn update_index_reranker(
+    Path((repository_name, index_name)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Json(payload): Json<UpdateRerankerRequest>,
+) -> Result<Json<UpdateRerankerResponse>, IndexifyAPIError> {
+    state
+        .repository_manager
+        .set_index_reranker(
+            &namespace,
+            &repository_name,
+            &index_name,
+            payload.reranker.clone().map(|r| r.into()),
+        )
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::InvalidReranker(_) => StatusCode::BAD_REQUEST,
+                DataRepositoryError::Persistence(RepositoryError::IndexNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(UpdateRerankerResponse {
+        index: index_name,
+        reranker: payload.reranker,
+    }))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/indexes/{index_name}/quarantine",
+    tag = "indexify",
+    params(ListParams),
+    responses(
+        (status = 200, description = "Attributes set aside because they do not match the schema of the index", body = ListQuarantinedAttributesResponse),
+        (status = 404, description = "Repository not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to list the attributes")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn list_quarantined_attributes(
+    Path((repository_name, index_name)): Path<(String, String)>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
+) -> Result<Json<ListQuarantinedAttributesResponse>, IndexifyAPIError> {
+    let attributes = state
+        .repository_manager
+        .quarantined_attributes(
+            &namespace,
+            &repository_name,
+            &index_name,
+            params.cursor.as_deref(),
+            params.limit() + 1,
+        )
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?
+        .into_iter()
+        .map(QuarantinedAttributes::from)
+        .collect();
+    let total = state
+        .repository_manager
+        .count_quarantined_attributes(&namespace, &repository_name, &index_name)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let page = Page::from_keyset(attributes, &params, total, |a: &QuarantinedAttributes| {
+        a.attributes.id.clone()
+    });
+    Ok(Json(page))
 }
 
 #[tracing::instrument]
@@ -598,6 +3634,7 @@ async fn list_indexes(
     tag = "indexify",
     responses(
         (status = 200, description = "Index search results", body = IndexSearchResponse),
+        (status = BAD_REQUEST, description = "Invalid mmr options"),
         (status = INTERNAL_SERVER_ERROR, description = "Unable to search index")
     ),
 )]
@@ -605,32 +3642,145 @@ async fn list_indexes(
 async fn index_search(
     Path(repository_name): Path<String>,
     State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
     Json(query): Json<SearchRequest>,
 ) -> Result<Json<IndexSearchResponse>, IndexifyAPIError> {
+    let attribute_prefilter = query
+        .attribute_filter
+        .as_ref()
+        .map(|f| f.prefilter())
+        .transpose()?;
+    let metadata_filter = query.metadata_filter()?;
+    let k = query.k.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let diversification = query
+        .mmr
+        .as_ref()
+        .map(|mmr| mmr.diversification(k))
+        .transpose()?;
+    let limits = query.result_limits()?;
+    let search_query = query.search_query()?;
     let results = state
         .repository_manager
         .search(
+            &namespace,
             &repository_name,
             &query.index,
-            &query.query,
-            query.k.unwrap_or(DEFAULT_SEARCH_LIMIT),
+            &search_query,
+            k,
+            attribute_prefilter,
+            metadata_filter,
+            diversification,
+            limits,
         )
         .await
         .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     let document_fragments: Vec<DocumentFragment> = results
-        .iter()
+        .into_iter()
         .map(|text| DocumentFragment {
-            content_id: text.content_id.clone(),
-            text: text.text.clone(),
-            metadata: text.metadata.clone(),
+            content_id: text.content_id,
+            text: text.text,
+            offsets: text.offsets.map(|offsets| offsets.into()),
+            region: text.region.map(|region| region.into()),
+            metadata: text.metadata,
             confidence_score: text.confidence_score,
+            explanation: query
+                .explain
+                .then(|| SearchExplanation::new(text.explanation, query.attribute_filter.clone())),
+            attributes: None,
+            summary: None,
         })
         .collect();
+    let document_fragments = with_attributes(
+        &state,
+        &namespace,
+        &repository_name,
+        &query.include_attributes,
+        document_fragments,
+    )
+    .await?;
+    let document_fragments = with_summaries(
+        &state,
+        &namespace,
+        &repository_name,
+        query.summary.as_ref(),
+        document_fragments,
+    )
+    .await?;
     Ok(Json(IndexSearchResponse {
         results: document_fragments,
     }))
 }
 
+/// Adds to every result the attributes extracted from its content into the
+/// attributes indexes, with one lookup for all the results.
+async fn with_attributes(
+    state: &RepositoryEndpointState,
+    namespace: &str,
+    repository: &str,
+    indexes: &[String],
+    mut document_fragments: Vec<DocumentFragment>,
+) -> Result<Vec<DocumentFragment>, IndexifyAPIError> {
+    if indexes.is_empty() {
+        return Ok(document_fragments);
+    }
+    let content_ids: Vec<String> = document_fragments
+        .iter()
+        .map(|f| f.content_id.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let content_attributes = state
+        .repository_manager
+        .content_attributes(namespace, repository, indexes, &content_ids)
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    for fragment in &mut document_fragments {
+        fragment.attributes = Some(
+            content_attributes
+                .get(&fragment.content_id)
+                .cloned()
+                .unwrap_or_default(),
+        );
+    }
+    Ok(document_fragments)
+}
+
+/// Adds to the results the summaries of their content, or replaces the
+/// chunks of summarized content by its summary, with one lookup for all the
+/// results.
+async fn with_summaries(
+    state: &RepositoryEndpointState,
+    namespace: &str,
+    repository: &str,
+    options: Option<&SummaryOptions>,
+    document_fragments: Vec<DocumentFragment>,
+) -> Result<Vec<DocumentFragment>, IndexifyAPIError> {
+    let Some(options) = options else {
+        return Ok(document_fragments);
+    };
+    let content_ids: Vec<String> = document_fragments
+        .iter()
+        .map(|f| f.content_id.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let summaries = state
+        .repository_manager
+        .content_summaries(namespace, repository, &options.index, &content_ids)
+        .await
+        .map_err(|e| {
+            let status_code = match e {
+                DataRepositoryError::Persistence(RepositoryError::IndexNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                DataRepositoryError::NotASummaryIndex(_) => StatusCode::BAD_REQUEST,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(options.apply(document_fragments, &summaries))
+}
+
 #[tracing::instrument]
 #[utoipa::path(
     get,
@@ -646,11 +3796,17 @@ async fn index_search(
 async fn attribute_lookup(
     Path(repository_name): Path<String>,
     State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
     Query(query): Query<AttributeLookupRequest>,
 ) -> Result<Json<AttributeLookupResponse>, IndexifyAPIError> {
     let attributes = state
         .repository_manager
-        .attribute_lookup(&repository_name, &query.index, query.content_id.as_ref())
+        .attribute_lookup(
+            &namespace,
+            &repository_name,
+            &query.index,
+            query.content_id.as_ref(),
+        )
         .await
         .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
@@ -659,6 +3815,166 @@ async fn attribute_lookup(
     }))
 }
 
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/attributes/query",
+    tag = "indexify",
+    params(ListParams, AttributeQueryParams),
+    responses(
+        (status = 200, description = "Attributes which match the query", body = QueryAttributesResponse),
+        (status = BAD_REQUEST, description = "Invalid query or sort"),
+        (status = 404, description = "Repository not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to query the attributes")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn query_attributes(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
+    Query(query_params): Query<AttributeQueryParams>,
+) -> Result<Json<QueryAttributesResponse>, IndexifyAPIError> {
+    let query = query_params.query()?;
+    let sort = query_params.sort()?;
+    let attributes = state
+        .repository_manager
+        .query_attributes(
+            &namespace,
+            &repository_name,
+            &query_params.index,
+            query.as_ref(),
+            sort.as_ref(),
+            params.offset()?,
+            params.limit(),
+        )
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    let total = state
+        .repository_manager
+        .count_attributes(
+            &namespace,
+            &repository_name,
+            &query_params.index,
+            query.as_ref(),
+        )
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let page = Page::from_offset(attributes, &params, total)?;
+    Ok(Json(page.map(|a| a.into())))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/attributes/aggregate",
+    tag = "indexify",
+    params(AttributeAggregationParams),
+    responses(
+        (status = 200, description = "Counts and metrics of the attributes which match the query, by group", body = AggregateAttributesResponse),
+        (status = BAD_REQUEST, description = "Invalid query, group or metrics"),
+        (status = 404, description = "Repository not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to aggregate the attributes")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn aggregate_attributes(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<AttributeAggregationParams>,
+) -> Result<Json<AggregateAttributesResponse>, IndexifyAPIError> {
+    let query = params.query()?;
+    let aggregation = params.aggregation()?;
+    let groups = state
+        .repository_manager
+        .aggregate_attributes(
+            &namespace,
+            &repository_name,
+            &params.index,
+            query.as_ref(),
+            &aggregation,
+        )
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    let total = state
+        .repository_manager
+        .count_attributes(&namespace, &repository_name, &params.index, query.as_ref())
+        .await
+        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(AggregateAttributesResponse {
+        index: params.index,
+        total,
+        groups: groups.into_iter().map(AttributeGroup::from).collect(),
+    }))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/changes",
+    tag = "indexify",
+    params(ListParams),
+    responses(
+        (status = 200, description = "Changes of the repository after the cursor, oldest first", body = ChangeFeedResponse),
+        (status = BAD_REQUEST, description = "Invalid cursor"),
+        (status = 404, description = "Repository not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to read the changes")
+    ),
+)]
+#[axum_macros::debug_handler]
+async fn list_changes(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    Namespace(namespace): Namespace,
+    Query(params): Query<ListParams>,
+) -> Result<Json<ChangeFeedResponse>, IndexifyAPIError> {
+    let after = params
+        .cursor
+        .as_deref()
+        .map(|cursor| {
+            cursor.parse::<u64>().map_err(|_| {
+                IndexifyAPIError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("invalid cursor: {}", cursor),
+                )
+            })
+        })
+        .transpose()?
+        .unwrap_or(0);
+    let changes = state
+        .repository_manager
+        .changes(&namespace, &repository_name, after, params.limit())
+        .await
+        .map_err(|e| {
+            let status_code = match &e {
+                DataRepositoryError::Persistence(RepositoryError::RepositoryNotFound(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            IndexifyAPIError::new(status_code, e.to_string())
+        })?;
+    Ok(Json(changes.into()))
+}
+
 #[tracing::instrument]
 async fn shutdown_signal() {
     let ctrl_c = async {
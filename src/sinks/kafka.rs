@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use rdkafka::{
+    producer::{FutureProducer, FutureRecord},
+    ClientConfig,
+};
+
+use super::{Sink, SinkRecord};
+
+pub struct KafkaSink {
+    topic: String,
+    producer: FutureProducer,
+}
+
+impl KafkaSink {
+    pub fn new(brokers: &str, topic: &str) -> Result<Self> {
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("message.timeout.ms", "5000")
+            .create()
+            .map_err(|e| anyhow!("unable to create kafka producer: {}", e))?;
+        Ok(Self {
+            topic: topic.into(),
+            producer,
+        })
+    }
+}
+
+#[async_trait]
+impl Sink for KafkaSink {
+    #[tracing::instrument(skip(self, record))]
+    async fn deliver(&self, record: &SinkRecord) -> Result<()> {
+        let payload = serde_json::to_vec(record)?;
+        let key = record.key();
+        self.producer
+            .send(
+                FutureRecord::to(&self.topic).key(&key).payload(&payload),
+                Duration::from_secs(0),
+            )
+            .await
+            .map_err(|(e, _)| anyhow!("unable to publish to topic {}: {}", self.topic, e))?;
+        Ok(())
+    }
+}
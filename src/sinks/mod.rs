@@ -0,0 +1,307 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use crate::{internal_api::Content, persistence::OutputSink};
+
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod s3;
+pub mod webhook;
+
+pub type SinkTS = Arc<dyn Sink + Sync + Send>;
+
+/// How many times the delivery of a record to a sink is attempted before it
+/// is given up.
+const DELIVERY_ATTEMPTS: u32 = 3;
+
+const INITIAL_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// The outputs of a single piece of work, as delivered to an external sink.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SinkRecord {
+    pub namespace: String,
+    pub repository: String,
+    pub extractor_binding: String,
+    pub content_id: String,
+    pub work_id: String,
+    pub content: Vec<Content>,
+}
+
+impl SinkRecord {
+    /// A stable key for the record which sinks can use for partitioning or
+    /// naming objects.
+    pub fn key(&self) -> String {
+        format!(
+            "{}/{}/{}/{}",
+            self.namespace, self.repository, self.extractor_binding, self.work_id
+        )
+    }
+}
+
+#[async_trait]
+pub trait Sink {
+    async fn deliver(&self, record: &SinkRecord) -> Result<()>;
+}
+
+pub fn create_sink(sink: &OutputSink) -> Result<SinkTS> {
+    match sink {
+        OutputSink::Webhook { url } => Ok(Arc::new(webhook::WebhookSink::new(url)?)),
+        #[cfg(feature = "kafka")]
+        OutputSink::Kafka { brokers, topic } => {
+            Ok(Arc::new(kafka::KafkaSink::new(brokers, topic)?))
+        }
+        #[cfg(not(feature = "kafka"))]
+        OutputSink::Kafka { .. } => Err(anyhow::anyhow!(
+            "indexify was built without the `kafka` feature"
+        )),
+        OutputSink::S3 {
+            bucket,
+            region,
+            prefix,
+        } => Ok(Arc::new(s3::S3Sink::new(bucket, region, prefix)?)),
+    }
+}
+
+/// Keeps one client per configured sink so that producers and HTTP clients
+/// are reused across deliveries.
+pub struct SinkManager {
+    sinks: RwLock<HashMap<String, SinkTS>>,
+    retry_backoff: Duration,
+}
+
+impl fmt::Debug for SinkManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SinkManager").finish()
+    }
+}
+
+impl SinkManager {
+    pub fn new() -> Self {
+        Self {
+            sinks: RwLock::new(HashMap::new()),
+            retry_backoff: INITIAL_RETRY_BACKOFF,
+        }
+    }
+
+    #[cfg(test)]
+    fn with_retry_backoff(mut self, retry_backoff: Duration) -> Self {
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    fn get_or_create(&self, sink: &OutputSink) -> Result<SinkTS> {
+        let key = serde_json::to_string(sink)?;
+        if let Some(sink) = self.sinks.read().unwrap().get(&key) {
+            return Ok(sink.clone());
+        }
+        let created = create_sink(sink)?;
+        self.sinks.write().unwrap().insert(key, created.clone());
+        Ok(created)
+    }
+
+    /// Delivers the record to every sink, retrying a failed delivery up to
+    /// `DELIVERY_ATTEMPTS` times with a doubling backoff. Failures are logged
+    /// and do not prevent delivery to the remaining sinks; the number of
+    /// failed deliveries is returned.
+    pub async fn deliver(&self, sinks: &[OutputSink], record: &SinkRecord) -> usize {
+        let mut failures = 0;
+        for sink in sinks {
+            let result = match self.get_or_create(sink) {
+                Ok(s) => self.deliver_with_retries(&s, sink, record).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = result {
+                error!(
+                    "unable to deliver output of work {} to sink {}: {}",
+                    record.work_id,
+                    sink,
+                    err.to_string()
+                );
+                failures += 1;
+            }
+        }
+        failures
+    }
+
+    async fn deliver_with_retries(
+        &self,
+        sink: &SinkTS,
+        config: &OutputSink,
+        record: &SinkRecord,
+    ) -> Result<()> {
+        let mut backoff = self.retry_backoff;
+        let mut attempt = 1;
+        loop {
+            match sink.deliver(record).await {
+                Ok(()) => return Ok(()),
+                Err(err) if attempt < DELIVERY_ATTEMPTS => warn!(
+                    "unable to deliver output of work {} to sink {}, retrying: {}",
+                    record.work_id,
+                    config,
+                    err.to_string()
+                ),
+                Err(err) => return Err(err),
+            }
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::TcpListener,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+    };
+
+    use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+
+    use super::*;
+
+    type Received = Arc<Mutex<Vec<SinkRecord>>>;
+
+    /// The records posted to the webhook, and how many posts failed.
+    #[derive(Clone, Default)]
+    struct Webhook {
+        received: Received,
+        failed: Arc<AtomicUsize>,
+    }
+
+    fn record(work_id: &str) -> SinkRecord {
+        SinkRecord {
+            namespace: "default".into(),
+            repository: "docs".into(),
+            extractor_binding: "embeddings".into(),
+            content_id: "content1".into(),
+            work_id: work_id.into(),
+            content: vec![],
+        }
+    }
+
+    /// Starts a webhook which keeps the records posted to `/ok` and fails
+    /// the ones posted to `/fail`, and returns its address.
+    fn start_webhook(webhook: Webhook) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new()
+            .route(
+                "/ok",
+                post(
+                    |State(webhook): State<Webhook>, Json(record): Json<SinkRecord>| async move {
+                        webhook.received.lock().unwrap().push(record);
+                        StatusCode::OK
+                    },
+                ),
+            )
+            .route(
+                "/fail",
+                post(|State(webhook): State<Webhook>| async move {
+                    webhook.failed.fetch_add(1, Ordering::Relaxed);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }),
+            )
+            .with_state(webhook);
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+        format!("http://{}", addr)
+    }
+
+    #[test]
+    fn test_record_key() {
+        assert_eq!(record("work1").key(), "default/docs/embeddings/work1");
+    }
+
+    #[test]
+    fn test_create_sink() {
+        assert!(create_sink(&OutputSink::Webhook {
+            url: "http://localhost:8080/outputs".into(),
+        })
+        .is_ok());
+        let kafka = create_sink(&OutputSink::Kafka {
+            brokers: "localhost:9092".into(),
+            topic: "outputs".into(),
+        });
+        #[cfg(feature = "kafka")]
+        assert!(kafka.is_ok());
+        #[cfg(not(feature = "kafka"))]
+        assert_eq!(
+            kafka.err().unwrap().to_string(),
+            "indexify was built without the `kafka` feature"
+        );
+    }
+
+    #[test]
+    fn test_sinks_are_reused() {
+        let manager = SinkManager::new();
+        let webhook = |url: &str| OutputSink::Webhook { url: url.into() };
+        let first = manager
+            .get_or_create(&webhook("http://localhost:8080/a"))
+            .unwrap();
+        let again = manager
+            .get_or_create(&webhook("http://localhost:8080/a"))
+            .unwrap();
+        let other = manager
+            .get_or_create(&webhook("http://localhost:8080/b"))
+            .unwrap();
+        assert!(Arc::ptr_eq(&first, &again));
+        assert!(!Arc::ptr_eq(&first, &other));
+        assert_eq!(manager.sinks.read().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_deliver_counts_failures() {
+        let server = Webhook::default();
+        let addr = start_webhook(server.clone());
+        let webhook = |path: &str| OutputSink::Webhook {
+            url: format!("{}{}", addr, path),
+        };
+        let manager = SinkManager::new().with_retry_backoff(Duration::from_millis(1));
+
+        let sinks = vec![
+            webhook("/fail"),
+            // Nothing listens on the port.
+            OutputSink::Webhook {
+                url: "http://127.0.0.1:1/outputs".into(),
+            },
+            webhook("/ok"),
+        ];
+        assert_eq!(manager.deliver(&sinks, &record("work1")).await, 2);
+        assert_eq!(
+            server.failed.load(Ordering::Relaxed),
+            DELIVERY_ATTEMPTS as usize
+        );
+        assert_eq!(
+            manager.deliver(&[webhook("/ok")], &record("work2")).await,
+            0
+        );
+
+        // The failed deliveries did not keep the record from the webhook
+        // after them.
+        let work_ids = server
+            .received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|r| r.work_id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(work_ids, vec!["work1", "work2"]);
+    }
+}
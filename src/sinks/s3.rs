@@ -0,0 +1,46 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use object_store::{aws::AmazonS3Builder, path::Path, ObjectStore};
+
+use super::{Sink, SinkRecord};
+
+pub struct S3Sink {
+    prefix: String,
+    store: Box<dyn ObjectStore>,
+}
+
+impl S3Sink {
+    pub fn new(bucket: &str, region: &str, prefix: &str) -> Result<Self> {
+        let store = AmazonS3Builder::from_env()
+            .with_bucket_name(bucket)
+            .with_region(region)
+            .build()?;
+        Ok(Self {
+            prefix: prefix.trim_end_matches('/').into(),
+            store: Box::new(store),
+        })
+    }
+}
+
+#[async_trait]
+impl Sink for S3Sink {
+    #[tracing::instrument(skip(self, record))]
+    async fn deliver(&self, record: &SinkRecord) -> Result<()> {
+        let path = Path::from(format!("{}/{}.json", self.prefix, record.key()));
+        let payload = serde_json::to_vec(record)?;
+        self.store.put(&path, Bytes::from(payload)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix() {
+        let sink = S3Sink::new("outputs", "us-east-1", "indexify/").unwrap();
+        assert_eq!(sink.prefix, "indexify");
+    }
+}
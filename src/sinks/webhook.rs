@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+
+use super::{Sink, SinkRecord};
+
+/// A webhook which does not answer in time fails the delivery, which is then
+/// retried, instead of holding the delivery of the other outputs.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct WebhookSink {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookSink {
+    pub fn new(url: &str) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()?;
+        Ok(Self {
+            url: url.into(),
+            client,
+        })
+    }
+}
+
+#[async_trait]
+impl Sink for WebhookSink {
+    #[tracing::instrument(skip(self, record))]
+    async fn deliver(&self, record: &SinkRecord) -> Result<()> {
+        let resp = self
+            .client
+            .post(&self.url)
+            .json(record)
+            .send()
+            .await
+            .map_err(|e| anyhow!("unable to post to webhook {}: {}", self.url, e))?;
+        if !resp.status().is_success() {
+            return Err(anyhow!(
+                "webhook {} responded with status: {}",
+                self.url,
+                resp.status()
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+
+use crate::persistence::{NewIndex, Repository, RepositoryError, Summary, SummarySchema};
+
+pub struct SummaryIndexManager {
+    repository: Arc<Repository>,
+}
+
+impl fmt::Debug for SummaryIndexManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SummaryIndexManager").finish()
+    }
+}
+
+impl SummaryIndexManager {
+    pub fn new(repository: Arc<Repository>) -> Self {
+        Self { repository }
+    }
+
+    pub async fn create_index(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        extractor: &str,
+        schema: SummarySchema,
+    ) -> Result<String> {
+        self.repository
+            .create_index_metadata(
+                namespace,
+                repository,
+                NewIndex {
+                    extractor_name: extractor,
+                    name: index_name,
+                    storage_name: "summary_store",
+                    schema: serde_json::json!(schema),
+                    index_type: "summary",
+                },
+            )
+            .await?;
+        Ok(index_name.to_string())
+    }
+
+    /// Sets the summary of a content, replacing the summary of an earlier
+    /// extraction so that the summary follows the content as it changes.
+    #[tracing::instrument(skip(self, text))]
+    pub async fn add_summary(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        content_id: &str,
+        extractor: &str,
+        text: &str,
+    ) -> Result<()> {
+        let updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self.repository
+            .upsert_summary(
+                namespace,
+                repository,
+                index_name,
+                Summary {
+                    content_id: content_id.into(),
+                    text: text.trim().into(),
+                    extractor_name: extractor.into(),
+                    updated_at,
+                },
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// The summaries of the content which has been summarized, by content id.
+    pub async fn summaries(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index_name: &str,
+        content_ids: &[String],
+    ) -> Result<HashMap<String, String>, RepositoryError> {
+        Ok(self
+            .repository
+            .summaries_of_content(namespace, repository, index_name, content_ids)
+            .await?
+            .into_iter()
+            .map(|s| (s.content_id, s.text))
+            .collect())
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+use anyhow::{anyhow, Result};
+use opentelemetry::{global, KeyValue};
+use opentelemetry_sdk::{propagation::TraceContextPropagator, runtime, trace, Resource};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// The environment variable which enables the export of traces over OTLP.
+pub const OTLP_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+const SERVICE_NAME: &str = "indexify";
+
+/// The levels of the logs until the config of the process is read.
+const DEFAULT_LOG_LEVEL: &str = "info";
+
+/// The filter of the logs of the process and the directives it was built
+/// from.
+struct LogFilter {
+    handle: reload::Handle<EnvFilter, Registry>,
+    directives: Mutex<String>,
+}
+
+static LOG_FILTER: OnceLock<LogFilter> = OnceLock::new();
+
+/// The filter of the logs of the process, whose levels are changed by
+/// `set_log_level` while the process runs.
+pub fn log_filter() -> reload::Layer<EnvFilter, Registry> {
+    let (layer, handle) = reload::Layer::new(EnvFilter::new(DEFAULT_LOG_LEVEL));
+    let _ = LOG_FILTER.set(LogFilter {
+        handle,
+        directives: Mutex::new(DEFAULT_LOG_LEVEL.into()),
+    });
+    layer
+}
+
+/// Changes the levels of the logs to `directives`, such as `info` or
+/// `info,indexify::coordinator=debug`. Returns whether the levels changed.
+pub fn set_log_level(directives: &str) -> Result<bool> {
+    let filter = EnvFilter::try_new(directives)
+        .map_err(|e| anyhow!("invalid log level {}: {}", directives, e))?;
+    let log_filter = LOG_FILTER
+        .get()
+        .ok_or(anyhow!("the logs are not initialized"))?;
+    let mut current = log_filter.directives.lock().unwrap();
+    if *current == directives {
+        return Ok(false);
+    }
+    log_filter.handle.reload(filter)?;
+    *current = directives.to_string();
+    Ok(true)
+}
+
+/// The W3C trace context of a span, as `traceparent` and `tracestate` headers.
+/// It is stored with the events and work created while ingesting content so
+/// that the spans of the later stages of the pipeline join the trace of the
+/// request which added the content.
+pub type TraceContext = HashMap<String, String>;
+
+/// A tracer which exports spans to the OTLP endpoint in
+/// `OTEL_EXPORTER_OTLP_ENDPOINT`, or `None` when the variable is not set.
+pub fn otlp_tracer() -> Result<Option<trace::Tracer>> {
+    if std::env::var_os(OTLP_ENDPOINT_ENV).is_none() {
+        return Ok(None);
+    }
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().http())
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                SERVICE_NAME,
+            )])),
+        )
+        .install_batch(runtime::Tokio)?;
+    Ok(Some(tracer))
+}
+
+/// The trace context of the current span.
+pub fn current_context() -> TraceContext {
+    let mut context = TraceContext::new();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&Span::current().context(), &mut context)
+    });
+    context
+}
+
+/// Makes the span a child of the span the trace context was taken from. An
+/// empty trace context leaves the span as it is.
+pub fn set_parent(span: &Span, context: &TraceContext) {
+    if context.is_empty() {
+        return;
+    }
+    let parent = global::get_text_map_propagator(|propagator| propagator.extract(context));
+    span.set_parent(parent);
+}
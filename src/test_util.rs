@@ -12,13 +12,17 @@ pub mod db_utils {
         executor::ExtractorExecutor,
         persistence::{
             DataRepository,
+            DedupPolicy,
             Extractor,
             ExtractorBinding,
             ExtractorOutputSchema,
             ExtractorSchema,
             Repository,
+            RetentionPolicy,
+            TextAnalysisConfig,
+            DEFAULT_NAMESPACE,
         },
-        server_config::{ExtractorConfig, ServerConfig},
+        server_config::{ExtractorConfig, SchedulerConfig, ServerConfig},
         vector_index::VectorIndexManager,
         vectordbs::{self, qdrant::QdrantDb, IndexDistance, VectorDBTS},
     };
@@ -29,9 +33,13 @@ pub mod db_utils {
 
     pub fn default_test_data_repository() -> DataRepository {
         DataRepository {
+            namespace: DEFAULT_NAMESPACE.into(),
             name: DEFAULT_TEST_REPOSITORY.into(),
             data_connectors: vec![],
             metadata: HashMap::new(),
+            dedup_policy: DedupPolicy::default(),
+            retention: RetentionPolicy::default(),
+            text_analysis: TextAnalysisConfig::default(),
             extractor_bindings: vec![ExtractorBinding::new(
                 "test_extractor_binding",
                 DEFAULT_TEST_REPOSITORY,
@@ -39,6 +47,7 @@ pub mod db_utils {
                 vec![],
                 serde_json::json!({}),
             )],
+            version: 0,
         }
     }
 
@@ -58,9 +67,12 @@ pub mod db_utils {
         db: DatabaseConnection,
     ) -> (Arc<VectorIndexManager>, ExtractorExecutor, Arc<Coordinator>) {
         let index_name = format!("{}/{}", DEFAULT_TEST_REPOSITORY, DEFAULT_TEST_EXTRACTOR);
-        let qdrant: VectorDBTS = Arc::new(QdrantDb::new(crate::server_config::QdrantConfig {
-            addr: "http://localhost:6334".into(),
-        }));
+        let qdrant: VectorDBTS = Arc::new(QdrantDb::new_with_pool(
+            crate::server_config::QdrantConfig {
+                addr: "http://localhost:6334".into(),
+            },
+            &crate::server_config::VectorDbPoolConfig::default(),
+        ));
         let _ = qdrant.drop_index(index_name).await;
         let repository = Arc::new(Repository::new_with_db(db.clone()));
         let server_config = Arc::new(ServerConfig::from_path("local_server_config.yaml").unwrap());
@@ -86,6 +98,7 @@ pub mod db_utils {
             repository.clone(),
             vector_index_manager.clone(),
             attribute_index_manager.clone(),
+            SchedulerConfig::default(),
         );
         coordinator
             .record_executor(extractor_executor.get_executor_info())
@@ -0,0 +1,142 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::persistence::TextAnalysisConfig;
+
+/// The text search configurations which ship with Postgres, which stem the
+/// terms of the keyword index in their language. `simple` only lowercases.
+pub const TEXT_SEARCH_LANGUAGES: [&str; 23] = [
+    "simple",
+    "arabic",
+    "danish",
+    "dutch",
+    "english",
+    "finnish",
+    "french",
+    "german",
+    "greek",
+    "hungarian",
+    "indonesian",
+    "irish",
+    "italian",
+    "lithuanian",
+    "nepali",
+    "norwegian",
+    "portuguese",
+    "romanian",
+    "russian",
+    "spanish",
+    "swedish",
+    "tamil",
+    "turkish",
+];
+
+pub const DEFAULT_TEXT_SEARCH_LANGUAGE: &str = "simple";
+
+/// Turns the queries of the keyword index of a repository into the terms
+/// looked up from it. Chunks are indexed with all their terms, and the
+/// analysis is only applied to queries: stop words are dropped from them, so
+/// they never match, and every term matches its synonyms, which are kept in
+/// both directions. Changing the stop words or synonyms of a repository takes
+/// effect without analyzing its chunks again.
+#[derive(Debug, Clone, Default)]
+pub struct TextAnalyzer {
+    stop_words: HashSet<String>,
+    synonyms: HashMap<String, Vec<String>>,
+}
+
+impl TextAnalyzer {
+    pub fn new(config: &TextAnalysisConfig) -> Self {
+        let stop_words = config.stop_words.iter().map(|w| w.to_lowercase()).collect();
+        let mut synonyms: HashMap<String, Vec<String>> = HashMap::new();
+        for (term, expansions) in &config.synonyms {
+            let term = term.to_lowercase();
+            for expansion in expansions {
+                let expansion = expansion.to_lowercase();
+                synonyms
+                    .entry(term.clone())
+                    .or_default()
+                    .push(expansion.clone());
+                synonyms.entry(expansion).or_default().push(term.clone());
+            }
+        }
+        Self {
+            stop_words,
+            synonyms,
+        }
+    }
+
+    /// Lowercased alphanumeric tokens of the text, without the stop words.
+    pub fn tokenize(&self, text: &str) -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_lowercase())
+            .filter(|t| !self.stop_words.contains(t))
+            .collect()
+    }
+
+    /// A Postgres `tsquery` matching text which has every token of the query,
+    /// or one of its synonyms. Synonyms of several words match as phrases.
+    /// `None` when the query has only stop words.
+    pub fn to_tsquery(&self, query: &str) -> Option<String> {
+        let clauses: Vec<String> = self
+            .tokenize(query)
+            .into_iter()
+            .map(|term| {
+                let mut alternatives = vec![term.clone()];
+                for synonym in self.synonyms.get(&term).into_iter().flatten() {
+                    let phrase = self.tokenize(synonym).join(" <-> ");
+                    if !phrase.is_empty() && !alternatives.contains(&phrase) {
+                        alternatives.push(phrase);
+                    }
+                }
+                if alternatives.len() == 1 {
+                    term
+                } else {
+                    format!("({})", alternatives.join(" | "))
+                }
+            })
+            .collect();
+        (!clauses.is_empty()).then(|| clauses.join(" & "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stop_words_and_synonyms() {
+        let analyzer = TextAnalyzer::new(&TextAnalysisConfig {
+            stop_words: vec!["The".to_string(), "of".to_string()],
+            synonyms: HashMap::from([("k8s".to_string(), vec!["kubernetes".to_string()])]),
+            ..Default::default()
+        });
+        assert_eq!(analyzer.tokenize("The state of K8s"), vec!["state", "k8s"]);
+        assert_eq!(
+            analyzer.to_tsquery("The state of K8s").as_deref(),
+            Some("state & (k8s | kubernetes)")
+        );
+        // Synonyms match in both directions.
+        assert_eq!(
+            analyzer.to_tsquery("kubernetes").as_deref(),
+            Some("(kubernetes | k8s)")
+        );
+    }
+
+    #[test]
+    fn test_to_tsquery() {
+        let analyzer = TextAnalyzer::new(&TextAnalysisConfig {
+            stop_words: vec!["the".to_string()],
+            synonyms: HashMap::from([(
+                "mi".to_string(),
+                vec!["myocardial infarction".to_string()],
+            )]),
+            ..Default::default()
+        });
+        assert_eq!(
+            analyzer.to_tsquery("The MI, error E1234").as_deref(),
+            Some("(mi | myocardial <-> infarction) & error & e1234")
+        );
+        assert_eq!(analyzer.to_tsquery("the"), None);
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    fs::{self, File},
+    future::Future,
+    io::BufReader,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, OnceLock},
+};
+
+use anyhow::{anyhow, Context, Result};
+use axum::Router;
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+use tokio_rustls::rustls::{
+    server::AllowAnyAuthenticatedClient,
+    Certificate,
+    PrivateKey,
+    RootCertStore,
+    ServerConfig,
+};
+use tracing::warn;
+
+use crate::server_config::TlsConfig;
+
+/// The client of the connections to the coordinator and the executors, and
+/// the scheme of their addresses.
+struct InternalClient {
+    client: reqwest::Client,
+    scheme: &'static str,
+}
+
+static INTERNAL_CLIENT: OnceLock<InternalClient> = OnceLock::new();
+
+/// Selects how this process connects to the coordinator and the executors.
+/// Called once at startup, before any connection is made.
+pub fn configure_internal(config: Option<&TlsConfig>) -> Result<()> {
+    let internal_client = match config {
+        Some(config) => InternalClient {
+            client: client(config)?,
+            scheme: "https",
+        },
+        None => InternalClient {
+            client: reqwest::Client::new(),
+            scheme: "http",
+        },
+    };
+    if INTERNAL_CLIENT.set(internal_client).is_err() {
+        warn!("the internal connections are already configured, ignoring the tls config");
+    }
+    Ok(())
+}
+
+fn internal_client() -> &'static InternalClient {
+    INTERNAL_CLIENT.get_or_init(|| InternalClient {
+        client: reqwest::Client::new(),
+        scheme: "http",
+    })
+}
+
+/// A client which connects to the coordinator and the executors, over TLS
+/// when the internal connections are configured with it.
+pub fn internal_http_client() -> reqwest::Client {
+    internal_client().client.clone()
+}
+
+/// The url of `path` on the coordinator or the executor at `addr`.
+pub fn internal_url(addr: &str, path: &str) -> String {
+    format!("{}://{}{}", internal_client().scheme, addr, path)
+}
+
+/// A client which trusts the CA of the config and presents its certificate.
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+fn client(config: &TlsConfig) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(ca_path) = &config.ca_path {
+        for cert in read_certs(ca_path)? {
+            builder = builder.add_root_certificate(reqwest::Certificate::from_der(&cert.0)?);
+        }
+    }
+    let cert = fs::read(&config.cert_path)
+        .with_context(|| format!("unable to read certificate {}", config.cert_path))?;
+    let key = fs::read(&config.key_path)
+        .with_context(|| format!("unable to read key {}", config.key_path))?;
+    #[cfg(feature = "native-tls")]
+    let identity = reqwest::Identity::from_pkcs8_pem(&cert, &key)?;
+    #[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+    let identity = reqwest::Identity::from_pem(&[cert, key].concat())?;
+    Ok(builder.identity(identity).build()?)
+}
+
+#[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+fn client(_config: &TlsConfig) -> Result<reqwest::Client> {
+    Err(anyhow!(
+        "indexify was built without the `native-tls` or `rustls` feature"
+    ))
+}
+
+fn read_certs(path: &str) -> Result<Vec<Certificate>> {
+    let mut reader = BufReader::new(
+        File::open(path).with_context(|| format!("unable to read certificates {}", path))?,
+    );
+    let certs: Vec<Certificate> = rustls_pemfile::certs(&mut reader)?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+    if certs.is_empty() {
+        return Err(anyhow!("no certificates found in {}", path));
+    }
+    Ok(certs)
+}
+
+fn read_key(path: &str) -> Result<PrivateKey> {
+    let mut reader =
+        BufReader::new(File::open(path).with_context(|| format!("unable to read key {}", path))?);
+    for item in rustls_pemfile::read_all(&mut reader)? {
+        match item {
+            rustls_pemfile::Item::PKCS8Key(key) |
+            rustls_pemfile::Item::RSAKey(key) |
+            rustls_pemfile::Item::ECKey(key) => return Ok(PrivateKey(key)),
+            _ => {}
+        }
+    }
+    Err(anyhow!("no private key found in {}", path))
+}
+
+/// The TLS config of a listener, which requires clients to present a
+/// certificate signed by the CA of the config when `verify_client_certs` is
+/// set.
+fn server_config(config: &TlsConfig) -> Result<ServerConfig> {
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = if config.verify_client_certs {
+        let ca_path = config
+            .ca_path
+            .as_ref()
+            .ok_or(anyhow!("verify_client_certs requires a ca_path"))?;
+        let mut roots = RootCertStore::empty();
+        for cert in read_certs(ca_path)? {
+            roots.add(&cert)?;
+        }
+        builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+    } else {
+        builder.with_no_client_auth()
+    };
+    let mut server_config =
+        builder.with_single_cert(read_certs(&config.cert_path)?, read_key(&config.key_path)?)?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(server_config)
+}
+
+/// Serves the app on the listener until `shutdown` completes, over TLS when
+/// the listener is configured with it. The address of the client is made
+/// available to the app as `ConnectInfo<SocketAddr>`.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    tls: Option<&TlsConfig>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    listener.set_nonblocking(true)?;
+    let Some(tls) = tls else {
+        axum::Server::from_tcp(listener)?
+            .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(shutdown)
+            .await?;
+        return Ok(());
+    };
+    let rustls_config = RustlsConfig::from_config(Arc::new(server_config(tls)?));
+    let handle = Handle::new();
+    let shutdown_handle = handle.clone();
+    tokio::spawn(async move {
+        shutdown.await;
+        shutdown_handle.graceful_shutdown(None);
+    });
+    axum_server::from_tcp_rustls(listener, rustls_config)
+        .handle(handle)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await?;
+    Ok(())
+}
+
+/// Binds the address and serves the app on it, see `serve`.
+pub async fn bind_and_serve(
+    addr: SocketAddr,
+    app: Router,
+    tls: Option<&TlsConfig>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    serve(TcpListener::bind(addr)?, app, tls, shutdown).await
+}
+
+/// The TLS config of the gRPC server.
+pub fn grpc_config(config: &TlsConfig) -> Result<tonic::transport::ServerTlsConfig> {
+    let cert = fs::read(&config.cert_path)
+        .with_context(|| format!("unable to read certificate {}", config.cert_path))?;
+    let key = fs::read(&config.key_path)
+        .with_context(|| format!("unable to read key {}", config.key_path))?;
+    let tls_config = tonic::transport::ServerTlsConfig::new()
+        .identity(tonic::transport::Identity::from_pem(cert, key));
+    if !config.verify_client_certs {
+        return Ok(tls_config);
+    }
+    let ca_path = config
+        .ca_path
+        .as_ref()
+        .ok_or(anyhow!("verify_client_certs requires a ca_path"))?;
+    let ca =
+        fs::read(ca_path).with_context(|| format!("unable to read certificates {}", ca_path))?;
+    Ok(tls_config.client_ca_root(tonic::transport::Certificate::from_pem(ca)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_internal_url() {
+        assert_eq!(
+            internal_url("localhost:8950", "/sync_executor"),
+            "http://localhost:8950/sync_executor"
+        );
+    }
+
+    #[test]
+    fn test_verify_client_certs_requires_ca() {
+        let config = TlsConfig {
+            cert_path: "cert.pem".into(),
+            key_path: "key.pem".into(),
+            ca_path: None,
+            verify_client_certs: true,
+        };
+        assert_eq!(
+            server_config(&config).unwrap_err().to_string(),
+            "verify_client_certs requires a ca_path"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! The names of the collections in the vector store which hold the embeddings
+//! of indexes. A collection is named after the namespace, the repository and
+//! the index it belongs to, and the generation of the index, so the
+//! collections of an index are known without asking the vector store, and are
+//! created and dropped along with the index.
+
+use crate::persistence::IndexSnapshot;
+
+/// The name of the collection in the vector store which holds the embeddings
+/// of a generation of an index. Namespaces can not contain `-`, so two
+/// namespaces never share a collection.
+pub fn vector_index_name(
+    namespace: &str,
+    repository: &str,
+    index_name: &str,
+    generation: i64,
+) -> String {
+    if generation == 0 {
+        format!("{}-{}-{}", namespace, repository, index_name)
+    } else {
+        format!(
+            "{}-{}-{}-g{}",
+            namespace, repository, index_name, generation
+        )
+    }
+}
+
+/// Whether the collection of a snapshot was named by Indexify, so that it can
+/// be created when it is missing. The collections of imported indexes are
+/// named by whoever wrote them, and are never created.
+pub fn is_managed(namespace: &str, snapshot: &IndexSnapshot) -> bool {
+    snapshot.vector_index_name ==
+        vector_index_name(
+            namespace,
+            &snapshot.repository,
+            &snapshot.index_name,
+            snapshot.generation,
+        )
+}
+
+/// Every collection an index may have left in the vector store, up to the
+/// generation after the current one, which a reindex that failed before the
+/// flip leaves behind. Reindexing drops the generation before the current one,
+/// the older generations are listed in case dropping them failed.
+pub fn collections_of_index(namespace: &str, snapshot: &IndexSnapshot) -> Vec<String> {
+    let mut collections: Vec<String> = (0..=snapshot.generation + 1)
+        .map(|generation| {
+            vector_index_name(
+                namespace,
+                &snapshot.repository,
+                &snapshot.index_name,
+                generation,
+            )
+        })
+        .collect();
+    if !collections.contains(&snapshot.vector_index_name) {
+        collections.push(snapshot.vector_index_name.clone());
+    }
+    collections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(vector_index_name: &str, generation: i64) -> IndexSnapshot {
+        IndexSnapshot {
+            repository: "docs".into(),
+            index_name: "minilm.embedding".into(),
+            vector_index_name: vector_index_name.into(),
+            generation,
+        }
+    }
+
+    #[test]
+    fn test_collections_of_index() {
+        assert_eq!(
+            vector_index_name("default", "docs", "minilm.embedding", 0),
+            "default-docs-minilm.embedding"
+        );
+        let current = snapshot("default-docs-minilm.embedding-g2", 2);
+        assert!(is_managed("default", &current));
+        assert!(!is_managed("research", &current));
+        assert_eq!(
+            collections_of_index("default", &current),
+            vec![
+                "default-docs-minilm.embedding",
+                "default-docs-minilm.embedding-g1",
+                "default-docs-minilm.embedding-g2",
+                "default-docs-minilm.embedding-g3",
+            ]
+        );
+
+        // An imported collection is dropped along with the collection of the
+        // generation a reindex would create.
+        let imported = snapshot("legacy_vectors", 0);
+        assert!(!is_managed("default", &imported));
+        assert_eq!(
+            collections_of_index("default", &imported),
+            vec![
+                "default-docs-minilm.embedding",
+                "default-docs-minilm.embedding-g1",
+                "legacy_vectors",
+            ]
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use serde_json::json;
+use tracing::info;
+
+use crate::{
+    persistence::{Chunk, ContentPayload, ExtractorOutputSchema, NewIndex, Repository},
+    vectordbs::{StoredPoint, VectorDBTS},
+};
+
+/// Where to import a collection of the vector store from, and the index of
+/// a repository it becomes.
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    pub namespace: String,
+    pub repository: String,
+    pub index: String,
+    pub collection: String,
+    /// The extractor which embedded the collection, which embeds the queries
+    /// of searches on the index.
+    pub extractor: String,
+    /// The payload field which holds the text of a point.
+    pub text_field: String,
+    pub batch_size: u32,
+}
+
+/// The outcome of an import.
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    pub imported: u64,
+    /// Points without a text in their payload, which can not be returned by
+    /// searches.
+    pub skipped: u64,
+}
+
+/// Bootstraps an index from a collection of the vector store which was not
+/// written by Indexify, so that a corpus embedded elsewhere does not have to
+/// be embedded again. The collection itself becomes the vector index, and
+/// every point of it becomes a content and a chunk whose id is the id of the
+/// point.
+pub struct VectorImport {
+    repository: Arc<Repository>,
+    vector_db: VectorDBTS,
+}
+
+impl VectorImport {
+    pub fn new(repository: Arc<Repository>, vector_db: VectorDBTS) -> Self {
+        Self {
+            repository,
+            vector_db,
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn run(&self, options: &ImportOptions) -> Result<ImportSummary> {
+        let data_repository = self
+            .repository
+            .repository_by_name(&options.namespace, &options.repository)
+            .await?;
+        let extractor = self
+            .repository
+            .extractor_by_name(&options.extractor)
+            .await?;
+        let schema = extractor
+            .schemas
+            .outputs
+            .values()
+            .find_map(|schema| match schema {
+                ExtractorOutputSchema::Embedding(schema) => Some(schema.clone()),
+                _ => None,
+            })
+            .ok_or(anyhow!(
+                "extractor {} does not extract embeddings",
+                options.extractor
+            ))?;
+        if schema.multi_vector {
+            return Err(anyhow!(
+                "extractor {} embeds chunks as multiple vectors, collections can only be \
+                 imported with single embeddings",
+                options.extractor
+            ));
+        }
+        self.vector_db.num_vectors(&options.collection).await?;
+        self.repository
+            .create_index_metadata(
+                &options.namespace,
+                &options.repository,
+                NewIndex {
+                    extractor_name: &options.extractor,
+                    name: &options.index,
+                    storage_name: &options.collection,
+                    schema: json!(schema),
+                    index_type: "embedding",
+                },
+            )
+            .await?;
+        let snapshot = self
+            .repository
+            .index_snapshot(&options.namespace, &options.index, &options.repository)
+            .await?;
+        if snapshot.vector_index_name != options.collection {
+            return Err(anyhow!(
+                "index {} already exists on collection {}",
+                options.index,
+                snapshot.vector_index_name
+            ));
+        }
+
+        // The content is not extracted again by the bindings of the
+        // repository, which would embed the corpus a second time.
+        let bindings: Vec<String> = data_repository
+            .extractor_bindings
+            .iter()
+            .map(|binding| binding.name.clone())
+            .collect();
+        let mut summary = ImportSummary::default();
+        let mut offset = None;
+        loop {
+            let page = self
+                .vector_db
+                .scroll(&options.collection, offset, options.batch_size.max(1))
+                .await?;
+            let mut content_list = Vec::new();
+            let mut chunks = Vec::new();
+            for point in page.points {
+                let Some((content, chunk)) =
+                    imported_chunk(&options.repository, &options.text_field, point)
+                else {
+                    summary.skipped += 1;
+                    continue;
+                };
+                content_list.push(content);
+                chunks.push(chunk);
+            }
+            summary.imported += chunks.len() as u64;
+            self.repository
+                .replicate_content(
+                    &options.namespace,
+                    &options.repository,
+                    content_list,
+                    &bindings,
+                )
+                .await?;
+            self.repository
+                .create_chunks(&options.namespace, chunks, &snapshot)
+                .await?;
+            info!(
+                "imported {} points of collection {} into index {}",
+                summary.imported, options.collection, options.index
+            );
+            offset = page.next_offset;
+            if offset.is_none() {
+                break;
+            }
+        }
+        Ok(summary)
+    }
+}
+
+/// The content and the chunk of a point. The text field is taken out of the
+/// payload and the rest of the payload becomes the metadata of the content.
+fn imported_chunk(
+    repository: &str,
+    text_field: &str,
+    mut point: StoredPoint,
+) -> Option<(ContentPayload, Chunk)> {
+    let text = match point.payload.remove(text_field)? {
+        serde_json::Value::String(text) => text,
+        _ => return None,
+    };
+    let content = ContentPayload::from_text(repository, &text, point.payload.into_iter().collect());
+    let chunk = Chunk {
+        text,
+        chunk_id: point.id,
+        content_id: content.id.clone(),
+        parent_id: None,
+        is_parent: false,
+        offsets: None,
+        region: None,
+    };
+    Some((content, chunk))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_imported_chunk() {
+        let payload = json!({"page_content": "hello", "source": "a.pdf"});
+        let point = StoredPoint {
+            id: "2a".into(),
+            payload: payload.as_object().unwrap().clone(),
+        };
+        let (content, chunk) = imported_chunk("docs", "page_content", point.clone()).unwrap();
+        assert_eq!(content.payload, "hello");
+        assert_eq!(
+            content.metadata,
+            [("source".to_string(), json!("a.pdf"))].into()
+        );
+        assert_eq!(chunk.chunk_id, "2a");
+        assert_eq!(chunk.text, "hello");
+        assert_eq!(chunk.content_id, content.id);
+
+        assert!(imported_chunk("docs", "text", point).is_none());
+    }
+}
@@ -1,21 +1,59 @@
-use std::{collections::HashMap, fmt, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use anyhow::{anyhow, Result};
-use tracing::error;
+use serde::de::DeserializeOwned;
+use tracing::{error, info, warn};
 
 use crate::{
     api::{self},
-    extractor::ExtractedEmbeddings,
+    chunking::ChunkOrigin,
+    embedding_cache::EmbeddingCache,
+    entity::index::Model as IndexModel,
+    extractor::{ExtractedEmbeddings, ExtractedMultiVectors},
     extractor_router::ExtractorRouter,
     index::IndexError,
-    persistence::{Chunk, EmbeddingSchema, Repository},
-    vectordbs::{CreateIndexParams, VectorChunk, VectorDBTS},
+    persistence::{
+        Chunk,
+        ChunkOffsets,
+        EmbeddingSchema,
+        ExtractorBinding,
+        ImageRegion,
+        IndexSnapshot,
+        Modality,
+        NewIndex,
+        Repository,
+        RepositoryError,
+        RerankerConfig,
+    },
+    rerank::{self, Reranker},
+    server_config::EmbeddingCacheConfig,
+    vector_collections::{self, vector_index_name},
+    vectordbs::{
+        CreateIndexParams,
+        MetadataMatch,
+        MultiVectorChunk,
+        PayloadIndex,
+        Quantization,
+        SearchFilter,
+        VectorChunk,
+        VectorDBTS,
+    },
 };
 
 pub struct VectorIndexManager {
     repository: Arc<Repository>,
     vector_db: VectorDBTS,
     extractor_router: ExtractorRouter,
+    reranker: Reranker,
+    embedding_cache: Arc<EmbeddingCache>,
+    /// The collections created in the vector store since the server started,
+    /// which are not created again when their index is used.
+    created_collections: Mutex<HashSet<String>>,
 }
 
 impl fmt::Debug for VectorIndexManager {
@@ -27,8 +65,156 @@ impl fmt::Debug for VectorIndexManager {
 pub struct ScoredText {
     pub text: String,
     pub content_id: String,
+    /// Where the text is in the content, when the chunk was split by the
+    /// chunker of a binding.
+    pub offsets: Option<ChunkOffsets>,
+    /// Where the chunk is in its image, when it is a tile of an image.
+    pub region: Option<ImageRegion>,
     pub metadata: HashMap<String, serde_json::Value>,
     pub confidence_score: f32,
+    pub explanation: HitExplanation,
+}
+
+/// What a search looks for, embedded by the extractor of the index searched.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchQuery {
+    Text(String),
+    /// An image, for indexes whose extractor embeds images.
+    Image {
+        content_type: String,
+        data: Vec<u8>,
+    },
+}
+
+impl SearchQuery {
+    fn modality(&self) -> Modality {
+        match self {
+            SearchQuery::Text(_) => Modality::Text,
+            SearchQuery::Image { .. } => Modality::Image,
+        }
+    }
+
+    fn content(&self) -> api::Content {
+        match self {
+            SearchQuery::Text(text) => text_content(text),
+            SearchQuery::Image { content_type, data } => api::Content {
+                content_type: content_type.clone(),
+                source: data.clone(),
+                feature: None,
+            },
+        }
+    }
+}
+
+fn text_content(text: &str) -> api::Content {
+    api::Content {
+        content_type: mime::TEXT_PLAIN.to_string(),
+        source: text.as_bytes().into(),
+        feature: None,
+    }
+}
+
+/// Where a search result came from and the scores which ranked it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HitExplanation {
+    /// Position of the chunk in the results of the vector store, starting at
+    /// 0.
+    pub rank: usize,
+    pub chunk_id: String,
+    /// The parent chunk the chunk was split out of.
+    pub parent_chunk_id: Option<String>,
+    /// Similarity of the embedding of the chunk to the embedding of the query.
+    pub similarity_score: f32,
+    /// Score of the query terms in the chunk, when the keyword index was
+    /// searched.
+    pub keyword_score: Option<f32>,
+    /// Score given by the reranker, when the results were reranked.
+    pub reranker_score: Option<f32>,
+    /// The index and the generation of it which served the result.
+    pub snapshot: IndexSnapshot,
+}
+
+/// The text embedded to load the model of an extractor while warming up an
+/// index.
+const WARMUP_QUERY: &str = "warmup";
+
+/// What warming up an index found.
+#[derive(Debug, Clone)]
+pub struct IndexWarmup {
+    pub snapshot: IndexSnapshot,
+    pub vectors: u64,
+    /// Whether a query was embedded by the extractor of the index.
+    pub extractor_warmed: bool,
+    pub elapsed: Duration,
+}
+
+/// The embeddings of chunks, as single vectors or as the vectors of
+/// multi-vector indexes.
+enum ChunkEmbeddings {
+    Single(Vec<VectorChunk>),
+    Multi(Vec<MultiVectorChunk>),
+}
+
+impl ChunkEmbeddings {
+    fn is_empty(&self) -> bool {
+        match self {
+            Self::Single(chunks) => chunks.is_empty(),
+            Self::Multi(chunks) => chunks.is_empty(),
+        }
+    }
+}
+
+/// Whether the index embeds every chunk as several vectors.
+fn is_multi_vector(index_info: &IndexModel) -> bool {
+    serde_json::from_value::<EmbeddingSchema>(index_info.index_schema.clone())
+        .map(|schema| schema.multi_vector)
+        .unwrap_or(false)
+}
+
+/// The fields of the metadata of content the index has payload indexes of.
+fn payload_indexes(index_info: &IndexModel) -> Vec<PayloadIndex> {
+    serde_json::from_value::<EmbeddingSchema>(index_info.index_schema.clone())
+        .map(|schema| schema.payload_indexes)
+        .unwrap_or_default()
+}
+
+/// The chunks of texts of content, split out of it where `origin` says, and
+/// the parent chunk of the origin they are children of, when it has one.
+/// Returns the chunks to store and the ids of the chunks of the texts, in the
+/// order of the texts.
+fn origin_chunks(origin: &ChunkOrigin, texts: &[(&str, &str)]) -> (Vec<Chunk>, Vec<String>) {
+    let mut chunks: Vec<Chunk> = Vec::new();
+    let mut chunk_ids = Vec::new();
+    for (content_id, text) in texts {
+        let parent = origin.parent.as_ref().map(|parent| {
+            Chunk::parent(parent.text.clone(), content_id.to_string())
+                .with_offsets(Some(parent.offsets))
+        });
+        let chunk = Chunk::new(text.to_string(), content_id.to_string())
+            .with_parent(parent.as_ref().map(|parent| parent.chunk_id.clone()))
+            .with_offsets(origin.offsets)
+            .with_region(origin.region);
+        if let Some(parent) = parent {
+            if !chunks.iter().any(|c| c.chunk_id == parent.chunk_id) {
+                chunks.push(parent);
+            }
+        }
+        chunk_ids.push(chunk.chunk_id.clone());
+        chunks.push(chunk);
+    }
+    (chunks, chunk_ids)
+}
+
+fn create_index_params(vector_index_name: &str, schema: &EmbeddingSchema) -> CreateIndexParams {
+    CreateIndexParams {
+        vectordb_index_name: vector_index_name.into(),
+        vector_dim: schema.dim as u64,
+        distance: schema.distance.clone(),
+        unique_params: None,
+        quantization: schema.quantization.clone(),
+        multi_vector: schema.multi_vector,
+        payload_indexes: schema.payload_indexes.clone(),
+    }
 }
 
 impl VectorIndexManager {
@@ -39,85 +225,349 @@ impl VectorIndexManager {
     ) -> Self {
         let extractor_router = ExtractorRouter::new(&coordinator_addr);
         Self {
+            embedding_cache: Arc::new(EmbeddingCache::new(
+                repository.clone(),
+                EmbeddingCacheConfig::default(),
+            )),
             repository,
             vector_db,
             extractor_router,
+            reranker: Reranker::new(&coordinator_addr),
+            created_collections: Mutex::new(HashSet::new()),
         }
     }
 
+    pub fn with_embedding_cache(mut self, config: EmbeddingCacheConfig) -> Self {
+        self.embedding_cache = Arc::new(EmbeddingCache::new(self.repository.clone(), config));
+        self
+    }
+
+    pub fn embedding_cache(&self) -> Arc<EmbeddingCache> {
+        self.embedding_cache.clone()
+    }
+
+    /// Caches the embeddings an extractor run with the parameters computed
+    /// for texts, so that the texts are not embedded again.
+    pub async fn cache_embeddings(
+        &self,
+        extractor_name: &str,
+        params: Option<&serde_json::Value>,
+        embeddings: &[ExtractedEmbeddings],
+    ) {
+        let embeddings = embeddings
+            .iter()
+            .map(|e| (e.text.as_str(), e.embeddings.clone()))
+            .collect();
+        self.embedding_cache
+            .put(extractor_name, params, embeddings)
+            .await;
+    }
+
+    pub fn supports_quantization(&self, quantization: &Quantization) -> bool {
+        self.vector_db.supports_quantization(quantization)
+    }
+
+    pub fn supports_multi_vector(&self) -> bool {
+        self.vector_db.supports_multi_vector()
+    }
+
+    pub fn supports_payload_indexes(&self) -> bool {
+        self.vector_db.supports_payload_indexes()
+    }
+
+    /// Creates the metadata of an index. Its collection is created in the
+    /// vector store when the index is first used, so that bindings which
+    /// never extract anything do not leave empty collections behind.
     pub async fn create_index(
         &self,
+        namespace: &str,
         repository: &str,
         index_name: &str,
         extractor_name: &str,
         schema: EmbeddingSchema,
     ) -> Result<String> {
-        let mut index_params: Option<CreateIndexParams> = None;
-        let vector_index_name = format!("{}-{}", repository, index_name);
-        let create_index_params = CreateIndexParams {
-            vectordb_index_name: vector_index_name.clone(),
-            vector_dim: schema.dim as u64,
-            distance: schema.distance.clone(),
-            unique_params: None,
-        };
-        index_params.replace(create_index_params);
+        if schema.multi_vector && !self.vector_db.supports_multi_vector() {
+            return Err(anyhow!(
+                "vector store {} can not store multiple vectors per chunk",
+                self.vector_db.name()
+            ));
+        }
+        let vector_index_name = vector_index_name(namespace, repository, index_name, 0);
         self.repository
             .create_index_metadata(
+                namespace,
                 repository,
-                extractor_name,
-                index_name,
-                &vector_index_name,
-                serde_json::json!(schema),
-                "embedding",
+                NewIndex {
+                    extractor_name,
+                    name: index_name,
+                    storage_name: &vector_index_name,
+                    schema: serde_json::json!(schema),
+                    index_type: "embedding",
+                },
             )
             .await?;
-        // Remove this unwrap and refactor the code to return a proper error
-        // if the extractor config doesn't have embedding type
-        self.vector_db.create_index(index_params.unwrap()).await?;
-        Ok(vector_index_name.to_string())
+        Ok(vector_index_name)
+    }
+
+    /// The snapshot of the current generation of an index, whose collection
+    /// is created in the vector store if this is the first time the index is
+    /// used. The collections of imported indexes are never created.
+    async fn used_snapshot(
+        &self,
+        namespace: &str,
+        index_info: &IndexModel,
+    ) -> Result<IndexSnapshot> {
+        let snapshot = IndexSnapshot::from(index_info);
+        if !vector_collections::is_managed(namespace, &snapshot) ||
+            self.created_collections
+                .lock()
+                .unwrap()
+                .contains(&snapshot.vector_index_name)
+        {
+            return Ok(snapshot);
+        }
+        let schema: EmbeddingSchema = serde_json::from_value(index_info.index_schema.clone())
+            .map_err(|e| {
+                anyhow!(
+                    "index: {} is not an embedding index: {}",
+                    snapshot.index_name,
+                    e
+                )
+            })?;
+        self.vector_db
+            .create_index(create_index_params(&snapshot.vector_index_name, &schema))
+            .await?;
+        self.created_collections
+            .lock()
+            .unwrap()
+            .insert(snapshot.vector_index_name.clone());
+        Ok(snapshot)
     }
 
+    /// Adds embeddings to an index along with their chunks, which were split
+    /// out of their content where `origin` says. The chunks are children of
+    /// the parent chunk of the origin, which is stored but not embedded, when
+    /// it has one.
+    #[tracing::instrument(skip(self, embeddings, origin))]
     pub async fn add_embedding(
         &self,
-        _repository: &str,
+        namespace: &str,
+        repository: &str,
         index: &str,
         embeddings: Vec<ExtractedEmbeddings>,
+        origin: ChunkOrigin,
     ) -> Result<()> {
-        let index_info = self.repository.get_index(index, _repository).await?;
-        let vector_index_name = index_info.vector_index_name.clone().unwrap();
-        let mut vector_chunks = Vec::new();
-        let mut chunks = Vec::new();
-        embeddings.iter().for_each(|embedding| {
-            let chunk = Chunk::new(embedding.text.clone(), embedding.content_id.clone());
-            let vector_chunk =
-                VectorChunk::new(chunk.chunk_id.clone(), embedding.embeddings.clone());
-            chunks.push(chunk);
-            vector_chunks.push(vector_chunk);
-        });
-        self.repository.create_chunks(chunks, index).await?;
+        let index_info = self
+            .repository
+            .get_index(namespace, index, repository)
+            .await?;
+        let snapshot = self.used_snapshot(namespace, &index_info).await?;
+        let texts: Vec<(&str, &str)> = embeddings
+            .iter()
+            .map(|e| (e.content_id.as_str(), e.text.as_str()))
+            .collect();
+        let (chunks, chunk_ids) = origin_chunks(&origin, &texts);
+        let vector_chunks = chunk_ids
+            .into_iter()
+            .zip(embeddings.iter())
+            .map(|(chunk_id, embedding)| {
+                VectorChunk::new(
+                    chunk_id,
+                    embedding.content_id.clone(),
+                    embedding.embeddings.clone(),
+                )
+            })
+            .collect();
+        let vector_chunks = self
+            .with_payload(
+                namespace,
+                repository,
+                &payload_indexes(&index_info),
+                vector_chunks,
+            )
+            .await?;
+        self.repository
+            .create_chunks(namespace, chunks, &snapshot)
+            .await?;
         self.vector_db
-            .add_embedding(&vector_index_name, vector_chunks)
+            .add_embedding(&snapshot.vector_index_name, vector_chunks)
             .await?;
         Ok(())
     }
 
-    pub async fn search(
+    /// Adds the vectors of chunks to a multi-vector index, the same way
+    /// `add_embedding` adds single embeddings. The vectors are not cached,
+    /// the embedding cache holds single embeddings.
+    #[tracing::instrument(skip(self, embeddings, origin))]
+    pub async fn add_multi_vectors(
         &self,
+        namespace: &str,
         repository: &str,
         index: &str,
-        query: &str,
-        k: usize,
-    ) -> Result<Vec<ScoredText>> {
-        let index_info = self.repository.get_index(index, repository).await?;
-        let vector_index_name = index_info.vector_index_name.clone().unwrap();
-        let content = api::Content {
-            content_type: mime::TEXT_PLAIN.to_string(),
-            source: query.as_bytes().into(),
-            feature: None,
-        };
+        embeddings: Vec<ExtractedMultiVectors>,
+        origin: ChunkOrigin,
+    ) -> Result<()> {
+        let index_info = self
+            .repository
+            .get_index(namespace, index, repository)
+            .await?;
+        let snapshot = self.used_snapshot(namespace, &index_info).await?;
+        let texts: Vec<(&str, &str)> = embeddings
+            .iter()
+            .map(|e| (e.content_id.as_str(), e.text.as_str()))
+            .collect();
+        let (chunks, chunk_ids) = origin_chunks(&origin, &texts);
+        let multi_vector_chunks = chunk_ids
+            .into_iter()
+            .zip(embeddings)
+            .map(|(chunk_id, embedding)| MultiVectorChunk {
+                chunk_id,
+                content_id: embedding.content_id,
+                vectors: embedding.vectors,
+            })
+            .collect();
+        self.repository
+            .create_chunks(namespace, chunks, &snapshot)
+            .await?;
+        self.vector_db
+            .add_multi_vectors(&snapshot.vector_index_name, multi_vector_chunks)
+            .await?;
+        Ok(())
+    }
+
+    /// Embeds texts with the extractor of an index and adds them to it, as
+    /// chunks of the content they belong to.
+    #[tracing::instrument(skip(self, texts))]
+    pub async fn embed_texts(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index: &str,
+        texts: Vec<(String, String)>,
+    ) -> Result<()> {
+        let index_info = self
+            .repository
+            .get_index(namespace, index, repository)
+            .await?;
+        let params = self.index_params(&index_info).await;
+        if is_multi_vector(&index_info) {
+            let mut embeddings = Vec::new();
+            for (content_id, text) in texts {
+                let vectors = self
+                    .embed_text_vectors(&index_info.extractor_name, params.as_ref(), &text)
+                    .await?;
+                embeddings.push(ExtractedMultiVectors {
+                    content_id,
+                    text,
+                    vectors,
+                });
+            }
+            return self
+                .add_multi_vectors(
+                    namespace,
+                    repository,
+                    index,
+                    embeddings,
+                    ChunkOrigin::default(),
+                )
+                .await;
+        }
+        let mut embeddings = Vec::new();
+        for (content_id, text) in texts {
+            let embedding = self
+                .cached_embedding(&index_info.extractor_name, params.as_ref(), &text)
+                .await?;
+            embeddings.push(ExtractedEmbeddings {
+                content_id,
+                text,
+                embeddings: embedding,
+            });
+        }
+        self.add_embedding(
+            namespace,
+            repository,
+            index,
+            embeddings,
+            ChunkOrigin::default(),
+        )
+        .await
+    }
+
+    /// The input params of the binding whose output the index holds, which
+    /// its texts and queries are embedded with. The indexes which are not the
+    /// output of a binding are embedded without params.
+    async fn index_params(&self, index_info: &IndexModel) -> Option<serde_json::Value> {
+        let (binding, _) = ExtractorBinding::parse_output_index_name(&index_info.name)?;
+        self.repository
+            .binding_by_id(&index_info.namespace, &index_info.repository_id, binding)
+            .await
+            .ok()
+            .map(|binding| binding.input_params)
+    }
+
+    /// The embedding of a text by an extractor, from the embedding cache when
+    /// the extractor already embedded the same text.
+    async fn cached_embedding(
+        &self,
+        extractor_name: &str,
+        params: Option<&serde_json::Value>,
+        text: &str,
+    ) -> Result<Vec<f32>> {
+        if let Some(embedding) = self.embedding_cache.get(extractor_name, params, text).await {
+            return Ok(embedding);
+        }
+        let embedding = self.embed_text(extractor_name, params, text).await?;
+        self.embedding_cache
+            .put(extractor_name, params, vec![(text, embedding.clone())])
+            .await;
+        Ok(embedding)
+    }
+
+    async fn embed_text(
+        &self,
+        extractor_name: &str,
+        params: Option<&serde_json::Value>,
+        text: &str,
+    ) -> Result<Vec<f32>> {
+        self.embed_query(extractor_name, params, text_content(text))
+            .await
+    }
+
+    /// The vectors of a text embedded by an extractor with a multi-vector
+    /// schema.
+    async fn embed_text_vectors(
+        &self,
+        extractor_name: &str,
+        params: Option<&serde_json::Value>,
+        text: &str,
+    ) -> Result<Vec<Vec<f32>>> {
+        self.embed_query(extractor_name, params, text_content(text))
+            .await
+    }
+
+    /// The embedding, or the vectors, an extractor extracts from a query.
+    async fn embed_query<T: DeserializeOwned>(
+        &self,
+        extractor_name: &str,
+        params: Option<&serde_json::Value>,
+        content: api::Content,
+    ) -> Result<T> {
+        let data = self
+            .extract_feature(extractor_name, params, content)
+            .await?;
+        serde_json::from_value(data).map_err(|e| anyhow!(e.to_string()))
+    }
+
+    /// The data of the feature an extractor extracts from content.
+    async fn extract_feature(
+        &self,
+        extractor_name: &str,
+        params: Option<&serde_json::Value>,
+        content: api::Content,
+    ) -> Result<serde_json::Value> {
         let content = self
             .extractor_router
-            .extract_content(&index_info.extractor_name, content, None)
+            .extract_content(extractor_name, content, params.cloned())
             .await
             .map_err(|e| IndexError::QueryEmbedding(e.to_string()))?
             .pop()
@@ -126,28 +576,711 @@ impl VectorIndexManager {
             .feature
             .as_ref()
             .ok_or(anyhow!("No features were extracted"))?;
-        let embedding: Vec<f32> =
-            serde_json::from_value(features.data.clone()).map_err(|e| anyhow!(e.to_string()))?;
-        let results = self
-            .vector_db
-            .search(vector_index_name, embedding, k as u64)
+        Ok(features.data.clone())
+    }
+
+    /// The matches of the metadata of content which a search filtered by the
+    /// fields of the metadata in `metadata_filter` is filtered by. Only fields
+    /// with a payload index in the index can be filtered by, and their values
+    /// must have the type of the payload index.
+    pub async fn metadata_matches(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index: &str,
+        metadata_filter: &HashMap<String, serde_json::Value>,
+    ) -> Result<Vec<MetadataMatch>> {
+        if metadata_filter.is_empty() {
+            return Ok(Vec::new());
+        }
+        let index_info = self
+            .repository
+            .get_index(namespace, index, repository)
             .await?;
+        let payload_indexes = payload_indexes(&index_info);
+        metadata_filter
+            .iter()
+            .map(|(field, value)| {
+                let payload_index = payload_indexes
+                    .iter()
+                    .find(|payload_index| &payload_index.field == field)
+                    .ok_or(anyhow!(
+                        "field {} of index {} has no payload index",
+                        field,
+                        index
+                    ))?;
+                let value = payload_index.value_of(value).ok_or(anyhow!(
+                    "field {} of index {} is a {} field, it can't match {}",
+                    field,
+                    index,
+                    payload_index.field_type,
+                    value
+                ))?;
+                Ok(MetadataMatch {
+                    field: field.clone(),
+                    value,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn search(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index: &str,
+        query: &SearchQuery,
+        k: usize,
+        filter: SearchFilter,
+    ) -> Result<Vec<ScoredText>> {
+        // The index is resolved once, so a reindex which flips the index while
+        // the query runs can not make it read chunks of the other generation.
+        let index_info = self
+            .repository
+            .get_index(namespace, index, repository)
+            .await?;
+        // The schemas of indexes created before modalities were recorded
+        // embed text.
+        if let Ok(schema) =
+            serde_json::from_value::<EmbeddingSchema>(index_info.index_schema.clone())
+        {
+            if !schema.embeds(query.modality()) {
+                return Err(anyhow!(
+                    "index {} can not be searched with {} queries, its extractor embeds {}",
+                    index,
+                    query.modality(),
+                    schema
+                        .modalities
+                        .iter()
+                        .map(|m| m.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" and ")
+                ));
+            }
+        }
+        let snapshot = self.used_snapshot(namespace, &index_info).await?;
+        // Rerankers score the text of the chunks against the text of the
+        // query, so searches for images keep the order of the vector store.
+        let reranker: Option<RerankerConfig> = index_info
+            .reranker
+            .clone()
+            .filter(|_| query.modality() == Modality::Text)
+            .and_then(|r| serde_json::from_value(r).ok());
+        // The reranker picks the results out of more candidates than are
+        // returned.
+        let candidates = reranker
+            .as_ref()
+            .map(|r| (r.candidates as usize).max(k))
+            .unwrap_or(k);
+        let params = self.index_params(&index_info).await;
+        let results = if is_multi_vector(&index_info) {
+            let query_vectors: Vec<Vec<f32>> = self
+                .embed_query(&index_info.extractor_name, params.as_ref(), query.content())
+                .await?;
+            self.vector_db
+                .search_multi_vector(
+                    snapshot.vector_index_name.clone(),
+                    query_vectors,
+                    candidates as u64,
+                    filter,
+                )
+                .await?
+        } else {
+            let embedding: Vec<f32> = self
+                .embed_query(&index_info.extractor_name, params.as_ref(), query.content())
+                .await?;
+            self.vector_db
+                .search(
+                    snapshot.vector_index_name.clone(),
+                    embedding,
+                    candidates as u64,
+                    filter,
+                )
+                .await?
+        };
         let mut index_search_results = Vec::new();
-        for result in results {
-            let chunk = self.repository.chunk_with_id(&result.chunk_id).await;
-            if chunk.as_ref().is_err() {
-                error!("Chunk with id {} not found", result.chunk_id);
+        for (rank, result) in results.into_iter().enumerate() {
+            let chunk = self
+                .repository
+                .chunk_with_id(namespace, &snapshot, &result.chunk_id, false)
+                .await;
+            if let Err(err) = chunk.as_ref() {
+                // Chunks of soft deleted content are skipped.
+                if !matches!(
+                    err.downcast_ref::<RepositoryError>(),
+                    Some(RepositoryError::ContentNotFound(_))
+                ) {
+                    error!("Chunk with id {} not found", result.chunk_id);
+                }
                 continue;
             }
             let search_result = ScoredText {
                 text: chunk.as_ref().unwrap().text.clone(),
                 content_id: chunk.as_ref().unwrap().content_id.clone(),
+                offsets: chunk.as_ref().unwrap().offsets,
+                region: chunk.as_ref().unwrap().region,
                 metadata: chunk.as_ref().unwrap().metadata.clone(),
                 confidence_score: result.confidence_score,
+                explanation: HitExplanation {
+                    rank,
+                    chunk_id: result.chunk_id.clone(),
+                    parent_chunk_id: chunk.as_ref().unwrap().parent_id.clone(),
+                    similarity_score: result.confidence_score,
+                    keyword_score: None,
+                    reranker_score: None,
+                    snapshot: snapshot.clone(),
+                },
             };
             index_search_results.push(search_result);
         }
-        Ok(index_search_results)
+        let (Some(reranker), SearchQuery::Text(query)) = (reranker, query) else {
+            return Ok(index_search_results);
+        };
+        match self
+            .reranker
+            .scores(&reranker, query, &index_search_results)
+            .await
+        {
+            Ok(scores) => Ok(rerank::apply_scores(index_search_results, scores, k)),
+            Err(err) => {
+                // A search is still answered in the order of the vector store
+                // while the reranker is unavailable.
+                warn!(
+                    "unable to rerank the results of index {}: {}",
+                    index,
+                    err.to_string()
+                );
+                index_search_results.truncate(k);
+                Ok(index_search_results)
+            }
+        }
+    }
+
+    /// Searches the chunks of the current generation of an index for the terms
+    /// of a `tsquery`, without embedding the query.
+    #[tracing::instrument(skip(self))]
+    pub async fn keyword_search(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index: &str,
+        tsquery: &str,
+        k: usize,
+    ) -> Result<Vec<ScoredText>> {
+        let snapshot = self
+            .repository
+            .index_snapshot(namespace, index, repository)
+            .await?;
+        let matches = self
+            .repository
+            .keyword_search(namespace, &snapshot, tsquery, k as u64)
+            .await?;
+        let results = matches
+            .into_iter()
+            .enumerate()
+            .map(|(rank, m)| ScoredText {
+                offsets: m.offsets(),
+                region: None,
+                text: m.text,
+                content_id: m.content_id,
+                metadata: m
+                    .metadata
+                    .and_then(|m| serde_json::from_value(m).ok())
+                    .unwrap_or_default(),
+                confidence_score: m.score,
+                explanation: HitExplanation {
+                    rank,
+                    chunk_id: m.chunk_id,
+                    parent_chunk_id: m.parent_id,
+                    similarity_score: 0.0,
+                    keyword_score: Some(m.score),
+                    reranker_score: None,
+                    snapshot: snapshot.clone(),
+                },
+            })
+            .collect();
+        Ok(results)
+    }
+
+    /// Replaces the text of the results which are chunks of a parent chunk
+    /// with the text of their parent, so that a search matching small chunks
+    /// returns the larger window around them. Only the first result of every
+    /// parent is kept, as the others would return the same text.
+    #[tracing::instrument(skip(self, results))]
+    pub async fn expand_to_parents(
+        &self,
+        namespace: &str,
+        results: Vec<ScoredText>,
+    ) -> Result<Vec<ScoredText>> {
+        let mut parents = HashSet::new();
+        let mut expanded = Vec::new();
+        for mut result in results {
+            let Some(parent_id) = result.explanation.parent_chunk_id.clone() else {
+                expanded.push(result);
+                continue;
+            };
+            if !parents.insert(parent_id) {
+                continue;
+            }
+            match self
+                .repository
+                .chunk_with_id(
+                    namespace,
+                    &result.explanation.snapshot,
+                    &result.explanation.chunk_id,
+                    true,
+                )
+                .await
+            {
+                Ok(parent) => {
+                    result.text = parent.text;
+                    result.offsets = parent.offsets;
+                }
+                Err(err) => error!(
+                    "parent of chunk with id {} not found: {}",
+                    result.explanation.chunk_id,
+                    err.to_string()
+                ),
+            }
+            expanded.push(result);
+        }
+        Ok(expanded)
+    }
+
+    /// Deletes the embeddings of chunks, given by the index and the generation
+    /// they belong to, from the vector store. Returns the number of chunks
+    /// whose embeddings were deleted.
+    #[tracing::instrument(skip(self, chunk_ids))]
+    pub async fn delete_embeddings(
+        &self,
+        namespace: &str,
+        repository: &str,
+        chunk_ids: HashMap<(String, i64), Vec<String>>,
+    ) -> Result<u64> {
+        let mut deleted = 0;
+        for ((index, generation), chunk_ids) in chunk_ids {
+            // The current generation of an imported index is a collection
+            // which was not named by Indexify.
+            let current = self
+                .repository
+                .get_index(namespace, &index, repository)
+                .await
+                .ok()
+                .map(|index_info| IndexSnapshot::from(&index_info))
+                .filter(|snapshot| snapshot.generation == generation);
+            let collection = match current {
+                Some(snapshot) => snapshot.vector_index_name,
+                None => vector_index_name(namespace, repository, &index, generation),
+            };
+            let count = chunk_ids.len() as u64;
+            self.vector_db
+                .delete_embeddings(&collection, chunk_ids)
+                .await?;
+            deleted += count;
+        }
+        Ok(deleted)
+    }
+
+    /// The collections in the vector store of the embedding indexes of a
+    /// repository. After a reindex, the generation before the current one is
+    /// kept until the next reindex, so it is a collection of the index too.
+    /// The other generations are only listed when they are still in the
+    /// vector store, left behind by a reindex which failed part way.
+    #[tracing::instrument(skip(self))]
+    pub async fn collections_of_indexes(
+        &self,
+        namespace: &str,
+        repository: &str,
+        indexes: &[String],
+    ) -> Result<Vec<String>> {
+        let mut collections = Vec::new();
+        for index in indexes {
+            let index_info = self
+                .repository
+                .get_index(namespace, index, repository)
+                .await?;
+            if index_info.index_type != "embedding" {
+                continue;
+            }
+            let current = IndexSnapshot::from(&index_info);
+            let previous = vector_index_name(namespace, repository, index, current.generation - 1);
+            for collection in vector_collections::collections_of_index(namespace, &current) {
+                let kept = collection == current.vector_index_name ||
+                    (current.generation > 0 && collection == previous);
+                if kept || self.vector_db.num_vectors(&collection).await.is_ok() {
+                    collections.push(collection);
+                }
+            }
+        }
+        Ok(collections)
+    }
+
+    /// Drops collections from the vector store. Collections which do not
+    /// exist are ignored.
+    #[tracing::instrument(skip(self))]
+    pub async fn drop_collections(&self, collections: &[String]) -> Result<()> {
+        for collection in collections {
+            self.vector_db.drop_index(collection.clone()).await?;
+            self.created_collections.lock().unwrap().remove(collection);
+        }
+        Ok(())
+    }
+
+    /// Rebuilds an index into a new generation from the chunks of the current
+    /// generation and flips the index to it. Queries read the current
+    /// generation until the flip, and the generation before the current one
+    /// is dropped once the flip is done, so queries still in flight on the
+    /// current generation can finish.
+    pub async fn reindex(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index: &str,
+    ) -> Result<IndexSnapshot> {
+        let index_info = self
+            .repository
+            .get_index(namespace, index, repository)
+            .await?;
+        let current = IndexSnapshot::from(&index_info);
+        let schema: EmbeddingSchema = serde_json::from_value(index_info.index_schema.clone())
+            .map_err(|e| anyhow!("index: {} is not an embedding index: {}", index, e))?;
+        let next = IndexSnapshot {
+            repository: current.repository.clone(),
+            index_name: current.index_name.clone(),
+            vector_index_name: vector_index_name(
+                namespace,
+                repository,
+                index,
+                current.generation + 1,
+            ),
+            generation: current.generation + 1,
+        };
+        self.vector_db
+            .create_index(create_index_params(&next.vector_index_name, &schema))
+            .await?;
+        let params = self.index_params(&index_info).await;
+        self.copy_chunks(
+            namespace,
+            &index_info.extractor_name,
+            params.as_ref(),
+            &schema,
+            &current,
+            &next,
+        )
+        .await?;
+        // The first queries on the new generation should not pay for loading
+        // it, but a generation which is not warm can still serve them.
+        if let Err(err) = self.vector_db.warm_index(&next.vector_index_name).await {
+            warn!(
+                "unable to warm up generation {} of index: {}: {}",
+                next.generation,
+                index,
+                err.to_string()
+            );
+        }
+        let next = self
+            .repository
+            .flip_index_generation(namespace, repository, &current, &next.vector_index_name)
+            .await?;
+        // Chunks added to the current generation while it was being copied
+        self.copy_chunks(
+            namespace,
+            &index_info.extractor_name,
+            params.as_ref(),
+            &schema,
+            &current,
+            &next,
+        )
+        .await?;
+
+        if current.generation > 0 {
+            let previous = IndexSnapshot {
+                repository: current.repository.clone(),
+                index_name: current.index_name.clone(),
+                vector_index_name: vector_index_name(
+                    namespace,
+                    repository,
+                    index,
+                    current.generation - 1,
+                ),
+                generation: current.generation - 1,
+            };
+            self.drop_collections(std::slice::from_ref(&previous.vector_index_name))
+                .await?;
+            self.repository
+                .delete_chunks_in_snapshot(namespace, &previous)
+                .await?;
+        }
+        info!(
+            "reindexed index: {} of repository: {} to generation {}",
+            index, repository, next.generation
+        );
+        Ok(next)
+    }
+
+    /// Loads the current generation of an index in the vector store and runs
+    /// a query through its extractor, so that neither the index nor the
+    /// embedding model are loaded by the first searches after a deploy or a
+    /// reindex. The extractor is warmed up on a best effort basis, searches
+    /// can be served without it.
+    #[tracing::instrument(skip(self))]
+    pub async fn warm_index(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index: &str,
+    ) -> Result<IndexWarmup> {
+        let started = Instant::now();
+        let index_info = self
+            .repository
+            .get_index(namespace, index, repository)
+            .await?;
+        serde_json::from_value::<EmbeddingSchema>(index_info.index_schema.clone())
+            .map_err(|e| anyhow!("index: {} is not an embedding index: {}", index, e))?;
+        let snapshot = self.used_snapshot(namespace, &index_info).await?;
+        self.vector_db
+            .warm_index(&snapshot.vector_index_name)
+            .await?;
+        let vectors = self
+            .vector_db
+            .num_vectors(&snapshot.vector_index_name)
+            .await?;
+        let params = self.index_params(&index_info).await;
+        let extractor_warmed = match self
+            .extract_feature(
+                &index_info.extractor_name,
+                params.as_ref(),
+                text_content(WARMUP_QUERY),
+            )
+            .await
+        {
+            Ok(_) => true,
+            Err(err) => {
+                warn!(
+                    "unable to warm up extractor: {} of index: {}: {}",
+                    &index_info.extractor_name,
+                    index,
+                    err.to_string()
+                );
+                false
+            }
+        };
+        info!(
+            "warmed up index: {} of repository: {} with {} vectors",
+            index, repository, vectors
+        );
+        Ok(IndexWarmup {
+            snapshot,
+            vectors,
+            extractor_warmed,
+            elapsed: started.elapsed(),
+        })
+    }
+
+    /// Adds chunks replicated from another deployment to the current
+    /// generation of an index. The embeddings of the chunks are computed
+    /// again with the extractor of the index when `embed` is set, otherwise
+    /// the chunks are only found by keyword searches.
+    #[tracing::instrument(skip(self, chunks))]
+    pub async fn replicate_chunks(
+        &self,
+        namespace: &str,
+        repository: &str,
+        index: &str,
+        chunks: Vec<Chunk>,
+        embed: bool,
+    ) -> Result<()> {
+        let index_info = self
+            .repository
+            .get_index(namespace, index, repository)
+            .await?;
+        let snapshot = self.used_snapshot(namespace, &index_info).await?;
+        let embeddings = if embed {
+            let params = self.index_params(&index_info).await;
+            let embeddings = self
+                .embed_chunks(
+                    &index_info.extractor_name,
+                    params.as_ref(),
+                    is_multi_vector(&index_info),
+                    &chunks,
+                )
+                .await?;
+            self.with_chunk_payload(
+                namespace,
+                repository,
+                &payload_indexes(&index_info),
+                embeddings,
+            )
+            .await?
+        } else {
+            ChunkEmbeddings::Single(Vec::new())
+        };
+        self.repository
+            .create_chunks(namespace, chunks, &snapshot)
+            .await?;
+        self.add_chunk_embeddings(&snapshot.vector_index_name, embeddings)
+            .await
+    }
+
+    /// Embeds the chunks which are not parent chunks with the extractor,
+    /// single embeddings through the embedding cache.
+    async fn embed_chunks(
+        &self,
+        extractor_name: &str,
+        params: Option<&serde_json::Value>,
+        multi_vector: bool,
+        chunks: &[Chunk],
+    ) -> Result<ChunkEmbeddings> {
+        let chunks = chunks.iter().filter(|chunk| !chunk.is_parent);
+        if multi_vector {
+            let mut multi_vector_chunks = Vec::new();
+            for chunk in chunks {
+                let vectors = self
+                    .embed_text_vectors(extractor_name, params, &chunk.text)
+                    .await?;
+                multi_vector_chunks.push(MultiVectorChunk {
+                    chunk_id: chunk.chunk_id.clone(),
+                    content_id: chunk.content_id.clone(),
+                    vectors,
+                });
+            }
+            return Ok(ChunkEmbeddings::Multi(multi_vector_chunks));
+        }
+        let mut vector_chunks = Vec::new();
+        for chunk in chunks {
+            let embedding = self
+                .cached_embedding(extractor_name, params, &chunk.text)
+                .await?;
+            vector_chunks.push(VectorChunk::new(
+                chunk.chunk_id.clone(),
+                chunk.content_id.clone(),
+                embedding,
+            ));
+        }
+        Ok(ChunkEmbeddings::Single(vector_chunks))
+    }
+
+    /// Sets the metadata of chunks to the fields of the metadata of their
+    /// content which have payload indexes, so that searches can be filtered by
+    /// them. Values which don't have the type of their payload index are left
+    /// out, searches filtered by the field never return their chunks.
+    async fn with_payload(
+        &self,
+        namespace: &str,
+        repository: &str,
+        payload_indexes: &[PayloadIndex],
+        chunks: Vec<VectorChunk>,
+    ) -> Result<Vec<VectorChunk>> {
+        if payload_indexes.is_empty() || chunks.is_empty() {
+            return Ok(chunks);
+        }
+        let mut content_ids: Vec<String> = chunks.iter().map(|c| c.content_id.clone()).collect();
+        content_ids.sort();
+        content_ids.dedup();
+        let payloads: HashMap<String, serde_json::Map<String, serde_json::Value>> = self
+            .repository
+            .content_with_ids(namespace, repository, &content_ids)
+            .await?
+            .into_iter()
+            .map(|content| {
+                let payload = payload_indexes
+                    .iter()
+                    .filter_map(|payload_index| {
+                        let value = content.metadata.get(&payload_index.field)?;
+                        let value = payload_index.value_of(value)?;
+                        Some((payload_index.field.clone(), value.into()))
+                    })
+                    .collect();
+                (content.id, payload)
+            })
+            .collect();
+        Ok(chunks
+            .into_iter()
+            .map(|chunk| match payloads.get(&chunk.content_id) {
+                Some(payload) => chunk.with_metadata(payload.clone()),
+                None => chunk,
+            })
+            .collect())
+    }
+
+    /// `with_payload` for the embeddings of chunks. Multi-vector indexes have
+    /// no payload indexes.
+    async fn with_chunk_payload(
+        &self,
+        namespace: &str,
+        repository: &str,
+        payload_indexes: &[PayloadIndex],
+        embeddings: ChunkEmbeddings,
+    ) -> Result<ChunkEmbeddings> {
+        match embeddings {
+            ChunkEmbeddings::Single(chunks) => Ok(ChunkEmbeddings::Single(
+                self.with_payload(namespace, repository, payload_indexes, chunks)
+                    .await?,
+            )),
+            embeddings => Ok(embeddings),
+        }
+    }
+
+    async fn add_chunk_embeddings(
+        &self,
+        collection: &str,
+        embeddings: ChunkEmbeddings,
+    ) -> Result<()> {
+        if embeddings.is_empty() {
+            return Ok(());
+        }
+        match embeddings {
+            ChunkEmbeddings::Single(chunks) => {
+                self.vector_db.add_embedding(collection, chunks).await?
+            }
+            ChunkEmbeddings::Multi(chunks) => {
+                self.vector_db.add_multi_vectors(collection, chunks).await?
+            }
+        }
+        Ok(())
+    }
+
+    async fn copy_chunks(
+        &self,
+        namespace: &str,
+        extractor_name: &str,
+        params: Option<&serde_json::Value>,
+        schema: &EmbeddingSchema,
+        from: &IndexSnapshot,
+        to: &IndexSnapshot,
+    ) -> Result<()> {
+        let copied: HashSet<String> = self
+            .repository
+            .chunks_in_snapshot(namespace, to)
+            .await?
+            .into_iter()
+            .map(|chunk| chunk.chunk_id)
+            .collect();
+        let chunks: Vec<Chunk> = self
+            .repository
+            .chunks_in_snapshot(namespace, from)
+            .await?
+            .into_iter()
+            .filter(|chunk| !copied.contains(&chunk.chunk_id))
+            .collect();
+        if chunks.is_empty() {
+            return Ok(());
+        }
+        // Parent chunks are copied without being embedded.
+        let embeddings = self
+            .embed_chunks(extractor_name, params, schema.multi_vector, &chunks)
+            .await?;
+        let embeddings = self
+            .with_chunk_payload(
+                namespace,
+                &to.repository,
+                &schema.payload_indexes,
+                embeddings,
+            )
+            .await?;
+        self.repository.create_chunks(namespace, chunks, to).await?;
+        self.add_chunk_embeddings(&to.vector_index_name, embeddings)
+            .await
     }
 }
 
@@ -159,7 +1292,15 @@ mod tests {
     use crate::{
         blob_storage::BlobStorageBuilder,
         data_repository_manager::DataRepositoryManager,
-        persistence::{ContentPayload, DataRepository, ExtractorBinding},
+        persistence::{
+            ContentPayload,
+            DataRepository,
+            DedupPolicy,
+            ExtractorBinding,
+            RetentionPolicy,
+            TextAnalysisConfig,
+            DEFAULT_NAMESPACE,
+        },
         test_util,
         test_util::db_utils::{
             create_index_manager,
@@ -181,9 +1322,13 @@ mod tests {
             DataRepositoryManager::new_with_db(db.clone(), index_manager.clone(), blob_storage);
         let _ = repository_manager
             .create(&DataRepository {
+                namespace: DEFAULT_NAMESPACE.into(),
                 name: DEFAULT_TEST_REPOSITORY.into(),
                 data_connectors: vec![],
                 metadata: HashMap::new(),
+                dedup_policy: DedupPolicy::default(),
+                retention: RetentionPolicy::default(),
+                text_analysis: TextAnalysisConfig::default(),
                 extractor_bindings: vec![ExtractorBinding::new(
                     "test_extractor_binding",
                     DEFAULT_TEST_REPOSITORY,
@@ -191,11 +1336,13 @@ mod tests {
                     vec![],
                     serde_json::json!({"a": 1, "b": "hello"}),
                 )],
+                version: 0,
             })
             .await;
 
         repository_manager
             .add_texts(
+                DEFAULT_NAMESPACE,
                 DEFAULT_TEST_REPOSITORY,
                 vec![
                     ContentPayload::from_text(
@@ -215,6 +1362,7 @@ mod tests {
             .unwrap();
         repository_manager
             .add_texts(
+                DEFAULT_NAMESPACE,
                 DEFAULT_TEST_REPOSITORY,
                 vec![ContentPayload::from_text(
                     DEFAULT_TEST_REPOSITORY,
@@ -6,12 +6,15 @@ use sea_orm::{DatabaseConnection, FromQueryResult};
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumString};
 use thiserror::Error;
+use tracing::warn;
 
 use crate::server_config::{IndexStoreKind, VectorIndexConfig};
 
 pub mod open_search;
 pub mod pg_vector;
+pub mod pool;
 pub mod qdrant;
+pub mod resilience;
 
 use qdrant::QdrantDb;
 
@@ -32,6 +35,77 @@ pub enum IndexDistance {
     Euclidean,
 }
 
+/// How the vectors of an index are compressed by the vector database. The
+/// smaller vectors keep more of the index in memory, which makes searches of
+/// large indexes faster, at the cost of some recall.
+#[derive(Display, Debug, Clone, PartialEq, EnumString, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Quantization {
+    /// Every dimension as an 8 bit integer, or a 16 bit float where the
+    /// vector database has no 8 bit integers.
+    Scalar,
+
+    /// Groups of dimensions as the code of their nearest centroid.
+    Product,
+}
+
+/// The type of a field of the metadata of content indexed in the vector
+/// database.
+#[derive(Display, Debug, Clone, Copy, PartialEq, EnumString, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum PayloadFieldType {
+    Keyword,
+    Integer,
+    Float,
+}
+
+/// A field of the metadata of content which the vector database indexes
+/// along with the embeddings of the content, so that searches filtered by the
+/// field do not scan the collection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PayloadIndex {
+    pub field: String,
+    pub field_type: PayloadFieldType,
+}
+
+impl PayloadIndex {
+    /// The value of the field in a metadata value, when it has the type of
+    /// the index.
+    pub fn value_of(&self, value: &serde_json::Value) -> Option<PayloadValue> {
+        match self.field_type {
+            PayloadFieldType::Keyword => value.as_str().map(|v| PayloadValue::Keyword(v.into())),
+            PayloadFieldType::Integer => value.as_i64().map(PayloadValue::Integer),
+            PayloadFieldType::Float => value.as_f64().map(PayloadValue::Float),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PayloadValue {
+    Keyword(String),
+    Integer(i64),
+    Float(f64),
+}
+
+impl From<PayloadValue> for serde_json::Value {
+    fn from(value: PayloadValue) -> Self {
+        match value {
+            PayloadValue::Keyword(v) => v.into(),
+            PayloadValue::Integer(v) => v.into(),
+            PayloadValue::Float(v) => v.into(),
+        }
+    }
+}
+
+/// Chunks of content whose metadata field has the value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataMatch {
+    pub field: String,
+    pub value: PayloadValue,
+}
+
 /// A request to create a new vector index in the vector database.
 #[derive(Clone, Debug)]
 pub struct CreateIndexParams {
@@ -40,6 +114,12 @@ pub struct CreateIndexParams {
     pub distance: IndexDistance,
     // TODO: Probably better if this is a HashMap<String, String> (?), or a generic (?)
     pub unique_params: Option<Vec<String>>,
+    pub quantization: Option<Quantization>,
+    /// The index holds several vectors for every chunk, see
+    /// [`VectorDb::search_multi_vector`].
+    pub multi_vector: bool,
+    /// The fields of the metadata of chunks the index can be filtered by.
+    pub payload_indexes: Vec<PayloadIndex>,
 }
 
 #[derive(Debug, Default, Clone, FromQueryResult)]
@@ -66,6 +146,30 @@ pub enum VectorDbError {
 
     #[error("error reading from index: `{0}`")]
     IndexNotRead(String),
+
+    #[error("unsupported operation: `{0}`")]
+    Unsupported(String),
+
+    /// The vector store failed too many calls in a row, and calls fail
+    /// without reaching it until it is tried again.
+    #[error("vector store `{store}` is unavailable, retry in {retry_after_secs}s")]
+    Unavailable {
+        store: String,
+        retry_after_secs: u64,
+    },
+}
+
+impl VectorDbError {
+    /// The seconds after which calls reach the vector store again, when the
+    /// error says it is unavailable.
+    pub fn unavailable_for(err: &anyhow::Error) -> Option<u64> {
+        match err.downcast_ref::<VectorDbError>() {
+            Some(VectorDbError::Unavailable {
+                retry_after_secs, ..
+            }) => Some(*retry_after_secs),
+            _ => None,
+        }
+    }
 }
 
 pub type VectorDBTS = Arc<dyn VectorDb + Sync + Send>;
@@ -73,16 +177,63 @@ pub type VectorDBTS = Arc<dyn VectorDb + Sync + Send>;
 #[derive(Debug, Clone)]
 pub struct VectorChunk {
     pub chunk_id: String,
+    pub content_id: String,
     // TODO should rename this to "embedding"
     pub embeddings: Vec<f32>,
+    /// The values of the fields of the metadata of the content which have a
+    /// payload index.
+    pub metadata: serde_json::Map<String, serde_json::Value>,
 }
 impl VectorChunk {
-    pub fn new(chunk_id: String, embeddings: Vec<f32>) -> Self {
+    pub fn new(chunk_id: String, content_id: String, embeddings: Vec<f32>) -> Self {
         Self {
             chunk_id,
+            content_id,
             embeddings,
+            metadata: serde_json::Map::new(),
         }
     }
+
+    pub fn with_metadata(mut self, metadata: serde_json::Map<String, serde_json::Value>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+}
+
+/// The vectors of a chunk for late interaction, such as the vectors of its
+/// tokens by ColBERT.
+#[derive(Debug, Clone)]
+pub struct MultiVectorChunk {
+    pub chunk_id: String,
+    pub content_id: String,
+    pub vectors: Vec<Vec<f32>>,
+}
+
+/// A point of a collection which was not written by Indexify, with the
+/// payload stored alongside its vector.
+#[derive(Debug, Clone)]
+pub struct StoredPoint {
+    pub id: String,
+    pub payload: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A page of the points of a collection and the offset the next page starts
+/// at, which is `None` after the last page.
+#[derive(Debug, Clone, Default)]
+pub struct ScrollPage {
+    pub points: Vec<StoredPoint>,
+    pub next_offset: Option<String>,
+}
+
+/// Restrictions applied by the vector database while searching, so that only
+/// matching chunks are considered as nearest neighbors.
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilter {
+    /// Only chunks belonging to one of these content ids are returned.
+    pub content_ids: Option<Vec<String>>,
+    /// Only chunks whose metadata matches all of these are returned. The
+    /// fields have payload indexes.
+    pub metadata: Vec<MetadataMatch>,
 }
 
 /// A trait that defines the interface for interacting with a vector database.
@@ -90,7 +241,31 @@ impl VectorChunk {
 /// embeddings.
 #[async_trait]
 pub trait VectorDb {
-    /// Creates a new vector index with the specified configuration.
+    /// Establishes the connection to the vector database ahead of the first
+    /// request and verifies that it is reachable.
+    async fn warmup(&self) -> Result<(), VectorDbError> {
+        Ok(())
+    }
+
+    /// Makes a round trip to the vector database to check that it is
+    /// reachable, for the readiness of the server.
+    async fn health_check(&self) -> Result<(), VectorDbError> {
+        self.warmup().await
+    }
+
+    /// Whether indexes can be created with the quantization.
+    fn supports_quantization(&self, _quantization: &Quantization) -> bool {
+        false
+    }
+
+    /// Whether indexes can be created with payload indexes, and searches
+    /// filtered by the metadata of chunks.
+    fn supports_payload_indexes(&self) -> bool {
+        false
+    }
+
+    /// Creates a new vector index with the specified configuration. Creating
+    /// an index which exists does nothing.
     async fn create_index(&self, index: CreateIndexParams) -> Result<(), VectorDbError>;
 
     /// Adds a vector embedding to the specified index, along with associated
@@ -101,21 +276,89 @@ pub trait VectorDb {
         chunks: Vec<VectorChunk>,
     ) -> Result<(), VectorDbError>;
 
+    /// Whether indexes can hold several vectors for every chunk.
+    fn supports_multi_vector(&self) -> bool {
+        false
+    }
+
+    /// Adds the vectors of chunks to a multi-vector index, in place of the
+    /// vectors the chunks had.
+    async fn add_multi_vectors(
+        &self,
+        index: &str,
+        _chunks: Vec<MultiVectorChunk>,
+    ) -> Result<(), VectorDbError> {
+        Err(VectorDbError::Unsupported(format!(
+            "{} can not store multiple vectors per chunk in index {}",
+            self.name(),
+            index
+        )))
+    }
+
+    /// Searches a multi-vector index for the chunks which best match the
+    /// vectors of a query. A chunk is scored by MaxSim, the sum over the
+    /// query vectors of their highest dot product with a vector of the chunk.
+    async fn search_multi_vector(
+        &self,
+        index: String,
+        _query_vectors: Vec<Vec<f32>>,
+        _k: u64,
+        _filter: SearchFilter,
+    ) -> Result<Vec<SearchResult>, VectorDbError> {
+        Err(VectorDbError::Unsupported(format!(
+            "{} can not search multiple vectors per chunk in index {}",
+            self.name(),
+            index
+        )))
+    }
+
+    /// Deletes the embeddings of chunks from the specified index. Chunks which
+    /// are not in the index are ignored.
+    async fn delete_embeddings(
+        &self,
+        index: &str,
+        chunk_ids: Vec<String>,
+    ) -> Result<(), VectorDbError>;
+
     /// Searches for the nearest neighbors of a query vector in the specified
-    /// index.
+    /// index, considering only the chunks which match the filter.
     async fn search(
         &self,
         index: String,
         query_embedding: Vec<f32>,
         k: u64,
+        filter: SearchFilter,
     ) -> Result<Vec<SearchResult>, VectorDbError>;
 
     /// Deletes the specified vector index from the vector database.
     async fn drop_index(&self, index: String) -> Result<(), VectorDbError>;
 
+    /// Loads an index into the memory of the vector database, such as its
+    /// HNSW graph, so that the first searches on it do not read it from disk.
+    /// Stores which keep their indexes in memory only check that it exists.
+    async fn warm_index(&self, index: &str) -> Result<(), VectorDbError> {
+        self.warmup().await?;
+        self.num_vectors(index).await.map(|_| ())
+    }
+
     /// Returns the number of vectors in the specified index.
     async fn num_vectors(&self, index: &str) -> Result<u64, VectorDbError>;
 
+    /// Reads a page of the points of a collection, starting at `offset`, so
+    /// that collections which were not written by Indexify can be imported.
+    async fn scroll(
+        &self,
+        index: &str,
+        _offset: Option<String>,
+        _limit: u32,
+    ) -> Result<ScrollPage, VectorDbError> {
+        Err(VectorDbError::Unsupported(format!(
+            "{} can not read the points of collection {}",
+            self.name(),
+            index
+        )))
+    }
+
     fn name(&self) -> String;
 }
 
@@ -125,13 +368,32 @@ pub fn create_vectordb(
     postgres_db_conn: DatabaseConnection,
 ) -> Result<VectorDBTS, VectorDbError> {
     match config.index_store {
-        IndexStoreKind::Qdrant => Ok(Arc::new(QdrantDb::new(config.qdrant_config.unwrap()))),
+        IndexStoreKind::Qdrant => Ok(Arc::new(QdrantDb::new_with_pool(
+            config.qdrant_config.unwrap(),
+            &config.pool,
+        ))),
         IndexStoreKind::PgVector => Ok(Arc::new(PgVector::new(
             config.pg_vector_config.unwrap(),
             postgres_db_conn,
         ))),
-        IndexStoreKind::OpenSearchKnn => Ok(Arc::new(OpenSearchKnn::new(
+        IndexStoreKind::OpenSearchKnn => Ok(Arc::new(OpenSearchKnn::new_with_pool(
             config.open_search_basic.unwrap(),
+            &config.pool,
         ))),
     }
 }
+
+/// Warms up the vector database when enabled in the config. Failures are only
+/// logged, since the connection is established again on the first request.
+pub async fn warmup_vectordb(vector_db: &VectorDBTS, config: &VectorIndexConfig) {
+    if !config.pool.warmup {
+        return;
+    }
+    if let Err(err) = vector_db.warmup().await {
+        warn!(
+            "unable to warm up vector store {}: {}",
+            vector_db.name(),
+            err.to_string()
+        );
+    }
+}
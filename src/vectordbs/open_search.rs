@@ -1,8 +1,14 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use opensearch::{
     auth::Credentials,
     cert::CertificateValidation,
-    http::transport::{SingleNodeConnectionPool, TransportBuilder},
+    http::{
+        headers::HeaderMap,
+        transport::{SingleNodeConnectionPool, TransportBuilder},
+        Method,
+    },
     indices::IndicesCreateParts,
     BulkOperation,
     OpenSearch,
@@ -11,27 +17,51 @@ use serde::Deserialize;
 use serde_json::{json, Value};
 use url::Url;
 
-use super::{CreateIndexParams, VectorDb, VectorDbError};
+use super::{
+    pool::{ClientPool, PooledClient},
+    CreateIndexParams,
+    Quantization,
+    VectorDb,
+    VectorDbError,
+};
 use crate::{
-    server_config::OpenSearchBasicConfig,
-    vectordbs::{IndexDistance, SearchResult, VectorChunk},
+    server_config::{OpenSearchBasicConfig, VectorDbPoolConfig},
+    vectordbs::{IndexDistance, SearchFilter, SearchResult, VectorChunk},
 };
 
 pub struct OpenSearchKnn {
-    config: OpenSearchBasicConfig,
+    pool: ClientPool<OpenSearch>,
 }
 
 impl OpenSearchKnn {
-    pub fn new(config: OpenSearchBasicConfig) -> OpenSearchKnn {
-        Self { config }
+    pub fn new_with_pool(
+        config: OpenSearchBasicConfig,
+        pool_config: &VectorDbPoolConfig,
+    ) -> OpenSearchKnn {
+        let pool = ClientPool::new(
+            "open search",
+            pool_config,
+            move || Self::create_client(&config),
+            |client: Arc<OpenSearch>| async move {
+                client
+                    .ping()
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status_code())
+                    .map(|_| ())
+                    .map_err(|e| {
+                        VectorDbError::Internal(format!("open search is unhealthy: {}", e))
+                    })
+            },
+        );
+        Self { pool }
     }
 
-    fn create_client(&self) -> Result<OpenSearch, VectorDbError> {
-        let url = Url::parse(&self.config.addr).map_err(|e| {
+    fn create_client(config: &OpenSearchBasicConfig) -> Result<OpenSearch, VectorDbError> {
+        let url = Url::parse(&config.addr).map_err(|e| {
             VectorDbError::Internal(format!("unable to parse open search url: {}", e))
         })?;
-        let credentials =
-            Credentials::Basic(self.config.username.clone(), self.config.password.clone());
+        let credentials = Credentials::Basic(config.username.clone(), config.password.clone());
         let transport = TransportBuilder::new(SingleNodeConnectionPool::new(url))
             .cert_validation(CertificateValidation::None)
             .auth(credentials)
@@ -41,6 +71,10 @@ impl OpenSearchKnn {
             })?;
         Ok(OpenSearch::new(transport))
     }
+
+    async fn client(&self) -> Result<PooledClient<'_, OpenSearch>, VectorDbError> {
+        self.pool.get().await
+    }
 }
 
 #[async_trait]
@@ -49,9 +83,67 @@ impl VectorDb for OpenSearchKnn {
         "open search".into()
     }
 
+    async fn warmup(&self) -> Result<(), VectorDbError> {
+        self.pool.warmup().await
+    }
+
+    async fn health_check(&self) -> Result<(), VectorDbError> {
+        self.pool.check().await
+    }
+
+    /// Loads the HNSW graphs of the index into the native memory of the k-NN
+    /// plugin with its warmup API.
+    async fn warm_index(&self, index: &str) -> Result<(), VectorDbError> {
+        let response = self
+            .client()
+            .await?
+            .send(
+                Method::Get,
+                &format!("/_plugins/_knn/warmup/{}", index),
+                HeaderMap::new(),
+                Option::<&()>::None,
+                Option::<()>::None,
+                None,
+            )
+            .await;
+        let response = self.pool.checked(response).await.map_err(|e| {
+            VectorDbError::IndexNotRead(format!("unable to warm up opensearch index: {}", e))
+        })?;
+        response.error_for_status_code().map(|_| ()).map_err(|e| {
+            VectorDbError::IndexNotRead(format!("unable to warm up opensearch index: {}", e))
+        })
+    }
+
+    /// Scalar quantization stores the vectors as 16 bit floats with the
+    /// encoder of the faiss engine, which OpenSearch has since 2.13.
+    fn supports_quantization(&self, quantization: &Quantization) -> bool {
+        *quantization == Quantization::Scalar
+    }
+
     async fn create_index(&self, index_params: CreateIndexParams) -> Result<(), VectorDbError> {
+        let space_type = match index_params.distance {
+            IndexDistance::Cosine => "cosinesimil",
+            IndexDistance::Dot => "innerproduct",
+            IndexDistance::Euclidean => "l2",
+        };
+        let method = match index_params.quantization {
+            Some(Quantization::Scalar) => json!({
+                "name": "hnsw",
+                "space_type": space_type,
+                "engine": "faiss",
+                "parameters": {
+                    "encoder": {"name": "sq", "parameters": {"type": "fp16"}}
+                }
+            }),
+            _ => json!({
+                "name": "hnsw",
+                "space_type": space_type,
+                "engine": "nmslib"
+            }),
+        };
         let response = self
-            .create_client()?
+            .client()
+            .await?
             .indices()
             .create(IndicesCreateParts::Index(&index_params.vectordb_index_name))
             .body(json!(
@@ -64,34 +156,30 @@ impl VectorDb for OpenSearchKnn {
                             "embeddings" : {
                                 "type" : "knn_vector",
                                 "dimension" : index_params.vector_dim as i32,
-                                "method": {
-                                    "name": "hnsw",
-                                    "space_type": match index_params.distance {
-                                        IndexDistance::Cosine => "cosinesimil",
-                                        IndexDistance::Dot => "innerproduct",
-                                        IndexDistance::Euclidean => "l2",
-                                    },
-                                    "engine": "nmslib"
-                                }
+                                "method": method
                             }
                         }
                     }
                 }
             ))
             .send()
-            .await
-            .map_err(|e| {
-                VectorDbError::Internal(format!("unable to create opensearch index: {}", e))
-            })?;
-        match response.error_for_status_code() {
-            Ok(_) => Ok(()),
-            Err(e) => {
-                return Err(VectorDbError::Internal(format!(
-                    "unable to create opensearch index: '{}'",
-                    e
-                )))
-            }
+            .await;
+        let response = self.pool.checked(response).await.map_err(|e| {
+            VectorDbError::Internal(format!("unable to create opensearch index: {}", e))
+        })?;
+        if response.status_code().is_success() {
+            return Ok(());
+        }
+        let body = response.text().await.unwrap_or_default();
+        // Creating an index which exists is not an error, as in the other
+        // stores, so that indexes can be created on their first use.
+        if body.contains("resource_already_exists_exception") {
+            return Ok(());
         }
+        Err(VectorDbError::Internal(format!(
+            "unable to create opensearch index: '{}'",
+            body
+        )))
     }
 
     async fn add_embedding(
@@ -104,19 +192,21 @@ impl VectorDb for OpenSearchKnn {
         for vector_chunk in vector_chunks {
             let body = json!({
                 "embeddings": vector_chunk.embeddings,
+                "content_id": vector_chunk.content_id,
             });
             bulk_ops.push(BulkOperation::create(vector_chunk.chunk_id, body).into());
         }
 
         let response = self
-            .create_client()?
+            .client()
+            .await?
             .bulk(opensearch::BulkParts::Index(index_name))
             .body(bulk_ops)
             .send()
-            .await
-            .map_err(|e| {
-                VectorDbError::Internal(format!("unable to add opensearch embeddings: {}", e))
-            })?;
+            .await;
+        let response = self.pool.checked(response).await.map_err(|e| {
+            VectorDbError::Internal(format!("unable to add opensearch embeddings: {}", e))
+        })?;
 
         match response.error_for_status_code() {
             Ok(_) => Ok(()),
@@ -129,30 +219,70 @@ impl VectorDb for OpenSearchKnn {
         }
     }
 
+    async fn delete_embeddings(
+        &self,
+        index_name: &str,
+        chunk_ids: Vec<String>,
+    ) -> Result<(), VectorDbError> {
+        if chunk_ids.is_empty() {
+            return Ok(());
+        }
+        let bulk_ops: Vec<opensearch::BulkOperation<Value>> = chunk_ids
+            .into_iter()
+            .map(|chunk_id| BulkOperation::delete(chunk_id).into())
+            .collect();
+        let response = self
+            .client()
+            .await?
+            .bulk(opensearch::BulkParts::Index(index_name))
+            .body(bulk_ops)
+            .send()
+            .await;
+        let response = self.pool.checked(response).await.map_err(|e| {
+            VectorDbError::Internal(format!("unable to delete opensearch embeddings: {}", e))
+        })?;
+        response.error_for_status_code().map(|_| ()).map_err(|e| {
+            VectorDbError::Internal(format!("unable to delete opensearch embeddings: '{}'", e))
+        })
+    }
+
     async fn search(
         &self,
         index_name: String,
         query_embedding: Vec<f32>,
         k: u64,
+        filter: SearchFilter,
     ) -> Result<Vec<SearchResult>, VectorDbError> {
+        let knn_query = json!({
+            "knn": {
+                "embeddings": {
+                    "vector": query_embedding,
+                    "k": k
+                }
+            }
+        });
+        let query = match filter.content_ids {
+            Some(content_ids) => json!({
+                "bool": {
+                    "must": knn_query,
+                    "filter": { "terms": { "content_id": content_ids } }
+                }
+            }),
+            None => knn_query,
+        };
         let response = self
-            .create_client()?
+            .client()
+            .await?
             .search(opensearch::SearchParts::Index(&[&index_name]))
             .body(json!({
-                "query": {
-                    "knn": {
-                        "embeddings": {
-                            "vector": query_embedding,
-                            "k": k
-                        }
-                    }
-                }
+                "size": k,
+                "query": query
             }))
             .send()
-            .await
-            .map_err(|e| {
-                VectorDbError::Internal(format!("unable to search opensearch embeddings: {}", e))
-            })?;
+            .await;
+        let response = self.pool.checked(response).await.map_err(|e| {
+            VectorDbError::Internal(format!("unable to search opensearch embeddings: {}", e))
+        })?;
 
         let response_body = response.json::<Value>().await.map_err(|e| {
             VectorDbError::Internal(format!("unable to parse opensearch search response: {}", e))
@@ -197,14 +327,15 @@ impl VectorDb for OpenSearchKnn {
 
     async fn drop_index(&self, index: String) -> Result<(), VectorDbError> {
         let response = self
-            .create_client()?
+            .client()
+            .await?
             .indices()
             .delete(opensearch::indices::IndicesDeleteParts::Index(&[&index]))
             .send()
-            .await
-            .map_err(|e| {
-                VectorDbError::Internal(format!("unable to delete opensearch index: {}", e))
-            })?;
+            .await;
+        let response = self.pool.checked(response).await.map_err(|e| {
+            VectorDbError::Internal(format!("unable to delete opensearch index: {}", e))
+        })?;
 
         match response.error_for_status_code() {
             Ok(_) => Ok(()),
@@ -224,13 +355,14 @@ impl VectorDb for OpenSearchKnn {
 
     async fn num_vectors(&self, index: &str) -> Result<u64, VectorDbError> {
         let response = self
-            .create_client()?
+            .client()
+            .await?
             .count(opensearch::CountParts::Index(&[&index]))
             .send()
-            .await
-            .map_err(|e| {
-                VectorDbError::Internal(format!("unable to count opensearch index: {}", e))
-            })?;
+            .await;
+        let response = self.pool.checked(response).await.map_err(|e| {
+            VectorDbError::Internal(format!("unable to count opensearch index: {}", e))
+        })?;
 
         let response_body = response.json::<Value>().await.map_err(|e| {
             VectorDbError::Internal(format!("unable to parse opensearch count response: {}", e))
@@ -254,19 +386,22 @@ mod tests {
     use std::sync::Arc;
 
     use crate::{
-        server_config::OpenSearchBasicConfig,
-        vectordbs::{IndexDistance, VectorChunk, VectorDBTS},
+        server_config::{OpenSearchBasicConfig, VectorDbPoolConfig},
+        vectordbs::{IndexDistance, SearchFilter, VectorChunk, VectorDBTS},
     };
     const TEST_INDEX_NAME: &str = "test_index_name";
 
     use super::{CreateIndexParams, OpenSearchKnn};
 
     fn initialize_opensearch() -> OpenSearchKnn {
-        OpenSearchKnn::new(OpenSearchBasicConfig {
-            addr: "https://localhost:9200".into(),
-            username: "admin".into(),
-            password: "admin".into(),
-        })
+        OpenSearchKnn::new_with_pool(
+            OpenSearchBasicConfig {
+                addr: "https://localhost:9200".into(),
+                username: "admin".into(),
+                password: "admin".into(),
+            },
+            &VectorDbPoolConfig::default(),
+        )
     }
 
     #[tokio::test]
@@ -281,13 +416,13 @@ mod tests {
                 vector_dim: 2,
                 distance: IndexDistance::Cosine,
                 unique_params: None,
+                quantization: None,
+                multi_vector: false,
+                payload_indexes: vec![],
             })
             .await
             .unwrap();
-        let chunk = VectorChunk {
-            chunk_id: "0".into(),
-            embeddings: vec![0., 2.],
-        };
+        let chunk = VectorChunk::new("0".into(), "0".into(), vec![0., 2.]);
         opensearch
             .add_embedding(TEST_INDEX_NAME, vec![chunk])
             .await
@@ -317,7 +452,12 @@ mod tests {
         }
 
         let results = opensearch
-            .search(TEST_INDEX_NAME.into(), vec![10., 8.], 1)
+            .search(
+                TEST_INDEX_NAME.into(),
+                vec![10., 8.],
+                1,
+                SearchFilter::default(),
+            )
             .await
             .unwrap();
         assert_eq!(results.len(), 1);
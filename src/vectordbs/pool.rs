@@ -0,0 +1,203 @@
+use std::{
+    future::Future,
+    ops::Deref,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::{Mutex, Semaphore, SemaphorePermit};
+use tracing::{info, warn};
+
+use super::VectorDbError;
+use crate::server_config::VectorDbPoolConfig;
+
+type ClientFactory<C> = Box<dyn Fn() -> Result<C, VectorDbError> + Send + Sync>;
+
+type HealthCheckFuture = Pin<Box<dyn Future<Output = Result<(), VectorDbError>> + Send>>;
+
+type HealthCheck<C> = Box<dyn Fn(Arc<C>) -> HealthCheckFuture + Send + Sync>;
+
+struct CachedClient<C> {
+    client: Arc<C>,
+    checked_at: Instant,
+}
+
+/// Keeps a single long lived client to a vector database which is shared by
+/// all the requests, instead of creating a new client for every request.
+///
+/// The client is health checked before it is reused once the health check
+/// interval has elapsed, and is replaced when the check fails. The number of
+/// requests in flight against the backend is bounded by the pool.
+pub struct ClientPool<C> {
+    backend: String,
+    factory: ClientFactory<C>,
+    health_check: HealthCheck<C>,
+    health_check_interval: Duration,
+    cached: Mutex<Option<CachedClient<C>>>,
+    permits: Semaphore,
+}
+
+/// A client checked out of the pool. The concurrency permit is released when
+/// this is dropped.
+pub struct PooledClient<'a, C> {
+    client: Arc<C>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl<'a, C> Deref for PooledClient<'a, C> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        &self.client
+    }
+}
+
+impl<C: Send + Sync + 'static> ClientPool<C> {
+    pub fn new<F, H, Fut>(
+        backend: &str,
+        config: &VectorDbPoolConfig,
+        factory: F,
+        health_check: H,
+    ) -> Self
+    where
+        F: Fn() -> Result<C, VectorDbError> + Send + Sync + 'static,
+        H: Fn(Arc<C>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), VectorDbError>> + Send + 'static,
+    {
+        Self {
+            backend: backend.to_string(),
+            factory: Box::new(factory),
+            health_check: Box::new(move |client| Box::pin(health_check(client))),
+            health_check_interval: Duration::from_secs(config.health_check_interval_secs),
+            cached: Mutex::new(None),
+            permits: Semaphore::new(config.max_concurrent_requests.max(1)),
+        }
+    }
+
+    /// Checks out the shared client, waiting while the backend is at its
+    /// concurrency limit.
+    pub async fn get(&self) -> Result<PooledClient<'_, C>, VectorDbError> {
+        let permit = self.permits.acquire().await.map_err(|e| {
+            VectorDbError::Internal(format!("{} client pool is closed: {}", self.backend, e))
+        })?;
+        let mut cached = self.cached.lock().await;
+        if let Some(entry) = cached.as_mut() {
+            if entry.checked_at.elapsed() < self.health_check_interval {
+                return Ok(PooledClient {
+                    client: entry.client.clone(),
+                    _permit: permit,
+                });
+            }
+            match (self.health_check)(entry.client.clone()).await {
+                Ok(()) => {
+                    entry.checked_at = Instant::now();
+                    return Ok(PooledClient {
+                        client: entry.client.clone(),
+                        _permit: permit,
+                    });
+                }
+                Err(err) => {
+                    warn!(
+                        "{} client failed health check, reconnecting: {}",
+                        self.backend,
+                        err.to_string()
+                    );
+                }
+            }
+        }
+        let client = Arc::new((self.factory)()?);
+        cached.replace(CachedClient {
+            client: client.clone(),
+            checked_at: Instant::now(),
+        });
+        Ok(PooledClient {
+            client,
+            _permit: permit,
+        })
+    }
+
+    /// Creates the client if needed and makes a round trip to the backend so
+    /// that the first search does not pay for establishing the connection.
+    pub async fn warmup(&self) -> Result<(), VectorDbError> {
+        self.check().await?;
+        info!("{} client pool is warm", self.backend);
+        Ok(())
+    }
+
+    /// Makes a round trip to the backend with the shared client.
+    pub async fn check(&self) -> Result<(), VectorDbError> {
+        let client = self.get().await?;
+        (self.health_check)(client.client.clone()).await
+    }
+
+    /// Drops the shared client so that the next request creates a new one.
+    pub async fn invalidate(&self) {
+        self.cached.lock().await.take();
+    }
+
+    /// Passes the result of a request with the shared client through. The
+    /// client is dropped when the request failed, since its connection may be
+    /// broken, rather than reused until its next health check.
+    pub async fn checked<T, E>(&self, result: Result<T, E>) -> Result<T, E> {
+        if result.is_err() {
+            self.invalidate().await;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_client_is_reused() {
+        let created = Arc::new(AtomicUsize::new(0));
+        let created_clone = created.clone();
+        let pool = ClientPool::new(
+            "test",
+            &VectorDbPoolConfig::default(),
+            move || Ok(created_clone.fetch_add(1, Ordering::SeqCst)),
+            |_| async { Ok(()) },
+        );
+        pool.warmup().await.unwrap();
+        assert_eq!(*pool.get().await.unwrap(), 0);
+        assert_eq!(*pool.get().await.unwrap(), 0);
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+
+        pool.invalidate().await;
+        assert_eq!(*pool.get().await.unwrap(), 1);
+
+        assert_eq!(pool.checked(Ok::<_, ()>(())).await, Ok(()));
+        assert_eq!(*pool.get().await.unwrap(), 1);
+        assert_eq!(pool.checked(Err::<(), _>("broken")).await, Err("broken"));
+        assert_eq!(*pool.get().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_client_is_replaced() {
+        let config = VectorDbPoolConfig {
+            health_check_interval_secs: 0,
+            ..Default::default()
+        };
+        let created = Arc::new(AtomicUsize::new(0));
+        let created_clone = created.clone();
+        let pool = ClientPool::new(
+            "test",
+            &config,
+            move || Ok(created_clone.fetch_add(1, Ordering::SeqCst)),
+            |client: Arc<usize>| async move {
+                if *client == 0 {
+                    return Err(VectorDbError::Internal("unhealthy".into()));
+                }
+                Ok(())
+            },
+        );
+        assert_eq!(*pool.get().await.unwrap(), 0);
+        assert_eq!(*pool.get().await.unwrap(), 1);
+        assert_eq!(*pool.get().await.unwrap(), 1);
+    }
+}
@@ -1,14 +1,30 @@
-use std::collections::HashMap;
+use std::fmt;
 
 use async_trait::async_trait;
 use qdrant_client::{
     client::{Payload, QdrantClient, QdrantClientConfig},
     qdrant::{
+        point_id::PointIdOptions,
+        points_selector::PointsSelectorOneOf,
+        quantization_config,
         vectors_config::Config,
         with_payload_selector::SelectorOptions,
+        CompressionRatio,
+        Condition,
         CreateCollection,
         Distance,
+        FieldType,
+        Filter,
+        PointId,
         PointStruct,
+        PointsIdsList,
+        PointsSelector,
+        ProductQuantization,
+        QuantizationConfig,
+        QuantizationType,
+        Range,
+        ScalarQuantization,
+        ScrollPoints,
         SearchPoints,
         VectorParams,
         VectorsConfig,
@@ -18,47 +34,107 @@ use qdrant_client::{
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
-use super::{CreateIndexParams, VectorDb, VectorDbError};
+use super::{
+    pool::{ClientPool, PooledClient},
+    CreateIndexParams,
+    MetadataMatch,
+    PayloadFieldType,
+    PayloadValue,
+    Quantization,
+    ScrollPage,
+    StoredPoint,
+    VectorDb,
+    VectorDbError,
+};
 use crate::{
-    server_config::QdrantConfig,
-    vectordbs::{IndexDistance, SearchResult, VectorChunk},
+    server_config::{QdrantConfig, VectorDbPoolConfig},
+    vectordbs::{IndexDistance, SearchFilter, SearchResult, VectorChunk},
 };
 
 fn hex_to_u64(hex: &str) -> Result<u64, std::num::ParseIntError> {
     u64::from_str_radix(hex, 16)
 }
 
-#[allow(dead_code)]
+/// Qdrant only accepts integers and UUIDs as the ids of points. Hex chunk ids,
+/// from the hash and snowflake id strategies, are stored as integers and the
+/// UUIDs as they are.
+fn point_id(chunk_id: &str) -> PointId {
+    match hex_to_u64(chunk_id) {
+        Ok(id) => id.into(),
+        Err(_) => chunk_id.to_string().into(),
+    }
+}
+
 fn u64_to_hex(number: u64) -> String {
     format!("{:x}", number)
 }
 
-#[derive(Debug)]
+/// The chunk id of a point, the inverse of `point_id`.
+fn chunk_id(point_id: &PointId) -> Option<String> {
+    match point_id.point_id_options.as_ref()? {
+        PointIdOptions::Num(id) => Some(u64_to_hex(*id)),
+        PointIdOptions::Uuid(id) => Some(id.clone()),
+    }
+}
+
 pub struct QdrantDb {
     qdrant_config: QdrantConfig,
+    pool: ClientPool<QdrantClient>,
+}
+
+impl fmt::Debug for QdrantDb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QdrantDb")
+            .field("qdrant_config", &self.qdrant_config)
+            .finish()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct QdrantPayload {
+    /// Points of collections imported into Indexify have no chunk id in their
+    /// payload, their chunk id is the id of the point.
+    #[serde(default)]
     pub chunk_id: String,
+    #[serde(default)]
+    pub content_id: String,
+    #[serde(default)]
     pub metadata: serde_json::Value,
 }
 
 impl QdrantDb {
-    pub fn new(config: QdrantConfig) -> QdrantDb {
+    pub fn new_with_pool(config: QdrantConfig, pool_config: &VectorDbPoolConfig) -> QdrantDb {
+        let addr = config.addr.clone();
+        let pool = ClientPool::new(
+            "qdrant",
+            pool_config,
+            move || Self::create_client(&addr),
+            |client: std::sync::Arc<QdrantClient>| async move {
+                client
+                    .health_check()
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| VectorDbError::Internal(format!("qdrant is unhealthy: {}", e)))
+            },
+        );
         Self {
             qdrant_config: config,
+            pool,
         }
     }
 
-    fn create_client(&self) -> Result<QdrantClient, VectorDbError> {
-        let client_config = QdrantClientConfig::from_url(&self.qdrant_config.addr);
+    fn create_client(addr: &str) -> Result<QdrantClient, VectorDbError> {
+        let client_config = QdrantClientConfig::from_url(addr);
         let client = QdrantClient::new(Some(client_config)).map_err(|e| {
             VectorDbError::Internal(format!("unable to create a new quadrant index: {}", e))
         })?;
         Ok(client)
     }
 
+    async fn client(&self) -> Result<PooledClient<'_, QdrantClient>, VectorDbError> {
+        self.pool.get().await
+    }
+
     fn to_distance(distance: IndexDistance) -> Distance {
         match distance {
             IndexDistance::Cosine => Distance::Cosine,
@@ -66,6 +142,58 @@ impl QdrantDb {
             IndexDistance::Euclidean => Distance::Euclid,
         }
     }
+
+    /// The quantized vectors are kept in memory and the original vectors of
+    /// the candidates rescore them.
+    fn to_quantization(quantization: &Quantization) -> QuantizationConfig {
+        let quantization = match quantization {
+            Quantization::Scalar => quantization_config::Quantization::Scalar(ScalarQuantization {
+                r#type: QuantizationType::Int8.into(),
+                quantile: Some(0.99),
+                always_ram: Some(true),
+            }),
+            Quantization::Product => {
+                quantization_config::Quantization::Product(ProductQuantization {
+                    compression: CompressionRatio::X16.into(),
+                    always_ram: Some(true),
+                })
+            }
+        };
+        QuantizationConfig {
+            quantization: Some(quantization),
+        }
+    }
+
+    /// The path of a field of the metadata of chunks in their payload.
+    fn metadata_key(field: &str) -> String {
+        format!("metadata.{}", field)
+    }
+
+    fn to_field_type(field_type: PayloadFieldType) -> FieldType {
+        match field_type {
+            PayloadFieldType::Keyword => FieldType::Keyword,
+            PayloadFieldType::Integer => FieldType::Integer,
+            PayloadFieldType::Float => FieldType::Float,
+        }
+    }
+
+    fn to_condition(metadata_match: MetadataMatch) -> Condition {
+        let key = Self::metadata_key(&metadata_match.field);
+        match metadata_match.value {
+            PayloadValue::Keyword(value) => Condition::matches(key, value),
+            PayloadValue::Integer(value) => Condition::matches(key, value),
+            // Floats are matched by a range, as only keywords and integers
+            // can be matched exactly.
+            PayloadValue::Float(value) => Condition::range(
+                key,
+                Range {
+                    gte: Some(value),
+                    lte: Some(value),
+                    ..Default::default()
+                },
+            ),
+        }
+    }
 }
 
 #[async_trait]
@@ -74,32 +202,65 @@ impl VectorDb for QdrantDb {
         "qdrant".into()
     }
 
+    async fn warmup(&self) -> Result<(), VectorDbError> {
+        self.pool.warmup().await
+    }
+
+    async fn health_check(&self) -> Result<(), VectorDbError> {
+        self.pool.check().await
+    }
+
     #[tracing::instrument]
+    fn supports_quantization(&self, _quantization: &Quantization) -> bool {
+        true
+    }
+
+    fn supports_payload_indexes(&self) -> bool {
+        true
+    }
+
     async fn create_index(&self, index: CreateIndexParams) -> Result<(), VectorDbError> {
-        let result = self
-            .create_client()?
+        let client = self.client().await?;
+        let result = client
             .create_collection(&CreateCollection {
-                collection_name: index.vectordb_index_name,
+                collection_name: index.vectordb_index_name.clone(),
                 vectors_config: Some(VectorsConfig {
                     config: Some(Config::Params(VectorParams {
                         on_disk: None,
                         size: index.vector_dim,
                         distance: Self::to_distance(index.distance).into(),
                         hnsw_config: None,
-                        quantization_config: None,
+                        quantization_config: index.quantization.as_ref().map(Self::to_quantization),
                     })),
                 }),
                 ..Default::default()
             })
             .await;
-        if let Err(err) = &result {
-            if err.to_string().contains("already exists") {
-                return Ok(());
+        if let Err(err) = result {
+            if !err.to_string().contains("already exists") {
+                self.pool.invalidate().await;
+                return Err(VectorDbError::IndexNotCreated(err.to_string()));
             }
         }
-        result
-            .map(|_| ())
-            .map_err(|e| VectorDbError::IndexNotCreated(e.to_string()))
+        // Creating a payload index which exists does nothing, so the payload
+        // indexes of a collection which existed are created if they are
+        // missing.
+        for payload_index in &index.payload_indexes {
+            let result = client
+                .create_field_index(
+                    &index.vectordb_index_name,
+                    Self::metadata_key(&payload_index.field),
+                    Self::to_field_type(payload_index.field_type),
+                    None,
+                    None,
+                )
+                .await;
+            self.pool
+                .checked(result)
+                .await
+                .map_err(|e| VectorDbError::IndexNotCreated(e.to_string()))?;
+        }
+        Ok(())
     }
 
     #[tracing::instrument]
@@ -113,42 +274,89 @@ impl VectorDb for QdrantDb {
             let chunk_id = chunk.chunk_id.clone();
             let payload: Payload = json!(QdrantPayload {
                 chunk_id: chunk_id.clone(),
-                metadata: json!(HashMap::<String, String>::new()),
+                content_id: chunk.content_id.clone(),
+                metadata: json!(chunk.metadata),
             })
             .try_into()
             .unwrap();
             points.push(PointStruct::new(
-                hex_to_u64(&chunk_id).unwrap(),
+                point_id(&chunk_id),
                 chunk.embeddings.clone(),
                 payload,
             ));
         }
-        let _result = self
-            .create_client()?
+        let result = self
+            .client()
+            .await?
             .upsert_points(&index, None, points, None)
+            .await;
+        self.pool
+            .checked(result)
             .await
             .map_err(|e| VectorDbError::IndexNotCreated(e.to_string()))?;
         Ok(())
     }
 
+    #[tracing::instrument(skip(chunk_ids))]
+    async fn delete_embeddings(
+        &self,
+        index: &str,
+        chunk_ids: Vec<String>,
+    ) -> Result<(), VectorDbError> {
+        if chunk_ids.is_empty() {
+            return Ok(());
+        }
+        let points = PointsSelector {
+            points_selector_one_of: Some(PointsSelectorOneOf::Points(PointsIdsList {
+                ids: chunk_ids.iter().map(|id| point_id(id)).collect(),
+            })),
+        };
+        let result = self
+            .client()
+            .await?
+            .delete_points(index, None, &points, None)
+            .await;
+        self.pool
+            .checked(result)
+            .await
+            .map_err(|e| VectorDbError::IndexNotWritten(e.to_string()))?;
+        Ok(())
+    }
+
     #[tracing::instrument]
     async fn search(
         &self,
         index: String,
         query_embedding: Vec<f32>,
         k: u64,
+        filter: SearchFilter,
     ) -> Result<Vec<SearchResult>, VectorDbError> {
+        let mut conditions: Vec<Condition> = filter
+            .metadata
+            .into_iter()
+            .map(Self::to_condition)
+            .collect();
+        if let Some(content_ids) = filter.content_ids {
+            conditions.push(Condition::matches("content_id", content_ids));
+        }
+        let filter = (!conditions.is_empty()).then(|| Filter::must(conditions));
         let result = self
-            .create_client()?
+            .client()
+            .await?
             .search_points(&SearchPoints {
                 collection_name: index,
                 vector: query_embedding,
                 limit: k,
+                filter,
                 with_payload: Some(WithPayloadSelector {
                     selector_options: Some(SelectorOptions::Enable(true)),
                 }),
                 ..Default::default()
             })
+            .await;
+        let result = self
+            .pool
+            .checked(result)
             .await
             .map_err(|e| VectorDbError::IndexNotRead(e.to_string()))?;
         let mut documents: Vec<SearchResult> = Vec::new();
@@ -157,9 +365,14 @@ impl VectorDb for QdrantDb {
                 .map_err(|e| VectorDbError::IndexNotRead(e.to_string()))?;
             let qdrant_payload: QdrantPayload = serde_json::from_value(json_value)
                 .map_err(|e| VectorDbError::IndexNotRead(e.to_string()))?;
+            let chunk_id = if qdrant_payload.chunk_id.is_empty() {
+                point.id.as_ref().and_then(chunk_id).unwrap_or_default()
+            } else {
+                qdrant_payload.chunk_id
+            };
             documents.push(SearchResult {
                 confidence_score: point.score,
-                chunk_id: qdrant_payload.chunk_id,
+                chunk_id,
             });
         }
         Ok(documents)
@@ -167,11 +380,12 @@ impl VectorDb for QdrantDb {
 
     #[tracing::instrument]
     async fn drop_index(&self, index: String) -> Result<(), VectorDbError> {
-        let result = self.create_client()?.delete_collection(index.clone()).await;
+        let result = self.client().await?.delete_collection(index.clone()).await;
         if let Err(err) = result {
             if err.to_string().contains("doesn't exist") {
                 return Ok(());
             }
+            self.pool.invalidate().await;
             return Err(VectorDbError::IndexNotDeleted(index, err.to_string()));
         }
         Ok(())
@@ -179,9 +393,10 @@ impl VectorDb for QdrantDb {
 
     #[tracing::instrument]
     async fn num_vectors(&self, index: &str) -> Result<u64, VectorDbError> {
+        let result = self.client().await?.collection_info(index).await;
         let result = self
-            .create_client()?
-            .collection_info(index)
+            .pool
+            .checked(result)
             .await
             .map_err(|e| VectorDbError::IndexNotRead(e.to_string()))?;
         let collection_info = result
@@ -189,6 +404,50 @@ impl VectorDb for QdrantDb {
             .ok_or(VectorDbError::IndexNotRead("index not found".into()))?;
         Ok(collection_info.points_count.unwrap_or_default())
     }
+
+    #[tracing::instrument]
+    async fn scroll(
+        &self,
+        index: &str,
+        offset: Option<String>,
+        limit: u32,
+    ) -> Result<ScrollPage, VectorDbError> {
+        let result = self
+            .client()
+            .await?
+            .scroll(&ScrollPoints {
+                collection_name: index.to_string(),
+                offset: offset.as_deref().map(point_id),
+                limit: Some(limit),
+                with_payload: Some(WithPayloadSelector {
+                    selector_options: Some(SelectorOptions::Enable(true)),
+                }),
+                ..Default::default()
+            })
+            .await;
+        let result = self
+            .pool
+            .checked(result)
+            .await
+            .map_err(|e| VectorDbError::IndexNotRead(e.to_string()))?;
+        let mut points = Vec::new();
+        for point in result.result {
+            let Some(id) = point.id.as_ref().and_then(chunk_id) else {
+                continue;
+            };
+            let payload = match serde_json::to_value(point.payload)
+                .map_err(|e| VectorDbError::IndexNotRead(e.to_string()))?
+            {
+                serde_json::Value::Object(payload) => payload,
+                _ => serde_json::Map::new(),
+            };
+            points.push(StoredPoint { id, payload });
+        }
+        Ok(ScrollPage {
+            points,
+            next_offset: result.next_page_offset.as_ref().and_then(chunk_id),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -197,16 +456,19 @@ mod tests {
 
     use super::{CreateIndexParams, QdrantDb};
     use crate::{
-        server_config::QdrantConfig,
-        vectordbs::{IndexDistance, VectorChunk, VectorDBTS},
+        server_config::{QdrantConfig, VectorDbPoolConfig},
+        vectordbs::{IndexDistance, SearchFilter, VectorChunk, VectorDBTS},
     };
 
     #[tokio::test]
     #[tracing_test::traced_test]
     async fn test_search_basic() {
-        let qdrant: VectorDBTS = Arc::new(QdrantDb::new(QdrantConfig {
-            addr: "http://localhost:6334".into(),
-        }));
+        let qdrant: VectorDBTS = Arc::new(QdrantDb::new_with_pool(
+            QdrantConfig {
+                addr: "http://localhost:6334".into(),
+            },
+            &VectorDbPoolConfig::default(),
+        ));
         qdrant.drop_index("hello-index".into()).await.unwrap();
         qdrant
             .create_index(CreateIndexParams {
@@ -214,20 +476,25 @@ mod tests {
                 vector_dim: 2,
                 distance: IndexDistance::Cosine,
                 unique_params: None,
+                quantization: None,
+                multi_vector: false,
+                payload_indexes: vec![],
             })
             .await
             .unwrap();
-        let chunk = VectorChunk {
-            chunk_id: "0".into(),
-            embeddings: vec![0., 2.],
-        };
+        let chunk = VectorChunk::new("0".into(), "0".into(), vec![0., 2.]);
         qdrant
             .add_embedding("hello-index", vec![chunk])
             .await
             .unwrap();
 
         let results = qdrant
-            .search("hello-index".into(), vec![10., 8.], 1)
+            .search(
+                "hello-index".into(),
+                vec![10., 8.],
+                1,
+                SearchFilter::default(),
+            )
             .await
             .unwrap();
         assert_eq!(results.len(), 1);
@@ -238,9 +505,12 @@ mod tests {
     async fn test_insertion_idempotent() {
         let index_name = "idempotent-index";
         let hash_on = vec!["user_id".to_string(), "url".to_string()];
-        let qdrant: VectorDBTS = Arc::new(QdrantDb::new(QdrantConfig {
-            addr: "http://localhost:6334".into(),
-        }));
+        let qdrant: VectorDBTS = Arc::new(QdrantDb::new_with_pool(
+            QdrantConfig {
+                addr: "http://localhost:6334".into(),
+            },
+            &VectorDbPoolConfig::default(),
+        ));
         qdrant.drop_index(index_name.into()).await.unwrap();
         qdrant
             .create_index(CreateIndexParams {
@@ -248,13 +518,13 @@ mod tests {
                 vector_dim: 2,
                 distance: IndexDistance::Cosine,
                 unique_params: Some(hash_on.clone()),
+                quantization: None,
+                multi_vector: false,
+                payload_indexes: vec![],
             })
             .await
             .unwrap();
-        let chunk = VectorChunk {
-            chunk_id: "0".into(),
-            embeddings: vec![0., 2.],
-        };
+        let chunk = VectorChunk::new("0".into(), "0".into(), vec![0., 2.]);
         qdrant
             .add_embedding(index_name, vec![chunk.clone()])
             .await
@@ -0,0 +1,450 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use tracing::warn;
+
+use super::{
+    CreateIndexParams,
+    MultiVectorChunk,
+    Quantization,
+    ScrollPage,
+    SearchFilter,
+    SearchResult,
+    VectorChunk,
+    VectorDBTS,
+    VectorDb,
+    VectorDbError,
+};
+use crate::server_config::VectorDbResilienceConfig;
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+/// Stops calls from reaching a vector store which failed `failure_threshold`
+/// calls in a row, for `open_for`. Once that has passed calls reach the store
+/// again, and the first of them which fails opens the breaker again, until a
+/// call succeeds.
+#[derive(Debug)]
+struct CircuitBreaker {
+    failure_threshold: u32,
+    open_for: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    fn new(failure_threshold: u32, open_for: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            open_for,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// How long calls are still stopped, when the breaker is open.
+    fn open_for(&self) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        state
+            .open_until
+            .and_then(|open_until| open_until.checked_duration_since(Instant::now()))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = 0;
+        state.open_until = None;
+    }
+
+    /// Records a failed call, returns whether it opened the breaker.
+    fn record_failure(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        if state.consecutive_failures < self.failure_threshold {
+            return false;
+        }
+        state.open_until = Some(Instant::now() + self.open_for);
+        true
+    }
+}
+
+/// Whether an attempt which failed with the error can succeed when it is
+/// made again. The errors of the stores don't say whether they are transient,
+/// so everything apart from the operations a store does not support is
+/// retried.
+fn is_retryable(err: &VectorDbError) -> bool {
+    !matches!(
+        err,
+        VectorDbError::Unsupported(_) | VectorDbError::Unavailable { .. }
+    )
+}
+
+/// The delay before retry `retry`, counted from 0, jittered between half of
+/// it and all of it so that callers which failed together don't retry
+/// together.
+fn backoff(config: &VectorDbResilienceConfig, retry: u32) -> Duration {
+    let delay = config
+        .initial_backoff_ms
+        .saturating_mul(1 << retry.min(16))
+        .min(config.max_backoff_ms);
+    let jitter = (delay as f64 / 2.0 * rand::random::<f64>()) as u64;
+    Duration::from_millis(delay - jitter)
+}
+
+/// Calls a vector store with a timeout on every attempt, retries the attempts
+/// which failed, and fails calls with `VectorDbError::Unavailable` without
+/// reaching the store while its circuit breaker is open, so that work backs
+/// off instead of retrying against a store which is down.
+pub struct ResilientVectorDb {
+    inner: VectorDBTS,
+    config: VectorDbResilienceConfig,
+    breaker: CircuitBreaker,
+}
+
+impl ResilientVectorDb {
+    pub fn wrap(inner: VectorDBTS, config: VectorDbResilienceConfig) -> VectorDBTS {
+        Arc::new(Self {
+            inner,
+            breaker: CircuitBreaker::new(
+                config.failure_threshold,
+                Duration::from_secs(config.open_secs),
+            ),
+            config,
+        })
+    }
+
+    async fn call<T, F, Fut>(&self, operation: &str, attempt: F) -> Result<T, VectorDbError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, VectorDbError>>,
+    {
+        if let Some(remaining) = self.breaker.open_for() {
+            return Err(self.unavailable(remaining));
+        }
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+        let mut retry = 0;
+        let err = loop {
+            let err = match tokio::time::timeout(timeout, attempt()).await {
+                Ok(Ok(result)) => {
+                    self.breaker.record_success();
+                    return Ok(result);
+                }
+                Ok(Err(err)) if !is_retryable(&err) => return Err(err),
+                Ok(Err(err)) => err,
+                Err(_) => VectorDbError::Internal(format!(
+                    "{} of {} timed out after {}ms",
+                    operation,
+                    self.inner.name(),
+                    self.config.timeout_ms
+                )),
+            };
+            if retry >= self.config.max_retries {
+                break err;
+            }
+            warn!(
+                "retrying {} of {}: {}",
+                operation,
+                self.inner.name(),
+                err.to_string()
+            );
+            tokio::time::sleep(backoff(&self.config, retry)).await;
+            retry += 1;
+        };
+        if self.breaker.record_failure() {
+            warn!(
+                "vector store {} is unavailable for {}s, {} failed: {}",
+                self.inner.name(),
+                self.config.open_secs,
+                operation,
+                err.to_string()
+            );
+            return Err(self.unavailable(Duration::from_secs(self.config.open_secs)));
+        }
+        Err(err)
+    }
+
+    fn unavailable(&self, remaining: Duration) -> VectorDbError {
+        VectorDbError::Unavailable {
+            store: self.inner.name(),
+            retry_after_secs: remaining.as_secs_f64().ceil() as u64,
+        }
+    }
+}
+
+#[async_trait]
+impl VectorDb for ResilientVectorDb {
+    async fn warmup(&self) -> Result<(), VectorDbError> {
+        self.inner.warmup().await
+    }
+
+    /// The store is unhealthy while its circuit breaker is open. Otherwise it
+    /// is checked once, health checks are neither retried nor counted by the
+    /// breaker.
+    async fn health_check(&self) -> Result<(), VectorDbError> {
+        if let Some(remaining) = self.breaker.open_for() {
+            return Err(self.unavailable(remaining));
+        }
+        tokio::time::timeout(
+            Duration::from_millis(self.config.timeout_ms),
+            self.inner.health_check(),
+        )
+        .await
+        .map_err(|_| {
+            VectorDbError::Internal(format!(
+                "health check of {} timed out after {}ms",
+                self.inner.name(),
+                self.config.timeout_ms
+            ))
+        })?
+    }
+
+    fn supports_quantization(&self, quantization: &Quantization) -> bool {
+        self.inner.supports_quantization(quantization)
+    }
+
+    fn supports_payload_indexes(&self) -> bool {
+        self.inner.supports_payload_indexes()
+    }
+
+    async fn create_index(&self, index: CreateIndexParams) -> Result<(), VectorDbError> {
+        self.call("create_index", || self.inner.create_index(index.clone()))
+            .await
+    }
+
+    async fn add_embedding(
+        &self,
+        index: &str,
+        chunks: Vec<VectorChunk>,
+    ) -> Result<(), VectorDbError> {
+        self.call("add_embedding", || {
+            self.inner.add_embedding(index, chunks.clone())
+        })
+        .await
+    }
+
+    fn supports_multi_vector(&self) -> bool {
+        self.inner.supports_multi_vector()
+    }
+
+    async fn add_multi_vectors(
+        &self,
+        index: &str,
+        chunks: Vec<MultiVectorChunk>,
+    ) -> Result<(), VectorDbError> {
+        self.call("add_multi_vectors", || {
+            self.inner.add_multi_vectors(index, chunks.clone())
+        })
+        .await
+    }
+
+    async fn search_multi_vector(
+        &self,
+        index: String,
+        query_vectors: Vec<Vec<f32>>,
+        k: u64,
+        filter: SearchFilter,
+    ) -> Result<Vec<SearchResult>, VectorDbError> {
+        self.call("search", || {
+            self.inner
+                .search_multi_vector(index.clone(), query_vectors.clone(), k, filter.clone())
+        })
+        .await
+    }
+
+    async fn delete_embeddings(
+        &self,
+        index: &str,
+        chunk_ids: Vec<String>,
+    ) -> Result<(), VectorDbError> {
+        self.call("delete_embeddings", || {
+            self.inner.delete_embeddings(index, chunk_ids.clone())
+        })
+        .await
+    }
+
+    async fn search(
+        &self,
+        index: String,
+        query_embedding: Vec<f32>,
+        k: u64,
+        filter: SearchFilter,
+    ) -> Result<Vec<SearchResult>, VectorDbError> {
+        self.call("search", || {
+            self.inner
+                .search(index.clone(), query_embedding.clone(), k, filter.clone())
+        })
+        .await
+    }
+
+    async fn drop_index(&self, index: String) -> Result<(), VectorDbError> {
+        self.call("drop_index", || self.inner.drop_index(index.clone()))
+            .await
+    }
+
+    async fn warm_index(&self, index: &str) -> Result<(), VectorDbError> {
+        self.call("warm_index", || self.inner.warm_index(index))
+            .await
+    }
+
+    async fn num_vectors(&self, index: &str) -> Result<u64, VectorDbError> {
+        self.call("num_vectors", || self.inner.num_vectors(index))
+            .await
+    }
+
+    async fn scroll(
+        &self,
+        index: &str,
+        offset: Option<String>,
+        limit: u32,
+    ) -> Result<ScrollPage, VectorDbError> {
+        self.call("scroll", || self.inner.scroll(index, offset.clone(), limit))
+            .await
+    }
+
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// A store whose `num_vectors` fails the first `failures` calls.
+    struct FlakyVectorDb {
+        failures: usize,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl VectorDb for FlakyVectorDb {
+        async fn create_index(&self, _index: CreateIndexParams) -> Result<(), VectorDbError> {
+            Ok(())
+        }
+
+        async fn add_embedding(
+            &self,
+            _index: &str,
+            _chunks: Vec<VectorChunk>,
+        ) -> Result<(), VectorDbError> {
+            Ok(())
+        }
+
+        async fn delete_embeddings(
+            &self,
+            _index: &str,
+            _chunk_ids: Vec<String>,
+        ) -> Result<(), VectorDbError> {
+            Ok(())
+        }
+
+        async fn search(
+            &self,
+            _index: String,
+            _query_embedding: Vec<f32>,
+            _k: u64,
+            _filter: SearchFilter,
+        ) -> Result<Vec<SearchResult>, VectorDbError> {
+            Ok(vec![])
+        }
+
+        async fn drop_index(&self, _index: String) -> Result<(), VectorDbError> {
+            Err(VectorDbError::Unsupported("drop_index".into()))
+        }
+
+        async fn num_vectors(&self, _index: &str) -> Result<u64, VectorDbError> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(VectorDbError::IndexNotRead("connection reset".into()));
+            }
+            Ok(42)
+        }
+
+        fn name(&self) -> String {
+            "flaky".into()
+        }
+    }
+
+    fn flaky(failures: usize) -> Arc<FlakyVectorDb> {
+        Arc::new(FlakyVectorDb {
+            failures,
+            calls: AtomicUsize::new(0),
+        })
+    }
+
+    fn config(max_retries: u32, failure_threshold: u32) -> VectorDbResilienceConfig {
+        VectorDbResilienceConfig {
+            max_retries,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 1,
+            failure_threshold,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_attempts_are_retried() {
+        let inner = flaky(2);
+        let vector_db = ResilientVectorDb::wrap(inner.clone(), config(2, 1));
+        assert_eq!(vector_db.num_vectors("index").await.unwrap(), 42);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+
+        // Unsupported operations are not retried.
+        assert!(matches!(
+            vector_db.drop_index("index".into()).await,
+            Err(VectorDbError::Unsupported(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_breaker_opens_after_consecutive_failures() {
+        let inner = flaky(usize::MAX);
+        let vector_db = ResilientVectorDb::wrap(inner.clone(), config(1, 2));
+        assert!(matches!(
+            vector_db.num_vectors("index").await,
+            Err(VectorDbError::IndexNotRead(_))
+        ));
+        assert!(matches!(
+            vector_db.num_vectors("index").await,
+            Err(VectorDbError::Unavailable {
+                retry_after_secs: 30,
+                ..
+            })
+        ));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 4);
+
+        // Calls don't reach the store while the breaker is open.
+        let err = vector_db.num_vectors("index").await.unwrap_err();
+        assert!(matches!(err, VectorDbError::Unavailable { .. }));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 4);
+        assert_eq!(
+            VectorDbError::unavailable_for(&anyhow::Error::from(err)),
+            Some(30)
+        );
+    }
+
+    #[test]
+    fn test_backoff() {
+        let config = VectorDbResilienceConfig {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1_000,
+            ..Default::default()
+        };
+        for (retry, max) in [(0, 100), (1, 200), (3, 800), (10, 1_000)] {
+            let delay = backoff(&config, retry).as_millis() as u64;
+            assert!(
+                delay >= max / 2 && delay <= max,
+                "{} of retry {}",
+                delay,
+                retry
+            );
+        }
+    }
+}
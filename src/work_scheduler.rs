@@ -0,0 +1,514 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::RwLock,
+    time::Duration,
+};
+
+use tracing::warn;
+
+use crate::{entity::work, persistence::WorkState, server_config::SchedulerConfig};
+
+/// An extractor binding of a repository of a namespace.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BindingKey {
+    pub namespace: String,
+    pub repository: String,
+    pub binding: String,
+}
+
+impl BindingKey {
+    pub fn of(work: &work::Model) -> Self {
+        Self {
+            namespace: work.namespace.clone(),
+            repository: work.repository_id.clone(),
+            binding: work.extractor_binding.clone(),
+        }
+    }
+}
+
+/// How much more work of an extractor binding can be assigned.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BindingLimit {
+    /// The work of a paused binding waits until it is resumed.
+    pub paused: bool,
+    /// The most work of the binding in flight at once.
+    pub max_concurrency: Option<usize>,
+    /// How much work of the binding can still be started in the current
+    /// minute.
+    pub remaining_requests: Option<usize>,
+}
+
+impl BindingLimit {
+    fn allows(&self, in_flight: usize, assigned: usize) -> bool {
+        !self.paused &&
+            self.max_concurrency.is_none_or(|max| in_flight < max) &&
+            self.remaining_requests
+                .is_none_or(|remaining| assigned < remaining)
+    }
+}
+
+/// Assigns waiting work to the executors which have free slots.
+///
+/// The free slots of the executors of an extractor are shared between the
+/// sources of the waiting work in proportion to the weights of the sources.
+/// The work a source already has in flight counts towards its share, so a
+/// source which has taken every slot gets none of the slots freed while other
+/// sources are waiting.
+///
+/// The config can be replaced while the coordinator runs, and applies from
+/// the next allocation.
+#[derive(Debug)]
+pub struct FairWorkScheduler {
+    config: RwLock<SchedulerConfig>,
+}
+
+impl FairWorkScheduler {
+    pub fn new(config: SchedulerConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+        }
+    }
+
+    pub fn reconfigure(&self, config: SchedulerConfig) {
+        *self.config.write().unwrap() = config;
+    }
+
+    pub fn extraction_poll_interval(&self) -> Duration {
+        Duration::from_secs(self.config.read().unwrap().extraction_poll_interval_secs)
+    }
+
+    /// Returns the executor each work is assigned to, keyed by work id. Work
+    /// which is not assigned keeps waiting for a free slot, or for the limits
+    /// of its binding to allow it.
+    pub fn allocate(
+        &self,
+        executors: &HashMap<String, Vec<String>>,
+        waiting: Vec<work::Model>,
+        in_flight: &[work::Model],
+        binding_limits: &HashMap<BindingKey, BindingLimit>,
+    ) -> HashMap<String, String> {
+        let config = self.config.read().unwrap().clone();
+        let mut executor_load: HashMap<String, usize> = HashMap::new();
+        let mut source_in_flight: HashMap<String, usize> = HashMap::new();
+        let mut extractor_source_in_flight: HashMap<(String, String), usize> = HashMap::new();
+        let mut binding_in_flight: HashMap<BindingKey, usize> = HashMap::new();
+        let mut binding_assigned: HashMap<BindingKey, usize> = HashMap::new();
+        for work in in_flight {
+            if let Some(worker_id) = &work.worker_id {
+                *executor_load.entry(worker_id.clone()).or_default() += 1;
+            }
+            *source_in_flight.entry(work.source.clone()).or_default() += 1;
+            *extractor_source_in_flight
+                .entry((work.extractor.clone(), work.source.clone()))
+                .or_default() += 1;
+            *binding_in_flight.entry(BindingKey::of(work)).or_default() += 1;
+        }
+
+        // extractor -> source -> work ids and their bindings, oldest first
+        let mut queues: HashMap<String, BTreeMap<String, VecDeque<(String, BindingKey)>>> =
+            HashMap::new();
+        for work in waiting {
+            let binding = BindingKey::of(&work);
+            queues
+                .entry(work.extractor)
+                .or_default()
+                .entry(work.source)
+                .or_default()
+                .push_back((work.id, binding));
+        }
+
+        let mut assignment = HashMap::new();
+        for (extractor, mut sources) in queues {
+            let Some(executor_ids) = executors.get(&extractor).filter(|e| !e.is_empty()) else {
+                warn!("no executors for extractor: {}", extractor);
+                continue;
+            };
+            while let Some(executor_id) = executor_ids
+                .iter()
+                .filter(|id| {
+                    executor_load.get(*id).copied().unwrap_or_default() <
+                        config.max_work_per_executor
+                })
+                .min_by_key(|id| executor_load.get(*id).copied().unwrap_or_default())
+            {
+                let share = |source: &str| {
+                    extractor_source_in_flight
+                        .get(&(extractor.clone(), source.to_string()))
+                        .copied()
+                        .unwrap_or_default() as u64
+                };
+                // The oldest work of the source whose binding allows more work.
+                let next_work = |queue: &VecDeque<(String, BindingKey)>| {
+                    queue.iter().position(|(_, binding)| {
+                        binding_limits.get(binding).is_none_or(|limit| {
+                            limit.allows(
+                                binding_in_flight.get(binding).copied().unwrap_or_default(),
+                                binding_assigned.get(binding).copied().unwrap_or_default(),
+                            )
+                        })
+                    })
+                };
+                let Some((source, position)) = sources
+                    .iter()
+                    .filter(|(source, _)| {
+                        is_under_limit(
+                            &config,
+                            source,
+                            source_in_flight.get(*source).copied().unwrap_or_default(),
+                        )
+                    })
+                    .filter_map(|(source, queue)| next_work(queue).map(|p| (source, p)))
+                    .min_by(|(a, _), (b, _)| {
+                        compare_shares(share(a), weight(&config, a), share(b), weight(&config, b))
+                    })
+                    .map(|(source, position)| (source.clone(), position))
+                else {
+                    break;
+                };
+                let (work_id, binding) = sources
+                    .get_mut(&source)
+                    .and_then(|queue| queue.remove(position))
+                    .unwrap();
+                *executor_load.entry(executor_id.clone()).or_default() += 1;
+                *source_in_flight.entry(source.clone()).or_default() += 1;
+                *extractor_source_in_flight
+                    .entry((extractor.clone(), source))
+                    .or_default() += 1;
+                *binding_in_flight.entry(binding.clone()).or_default() += 1;
+                *binding_assigned.entry(binding).or_default() += 1;
+                assignment.insert(work_id, executor_id.clone());
+            }
+        }
+        assignment
+    }
+
+    /// Moves work from busy executors to the idle executors of the same
+    /// extractor. Returns the executor each work is moved to, keyed by work
+    /// id.
+    ///
+    /// An executor takes the work assigned to it when it syncs, and performs
+    /// it before it syncs again, so only the work assigned after the last
+    /// sync of its executor is moved, the oldest first. Work is moved until
+    /// the executors which were idle are as loaded as the busy ones.
+    pub fn rebalance(
+        &self,
+        executors: &HashMap<String, Vec<String>>,
+        in_flight: &[work::Model],
+        last_synced: &HashMap<String, u64>,
+    ) -> HashMap<String, String> {
+        let config = self.config.read().unwrap().clone();
+        let mut moves = HashMap::new();
+        if !config.work_stealing {
+            return moves;
+        }
+        let mut executor_load: HashMap<String, usize> = HashMap::new();
+        // (extractor, executor) -> work the executor has not taken, the
+        // oldest last
+        let mut untaken: HashMap<(String, String), Vec<String>> = HashMap::new();
+        let mut in_flight: Vec<&work::Model> = in_flight.iter().collect();
+        in_flight.sort_by_key(|work| Reverse(work.assigned_at));
+        for work in in_flight {
+            let Some(worker_id) = &work.worker_id else {
+                continue;
+            };
+            *executor_load.entry(worker_id.clone()).or_default() += 1;
+            let taken = match (work.assigned_at, last_synced.get(worker_id)) {
+                (Some(assigned_at), Some(last_synced)) => assigned_at as u64 <= *last_synced,
+                _ => true,
+            };
+            if !taken && work.state == WorkState::Pending.to_string() {
+                untaken
+                    .entry((work.extractor.clone(), worker_id.clone()))
+                    .or_default()
+                    .push(work.id.clone());
+            }
+        }
+
+        for (extractor, executor_ids) in executors {
+            let load = |executor_load: &HashMap<String, usize>, id: &String| {
+                executor_load.get(id).copied().unwrap_or_default()
+            };
+            let idle: Vec<&String> = executor_ids
+                .iter()
+                .filter(|id| load(&executor_load, id) == 0)
+                .collect();
+            if idle.is_empty() {
+                continue;
+            }
+            while let Some(to) = idle
+                .iter()
+                .filter(|id| load(&executor_load, id) < config.max_work_per_executor)
+                .min_by_key(|id| load(&executor_load, id))
+            {
+                let Some(from) = executor_ids
+                    .iter()
+                    .filter(|id| {
+                        untaken
+                            .get(&(extractor.clone(), id.to_string()))
+                            .is_some_and(|queue| !queue.is_empty())
+                    })
+                    .max_by_key(|id| load(&executor_load, id))
+                else {
+                    break;
+                };
+                if load(&executor_load, from) <= load(&executor_load, to) + 1 {
+                    break;
+                }
+                let work_id = untaken
+                    .get_mut(&(extractor.clone(), from.clone()))
+                    .and_then(|queue| queue.pop())
+                    .unwrap();
+                *executor_load.entry(from.clone()).or_default() -= 1;
+                *executor_load.entry(to.to_string()).or_default() += 1;
+                moves.insert(work_id, to.to_string());
+            }
+        }
+        moves
+    }
+}
+
+fn weight(config: &SchedulerConfig, source: &str) -> u64 {
+    config
+        .source_weights
+        .get(source)
+        .copied()
+        .unwrap_or(1)
+        .max(1) as u64
+}
+
+fn is_under_limit(config: &SchedulerConfig, source: &str, in_flight: usize) -> bool {
+    config
+        .source_concurrency_limits
+        .get(source)
+        .is_none_or(|limit| in_flight < *limit)
+}
+
+/// Compares `a_count / a_weight` with `b_count / b_weight`.
+fn compare_shares(a_count: u64, a_weight: u64, b_count: u64, b_weight: u64) -> Ordering {
+    (a_count * b_weight).cmp(&(b_count * a_weight))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn work(id: &str, source: &str, worker_id: Option<&str>) -> work::Model {
+        work::Model {
+            id: id.into(),
+            namespace: "default".into(),
+            state: "Pending".into(),
+            worker_id: worker_id.map(|w| w.into()),
+            content_id: id.into(),
+            extractor: "extractor".into(),
+            extractor_binding: "binding".into(),
+            extractor_params: json!({}),
+            repository_id: "repository".into(),
+            source: source.into(),
+            trace_context: None,
+            created_at: 0,
+            assigned_at: None,
+            completed_at: None,
+            items_processed: 0,
+            percent: None,
+            log: None,
+            last_error: None,
+        }
+    }
+
+    fn count_by_source(
+        assignment: &HashMap<String, String>,
+        waiting: &[work::Model],
+    ) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for work in waiting {
+            if assignment.contains_key(&work.id) {
+                *counts.entry(work.source.clone()).or_default() += 1;
+            }
+        }
+        counts
+    }
+
+    #[test]
+    fn test_sources_share_free_slots() {
+        let scheduler = FairWorkScheduler::new(SchedulerConfig {
+            max_work_per_executor: 4,
+            source_weights: HashMap::from([("api".to_string(), 3)]),
+            ..Default::default()
+        });
+        let executors = HashMap::from([(
+            "extractor".to_string(),
+            vec!["executor1".to_string(), "executor2".to_string()],
+        )]);
+        let mut waiting: Vec<work::Model> = (0..100)
+            .map(|i| work(&format!("slack-{}", i), "archive", None))
+            .collect();
+        waiting.extend((0..10).map(|i| work(&format!("upload-{}", i), "api", None)));
+
+        let assignment = scheduler.allocate(&executors, waiting.clone(), &[], &HashMap::new());
+        assert_eq!(assignment.len(), 8);
+        let counts = count_by_source(&assignment, &waiting);
+        assert_eq!(counts["api"], 6);
+        assert_eq!(counts["archive"], 2);
+    }
+
+    #[test]
+    fn test_in_flight_work_counts_towards_share() {
+        let scheduler = FairWorkScheduler::new(SchedulerConfig {
+            max_work_per_executor: 4,
+            ..Default::default()
+        });
+        let executors = HashMap::from([("extractor".to_string(), vec!["executor1".to_string()])]);
+        let in_flight = vec![
+            work("slack-0", "archive", Some("executor1")),
+            work("slack-1", "archive", Some("executor1")),
+        ];
+        let waiting = vec![
+            work("slack-2", "archive", None),
+            work("upload-0", "api", None),
+            work("upload-1", "api", None),
+        ];
+        let assignment =
+            scheduler.allocate(&executors, waiting.clone(), &in_flight, &HashMap::new());
+        let counts = count_by_source(&assignment, &waiting);
+        assert_eq!(counts["api"], 2);
+        assert_eq!(counts.get("archive"), None);
+    }
+
+    #[test]
+    fn test_source_concurrency_limit() {
+        let scheduler = FairWorkScheduler::new(SchedulerConfig {
+            max_work_per_executor: 10,
+            source_concurrency_limits: HashMap::from([("archive".to_string(), 1)]),
+            ..Default::default()
+        });
+        let executors = HashMap::from([("extractor".to_string(), vec!["executor1".to_string()])]);
+        let waiting = vec![
+            work("slack-0", "archive", None),
+            work("slack-1", "archive", None),
+        ];
+        let assignment = scheduler.allocate(&executors, waiting.clone(), &[], &HashMap::new());
+        assert_eq!(assignment.len(), 1);
+
+        scheduler.reconfigure(SchedulerConfig {
+            max_work_per_executor: 10,
+            source_concurrency_limits: HashMap::from([("archive".to_string(), 2)]),
+            ..Default::default()
+        });
+        let assignment = scheduler.allocate(&executors, waiting, &[], &HashMap::new());
+        assert_eq!(assignment.len(), 2);
+    }
+
+    #[test]
+    fn test_binding_limits() {
+        let scheduler = FairWorkScheduler::new(SchedulerConfig {
+            max_work_per_executor: 10,
+            ..Default::default()
+        });
+        let executors = HashMap::from([("extractor".to_string(), vec!["executor1".to_string()])]);
+        let mut openai = work("openai-0", "api", None);
+        openai.extractor_binding = "openai".into();
+        let in_flight = vec![openai.clone()];
+        let mut waiting: Vec<work::Model> = (1..5)
+            .map(|i| {
+                let mut work = openai.clone();
+                work.id = format!("openai-{}", i);
+                work
+            })
+            .collect();
+        waiting.push(work("minilm-0", "api", None));
+        let key = BindingKey::of(&openai);
+
+        let limits = HashMap::from([(
+            key.clone(),
+            BindingLimit {
+                max_concurrency: Some(3),
+                ..Default::default()
+            },
+        )]);
+        let assignment = scheduler.allocate(&executors, waiting.clone(), &in_flight, &limits);
+        // The other binding is not held back by the limit of the first.
+        assert_eq!(assignment.len(), 3);
+        assert!(assignment.contains_key("minilm-0"));
+
+        let limits = HashMap::from([(
+            key.clone(),
+            BindingLimit {
+                remaining_requests: Some(1),
+                ..Default::default()
+            },
+        )]);
+        let assignment = scheduler.allocate(&executors, waiting.clone(), &in_flight, &limits);
+        assert_eq!(assignment.len(), 2);
+        assert!(assignment.contains_key("openai-1"));
+
+        let limits = HashMap::from([(
+            key,
+            BindingLimit {
+                paused: true,
+                ..Default::default()
+            },
+        )]);
+        let assignment = scheduler.allocate(&executors, waiting, &in_flight, &limits);
+        assert_eq!(assignment.keys().collect::<Vec<_>>(), vec!["minilm-0"]);
+    }
+
+    #[test]
+    fn test_rebalance_moves_untaken_work_to_idle_executors() {
+        let scheduler = FairWorkScheduler::new(SchedulerConfig::default());
+        let executors = HashMap::from([
+            (
+                "extractor".to_string(),
+                vec![
+                    "busy".to_string(),
+                    "working".to_string(),
+                    "idle".to_string(),
+                ],
+            ),
+            ("other".to_string(), vec!["other-idle".to_string()]),
+        ]);
+        let assigned = |id: &str, worker_id: &str, assigned_at: i64| {
+            let mut work = work(id, "api", Some(worker_id));
+            work.assigned_at = Some(assigned_at);
+            work
+        };
+        // The busy executor took two works when it synced at 100, and eight
+        // more were assigned to it since.
+        let mut in_flight = vec![
+            assigned("taken-0", "busy", 90),
+            assigned("taken-1", "busy", 100),
+        ];
+        in_flight.extend((0..8).map(|i| assigned(&format!("untaken-{}", i), "busy", 101 + i)));
+        in_flight.push(assigned("working-0", "working", 90));
+        let last_synced = HashMap::from([("busy".to_string(), 100), ("working".to_string(), 100)]);
+
+        let moves = scheduler.rebalance(&executors, &in_flight, &last_synced);
+        let mut moved: Vec<&String> = moves.keys().collect();
+        moved.sort();
+        assert_eq!(
+            moved,
+            vec![
+                "untaken-0",
+                "untaken-1",
+                "untaken-2",
+                "untaken-3",
+                "untaken-4"
+            ]
+        );
+        assert!(moves.values().all(|executor_id| executor_id == "idle"));
+
+        // Work of an executor which did not sync with this coordinator is
+        // left where it is.
+        let moves = scheduler.rebalance(&executors, &in_flight, &HashMap::new());
+        assert!(moves.is_empty());
+
+        scheduler.reconfigure(SchedulerConfig {
+            work_stealing: false,
+            ..Default::default()
+        });
+        let moves = scheduler.rebalance(&executors, &in_flight, &last_synced);
+        assert!(moves.is_empty());
+    }
+}
@@ -3,11 +3,65 @@ use std::{
     sync::{Arc, RwLock},
 };
 
+use anyhow::{anyhow, Result};
+use serde::{de::DeserializeOwned, Serialize};
+
 use crate::internal_api::{Work, WorkStatus};
 
+/// The key of the id of the executor in the default tree of the store.
+const EXECUTOR_ID_KEY: &[u8] = b"executor_id";
+
+/// The work and the statuses of a work store on disk, in a sled database.
+struct DiskStore {
+    db: sled::Db,
+    allocated_work: sled::Tree,
+    completed_work: sled::Tree,
+}
+
+impl DiskStore {
+    fn open(path: &str) -> Result<Self> {
+        let db = sled::open(path)
+            .map_err(|e| anyhow!("unable to open the work store at {}: {}", path, e))?;
+        Ok(Self {
+            allocated_work: db.open_tree("allocated_work")?,
+            completed_work: db.open_tree("completed_work")?,
+            db,
+        })
+    }
+
+    fn load<T: DeserializeOwned>(tree: &sled::Tree) -> Result<HashMap<String, T>> {
+        tree.iter()
+            .map(|entry| {
+                let (key, value) = entry?;
+                Ok((
+                    String::from_utf8_lossy(&key).to_string(),
+                    serde_json::from_slice(&value)?,
+                ))
+            })
+            .collect()
+    }
+
+    fn insert<T: Serialize>(tree: &sled::Tree, id: &str, value: &T) -> Result<()> {
+        tree.insert(id, serde_json::to_vec(value)?)?;
+        Ok(())
+    }
+
+    /// Waits until the writes are on disk, so that they survive a crash.
+    fn flush(&self) -> Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+/// The work assigned to an executor and the statuses of the work it
+/// performed which the coordinator has not been told about yet. With a path,
+/// they are kept on disk as well, so that an executor which restarts resumes
+/// the work assigned to it and reports the work it performed before the
+/// restart, instead of waiting for the work to be assigned again.
 pub struct WorkStore {
     allocated_work: Arc<RwLock<HashMap<String, Work>>>,
     completed_work: Arc<RwLock<HashMap<String, WorkStatus>>>,
+    disk: Option<DiskStore>,
 }
 
 impl WorkStore {
@@ -15,27 +69,106 @@ impl WorkStore {
         Self {
             allocated_work: Arc::new(RwLock::new(HashMap::new())),
             completed_work: Arc::new(RwLock::new(HashMap::new())),
+            disk: None,
+        }
+    }
+
+    /// Opens the work store kept at the path, with the work and statuses it
+    /// had when the executor stopped.
+    pub fn open(path: &str) -> Result<Self> {
+        let disk = DiskStore::open(path)?;
+        Ok(Self {
+            allocated_work: Arc::new(RwLock::new(DiskStore::load(&disk.allocated_work)?)),
+            completed_work: Arc::new(RwLock::new(DiskStore::load(&disk.completed_work)?)),
+            disk: Some(disk),
+        })
+    }
+
+    /// The id the executor had before it restarted, so that the coordinator
+    /// still finds the work it assigned to it, or `create_id` which is kept
+    /// for the next restart. Without a path every executor gets a new id.
+    pub fn executor_id(&self, create_id: impl FnOnce() -> String) -> Result<String> {
+        let Some(disk) = &self.disk else {
+            return Ok(create_id());
+        };
+        if let Some(id) = disk.db.get(EXECUTOR_ID_KEY)? {
+            return Ok(String::from_utf8_lossy(&id).to_string());
         }
+        let id = create_id();
+        disk.db.insert(EXECUTOR_ID_KEY, id.as_bytes())?;
+        disk.flush()?;
+        Ok(id)
     }
 
-    pub fn clear_completed_work(&self) {
-        self.completed_work.write().unwrap().clear();
+    pub fn clear_completed_work(&self) -> Result<()> {
+        let mut completed_work = self.completed_work.write().unwrap();
+        if let Some(disk) = &self.disk {
+            for work_id in completed_work.keys() {
+                disk.completed_work.remove(work_id)?;
+            }
+            disk.flush()?;
+        }
+        completed_work.clear();
+        Ok(())
     }
 
-    pub fn add_work_list(&self, work_list: Vec<Work>) {
+    pub fn add_work_list(&self, work_list: Vec<Work>) -> Result<()> {
         let mut allocated_work = self.allocated_work.write().unwrap();
         for work in work_list {
+            if let Some(disk) = &self.disk {
+                DiskStore::insert(&disk.allocated_work, &work.id, &work)?;
+            }
             allocated_work.insert(work.id.clone(), work);
         }
+        if let Some(disk) = &self.disk {
+            disk.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the pending work with the work the coordinator assigns to the
+    /// executor. Work which the coordinator no longer assigns to it, such as
+    /// work given to another executor while this one was unreachable, is
+    /// dropped. Returns the number of work dropped.
+    pub fn reconcile(&self, assigned_work: Vec<Work>) -> Result<usize> {
+        let dropped: Vec<String> = {
+            let allocated_work = self.allocated_work.read().unwrap();
+            allocated_work
+                .keys()
+                .filter(|id| !assigned_work.iter().any(|work| &work.id == *id))
+                .cloned()
+                .collect()
+        };
+        {
+            let mut allocated_work = self.allocated_work.write().unwrap();
+            for work_id in &dropped {
+                if let Some(disk) = &self.disk {
+                    disk.allocated_work.remove(work_id)?;
+                }
+                allocated_work.remove(work_id);
+            }
+        }
+        self.add_work_list(assigned_work)?;
+        Ok(dropped.len())
     }
 
-    pub fn update_work_status(&self, work_status: Vec<WorkStatus>) {
+    /// Records the statuses of performed work as they come, so that the work
+    /// finished before a restart is not performed again.
+    pub fn update_work_status(&self, work_status: Vec<WorkStatus>) -> Result<()> {
         let mut allocated_work_handle = self.allocated_work.write().unwrap();
         let mut completed_work_handle = self.completed_work.write().unwrap();
         for work in work_status {
+            if let Some(disk) = &self.disk {
+                disk.allocated_work.remove(&work.work_id)?;
+                DiskStore::insert(&disk.completed_work, &work.work_id, &work)?;
+            }
             allocated_work_handle.remove(&work.work_id);
             completed_work_handle.insert(work.work_id.clone(), work);
         }
+        if let Some(disk) = &self.disk {
+            disk.flush()?;
+        }
+        Ok(())
     }
 
     pub fn pending_work(&self) -> Vec<Work> {
@@ -48,3 +181,88 @@ impl WorkStore {
         allocated_work.values().cloned().collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::internal_api::WorkState;
+
+    fn work(id: &str) -> Work {
+        serde_json::from_value(json!({
+            "id": id,
+            "content_payload": {"content_type": "text/plain", "content": "hello", "external_url": null},
+            "params": {},
+        }))
+        .unwrap()
+    }
+
+    fn completed(id: &str) -> WorkStatus {
+        WorkStatus {
+            work_id: id.into(),
+            status: WorkState::Completed,
+            extracted_content: vec![],
+            chunk_origins: vec![],
+            items_processed: Some(1),
+            percent: Some(100.0),
+            log: vec![],
+            error: None,
+        }
+    }
+
+    fn ids<T>(items: Vec<T>, id: impl Fn(&T) -> &str) -> Vec<String> {
+        let mut ids: Vec<String> = items.iter().map(|item| id(item).to_string()).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_resumes_after_restart() {
+        let path = std::env::temp_dir().join(format!("work_store_{}", nanoid::nanoid!()));
+        let path = path.to_str().unwrap();
+        {
+            let store = WorkStore::open(path).unwrap();
+            assert_eq!(
+                store.executor_id(|| "executor-1".into()).unwrap(),
+                "executor-1"
+            );
+            store
+                .add_work_list(vec![work("w1"), work("w2"), work("w3")])
+                .unwrap();
+            store.update_work_status(vec![completed("w1")]).unwrap();
+        }
+
+        let store = WorkStore::open(path).unwrap();
+        assert_eq!(
+            store.executor_id(|| "executor-2".into()).unwrap(),
+            "executor-1"
+        );
+        assert_eq!(ids(store.pending_work(), |w| &w.id), vec!["w2", "w3"]);
+        assert_eq!(ids(store.completed_work(), |s| &s.work_id), vec!["w1"]);
+
+        // The coordinator gave w3 to another executor and assigned w4.
+        assert_eq!(store.reconcile(vec![work("w2"), work("w4")]).unwrap(), 1);
+        store.clear_completed_work().unwrap();
+        drop(store);
+
+        let store = WorkStore::open(path).unwrap();
+        assert_eq!(ids(store.pending_work(), |w| &w.id), vec!["w2", "w4"]);
+        assert!(store.completed_work().is_empty());
+        drop(store);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_in_memory() {
+        let store = WorkStore::new();
+        store.add_work_list(vec![work("w1")]).unwrap();
+        store.update_work_status(vec![completed("w1")]).unwrap();
+        assert!(store.pending_work().is_empty());
+        assert_eq!(store.completed_work().len(), 1);
+        assert_ne!(
+            store.executor_id(|| nanoid::nanoid!()).unwrap(),
+            store.executor_id(|| nanoid::nanoid!()).unwrap()
+        );
+    }
+}